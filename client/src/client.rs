@@ -0,0 +1,631 @@
+use bytes::Bytes;
+use futures_util::{Stream, StreamExt};
+use reqwest::{Method, RequestBuilder, Response, StatusCode, Url};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::RwLock;
+use tokio_util::io::ReaderStream;
+
+use crate::error::{ClientError, ClientResult};
+use crate::models::{
+    CameraUploadResponse, DeleteResponse, Entry, FileRequest, FileRequestList, Listing,
+    LoginRequest, LoginResponse, MetadataResponse, NewFileRequest, PatchFileResponse, SearchHit,
+    ServerStatus, SetMetadataRequest, UploadOptions, UploadResponse,
+};
+
+/// Uploads are sent in chunks of this size so progress can be reported
+const UPLOAD_CHUNK_SIZE: usize = 64 * 1024;
+
+/// How failed requests are retried
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Number of retries after the first attempt
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled after every attempt
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(250),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            base_delay: Duration::ZERO,
+        }
+    }
+
+    fn delay_for(&self, attempt: u32) -> Duration {
+        self.base_delay * 2u32.saturating_pow(attempt)
+    }
+}
+
+/// Credentials kept by the client so an expired token can be refreshed
+#[derive(Default)]
+struct Session {
+    token: Option<String>,
+    password: Option<String>,
+}
+
+/// Async client for a single CloudHost cloud
+///
+/// Cloning is cheap; clones share the same connection pool and session.
+#[derive(Clone)]
+pub struct CloudHostClient {
+    base_url: Url,
+    http: reqwest::Client,
+    retry: RetryPolicy,
+    session: Arc<RwLock<Session>>,
+}
+
+impl CloudHostClient {
+    /// Create a client for the cloud served at `base_url` (e.g. `http://localhost:3000`)
+    pub fn new(base_url: &str) -> ClientResult<Self> {
+        Self::with_retry_policy(base_url, RetryPolicy::default())
+    }
+
+    pub fn with_retry_policy(base_url: &str, retry: RetryPolicy) -> ClientResult<Self> {
+        let mut base_url = Url::parse(base_url.trim())
+            .map_err(|e| ClientError::validation(format!("Invalid URL '{}': {}", base_url, e)))?;
+        if base_url.cannot_be_a_base() {
+            return Err(ClientError::validation(format!(
+                "URL '{}' cannot be used as a base URL",
+                base_url
+            )));
+        }
+        // Drop any trailing slash so endpoint segments append cleanly
+        if base_url.path().ends_with('/') {
+            let trimmed = base_url.path().trim_end_matches('/').to_string();
+            base_url.set_path(&trimmed);
+        }
+
+        let http = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(10))
+            .build()?;
+
+        Ok(Self {
+            base_url,
+            http,
+            retry,
+            session: Arc::new(RwLock::new(Session::default())),
+        })
+    }
+
+    /// The URL this client talks to
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// Use an existing token instead of logging in
+    pub async fn set_token(&self, token: impl Into<String>) {
+        self.session.write().await.token = Some(token.into());
+    }
+
+    /// The current JWT, if logged in
+    pub async fn token(&self) -> Option<String> {
+        self.session.read().await.token.clone()
+    }
+
+    pub async fn is_logged_in(&self) -> bool {
+        self.session.read().await.token.is_some()
+    }
+
+    /// Forget the token and stored password
+    pub async fn logout(&self) {
+        *self.session.write().await = Session::default();
+    }
+
+    // ========== Authentication ==========
+
+    /// Log in with the cloud password and keep the token for later requests.
+    /// The password is remembered so an expired token can be refreshed transparently.
+    pub async fn login(&self, password: &str) -> ClientResult<()> {
+        let token = self.request_token(password).await?;
+        let mut session = self.session.write().await;
+        session.token = Some(token);
+        session.password = Some(password.to_string());
+        Ok(())
+    }
+
+    async fn request_token(&self, password: &str) -> ClientResult<String> {
+        let url = self.endpoint(&["api", "login"])?;
+        let response = self
+            .send_with_retry(|| self.http.post(url.clone()).json(&LoginRequest { password }))
+            .await?;
+
+        if response.status() == StatusCode::UNAUTHORIZED {
+            return Err(ClientError::authentication("Invalid credentials"));
+        }
+        let response = check_status(response).await?;
+        Ok(response.json::<LoginResponse>().await?.token)
+    }
+
+    /// Re-login with the stored password; returns false if no password is known
+    async fn refresh_token(&self) -> ClientResult<bool> {
+        let password = self.session.read().await.password.clone();
+        match password {
+            Some(password) => {
+                let token = self.request_token(&password).await?;
+                self.session.write().await.token = Some(token);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    // ========== Browsing ==========
+
+    /// Server status and the cloud folders of this cloud
+    pub async fn status(&self) -> ClientResult<ServerStatus> {
+        let url = self.endpoint(&["api"])?;
+        let response = self.authed(Method::GET, url, |r| r).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Names of the cloud folders served by this cloud
+    pub async fn cloud_folders(&self) -> ClientResult<Vec<String>> {
+        Ok(self
+            .status()
+            .await?
+            .cloud
+            .cloud_folders
+            .into_iter()
+            .map(|folder| folder.name)
+            .collect())
+    }
+
+    /// List a directory (or describe a file) inside a cloud folder.
+    /// An empty `path` lists the cloud folder root.
+    pub async fn list(&self, cloud_folder: &str, path: &str) -> ClientResult<Listing> {
+        let url = self.endpoint(&["api", cloud_folder, "files", path])?;
+        let response = self.authed(Method::GET, url, |r| r).await?;
+        Ok(response.json().await?)
+    }
+
+    /// List a directory, failing if `path` points at a file
+    pub async fn list_dir(&self, cloud_folder: &str, path: &str) -> ClientResult<Vec<Entry>> {
+        match self.list(cloud_folder, path).await? {
+            Listing::Directory { items, .. } => Ok(items),
+            Listing::File { .. } => Err(ClientError::validation(format!(
+                "'{}' is a file, not a directory",
+                path
+            ))),
+        }
+    }
+
+    /// Recursively search a cloud folder for entries whose name contains `query`
+    /// (case-insensitive). The walk happens client-side over the listing API.
+    pub async fn search(&self, cloud_folder: &str, query: &str) -> ClientResult<Vec<SearchHit>> {
+        let needle = query.to_lowercase();
+        let mut hits = Vec::new();
+        let mut pending = vec![String::new()];
+
+        while let Some(dir) = pending.pop() {
+            for entry in self.list_dir(cloud_folder, &dir).await? {
+                if entry.is_dir() {
+                    pending.push(entry.path.clone());
+                }
+                if entry.name.to_lowercase().contains(&needle) {
+                    hits.push(SearchHit {
+                        cloud_folder: cloud_folder.to_string(),
+                        entry,
+                    });
+                }
+            }
+        }
+
+        Ok(hits)
+    }
+
+    /// Search every cloud folder of this cloud
+    pub async fn search_all(&self, query: &str) -> ClientResult<Vec<SearchHit>> {
+        let mut hits = Vec::new();
+        for cloud_folder in self.cloud_folders().await? {
+            hits.extend(self.search(&cloud_folder, query).await?);
+        }
+        Ok(hits)
+    }
+
+    // ========== Transfers ==========
+
+    /// Stream the contents of a file
+    pub async fn download_stream(
+        &self,
+        cloud_folder: &str,
+        path: &str,
+    ) -> ClientResult<impl Stream<Item = ClientResult<Bytes>>> {
+        let url = self.endpoint(&["api", cloud_folder, "static", path])?;
+        let response = self.authed(Method::GET, url, |r| r).await?;
+        Ok(response
+            .bytes_stream()
+            .map(|chunk| chunk.map_err(ClientError::from)))
+    }
+
+    /// Download a file to `destination`, returning the number of bytes written
+    pub async fn download_to_file(
+        &self,
+        cloud_folder: &str,
+        path: &str,
+        destination: &Path,
+    ) -> ClientResult<u64> {
+        self.download_to_file_with_progress(cloud_folder, path, destination, |_, _| {})
+            .await
+    }
+
+    /// Like `download_to_file`, calling `on_progress(written, total)` after every chunk.
+    /// `total` is `None` when the server doesn't send a content length.
+    pub async fn download_to_file_with_progress<F>(
+        &self,
+        cloud_folder: &str,
+        path: &str,
+        destination: &Path,
+        mut on_progress: F,
+    ) -> ClientResult<u64>
+    where
+        F: FnMut(u64, Option<u64>),
+    {
+        let url = self.endpoint(&["api", cloud_folder, "static", path])?;
+        let response = self.authed(Method::GET, url, |r| r).await?;
+        let total = response.content_length();
+        let mut stream = Box::pin(response.bytes_stream());
+
+        if let Some(parent) = destination.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut file = tokio::fs::File::create(destination).await?;
+        let mut written = 0u64;
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            file.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+            on_progress(written, total);
+        }
+        file.flush().await?;
+        Ok(written)
+    }
+
+    /// Upload in-memory data as `filename` into `directory` of a cloud folder
+    pub async fn upload_bytes(
+        &self,
+        cloud_folder: &str,
+        directory: &str,
+        filename: &str,
+        data: impl Into<Bytes>,
+        options: &UploadOptions,
+    ) -> ClientResult<UploadResponse> {
+        let url = self.upload_endpoint(cloud_folder, directory, options)?;
+        let data: Bytes = data.into();
+        let filename = filename.to_string();
+        let response = self
+            .authed(Method::POST, url, |r| {
+                let part =
+                    reqwest::multipart::Part::stream(data.clone()).file_name(filename.clone());
+                with_mtime(r, options).multipart(reqwest::multipart::Form::new().part("file", part))
+            })
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    /// Upload a local file into `directory` of a cloud folder, keeping its file name
+    pub async fn upload_file(
+        &self,
+        cloud_folder: &str,
+        directory: &str,
+        source: &Path,
+        options: &UploadOptions,
+    ) -> ClientResult<UploadResponse> {
+        self.upload_file_with_progress(cloud_folder, directory, source, options, |_, _| {})
+            .await
+    }
+
+    /// Like `upload_file`, calling `on_progress(sent, total)` as the body is sent
+    pub async fn upload_file_with_progress<F>(
+        &self,
+        cloud_folder: &str,
+        directory: &str,
+        source: &Path,
+        options: &UploadOptions,
+        on_progress: F,
+    ) -> ClientResult<UploadResponse>
+    where
+        F: Fn(u64, u64) + Send + Sync + 'static,
+    {
+        let filename = source
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| {
+                ClientError::validation(format!("'{}' has no file name", source.display()))
+            })?
+            .to_string();
+        let total = tokio::fs::metadata(source).await?.len();
+        let source = source.to_path_buf();
+        let on_progress = Arc::new(on_progress);
+
+        let url = self.upload_endpoint(cloud_folder, directory, options)?;
+        let response = self
+            .authed(Method::POST, url, |r| {
+                // Reopened for every attempt, so a retry sends the file and reports
+                // progress from the start
+                let body = match std::fs::File::open(&source) {
+                    Ok(file) => {
+                        let on_progress = on_progress.clone();
+                        let mut sent = 0u64;
+                        ReaderStream::with_capacity(
+                            tokio::fs::File::from_std(file),
+                            UPLOAD_CHUNK_SIZE,
+                        )
+                        .inspect(move |chunk| {
+                            if let Ok(chunk) = chunk {
+                                sent += chunk.len() as u64;
+                                on_progress(sent, total);
+                            }
+                        })
+                        .boxed()
+                    }
+                    Err(e) => futures_util::stream::once(async move { Err(e) }).boxed(),
+                };
+                let part = reqwest::multipart::Part::stream_with_length(
+                    reqwest::Body::wrap_stream(body),
+                    total,
+                )
+                .file_name(filename.clone());
+                with_mtime(r, options).multipart(reqwest::multipart::Form::new().part("file", part))
+            })
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    /// Upload a photo or video to a camera roll folder, which sorts it into `YYYY/MM`.
+    /// `mtime` (unix seconds) is used when the file has no EXIF date. Content the
+    /// folder already has isn't stored twice, so retrying is always safe.
+    pub async fn camera_upload(
+        &self,
+        cloud_folder: &str,
+        filename: &str,
+        data: impl Into<Bytes>,
+        mtime: Option<i64>,
+    ) -> ClientResult<CameraUploadResponse> {
+        let mut url = self.endpoint(&["api", "camera", cloud_folder])?;
+        url.query_pairs_mut().append_pair("name", filename);
+        let data: Bytes = data.into();
+        let response = self
+            .authed(Method::POST, url, |r| {
+                let r = r.body(data.clone());
+                match mtime {
+                    Some(mtime) => r.header("X-OC-Mtime", mtime.to_string()),
+                    None => r,
+                }
+            })
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    /// Append `data` to a file, creating it first if `create` is set.
+    /// Transient failures are retried, so data may end up appended twice; use
+    /// `write_range` at a known offset where that matters.
+    pub async fn append(
+        &self,
+        cloud_folder: &str,
+        path: &str,
+        data: impl Into<Bytes>,
+        create: bool,
+    ) -> ClientResult<PatchFileResponse> {
+        let mut url = self.endpoint(&["api", "file", cloud_folder, path])?;
+        url.query_pairs_mut().append_pair("append", "true");
+        if create {
+            url.query_pairs_mut().append_pair("create", "true");
+        }
+        self.patch_file(url, data.into()).await
+    }
+
+    /// Overwrite a file from byte `offset` on with `data`, extending it as needed.
+    /// With `truncate` the file ends after the written range.
+    pub async fn write_range(
+        &self,
+        cloud_folder: &str,
+        path: &str,
+        offset: u64,
+        data: impl Into<Bytes>,
+        truncate: bool,
+    ) -> ClientResult<PatchFileResponse> {
+        let mut url = self.endpoint(&["api", "file", cloud_folder, path])?;
+        url.query_pairs_mut()
+            .append_pair("offset", &offset.to_string());
+        if truncate {
+            url.query_pairs_mut().append_pair("truncate", "true");
+        }
+        self.patch_file(url, data.into()).await
+    }
+
+    /// Set the modification time (unix seconds) of a file, `path` relative to the cloud folder
+    pub async fn set_mtime(
+        &self,
+        cloud_folder: &str,
+        path: &str,
+        mtime: i64,
+    ) -> ClientResult<MetadataResponse> {
+        let url = self.endpoint(&["api", "metadata", cloud_folder, path])?;
+        let response = self
+            .authed(Method::PATCH, url, |r| {
+                r.json(&SetMetadataRequest { mtime })
+            })
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    /// Delete a file (moved to the host's trash where supported)
+    pub async fn delete(&self, cloud_folder: &str, path: &str) -> ClientResult<DeleteResponse> {
+        let url = self.endpoint(&["api", "delete", cloud_folder, path])?;
+        let response = self.authed(Method::DELETE, url, |r| r).await?;
+        Ok(response.json().await?)
+    }
+
+    // ========== Sharing ==========
+
+    /// The cloud's file requests, open or not. Like creating and revoking them, this
+    /// takes a login; API tokens are refused.
+    pub async fn file_requests(&self) -> ClientResult<Vec<FileRequest>> {
+        let url = self.endpoint(&["api", "file-requests"])?;
+        let response = self.authed(Method::GET, url, |r| r).await?;
+        Ok(response.json::<FileRequestList>().await?.file_requests)
+    }
+
+    /// Share a directory for uploads: a link anyone can send files into
+    pub async fn create_file_request(&self, request: &NewFileRequest) -> ClientResult<FileRequest> {
+        let url = self.endpoint(&["api", "file-requests"])?;
+        let response = self.authed(Method::POST, url, |r| r.json(request)).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Stop a file request's link from working; the files it received stay
+    pub async fn revoke_file_request(&self, id: &str) -> ClientResult<()> {
+        let url = self.endpoint(&["api", "file-requests", id])?;
+        self.authed(Method::DELETE, url, |r| r).await?;
+        Ok(())
+    }
+
+    // ========== Plumbing ==========
+
+    /// Build an endpoint URL; every segment may itself contain `/` separated parts,
+    /// each of which is percent-encoded individually
+    fn endpoint(&self, segments: &[&str]) -> ClientResult<Url> {
+        let mut url = self.base_url.clone();
+        {
+            let mut path = url
+                .path_segments_mut()
+                .map_err(|_| ClientError::validation("Base URL cannot have a path"))?;
+            for segment in segments {
+                path.extend(segment.split('/').filter(|part| !part.is_empty()));
+            }
+        }
+        Ok(url)
+    }
+
+    async fn patch_file(&self, url: Url, data: Bytes) -> ClientResult<PatchFileResponse> {
+        let response = self
+            .authed(Method::PATCH, url, |r| r.body(data.clone()))
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    fn upload_endpoint(
+        &self,
+        cloud_folder: &str,
+        directory: &str,
+        options: &UploadOptions,
+    ) -> ClientResult<Url> {
+        let mut url = self.endpoint(&["api", "upload", cloud_folder, directory])?;
+        if options.create_dirs {
+            url.query_pairs_mut().append_pair("create_dirs", "true");
+        }
+        if let Some(on_conflict) = options.on_conflict {
+            url.query_pairs_mut()
+                .append_pair("on_conflict", on_conflict.as_str());
+        }
+        Ok(url)
+    }
+
+    /// Send an authenticated request, refreshing the token once on 401
+    async fn authed<F>(&self, method: Method, url: Url, build: F) -> ClientResult<Response>
+    where
+        F: Fn(RequestBuilder) -> RequestBuilder,
+    {
+        let make = |token: Option<String>| {
+            let mut request = self.http.request(method.clone(), url.clone());
+            if let Some(token) = token {
+                request = request.bearer_auth(token);
+            }
+            build(request)
+        };
+
+        let token = self.token().await;
+        let response = self.send_with_retry(|| make(token.clone())).await?;
+
+        if response.status() == StatusCode::UNAUTHORIZED {
+            if self.refresh_token().await? {
+                let token = self.token().await;
+                let response = self.send_with_retry(|| make(token.clone())).await?;
+                return check_status(response).await;
+            }
+            return Err(ClientError::authentication(
+                "Not logged in or session expired",
+            ));
+        }
+
+        check_status(response).await
+    }
+
+    /// Send a request, retrying transient failures with exponential backoff
+    async fn send_with_retry<F>(&self, make: F) -> ClientResult<Response>
+    where
+        F: Fn() -> RequestBuilder,
+    {
+        let mut attempt = 0;
+        loop {
+            let result = match make().send().await {
+                Ok(response) if matches!(response.status().as_u16(), 502..=504) => {
+                    Err(ClientError::Api {
+                        status: response.status().as_u16(),
+                        message: response.text().await.unwrap_or_default(),
+                    })
+                }
+                Ok(response) => Ok(response),
+                Err(e) => Err(ClientError::from(e)),
+            };
+
+            match result {
+                Err(e) if e.is_transient() && attempt < self.retry.max_retries => {
+                    tokio::time::sleep(self.retry.delay_for(attempt)).await;
+                    attempt += 1;
+                }
+                other => return other,
+            }
+        }
+    }
+}
+
+/// Ask the server to keep the upload's modification time
+fn with_mtime(request: RequestBuilder, options: &UploadOptions) -> RequestBuilder {
+    match options.mtime {
+        Some(mtime) => request.header("X-OC-Mtime", mtime.to_string()),
+        None => request,
+    }
+}
+
+/// Turn a non-success response into an `ClientError::Api` with the server's message
+async fn check_status(response: Response) -> ClientResult<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|json| {
+            json.get("message")
+                .or_else(|| json.get("error"))
+                .and_then(|m| m.as_str())
+                .map(str::to_string)
+        })
+        .unwrap_or(body);
+
+    if status == StatusCode::UNAUTHORIZED {
+        return Err(ClientError::authentication(message));
+    }
+
+    Err(ClientError::Api {
+        status: status.as_u16(),
+        message,
+    })
+}
@@ -0,0 +1,83 @@
+use std::fmt;
+
+/// Client error types
+#[derive(Debug)]
+pub enum ClientError {
+    /// The request could not be sent or the connection dropped
+    Network(String),
+    /// The server rejected our credentials or token
+    Authentication(String),
+    /// The server answered with a non-success status
+    Api { status: u16, message: String },
+    /// The response body could not be decoded
+    Decode(String),
+    /// Local file system error (downloads/uploads)
+    FileSystem(String),
+    /// Invalid input supplied by the caller
+    Validation(String),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Network(msg) => write!(f, "Network error: {}", msg),
+            ClientError::Authentication(msg) => write!(f, "Authentication error: {}", msg),
+            ClientError::Api { status, message } => {
+                write!(f, "API error ({}): {}", status, message)
+            }
+            ClientError::Decode(msg) => write!(f, "Decode error: {}", msg),
+            ClientError::FileSystem(msg) => write!(f, "File system error: {}", msg),
+            ClientError::Validation(msg) => write!(f, "Validation error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl ClientError {
+    pub fn network(msg: impl Into<String>) -> Self {
+        Self::Network(msg.into())
+    }
+
+    pub fn authentication(msg: impl Into<String>) -> Self {
+        Self::Authentication(msg.into())
+    }
+
+    pub fn validation(msg: impl Into<String>) -> Self {
+        Self::Validation(msg.into())
+    }
+
+    /// Whether retrying the same request may succeed
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ClientError::Network(_) => true,
+            ClientError::Api { status, .. } => matches!(status, 502..=504),
+            _ => false,
+        }
+    }
+}
+
+// Auto-convert common error types
+impl From<reqwest::Error> for ClientError {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_decode() {
+            Self::Decode(err.to_string())
+        } else {
+            Self::Network(err.to_string())
+        }
+    }
+}
+
+impl From<std::io::Error> for ClientError {
+    fn from(err: std::io::Error) -> Self {
+        Self::FileSystem(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for ClientError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Decode(format!("JSON error: {}", err))
+    }
+}
+
+pub type ClientResult<T> = Result<T, ClientError>;
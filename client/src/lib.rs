@@ -0,0 +1,20 @@
+//! Async client library for the CloudHost API.
+//!
+//! ```no_run
+//! # async fn run() -> cloudhost_client::ClientResult<()> {
+//! let client = cloudhost_client::CloudHostClient::new("http://localhost:3000")?;
+//! client.login("my-password").await?;
+//! for entry in client.list_dir("photos", "").await? {
+//!     println!("{} ({} bytes)", entry.name, entry.size);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod client;
+pub mod error;
+pub mod models;
+
+pub use client::{CloudHostClient, RetryPolicy};
+pub use error::{ClientError, ClientResult};
+pub use models::*;
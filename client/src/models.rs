@@ -0,0 +1,247 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize)]
+pub(crate) struct LoginRequest<'a> {
+    pub password: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct LoginResponse {
+    pub token: String,
+}
+
+/// Response of `GET /api`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServerStatus {
+    pub status: String,
+    pub cloud: CloudSummary,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CloudSummary {
+    pub name: String,
+    pub cloud_folders: Vec<CloudFolderSummary>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CloudFolderSummary {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    File,
+    Directory,
+}
+
+/// A single item of a directory listing
+#[derive(Debug, Clone, Deserialize)]
+pub struct Entry {
+    pub name: String,
+    /// Path relative to the cloud folder root
+    pub path: String,
+    #[serde(rename = "type")]
+    pub kind: EntryKind,
+    pub size: u64,
+    /// Modification time in unix seconds
+    #[serde(default)]
+    pub modified: Option<i64>,
+    /// Content type of files, unset for directories and older servers
+    #[serde(default)]
+    pub mime_type: Option<String>,
+}
+
+impl Entry {
+    pub fn is_dir(&self) -> bool {
+        self.kind == EntryKind::Directory
+    }
+}
+
+/// Response of `GET /api/{cloud_folder}/files[/path]`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Listing {
+    Directory {
+        path: String,
+        items: Vec<Entry>,
+    },
+    File {
+        name: String,
+        path: String,
+        size: u64,
+        #[serde(default)]
+        modified: Option<i64>,
+        #[serde(default)]
+        mime_type: Option<String>,
+        download_url: String,
+    },
+}
+
+/// What an upload does when a file with the same name exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnConflict {
+    /// Store as `name(1).ext`, `name(2).ext`, ...
+    Rename,
+    /// Fail with `ClientError` (409 Conflict)
+    Reject,
+    /// Replace the existing file
+    Overwrite,
+}
+
+impl OnConflict {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OnConflict::Rename => "rename",
+            OnConflict::Reject => "reject",
+            OnConflict::Overwrite => "overwrite",
+        }
+    }
+}
+
+/// How an upload treats its target directory and name collisions
+#[derive(Debug, Clone, Default)]
+pub struct UploadOptions {
+    /// Create missing directories of the target instead of failing with 404
+    pub create_dirs: bool,
+    /// `None` uses the cloud's default (rename unless configured otherwise)
+    pub on_conflict: Option<OnConflict>,
+    /// Modification time (unix seconds) to give the stored file, e.g. the source's
+    pub mtime: Option<i64>,
+}
+
+/// How a name collision was resolved
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictOutcome {
+    /// No file had that name
+    #[default]
+    None,
+    Renamed,
+    Overwritten,
+}
+
+/// Response of `POST /api/upload/*path`
+#[derive(Debug, Clone, Deserialize)]
+pub struct UploadResponse {
+    pub success: bool,
+    pub message: String,
+    pub path: String,
+    /// `cloud_folder/sub/dir/filename` of the stored file
+    #[serde(default)]
+    pub stored_path: String,
+    pub filename: String,
+    #[serde(default)]
+    pub conflict: ConflictOutcome,
+    /// Whether missing directories were created for this upload
+    #[serde(default)]
+    pub created_directories: bool,
+    /// The modification time that was set, if one was sent
+    #[serde(default)]
+    pub mtime: Option<i64>,
+}
+
+/// Response of `PATCH /api/file/{cloud_folder}/*path`
+#[derive(Debug, Clone, Deserialize)]
+pub struct PatchFileResponse {
+    pub success: bool,
+    /// `cloud_folder/dir/name` of the file
+    pub path: String,
+    /// Whether the file was created by this request
+    pub created: bool,
+    /// Where the data was written
+    pub offset: u64,
+    pub written: u64,
+    /// Size of the file afterwards
+    pub size: u64,
+}
+
+/// Response of `POST /api/camera/{cloud_folder}`
+#[derive(Debug, Clone, Deserialize)]
+pub struct CameraUploadResponse {
+    pub success: bool,
+    /// `cloud_folder/YYYY/MM/name` where the content is stored
+    pub path: String,
+    /// The content was already there and wasn't stored again
+    pub duplicate: bool,
+    /// Local date and time the file was sorted by, `YYYY-MM-DDTHH:MM:SS`
+    pub taken: String,
+    /// Where that date came from: `exif`, `mtime` or `received`
+    pub sorted_by: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// Response of `PATCH /api/metadata/*path`
+#[derive(Debug, Clone, Deserialize)]
+pub struct MetadataResponse {
+    pub success: bool,
+    pub path: String,
+    pub mtime: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct SetMetadataRequest {
+    pub mtime: i64,
+}
+
+/// Response of `DELETE /api/delete/*path`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeleteResponse {
+    pub success: bool,
+    pub message: String,
+    pub recovery: String,
+    pub platform: String,
+}
+
+/// A file request: a link at `/drop/<id>` through which anyone can upload files into a
+/// directory without logging in. It's how a cloud shares; they are managed with a login.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FileRequest {
+    /// Secret part of the link, also used to revoke it
+    pub id: String,
+    /// Shown on the upload page
+    pub name: String,
+    /// `cloud_folder/sub/dir` the files go to
+    pub path: String,
+    /// The link relative to the server, `/drop/<id>`
+    pub url: String,
+    /// Largest accepted file in bytes
+    pub max_file_size: Option<u64>,
+    /// How many files the link accepts in total
+    pub max_files: Option<u32>,
+    /// Files received so far
+    pub uploads: u32,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// Whether it still takes uploads: not expired and not full
+    pub open: bool,
+}
+
+/// What a new file request accepts; `name` and `path` (`cloud_folder/sub/dir`, an
+/// existing directory) are required
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NewFileRequest {
+    pub name: String,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_file_size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_files: Option<u32>,
+    /// The link stops working after this many hours
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_in_hours: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct FileRequestList {
+    pub file_requests: Vec<FileRequest>,
+}
+
+/// A search hit, carrying the cloud folder it was found in
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub cloud_folder: String,
+    pub entry: Entry,
+}
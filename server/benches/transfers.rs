@@ -0,0 +1,133 @@
+//! Benchmarks of the paths most requests take: directory listings by entry count, and
+//! download and upload throughput by file size, all through HTTP against a cloud on a
+//! local port. Run with `cargo bench -p cloudhost-server`; criterion keeps the numbers of
+//! the previous run in `target/criterion` and reports the change against them, so a
+//! redesign can be measured by running it before and after.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use common::{TestServer, CLOUD_FOLDER};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use tokio::runtime::Runtime;
+
+/// Entries in the listed directories
+const ENTRY_COUNTS: &[usize] = &[10, 100, 1_000, 10_000];
+/// Sizes of the downloaded and uploaded files
+const FILE_SIZES: &[usize] = &[64 * 1024, 1024 * 1024, 16 * 1024 * 1024];
+
+fn runtime() -> Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+/// `1 MiB` rather than `1048576` in the benchmark names
+fn size_name(bytes: usize) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 => format!("{} MiB", b / (1024 * 1024)),
+        b => format!("{} KiB", b / 1024),
+    }
+}
+
+fn listing(c: &mut Criterion) {
+    let runtime = runtime();
+    let server = runtime.block_on(TestServer::start());
+    for &count in ENTRY_COUNTS {
+        let directory = format!("dir-{}", count);
+        std::fs::create_dir(server.file(&directory)).unwrap();
+        for i in 0..count {
+            std::fs::write(server.file(&format!("{}/file-{:05}.txt", directory, i)), "").unwrap();
+        }
+    }
+
+    let mut group = c.benchmark_group("listing");
+    for &count in ENTRY_COUNTS {
+        let path = format!("/api/{}/files/dir-{}", CLOUD_FOLDER, count);
+        // Repeated listings of a directory that doesn't change are served from the cache
+        group.bench_with_input(BenchmarkId::new("cached", count), &path, |b, path| {
+            b.iter(|| {
+                let response = runtime.block_on(server.request("GET", path).send());
+                assert_eq!(response.status, 200);
+            })
+        });
+        // A directory modified since its last listing is read and sorted again
+        let marker = server.file(&format!("dir-{}/marker", count));
+        group.bench_with_input(BenchmarkId::new("changed", count), &path, |b, path| {
+            b.iter_batched(
+                || {
+                    std::fs::write(&marker, "").unwrap();
+                    std::fs::remove_file(&marker).unwrap();
+                },
+                |_| {
+                    let response = runtime.block_on(server.request("GET", path).send());
+                    assert_eq!(response.status, 200);
+                },
+                criterion::BatchSize::PerIteration,
+            )
+        });
+    }
+    group.finish();
+    runtime.block_on(server.stop());
+}
+
+fn download(c: &mut Criterion) {
+    let runtime = runtime();
+    let server = runtime.block_on(TestServer::start());
+
+    let mut group = c.benchmark_group("download");
+    group.sample_size(20);
+    for &size in FILE_SIZES {
+        let name = format!("download-{}.bin", size);
+        std::fs::write(server.file(&name), vec![7u8; size]).unwrap();
+        let path = format!("/api/{}/static/{}", CLOUD_FOLDER, name);
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size_name(size)),
+            &path,
+            |b, path| {
+                b.iter(|| {
+                    let response = runtime.block_on(server.request("GET", path).send());
+                    assert_eq!(response.body.len(), size);
+                })
+            },
+        );
+    }
+    group.finish();
+    runtime.block_on(server.stop());
+}
+
+fn upload(c: &mut Criterion) {
+    let runtime = runtime();
+    let server = runtime.block_on(TestServer::start());
+    let path = format!(
+        "/api/upload/{}/uploads?create_dirs=true&on_conflict=overwrite",
+        CLOUD_FOLDER
+    );
+
+    let mut group = c.benchmark_group("upload");
+    group.sample_size(20);
+    for &size in FILE_SIZES {
+        let contents = vec![7u8; size];
+        let name = format!("upload-{}.bin", size);
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size_name(size)),
+            &contents,
+            |b, contents| {
+                b.iter(|| {
+                    let response = runtime
+                        .block_on(server.request("POST", &path).file(&name, contents).send());
+                    assert_eq!(response.status, 200, "{}", response.text());
+                })
+            },
+        );
+    }
+    group.finish();
+    runtime.block_on(server.stop());
+}
+
+criterion_group!(benches, listing, download, upload);
+criterion_main!(benches);
@@ -0,0 +1,274 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::Json,
+};
+use chrono::{DateTime, Local, NaiveDateTime};
+use futures_util::StreamExt;
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path as StdPath, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+use super::upload::renamed_candidates;
+use crate::auth::AuthenticatedUser;
+use crate::cloud::CloudServerState;
+use crate::disk_space::{self, DiskSpaceConfig};
+use crate::error::{ApiError, ErrorCode};
+use crate::hooks::{self, HookFile};
+use crate::utils::photo_metadata::date_taken;
+use crate::utils::{
+    ensure_inside_cloud_folder, find_cloud_folder, mtime_from_headers, set_file_mtime,
+    upload_temp_path, validate_path_component,
+};
+use crate::webhooks::WebhookEvent;
+
+#[derive(Debug, Deserialize)]
+pub struct CameraUploadQuery {
+    /// File name to store the photo under, e.g. `IMG_0001.jpg`
+    pub name: String,
+}
+
+/// Upload a photo or video to a camera roll folder: the raw file is the request body,
+/// the name goes in `?name=`. The file is sorted into `YYYY/MM` by its EXIF date, else by
+/// the `X-OC-Mtime` header, else by when it arrived. A file with the same content already
+/// in that month is not stored again (200 with `"duplicate": true`), so phones can simply
+/// re-send everything after a failed or interrupted sync.
+pub async fn api_camera_upload(
+    State(server_state): State<CloudServerState>,
+    Path(cloud_folder_name): Path<String>,
+    Query(query): Query<CameraUploadQuery>,
+    headers: HeaderMap,
+    user: AuthenticatedUser,
+    body: Body,
+) -> Result<(StatusCode, Json<serde_json::Value>), ApiError> {
+    validate_path_component(&query.name)
+        .map_err(|e| ApiError::new(ErrorCode::Validation, format!("Invalid file name: {}", e)))?;
+    let mtime = mtime_from_headers(&headers)?;
+    let cloud_folder = find_cloud_folder(&server_state, &cloud_folder_name)?;
+    if !cloud_folder.camera_roll {
+        return Err(ApiError::new(
+            ErrorCode::Validation,
+            format!("'{}' is not a camera roll folder", cloud_folder_name),
+        ));
+    }
+    let root = &cloud_folder.folder_path;
+    let announced_size = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok());
+    let config = &server_state.cloud.disk_space;
+    if let Some(size) = announced_size {
+        disk_space::check_upload_size(config, size)
+            .map_err(|e| ApiError::new(ErrorCode::PayloadTooLarge, e))?;
+    }
+    disk_space::check_upload(config, cloud_folder, announced_size)
+        .map_err(|e| ApiError::new(ErrorCode::InsufficientStorage, e))?;
+
+    // Received next to the month folders, then moved into place
+    let temp_path = upload_temp_path(root);
+    let (size, hash) = match receive(body, &temp_path, config).await {
+        Ok(received) => received,
+        Err(e) => {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(e);
+        }
+    };
+    let user = user.name();
+    let requested_path = format!("{}/{}", cloud_folder_name, query.name);
+    if let Err(e) = hooks::pre_upload(
+        &server_state.cloud.name,
+        cloud_folder,
+        &HookFile {
+            path: &temp_path,
+            display_path: &requested_path,
+            size: Some(size),
+            user: &user,
+            source: "camera",
+        },
+    )
+    .await
+    {
+        let _ = fs::remove_file(&temp_path).await;
+        return Err(ApiError::new(ErrorCode::Forbidden, e));
+    }
+    let result = store(&temp_path, root, &query.name, size, &hash, mtime).await;
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path).await;
+    }
+    let (stored, duplicate, taken, sorted_by) = result?;
+
+    let relative = stored
+        .strip_prefix(root)
+        .unwrap_or(&stored)
+        .to_string_lossy()
+        .replace('\\', "/");
+    let display_path = format!("{}/{}", cloud_folder_name, relative);
+    if duplicate {
+        tracing::info!("Camera upload '{}' is already stored", query.name);
+    } else {
+        tracing::info!(bytes = size, sorted_by, "Camera upload '{}'", display_path);
+        server_state.webhooks.file_event(
+            WebhookEvent::FileUploaded,
+            &display_path,
+            Some(size),
+            "camera",
+        );
+        hooks::post_upload(
+            &server_state.cloud.name,
+            cloud_folder,
+            &HookFile {
+                path: &stored,
+                display_path: &display_path,
+                size: Some(size),
+                user: &user,
+                source: "camera",
+            },
+        );
+    }
+
+    let status = if duplicate {
+        StatusCode::OK
+    } else {
+        StatusCode::CREATED
+    };
+    Ok((
+        status,
+        Json(json!({
+            "success": true,
+            "path": display_path,
+            "duplicate": duplicate,
+            "taken": taken.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            "sorted_by": sorted_by,
+            "size": size,
+            "sha256": hash,
+        })),
+    ))
+}
+
+/// Stream the body to `path`, stopping once it gets larger than the cloud's upload limit;
+/// returns its size and SHA-256
+async fn receive(
+    body: Body,
+    path: &StdPath,
+    config: &DiskSpaceConfig,
+) -> Result<(u64, String), ApiError> {
+    let aborted =
+        |e: std::io::Error| ApiError::new(ErrorCode::Validation, format!("Upload aborted: {}", e));
+    let mut file = fs::File::create_new(path).await.map_err(aborted)?;
+    let mut hasher = Sha256::new();
+    let mut size = 0u64;
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| aborted(std::io::Error::other(e)))?;
+        size += chunk.len() as u64;
+        disk_space::check_upload_size(config, size)
+            .map_err(|e| ApiError::new(ErrorCode::PayloadTooLarge, e))?;
+        hasher.update(&chunk);
+        file.write_all(&chunk).await.map_err(aborted)?;
+    }
+    file.flush().await.map_err(aborted)?;
+    Ok((size, hex(&hasher.finalize())))
+}
+
+/// Move the received file into its month folder unless that already has the same content.
+/// Returns where the content is, whether it was there already, the date used and its source.
+async fn store(
+    temp_path: &StdPath,
+    root: &StdPath,
+    name: &str,
+    size: u64,
+    hash: &str,
+    mtime: Option<std::time::SystemTime>,
+) -> Result<(PathBuf, bool, NaiveDateTime, &'static str), ApiError> {
+    let (taken, sorted_by) = match (date_taken(temp_path).await, mtime) {
+        (Some(taken), _) => (taken, "exif"),
+        (None, Some(mtime)) => (DateTime::<Local>::from(mtime).naive_local(), "mtime"),
+        (None, None) => (Local::now().naive_local(), "received"),
+    };
+    let month = taken.format("%Y/%m").to_string();
+    let directory = root
+        .join(taken.format("%Y").to_string())
+        .join(taken.format("%m").to_string());
+    fs::create_dir_all(&directory).await.map_err(|e| {
+        ApiError::new(
+            ErrorCode::FileSystem,
+            format!("Failed to create '{}': {}", month, e),
+        )
+    })?;
+    ensure_inside_cloud_folder(root, &directory, &month).await?;
+
+    if let Some(existing) = find_duplicate(&directory, size, hash).await {
+        let _ = fs::remove_file(temp_path).await;
+        return Ok((existing, true, taken, sorted_by));
+    }
+
+    let write_error = |e: std::io::Error| {
+        ApiError::new(
+            ErrorCode::FileSystem,
+            format!("Failed to store file: {}", e),
+        )
+    };
+    // Camera names repeat across phones and counter resets, so taken names are never replaced
+    for candidate in std::iter::once(name.to_string()).chain(renamed_candidates(name)) {
+        let target = directory.join(&candidate);
+        if fs::symlink_metadata(&target).await.is_ok() {
+            continue;
+        }
+        fs::rename(temp_path, &target).await.map_err(write_error)?;
+        if let Some(mtime) = mtime {
+            set_file_mtime(&target, mtime).await.map_err(write_error)?;
+        }
+        return Ok((target, false, taken, sorted_by));
+    }
+    Err(ApiError::new(
+        ErrorCode::Conflict,
+        format!("No free name for '{}' in '{}'", name, month),
+    ))
+}
+
+/// A file in `directory` with the given size and SHA-256, if any. Only files of the same
+/// size are hashed, so this is cheap even for big months.
+async fn find_duplicate(directory: &StdPath, size: u64, hash: &str) -> Option<PathBuf> {
+    let mut entries = fs::read_dir(directory).await.ok()?;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let same_size = entry
+            .metadata()
+            .await
+            .is_ok_and(|metadata| metadata.is_file() && metadata.len() == size);
+        if !same_size {
+            continue;
+        }
+        let path = entry.path();
+        let candidate = path.clone();
+        let candidate_hash = tokio::task::spawn_blocking(move || sha256_file(&candidate))
+            .await
+            .ok()
+            .and_then(Result::ok);
+        if candidate_hash.as_deref() == Some(hash) {
+            return Some(path);
+        }
+    }
+    None
+}
+
+pub(crate) fn sha256_file(path: &StdPath) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(hex(&hasher.finalize()));
+        }
+        hasher.update(&buffer[..read]);
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -0,0 +1,48 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use serde_json::json;
+
+use crate::auth::AuthenticatedUser;
+use crate::cloud::CloudServerState;
+use crate::error::{ApiError, ErrorCode};
+
+/// Other people's addresses and transfers are for the owner's eyes only
+fn require_login(user: &AuthenticatedUser) -> Result<(), ApiError> {
+    user.refuse_guest()?;
+    match user.api_token {
+        Some(_) => Err(ApiError::new(
+            ErrorCode::Forbidden,
+            "Clients are managed with a login, not with an API token",
+        )),
+        None => Ok(()),
+    }
+}
+
+/// `GET /api/clients`: the requests being served, with who made them and how fast
+pub async fn api_list_clients(
+    State(server_state): State<CloudServerState>,
+    user: AuthenticatedUser,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    require_login(&user)?;
+    Ok(Json(json!({
+        "clients": server_state.stats.clients.list(),
+    })))
+}
+
+/// `DELETE /api/clients/:id`: end a client's transfer and close its connection
+pub async fn api_kill_client(
+    State(server_state): State<CloudServerState>,
+    user: AuthenticatedUser,
+    Path(id): Path<u64>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    require_login(&user)?;
+    if !server_state.stats.clients.kill(id) {
+        return Err(ApiError::new(
+            ErrorCode::NotFound,
+            "The client is no longer connected",
+        ));
+    }
+    Ok(Json(json!({ "killed": id })))
+}
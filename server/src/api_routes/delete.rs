@@ -1,53 +1,64 @@
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
     response::Json,
 };
 use serde_json::json;
 
+use crate::auth::AuthenticatedUser;
 use crate::cloud::CloudServerState;
-use crate::error::ServerError;
+use crate::error::{ApiError, ServerError};
+use crate::hooks::{self, HookFile};
 use crate::utils::{
-    construct_file_path, find_cloud_folder, parse_target_path, validate_file_exists,
+    ensure_inside_cloud_folder, find_cloud_folder, parse_directory_path, validate_file_exists,
 };
+use crate::webhooks::WebhookEvent;
 
 /// Delete a file by moving it to trash
 /// The path should be in format: "cloud_folder_name/subdirectory/path/filename"
 pub async fn api_delete_file(
     State(server_state): State<CloudServerState>,
     Path(target_path): Path<String>,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
-    // Parse the target path using shared utils
-    let parsed_path = parse_target_path(&target_path)?;
+    user: AuthenticatedUser,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let (cloud_folder_name, relative_path) = parse_directory_path(&target_path)?;
 
     // Ensure we have a filename for deletion
-    let filename = parsed_path.filename.ok_or_else(|| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(json!({
-                "error": "Filename required for deletion. Expected: cloud_folder_name/subdirectory/path/filename"
-            })),
-        )
-    })?;
+    let filename = relative_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| {
+            ApiError::validation(
+                "Filename required for deletion. Expected: cloud_folder_name/subdirectory/path/filename",
+            )
+        })?;
 
-    // Find the cloud folder using shared utils
-    let cloud_folder = find_cloud_folder(&server_state, &parsed_path.cloud_folder_name)?;
+    let cloud_folder = find_cloud_folder(&server_state, &cloud_folder_name)?;
+    let file_path = cloud_folder.folder_path.join(&relative_path);
 
-    // Construct the full file path using shared utils
-    let file_path = construct_file_path(cloud_folder, &parsed_path.subdirectory_path, &filename);
-
-    // Validate file exists using shared utils
     validate_file_exists(&file_path).await?;
+    // A symlink is removed itself, so only the directory it's in has to be inside
+    let display_path = target_path.trim_matches('/');
+    if let Some(directory) = file_path.parent() {
+        ensure_inside_cloud_folder(&cloud_folder.folder_path, directory, display_path).await?;
+    }
 
     // Platform-specific deletion handling
-    let (deletion_info, recovery, platform) = delete_file(&file_path).await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": e.to_string()
-            })),
-        )
-    })?;
+    let (deletion_info, recovery, platform) = delete_file(&file_path).await?;
+    tracing::info!("Deleted '{}' ({})", file_path.display(), platform);
+    server_state
+        .webhooks
+        .file_event(WebhookEvent::FileDeleted, &target_path, None, "api");
+    hooks::post_delete(
+        &server_state.cloud.name,
+        cloud_folder,
+        &HookFile {
+            path: &file_path,
+            display_path,
+            size: None,
+            user: &user.name(),
+            source: "api",
+        },
+    );
 
     Ok(Json(json!({
         "success": true,
@@ -67,7 +78,9 @@ pub async fn api_delete_file(
 }
 
 /// Delete a file using platform-appropriate method
-async fn delete_file(file_path: &std::path::Path) -> Result<(String, String, String), ServerError> {
+pub(crate) async fn delete_file(
+    file_path: &std::path::Path,
+) -> Result<(String, String, String), ServerError> {
     #[cfg(feature = "desktop")]
     {
         delete_file_desktop(file_path).await
@@ -83,9 +96,11 @@ async fn delete_file(file_path: &std::path::Path) -> Result<(String, String, Str
 async fn delete_file_desktop(
     file_path: &std::path::Path,
 ) -> Result<(String, String, String), ServerError> {
-    use trash;
-
-    trash::delete(file_path)
+    // Blocking: the trash crate moves the file and writes its trash info synchronously
+    let path = file_path.to_path_buf();
+    tokio::task::spawn_blocking(move || trash::delete(path))
+        .await
+        .map_err(|e| ServerError::file_system(format!("Failed to move file to OS trash: {}", e)))?
         .map_err(|e| ServerError::file_system(format!("Failed to move file to OS trash: {}", e)))?;
 
     Ok((
@@ -0,0 +1,289 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::Json,
+};
+use futures_util::StreamExt;
+use serde::Deserialize;
+use serde_json::json;
+use std::io::SeekFrom;
+use std::path::Path as StdPath;
+use tokio::fs;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+use crate::auth::AuthenticatedUser;
+use crate::cloud::CloudServerState;
+use crate::disk_space::{self, DiskSpaceConfig};
+use crate::error::{ApiError, ErrorCode};
+use crate::hooks::{self, HookFile};
+use crate::utils::{
+    ensure_inside_cloud_folder, find_cloud_folder, mtime_from_headers, parse_directory_path,
+    set_file_mtime, upload_temp_path,
+};
+use crate::webhooks::WebhookEvent;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PatchFileQuery {
+    /// Write the body at the end of the file
+    #[serde(default)]
+    pub append: bool,
+    /// Write the body starting at this byte, at most the current size
+    pub offset: Option<u64>,
+    /// With `offset`: cut the file off after the written range
+    #[serde(default)]
+    pub truncate: bool,
+    /// Create the file if it doesn't exist (its directory must)
+    #[serde(default)]
+    pub create: bool,
+}
+
+/// Update part of a file without uploading all of it: `?append=true` adds the request
+/// body at the end, `?offset=N` overwrites from byte N on (extending the file as needed).
+/// The body is the raw bytes and is streamed to disk. Like other uploads, it is refused
+/// when the disk is almost full or the file would get larger than the upload limit, and
+/// it runs the folder's upload hooks.
+/// The path should be in format: "cloud_folder_name/subdirectory/path/filename"
+pub async fn api_patch_file(
+    State(server_state): State<CloudServerState>,
+    Path((cloud_folder_name, path)): Path<(String, String)>,
+    Query(query): Query<PatchFileQuery>,
+    headers: HeaderMap,
+    user: AuthenticatedUser,
+    body: Body,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let offset = match (query.append, query.offset) {
+        (true, None) => None,
+        (false, Some(offset)) => Some(offset),
+        _ => {
+            return Err(ApiError::new(
+                ErrorCode::Validation,
+                "Expected either ?append=true or ?offset=N",
+            ))
+        }
+    };
+    if query.truncate && offset.is_none() {
+        return Err(ApiError::new(
+            ErrorCode::Validation,
+            "?truncate=true needs ?offset=N",
+        ));
+    }
+    let mtime = mtime_from_headers(&headers)?;
+
+    let (_, relative_path) = parse_directory_path(&format!("{}/{}", cloud_folder_name, path))?;
+    let Some(file_name) = relative_path.file_name() else {
+        return Err(ApiError::new(
+            ErrorCode::Validation,
+            "Expected: /api/file/cloud_folder_name/subdirectory/path/filename",
+        ));
+    };
+    let cloud_folder = find_cloud_folder(&server_state, &cloud_folder_name)?;
+    let file_path = cloud_folder.folder_path.join(&relative_path);
+    let display_path = format!(
+        "{}/{}",
+        cloud_folder_name,
+        relative_path.to_string_lossy().replace('\\', "/")
+    );
+
+    let size = match fs::metadata(&file_path).await {
+        Ok(metadata) if metadata.is_file() => Some(metadata.len()),
+        Ok(_) => {
+            return Err(ApiError::new(
+                ErrorCode::Conflict,
+                format!("'{}' is not a file", display_path),
+            ))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && query.create => None,
+        Err(_) => {
+            return Err(ApiError::new(
+                ErrorCode::NotFound,
+                format!(
+                    "File '{}' not found (add ?create=true to create it)",
+                    display_path
+                ),
+            ))
+        }
+    };
+
+    // A symlink inside the cloud folder could point anywhere; a new file is checked by its directory
+    let checked_path = match size {
+        Some(_) => file_path.clone(),
+        None => file_path.parent().unwrap_or(&file_path).to_path_buf(),
+    };
+    if size.is_none() && fs::metadata(&checked_path).await.is_err() {
+        return Err(ApiError::new(
+            ErrorCode::NotFound,
+            format!("Directory of '{}' does not exist", display_path),
+        ));
+    }
+    ensure_inside_cloud_folder(&cloud_folder.folder_path, &checked_path, &display_path).await?;
+
+    let current_size = size.unwrap_or(0);
+    if let Some(offset) = offset {
+        if offset > current_size {
+            return Err(ApiError::new(
+                ErrorCode::RangeNotSatisfiable,
+                format!(
+                    "Offset {} is past the end of '{}' ({} bytes)",
+                    offset, display_path, current_size
+                ),
+            )
+            .with("size", current_size));
+        }
+    }
+
+    let write_error = |e: std::io::Error| {
+        ApiError::new(
+            ErrorCode::FileSystem,
+            format!("Failed to write '{}': {}", display_path, e),
+        )
+    };
+    let config = &server_state.cloud.disk_space;
+    let announced_size: Option<u64> = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok());
+    let start = offset.unwrap_or(current_size);
+    if let Some(size) = announced_size {
+        disk_space::check_upload_size(config, start + size)
+            .map_err(|e| ApiError::new(ErrorCode::PayloadTooLarge, e))?;
+    }
+    disk_space::check_upload(config, cloud_folder, announced_size)
+        .map_err(|e| ApiError::new(ErrorCode::InsufficientStorage, e))?;
+
+    // The patch is made to a copy, so the pre_upload hook sees the result before it
+    // replaces the file and readers never see half of it
+    let temp_path = upload_temp_path(file_path.parent().unwrap_or(&file_path));
+    let user = user.name();
+    let patched = async {
+        let written = patch_copy(
+            size.map(|_| file_path.as_path()),
+            &temp_path,
+            start,
+            query.truncate,
+            body,
+            config,
+            &display_path,
+        )
+        .await?;
+        let new_size = fs::metadata(&temp_path).await.map_err(write_error)?.len();
+        hooks::pre_upload(
+            &server_state.cloud.name,
+            cloud_folder,
+            &HookFile {
+                path: &temp_path,
+                display_path: &display_path,
+                size: Some(new_size),
+                user: &user,
+                source: "api",
+            },
+        )
+        .await
+        .map_err(|e| ApiError::new(ErrorCode::Forbidden, e))?;
+        fs::rename(&temp_path, &file_path)
+            .await
+            .map_err(write_error)?;
+        Ok::<_, ApiError>((written, new_size))
+    }
+    .await;
+    let (written, new_size) = match patched {
+        Ok(patched) => patched,
+        Err(e) => {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(e);
+        }
+    };
+
+    if let Some(mtime) = mtime {
+        set_file_mtime(&file_path, mtime)
+            .await
+            .map_err(write_error)?;
+    }
+    tracing::info!(
+        offset = start,
+        bytes = written,
+        "Patched '{}' ({})",
+        display_path,
+        if offset.is_some() { "range" } else { "append" }
+    );
+    server_state.webhooks.file_event(
+        WebhookEvent::FileUploaded,
+        &display_path,
+        Some(new_size),
+        "api",
+    );
+    hooks::post_upload(
+        &server_state.cloud.name,
+        cloud_folder,
+        &HookFile {
+            path: &file_path,
+            display_path: &display_path,
+            size: Some(new_size),
+            user: &user,
+            source: "api",
+        },
+    );
+
+    Ok(Json(json!({
+        "success": true,
+        "path": display_path,
+        "name": file_name.to_string_lossy(),
+        "created": size.is_none(),
+        "offset": start,
+        "written": written,
+        "size": new_size
+    })))
+}
+
+/// Write `body` into a copy of `original` (an empty file when there is none) at
+/// `temp_path`, from byte `start` on, stopping once the file would get larger than the
+/// cloud's upload limit. Returns the bytes written.
+async fn patch_copy(
+    original: Option<&StdPath>,
+    temp_path: &StdPath,
+    start: u64,
+    truncate: bool,
+    body: Body,
+    config: &DiskSpaceConfig,
+    display_path: &str,
+) -> Result<u64, ApiError> {
+    let write_error = |e: std::io::Error| {
+        ApiError::new(
+            ErrorCode::FileSystem,
+            format!("Failed to write '{}': {}", display_path, e),
+        )
+    };
+    if let Some(original) = original {
+        fs::copy(original, temp_path).await.map_err(write_error)?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(temp_path)
+        .await
+        .map_err(write_error)?;
+    file.seek(SeekFrom::Start(start))
+        .await
+        .map_err(write_error)?;
+
+    let mut written = 0u64;
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| {
+            ApiError::new(
+                ErrorCode::Validation,
+                format!("Failed to read request body after {} bytes: {}", written, e),
+            )
+        })?;
+        written += chunk.len() as u64;
+        disk_space::check_upload_size(config, start + written)
+            .map_err(|e| ApiError::new(ErrorCode::PayloadTooLarge, e))?;
+        file.write_all(&chunk).await.map_err(write_error)?;
+    }
+    if truncate {
+        file.set_len(start + written).await.map_err(write_error)?;
+    }
+    file.flush().await.map_err(write_error)?;
+    Ok(written)
+}
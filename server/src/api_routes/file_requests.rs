@@ -0,0 +1,130 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::auth::AuthenticatedUser;
+use crate::cloud::CloudServerState;
+use crate::error::{ApiError, ErrorCode};
+use crate::file_requests::FileRequest;
+use crate::utils::{find_cloud_folder, is_dir, parse_directory_path};
+
+/// Longest accepted file request name
+const MAX_NAME_LENGTH: usize = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateFileRequest {
+    /// Shown on the upload page
+    pub name: String,
+    /// Where the files go: `cloud_folder_name/sub/dir`, every part of it an existing directory
+    pub path: String,
+    pub max_file_size: Option<u64>,
+    pub max_files: Option<u32>,
+    /// The link stops working after this many hours
+    pub expires_in_hours: Option<u32>,
+}
+
+/// Like API tokens, drop links are handed out with a login only
+fn require_login(user: &AuthenticatedUser) -> Result<(), ApiError> {
+    user.refuse_guest()?;
+    match user.api_token {
+        Some(_) => Err(ApiError::new(
+            ErrorCode::Forbidden,
+            "File requests are managed with a login, not with an API token",
+        )),
+        None => Ok(()),
+    }
+}
+
+fn file_request_json(request: &FileRequest) -> serde_json::Value {
+    json!({
+        "id": request.id,
+        "name": request.name,
+        "path": format!("{}/{}", request.cloud_folder, request.path).trim_end_matches('/'),
+        "url": format!("/drop/{}", request.id),
+        "max_file_size": request.max_file_size,
+        "max_files": request.max_files,
+        "uploads": request.uploads,
+        "expires_at": request.expires_at,
+        "created_at": request.created_at,
+        "open": request.check_open().is_ok(),
+    })
+}
+
+/// `GET /api/file-requests`
+pub async fn api_list_file_requests(
+    State(server_state): State<CloudServerState>,
+    user: AuthenticatedUser,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    require_login(&user)?;
+    let requests: Vec<_> = server_state
+        .file_requests
+        .list()
+        .iter()
+        .map(file_request_json)
+        .collect();
+    Ok(Json(json!({ "file_requests": requests })))
+}
+
+/// `POST /api/file-requests` with `{"name": ..., "path": ..., "max_file_size": ...,
+/// "max_files": ..., "expires_in_hours": ...}`
+pub async fn api_create_file_request(
+    State(server_state): State<CloudServerState>,
+    user: AuthenticatedUser,
+    Json(body): Json<CreateFileRequest>,
+) -> Result<(StatusCode, Json<serde_json::Value>), ApiError> {
+    require_login(&user)?;
+    let name = body.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err(ApiError::new(
+            ErrorCode::Validation,
+            format!("Name must be 1 to {} characters", MAX_NAME_LENGTH),
+        ));
+    }
+
+    let (cloud_folder_name, subdirectory) = parse_directory_path(&body.path)?;
+    let subdirectory = subdirectory.to_string_lossy().replace('\\', "/");
+    let cloud_folder = find_cloud_folder(&server_state, &cloud_folder_name)?;
+    if !is_dir(&cloud_folder.folder_path.join(&subdirectory)).await {
+        return Err(ApiError::new(
+            ErrorCode::NotFound,
+            format!("Directory '{}' not found", body.path.trim_matches('/')),
+        ));
+    }
+
+    let mut request = FileRequest::new(name, &cloud_folder_name, &subdirectory);
+    request.max_file_size = body.max_file_size;
+    request.max_files = body.max_files;
+    request.expires_at = body
+        .expires_in_hours
+        .map(|hours| request.created_at + chrono::Duration::hours(hours.into()));
+    if let Some(problem) = request.validate().into_iter().next() {
+        return Err(ApiError::new(ErrorCode::Validation, problem));
+    }
+
+    server_state.file_requests.add(request.clone());
+    tracing::info!(
+        "Created file request '{}' for '{}/{}'",
+        request.name,
+        request.cloud_folder,
+        request.path
+    );
+    Ok((StatusCode::CREATED, Json(file_request_json(&request))))
+}
+
+/// `DELETE /api/file-requests/:id`
+pub async fn api_delete_file_request(
+    State(server_state): State<CloudServerState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    require_login(&user)?;
+    if !server_state.file_requests.remove(&id) {
+        return Err(ApiError::new(ErrorCode::NotFound, "No such file request"));
+    }
+    tracing::info!("Deleted file request {}", id);
+    Ok(Json(json!({ "deleted": id })))
+}
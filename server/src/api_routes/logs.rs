@@ -0,0 +1,114 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::auth::{AuthMethod, AuthenticatedUser};
+use crate::cloud::CloudServerState;
+use crate::debug_stream::{LogFrame, LogLevel};
+use crate::error::ApiError;
+
+#[derive(Debug, Deserialize)]
+pub struct LogStreamQuery {
+    /// Minimum level to send: debug, info, warn or error (default: debug)
+    pub level: Option<String>,
+    /// Number of past messages to send first (default: none)
+    pub history: Option<usize>,
+}
+
+/// Browsers attach cookies to cross-site WebSocket handshakes, so a cookie-authenticated
+/// stream must come from a page served by this cloud
+fn is_same_origin(headers: &HeaderMap) -> bool {
+    let Some(origin) = headers.get(header::ORIGIN).and_then(|v| v.to_str().ok()) else {
+        // Not a browser
+        return true;
+    };
+    let host = headers.get(header::HOST).and_then(|v| v.to_str().ok());
+    let origin_host = origin.split_once("://").map(|(_, rest)| rest);
+    host.is_some() && origin_host == host
+}
+
+// WebSocket endpoint streaming the cloud's debug log: /api/logs/stream?level=warn&history=50
+pub async fn api_stream_logs(
+    State(server_state): State<CloudServerState>,
+    user: AuthenticatedUser,
+    headers: HeaderMap,
+    Query(query): Query<LogStreamQuery>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    if user.method == AuthMethod::Cookie && !is_same_origin(&headers) {
+        return ApiError::forbidden("Cross-origin log streams are not allowed").into_response();
+    }
+
+    let min_level = match query.level.as_deref().map(str::parse::<LogLevel>) {
+        None => LogLevel::Debug,
+        Some(Ok(level)) => level,
+        Some(Err(e)) => return ApiError::validation(e).into_response(),
+    };
+    let history = query.history.unwrap_or(0);
+
+    ws.on_upgrade(move |socket| stream_logs(socket, server_state, min_level, history))
+}
+
+async fn stream_logs(
+    mut socket: WebSocket,
+    server_state: CloudServerState,
+    min_level: LogLevel,
+    history: usize,
+) {
+    let debug_stream = &server_state.debug_stream;
+    let mut stopped = server_state.stopped.clone();
+    // Subscribe before reading the history so nothing falls in between
+    let mut receiver = debug_stream.subscribe();
+
+    if history > 0 {
+        for message in debug_stream.get_recent(history).await {
+            if message.level.at_least(&min_level)
+                && send_frame(&mut socket, &LogFrame::Log(message))
+                    .await
+                    .is_err()
+            {
+                return;
+            }
+        }
+    }
+
+    loop {
+        tokio::select! {
+            received = receiver.recv() => {
+                let sent = match received {
+                    Ok(message) if message.level.at_least(&min_level) => {
+                        send_frame(&mut socket, &LogFrame::Log(message)).await
+                    }
+                    Ok(_) => Ok(()),
+                    Err(RecvError::Lagged(skipped)) => {
+                        send_frame(&mut socket, &LogFrame::Lagged { skipped }).await
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if sent.is_err() {
+                    return;
+                }
+            }
+            // The cloud stopped
+            _ = stopped.changed() => break,
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => return,
+                // Pings are answered by axum; anything else from the client is ignored
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+async fn send_frame(socket: &mut WebSocket, frame: &LogFrame) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(frame).unwrap_or_default();
+    socket.send(Message::Text(text)).await
+}
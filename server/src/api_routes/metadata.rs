@@ -0,0 +1,83 @@
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::fs;
+
+use crate::cloud::CloudServerState;
+use crate::error::{ApiError, ErrorCode};
+use crate::utils::{
+    ensure_inside_cloud_folder, find_cloud_folder, from_unix_seconds, parse_directory_path,
+    set_file_mtime,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct SetMetadataRequest {
+    /// New modification time in unix seconds
+    pub mtime: i64,
+}
+
+/// Set the modification time of an existing file, so sync clients can give a
+/// file the time of its source after a rename or copy
+/// The path should be in format: "cloud_folder_name/subdirectory/path/filename"
+pub async fn api_set_file_metadata(
+    State(server_state): State<CloudServerState>,
+    Path(target_path): Path<String>,
+    Json(request): Json<SetMetadataRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let mtime = from_unix_seconds(request.mtime)
+        .map_err(|e| ApiError::new(ErrorCode::Validation, format!("Invalid mtime: {}", e)))?;
+    let (cloud_folder_name, relative_path) = parse_directory_path(&target_path)?;
+    if relative_path.as_os_str().is_empty() {
+        return Err(ApiError::new(
+            ErrorCode::Validation,
+            "Expected: cloud_folder_name/subdirectory/path/filename",
+        ));
+    }
+    let cloud_folder = find_cloud_folder(&server_state, &cloud_folder_name)?;
+    let file_path = cloud_folder.folder_path.join(&relative_path);
+    let display_path = format!(
+        "{}/{}",
+        cloud_folder_name,
+        relative_path.to_string_lossy().replace('\\', "/")
+    );
+
+    match fs::metadata(&file_path).await {
+        Ok(metadata) if metadata.is_file() => {}
+        Ok(_) => {
+            return Err(ApiError::new(
+                ErrorCode::Conflict,
+                format!("'{}' is not a file", display_path),
+            ))
+        }
+        Err(_) => {
+            return Err(ApiError::new(
+                ErrorCode::NotFound,
+                format!("File '{}' not found", display_path),
+            ))
+        }
+    }
+
+    // A symlink inside the cloud folder could point anywhere
+    ensure_inside_cloud_folder(&cloud_folder.folder_path, &file_path, &display_path).await?;
+
+    set_file_mtime(&file_path, mtime).await.map_err(|e| {
+        ApiError::new(
+            ErrorCode::FileSystem,
+            format!("Failed to set modification time: {}", e),
+        )
+    })?;
+    tracing::info!(
+        mtime = request.mtime,
+        "Set modification time of '{}'",
+        display_path
+    );
+
+    Ok(Json(json!({
+        "success": true,
+        "path": display_path,
+        "mtime": request.mtime
+    })))
+}
@@ -1,7 +1,18 @@
+pub mod camera;
+pub mod clients;
 pub mod cloud;
 pub mod delete;
+pub mod file;
+pub mod file_requests;
 pub mod index;
+pub mod logs;
+pub mod metadata;
+pub mod photos;
+pub mod preview;
+pub mod stream;
+pub mod tokens;
 pub mod upload;
+pub mod usage;
 
 pub use cloud::*;
 pub use index::*;
@@ -0,0 +1,213 @@
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use chrono::{DateTime, Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use crate::cloud::{CloudFolder, CloudServerState};
+use crate::error::{ApiError, ErrorCode};
+use crate::photo_cache::PhotoCache;
+use crate::utils::photo_metadata::{read_photo_metadata, GpsPosition};
+use crate::utils::{entry_name, find_cloud_folder, mime_from_extension};
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimelineGrouping {
+    #[default]
+    Day,
+    Month,
+}
+
+impl TimelineGrouping {
+    fn key(self, taken: &NaiveDateTime) -> String {
+        match self {
+            Self::Day => taken.format("%Y-%m-%d").to_string(),
+            Self::Month => taken.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimelineQuery {
+    #[serde(default)]
+    pub group: TimelineGrouping,
+    /// Only this cloud folder instead of all of them
+    pub cloud_folder: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimelinePhoto {
+    pub cloud_folder: String,
+    /// Relative to the cloud folder, with `/` separators
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    pub taken: NaiveDateTime,
+    /// `exif`, or `mtime` for photos without an EXIF date
+    pub taken_from: &'static str,
+    pub camera: Option<String>,
+    pub gps: Option<GpsPosition>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimelineGroup {
+    /// `YYYY-MM-DD` or `YYYY-MM`
+    pub date: String,
+    pub count: usize,
+    pub photos: Vec<TimelinePhoto>,
+}
+
+/// The images of the cloud folders grouped by the day or month they were taken, newest
+/// first. The date comes from the EXIF data, or the file's modification time if there is
+/// none. EXIF data is cached per cloud, so only new and changed files are read.
+pub async fn api_photo_timeline(
+    State(server_state): State<CloudServerState>,
+    Query(query): Query<TimelineQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let cloud_folders: Vec<CloudFolder> = match &query.cloud_folder {
+        Some(name) => vec![find_cloud_folder(&server_state, name)?.clone()],
+        // Those that went away are left out until they are back
+        None => server_state
+            .cloud
+            .cloud_folders
+            .iter()
+            .filter(|folder| server_state.folder_health.problem(&folder.name).is_none())
+            .cloned()
+            .collect(),
+    };
+    // Photos of folders left out keep their cached EXIF data
+    let full_scan = query.cloud_folder.is_none()
+        && cloud_folders.len() == server_state.cloud.cloud_folders.len();
+    let cache = server_state.photo_cache.clone();
+
+    let photos =
+        tokio::task::spawn_blocking(move || collect_photos(&cloud_folders, &cache, full_scan))
+            .await
+            .map_err(|e| {
+                ApiError::new(
+                    ErrorCode::FileSystem,
+                    format!("Failed to scan photos: {}", e),
+                )
+            })?;
+
+    let total = photos.len();
+    let mut groups: BTreeMap<String, Vec<TimelinePhoto>> = BTreeMap::new();
+    for photo in photos {
+        groups
+            .entry(query.group.key(&photo.taken))
+            .or_default()
+            .push(photo);
+    }
+    let groups: Vec<TimelineGroup> = groups
+        .into_iter()
+        .rev()
+        .map(|(date, mut photos)| {
+            photos.sort_by(|a, b| b.taken.cmp(&a.taken).then_with(|| a.path.cmp(&b.path)));
+            TimelineGroup {
+                date,
+                count: photos.len(),
+                photos,
+            }
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "group": query.group,
+        "total": total,
+        "groups": groups,
+    })))
+}
+
+/// All images below the cloud folders. Blocking. With `full_scan` the cache entries of
+/// files that weren't found are dropped.
+pub(crate) fn collect_photos(
+    cloud_folders: &[CloudFolder],
+    cache: &PhotoCache,
+    full_scan: bool,
+) -> Vec<TimelinePhoto> {
+    let mut photos = Vec::new();
+    let mut seen = HashSet::new();
+    for cloud_folder in cloud_folders {
+        let mut pending = vec![cloud_folder.folder_path.clone()];
+        while let Some(directory) = pending.pop() {
+            let Ok(entries) = std::fs::read_dir(&directory) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Some(name) = entry_name(entry.file_name()) else {
+                    continue;
+                };
+                // Hidden files, and upload temp files which are hidden too
+                if name.starts_with('.') {
+                    continue;
+                }
+                // Symlinks aren't followed, they could lead out of the cloud folder
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                let path = entry.path();
+                if file_type.is_dir() {
+                    pending.push(path);
+                } else if file_type.is_file() && is_image(&path) {
+                    if let Some(photo) = photo(cloud_folder, &path, name, cache) {
+                        seen.insert(format!("{}/{}", photo.cloud_folder, photo.path));
+                        photos.push(photo);
+                    }
+                }
+            }
+        }
+    }
+    if full_scan {
+        cache.retain(|key| seen.contains(key));
+    }
+    cache.save();
+    photos
+}
+
+fn is_image(path: &Path) -> bool {
+    mime_from_extension(path).is_some_and(|mime| mime.starts_with("image/"))
+}
+
+fn photo(
+    cloud_folder: &CloudFolder,
+    path: &Path,
+    name: String,
+    cache: &PhotoCache,
+) -> Option<TimelinePhoto> {
+    let file_metadata = std::fs::metadata(path).ok()?;
+    let modified = DateTime::<Local>::from(file_metadata.modified().ok()?);
+    let relative = path
+        .strip_prefix(&cloud_folder.folder_path)
+        .ok()?
+        .to_string_lossy()
+        .replace('\\', "/");
+    let key = format!("{}/{}", cloud_folder.name, relative);
+    let size = file_metadata.len();
+
+    let metadata = match cache.get(&key, size, modified.timestamp()) {
+        Some(metadata) => metadata,
+        None => {
+            let metadata = read_photo_metadata(path);
+            cache.insert(key, size, modified.timestamp(), metadata.clone());
+            metadata
+        }
+    };
+    let (taken, taken_from) = match metadata.taken {
+        Some(taken) => (taken, "exif"),
+        None => (modified.naive_local(), "mtime"),
+    };
+    Some(TimelinePhoto {
+        cloud_folder: cloud_folder.name.clone(),
+        path: relative,
+        name,
+        size,
+        taken,
+        taken_from,
+        camera: metadata.camera,
+        gps: metadata.gps,
+    })
+}
@@ -0,0 +1,91 @@
+use axum::{
+    body::Body,
+    extract::{Path, Request, State},
+    http::{header, StatusCode},
+    response::Response,
+};
+use tower_http::services::ServeFile;
+
+use crate::cloud::CloudServerState;
+use crate::error::{ApiError, ErrorCode};
+use crate::office_preview::is_previewable;
+use crate::utils::{content_disposition, resolve_path};
+
+/// A Word, Excel or PowerPoint document as PDF, shown inline by the browser. The first
+/// view of a document waits for the converter; after that the PDF comes from the cache.
+pub async fn api_preview_document(
+    State(server_state): State<CloudServerState>,
+    Path((cloud_folder_name, path)): Path<(String, String)>,
+    request: Request,
+) -> Result<Response, ApiError> {
+    let Some(office_preview) = server_state.office_preview.clone() else {
+        return Err(ApiError::new(
+            ErrorCode::NotFound,
+            "Office previews are not enabled for this cloud",
+        ));
+    };
+    let display_path = format!("{}/{}", cloud_folder_name, path.trim_matches('/'));
+    let resolved = resolve_path(&server_state, &cloud_folder_name, &path).await?;
+    if !resolved.metadata.is_file() {
+        return Err(ApiError::new(
+            ErrorCode::NotFound,
+            format!("'{}' is not a file", display_path),
+        ));
+    }
+    let source = resolved.full_path;
+    if !is_previewable(&source) {
+        return Err(ApiError::new(
+            ErrorCode::UnsupportedMediaType,
+            format!("'{}' is not an office document", display_path),
+        ));
+    }
+
+    let pdf = office_preview.pdf(&source).await.map_err(|e| {
+        ApiError::new(
+            ErrorCode::Network,
+            format!("Failed to convert '{}': {}", display_path, e),
+        )
+    })?;
+    let mut response = ServeFile::new(&pdf)
+        .try_call(request)
+        .await
+        .map_err(|e| {
+            ApiError::new(
+                ErrorCode::FileSystem,
+                format!("Failed to read the preview: {}", e),
+            )
+        })?
+        .map(Body::new);
+    if response.status().is_success() {
+        // The document's name with the PDF's extension, for when the PDF is saved
+        let name = source.with_extension("pdf");
+        let name = name
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("preview.pdf");
+        response.headers_mut().insert(
+            header::CONTENT_DISPOSITION,
+            content_disposition("inline", name),
+        );
+    }
+    Ok(response)
+}
+
+/// `GET /preview-source/:token`, public: the document behind a short-lived link the
+/// OnlyOffice Document Server was handed to fetch it
+pub async fn preview_source(
+    State(server_state): State<CloudServerState>,
+    Path(link_token): Path<String>,
+    request: Request,
+) -> Result<Response, StatusCode> {
+    let source = server_state
+        .office_preview
+        .as_ref()
+        .and_then(|office_preview| office_preview.source_link(&link_token))
+        .ok_or(StatusCode::NOT_FOUND)?;
+    ServeFile::new(&source)
+        .try_call(request)
+        .await
+        .map(|response| response.map(Body::new))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
@@ -0,0 +1,199 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::Deserialize;
+use serde_json::json;
+use std::path::Path as StdPath;
+use std::time::{Duration, Instant};
+use tower_http::services::ServeFile;
+
+use crate::cloud::CloudServerState;
+use crate::error::{ApiError, ErrorCode};
+use crate::transcode::{segment_path, Rendition, RenditionFormat};
+use crate::utils::{mime_from_extension, resolve_path};
+
+/// Extensions of videos every current browser plays as they are
+const BROWSER_NATIVE: &[&str] = &["mp4", "m4v", "webm", "ogv"];
+/// How long a request for a new HLS stream waits for the first segment
+const FIRST_SEGMENT_WAIT: Duration = Duration::from_secs(20);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Everything but unreserved characters is escaped in segment links
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamFormat {
+    /// The file as it is
+    Original,
+    Hls,
+    Mp4,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StreamQuery {
+    /// Unset picks the original for videos browsers play, HLS for the rest
+    pub format: Option<StreamFormat>,
+    /// A segment of the HLS playlist, the playlist links them like this
+    pub segment: Option<String>,
+}
+
+/// Stream a video in a form the browser can play. Videos in browser formats are served
+/// as they are (with range requests); others are transcoded to H.264 by ffmpeg when the
+/// cloud has `transcoding` configured. HLS playlists are served as soon as ffmpeg has
+/// written the first segment; an MP4 answers 202 with `Retry-After` until it's done.
+pub async fn api_stream_video(
+    State(server_state): State<CloudServerState>,
+    Path((cloud_folder_name, path)): Path<(String, String)>,
+    Query(query): Query<StreamQuery>,
+    request: Request,
+) -> Result<Response, ApiError> {
+    let display_path = format!("{}/{}", cloud_folder_name, path.trim_matches('/'));
+    let resolved = resolve_path(&server_state, &cloud_folder_name, &path).await?;
+    if !resolved.metadata.is_file() {
+        return Err(ApiError::new(
+            ErrorCode::NotFound,
+            format!("'{}' is not a file", display_path),
+        ));
+    }
+    let source = resolved.full_path;
+    if !is_video(&source) {
+        return Err(ApiError::new(
+            ErrorCode::UnsupportedMediaType,
+            format!("'{}' is not a video", display_path),
+        ));
+    }
+
+    let format = match (query.format, &query.segment) {
+        (_, Some(_)) => RenditionFormat::Hls,
+        (Some(StreamFormat::Original), None) => return serve_file(&source, request).await,
+        (None, None) if is_browser_native(&source) => return serve_file(&source, request).await,
+        (Some(StreamFormat::Mp4), None) => RenditionFormat::Mp4,
+        (Some(StreamFormat::Hls) | None, None) => RenditionFormat::Hls,
+    };
+    let Some(transcoder) = server_state.transcoder.clone() else {
+        return Err(ApiError::new(ErrorCode::UnsupportedMediaType, "Transcoding is not enabled for this cloud, request ?format=original for the file as it is",
+        ));
+    };
+
+    let started = Instant::now();
+    loop {
+        let rendition = transcoder
+            .rendition(&source, &display_path, format, server_state.stopped.clone())
+            .map_err(|e| {
+                ApiError::new(
+                    ErrorCode::Internal,
+                    format!("Failed to transcode '{}': {}", display_path, e),
+                )
+            })?;
+        match rendition {
+            Rendition::Playlist(playlist) => {
+                return match &query.segment {
+                    Some(segment) => match segment_path(&playlist, segment) {
+                        Some(segment) => {
+                            let mut response = serve_file(&segment, request).await?;
+                            if response.status().is_success() {
+                                response.headers_mut().insert(
+                                    header::CONTENT_TYPE,
+                                    HeaderValue::from_static("video/mp2t"),
+                                );
+                            }
+                            Ok(response)
+                        }
+                        None => Err(ApiError::new(
+                            ErrorCode::NotFound,
+                            format!("No segment '{}'", segment),
+                        )),
+                    },
+                    None => serve_playlist(&playlist, &source).await,
+                };
+            }
+            Rendition::File(file) => return serve_file(&file, request).await,
+            Rendition::Failed(error) => {
+                return Err(ApiError::new(
+                    ErrorCode::Internal,
+                    format!("Failed to transcode '{}': {}", display_path, error),
+                ))
+            }
+            Rendition::Pending => {}
+        }
+        // Segments are only linked from a playlist that has them
+        if query.segment.is_some() {
+            return Err(ApiError::new(
+                ErrorCode::NotFound,
+                "The stream has no segments yet",
+            ));
+        }
+        if format == RenditionFormat::Mp4 || started.elapsed() >= FIRST_SEGMENT_WAIT {
+            return Ok((
+                StatusCode::ACCEPTED,
+                [(header::RETRY_AFTER, "5")],
+                Json(json!({ "status": "transcoding" })),
+            )
+                .into_response());
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+fn is_video(path: &StdPath) -> bool {
+    mime_from_extension(path).is_some_and(|mime| mime.starts_with("video/"))
+}
+
+fn is_browser_native(path: &StdPath) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| BROWSER_NATIVE.contains(&extension.to_ascii_lowercase().as_str()))
+}
+
+/// Ranges, conditional requests and HEAD come with ServeFile
+async fn serve_file(path: &StdPath, request: Request) -> Result<Response, ApiError> {
+    ServeFile::new(path)
+        .try_call(request)
+        .await
+        .map(|response| response.map(Body::new))
+        .map_err(|e| ApiError::new(ErrorCode::FileSystem, format!("Failed to read file: {}", e)))
+}
+
+/// The playlist with its segments linked as `<video name>?segment=<segment>`, which
+/// resolves against the stream URL the player requested
+async fn serve_playlist(playlist: &StdPath, source: &StdPath) -> Result<Response, ApiError> {
+    let contents = tokio::fs::read_to_string(playlist).await.map_err(|e| {
+        ApiError::new(
+            ErrorCode::FileSystem,
+            format!("Failed to read the playlist: {}", e),
+        )
+    })?;
+    let name = source
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name = utf8_percent_encode(&name, PATH_SEGMENT).to_string();
+    let rewritten: String = contents
+        .lines()
+        .map(|line| {
+            if line.is_empty() || line.starts_with('#') {
+                format!("{}\n", line)
+            } else {
+                format!("{}?segment={}\n", name, line)
+            }
+        })
+        .collect();
+
+    let mut response = rewritten.into_response();
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/vnd.apple.mpegurl"),
+    );
+    // Grows until ffmpeg is done
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    Ok(response)
+}
@@ -0,0 +1,108 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::api_tokens::{ApiToken, ApiTokenScope};
+use crate::auth::AuthenticatedUser;
+use crate::cloud::CloudServerState;
+use crate::error::{ApiError, ErrorCode};
+
+/// Longest accepted token name
+const MAX_NAME_LENGTH: usize = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTokenRequest {
+    /// What the token is for, e.g. "backup script"
+    pub name: String,
+    #[serde(default)]
+    pub scope: ApiTokenScope,
+}
+
+/// A token can't be used to mint or revoke tokens, so a leaked one can be revoked for good
+fn require_login(user: &AuthenticatedUser) -> Result<(), ApiError> {
+    user.refuse_guest()?;
+    match user.api_token {
+        Some(_) => Err(ApiError::new(
+            ErrorCode::Forbidden,
+            "API tokens are managed with a login, not with another API token",
+        )),
+        None => Ok(()),
+    }
+}
+
+fn token_json(token: &ApiToken) -> serde_json::Value {
+    json!({
+        "id": token.id,
+        "name": token.name,
+        "scope": token.scope,
+        "created_at": token.created_at,
+    })
+}
+
+/// `GET /api/tokens`
+pub async fn api_list_tokens(
+    State(server_state): State<CloudServerState>,
+    user: AuthenticatedUser,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    require_login(&user)?;
+    let tokens: Vec<_> = server_state
+        .auth_state
+        .api_tokens
+        .list()
+        .iter()
+        .map(token_json)
+        .collect();
+    Ok(Json(json!({ "tokens": tokens })))
+}
+
+/// `POST /api/tokens` with `{"name": ..., "scope": ...}`. The answer is the only
+/// time the token is shown.
+pub async fn api_create_token(
+    State(server_state): State<CloudServerState>,
+    user: AuthenticatedUser,
+    Json(request): Json<CreateTokenRequest>,
+) -> Result<(StatusCode, Json<serde_json::Value>), ApiError> {
+    require_login(&user)?;
+    let name = request.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err(ApiError::new(
+            ErrorCode::Validation,
+            format!("Token name must be 1 to {} characters", MAX_NAME_LENGTH),
+        ));
+    }
+
+    let (token, value) = server_state
+        .auth_state
+        .api_tokens
+        .create(name, request.scope);
+    tracing::info!(
+        "Created API token '{}' ({}, {})",
+        token.name,
+        token.id,
+        token.scope
+    );
+    let mut body = token_json(&token);
+    body["token"] = json!(value);
+    Ok((StatusCode::CREATED, Json(body)))
+}
+
+/// `DELETE /api/tokens/:id`
+pub async fn api_revoke_token(
+    State(server_state): State<CloudServerState>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    require_login(&user)?;
+    if !server_state.auth_state.api_tokens.revoke(&id) {
+        return Err(ApiError::new(
+            ErrorCode::NotFound,
+            format!("No API token '{}'", id),
+        ));
+    }
+    tracing::info!("Revoked API token {}", id);
+    Ok(Json(json!({ "revoked": id })))
+}
@@ -1,187 +1,438 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::Json,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Json, Response},
 };
+use axum_extra::extract::multipart::Field;
 use axum_extra::extract::Multipart;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::path::Path as StdPath;
+use std::path::{Path as StdPath, PathBuf};
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
 
-use crate::cloud::CloudServerState;
-use crate::utils::parse_target_path;
+use crate::auth::AuthenticatedUser;
+use crate::cloud::{CloudFolder, CloudServerState};
+use crate::disk_space::{self, DiskSpaceConfig};
+use crate::error::{ApiError, ErrorCode};
+use crate::hooks::{self, HookFile};
+use crate::utils::{
+    ensure_inside_cloud_folder, find_cloud_folder, is_dir, mtime_from_headers,
+    parse_directory_path, set_file_mtime, to_unix_seconds, upload_temp_path,
+    validate_path_component, MTIME_HEADER,
+};
+use crate::webhooks::WebhookEvent;
 
-/// Generate a unique filename by appending (1), (2), etc. if the file already exists
-/// This mimics Windows-style duplicate file handling
-fn generate_unique_filename(base_path: &StdPath, filename: &str) -> String {
-    if !base_path.exists() {
-        return filename.to_string();
-    }
+/// What an upload does when a file with the same name exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnConflict {
+    /// Store as `name(1).ext`, `name(2).ext`, ...
+    #[default]
+    Rename,
+    /// Fail with 409 Conflict
+    Reject,
+    /// Replace the existing file. Only honored when the upload asks for it,
+    /// never as a cloud's default.
+    Overwrite,
+}
 
-    let path = base_path.join(filename);
-    if !path.exists() {
-        return filename.to_string();
+impl OnConflict {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
     }
+}
 
+#[derive(Debug, Default, Deserialize)]
+pub struct UploadQuery {
+    /// Create missing directories of the target path instead of failing
+    #[serde(default)]
+    pub create_dirs: bool,
+    /// Overrides the cloud's `upload_conflict` for this upload
+    pub on_conflict: Option<OnConflict>,
+}
+
+/// Make sure the target directory exists (creating it if allowed) and is inside the
+/// cloud folder once symlinks are resolved. Returns whether directories were created.
+pub(crate) async fn prepare_target_directory(
+    root: &StdPath,
+    directory: &StdPath,
+    display_path: &str,
+    create_dirs: bool,
+) -> Result<bool, ApiError> {
+    let created = match fs::metadata(directory).await {
+        Ok(metadata) if metadata.is_dir() => false,
+        Ok(_) => {
+            return Err(ApiError::new(
+                ErrorCode::Conflict,
+                format!("Target '{}' is not a directory", display_path),
+            ))
+        }
+        // A file somewhere along the path
+        Err(e) if e.kind() == std::io::ErrorKind::NotADirectory => {
+            return Err(ApiError::new(
+                ErrorCode::Conflict,
+                format!("Target '{}' is inside a file", display_path),
+            ))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && !create_dirs => {
+            return Err(ApiError::new(
+                ErrorCode::NotFound,
+                format!(
+                    "Target directory '{}' does not exist (add ?create_dirs=true to create it)",
+                    display_path
+                ),
+            ))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            fs::create_dir_all(directory).await.map_err(|e| {
+                ApiError::new(
+                    ErrorCode::Conflict,
+                    format!("Failed to create directory '{}': {}", display_path, e),
+                )
+            })?;
+            true
+        }
+        Err(e) => {
+            return Err(ApiError::new(
+                ErrorCode::FileSystem,
+                format!("Failed to read target '{}': {}", display_path, e),
+            ))
+        }
+    };
+
+    // A symlink inside the cloud folder could point anywhere
+    ensure_inside_cloud_folder(root, directory, display_path).await?;
+    Ok(created)
+}
+
+/// Windows-style alternatives for a taken name: `name(1).ext` ... `name(49).ext`,
+/// then a timestamped name
+pub(crate) fn renamed_candidates(filename: &str) -> impl Iterator<Item = String> + '_ {
     // Split filename into name and extension
-    let (name, extension) = if let Some(dot_pos) = filename.rfind('.') {
-        let (name_part, ext_part) = filename.split_at(dot_pos);
-        (name_part, ext_part)
-    } else {
-        (filename, "")
+    let (name, extension) = match filename.rfind('.') {
+        Some(dot_pos) if dot_pos > 0 => filename.split_at(dot_pos),
+        _ => (filename, ""),
     };
+    let numbered = (1..50).map(move |i| format!("{}({}){}", name, i, extension));
+    let timestamped = std::iter::once_with(move || {
+        let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S_%3f");
+        format!("{}_{}{}", name, timestamp, extension)
+    });
+    numbered.chain(timestamped)
+}
 
-    // Try filename(1), filename(2), etc.
-    for i in 1..50 {
-        let new_filename = if extension.is_empty() {
-            format!("{}({})", name, i)
-        } else {
-            format!("{}({}){}", name, i, extension)
-        };
+fn write_error(e: std::io::Error) -> ApiError {
+    ApiError::new(
+        ErrorCode::FileSystem,
+        format!("Failed to write file: {}", e),
+    )
+}
+
+/// Create `path` only if nothing is there; `Ok(false)` when it already exists
+async fn write_new_file(path: &StdPath, data: &[u8]) -> Result<bool, ApiError> {
+    let mut file = match fs::File::create_new(path).await {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => return Ok(false),
+        Err(e) => return Err(write_error(e)),
+    };
+    file.write_all(data).await.map_err(write_error)?;
+    file.flush().await.map_err(write_error)?;
+    Ok(true)
+}
 
-        let new_path = base_path.join(&new_filename);
-        if !new_path.exists() {
-            return new_filename;
+/// Move a finished upload from `temp_path` to `filename` in `directory`, or to a
+/// renamed alternative when that's taken; never replaces a file. Returns the name used.
+pub(crate) async fn move_to_free_name(
+    directory: &StdPath,
+    temp_path: &StdPath,
+    filename: &str,
+) -> Result<String, ApiError> {
+    for candidate in std::iter::once(filename.to_string()).chain(renamed_candidates(filename)) {
+        let path = directory.join(&candidate);
+        // Claim the name with an empty file, then move the upload over it
+        if write_new_file(&path, &[]).await? {
+            if let Err(e) = fs::rename(temp_path, &path).await {
+                let _ = fs::remove_file(&path).await;
+                return Err(write_error(e));
+            }
+            return Ok(candidate);
         }
     }
+    Err(ApiError::new(
+        ErrorCode::Conflict,
+        format!("No free name found for '{}'", filename),
+    ))
+}
+
+/// Where an upload ended up
+struct StoredUpload {
+    /// For the folder's post_upload hook
+    cloud_folder: CloudFolder,
+    path: PathBuf,
+    /// `cloud_folder_name/sub/dir/filename`, the path clients use with the other endpoints
+    stored_path: String,
+    filename: String,
+    size: u64,
+    created_dirs: bool,
+    conflict: ConflictOutcome,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ConflictOutcome {
+    /// No file had that name
+    None,
+    Renamed,
+    Overwritten,
+}
+
+/// Stream a multipart field to a new file at `path`, stopping once it gets larger than
+/// the cloud's upload limit; returns its size
+async fn receive_field(
+    field: &mut Field,
+    path: &StdPath,
+    config: &DiskSpaceConfig,
+) -> Result<u64, ApiError> {
+    let mut file = fs::File::create_new(path).await.map_err(write_error)?;
+    let mut size = 0u64;
+    while let Some(chunk) = field
+        .chunk()
+        .await
+        .map_err(|e| ApiError::validation(format!("Failed to read file data: {}", e)))?
+    {
+        size += chunk.len() as u64;
+        disk_space::check_upload_size(config, size)
+            .map_err(|e| ApiError::new(ErrorCode::PayloadTooLarge, e))?;
+        file.write_all(&chunk).await.map_err(write_error)?;
+    }
+    file.flush().await.map_err(write_error)?;
+    Ok(size)
+}
 
-    // Fallback: use timestamp if we can't find a unique name
-    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S_%3f");
-    if extension.is_empty() {
-        format!("{}_{}", name, timestamp)
-    } else {
-        format!("{}_{}{}", name, timestamp, extension)
+/// Move the received upload at `temp_path` to `filename` in `directory`, resolving a
+/// name collision according to `on_conflict`. Returns the name used and what happened.
+pub(crate) async fn place_upload(
+    directory: &StdPath,
+    temp_path: &StdPath,
+    filename: &str,
+    on_conflict: OnConflict,
+) -> Result<(String, ConflictOutcome), ApiError> {
+    let path = directory.join(filename);
+    match on_conflict {
+        OnConflict::Reject => {
+            // Claim the name with an empty file, then move the upload over it
+            if !write_new_file(&path, &[]).await? {
+                return Err(ApiError::new(
+                    ErrorCode::FileExists,
+                    format!("File '{}' already exists", filename),
+                ));
+            }
+            if let Err(e) = fs::rename(temp_path, &path).await {
+                let _ = fs::remove_file(&path).await;
+                return Err(write_error(e));
+            }
+            Ok((filename.to_string(), ConflictOutcome::None))
+        }
+        OnConflict::Overwrite => {
+            if is_dir(&path).await {
+                return Err(ApiError::new(
+                    ErrorCode::Conflict,
+                    format!("'{}' is a directory", filename),
+                ));
+            }
+            let existed = fs::symlink_metadata(&path).await.is_ok();
+            // Readers see the old or the new file, never half of one
+            fs::rename(temp_path, &path).await.map_err(write_error)?;
+            let conflict = if existed {
+                ConflictOutcome::Overwritten
+            } else {
+                ConflictOutcome::None
+            };
+            Ok((filename.to_string(), conflict))
+        }
+        OnConflict::Rename => {
+            let name = move_to_free_name(directory, temp_path, filename).await?;
+            let conflict = if name == filename {
+                ConflictOutcome::None
+            } else {
+                ConflictOutcome::Renamed
+            };
+            Ok((name, conflict))
+        }
     }
 }
 
-/// Shared function to save uploaded file to the filesystem with duplicate handling
+/// Shared function to save an uploaded file to the filesystem. The field is streamed to
+/// a temporary file next to its target, then moved into place as the upload's
+/// `on_conflict` or the cloud's default says.
 async fn save_uploaded_file(
     server_state: &CloudServerState,
-    cloud_folder_name: &str,
-    subdirectory_path: &str,
+    target_path: &str,
     filename: &str,
-    data: &[u8],
-) -> Result<(String, String), (StatusCode, Json<serde_json::Value>)> {
-    // Find the cloud folder
-    let cloud_folder = server_state
-        .cloud
-        .cloud_folders
-        .iter()
-        .find(|folder| folder.name == *cloud_folder_name)
-        .ok_or_else(|| {
-            (
-                StatusCode::NOT_FOUND,
-                Json(json!({
-                    "error": "Cloud folder not found"
-                })),
-            )
-        })?;
-
-    // Create the directory path for the uploaded file
-    let final_path = if subdirectory_path.is_empty() {
-        cloud_folder.folder_path.clone()
-    } else {
-        cloud_folder.folder_path.join(subdirectory_path)
-    };
+    field: &mut Field,
+    announced_size: Option<u64>,
+    query: &UploadQuery,
+    user: &str,
+) -> Result<StoredUpload, ApiError> {
+    let (cloud_folder_name, subdirectory) = parse_directory_path(target_path)?;
+    validate_path_component(filename)
+        .map_err(|e| ApiError::new(ErrorCode::InvalidPath, format!("Invalid file name: {}", e)))?;
+    let cloud_folder = find_cloud_folder(server_state, &cloud_folder_name)?;
 
-    // Ensure the directory exists
-    fs::create_dir_all(&final_path).await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": format!("Failed to create directory: {}", e)
-            })),
-        )
-    })?;
+    let final_path = cloud_folder.folder_path.join(&subdirectory);
+    let display_path = StdPath::new(&cloud_folder_name).join(&subdirectory);
+    let created_dirs = prepare_target_directory(
+        &cloud_folder.folder_path,
+        &final_path,
+        &display_path.to_string_lossy(),
+        query.create_dirs,
+    )
+    .await?;
+    let on_conflict = query
+        .on_conflict
+        .unwrap_or(server_state.cloud.upload_conflict);
 
-    // Generate unique filename to handle duplicates
-    let unique_filename = generate_unique_filename(&final_path, filename);
-    let upload_path = final_path.join(&unique_filename);
+    let requested_path = display_path
+        .join(filename)
+        .to_string_lossy()
+        .replace('\\', "/");
+    disk_space::check_upload(&server_state.cloud.disk_space, cloud_folder, announced_size)
+        .map_err(|e| ApiError::new(ErrorCode::InsufficientStorage, e))?;
 
-    // Try to create the file with create_new (fails if exists)
-    let mut file = match fs::File::create_new(&upload_path).await {
-        Ok(file) => file,
-        Err(_) => {
-            // If create_new fails, fall back to regular create (overwrite)
-            fs::File::create(&upload_path).await.map_err(|e| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(json!({
-                        "error": format!("Failed to create file: {}", e)
-                    })),
-                )
-            })?
+    let temp_path = upload_temp_path(&final_path);
+    let placed = async {
+        let size = receive_field(field, &temp_path, &server_state.cloud.disk_space).await?;
+        hooks::pre_upload(
+            &server_state.cloud.name,
+            cloud_folder,
+            &HookFile {
+                path: &temp_path,
+                display_path: &requested_path,
+                size: Some(size),
+                user,
+                source: "api",
+            },
+        )
+        .await
+        .map_err(|e| ApiError::new(ErrorCode::Forbidden, e))?;
+        place_upload(&final_path, &temp_path, filename, on_conflict)
+            .await
+            .map(|(filename, conflict)| (filename, conflict, size))
+    }
+    .await;
+    let (filename, conflict, size) = match placed {
+        Ok(placed) => placed,
+        Err(e) => {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(match e.code {
+                ErrorCode::FileExists => e.with("existing_path", requested_path).with(
+                    "hint",
+                    "Upload with ?on_conflict=rename or ?on_conflict=overwrite",
+                ),
+                _ => e,
+            });
         }
     };
 
-    file.write_all(data).await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": format!("Failed to write file: {}", e)
-            })),
-        )
-    })?;
-
-    Ok((upload_path.to_string_lossy().to_string(), unique_filename))
+    Ok(StoredUpload {
+        cloud_folder: cloud_folder.clone(),
+        path: final_path.join(&filename),
+        stored_path: display_path
+            .join(&filename)
+            .to_string_lossy()
+            .replace('\\', "/"),
+        filename,
+        size,
+        created_dirs,
+        conflict,
+    })
 }
 
 /// Upload a file to a specific path
-/// The path should be in format: "cloud_folder_name/subdirectory/path"
+/// The path should be in format: "cloud_folder_name/subdirectory/path", every part of it
+/// a directory. Missing directories are created with `?create_dirs=true`.
+/// An `X-OC-Mtime: <unix seconds>` header sets the stored file's modification time,
+/// confirmed with `X-OC-MTime: accepted` in the response.
 pub async fn api_upload_file(
     State(server_state): State<CloudServerState>,
     Path(target_path): Path<String>,
+    Query(query): Query<UploadQuery>,
+    headers: HeaderMap,
+    user: AuthenticatedUser,
     mut multipart: Multipart,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
-    // Parse the target path using shared utils
-    let parsed_path = parse_target_path(&target_path)?;
+) -> Result<Response, ApiError> {
+    // Checked before reading the body so a bad header doesn't cost an upload
+    let mtime = mtime_from_headers(&headers)?;
 
     // Process the multipart form data
-    if let Some(field) = multipart.next_field().await.map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(json!({
-                "error": format!("Failed to read multipart field: {}", e)
-            })),
-        )
-    })? {
+    if let Some(mut field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| ApiError::validation(format!("Failed to read multipart field: {}", e)))?
+    {
         let filename = field
             .file_name()
-            .ok_or_else(|| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(json!({
-                        "error": "No filename provided"
-                    })),
-                )
-            })?
+            .ok_or_else(|| ApiError::validation("No filename provided"))?
             .to_string();
 
-        let data = field.bytes().await.map_err(|e| {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(json!({
-                    "error": format!("Failed to read file data: {}", e)
-                })),
-            )
-        })?;
+        // Known only for the whole request, which is a little more than the file
+        let announced_size = headers
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok());
 
         // Use the shared function to save the file
-        let (upload_path, actual_filename) = save_uploaded_file(
+        let upload = save_uploaded_file(
             &server_state,
-            &parsed_path.cloud_folder_name,
-            &parsed_path.subdirectory_path,
+            &target_path,
             &filename,
-            &data,
+            &mut field,
+            announced_size,
+            &query,
+            &user.name(),
         )
         .await?;
+        if let Some(mtime) = mtime {
+            set_file_mtime(&upload.path, mtime).await.map_err(|e| {
+                ApiError::new(
+                    ErrorCode::FileSystem,
+                    format!(
+                        "Stored '{}' but failed to set its modification time: {}",
+                        upload.stored_path, e
+                    ),
+                )
+            })?;
+        }
+        tracing::info!(
+            bytes = upload.size,
+            conflict = ?upload.conflict,
+            "Uploaded '{}'",
+            upload.stored_path
+        );
+        server_state.webhooks.file_event(
+            WebhookEvent::FileUploaded,
+            &upload.stored_path,
+            Some(upload.size),
+            "api",
+        );
+        hooks::post_upload(
+            &server_state.cloud.name,
+            &upload.cloud_folder,
+            &HookFile {
+                path: &upload.path,
+                display_path: &upload.stored_path,
+                size: Some(upload.size),
+                user: &user.name(),
+                source: "api",
+            },
+        );
 
         // Check if filename was changed due to duplicates
-        let duplicate_info = if actual_filename != filename {
+        let duplicate_info = if upload.filename != filename {
             json!({
                 "original_filename": filename,
-                "actual_filename": actual_filename,
+                "actual_filename": upload.filename,
                 "duplicate_handled": true
             })
         } else {
@@ -190,26 +441,34 @@ pub async fn api_upload_file(
             })
         };
 
-        return Ok(Json(json!({
+        let mut response = Json(json!({
             "success": true,
-            "message": format!("File '{}' uploaded successfully", actual_filename),
-            "path": upload_path,
-            "filename": actual_filename,
+            "message": format!("File '{}' uploaded successfully", upload.filename),
+            "path": upload.path.to_string_lossy(),
+            "stored_path": upload.stored_path,
+            "filename": upload.filename,
+            "conflict": upload.conflict,
             "duplicate_info": duplicate_info,
+            "created_directories": upload.created_dirs,
+            "mtime": mtime.map(to_unix_seconds),
             "usage": {
                 "path_based": "POST /api/upload/{cloud_folder_name}/{subdirectory_path}",
                 "examples": [
                     "POST /api/upload/my_cloud",
-                    "POST /api/upload/my_cloud/documents/projects"
+                    "POST /api/upload/my_cloud/documents/projects",
+                    "POST /api/upload/my_cloud/new/nested/dir?create_dirs=true",
+                    "POST /api/upload/my_cloud/documents?on_conflict=overwrite"
                 ]
             }
-        })));
+        }))
+        .into_response();
+        if mtime.is_some() {
+            response
+                .headers_mut()
+                .insert(MTIME_HEADER, HeaderValue::from_static("accepted"));
+        }
+        return Ok(response);
     }
 
-    Err((
-        StatusCode::BAD_REQUEST,
-        Json(json!({
-            "error": "No file provided in the request"
-        })),
-    ))
+    Err(ApiError::validation("No file provided in the request"))
 }
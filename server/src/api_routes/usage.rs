@@ -0,0 +1,134 @@
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::json;
+use std::fmt::Write;
+
+use crate::auth::AuthenticatedUser;
+use crate::cloud::CloudServerState;
+use crate::disk_space::{self, FolderSpace, SpaceLevel};
+use crate::error::ApiError;
+
+/// `GET /api/usage`: free space of every cloud folder's filesystem
+pub async fn api_usage(
+    State(server_state): State<CloudServerState>,
+    user: AuthenticatedUser,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    user.refuse_guest()?;
+    let cloud = &server_state.cloud;
+    let config = &cloud.disk_space;
+    let folders = disk_space::folder_space(config, &cloud.cloud_folders);
+    let level = folders
+        .iter()
+        .map(|folder| folder.level)
+        .max()
+        .unwrap_or(SpaceLevel::Ok);
+
+    Ok(Json(json!({
+        "cloud": cloud.name,
+        "level": level,
+        "min_free_mb": config.min_free_mb,
+        "warn_free_mb": config.warn_free_mb,
+        "max_upload_mb": config.max_upload_mb,
+        "folders": folders
+            .iter()
+            .map(|folder| {
+                json!({
+                    "name": folder.folder,
+                    "free_bytes": folder.free_bytes,
+                    "total_bytes": folder.total_bytes,
+                    "level": folder.level,
+                })
+            })
+            .collect::<Vec<_>>(),
+    })))
+}
+
+/// `GET /api/stats`: requests, transfers, active connections and uptime since the start
+pub async fn api_stats(
+    State(server_state): State<CloudServerState>,
+    user: AuthenticatedUser,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    user.refuse_guest()?;
+    Ok(Json(json!({
+        "cloud": server_state.cloud.name,
+        "stats": server_state.stats.snapshot(),
+    })))
+}
+
+/// Label values may not contain raw backslashes, quotes or newlines
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+type FolderValue = fn(&FolderSpace) -> u64;
+
+/// `GET /api/metrics`: the same numbers in the Prometheus text format
+pub async fn api_metrics(
+    State(server_state): State<CloudServerState>,
+    user: AuthenticatedUser,
+) -> Result<Response, ApiError> {
+    user.refuse_guest()?;
+    let cloud = &server_state.cloud;
+    let config = &cloud.disk_space;
+    let folders = disk_space::folder_space(config, &cloud.cloud_folders);
+    let cloud_label = escape_label(&cloud.name);
+
+    let mut body = String::new();
+    let gauges: [(&str, &str, FolderValue); 3] = [
+        (
+            "cloudhost_folder_free_bytes",
+            "Free space on the filesystem of a cloud folder",
+            |folder| folder.free_bytes,
+        ),
+        (
+            "cloudhost_folder_total_bytes",
+            "Size of the filesystem of a cloud folder",
+            |folder| folder.total_bytes,
+        ),
+        (
+            "cloudhost_folder_low_space",
+            "1 when free space is below warn_free_mb, 2 when uploads are refused",
+            |folder| folder.level as u64,
+        ),
+    ];
+    for (name, help, value) in gauges {
+        let _ = writeln!(body, "# HELP {} {}", name, help);
+        let _ = writeln!(body, "# TYPE {} gauge", name);
+        for folder in &folders {
+            let _ = writeln!(
+                body,
+                "{}{{cloud=\"{}\",folder=\"{}\"}} {}",
+                name,
+                cloud_label,
+                escape_label(&folder.folder),
+                value(folder)
+            );
+        }
+    }
+    let _ = writeln!(
+        body,
+        "# HELP cloudhost_min_free_bytes Uploads are refused below this much free space"
+    );
+    let _ = writeln!(body, "# TYPE cloudhost_min_free_bytes gauge");
+    let _ = writeln!(
+        body,
+        "cloudhost_min_free_bytes{{cloud=\"{}\"}} {}",
+        cloud_label,
+        config.min_free_mb * 1024 * 1024
+    );
+
+    Ok((
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        body,
+    )
+        .into_response())
+}
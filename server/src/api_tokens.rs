@@ -0,0 +1,222 @@
+/// API tokens: long-lived bearer tokens for scripts and devices, each with its own name
+/// and scope, revocable one by one. Only a SHA-256 hash of each token is kept; the
+/// token itself is shown once when it's created.
+use axum::http::Method;
+use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use crate::auth::lock;
+
+/// Prefix of every API token, so they're easy to tell from login tokens (and to find
+/// when they leak into a repository)
+pub const TOKEN_PREFIX: &str = "cht_";
+
+/// What requests an API token may make
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ApiTokenScope {
+    /// Everything a login can do, except managing API tokens
+    #[default]
+    Full,
+    /// Listing, downloading and streaming
+    ReadOnly,
+    /// Uploads (`/api/upload`, `/api/camera`) and nothing else, for cameras and backups
+    UploadOnly,
+    /// The Nextcloud and WebDAV routes (`/remote.php`, `/ocs`) and nothing else; the
+    /// Nextcloud login flow hands these out as app passwords
+    Nextcloud,
+}
+
+impl ApiTokenScope {
+    pub const ALL: [ApiTokenScope; 4] = [
+        Self::Full,
+        Self::ReadOnly,
+        Self::UploadOnly,
+        Self::Nextcloud,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Full => "full",
+            Self::ReadOnly => "read-only",
+            Self::UploadOnly => "upload-only",
+            Self::Nextcloud => "nextcloud",
+        }
+    }
+
+    /// Whether a request with this method and path is within the scope
+    pub fn allows(self, method: &Method, path: &str) -> bool {
+        match self {
+            Self::Full => true,
+            Self::ReadOnly => {
+                matches!(method.as_str(), "GET" | "HEAD" | "OPTIONS" | "PROPFIND")
+            }
+            Self::UploadOnly => {
+                *method == Method::POST
+                    && (path.starts_with("/api/upload/") || path.starts_with("/api/camera/"))
+            }
+            Self::Nextcloud => path.starts_with("/remote.php/") || path.starts_with("/ocs/"),
+        }
+    }
+}
+
+impl fmt::Display for ApiTokenScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ApiTokenScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| {
+                format!(
+                    "Unknown scope '{}', expected {}",
+                    s,
+                    Self::ALL.map(|scope| scope.as_str()).join(", ")
+                )
+            })
+    }
+}
+
+/// `[[clouds.api_tokens]]` in the clouds config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiToken {
+    /// Public part of the token, used to revoke it
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub scope: ApiTokenScope,
+    /// SHA-256 of the whole token, hex
+    pub hash: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ApiToken {
+    /// A new token and its secret value, `cht_<id>_<secret>`
+    pub fn generate(name: &str, scope: ApiTokenScope) -> (Self, String) {
+        let id = uuid::Uuid::new_v4().simple().to_string()[..8].to_string();
+        let mut secret = [0u8; 32];
+        OsRng.fill_bytes(&mut secret);
+        let secret: String = secret.iter().map(|byte| format!("{:02x}", byte)).collect();
+        let value = format!("{}{}_{}", TOKEN_PREFIX, id, secret);
+        let token = Self {
+            id,
+            name: name.trim().to_string(),
+            scope,
+            hash: hash_token(&value),
+            created_at: chrono::Utc::now(),
+        };
+        (token, value)
+    }
+
+    /// Problems with a configured token, as `field: message`
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.id.is_empty() || !self.id.bytes().all(|byte| byte.is_ascii_alphanumeric()) {
+            problems.push(format!("id: '{}' must be letters and digits", self.id));
+        }
+        if self.name.trim().is_empty() {
+            problems.push("name: must not be empty".to_string());
+        }
+        if self.hash.len() != 64 || !self.hash.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            problems.push("hash: must be a hex SHA-256".to_string());
+        }
+        problems
+    }
+}
+
+fn hash_token(value: &str) -> String {
+    Sha256::digest(value.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// The API tokens of a running cloud. Tokens created or revoked through the API are
+/// saved by the orchestrator, which picks them up with `take_changes`.
+#[derive(Debug, Default)]
+pub struct ApiTokens {
+    tokens: Mutex<Vec<ApiToken>>,
+    changed: Mutex<bool>,
+}
+
+impl ApiTokens {
+    pub fn new(tokens: Vec<ApiToken>) -> Self {
+        Self {
+            tokens: Mutex::new(tokens),
+            changed: Mutex::new(false),
+        }
+    }
+
+    /// The token a bearer value belongs to
+    pub fn verify(&self, value: &str) -> Option<ApiToken> {
+        let (id, _) = value.strip_prefix(TOKEN_PREFIX)?.split_once('_')?;
+        let hash = hash_token(value);
+        lock(&self.tokens)
+            .iter()
+            .find(|token| token.id == id && constant_time_eq(&token.hash, &hash))
+            .cloned()
+    }
+
+    pub fn list(&self) -> Vec<ApiToken> {
+        lock(&self.tokens).clone()
+    }
+
+    /// Create a token; returns it with its secret value
+    pub fn create(&self, name: &str, scope: ApiTokenScope) -> (ApiToken, String) {
+        let (token, value) = ApiToken::generate(name, scope);
+        lock(&self.tokens).push(token.clone());
+        self.mark_changed();
+        (token, value)
+    }
+
+    /// Revoke a token by id; false if there's none
+    pub fn revoke(&self, id: &str) -> bool {
+        let removed = {
+            let mut tokens = lock(&self.tokens);
+            let before = tokens.len();
+            tokens.retain(|token| token.id != id);
+            tokens.len() != before
+        };
+        if removed {
+            self.mark_changed();
+        }
+        removed
+    }
+
+    /// Replace the tokens with the saved ones, after they were changed outside the server
+    pub fn replace(&self, new_tokens: Vec<ApiToken>) {
+        *lock(&self.tokens) = new_tokens;
+    }
+
+    /// All tokens, if they were created or revoked through the API since the last call
+    pub fn take_changes(&self) -> Option<Vec<ApiToken>> {
+        let mut changed = lock(&self.changed);
+        if !*changed {
+            return None;
+        }
+        *changed = false;
+        Some(self.list())
+    }
+
+    fn mark_changed(&self) {
+        *lock(&self.changed) = true;
+    }
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
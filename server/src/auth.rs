@@ -1,24 +1,82 @@
+use crate::api_tokens::{ApiToken, ApiTokenScope, ApiTokens, TOKEN_PREFIX};
+use crate::auth_log::{AuthFailure, AuthFailureLog};
+use crate::cloud::{Cloud, CloudServerState};
+use crate::email::EmailNotifier;
+use crate::error::{ApiError, ErrorCode};
+use crate::login_limiter::LoginLimiter;
+use crate::password::{self, PasswordHashParams};
+use crate::recovery::RecoveryCodes;
+use crate::sessions::{RevokedSession, RevokedSessions, SessionConfig};
+use crate::webhooks::{WebhookEvent, Webhooks};
 use axum::{
-    extract::{Request, State},
-    http::{HeaderMap, StatusCode},
+    async_trait,
+    extract::{FromRequestParts, Request, State},
+    http::{
+        header::{AUTHORIZATION, COOKIE},
+        request::Parts,
+        HeaderMap, Method,
+    },
     middleware::Next,
-    response::Response,
+    response::{Html, IntoResponse, Response},
 };
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use percent_encoding::percent_decode_str;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
     pub exp: usize,
     pub pwd_changed: i64, // Password changed timestamp
+    /// Id for revoking the token on logout; empty in tokens from before logout existed
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub jti: String,
+}
+
+/// The password hash and when it last changed, replaced together so a request never sees
+/// one without the other
+#[derive(Debug, Default)]
+struct Credentials {
+    password: Option<String>,
+    changed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Subject of the year-long app passwords the Nextcloud login flow signed before it
+/// handed out API tokens
+const LEGACY_APP_PASSWORD_SUBJECT: &str = "app";
+
+/// A poisoned lock is used as it is: the auth state's locks, the API tokens' included,
+/// are only held for a copy, an assignment or a `Vec` edit, which leave it consistent,
+/// and a panic elsewhere mustn't lock everyone out
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
 }
 
 pub struct AuthState {
     pub secret: String,
-    pub password: std::sync::Mutex<Option<String>>,
-    pub password_changed_at: std::sync::Mutex<Option<chrono::DateTime<chrono::Utc>>>,
+    /// Read by every request, snapshotted rather than held
+    credentials: RwLock<Arc<Credentials>>,
+    pub hash_params: PasswordHashParams,
+    /// New hash of a plaintext or outdated password, set on login until the orchestrator saves it
+    upgraded_password: Mutex<Option<String>>,
+    pub failure_log: Option<Arc<AuthFailureLog>>,
+    /// Bearer tokens for scripts and devices, besides the login tokens
+    pub api_tokens: ApiTokens,
+    /// Emails about bursts of failed logins, when the cloud has `email` configured
+    pub notifier: Option<Arc<EmailNotifier>>,
+    /// Also used for the cloud's file events, by HTTP and FTP alike
+    pub webhooks: Arc<Webhooks>,
+    /// One-time code issued in the TUI for setting a forgotten password
+    pub recovery: RecoveryCodes,
+    /// Failed logins per address, for lockouts
+    pub login_limiter: LoginLimiter,
+    /// New hash and change time set with a recovery code, until the orchestrator saves it
+    recovered_password: Mutex<Option<(String, chrono::DateTime<chrono::Utc>)>>,
+    /// Lifetimes of web logins
+    pub sessions: SessionConfig,
+    /// Login tokens ended by logging out
+    pub revoked_sessions: RevokedSessions,
 }
 
 impl AuthState {
@@ -26,39 +84,199 @@ impl AuthState {
         secret: String,
         password: Option<String>,
         password_changed_at: Option<chrono::DateTime<chrono::Utc>>,
+        hash_params: PasswordHashParams,
     ) -> Self {
         Self {
             secret,
-            password: std::sync::Mutex::new(password),
-            password_changed_at: std::sync::Mutex::new(password_changed_at),
+            credentials: RwLock::new(Arc::new(Credentials {
+                password,
+                changed_at: password_changed_at,
+            })),
+            hash_params,
+            upgraded_password: Mutex::new(None),
+            failure_log: None,
+            api_tokens: ApiTokens::default(),
+            notifier: None,
+            webhooks: Arc::default(),
+            recovery: RecoveryCodes::default(),
+            login_limiter: LoginLimiter::default(),
+            recovered_password: Mutex::new(None),
+            sessions: SessionConfig::default(),
+            revoked_sessions: RevokedSessions::default(),
+        }
+    }
+
+    /// Record failed logins in the auth failure log
+    pub fn with_failure_log(mut self, failure_log: Arc<AuthFailureLog>) -> Self {
+        self.failure_log = Some(failure_log);
+        self
+    }
+
+    /// Accept the cloud's API tokens as bearer tokens
+    pub fn with_api_tokens(mut self, api_tokens: Vec<ApiToken>) -> Self {
+        self.api_tokens = ApiTokens::new(api_tokens);
+        self
+    }
+
+    /// Send the cloud's email notifications through `notifier`
+    pub fn with_notifier(mut self, notifier: Arc<EmailNotifier>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Make web logins last as long as the cloud configures
+    pub fn with_sessions(mut self, sessions: SessionConfig) -> Self {
+        self.sessions = sessions;
+        self
+    }
+
+    /// Keep refusing the tokens that were logged out
+    pub fn with_revoked_sessions(mut self, revoked: Vec<RevokedSession>) -> Self {
+        self.revoked_sessions = RevokedSessions::new(revoked);
+        self
+    }
+
+    /// Send the cloud's events to its webhooks
+    pub fn with_webhooks(mut self, webhooks: Webhooks) -> Self {
+        self.webhooks = Arc::new(webhooks);
+        self
+    }
+
+    pub fn record_failure(&self, failure: &AuthFailure) {
+        tracing::warn!(
+            ip = %failure.ip,
+            reason = failure.reason,
+            "Failed login"
+        );
+        if let Some(failure_log) = &self.failure_log {
+            failure_log.record(failure);
         }
+        self.webhooks.emit(
+            WebhookEvent::LoginFailed,
+            serde_json::json!({
+                "ip": failure.ip,
+                "forwarded_for": failure.forwarded_for,
+                "reason": failure.reason,
+                "request_id": failure.request_id,
+            }),
+        );
+        if let Some(notifier) = &self.notifier {
+            notifier.record_failed_login(&failure.forwarded_for.unwrap_or(failure.ip).to_string());
+        }
+    }
+
+    fn credentials(&self) -> Arc<Credentials> {
+        self.credentials
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
     }
 
+    fn set_credentials(&self, credentials: Credentials) {
+        *self
+            .credentials
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Arc::new(credentials);
+    }
+
+    /// Verify a login password, rehashing it when the stored one is plaintext or outdated.
+    /// Hashing is slow on purpose, so it works on a snapshot and other logins go on.
     pub fn verify_password(&self, password: &str) -> bool {
-        if let Ok(stored_password) = self.password.lock() {
-            if let Some(ref stored) = *stored_password {
-                password == stored
-            } else {
-                false
+        let credentials = self.credentials();
+        let Some(stored) = &credentials.password else {
+            return false;
+        };
+        if !password::verify_password(password, stored) {
+            return false;
+        }
+
+        // Keep the old hash if rehashing fails, the next login tries again
+        if password::needs_rehash(stored, &self.hash_params) {
+            if let Ok(hash) = password::hash_password(password, &self.hash_params) {
+                let mut current = self
+                    .credentials
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner);
+                // Unless the password was replaced meanwhile
+                if Arc::ptr_eq(&current, &credentials) {
+                    *current = Arc::new(Credentials {
+                        password: Some(hash.clone()),
+                        changed_at: credentials.changed_at,
+                    });
+                    *lock(&self.upgraded_password) = Some(hash);
+                }
             }
-        } else {
-            false
         }
+        true
+    }
+
+    /// Switch a running cloud to a new password, ending every session made with the old one
+    pub fn replace_password(
+        &self,
+        password: Option<String>,
+        changed_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) {
+        self.set_credentials(Credentials {
+            password,
+            changed_at,
+        });
+        *lock(&self.upgraded_password) = None;
+    }
+
+    /// Set a new password after a recovery code was redeemed, ending every session
+    pub fn reset_password(&self, password: &str) -> crate::error::ServerResult<()> {
+        let hash = password::hash_password(password, &self.hash_params)?;
+        let changed_at = chrono::Utc::now();
+        self.replace_password(Some(hash.clone()), Some(changed_at));
+        *lock(&self.recovered_password) = Some((hash, changed_at));
+        Ok(())
+    }
+
+    /// The password set with a recovery code, if one was set since the last call
+    pub fn take_recovered_password(&self) -> Option<(String, chrono::DateTime<chrono::Utc>)> {
+        lock(&self.recovered_password).take()
+    }
+
+    /// The rehashed password to persist, if a login upgraded it since the last call
+    pub fn take_upgraded_password(&self) -> Option<String> {
+        lock(&self.upgraded_password).take()
+    }
+
+    /// End the session of a login token, until it would have expired
+    pub fn revoke_session(&self, claims: &Claims) {
+        let expires_at =
+            chrono::DateTime::from_timestamp(claims.exp as i64, 0).unwrap_or_else(chrono::Utc::now);
+        self.revoked_sessions.revoke(&claims.jti, expires_at);
     }
 
     pub fn generate_token(&self) -> Result<String, jsonwebtoken::errors::Error> {
+        self.generate_session_token(false)
+    }
+
+    /// Login token for the web UI; "remember me" makes it last for weeks instead of a day
+    pub fn generate_session_token(
+        &self,
+        remember: bool,
+    ) -> Result<String, jsonwebtoken::errors::Error> {
+        self.sign("admin", self.sessions.lifetime(remember))
+    }
+
+    fn sign(
+        &self,
+        sub: &str,
+        lifetime: chrono::Duration,
+    ) -> Result<String, jsonwebtoken::errors::Error> {
         let pwd_changed_timestamp = self
-            .password_changed_at
-            .lock()
-            .ok()
-            .and_then(|dt| *dt)
+            .credentials()
+            .changed_at
             .map(|dt| dt.timestamp())
             .unwrap_or(0);
 
         let claims = Claims {
-            sub: "admin".to_string(),
-            exp: (chrono::Utc::now() + chrono::Duration::hours(24)).timestamp() as usize,
+            sub: sub.to_string(),
+            exp: (chrono::Utc::now() + lifetime).timestamp() as usize,
             pwd_changed: pwd_changed_timestamp,
+            jti: uuid::Uuid::new_v4().simple().to_string(),
         };
 
         encode(
@@ -77,16 +295,18 @@ impl AuthState {
         )?;
 
         let claims = token_data.claims;
+        if !claims.jti.is_empty() && self.revoked_sessions.is_revoked(&claims.jti) {
+            return Err(jsonwebtoken::errors::Error::from(
+                jsonwebtoken::errors::ErrorKind::InvalidToken,
+            ));
+        }
 
         // Check if password was changed after token was issued
-        if let Ok(current_pwd_changed) = self.password_changed_at.lock() {
-            if let Some(current_pwd_changed) = *current_pwd_changed {
-                let current_pwd_timestamp = current_pwd_changed.timestamp();
-                if current_pwd_timestamp > claims.pwd_changed {
-                    return Err(jsonwebtoken::errors::Error::from(
-                        jsonwebtoken::errors::ErrorKind::InvalidToken,
-                    ));
-                }
+        if let Some(current_pwd_changed) = self.credentials().changed_at {
+            if current_pwd_changed.timestamp() > claims.pwd_changed {
+                return Err(jsonwebtoken::errors::Error::from(
+                    jsonwebtoken::errors::ErrorKind::InvalidToken,
+                ));
             }
         }
 
@@ -94,55 +314,290 @@ impl AuthState {
     }
 }
 
-pub async fn auth_middleware(
-    State(auth_state): State<Arc<AuthState>>,
-    headers: HeaderMap,
-    request: Request,
-    next: Next,
-) -> Result<Response, StatusCode> {
-    // Check for Authorization header
-    if let Some(auth_header) = headers.get("Authorization") {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if let Some(token) = auth_str.strip_prefix("Bearer ") {
-                if auth_state.verify_token(token).is_ok() {
-                    return Ok(next.run(request).await);
+// ========== Request Authentication ==========
+
+/// A request that carries a valid token, as a bearer token or an `auth_token_*` cookie.
+/// Router groups behind `require_api_auth` or `require_web_auth` always have one;
+/// handlers that need the claims can take it as an extractor.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    /// Claims of a login token, `None` for an API token
+    pub claims: Option<Claims>,
+    pub method: AuthMethod,
+    /// What the request may do; login tokens can do everything
+    pub scope: ApiTokenScope,
+    /// Id of the API token the request came with
+    pub api_token: Option<String>,
+}
+
+/// Where the token came from. Browsers attach cookies on their own,
+/// so only cookie-authenticated requests need CSRF protection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthMethod {
+    Bearer,
+    Cookie,
+    /// No token, on a cloud with `guest_access`; only ever reads
+    Guest,
+}
+
+impl AuthenticatedUser {
+    /// Check the Authorization header first, then the auth cookies
+    pub fn from_headers(headers: &HeaderMap, auth_state: &AuthState) -> Option<Self> {
+        let bearer = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|token| (token, AuthMethod::Bearer));
+        let cookies = cookies(headers)
+            .filter(|(name, _)| name.starts_with("auth_token_"))
+            .map(|(_, token)| (token, AuthMethod::Cookie));
+
+        bearer
+            .into_iter()
+            .chain(cookies)
+            .find_map(|(token, method)| {
+                if method == AuthMethod::Bearer && token.starts_with(TOKEN_PREFIX) {
+                    return auth_state
+                        .api_tokens
+                        .verify(token)
+                        .map(Self::from_api_token);
                 }
-            }
+                let claims = auth_state.verify_token(token).ok()?;
+                // App passwords the Nextcloud login flow once signed are only good there
+                if claims.sub == LEGACY_APP_PASSWORD_SUBJECT {
+                    return None;
+                }
+                Some(Self {
+                    claims: Some(claims),
+                    method,
+                    scope: ApiTokenScope::Full,
+                    api_token: None,
+                })
+            })
+    }
+
+    /// Who made the request, for folder hooks: `owner` with a login, `token:<id>`
+    /// with an API token, `guest` without either
+    pub fn name(&self) -> String {
+        match &self.api_token {
+            Some(id) => format!("token:{}", id),
+            None if self.is_guest() => "guest".to_string(),
+            None => "owner".to_string(),
+        }
+    }
+
+    /// A visitor without a login on a cloud with `guest_access`
+    pub fn guest() -> Self {
+        Self {
+            claims: None,
+            method: AuthMethod::Guest,
+            scope: ApiTokenScope::ReadOnly,
+            api_token: None,
+        }
+    }
+
+    pub fn is_guest(&self) -> bool {
+        self.method == AuthMethod::Guest
+    }
+
+    /// Refuse guests, for the handlers only the owner and API tokens may use
+    pub fn refuse_guest(&self) -> Result<(), ApiError> {
+        if self.is_guest() {
+            return Err(ApiError::new(
+                ErrorCode::Unauthorized,
+                "Log in to see this; guests can only browse the cloud folders",
+            ));
         }
+        Ok(())
     }
 
-    // Check for password in query parameters (for simple auth)
-    if let Some(password) = request.uri().query().and_then(|q| {
-        q.split('&')
-            .find(|param| param.starts_with("password="))
-            .map(|param| &param[9..])
-    }) {
-        if auth_state.verify_password(password) {
-            return Ok(next.run(request).await);
+    pub fn from_api_token(token: ApiToken) -> Self {
+        Self {
+            claims: None,
+            method: AuthMethod::Bearer,
+            scope: token.scope,
+            api_token: Some(token.id),
         }
     }
+}
+
+/// All `name=value` pairs of the Cookie headers
+pub(crate) fn cookies(headers: &HeaderMap) -> impl Iterator<Item = (&str, &str)> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+}
+
+pub(crate) fn cookie_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    cookies(headers)
+        .find(|(cookie_name, _)| *cookie_name == name)
+        .map(|(_, value)| value.to_string())
+}
+
+#[async_trait]
+impl FromRequestParts<CloudServerState> for AuthenticatedUser {
+    type Rejection = AuthRejection;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &CloudServerState,
+    ) -> Result<Self, Self::Rejection> {
+        // Already checked by the router group's middleware
+        if let Some(user) = parts.extensions.get::<AuthenticatedUser>() {
+            return Ok(user.clone());
+        }
+        Self::from_headers(&parts.headers, &state.auth_state).ok_or(AuthRejection::Api)
+    }
+}
 
-    Err(StatusCode::UNAUTHORIZED)
+/// Response for a request without a valid token
+#[derive(Debug, Clone, Copy)]
+pub enum AuthRejection {
+    /// JSON error for API clients
+    Api,
+    /// Send browsers to the login page
+    Web,
 }
 
-pub async fn login(
-    State(auth_state): State<Arc<AuthState>>,
-    axum::Json(payload): axum::Json<LoginRequest>,
-) -> Result<axum::Json<LoginResponse>, StatusCode> {
-    if auth_state.verify_password(&payload.password) {
-        if let Ok(token) = auth_state.generate_token() {
-            return Ok(axum::Json(LoginResponse { token }));
+impl IntoResponse for AuthRejection {
+    fn into_response(self) -> Response {
+        match self {
+            AuthRejection::Api => ApiError::new(
+                ErrorCode::Unauthorized,
+                "Authentication required. Please provide a valid JWT token.",
+            )
+            .with("login_url", "/api/login")
+            .into_response(),
+            AuthRejection::Web => Html(
+                r#"
+<!DOCTYPE html>
+<html>
+<head>
+    <meta http-equiv="refresh" content="0; url=/login">
+    <title>Redirecting to Login</title>
+</head>
+<body>
+    <p>Redirecting to login page...</p>
+    <script>window.location.href = '/login';</script>
+</body>
+</html>
+    "#,
+            )
+            .into_response(),
         }
     }
-    Err(StatusCode::UNAUTHORIZED)
+}
+
+/// Middleware for API routes: rejects requests without a valid token with a JSON 401
+pub async fn require_api_auth(
+    State(state): State<CloudServerState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    authenticate(&state, request, next, AuthRejection::Api).await
+}
+
+/// Middleware for web pages: sends browsers without a valid token to the login page
+pub async fn require_web_auth(
+    State(state): State<CloudServerState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    authenticate(&state, request, next, AuthRejection::Web).await
+}
+
+async fn authenticate(
+    state: &CloudServerState,
+    mut request: Request,
+    next: Next,
+    rejection: AuthRejection,
+) -> Response {
+    let user =
+        AuthenticatedUser::from_headers(request.headers(), &state.auth_state).or_else(|| {
+            (state.cloud.guest_access
+                && guest_allows(&state.cloud, request.method(), request.uri().path()))
+            .then(AuthenticatedUser::guest)
+        });
+    match user {
+        Some(user) if !user.scope.allows(request.method(), request.uri().path()) => {
+            out_of_scope(user.scope)
+        }
+        Some(user) => {
+            crate::clients::identify(&request, &user);
+            request.extensions_mut().insert(user);
+            next.run(request).await
+        }
+        None => rejection.into_response(),
+    }
+}
+
+/// The fixed `/api/<name>` routes. They are matched before `/api/:cloud_folder_name`, so a
+/// cloud folder with one of these names doesn't make them readable by guests.
+const API_ROUTE_NAMES: [&str; 8] = [
+    "clients",
+    "file-requests",
+    "login",
+    "logout",
+    "metrics",
+    "stats",
+    "tokens",
+    "usage",
+];
+
+/// What guests may do: read the pages, listings, files, streams and previews of the
+/// cloud folders and the photo timeline. Logs, stats, clients, tokens and file requests
+/// stay behind the login.
+fn guest_allows(cloud: &Cloud, method: &Method, path: &str) -> bool {
+    if !matches!(*method, Method::GET | Method::HEAD) {
+        return false;
+    }
+    let segments: Vec<String> = path
+        .trim_start_matches('/')
+        .split('/')
+        .map(|segment| percent_decode_str(segment).decode_utf8_lossy().into_owned())
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    let is_folder = |name: &str| cloud.has_cloud_folder(name);
+    match segments.as_slice() {
+        [""] | ["api"] | ["web", "photos"] | ["api", "photos", "timeline"] => true,
+        ["web", folder, "files", ..] => is_folder(folder),
+        ["api", "stream" | "preview", folder, ..] => is_folder(folder),
+        ["api", folder] => is_folder(folder) && !API_ROUTE_NAMES.contains(folder),
+        ["api", folder, "files" | "static", ..] => is_folder(folder),
+        _ => false,
+    }
+}
+
+/// Response for an API token used outside its scope
+pub fn out_of_scope(scope: ApiTokenScope) -> Response {
+    ApiError::new(
+        ErrorCode::TokenOutOfScope,
+        format!("This API token is {} and can't make this request", scope),
+    )
+    .with("scope", scope.to_string())
+    .into_response()
 }
 
 #[derive(Deserialize)]
 pub struct LoginRequest {
     pub password: String,
+    /// Keep the session for weeks instead of a day
+    #[serde(default)]
+    pub remember: bool,
+}
+
+#[derive(Deserialize)]
+pub struct RecoverRequest {
+    pub code: String,
+    pub password: String,
 }
 
 #[derive(Serialize)]
 pub struct LoginResponse {
     pub token: String,
+    /// Seconds until the token expires
+    pub expires_in: i64,
 }
@@ -0,0 +1,148 @@
+/// Structured log of failed logins for fail2ban or custom banning scripts.
+///
+/// One line per failure in `auth-failures.log` in the data directory:
+///
+/// ```text
+/// 2026-01-02T03:04:05Z cloudhost auth failure: ip=203.0.113.7 cloud=photos reason=bad_password request_id=90d25024 forwarded_for=-
+/// ```
+///
+/// The timestamp is UTC (RFC 3339). `ip` is the peer address of the connection and
+/// `forwarded_for` the client address reported by a proxy (`CF-Connecting-IP` or the
+/// first `X-Forwarded-For` entry), `-` when there is none. `request_id` matches the
+/// `X-Request-Id` header of the response and the request's lines in the cloud log. Behind a tunnel every `ip`
+/// is the proxy's own, so ban on `forwarded_for` instead.
+///
+/// An optional hook command (`auth_failure_hook` in the clouds config) runs for each
+/// failure through the shell, with the fields in `CLOUDHOST_AUTH_*` environment variables.
+use axum::http::HeaderMap;
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::io::Write;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+use crate::config_paths;
+use crate::request_id;
+
+#[derive(Debug, Clone)]
+pub struct AuthFailure {
+    pub timestamp: DateTime<Utc>,
+    pub ip: IpAddr,
+    pub forwarded_for: Option<IpAddr>,
+    pub cloud: String,
+    pub reason: &'static str,
+    pub request_id: Option<String>,
+}
+
+impl AuthFailure {
+    pub fn new(ip: IpAddr, headers: &HeaderMap, cloud: &str, reason: &'static str) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            ip,
+            forwarded_for: forwarded_client_ip(headers),
+            cloud: cloud.to_string(),
+            reason,
+            request_id: request_id::from_headers(headers).map(str::to_string),
+        }
+    }
+
+    pub fn to_log_line(&self) -> String {
+        format!(
+            "{} cloudhost auth failure: ip={} cloud={} reason={} request_id={} forwarded_for={}",
+            self.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+            self.ip,
+            // Keep one token per field so filters can split on spaces
+            self.cloud.replace(char::is_whitespace, "_"),
+            self.reason,
+            self.request_id.as_deref().unwrap_or("-"),
+            self.forwarded_for
+                .map(|ip| ip.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+        )
+    }
+}
+
+/// Client address reported by a proxy. Only parsed IPs are returned,
+/// so a forged header can't inject text into the log.
+pub(crate) fn forwarded_client_ip(headers: &HeaderMap) -> Option<IpAddr> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    header("cf-connecting-ip")
+        .or_else(|| header("x-forwarded-for").and_then(|value| value.split(',').next()))
+        .and_then(|value| value.trim().parse().ok())
+}
+
+pub fn get_auth_failure_log_path() -> PathBuf {
+    config_paths::get_data_dir().join("auth-failures.log")
+}
+
+pub struct AuthFailureLog {
+    path: PathBuf,
+    hook: Option<String>,
+    /// Serializes writes from concurrent requests
+    lock: Mutex<()>,
+}
+
+impl AuthFailureLog {
+    pub fn new(hook: Option<String>) -> Self {
+        Self {
+            path: get_auth_failure_log_path(),
+            hook: hook.filter(|hook| !hook.trim().is_empty()),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Append the failure to the log and run the hook. Errors are ignored:
+    /// a full disk or a broken hook must not turn failed logins into server errors.
+    pub fn record(&self, failure: &AuthFailure) {
+        let line = failure.to_log_line();
+        if let Ok(_guard) = self.lock.lock() {
+            let _ = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .and_then(|mut file| writeln!(file, "{}", line));
+        }
+
+        if let Some(hook) = &self.hook {
+            run_hook(hook, failure, &line);
+        }
+    }
+}
+
+fn run_hook(hook: &str, failure: &AuthFailure, line: &str) {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", hook]);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.args(["-c", hook]);
+        command
+    };
+    command
+        .env("CLOUDHOST_AUTH_TIME", failure.timestamp.to_rfc3339())
+        .env("CLOUDHOST_AUTH_IP", failure.ip.to_string())
+        .env(
+            "CLOUDHOST_AUTH_FORWARDED_FOR",
+            failure
+                .forwarded_for
+                .map(|ip| ip.to_string())
+                .unwrap_or_default(),
+        )
+        .env("CLOUDHOST_AUTH_CLOUD", &failure.cloud)
+        .env("CLOUDHOST_AUTH_REASON", failure.reason)
+        .env(
+            "CLOUDHOST_AUTH_REQUEST_ID",
+            failure.request_id.as_deref().unwrap_or_default(),
+        )
+        .env("CLOUDHOST_AUTH_LINE", line)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    // Reap the hook in the background so slow scripts don't hold up the response
+    if let Ok(mut child) = command.spawn() {
+        std::thread::spawn(move || child.wait());
+    }
+}
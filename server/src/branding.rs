@@ -0,0 +1,241 @@
+/// Per-cloud branding of the web pages: the name in titles and headings, a logo, the
+/// accent color, and custom error pages. Loaded when the cloud starts, so a missing
+/// logo or error page keeps it from starting like a missing cloud folder does.
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{Html, IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::error::{ServerError, ServerResult};
+use crate::html::escape_html;
+use crate::utils::{mime_from_extension, sniff_mime, OCTET_STREAM};
+
+const DEFAULT_DISPLAY_NAME: &str = "CloudHost";
+const DEFAULT_ACCENT_COLOR: &str = "#007bff";
+/// The login page's background unless an accent color is set
+const DEFAULT_ACCENT_BACKGROUND: &str = "linear-gradient(135deg, #667eea 0%, #764ba2 100%)";
+/// Logos are served from memory
+const MAX_LOGO_BYTES: u64 = 1024 * 1024;
+
+/// `[clouds.branding]` in the clouds config
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BrandingConfig {
+    /// Shown in page titles and headings instead of "CloudHost"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// Image file shown instead of the cloud emoji
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logo: Option<PathBuf>,
+    /// CSS color of buttons and links, e.g. `#2e7d32`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accent_color: Option<String>,
+    /// Directory with `404.html`, `500.html`, ... and `error.html` for any other status.
+    /// `{status}` and `{message}` in them are replaced.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_pages: Option<PathBuf>,
+}
+
+impl BrandingConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Problems with the settings, as `field: message`
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self
+            .display_name
+            .as_ref()
+            .is_some_and(|name| name.trim().is_empty())
+        {
+            problems.push("display_name: must not be empty".to_string());
+        }
+        if let Some(color) = &self.accent_color {
+            if !is_css_color(color) {
+                problems.push(format!(
+                    "accent_color: '{}' is not a hex color (#rgb, #rrggbb) or a color name",
+                    color
+                ));
+            }
+        }
+        problems
+    }
+}
+
+/// `#rgb`, `#rrggbb` or a name like `teal`; nothing that could break out of a style
+fn is_css_color(color: &str) -> bool {
+    match color.strip_prefix('#') {
+        Some(hex) => matches!(hex.len(), 3 | 6) && hex.bytes().all(|byte| byte.is_ascii_hexdigit()),
+        None => !color.is_empty() && color.bytes().all(|byte| byte.is_ascii_alphabetic()),
+    }
+}
+
+/// The loaded branding of a running cloud
+#[derive(Debug, Clone)]
+pub struct Branding {
+    display_name: String,
+    accent_color: String,
+    accent_background: String,
+    /// Bytes and content type
+    logo: Option<(Vec<u8>, String)>,
+    /// Error pages by status code, `None` for `error.html`
+    error_pages: HashMap<Option<u16>, String>,
+}
+
+impl Default for Branding {
+    fn default() -> Self {
+        Self {
+            display_name: DEFAULT_DISPLAY_NAME.to_string(),
+            accent_color: DEFAULT_ACCENT_COLOR.to_string(),
+            accent_background: DEFAULT_ACCENT_BACKGROUND.to_string(),
+            logo: None,
+            error_pages: HashMap::new(),
+        }
+    }
+}
+
+impl Branding {
+    pub fn load(config: &BrandingConfig) -> ServerResult<Self> {
+        let mut branding = Self::default();
+        if let Some(name) = &config.display_name {
+            branding.display_name = name.trim().to_string();
+        }
+        if let Some(color) = &config.accent_color {
+            branding.accent_color = color.clone();
+            branding.accent_background = color.clone();
+        }
+
+        if let Some(logo) = &config.logo {
+            let size = std::fs::metadata(logo)
+                .map_err(|e| ServerError::InvalidPath(format!("Logo {}: {}", logo.display(), e)))?
+                .len();
+            if size > MAX_LOGO_BYTES {
+                return Err(ServerError::Configuration(format!(
+                    "Logo {} is larger than {} KiB",
+                    logo.display(),
+                    MAX_LOGO_BYTES / 1024
+                )));
+            }
+            let bytes = std::fs::read(logo)
+                .map_err(|e| ServerError::InvalidPath(format!("Logo {}: {}", logo.display(), e)))?;
+            let content_type = mime_from_extension(logo)
+                .or_else(|| sniff_mime(&bytes))
+                .unwrap_or(OCTET_STREAM)
+                .to_string();
+            branding.logo = Some((bytes, content_type));
+        }
+
+        if let Some(dir) = &config.error_pages {
+            let entries = std::fs::read_dir(dir).map_err(|e| {
+                ServerError::InvalidPath(format!("Error pages {}: {}", dir.display(), e))
+            })?;
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let Some(stem) = name.strip_suffix(".html") else {
+                    continue;
+                };
+                let status = match stem {
+                    "error" => None,
+                    code => match code.parse::<u16>() {
+                        Ok(code) if StatusCode::from_u16(code).is_ok() => Some(code),
+                        _ => continue,
+                    },
+                };
+                let page = std::fs::read_to_string(entry.path())
+                    .map_err(|e| ServerError::InvalidPath(format!("Error page {}: {}", name, e)))?;
+                branding.error_pages.insert(status, page);
+            }
+        }
+        Ok(branding)
+    }
+
+    /// The display name, escaped for HTML
+    pub fn name_html(&self) -> String {
+        escape_html(&self.display_name)
+    }
+
+    /// The logo, or the cloud emoji without one
+    pub fn logo_html(&self) -> String {
+        match self.logo {
+            Some(_) => format!(
+                r#"<img src="/branding/logo" alt="{}" style="height: 1.2em; vertical-align: middle;">"#,
+                self.name_html()
+            ),
+            None => "🌩️".to_string(),
+        }
+    }
+
+    /// CSS custom properties for the pages: `--accent` for links and buttons,
+    /// `--accent-background` for large areas
+    pub fn css_variables(&self) -> String {
+        format!(
+            ":root {{ --accent: {}; --accent-background: {}; }}",
+            self.accent_color, self.accent_background
+        )
+    }
+
+    /// `GET /branding/logo`
+    pub fn logo_response(&self) -> Response {
+        let Some((bytes, content_type)) = &self.logo else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+        let mut response = bytes.clone().into_response();
+        let headers = response.headers_mut();
+        if let Ok(value) = HeaderValue::from_str(content_type) {
+            headers.insert(header::CONTENT_TYPE, value);
+        }
+        headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("public, max-age=3600"),
+        );
+        response
+    }
+
+    /// An error page for the web UI: the custom page for the status if there is one,
+    /// else a plain page in the cloud's colors. `message` is plain text.
+    pub fn error_page(&self, status: StatusCode, message: &str) -> Response {
+        let custom = self
+            .error_pages
+            .get(&Some(status.as_u16()))
+            .or_else(|| self.error_pages.get(&None));
+        let html = match custom {
+            Some(page) => page
+                .replace("{status}", status.as_str())
+                .replace("{message}", &escape_html(message)),
+            None => format!(
+                r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <title>{} - {}</title>
+    <style>
+        {}
+        body {{ font-family: Arial, sans-serif; margin: 40px; }}
+        .container {{ max-width: 800px; margin: 0 auto; }}
+        a {{ color: var(--accent); }}
+    </style>
+</head>
+<body>
+    <main class="container">
+        <h1>{} {}</h1>
+        <p>{}</p>
+        <a href="/">Back to Home</a>
+    </main>
+</body>
+</html>
+"#,
+                self.name_html(),
+                status.canonical_reason().unwrap_or("Error"),
+                self.css_variables(),
+                self.logo_html(),
+                status.canonical_reason().unwrap_or("Error"),
+                escape_html(message)
+            ),
+        };
+        (status, Html(html)).into_response()
+    }
+}
@@ -0,0 +1,161 @@
+/// Clients currently being served by a cloud: one entry per request in progress, with
+/// who made it, what it transfers and how fast. Listed in the TUI's Clients panel and at
+/// `/api/clients`; killing an entry ends its transfer and closes the connection.
+use axum::extract::Request;
+use futures_util::task::AtomicWaker;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::auth::AuthenticatedUser;
+
+/// A request being served
+#[derive(Debug)]
+pub struct ClientConnection {
+    pub id: u64,
+    /// The address reported by a proxy, else the peer's
+    pub ip: IpAddr,
+    pub method: String,
+    pub path: String,
+    started_at: Instant,
+    /// Set once the request is authenticated
+    user: Mutex<Option<String>>,
+    /// Bytes of the request and response bodies so far
+    bytes: AtomicU64,
+    killed: AtomicBool,
+    /// Wakes a transfer that waits for data, so a kill ends it right away
+    waker: AtomicWaker,
+}
+
+/// What is known about a client, for listing
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientInfo {
+    pub id: u64,
+    pub ip: IpAddr,
+    pub user: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub bytes: u64,
+    /// Average since the request started, in bytes per second
+    pub bytes_per_sec: u64,
+    pub duration_secs: u64,
+}
+
+impl ClientConnection {
+    pub fn set_user(&self, user: String) {
+        if let Ok(mut current) = self.user.lock() {
+            *current = Some(user);
+        }
+    }
+
+    pub fn add_bytes(&self, bytes: u64) {
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn is_killed(&self) -> bool {
+        self.killed.load(Ordering::Relaxed)
+    }
+
+    /// Wake the current task when the client gets killed
+    pub fn register_waker(&self, waker: &std::task::Waker) {
+        self.waker.register(waker);
+    }
+
+    fn info(&self) -> ClientInfo {
+        let elapsed = self.started_at.elapsed();
+        let bytes = self.bytes.load(Ordering::Relaxed);
+        ClientInfo {
+            id: self.id,
+            ip: self.ip,
+            user: self.user.lock().ok().and_then(|user| user.clone()),
+            method: self.method.clone(),
+            path: self.path.clone(),
+            bytes,
+            bytes_per_sec: (bytes as f64 / elapsed.as_secs_f64().max(1.0)) as u64,
+            duration_secs: elapsed.as_secs(),
+        }
+    }
+}
+
+/// The clients of one cloud
+#[derive(Debug, Default)]
+pub struct Clients {
+    next_id: AtomicU64,
+    connections: Mutex<HashMap<u64, Arc<ClientConnection>>>,
+}
+
+impl Clients {
+    pub fn register(&self, ip: IpAddr, request: &Request) -> Arc<ClientConnection> {
+        let client = Arc::new(ClientConnection {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed) + 1,
+            ip,
+            method: request.method().to_string(),
+            path: request.uri().path().to_string(),
+            started_at: Instant::now(),
+            user: Mutex::new(None),
+            bytes: AtomicU64::new(0),
+            killed: AtomicBool::new(false),
+            waker: AtomicWaker::new(),
+        });
+        if let Ok(mut connections) = self.connections.lock() {
+            connections.insert(client.id, client.clone());
+        }
+        client
+    }
+
+    pub fn remove(&self, id: u64) {
+        if let Ok(mut connections) = self.connections.lock() {
+            connections.remove(&id);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.connections
+            .lock()
+            .map(|connections| connections.len())
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The clients, longest connected first
+    pub fn list(&self) -> Vec<ClientInfo> {
+        let mut clients: Vec<ClientInfo> = self
+            .connections
+            .lock()
+            .map(|connections| connections.values().map(|client| client.info()).collect())
+            .unwrap_or_default();
+        clients.sort_by_key(|client| client.id);
+        clients
+    }
+
+    /// End a client's transfer; false when it's already gone
+    pub fn kill(&self, id: u64) -> bool {
+        let client = self
+            .connections
+            .lock()
+            .ok()
+            .and_then(|connections| connections.get(&id).cloned());
+        match client {
+            Some(client) => {
+                tracing::info!("Killed the connection of {} ({})", client.ip, client.path);
+                client.killed.store(true, Ordering::Relaxed);
+                client.waker.wake();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Record who an authenticated request came from
+pub fn identify(request: &Request, user: &AuthenticatedUser) {
+    if let Some(client) = request.extensions().get::<Arc<ClientConnection>>() {
+        client.set_user(user.name());
+    }
+}
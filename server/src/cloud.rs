@@ -1,8 +1,37 @@
-use crate::auth::AuthState;
-use crate::debug_stream::DebugStream;
+use crate::api_routes::upload::OnConflict;
+use crate::api_tokens::ApiToken;
+use crate::auth::{self, AuthState};
+use crate::branding::{Branding, BrandingConfig};
+use crate::compression;
+use crate::csrf;
+use crate::debug_stream::{DebugStream, LogLevelConfig};
+use crate::disk_space::DiskSpaceConfig;
+use crate::email::{EmailConfig, EmailNotifier};
 use crate::error::{ServerError, ServerResult};
+use crate::file_requests::{FileRequest, FileRequests};
+use crate::folder_health::FolderHealth;
+use crate::ftp::{FtpConfig, FtpListener};
+use crate::hooks::FolderHooks;
+use crate::listing_cache::ListingCache;
+use crate::nextcloud::{self, LoginFlows};
+use crate::office_preview::{OfficePreview, OfficePreviewConfig};
+use crate::password::{self, PasswordHashParams};
+use crate::photo_cache::PhotoCache;
+use crate::request_id::{self, RequestId};
 use crate::routes;
+use crate::security_headers::{self, ResponseKind, SecurityHeaders, SecurityHeadersConfig};
+use crate::sessions::{RevokedSession, SessionConfig};
+use crate::sftp::{SftpConfig, SftpListener};
+use crate::stats::{self, CloudStats};
+use crate::tracing_layer;
+use crate::transcode::{TranscodeConfig, Transcoder};
+use crate::utils::remove_stale_upload_temps;
+use crate::webhooks::{WebhookConfig, WebhookEvent, Webhooks};
 use axum::{
+    body::Body,
+    extract::DefaultBodyLimit,
+    http::{Request, Response},
+    middleware,
     routing::{get, post},
     Router,
 };
@@ -10,48 +39,217 @@ use serde::{Deserialize, Serialize};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
-use std::{net::SocketAddr, sync::Arc};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex},
+};
 use tower_http::cors::CorsLayer;
+use tower_http::trace::TraceLayer;
+use tracing::{Instrument, Span};
 
 /// Represents a single cloud folder with a name and path
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct CloudFolder {
     pub name: String,
     pub folder_path: PathBuf,
+    /// Camera uploads (`POST /api/camera/<name>`) are sorted into `YYYY/MM` folders
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub camera_roll: bool,
+    /// Served without login as a static website under `/site/<name>/`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub website: Option<WebsiteConfig>,
+    /// Shell commands run on uploads and deletes in this folder
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<FolderHooks>,
+}
+
+/// `website = { ... }` on a cloud folder
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WebsiteConfig {
+    /// List directories without an `index.html` instead of answering 404
+    #[serde(default)]
+    pub directory_listing: bool,
 }
 
 impl CloudFolder {
     pub fn new(name: String, folder_path: PathBuf) -> Self {
-        Self { name, folder_path }
+        Self {
+            name,
+            folder_path,
+            camera_roll: false,
+            website: None,
+            hooks: None,
+        }
     }
 }
 
+fn default_compression() -> bool {
+    true
+}
+
+fn is_default_compression(compression: &bool) -> bool {
+    *compression == default_compression()
+}
+
 /// Represents a cloud containing multiple cloud folders
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Cloud {
     pub name: String,
     pub cloud_folders: Vec<CloudFolder>,
+    /// Labels like `media` or `work` to group, filter and start or stop clouds by
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Argon2id hash of the password (plaintext in configs from before hashing)
     pub password: Option<String>,
     pub password_changed_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Empty on disk when the secrets section is encrypted
+    #[serde(default)]
     pub jwt_secret: String,
+    /// Overrides for the security headers of this cloud's responses
+    #[serde(default, skip_serializing_if = "SecurityHeadersConfig::is_default")]
+    pub security_headers: SecurityHeadersConfig,
+    /// What uploads do when the file name is taken, unless the upload says otherwise
+    #[serde(default, skip_serializing_if = "OnConflict::is_default")]
+    pub upload_conflict: OnConflict,
+    /// FTP(S) listener for devices that can only upload over FTP
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ftp: Option<FtpConfig>,
+    /// SFTP listener for `sftp`, `scp` and FileZilla
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sftp: Option<SftpConfig>,
+    /// gzip listings, pages and text files for clients that accept it
+    #[serde(
+        default = "default_compression",
+        skip_serializing_if = "is_default_compression"
+    )]
+    pub compression: bool,
+    /// Let visitors without a login browse and download the cloud folders, read-only
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub guest_access: bool,
+    /// Serve the Nextcloud endpoints the official mobile and desktop apps need
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub nextcloud_compat: bool,
+    /// Transcode videos browsers can't play with ffmpeg, for `/api/stream`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transcoding: Option<TranscodeConfig>,
+    /// Convert office documents to PDF for `/api/preview`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub office_preview: Option<OfficePreviewConfig>,
+    /// Name, logo, colors and error pages of the web pages
+    #[serde(default, skip_serializing_if = "BrandingConfig::is_default")]
+    pub branding: BrandingConfig,
+    /// Bearer tokens for scripts and devices, managed with `/api/tokens` or `:token`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub api_tokens: Vec<ApiToken>,
+    /// Anonymous upload links, managed with `/api/file-requests`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub file_requests: Vec<FileRequest>,
+    /// Email notifications over SMTP
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<EmailConfig>,
+    /// URLs that get signed JSON payloads of the cloud's events
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<WebhookConfig>,
+    /// Free space below which uploads are refused and warnings shown
+    #[serde(default, skip_serializing_if = "DiskSpaceConfig::is_default")]
+    pub disk_space: DiskSpaceConfig,
+    /// How long web logins last, with and without "remember me"
+    #[serde(default, skip_serializing_if = "SessionConfig::is_default")]
+    pub sessions: SessionConfig,
+    /// Login tokens ended by logging out, until they expire
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub revoked_sessions: Vec<RevokedSession>,
+    /// Least severe level logged, overall and per source
+    #[serde(default, skip_serializing_if = "LogLevelConfig::is_default")]
+    pub logging: LogLevelConfig,
+}
+
+/// Where a cloud's server is in its life
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CloudStatus {
+    Stopped,
+    /// Started, not listening yet
+    Starting,
+    Running,
+    /// Didn't start, or stopped on its own
+    Failed(String),
 }
 
 /// Runtime server state for a cloud (not serialized)
 pub struct CloudServer {
     pub cloud: Cloud,
     pub port: u16,
+    /// Address the server and its FTP and SFTP listeners bind to, every network by default
+    pub bind_address: IpAddr,
     pub server_handle: Option<tokio::task::JoinHandle<()>>,
     pub shutdown_tx: Option<tokio::sync::oneshot::Sender<()>>,
     pub auth_state: Option<Arc<AuthState>>,
     pub debug_stream: Option<Arc<DebugStream>>,
+    pub file_requests: Option<Arc<FileRequests>>,
+    pub photo_cache: Option<Arc<PhotoCache>>,
+    pub transcoder: Option<Arc<Transcoder>>,
+    pub stats: Option<Arc<CloudStats>>,
+    /// Which folders are unavailable, shared with the orchestrator that checks them
+    pub folder_health: Arc<FolderHealth>,
+    /// Updated by the server task
+    status: Arc<Mutex<CloudStatus>>,
+    /// Hands the running server new versions of the cloud
+    cloud_updates: Option<tokio::sync::watch::Sender<Arc<Cloud>>>,
 }
 
-/// State for an individual cloud server (used in routes)
-#[derive(Clone)]
+/// State for an individual cloud server (used in routes). Axum clones it for each
+/// request, and each clone takes the latest version of the cloud, so a request sees the
+/// same cloud throughout while changes apply from the next request on.
 pub struct CloudServerState {
     pub cloud: Arc<Cloud>,
+    /// Latest version of the cloud, replaced by `CloudServer::update_cloud`
+    cloud_updates: tokio::sync::watch::Receiver<Arc<Cloud>>,
     pub auth_state: Arc<AuthState>,
+    pub debug_stream: Arc<DebugStream>,
+    /// Changes (closes) when the server stops
+    pub stopped: tokio::sync::watch::Receiver<()>,
+    /// Pending Nextcloud app logins
+    pub login_flows: Arc<LoginFlows>,
+    /// EXIF data of the photos, for the timeline
+    pub photo_cache: Arc<PhotoCache>,
+    /// Directory listings of the API
+    pub listing_cache: Arc<ListingCache>,
+    /// Set when the cloud has `transcoding` configured
+    pub transcoder: Option<Arc<Transcoder>>,
+    /// Set when the cloud has `office_preview` configured
+    pub office_preview: Option<Arc<OfficePreview>>,
+    pub branding: Arc<Branding>,
+    pub file_requests: Arc<FileRequests>,
+    /// Set when the cloud has `email` configured
+    pub notifier: Option<Arc<EmailNotifier>>,
+    pub webhooks: Arc<Webhooks>,
+    pub stats: Arc<CloudStats>,
+    /// Folders found unavailable, whose requests answer 503
+    pub folder_health: Arc<FolderHealth>,
+}
+
+impl Clone for CloudServerState {
+    fn clone(&self) -> Self {
+        Self {
+            cloud: self.cloud_updates.borrow().clone(),
+            cloud_updates: self.cloud_updates.clone(),
+            auth_state: self.auth_state.clone(),
+            debug_stream: self.debug_stream.clone(),
+            stopped: self.stopped.clone(),
+            login_flows: self.login_flows.clone(),
+            photo_cache: self.photo_cache.clone(),
+            listing_cache: self.listing_cache.clone(),
+            transcoder: self.transcoder.clone(),
+            office_preview: self.office_preview.clone(),
+            branding: self.branding.clone(),
+            file_requests: self.file_requests.clone(),
+            notifier: self.notifier.clone(),
+            webhooks: self.webhooks.clone(),
+            stats: self.stats.clone(),
+            folder_health: self.folder_health.clone(),
+        }
+    }
 }
 
 impl Cloud {
@@ -59,14 +257,33 @@ impl Cloud {
         Self {
             name: name.clone(),
             cloud_folders,
+            tags: Vec::new(),
             password: None,
             password_changed_at: None,
             jwt_secret: Self::generate_jwt_secret(&name),
+            security_headers: SecurityHeadersConfig::default(),
+            upload_conflict: OnConflict::default(),
+            ftp: None,
+            sftp: None,
+            compression: default_compression(),
+            guest_access: false,
+            nextcloud_compat: false,
+            transcoding: None,
+            office_preview: None,
+            branding: BrandingConfig::default(),
+            api_tokens: Vec::new(),
+            file_requests: Vec::new(),
+            email: None,
+            webhooks: Vec::new(),
+            disk_space: DiskSpaceConfig::default(),
+            sessions: SessionConfig::default(),
+            revoked_sessions: Vec::new(),
+            logging: LogLevelConfig::default(),
         }
     }
 
     /// Generate a unique JWT secret for this cloud
-    fn generate_jwt_secret(cloud_name: &str) -> String {
+    pub(crate) fn generate_jwt_secret(cloud_name: &str) -> String {
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
@@ -77,9 +294,13 @@ impl Cloud {
         format!("cloud-{}-secret-{:x}", cloud_name, hasher.finish())
     }
 
-    /// Set password for this cloud
-    pub fn set_password(&mut self, password: &str) -> Result<(), String> {
-        self.password = Some(password.to_string());
+    /// Set password for this cloud, stored as an Argon2id hash
+    pub fn set_password(
+        &mut self,
+        password: &str,
+        params: &PasswordHashParams,
+    ) -> ServerResult<()> {
+        self.password = Some(password::hash_password(password, params)?);
         self.password_changed_at = Some(chrono::Utc::now());
         Ok(())
     }
@@ -91,11 +312,9 @@ impl Cloud {
 
     /// Verify password
     pub fn verify_password(&self, password: &str) -> bool {
-        if let Some(ref stored_password) = self.password {
-            password == stored_password
-        } else {
-            false
-        }
+        self.password
+            .as_deref()
+            .is_some_and(|stored| password::verify_password(password, stored))
     }
 
     /// Check if this cloud contains a cloud folder with the given name
@@ -119,6 +338,24 @@ impl Cloud {
         }
     }
 
+    /// Whether `new` differs from this cloud only in what a running server picks up
+    /// without restarting: the folders, tags, upload conflicts, guest access and disk
+    /// space limits. Everything else is set up when the server starts.
+    pub fn is_live_update(&self, new: &Cloud) -> bool {
+        let mut updated = self.clone();
+        updated.apply_live_changes(new);
+        updated == *new
+    }
+
+    /// Take over the settings of `new` that a running server picks up
+    fn apply_live_changes(&mut self, new: &Cloud) {
+        self.cloud_folders = new.cloud_folders.clone();
+        self.tags = new.tags.clone();
+        self.upload_conflict = new.upload_conflict;
+        self.guest_access = new.guest_access;
+        self.disk_space = new.disk_space;
+    }
+
     /// Remove a cloud folder from this cloud
     pub fn remove_cloud_folder(&mut self, cloud_folder_name: &str) -> bool {
         if let Some(pos) = self
@@ -139,10 +376,54 @@ impl CloudServer {
         Self {
             cloud,
             port,
+            bind_address: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
             server_handle: None,
             shutdown_tx: None,
             auth_state: None,
             debug_stream: None,
+            file_requests: None,
+            photo_cache: None,
+            transcoder: None,
+            stats: None,
+            folder_health: Arc::new(FolderHealth::default()),
+            status: Arc::new(Mutex::new(CloudStatus::Stopped)),
+            cloud_updates: None,
+        }
+    }
+
+    pub fn with_bind_address(mut self, address: IpAddr) -> Self {
+        self.bind_address = address;
+        self
+    }
+
+    pub fn with_folder_health(mut self, folder_health: Arc<FolderHealth>) -> Self {
+        self.folder_health = folder_health;
+        self
+    }
+
+    fn set_status(&self, status: CloudStatus) {
+        if let Ok(mut current) = self.status.lock() {
+            *current = status;
+        }
+    }
+
+    /// The server's state; one whose task ended without being stopped has failed
+    pub fn status(&self) -> CloudStatus {
+        let status = self
+            .status
+            .lock()
+            .map(|status| status.clone())
+            .unwrap_or(CloudStatus::Stopped);
+        match status {
+            CloudStatus::Running
+                if self
+                    .server_handle
+                    .as_ref()
+                    .is_none_or(|handle| handle.is_finished()) =>
+            {
+                CloudStatus::Failed("The server stopped unexpectedly".to_string())
+            }
+            status => status,
         }
     }
 
@@ -158,27 +439,126 @@ impl CloudServer {
             return Err(ServerError::ServerAlreadyRunning);
         }
 
-        // Verify all cloud folder paths exist
-        for cloud_folder in &self.cloud.cloud_folders {
-            if !cloud_folder.folder_path.exists() {
-                return Err(ServerError::InvalidPath(format!(
-                    "Cloud folder path does not exist: {}",
-                    cloud_folder.folder_path.display()
-                )));
-            }
-        }
+        check_folder_paths(&self.cloud)?;
+
+        let branding = Arc::new(Branding::load(&self.cloud.branding)?);
+        let file_requests = Arc::new(FileRequests::new(self.cloud.file_requests.clone()));
 
         let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+        // Dropped when the server task ends, which ends upgraded connections too
+        let (stopped_tx, stopped_rx) = tokio::sync::watch::channel(());
+        let cloud = Arc::new(self.cloud.clone());
+        let (cloud_updates, cloud_rx) = tokio::sync::watch::channel(cloud.clone());
 
         let state = CloudServerState {
-            cloud: Arc::new(self.cloud.clone()),
+            cloud,
+            cloud_updates: cloud_rx,
             auth_state: auth_state.clone(),
+            debug_stream: debug_stream.clone(),
+            stopped: stopped_rx,
+            login_flows: Arc::new(LoginFlows::default()),
+            photo_cache: Arc::new(PhotoCache::for_cloud(&self.cloud.name)),
+            listing_cache: Arc::new(ListingCache::new()),
+            transcoder: self
+                .cloud
+                .transcoding
+                .as_ref()
+                .map(|config| Arc::new(Transcoder::new(&self.cloud.name, config))),
+            office_preview: self
+                .cloud
+                .office_preview
+                .as_ref()
+                .map(|config| Arc::new(OfficePreview::new(&self.cloud.name, config))),
+            branding,
+            file_requests: file_requests.clone(),
+            notifier: auth_state.notifier.clone(),
+            webhooks: auth_state.webhooks.clone(),
+            stats: Arc::new(CloudStats::new()),
+            folder_health: self.folder_health.clone(),
         };
-
-        let mut app = Router::new()
-            .route("/", get(routes::index))
+        let photo_cache = state.photo_cache.clone();
+        let transcoder = state.transcoder.clone();
+        let cloud_stats = state.stats.clone();
+
+        // Bound up front so a taken port or a bad certificate fails the start
+        let ftp_listener = match &self.cloud.ftp {
+            Some(ftp) => Some(
+                FtpListener::bind(
+                    ftp,
+                    self.bind_address,
+                    state.cloud_updates.clone(),
+                    auth_state.clone(),
+                    state.folder_health.clone(),
+                )
+                .await?,
+            ),
+            None => None,
+        };
+        let sftp_listener = match &self.cloud.sftp {
+            Some(sftp) => Some(
+                SftpListener::bind(
+                    sftp,
+                    self.bind_address,
+                    state.cloud_updates.clone(),
+                    auth_state.clone(),
+                    state.folder_health.clone(),
+                )
+                .await?,
+            ),
+            None => None,
+        };
+        let ftp_stopped = state.stopped.clone();
+
+        let page_headers = Arc::new(SecurityHeaders::new(
+            ResponseKind::Page,
+            &self.cloud.security_headers,
+        ));
+        let api_headers = Arc::new(SecurityHeaders::new(
+            ResponseKind::Api,
+            &self.cloud.security_headers,
+        ));
+        let website_headers = Arc::new(SecurityHeaders::new(
+            ResponseKind::Website,
+            &self.cloud.security_headers,
+        ));
+        let nextcloud = self
+            .cloud
+            .nextcloud_compat
+            .then(|| nextcloud::router(state.clone(), page_headers.clone(), api_headers.clone()));
+
+        // Login is the only thing reachable without a token, besides its logo, the
+        // short-lived document links handed to an OnlyOffice server and the file
+        // request upload pages
+        let public = Router::new()
             .route("/login", get(routes::login_page))
             .route("/api/login", post(routes::login))
+            .route("/login/recover", get(routes::recover_page))
+            .route("/api/login/recover", post(routes::recover_password))
+            .route("/preview-source/:token", get(routes::preview_source))
+            .route("/branding/logo", get(routes::branding_logo))
+            .route(
+                "/drop/:id",
+                // Size limits are the file request's own
+                get(routes::file_request_page)
+                    .post(routes::file_request_upload)
+                    .layer(DefaultBodyLimit::disable()),
+            )
+            .layer(middleware::from_fn_with_state(
+                page_headers.clone(),
+                security_headers::apply,
+            ));
+
+        // Cloud folders with `website` set, public like the login
+        let website = Router::new()
+            .route("/site/:cloud_folder_name", get(routes::website_root))
+            .route("/site/:cloud_folder_name/", get(routes::website_index))
+            .route("/site/:cloud_folder_name/*path", get(routes::website_file))
+            .layer(middleware::from_fn_with_state(
+                website_headers,
+                security_headers::apply,
+            ));
+
+        let api = Router::new()
             .route("/api", get(routes::api_index))
             .route(
                 "/api/:cloud_folder_name",
@@ -196,14 +576,75 @@ impl CloudServer {
                 "/api/:cloud_folder_name/static/*path",
                 get(routes::serve_static_file),
             )
-            .route("/api/upload/*path", post(routes::api_upload_file))
+            .route("/api/logs/stream", get(routes::api_stream_logs))
+            .route("/api/usage", get(routes::api_usage))
+            .route("/api/metrics", get(routes::api_metrics))
+            .route("/api/stats", get(routes::api_stats))
+            .route("/api/logout", post(routes::api_logout))
+            .route("/api/clients", get(routes::api_list_clients))
+            .route(
+                "/api/clients/:id",
+                axum::routing::delete(routes::api_kill_client),
+            )
+            .route("/api/photos/timeline", get(routes::api_photo_timeline))
+            .route(
+                "/api/stream/:cloud_folder_name/*path",
+                get(routes::api_stream_video),
+            )
+            .route(
+                "/api/preview/:cloud_folder_name/*path",
+                get(routes::api_preview_document),
+            )
+            .route(
+                "/api/upload/*path",
+                // Streamed to disk and limited by `disk_space.max_upload_mb` instead
+                post(routes::api_upload_file).layer(DefaultBodyLimit::disable()),
+            )
+            .route(
+                "/api/camera/:cloud_folder_name",
+                post(routes::api_camera_upload),
+            )
             .route(
                 "/api/delete/*path",
                 axum::routing::delete(routes::api_delete_file),
-            );
-
-        // Add dynamic routes for cloud folders
-        app = app
+            )
+            .route(
+                "/api/file/:cloud_folder_name/*path",
+                axum::routing::patch(routes::api_patch_file),
+            )
+            .route(
+                "/api/metadata/*path",
+                axum::routing::patch(routes::api_set_file_metadata),
+            )
+            .route(
+                "/api/tokens",
+                get(routes::api_list_tokens).post(routes::api_create_token),
+            )
+            .route(
+                "/api/tokens/:id",
+                axum::routing::delete(routes::api_revoke_token),
+            )
+            .route(
+                "/api/file-requests",
+                get(routes::api_list_file_requests).post(routes::api_create_file_request),
+            )
+            .route(
+                "/api/file-requests/:id",
+                axum::routing::delete(routes::api_delete_file_request),
+            )
+            .route_layer(middleware::from_fn(csrf::require_token))
+            .route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth::require_api_auth,
+            ))
+            .layer(middleware::from_fn_with_state(
+                api_headers,
+                security_headers::apply,
+            ));
+
+        let web = Router::new()
+            .route("/", get(routes::index))
+            .route("/web/photos", get(routes::photo_timeline))
             .route(
                 "/web/:cloud_folder_name/files",
                 get(routes::list_cloud_folder_files),
@@ -211,64 +652,200 @@ impl CloudServer {
             .route(
                 "/web/:cloud_folder_name/files/*path",
                 get(routes::browse_file_or_directory),
-            );
-
-        let app = app.layer(CorsLayer::permissive()).with_state(state);
-
-        let addr = SocketAddr::from(([0, 0, 0, 0], self.port));
-        let cloud_name_clone = self.cloud.name.clone();
-
-        debug_stream
-            .info(
-                "Cloud",
-                &format!(
-                    "Starting cloud '{}' on port {}",
-                    cloud_name_clone, self.port
-                ),
             )
-            .await;
+            .route_layer(middleware::from_fn(csrf::issue_token))
+            .route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth::require_web_auth,
+            ))
+            .layer(middleware::from_fn_with_state(
+                page_headers,
+                security_headers::apply,
+            ));
+
+        // One span per HTTP request, tagged with the cloud and the request ID,
+        // with the response status and latency logged when it completes
+        let cloud_name = self.cloud.name.clone();
+        let span_cloud_name = cloud_name.clone();
+        let request_tracing = TraceLayer::new_for_http()
+            .make_span_with(move |request: &Request<Body>| {
+                let request_id = request
+                    .extensions()
+                    .get::<RequestId>()
+                    .map(|id| id.0.as_str())
+                    .unwrap_or_default();
+                tracing::info_span!(
+                    "request",
+                    cloud = %span_cloud_name,
+                    request_id,
+                    method = %request.method(),
+                    path = %request.uri().path(),
+                )
+            })
+            .on_request(())
+            .on_response(
+                |response: &Response<Body>, latency: Duration, _span: &Span| {
+                    tracing::info!(
+                        target: "http",
+                        status = response.status().as_u16(),
+                        latency_ms = latency.as_millis() as u64,
+                        "request finished"
+                    );
+                },
+            );
 
-        let debug_stream_clone = debug_stream.clone();
-        let server_handle = tokio::spawn(async move {
+        // The CORS layer answers every OPTIONS as a preflight, so it stays off the
+        // Nextcloud routes, where OPTIONS is WebDAV's
+        let mut app = public
+            .merge(website)
+            .merge(api)
+            .merge(web)
+            .layer(CorsLayer::permissive());
+        if let Some(nextcloud) = nextcloud {
+            app = app.merge(nextcloud);
+        }
+        // Inside the stats, so they count the bytes that go over the wire
+        if self.cloud.compression {
+            app = app
+                .layer(compression::layer())
+                .layer(middleware::map_response(compression::weaken_etag));
+        }
+        let app = app
+            .layer(request_tracing)
+            .layer(middleware::from_fn(request_id::assign))
+            .layer(middleware::from_fn_with_state(
+                cloud_stats.clone(),
+                stats::track,
+            ))
+            .with_state(state);
+
+        let addr = SocketAddr::new(self.bind_address, self.port);
+
+        tracing_layer::install();
+        tracing_layer::register(&cloud_name, debug_stream.clone());
+        let cloud_span = tracing::info_span!("cloud", cloud = %cloud_name);
+        cloud_span
+            .in_scope(|| tracing::info!("Starting cloud '{}' on port {}", cloud_name, self.port));
+
+        self.set_status(CloudStatus::Starting);
+        // The task reports whether it could bind before the start counts as done
+        let (ready_tx, ready_rx) = tokio::sync::oneshot::channel::<ServerResult<()>>();
+        let status = self.status.clone();
+        let webhooks = auth_state.webhooks.clone();
+        let port = self.port;
+        let server_task = async move {
+            let _stopped_tx = stopped_tx;
             let listener = match tokio::net::TcpListener::bind(addr).await {
                 Ok(listener) => listener,
                 Err(e) => {
-                    debug_stream_clone
-                        .error("Cloud", &format!("Failed to bind to {}: {}", addr, e))
-                        .await;
+                    tracing::error!("Failed to bind to {}: {}", addr, e);
+                    let _ = ready_tx.send(Err(match e.kind() {
+                        std::io::ErrorKind::AddrInUse => ServerError::PortInUse(port),
+                        _ => ServerError::Network(format!("Failed to bind to {}: {}", addr, e)),
+                    }));
                     return;
                 }
             };
+            if let Ok(mut status) = status.lock() {
+                *status = CloudStatus::Running;
+            }
+            let _ = ready_tx.send(Ok(()));
 
-            debug_stream_clone
-                .info(
-                    "Cloud",
-                    &format!("Cloud '{}' listening on {}", cloud_name_clone, addr),
-                )
-                .await;
+            tracing::info!("Cloud '{}' listening on {}", cloud_name, addr);
+            webhooks.emit(
+                WebhookEvent::CloudStarted,
+                serde_json::json!({ "port": port }),
+            );
 
-            let server = axum::serve(listener, app).with_graceful_shutdown(async move {
+            // Peer addresses are needed for the auth failure log
+            let server = axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .with_graceful_shutdown(async move {
                 shutdown_rx.await.ok();
             });
 
             if let Err(e) = server.await {
-                debug_stream_clone
-                    .error(
-                        "Cloud",
-                        &format!("Cloud server error for '{}': {}", cloud_name_clone, e),
-                    )
-                    .await;
+                tracing::error!("Cloud server error for '{}': {}", cloud_name, e);
+                if let Ok(mut status) = status.lock() {
+                    *status = CloudStatus::Failed(e.to_string());
+                }
             }
+        };
+        let server_handle = tokio::spawn(server_task.instrument(cloud_span.clone()));
+        let ready = ready_rx.await.unwrap_or_else(|_| {
+            Err(ServerError::ServerError(
+                "The server task ended before it was listening".to_string(),
+            ))
+        });
+        if let Err(e) = ready {
+            self.set_status(CloudStatus::Failed(e.to_string()));
+            tracing_layer::unregister(&self.cloud.name);
+            return Err(e);
+        }
+        // The FTP and SFTP listeners stop with the HTTP server, when `stopped` closes
+        if let Some(sftp_listener) = sftp_listener {
+            tokio::spawn(
+                sftp_listener
+                    .run(ftp_stopped.clone())
+                    .instrument(cloud_span.clone()),
+            );
+        }
+        if let Some(ftp_listener) = ftp_listener {
+            tokio::spawn(ftp_listener.run(ftp_stopped).instrument(cloud_span.clone()));
+        }
+        // Temp files of uploads cut short by a crash
+        let folders: Vec<PathBuf> = self
+            .cloud
+            .cloud_folders
+            .iter()
+            .map(|folder| folder.folder_path.clone())
+            .collect();
+        tokio::task::spawn_blocking(move || {
+            cloud_span.in_scope(|| {
+                for folder in folders {
+                    let removed = remove_stale_upload_temps(&folder);
+                    if removed > 0 {
+                        tracing::info!(
+                            "Removed {} unfinished upload(s) from '{}'",
+                            removed,
+                            folder.display()
+                        );
+                    }
+                }
+            })
         });
 
         self.server_handle = Some(server_handle);
         self.shutdown_tx = Some(shutdown_tx);
         self.auth_state = Some(auth_state);
         self.debug_stream = Some(debug_stream);
+        self.file_requests = Some(file_requests);
+        self.photo_cache = Some(photo_cache);
+        self.transcoder = transcoder;
+        self.stats = Some(cloud_stats);
+        self.cloud_updates = Some(cloud_updates);
 
         Ok(())
     }
 
+    /// Hand the running server the folders, tags, upload conflicts, guest access and disk
+    /// space limits of `cloud`, without dropping a connection. Requests being served finish
+    /// with the old version. Other settings are left as they are until a restart.
+    pub fn update_cloud(&mut self, cloud: &Cloud) -> ServerResult<()> {
+        let updates = self
+            .cloud_updates
+            .as_ref()
+            .ok_or(ServerError::ServerNotRunning)?;
+        check_folder_paths(cloud)?;
+        self.cloud.apply_live_changes(cloud);
+        updates.send_replace(Arc::new(self.cloud.clone()));
+        tracing::info_span!("cloud", cloud = %self.cloud.name)
+            .in_scope(|| tracing::info!("Cloud '{}' updated while running", self.cloud.name));
+        Ok(())
+    }
+
     /// Stop the cloud server
     pub async fn stop_server(&mut self) -> ServerResult<()> {
         if let Some(shutdown_tx) = self.shutdown_tx.take() {
@@ -283,14 +860,12 @@ impl CloudServer {
                 })?;
             }
 
-            if let Some(debug_stream) = &self.debug_stream {
-                debug_stream
-                    .info(
-                        "Cloud",
-                        &format!("Cloud '{}' stopped on port {}", self.cloud.name, self.port),
-                    )
-                    .await;
-            }
+            tracing::info_span!("cloud", cloud = %self.cloud.name).in_scope(|| {
+                tracing::info!("Cloud '{}' stopped on port {}", self.cloud.name, self.port)
+            });
+            tracing_layer::unregister(&self.cloud.name);
+            self.cloud_updates = None;
+            self.set_status(CloudStatus::Stopped);
             Ok(())
         } else {
             Err(ServerError::ServerNotRunning)
@@ -299,7 +874,7 @@ impl CloudServer {
 
     /// Check if the cloud server is running
     pub fn is_server_running(&self) -> bool {
-        self.server_handle.is_some()
+        self.status() == CloudStatus::Running
     }
 
     /// Get the cloud server port
@@ -314,9 +889,27 @@ impl CloudServer {
     /// Get the full server URL for this cloud
     pub fn get_server_url(&self) -> Option<String> {
         if self.is_server_running() {
-            Some(format!("http://localhost:{}", self.port))
+            let host = if self.bind_address.is_unspecified() || self.bind_address.is_loopback() {
+                "localhost".to_string()
+            } else {
+                self.bind_address.to_string()
+            };
+            Some(format!("http://{}:{}", host, self.port))
         } else {
             None
         }
     }
 }
+
+/// A cloud is only served with all of its folders there
+fn check_folder_paths(cloud: &Cloud) -> ServerResult<()> {
+    for cloud_folder in &cloud.cloud_folders {
+        if !cloud_folder.folder_path.exists() {
+            return Err(ServerError::InvalidPath(format!(
+                "Cloud folder path does not exist: {}",
+                cloud_folder.folder_path.display()
+            )));
+        }
+    }
+    Ok(())
+}
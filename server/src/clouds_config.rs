@@ -1,20 +1,113 @@
+use crate::api_routes::upload::OnConflict;
 use crate::cloud::{Cloud, CloudFolder};
+use crate::config_migration::{self, Migration};
 use crate::config_paths;
+use crate::defaults::ServerDefaults;
 use crate::error::{ServerError, ServerResult};
+use crate::password::PasswordHashParams;
+use crate::scheduler::TaskConfig;
+use crate::secrets::{CloudSecrets, EncryptedSecrets, SecretsKey, SecretsProtection};
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::io::Write;
 use std::path::PathBuf;
+use std::time::SystemTime;
 
 const CLOUDS_CONFIG_FILE: &str = "clouds-config.toml";
+/// Next to the config: copy of the last config that loaded and validated successfully
+const CLOUDS_CONFIG_BACKUP_SUFFIX: &str = ".bak";
+/// Next to the config: advisory lock shared by every process writing it (TUI, daemon)
+const CLOUDS_CONFIG_LOCK_SUFFIX: &str = ".lock";
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+/// Version of the clouds config format this CloudHost writes
+pub const CLOUDS_SCHEMA_VERSION: u32 = 1;
+
+/// Steps from older versions of the format, see `config_migration`
+const MIGRATIONS: &[Migration] = &[];
+
+fn default_schema_version() -> u32 {
+    CLOUDS_SCHEMA_VERSION
+}
+
+fn default_port_retries() -> u16 {
+    10
+}
+
+fn is_default_port_retries(retries: &u16) -> bool {
+    *retries == default_port_retries()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CloudsConfig {
+    /// Format version of the file, see `config_migration`
+    #[serde(default = "default_schema_version")]
+    pub schema_version: u32,
     pub cloud_folders: Vec<CloudFolder>,
     pub clouds: Vec<Cloud>,
+    /// Argon2id cost of cloud password hashes
+    #[serde(default)]
+    pub password_hashing: PasswordHashParams,
+    /// Shell command run for every failed login, see `auth_log`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_failure_hook: Option<String>,
+    /// Maintenance tasks the orchestrator runs on a schedule, see `scheduler`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tasks: Vec<TaskConfig>,
+    /// How many following ports to try when a cloud's port is taken, 0 to fail right away
+    #[serde(
+        default = "default_port_retries",
+        skip_serializing_if = "is_default_port_retries"
+    )]
+    pub port_retries: u16,
+    /// Port of a page listing all clouds, with links to their own ports; none by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub landing_port: Option<u16>,
+    /// Service that tries to connect back to a cloud from the internet, asked by the
+    /// reachability test with `{ip}` and `{port}` filled in. It answers with a 2xx status
+    /// when it could connect and any other status when it couldn't. Without one the test
+    /// connects to the public IP from this machine, which some routers don't allow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reachability_url: Option<String>,
+    /// Ports and address to listen on, and defaults for the clouds, see `defaults`
+    #[serde(default, skip_serializing_if = "ServerDefaults::is_default")]
+    pub defaults: ServerDefaults,
+    /// Passwords, JWT secrets, SMTP passwords and webhook secrets of all clouds, when they
+    /// are encrypted at rest.
+    /// Only set while locked; unlocking moves the secrets back into `clouds`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypted_secrets: Option<EncryptedSecrets>,
+    /// Modification time of the file when this config was loaded or last saved
+    #[serde(skip)]
+    disk_mtime: Option<SystemTime>,
+    /// Key used to encrypt the secrets when saving
+    #[serde(skip)]
+    secrets_key: Option<SecretsKey>,
+}
+
+impl Default for CloudsConfig {
+    fn default() -> Self {
+        Self {
+            schema_version: CLOUDS_SCHEMA_VERSION,
+            cloud_folders: Vec::new(),
+            clouds: Vec::new(),
+            password_hashing: PasswordHashParams::default(),
+            auth_failure_hook: None,
+            tasks: Vec::new(),
+            port_retries: default_port_retries(),
+            landing_port: None,
+            reachability_url: None,
+            defaults: ServerDefaults::default(),
+            encrypted_secrets: None,
+            disk_mtime: None,
+            secrets_key: None,
+        }
+    }
 }
 
 impl CloudsConfig {
-    /// Load clouds config from file
+    /// Load clouds config from file.
+    /// Fails with a line- or field-level message when the file is malformed or invalid;
+    /// on success the file is backed up as the last good config.
     pub fn load_from_file() -> ServerResult<Self> {
         let config_path = Self::get_config_path();
 
@@ -22,27 +115,471 @@ impl CloudsConfig {
             return Ok(Self::default());
         }
 
-        let config_str = fs::read_to_string(&config_path)?;
-        let config: CloudsConfig = toml::from_str(&config_str)?;
+        let mut config_str = fs::read_to_string(&config_path)?;
+        // Upgrade an older file on disk, keeping the original next to it
+        if let Some(migrated) = Self::migrate(&config_str)? {
+            config_migration::backup_before_migration(&config_path, migrated.from)?;
+            fs::write(&config_path, &migrated.content)?;
+            tracing::info!(
+                "Upgraded {} from schema_version {} to {}: {}",
+                CLOUDS_CONFIG_FILE,
+                migrated.from,
+                migrated.to,
+                migrated.steps.join("; ")
+            );
+            config_str = migrated.content;
+        }
+        let mut config = Self::parse(&config_str)?;
+        config.disk_mtime = Self::current_disk_mtime();
+
+        // A failed backup shouldn't prevent using a valid config
+        let _ = fs::write(Self::get_backup_path(), &config_str);
+        Ok(config)
+    }
+
+    /// The contents of a clouds config of an older version upgraded to the current one
+    pub fn migrate(config_str: &str) -> ServerResult<Option<config_migration::Migrated>> {
+        config_migration::migrate(
+            CLOUDS_CONFIG_FILE,
+            config_str,
+            CLOUDS_SCHEMA_VERSION,
+            MIGRATIONS,
+        )
+        .map_err(ServerError::Configuration)
+    }
+
+    /// Parse and validate the contents of a clouds config file, of any version up to the
+    /// current one
+    pub fn parse(config_str: &str) -> ServerResult<Self> {
+        let migrated = Self::migrate(config_str)?;
+        let config_str = migrated
+            .as_ref()
+            .map_or(config_str, |migrated| migrated.content.as_str());
+        let config: CloudsConfig = toml::from_str(config_str).map_err(|e| {
+            ServerError::Configuration(describe_parse_error(CLOUDS_CONFIG_FILE, config_str, &e))
+        })?;
+
+        let problems = config.validate();
+        if !problems.is_empty() {
+            return Err(ServerError::Configuration(format!(
+                "{} is invalid:\n  - {}",
+                CLOUDS_CONFIG_FILE,
+                problems.join("\n  - ")
+            )));
+        }
         Ok(config)
     }
 
-    /// Save clouds config to file
-    pub fn save_to_file(&self) -> ServerResult<()> {
+    /// Check the rules the rest of the app relies on; returns one message per problem
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut folder_names = std::collections::HashSet::new();
+
+        for (i, folder) in self.cloud_folders.iter().enumerate() {
+            let field = format!("cloud_folders[{}]", i);
+            if folder.name.trim().is_empty() {
+                problems.push(format!("{}.name must not be empty", field));
+            } else if !folder_names.insert(folder.name.as_str()) {
+                problems.push(format!(
+                    "{}.name: duplicate cloud folder name '{}'",
+                    field, folder.name
+                ));
+            }
+            if folder.folder_path.as_os_str().is_empty() {
+                problems.push(format!(
+                    "{}.folder_path must not be empty ('{}')",
+                    field, folder.name
+                ));
+            }
+            if let Some(hooks) = &folder.hooks {
+                for problem in hooks.validate() {
+                    problems.push(format!("{}.hooks.{}", field, problem));
+                }
+            }
+        }
+
+        let mut cloud_names = std::collections::HashSet::new();
+        for (i, cloud) in self.clouds.iter().enumerate() {
+            let field = format!("clouds[{}]", i);
+            if cloud.name.trim().is_empty() {
+                problems.push(format!("{}.name must not be empty", field));
+            } else if !cloud_names.insert(cloud.name.as_str()) {
+                problems.push(format!(
+                    "{}.name: duplicate cloud name '{}'",
+                    field, cloud.name
+                ));
+            }
+            if cloud.cloud_folders.is_empty() {
+                problems.push(format!(
+                    "{}.cloud_folders: cloud '{}' must contain at least one cloud folder",
+                    field, cloud.name
+                ));
+            }
+            for (j, folder) in cloud.cloud_folders.iter().enumerate() {
+                if folder.name.trim().is_empty() || folder.folder_path.as_os_str().is_empty() {
+                    problems.push(format!(
+                        "{}.cloud_folders[{}] needs both a name and a folder_path",
+                        field, j
+                    ));
+                }
+                if let Some(hooks) = &folder.hooks {
+                    for problem in hooks.validate() {
+                        problems.push(format!("{}.cloud_folders[{}].hooks.{}", field, j, problem));
+                    }
+                }
+            }
+            for problem in cloud.security_headers.validate() {
+                problems.push(format!("{}.security_headers.{}", field, problem));
+            }
+            if let Some(ftp) = &cloud.ftp {
+                for problem in ftp.validate() {
+                    problems.push(format!("{}.ftp.{}", field, problem));
+                }
+            }
+            if let Some(sftp) = &cloud.sftp {
+                for problem in sftp.validate() {
+                    problems.push(format!("{}.sftp.{}", field, problem));
+                }
+            }
+            if let Some(transcoding) = &cloud.transcoding {
+                for problem in transcoding.validate() {
+                    problems.push(format!("{}.transcoding.{}", field, problem));
+                }
+            }
+            if let Some(office_preview) = &cloud.office_preview {
+                for problem in office_preview.validate() {
+                    problems.push(format!("{}.office_preview.{}", field, problem));
+                }
+            }
+            for problem in cloud.branding.validate() {
+                problems.push(format!("{}.branding.{}", field, problem));
+            }
+            for (j, token) in cloud.api_tokens.iter().enumerate() {
+                for problem in token.validate() {
+                    problems.push(format!("{}.api_tokens[{}].{}", field, j, problem));
+                }
+                if cloud.api_tokens[..j]
+                    .iter()
+                    .any(|other| other.id == token.id)
+                {
+                    problems.push(format!(
+                        "{}.api_tokens[{}].id: '{}' is used twice",
+                        field, j, token.id
+                    ));
+                }
+            }
+            // Locked secrets are missing from the clouds until they are unlocked
+            let locked = self.encrypted_secrets.is_some();
+            if let Some(email) = &cloud.email {
+                for problem in email.validate() {
+                    if !(locked && problem.starts_with("password:")) {
+                        problems.push(format!("{}.email.{}", field, problem));
+                    }
+                }
+            }
+            for (j, tag) in cloud.tags.iter().enumerate() {
+                if tag.is_empty() || tag.contains(char::is_whitespace) {
+                    problems.push(format!(
+                        "{}.tags[{}]: must be a single word, got '{}'",
+                        field, j, tag
+                    ));
+                } else if cloud.tags[..j].contains(tag) {
+                    problems.push(format!("{}.tags[{}]: '{}' is used twice", field, j, tag));
+                }
+            }
+            for problem in cloud.disk_space.validate() {
+                problems.push(format!("{}.disk_space.{}", field, problem));
+            }
+            for problem in cloud.sessions.validate() {
+                problems.push(format!("{}.sessions.{}", field, problem));
+            }
+            for problem in cloud.logging.validate() {
+                problems.push(format!("{}.logging.{}", field, problem));
+            }
+            for (j, webhook) in cloud.webhooks.iter().enumerate() {
+                for problem in webhook.validate() {
+                    if !(locked && problem.starts_with("secret:")) {
+                        problems.push(format!("{}.webhooks[{}].{}", field, j, problem));
+                    }
+                }
+            }
+            for (j, request) in cloud.file_requests.iter().enumerate() {
+                for problem in request.validate() {
+                    problems.push(format!("{}.file_requests[{}].{}", field, j, problem));
+                }
+                if cloud.file_requests[..j]
+                    .iter()
+                    .any(|other| other.id == request.id)
+                {
+                    problems.push(format!("{}.file_requests[{}].id: is used twice", field, j));
+                }
+            }
+            if cloud.upload_conflict == OnConflict::Overwrite {
+                problems.push(format!(
+                    "{}.upload_conflict: overwrite can't be a default, uploads must ask for it with ?on_conflict=overwrite",
+                    field
+                ));
+            }
+            if cloud.jwt_secret.is_empty() && !locked {
+                problems.push(format!(
+                    "{}.jwt_secret must not be empty (cloud '{}')",
+                    field, cloud.name
+                ));
+            }
+        }
+
+        if self.landing_port == Some(0) {
+            problems.push("landing_port: must not be 0".to_string());
+        }
+        for problem in self.defaults.validate() {
+            problems.push(format!("defaults.{}", problem));
+        }
+        if let Some(url) = &self.reachability_url {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                problems.push(format!(
+                    "reachability_url: must be an http(s) URL, got '{}'",
+                    url
+                ));
+            } else if !url.contains("{port}") {
+                problems.push("reachability_url: must contain {port}".to_string());
+            }
+        }
+        if let Some(problem) = self.password_hashing.problem() {
+            problems.push(format!("password_hashing: {}", problem));
+        }
+        for (i, task) in self.tasks.iter().enumerate() {
+            for problem in task.validate() {
+                problems.push(format!("tasks[{}].{}", i, problem));
+            }
+        }
+
+        problems
+    }
+
+    /// Save clouds config to file.
+    /// Fails with `ConfigConflict` if another process changed the file since it was loaded.
+    pub fn save_to_file(&mut self) -> ServerResult<()> {
+        self.write_locked(true)
+    }
+
+    /// Save clouds config to file, replacing whatever is on disk
+    pub fn overwrite_file(&mut self) -> ServerResult<()> {
+        self.write_locked(false)
+    }
+
+    /// Write to a temp file and rename it over the config while holding the lock,
+    /// so readers never see a half written file and writers don't interleave
+    fn write_locked(&mut self, check_conflict: bool) -> ServerResult<()> {
         let config_path = Self::get_config_path();
+        if let Some(config_dir) = config_path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+        {
+            fs::create_dir_all(config_dir)?;
+        }
+
+        let lock_file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(Self::sibling_path(CLOUDS_CONFIG_LOCK_SUFFIX))?;
+        lock_file.lock()?;
+
+        if check_conflict {
+            let on_disk = Self::current_disk_mtime();
+            if on_disk.is_some() && on_disk != self.disk_mtime {
+                return Err(ServerError::ConfigConflict(
+                    "clouds-config.toml was changed by another program since it was loaded"
+                        .to_string(),
+                ));
+            }
+        }
+
+        let config_str = toml::to_string_pretty(&self.disk_form()?)?;
+        let temp_path = config_path.with_extension(format!("toml.tmp-{}", std::process::id()));
+        let mut temp_file = fs::File::create(&temp_path)?;
+        temp_file.write_all(config_str.as_bytes())?;
+        temp_file.sync_all()?;
+        drop(temp_file);
+        if let Err(e) = fs::rename(&temp_path, &config_path) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e.into());
+        }
+
+        self.disk_mtime = Self::current_disk_mtime();
+        // The lock is released when `lock_file` is dropped
+        Ok(())
+    }
+
+    /// Whether the file on disk changed since this config was loaded or saved
+    pub fn is_stale(&self) -> bool {
+        Self::current_disk_mtime() != self.disk_mtime
+    }
+
+    /// Human readable list of differences, e.g. "added cloud 'home'"
+    pub fn diff(old: &CloudsConfig, new: &CloudsConfig) -> Vec<String> {
+        let mut changes = diff_named(
+            "cloud folder",
+            &old.cloud_folders,
+            &new.cloud_folders,
+            |f| &f.name,
+        );
+        changes.extend(diff_named("cloud", &old.clouds, &new.clouds, |c| &c.name));
+        changes
+    }
+
+    /// Treat the file currently on disk as the one this config came from,
+    /// so the next save replaces it without reporting a conflict
+    pub(crate) fn adopt_disk_file(&mut self) {
+        self.disk_mtime = Self::current_disk_mtime();
+    }
+
+    fn current_disk_mtime() -> Option<SystemTime> {
+        fs::metadata(Self::get_config_path())
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
 
-        if let Some(parent) = config_path.parent() {
-            fs::create_dir_all(parent)?;
+    /// Three-way merge by name: changes made here since `base` win over the disk version
+    /// (`theirs`), everything else comes from `theirs`
+    pub fn merge(base: &CloudsConfig, ours: &CloudsConfig, theirs: &CloudsConfig) -> CloudsConfig {
+        CloudsConfig {
+            schema_version: CLOUDS_SCHEMA_VERSION,
+            cloud_folders: merge_named(
+                &base.cloud_folders,
+                &ours.cloud_folders,
+                &theirs.cloud_folders,
+                |f| &f.name,
+            ),
+            clouds: merge_named(&base.clouds, &ours.clouds, &theirs.clouds, |c| &c.name),
+            password_hashing: if ours.password_hashing != base.password_hashing {
+                ours.password_hashing
+            } else {
+                theirs.password_hashing
+            },
+            auth_failure_hook: if ours.auth_failure_hook != base.auth_failure_hook {
+                ours.auth_failure_hook.clone()
+            } else {
+                theirs.auth_failure_hook.clone()
+            },
+            tasks: if ours.tasks != base.tasks {
+                ours.tasks.clone()
+            } else {
+                theirs.tasks.clone()
+            },
+            port_retries: if ours.port_retries != base.port_retries {
+                ours.port_retries
+            } else {
+                theirs.port_retries
+            },
+            landing_port: if ours.landing_port != base.landing_port {
+                ours.landing_port
+            } else {
+                theirs.landing_port
+            },
+            reachability_url: if ours.reachability_url != base.reachability_url {
+                ours.reachability_url.clone()
+            } else {
+                theirs.reachability_url.clone()
+            },
+            defaults: if ours.defaults != base.defaults {
+                ours.defaults.clone()
+            } else {
+                theirs.defaults.clone()
+            },
+            encrypted_secrets: None,
+            disk_mtime: theirs.disk_mtime,
+            secrets_key: ours.secrets_key.clone(),
         }
+    }
+
+    // ========== Secrets encryption ==========
+
+    /// How secrets are protected on disk, if they are encrypted
+    pub fn secrets_protection(&self) -> Option<SecretsProtection> {
+        self.secrets_key
+            .as_ref()
+            .map(SecretsKey::protection)
+            .or_else(|| self.encrypted_secrets.as_ref().map(|s| s.protection))
+    }
+
+    /// Secrets are encrypted and haven't been decrypted yet
+    pub fn secrets_locked(&self) -> bool {
+        self.encrypted_secrets.is_some()
+    }
 
-        let config_str = toml::to_string_pretty(self)?;
-        fs::write(&config_path, config_str)?;
+    /// Decrypt the secrets section into the clouds and keep the key for saving
+    pub fn unlock_secrets(&mut self, key: SecretsKey) -> ServerResult<()> {
+        let Some(encrypted) = self.encrypted_secrets.as_ref() else {
+            self.secrets_key = Some(key);
+            return Ok(());
+        };
+        let mut secrets = key.open(encrypted)?;
+        for cloud in &mut self.clouds {
+            if let Some(cloud_secrets) = secrets.remove(&cloud.name) {
+                cloud_secrets.restore_into(cloud);
+            }
+            if cloud.jwt_secret.is_empty() {
+                cloud.jwt_secret = Cloud::generate_jwt_secret(&cloud.name);
+            }
+        }
+        self.encrypted_secrets = None;
+        self.secrets_key = Some(key);
+        Ok(())
+    }
+
+    /// The key of an unlocked config, to unlock a freshly loaded copy of the same file
+    pub fn secrets_key(&self) -> Option<&SecretsKey> {
+        self.secrets_key.as_ref()
+    }
+
+    /// Encrypt secrets with `key` from now on, or store them in plain text with `None`
+    pub fn set_secrets_key(&mut self, key: Option<SecretsKey>) -> ServerResult<()> {
+        if self.secrets_locked() {
+            return Err(ServerError::validation(
+                "Unlock the secrets before changing their protection",
+            ));
+        }
+        self.secrets_key = key;
         Ok(())
     }
 
+    /// What gets written to disk: secrets moved into the encrypted section when there's a key
+    fn disk_form(&self) -> ServerResult<CloudsConfig> {
+        if self.secrets_locked() {
+            // Saving now could mix plain and encrypted secrets
+            return Err(ServerError::validation(
+                "Cloud secrets are locked; unlock them to save changes",
+            ));
+        }
+        let mut disk = self.clone();
+        let Some(key) = &self.secrets_key else {
+            return Ok(disk);
+        };
+
+        let mut secrets = std::collections::BTreeMap::new();
+        for cloud in &mut disk.clouds {
+            secrets.insert(cloud.name.clone(), CloudSecrets::take_from(cloud));
+        }
+        disk.encrypted_secrets = Some(key.seal(&secrets)?);
+        Ok(disk)
+    }
+
     /// Get the path to the clouds config file
     fn get_config_path() -> PathBuf {
-        config_paths::get_config_dir().join(CLOUDS_CONFIG_FILE)
+        config_paths::get_clouds_config_path()
+    }
+
+    /// Get the path to the backup of the last good clouds config
+    pub fn get_backup_path() -> PathBuf {
+        Self::sibling_path(CLOUDS_CONFIG_BACKUP_SUFFIX)
+    }
+
+    /// The config path with `suffix` appended, e.g. `clouds-config.toml.bak`
+    fn sibling_path(suffix: &str) -> PathBuf {
+        let mut path = Self::get_config_path().into_os_string();
+        path.push(suffix);
+        PathBuf::from(path)
     }
 
     /// Add a cloud folder
@@ -180,3 +717,87 @@ impl CloudsConfig {
         self.clouds.iter().find(|c| c.name == cloud_name)
     }
 }
+
+fn diff_named<T: PartialEq>(
+    kind: &str,
+    old: &[T],
+    new: &[T],
+    name: impl Fn(&T) -> &String,
+) -> Vec<String> {
+    let find = |items: &'_ [T], wanted: &String| -> Option<usize> {
+        items.iter().position(|item| name(item) == wanted)
+    };
+    let mut changes = Vec::new();
+    for item in new {
+        match find(old, name(item)) {
+            None => changes.push(format!("added {} '{}'", kind, name(item))),
+            Some(i) if old[i] != *item => {
+                changes.push(format!("changed {} '{}'", kind, name(item)))
+            }
+            Some(_) => {}
+        }
+    }
+    for item in old {
+        if find(new, name(item)).is_none() {
+            changes.push(format!("removed {} '{}'", kind, name(item)));
+        }
+    }
+    changes
+}
+
+fn merge_named<T: Clone + PartialEq>(
+    base: &[T],
+    ours: &[T],
+    theirs: &[T],
+    name: impl Fn(&T) -> &String,
+) -> Vec<T> {
+    let find = |items: &'_ [T], wanted: &String| -> Option<usize> {
+        items.iter().position(|item| name(item) == wanted)
+    };
+    let mut merged = theirs.to_vec();
+
+    // Added or changed here
+    for item in ours {
+        let unchanged = find(base, name(item)).is_some_and(|i| base[i] == *item);
+        if unchanged {
+            continue;
+        }
+        match find(&merged, name(item)) {
+            Some(i) => merged[i] = item.clone(),
+            None => merged.push(item.clone()),
+        }
+    }
+
+    // Removed here, unless the other side changed it meanwhile
+    for item in base {
+        if find(ours, name(item)).is_none() {
+            if let Some(i) = find(&merged, name(item)) {
+                if merged[i] == *item {
+                    merged.remove(i);
+                }
+            }
+        }
+    }
+
+    merged
+}
+
+/// "file line L, column C: message" plus the offending line, from a TOML parse error
+fn describe_parse_error(file_name: &str, content: &str, error: &toml::de::Error) -> String {
+    let Some(span) = error.span() else {
+        return format!("{}: {}", file_name, error.message());
+    };
+    let before = &content[..span.start.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map(|i| i + 1).unwrap_or(0) + 1;
+    let source_line = content.lines().nth(line - 1).unwrap_or("").trim();
+
+    format!(
+        "{} line {}, column {}: {}\n  > {}",
+        file_name,
+        line,
+        column,
+        error.message(),
+        source_line
+    )
+}
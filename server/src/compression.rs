@@ -0,0 +1,92 @@
+/// gzip and zstd compression of responses: listings, pages and text files, for browsing
+/// large directories over slow links. Bodies are compressed as they stream, so large text
+/// downloads are too. Media, archives and other formats that are compressed already are
+/// sent as they are, and so are partial responses and tiny bodies. Clouds can turn it off
+/// with `compression = false`.
+use axum::{
+    body::HttpBody,
+    http::{
+        header::{CONTENT_ENCODING, CONTENT_TYPE, ETAG},
+        HeaderValue, Response,
+    },
+};
+use tower_http::compression::{predicate::Predicate, CompressionLayer, CompressionLevel};
+
+/// Smaller bodies don't get noticeably smaller
+const MIN_BYTES: u64 = 1024;
+
+/// Text-like types; images (except SVG), audio, video, archives and PDFs aren't
+pub fn is_compressible(content_type: &str) -> bool {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    essence.starts_with("text/")
+        || essence.ends_with("+json")
+        || essence.ends_with("+xml")
+        || matches!(
+            essence.as_str(),
+            "application/json"
+                | "application/javascript"
+                | "application/xml"
+                | "application/x-ndjson"
+                | "application/toml"
+                | "application/yaml"
+                | "application/x-yaml"
+                | "application/wasm"
+                | "image/svg+xml"
+                | "image/bmp"
+        )
+}
+
+/// Compress compressible types unless the body is known to be tiny. Streamed bodies,
+/// whose length isn't known up front, are compressed, except event streams: the
+/// compressor would hold events back until it had enough of them.
+#[derive(Clone, Copy)]
+pub struct Compressible;
+
+impl Predicate for Compressible {
+    fn should_compress<B>(&self, response: &Response<B>) -> bool
+    where
+        B: HttpBody,
+    {
+        let compressible = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|content_type| {
+                is_compressible(content_type) && !content_type.starts_with("text/event-stream")
+            });
+        let size = response.body().size_hint().exact();
+        compressible && size.is_none_or(|size| size >= MIN_BYTES)
+    }
+}
+
+/// The compression layer of a cloud's router. Ranged and already encoded responses are
+/// left alone by the layer itself.
+pub fn layer() -> CompressionLayer<Compressible> {
+    CompressionLayer::new()
+        // Fast rather than small: most of the gain at a fraction of the time
+        .quality(CompressionLevel::Fastest)
+        .compress_when(Compressible)
+}
+
+/// The bytes of a compressed response differ from the uncompressed ones, so its tag is
+/// only weak. Goes outside `layer`.
+pub async fn weaken_etag<B>(mut response: Response<B>) -> Response<B> {
+    if !response.headers().contains_key(CONTENT_ENCODING) {
+        return response;
+    }
+    if let Some(weak) = response
+        .headers()
+        .get(ETAG)
+        .and_then(|tag| tag.to_str().ok())
+        .filter(|tag| !tag.starts_with("W/"))
+        .and_then(|tag| HeaderValue::from_str(&format!("W/{}", tag)).ok())
+    {
+        response.headers_mut().insert(ETAG, weak);
+    }
+    response
+}
@@ -0,0 +1,102 @@
+/// Versioned config files. Each file carries a `schema_version`; files without one are
+/// version 1. On load, the steps from the file's version to the current one are applied
+/// in order to the parsed TOML, so old files keep working after the format changes.
+/// A step to version N is written once and never changed afterwards. Files from a newer
+/// CloudHost are refused rather than read wrongly.
+use std::path::{Path, PathBuf};
+
+/// Key holding the version in every config file
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// One format change: upgrades a file of version `to - 1` to version `to`
+pub struct Migration {
+    pub to: u32,
+    /// What changed, for the log
+    pub description: &'static str,
+    pub apply: fn(&mut toml::Table) -> Result<(), String>,
+}
+
+/// A file brought up to date
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migrated {
+    pub from: u32,
+    pub to: u32,
+    /// Descriptions of the steps applied, oldest first
+    pub steps: Vec<&'static str>,
+    /// The upgraded file
+    pub content: String,
+}
+
+/// The version `table` declares, 1 when it doesn't
+pub fn schema_version(table: &toml::Table) -> Result<u32, String> {
+    match table.get(SCHEMA_VERSION_KEY) {
+        None => Ok(1),
+        Some(toml::Value::Integer(version)) if *version >= 1 => Ok(*version as u32),
+        Some(value) => Err(format!(
+            "{} must be a positive number, not {}",
+            SCHEMA_VERSION_KEY, value
+        )),
+    }
+}
+
+/// Upgrade `content` of `file_name` to `latest` with `migrations`. None when it already is
+/// at `latest`; fails when it doesn't parse, is newer than `latest` or a step fails.
+pub fn migrate(
+    file_name: &str,
+    content: &str,
+    latest: u32,
+    migrations: &[Migration],
+) -> Result<Option<Migrated>, String> {
+    // Syntax errors are left to the real parse, which describes them better
+    let Ok(mut table) = content.parse::<toml::Table>() else {
+        return Ok(None);
+    };
+    let from = schema_version(&table).map_err(|e| format!("{}: {}", file_name, e))?;
+    if from > latest {
+        return Err(format!(
+            "{} is schema_version {}, but this CloudHost reads up to {}; update CloudHost \
+             or restore a backup",
+            file_name, from, latest
+        ));
+    }
+    if from == latest {
+        return Ok(None);
+    }
+
+    let mut steps = Vec::new();
+    for migration in migrations
+        .iter()
+        .filter(|migration| migration.to > from && migration.to <= latest)
+    {
+        (migration.apply)(&mut table).map_err(|e| {
+            format!(
+                "{}: upgrading to schema_version {} failed: {}",
+                file_name, migration.to, e
+            )
+        })?;
+        steps.push(migration.description);
+    }
+    table.insert(
+        SCHEMA_VERSION_KEY.to_string(),
+        toml::Value::Integer(latest as i64),
+    );
+    let content = toml::to_string_pretty(&table).map_err(|e| e.to_string())?;
+    Ok(Some(Migrated {
+        from,
+        to: latest,
+        steps,
+        content,
+    }))
+}
+
+/// Keep `path` as `<path>.v<version>` before it is replaced by an upgraded version.
+/// An existing backup of that version is kept, as it is the older original.
+pub fn backup_before_migration(path: &Path, version: u32) -> std::io::Result<PathBuf> {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".v{}", version));
+    let backup = PathBuf::from(backup);
+    if !backup.exists() {
+        std::fs::copy(path, &backup)?;
+    }
+    Ok(backup)
+}
@@ -1,9 +1,42 @@
-use std::path::PathBuf;
+//! Where the configs and data live, and the environment variables that override the
+//! configs. The command line flags of the TUI set the same variables, so a flag beats the
+//! variable, which beats the config file, which beats the built-in value.
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{PoisonError, RwLock};
+
+use crate::debug_stream::LogLevel;
 
 // Constants for config file names
 const APP_NAME: &str = "CloudHost";
 const TUI_CONFIG_FILE: &str = "tui-config.toml";
 const CLOUDS_CONFIG_FILE: &str = "clouds-config.toml";
+/// In the default config directory: where `--config-dir` moved the configs to
+const BOOTSTRAP_FILE: &str = "location.toml";
+/// SSH host key of the SFTP listeners, made when the first one starts
+const SFTP_HOST_KEY_FILE: &str = "sftp-host-key";
+
+/// What CloudHost keeps in the config directory, moved along when it changes
+const CONFIG_DIR_ENTRIES: &[&str] = &[
+    TUI_CONFIG_FILE,
+    CLOUDS_CONFIG_FILE,
+    "clouds-config.toml.bak",
+    "clouds-config.toml.invalid",
+    "auth-failures.log",
+    "logs",
+    "backups",
+    "photo-cache",
+    "transcode-cache",
+    "preview-cache",
+    "nextcloud-uploads",
+    SFTP_HOST_KEY_FILE,
+    PROFILES_DIR,
+];
+/// In the base config directory: one directory of configs and data per named profile
+const PROFILES_DIR: &str = "profiles";
+/// The profile of the base config directory itself
+pub const DEFAULT_PROFILE: &str = "default";
 
 /// Get the current directory with fallback
 fn get_current_dir() -> PathBuf {
@@ -41,17 +74,189 @@ pub fn is_dev_mode() -> bool {
     false
 }
 
-/// Get the config directory path based on the current mode
+/// Set to keep the configs, logs and caches in another directory, whatever the mode
+pub const CONFIG_DIR_VAR: &str = "CLOUDHOST_CONFIG_DIR";
+/// Set to use a named profile, with configs and data of its own (`--profile`)
+pub const PROFILE_VAR: &str = "CLOUDHOST_PROFILE";
+/// Set to use another clouds config file (`--config`)
+pub const CLOUDS_CONFIG_VAR: &str = "CLOUDHOST_CONFIG";
+/// Set to keep logs, caches and backups apart from the configs (`--data-dir`)
+pub const DATA_DIR_VAR: &str = "CLOUDHOST_DATA_DIR";
+/// Override `[defaults] base_port` of the clouds config (`--base-port`)
+pub const BASE_PORT_VAR: &str = "CLOUDHOST_BASE_PORT";
+/// Override `[defaults] bind_address` of the clouds config (`--bind-address`)
+pub const BIND_ADDRESS_VAR: &str = "CLOUDHOST_BIND_ADDRESS";
+/// Override `[defaults] log_level` of the clouds config (`--log-level`)
+pub const LOG_LEVEL_VAR: &str = "CLOUDHOST_LOG_LEVEL";
+
+/// A variable that is set and not empty
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Get the config directory path: that of the current profile in the base config directory
 pub fn get_config_dir() -> PathBuf {
-    if is_dev_mode() {
+    let base = get_base_config_dir();
+    match current_profile() {
+        Some(profile) => base.join(PROFILES_DIR).join(profile),
+        None => base,
+    }
+}
+
+/// The profile `set_profile` switched to, which wins over `CLOUDHOST_PROFILE`. Kept here
+/// rather than in the environment, which mustn't change while other threads run.
+static CHOSEN_PROFILE: RwLock<Option<String>> = RwLock::new(None);
+
+/// The profile in use, None for the default one. Names that aren't valid are ignored;
+/// the binary refuses to start with them.
+pub fn current_profile() -> Option<String> {
+    let chosen = CHOSEN_PROFILE
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    chosen
+        .or_else(|| env_var(PROFILE_VAR))
+        .filter(|name| name != DEFAULT_PROFILE && validate_profile_name(name).is_ok())
+}
+
+/// Use the profile `name` from now on; it is created when its configs are first saved
+pub fn set_profile(name: &str) -> Result<(), String> {
+    validate_profile_name(name)?;
+    *CHOSEN_PROFILE
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = Some(name.to_string());
+    Ok(())
+}
+
+/// Profile names become directory names: letters, digits, `-` and `_` only
+pub fn validate_profile_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Profile name must not be empty".to_string());
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Profile name '{}' may only contain letters, digits, - and _",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// The default profile, then the others in the base config directory by name
+pub fn list_profiles() -> Vec<String> {
+    let mut profiles: Vec<String> = std::fs::read_dir(get_base_config_dir().join(PROFILES_DIR))
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|name| name != DEFAULT_PROFILE && validate_profile_name(name).is_ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    profiles.sort();
+    profiles.insert(0, DEFAULT_PROFILE.to_string());
+    profiles
+}
+
+/// Get the base config directory, which holds the default profile and the others:
+/// `CLOUDHOST_CONFIG_DIR` if set, otherwise based on the current mode; in production mode
+/// the one `--config-dir` chose, if any
+pub fn get_base_config_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os(CONFIG_DIR_VAR).filter(|dir| !dir.is_empty()) {
+        PathBuf::from(dir)
+    } else if is_dev_mode() {
         // Development mode: use project root
         get_project_root()
+    } else if let Some(dir) = bootstrap_config_dir() {
+        dir
     } else {
         // Production mode: use appdata directory
         get_appdata_dir()
     }
 }
 
+/// The bootstrap file, `location.toml` in the default config directory
+#[derive(Debug, Serialize, Deserialize)]
+struct Bootstrap {
+    config_dir: PathBuf,
+}
+
+/// Get the path of the file remembering the config directory `--config-dir` chose
+pub fn get_bootstrap_path() -> PathBuf {
+    get_appdata_dir().join(BOOTSTRAP_FILE)
+}
+
+fn bootstrap_config_dir() -> Option<PathBuf> {
+    let content = std::fs::read_to_string(get_bootstrap_path()).ok()?;
+    toml::from_str::<Bootstrap>(&content)
+        .ok()
+        .map(|bootstrap| bootstrap.config_dir)
+}
+
+/// Keep the configs in `dir` from now on, outside development mode. The default
+/// directory forgets the choice instead.
+pub fn set_config_dir(dir: &Path) -> std::io::Result<()> {
+    let path = get_bootstrap_path();
+    if dir == get_appdata_dir() {
+        return match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    let bootstrap = Bootstrap {
+        config_dir: dir.to_path_buf(),
+    };
+    let content = toml::to_string(&bootstrap)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    std::fs::create_dir_all(get_appdata_dir())?;
+    std::fs::write(path, content)
+}
+
+/// The configs and data in `from` that `to` doesn't have yet
+pub fn movable_config_entries(from: &Path, to: &Path) -> Vec<&'static str> {
+    CONFIG_DIR_ENTRIES
+        .iter()
+        .copied()
+        .filter(|name| from.join(name).exists() && !to.join(name).exists())
+        .collect()
+}
+
+/// Move the configs and data in `from` that `to` doesn't have yet; returns what moved
+pub fn move_config_entries(from: &Path, to: &Path) -> std::io::Result<Vec<&'static str>> {
+    std::fs::create_dir_all(to)?;
+    let names = movable_config_entries(from, to);
+    for name in &names {
+        let (source, target) = (from.join(name), to.join(name));
+        // Another file system can't take a rename: copy, then remove
+        if std::fs::rename(&source, &target).is_err() {
+            copy_recursively(&source, &target)?;
+            if source.is_dir() {
+                std::fs::remove_dir_all(&source)?;
+            } else {
+                std::fs::remove_file(&source)?;
+            }
+        }
+    }
+    Ok(names)
+}
+
+fn copy_recursively(source: &Path, target: &Path) -> std::io::Result<()> {
+    if !source.is_dir() {
+        std::fs::copy(source, target)?;
+        return Ok(());
+    }
+    std::fs::create_dir_all(target)?;
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        copy_recursively(&entry.path(), &target.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
 /// Get the project root directory for development mode
 /// In dev mode, we use the current working directory as the project root
 fn get_project_root() -> PathBuf {
@@ -75,16 +280,122 @@ pub fn get_tui_config_path() -> PathBuf {
     path
 }
 
-/// Get the full path to the clouds config file
+/// Get the full path to the clouds config file: `CLOUDHOST_CONFIG` if set, otherwise in
+/// the config directory
 pub fn get_clouds_config_path() -> PathBuf {
+    if let Some(path) = env_var(CLOUDS_CONFIG_VAR) {
+        return PathBuf::from(path);
+    }
     let mut path = get_config_dir();
     path.push(CLOUDS_CONFIG_FILE);
     path
 }
 
+/// Get the full path to the SSH host key the SFTP listeners share
+pub fn get_sftp_host_key_path() -> PathBuf {
+    get_config_dir().join(SFTP_HOST_KEY_FILE)
+}
+
+/// Get the directory of logs, caches and backups: `CLOUDHOST_DATA_DIR` if set, otherwise
+/// the config directory
+pub fn get_data_dir() -> PathBuf {
+    env_var(DATA_DIR_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(get_config_dir)
+}
+
+/// Server defaults set by environment variables, over those of the clouds config.
+/// They are never saved to the config.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Overrides {
+    pub base_port: Option<u16>,
+    pub bind_address: Option<IpAddr>,
+    pub log_level: Option<LogLevel>,
+}
+
+impl Overrides {
+    /// From the environment, failing on a value that doesn't parse
+    pub fn from_env() -> Result<Self, String> {
+        Self::parse(env_var)
+    }
+
+    /// From the variables `var` returns
+    pub fn parse(var: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let base_port = var(BASE_PORT_VAR)
+            .map(|value| {
+                value
+                    .trim()
+                    .parse::<u16>()
+                    .ok()
+                    .filter(|port| *port > 0)
+                    .ok_or_else(|| {
+                        format!(
+                            "{}: '{}' is not a port from 1 to 65535",
+                            BASE_PORT_VAR, value
+                        )
+                    })
+            })
+            .transpose()?;
+        let bind_address = var(BIND_ADDRESS_VAR)
+            .map(|value| {
+                value
+                    .trim()
+                    .parse::<IpAddr>()
+                    .map_err(|_| format!("{}: '{}' is not an IP address", BIND_ADDRESS_VAR, value))
+            })
+            .transpose()?;
+        let log_level = var(LOG_LEVEL_VAR)
+            .map(|value| {
+                value
+                    .trim()
+                    .parse::<LogLevel>()
+                    .map_err(|e| format!("{}: {}", LOG_LEVEL_VAR, e))
+            })
+            .transpose()?;
+        Ok(Self {
+            base_port,
+            bind_address,
+            log_level,
+        })
+    }
+
+    /// `VARIABLE=value` of each override, to show where a value comes from
+    pub fn describe(&self) -> Vec<String> {
+        let mut set = Vec::new();
+        if let Some(port) = self.base_port {
+            set.push(format!("{}={}", BASE_PORT_VAR, port));
+        }
+        if let Some(address) = self.bind_address {
+            set.push(format!("{}={}", BIND_ADDRESS_VAR, address));
+        }
+        if let Some(level) = &self.log_level {
+            set.push(format!(
+                "{}={}",
+                LOG_LEVEL_VAR,
+                level.to_string().to_lowercase()
+            ));
+        }
+        set
+    }
+}
+
 /// Ensure the config directory exists
 pub fn ensure_config_dir() -> std::io::Result<()> {
     let config_dir = get_config_dir();
     std::fs::create_dir_all(&config_dir)?;
     Ok(())
 }
+
+/// A cloud name usable as a file name: unusual characters are replaced with `_`
+pub fn cloud_file_stem(cloud_name: &str) -> String {
+    cloud_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,227 @@
+/// Portable export/import of the clouds configuration, for moving clouds to another host.
+/// Exports either leave out secrets (passwords, JWT secrets, SMTP passwords, webhook
+/// secrets) or encrypt the whole
+/// configuration with a passphrase (Argon2id key derivation + XChaCha20-Poly1305).
+use crate::cloud::Cloud;
+use crate::clouds_config::CloudsConfig;
+use crate::error::{ServerError, ServerResult};
+use crate::secrets::{decode, decrypt, derive_key, encode, encrypt, random_salt, CloudSecrets};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+const EXPORT_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigExport {
+    pub format_version: u32,
+    pub exported_at: chrono::DateTime<chrono::Utc>,
+    pub includes_secrets: bool,
+    /// Plain configuration, absent when encrypted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<CloudsConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypted: Option<EncryptedPayload>,
+}
+
+/// Base64 encoded pieces of an encrypted configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedPayload {
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+impl ConfigExport {
+    /// Export without any of the secrets; clouds need a new password after import, and
+    /// their email and webhooks need setting up again
+    pub fn without_secrets(config: &CloudsConfig) -> Self {
+        let mut config = config.clone();
+        config.encrypted_secrets = None;
+        for cloud in &mut config.clouds {
+            CloudSecrets::take_from(cloud);
+            cloud.password_changed_at = None;
+        }
+        Self {
+            format_version: EXPORT_FORMAT_VERSION,
+            exported_at: chrono::Utc::now(),
+            includes_secrets: false,
+            config: Some(config),
+            encrypted: None,
+        }
+    }
+
+    /// Export everything, encrypted with `passphrase`
+    pub fn encrypted(config: &CloudsConfig, passphrase: &str) -> ServerResult<Self> {
+        if config.secrets_locked() {
+            return Err(ServerError::validation(
+                "Unlock the cloud secrets before exporting them",
+            ));
+        }
+        let salt = random_salt();
+        let key = derive_key(passphrase, &salt)?;
+        let plaintext = toml::to_string(config)?;
+        let (nonce, ciphertext) = encrypt(&key, plaintext.as_bytes())?;
+
+        Ok(Self {
+            format_version: EXPORT_FORMAT_VERSION,
+            exported_at: chrono::Utc::now(),
+            includes_secrets: true,
+            config: None,
+            encrypted: Some(EncryptedPayload {
+                salt: encode(&salt),
+                nonce: encode(&nonce),
+                ciphertext: encode(&ciphertext),
+            }),
+        })
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted.is_some()
+    }
+
+    /// Get the configuration back; `passphrase` is required for encrypted exports
+    pub fn into_config(self, passphrase: Option<&str>) -> ServerResult<CloudsConfig> {
+        if self.format_version > EXPORT_FORMAT_VERSION {
+            return Err(ServerError::configuration(format!(
+                "Export format version {} is newer than supported version {}",
+                self.format_version, EXPORT_FORMAT_VERSION
+            )));
+        }
+
+        match (self.config, self.encrypted) {
+            (Some(config), None) => Ok(config),
+            (None, Some(payload)) => {
+                let passphrase = passphrase.ok_or_else(|| {
+                    ServerError::validation("This export is encrypted, a passphrase is required")
+                })?;
+                let salt = decode(&payload.salt, "salt in export file")?;
+                let nonce = decode(&payload.nonce, "nonce in export file")?;
+                let ciphertext = decode(&payload.ciphertext, "ciphertext in export file")?;
+
+                let key = derive_key(passphrase, &salt)?;
+                let plaintext = decrypt(&key, &nonce, &ciphertext).map_err(|_| {
+                    ServerError::authentication("Wrong passphrase or corrupted export file")
+                })?;
+                let plaintext = String::from_utf8(plaintext).map_err(|_| {
+                    ServerError::configuration("Decrypted configuration is not valid UTF-8")
+                })?;
+                Ok(toml::from_str(&plaintext)?)
+            }
+            _ => Err(ServerError::configuration(
+                "Export file must contain either a plain or an encrypted configuration",
+            )),
+        }
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> ServerResult<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn read_from_file(path: &Path) -> ServerResult<Self> {
+        let content = fs::read_to_string(path)?;
+        Ok(toml::from_str(&content)?)
+    }
+}
+
+/// What an import changed
+#[derive(Debug, Clone, Default)]
+pub struct ImportSummary {
+    pub added_folders: Vec<String>,
+    pub added_clouds: Vec<String>,
+    /// Names that already exist here and were left untouched
+    pub skipped: Vec<String>,
+    /// Imported folders whose path doesn't exist on this machine
+    pub missing_paths: Vec<String>,
+    /// Imported clouds that need a password before they can start
+    pub needs_password: Vec<String>,
+    /// Email and webhooks left out of imported clouds because the export had no secrets
+    /// for them, as `cloud email` or `cloud webhooks`
+    pub dropped_without_secrets: Vec<String>,
+}
+
+impl ImportSummary {
+    /// One line summary for the UI
+    pub fn describe(&self) -> String {
+        let mut parts = vec![format!(
+            "Imported {} folder(s) and {} cloud(s)",
+            self.added_folders.len(),
+            self.added_clouds.len()
+        )];
+        if !self.skipped.is_empty() {
+            parts.push(format!("skipped existing: {}", self.skipped.join(", ")));
+        }
+        if !self.missing_paths.is_empty() {
+            parts.push(format!("missing paths: {}", self.missing_paths.join(", ")));
+        }
+        if !self.needs_password.is_empty() {
+            parts.push(format!(
+                "set a password for: {}",
+                self.needs_password.join(", ")
+            ));
+        }
+        if !self.dropped_without_secrets.is_empty() {
+            parts.push(format!(
+                "set up again, their secrets weren't exported: {}",
+                self.dropped_without_secrets.join(", ")
+            ));
+        }
+        parts.join("; ")
+    }
+}
+
+/// Merge `imported` into `config`, never overwriting existing folders or clouds
+pub fn merge_into(config: &mut CloudsConfig, imported: CloudsConfig) -> ImportSummary {
+    let mut summary = ImportSummary::default();
+
+    for folder in imported.cloud_folders {
+        if config.cloud_folders.iter().any(|f| f.name == folder.name) {
+            summary.skipped.push(folder.name);
+            continue;
+        }
+        if !folder.folder_path.is_dir() {
+            summary.missing_paths.push(folder.name.clone());
+        }
+        summary.added_folders.push(folder.name.clone());
+        config.cloud_folders.push(folder);
+    }
+
+    for mut cloud in imported.clouds {
+        if config.clouds.iter().any(|c| c.name == cloud.name) || cloud.cloud_folders.is_empty() {
+            summary.skipped.push(cloud.name);
+            continue;
+        }
+        if cloud.jwt_secret.is_empty() {
+            cloud.jwt_secret = Cloud::generate_jwt_secret(&cloud.name);
+        }
+        if !cloud.has_password() {
+            summary.needs_password.push(cloud.name.clone());
+        }
+        // Useless without the secrets, and the config would not load with them
+        if cloud
+            .email
+            .as_ref()
+            .is_some_and(|email| email.username.is_some() && email.password.is_none())
+        {
+            cloud.email = None;
+            summary
+                .dropped_without_secrets
+                .push(format!("{} email", cloud.name));
+        }
+        let webhooks = cloud.webhooks.len();
+        cloud.webhooks.retain(|webhook| !webhook.secret.is_empty());
+        if cloud.webhooks.len() < webhooks {
+            summary
+                .dropped_without_secrets
+                .push(format!("{} webhooks", cloud.name));
+        }
+        summary.added_clouds.push(cloud.name.clone());
+        config.clouds.push(cloud);
+    }
+
+    summary
+}
@@ -0,0 +1,113 @@
+/// CSRF protection with double-submit cookies.
+/// Web pages get a random token in a `SameSite=Strict` cookie and embed the same value
+/// for their scripts, which echo it in the `X-CSRF-Token` header. State-changing requests
+/// authenticated by cookie must carry a header matching the cookie. Bearer-token requests
+/// are exempt: a browser never attaches those on its own.
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request},
+    http::{header::SET_COOKIE, request::Parts, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+
+use crate::auth::{cookie_value, AuthMethod, AuthenticatedUser};
+use crate::error::{ApiError, ErrorCode};
+use crate::security_headers::is_https;
+
+pub const CSRF_COOKIE: &str = "csrf_token";
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+
+const TOKEN_BYTES: usize = 32;
+
+/// The CSRF token of the current page request, for embedding in forms and scripts
+#[derive(Debug, Clone)]
+pub struct CsrfToken(pub String);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for CsrfToken {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<CsrfToken>()
+            .cloned()
+            .ok_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+fn generate_token() -> String {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Tokens end up in HTML and headers, so only accept what `generate_token` produces
+fn is_well_formed(token: &str) -> bool {
+    token.len() == URL_SAFE_NO_PAD.encode([0u8; TOKEN_BYTES]).len()
+        && token
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+fn token_from_cookie(headers: &HeaderMap) -> Option<String> {
+    cookie_value(headers, CSRF_COOKIE).filter(|token| is_well_formed(token))
+}
+
+/// Middleware for web pages: make a token available to the handler (`CsrfToken`)
+/// and set the cookie if the browser doesn't have one yet
+pub async fn issue_token(mut request: Request, next: Next) -> Response {
+    let existing = token_from_cookie(request.headers());
+    let https = is_https(request.uri(), request.headers());
+    let token = existing.clone().unwrap_or_else(generate_token);
+    request.extensions_mut().insert(CsrfToken(token.clone()));
+
+    let mut response = next.run(request).await;
+    if existing.is_none() {
+        let mut cookie = format!(
+            "{}={}; Path=/; SameSite=Strict; HttpOnly",
+            CSRF_COOKIE, token
+        );
+        if https {
+            cookie.push_str("; Secure");
+        }
+        if let Ok(value) = HeaderValue::from_str(&cookie) {
+            response.headers_mut().append(SET_COOKIE, value);
+        }
+    }
+    response
+}
+
+/// Middleware for API routes: reject state-changing requests authenticated by cookie
+/// whose `X-CSRF-Token` header doesn't match the CSRF cookie.
+/// Must run after the auth middleware (add it with `route_layer` before the auth layer).
+pub async fn require_token(request: Request, next: Next) -> Response {
+    let safe_method = matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    );
+    let via_bearer = request
+        .extensions()
+        .get::<AuthenticatedUser>()
+        .is_some_and(|user| user.method == AuthMethod::Bearer);
+    if safe_method || via_bearer {
+        return next.run(request).await;
+    }
+
+    let cookie = token_from_cookie(request.headers());
+    let header = request
+        .headers()
+        .get(CSRF_HEADER)
+        .and_then(|value| value.to_str().ok());
+    match (cookie, header) {
+        (Some(cookie), Some(header)) if cookie == header => next.run(request).await,
+        _ => ApiError::new(
+            ErrorCode::CsrfToken,
+            "Missing or invalid CSRF token. Reload the page and try again.",
+        )
+        .into_response(),
+    }
+}
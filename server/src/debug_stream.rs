@@ -1,31 +1,53 @@
-use chrono::{DateTime, Utc};
-use std::sync::Arc;
-use tokio::sync::{broadcast, RwLock};
-
-#[derive(Debug, Clone)]
-pub struct DebugMessage {
-    pub timestamp: DateTime<Utc>,
-    pub level: LogLevel,
-    pub source: String,
-    pub message: String,
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+use crate::log_store::LogStore;
+
+pub use cloudhost_shared::debug_stream::{DebugMessage, LogFrame, LogLevel};
+
+/// `[clouds.logging]`: the least severe level a cloud logs, with overrides for single
+/// sources (the module a message comes from, as shown in the log: `auth`, `upload`,
+/// `listing_cache`, ...). Errors are always logged, whatever the levels.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogLevelConfig {
+    /// Messages below this level are dropped (default: debug, everything)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<LogLevel>,
+    /// Levels by source, overriding `level`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sources: BTreeMap<String, LogLevel>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum LogLevel {
-    Info,
-    Warning,
-    Error,
-    Debug,
-}
+impl LogLevelConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
 
-impl std::fmt::Display for LogLevel {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            LogLevel::Info => write!(f, "INFO"),
-            LogLevel::Warning => write!(f, "WARN"),
-            LogLevel::Error => write!(f, "ERROR"),
-            LogLevel::Debug => write!(f, "DEBUG"),
-        }
+    /// Problems with the sources, as `field: message`
+    pub fn validate(&self) -> Vec<String> {
+        self.sources
+            .keys()
+            .filter(|source| source.trim().is_empty())
+            .map(|_| "sources: a source name must not be empty".to_string())
+            .collect()
+    }
+
+    /// The least severe level logged from `source`
+    pub fn min_level(&self, source: &str) -> LogLevel {
+        self.sources
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(source))
+            .map(|(_, level)| level.clone())
+            .or_else(|| self.level.clone())
+            .unwrap_or(LogLevel::Debug)
+    }
+
+    /// Whether a message of `level` from `source` is logged
+    pub fn allows(&self, level: &LogLevel, source: &str) -> bool {
+        level.at_least(&self.min_level(source))
     }
 }
 
@@ -33,7 +55,11 @@ impl std::fmt::Display for LogLevel {
 pub struct DebugStream {
     sender: broadcast::Sender<DebugMessage>,
     max_messages: usize,
-    message_history: Arc<RwLock<Vec<DebugMessage>>>,
+    message_history: Arc<Mutex<Vec<DebugMessage>>>,
+    /// Where messages are persisted, if anywhere
+    store: Option<LogStore>,
+    /// Which messages are kept at all
+    levels: LogLevelConfig,
 }
 
 impl DebugStream {
@@ -43,22 +69,55 @@ impl DebugStream {
         Self {
             sender,
             max_messages,
-            message_history: Arc::new(RwLock::new(Vec::new())),
+            message_history: Arc::new(Mutex::new(Vec::new())),
+            store: None,
+            levels: LogLevelConfig::default(),
+        }
+    }
+
+    /// Drop the messages `levels` filters out before they're stored or sent
+    pub fn with_levels(mut self, levels: LogLevelConfig) -> Self {
+        self.levels = levels;
+        self
+    }
+
+    /// Also append every message to `store`
+    pub fn with_store(mut self, store: LogStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Start a new log file, see `LogStore::rotate_now`
+    pub fn rotate_log(&self) -> std::io::Result<bool> {
+        match &self.store {
+            Some(store) => store.rotate_now(),
+            None => Ok(false),
         }
     }
 
     /// Send a debug message to all subscribers
     pub async fn send(&self, level: LogLevel, source: &str, message: &str) {
+        self.push(level, source, message.to_string());
+    }
+
+    /// Same as `send`, for synchronous callers such as the tracing layer
+    pub fn push(&self, level: LogLevel, source: &str, message: String) {
+        if !self.levels.allows(&level, source) {
+            return;
+        }
         let debug_msg = DebugMessage {
             timestamp: Utc::now(),
             level,
             source: source.to_string(),
-            message: message.to_string(),
+            message,
         };
 
+        if let Some(store) = &self.store {
+            store.append(&debug_msg);
+        }
+
         // Store in history
-        {
-            let mut history = self.message_history.write().await;
+        if let Ok(mut history) = self.message_history.lock() {
             history.push(debug_msg.clone());
 
             // Keep only the last max_messages
@@ -79,12 +138,17 @@ impl DebugStream {
 
     /// Get the message history
     pub async fn get_history(&self) -> Vec<DebugMessage> {
-        self.message_history.read().await.clone()
+        self.message_history
+            .lock()
+            .map(|history| history.clone())
+            .unwrap_or_default()
     }
 
     /// Get recent messages (last N messages)
     pub async fn get_recent(&self, count: usize) -> Vec<DebugMessage> {
-        let history = self.message_history.read().await;
+        let Ok(history) = self.message_history.lock() else {
+            return Vec::new();
+        };
         let start = if history.len() > count {
             history.len() - count
         } else {
@@ -95,7 +159,9 @@ impl DebugStream {
 
     /// Clear message history
     pub async fn clear_history(&self) {
-        self.message_history.write().await.clear();
+        if let Ok(mut history) = self.message_history.lock() {
+            history.clear();
+        }
     }
 }
 
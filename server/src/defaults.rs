@@ -0,0 +1,124 @@
+/// Settings of the whole server, and defaults for the clouds that don't set their own.
+/// Edited in the Settings tab of the TUI or under `[defaults]` in the clouds config.
+///
+/// The log level, login lifetime, free space for uploads and largest upload only fill in
+/// for clouds that leave theirs at the built-in value. The ports, the address and the log
+/// level and login lifetime apply when a cloud starts; the upload limits right away.
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr};
+
+use crate::cloud::Cloud;
+use crate::config_paths::Overrides;
+use crate::debug_stream::LogLevel;
+use crate::disk_space::DiskSpaceConfig;
+use crate::orchestrator::BASE_PORT;
+use crate::sessions::SessionConfig;
+
+fn default_base_port() -> u16 {
+    BASE_PORT
+}
+
+fn default_bind_address() -> IpAddr {
+    IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerDefaults {
+    /// Port of the first cloud started; the next ones get the following ports
+    #[serde(default = "default_base_port")]
+    pub base_port: u16,
+    /// Address the clouds, their FTP and SFTP listeners and the landing page listen on,
+    /// 0.0.0.0 for every network, 127.0.0.1 for this machine only
+    #[serde(default = "default_bind_address")]
+    pub bind_address: IpAddr,
+    /// Least severe level logged by clouds without their own `logging.level`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<LogLevel>,
+    /// How long web logins last, for clouds with the built-in `sessions.lifetime_hours`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_lifetime_hours: Option<u32>,
+    /// Free space uploads must leave, for clouds with the built-in
+    /// `disk_space.min_free_mb`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_free_mb: Option<u64>,
+    /// Largest upload taken, for clouds with the built-in `disk_space.max_upload_mb`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_upload_mb: Option<u64>,
+}
+
+impl Default for ServerDefaults {
+    fn default() -> Self {
+        Self {
+            base_port: default_base_port(),
+            bind_address: default_bind_address(),
+            log_level: None,
+            session_lifetime_hours: None,
+            min_free_mb: None,
+            max_upload_mb: None,
+        }
+    }
+}
+
+impl ServerDefaults {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Problems with the values, as `field: message`
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.base_port == 0 {
+            problems.push("base_port: must not be 0".to_string());
+        }
+        if let Some(hours) = self.session_lifetime_hours {
+            let sessions = SessionConfig {
+                lifetime_hours: hours,
+                ..SessionConfig::default()
+            };
+            for problem in sessions.validate() {
+                problems.push(problem.replace("lifetime_hours", "session_lifetime_hours"));
+            }
+        }
+        problems
+    }
+
+    /// These defaults with `overrides` on top
+    pub fn with_overrides(&self, overrides: &Overrides) -> Self {
+        let mut defaults = self.clone();
+        if let Some(port) = overrides.base_port {
+            defaults.base_port = port;
+        }
+        if let Some(address) = overrides.bind_address {
+            defaults.bind_address = address;
+        }
+        if let Some(level) = &overrides.log_level {
+            defaults.log_level = Some(level.clone());
+        }
+        defaults
+    }
+
+    /// `cloud` with these defaults filled in where it keeps the built-in values
+    pub fn apply(&self, cloud: &Cloud) -> Cloud {
+        let mut cloud = cloud.clone();
+        if cloud.logging.level.is_none() {
+            cloud.logging.level = self.log_level.clone();
+        }
+        if let Some(hours) = self.session_lifetime_hours {
+            if cloud.sessions.lifetime_hours == SessionConfig::default().lifetime_hours {
+                cloud.sessions.lifetime_hours = hours;
+            }
+        }
+        if let Some(min_free_mb) = self.min_free_mb {
+            if cloud.disk_space.min_free_mb == DiskSpaceConfig::default().min_free_mb {
+                cloud.disk_space.min_free_mb = min_free_mb;
+                cloud.disk_space.warn_free_mb = cloud.disk_space.warn_free_mb.max(min_free_mb);
+            }
+        }
+        if let Some(max_upload_mb) = self.max_upload_mb {
+            if cloud.disk_space.max_upload_mb == DiskSpaceConfig::default().max_upload_mb {
+                cloud.disk_space.max_upload_mb = max_upload_mb;
+            }
+        }
+        cloud
+    }
+}
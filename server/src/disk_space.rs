@@ -0,0 +1,155 @@
+/// Free space on the filesystems the cloud folders live on. Uploads are refused when
+/// a folder's filesystem gets below `min_free_mb` or they are larger than
+/// `max_upload_mb`, and the TUI, the web UI, `/api/usage` and `/api/metrics` warn below
+/// `warn_free_mb`.
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::cloud::CloudFolder;
+
+const MB: u64 = 1024 * 1024;
+
+fn default_min_free_mb() -> u64 {
+    512
+}
+
+fn default_warn_free_mb() -> u64 {
+    2048
+}
+
+fn default_max_upload_mb() -> u64 {
+    10240
+}
+
+/// `[clouds.disk_space]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiskSpaceConfig {
+    /// Uploads are refused when less would be left, 0 to never refuse
+    #[serde(default = "default_min_free_mb")]
+    pub min_free_mb: u64,
+    /// Warn when there is less than this
+    #[serde(default = "default_warn_free_mb")]
+    pub warn_free_mb: u64,
+    /// Larger uploads are refused, 0 for no limit
+    #[serde(default = "default_max_upload_mb")]
+    pub max_upload_mb: u64,
+}
+
+impl Default for DiskSpaceConfig {
+    fn default() -> Self {
+        Self {
+            min_free_mb: default_min_free_mb(),
+            warn_free_mb: default_warn_free_mb(),
+            max_upload_mb: default_max_upload_mb(),
+        }
+    }
+}
+
+impl DiskSpaceConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Problems with the thresholds, as `field: message`
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.warn_free_mb < self.min_free_mb {
+            problems.push("warn_free_mb: must not be below min_free_mb".to_string());
+        }
+        problems
+    }
+
+    /// The largest upload taken, None for no limit
+    pub fn max_upload_bytes(&self) -> Option<u64> {
+        (self.max_upload_mb > 0).then(|| self.max_upload_mb * MB)
+    }
+
+    pub fn level(&self, free_bytes: u64) -> SpaceLevel {
+        if free_bytes < self.min_free_mb * MB {
+            SpaceLevel::Critical
+        } else if free_bytes < self.warn_free_mb * MB {
+            SpaceLevel::Low
+        } else {
+            SpaceLevel::Ok
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpaceLevel {
+    Ok,
+    /// Below `warn_free_mb`
+    Low,
+    /// Below `min_free_mb`, uploads are refused
+    Critical,
+}
+
+/// Free space of a cloud folder's filesystem
+#[derive(Debug, Clone, Serialize)]
+pub struct FolderSpace {
+    pub folder: String,
+    pub path: PathBuf,
+    pub free_bytes: u64,
+    pub total_bytes: u64,
+    pub level: SpaceLevel,
+}
+
+/// Free space of every folder; folders whose filesystem can't be asked are left out
+pub fn folder_space(config: &DiskSpaceConfig, folders: &[CloudFolder]) -> Vec<FolderSpace> {
+    folders
+        .iter()
+        .filter_map(|folder| {
+            let free_bytes = fs2::available_space(&folder.folder_path).ok()?;
+            let total_bytes = fs2::total_space(&folder.folder_path).ok()?;
+            Some(FolderSpace {
+                folder: folder.name.clone(),
+                path: folder.folder_path.clone(),
+                free_bytes,
+                total_bytes,
+                level: config.level(free_bytes),
+            })
+        })
+        .collect()
+}
+
+/// Refuse an upload of `size` bytes (when known) that would leave the folder's
+/// filesystem below `min_free_mb`
+pub fn check_upload(
+    config: &DiskSpaceConfig,
+    folder: &CloudFolder,
+    size: Option<u64>,
+) -> Result<(), String> {
+    if config.min_free_mb == 0 {
+        return Ok(());
+    }
+    // A filesystem that can't be asked isn't a reason to refuse
+    let Ok(free) = fs2::available_space(&folder.folder_path) else {
+        return Ok(());
+    };
+    let minimum = config.min_free_mb * MB;
+    if free.saturating_sub(size.unwrap_or(0)) < minimum {
+        tracing::warn!(
+            free_bytes = free,
+            "Refused an upload to '{}': the disk is almost full",
+            folder.name
+        );
+        return Err(format!(
+            "Not enough disk space: {} MB free, uploads stop below {} MB",
+            free / MB,
+            config.min_free_mb
+        ));
+    }
+    Ok(())
+}
+
+/// Refuse an upload of `size` bytes larger than `max_upload_mb`
+pub fn check_upload_size(config: &DiskSpaceConfig, size: u64) -> Result<(), String> {
+    match config.max_upload_bytes() {
+        Some(maximum) if size > maximum => Err(format!(
+            "The upload is larger than the {} MB limit",
+            config.max_upload_mb
+        )),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,711 @@
+/// Checks of what usually keeps clouds from working or from being reached: the clouds
+/// config, the cloud folders, free ports, the firewall, TLS certificates, the public IP
+/// and the clock. Run by `cloudhost-tui doctor` and the Diagnostics entry of the TUI.
+///
+/// The local checks only look at this machine and are quick. The online ones ask a public
+/// service for the IP address the internet sees, try to connect back to running clouds
+/// through it, and compare the clock with the service's.
+use std::collections::BTreeSet;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Utc};
+use tokio_rustls::rustls::pki_types::{pem::PemObject, CertificateDer};
+
+use crate::clouds_config::CloudsConfig;
+use crate::config_paths::Overrides;
+
+/// Answers with the caller's public IP address as plain text
+const PUBLIC_IP_URL: &str = "https://api.ipify.org";
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(5);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+/// Certificates expiring sooner are reported
+const CERT_WARN_DAYS: i64 = 14;
+/// Clock differences above these break login expiry and signed links
+const CLOCK_WARN_SECS: i64 = 30;
+const CLOCK_ERROR_SECS: i64 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+    Ok,
+    /// Nothing wrong, but worth knowing
+    Info,
+    Warning,
+    Error,
+}
+
+impl Status {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Status::Ok => "✔",
+            Status::Info => "ℹ",
+            Status::Warning => "⚠",
+            Status::Error => "✘",
+        }
+    }
+}
+
+/// One finding, with what to do about it when something is wrong
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    /// What was checked, e.g. "ports"
+    pub area: &'static str,
+    pub status: Status,
+    pub message: String,
+    pub hint: Option<String>,
+}
+
+impl Check {
+    pub fn new(area: &'static str, status: Status, message: impl Into<String>) -> Self {
+        Self {
+            area,
+            status,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    pub fn hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {:<9} {}",
+            self.status.symbol(),
+            self.area,
+            self.message
+        )?;
+        if let Some(hint) = &self.hint {
+            write!(f, "\n  → {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+/// The most severe status of `checks`
+pub fn worst(checks: &[Check]) -> Status {
+    checks
+        .iter()
+        .map(|check| check.status)
+        .max()
+        .unwrap_or(Status::Ok)
+}
+
+pub struct Doctor {
+    config_path: PathBuf,
+    /// Overrides `defaults.base_port` of the config
+    base_port: Option<u16>,
+    /// Ports this process listens on itself, which are taken but not by someone else
+    own_ports: BTreeSet<u16>,
+}
+
+impl Doctor {
+    /// Checks of the clouds config at `config_path`
+    pub fn new(config_path: PathBuf) -> Self {
+        Self {
+            config_path,
+            base_port: None,
+            own_ports: BTreeSet::new(),
+        }
+    }
+
+    /// First port clouds get, when the orchestrator was told another than the config's
+    pub fn with_base_port(mut self, port: u16) -> Self {
+        self.base_port = Some(port);
+        self
+    }
+
+    /// Ports of the clouds and landing page this process runs
+    pub fn with_own_ports(mut self, ports: impl IntoIterator<Item = u16>) -> Self {
+        self.own_ports = ports.into_iter().collect();
+        self
+    }
+
+    /// Everything that can be checked without the internet
+    pub fn local_checks(&self) -> Vec<Check> {
+        let (config_check, config) = self.check_config();
+        let mut checks = vec![config_check];
+        checks.extend(check_overrides());
+        let Some(config) = config else {
+            return checks;
+        };
+        checks.extend(check_folders(&config));
+        checks.extend(self.check_ports(&config));
+        checks.push(self.firewall_hint(&config));
+        checks.extend(check_certificates(&config));
+        checks
+    }
+
+    /// The public IP, whether `running` clouds (name and port) can be reached through it,
+    /// and the clock. Slow, up to a few seconds per cloud.
+    pub async fn online_checks(&self, running: &[(String, u16)]) -> Vec<Check> {
+        let (ip, server_time) = match lookup_public_ip().await {
+            Ok(found) => found,
+            Err(e) => {
+                return vec![Check::new(
+                    "internet",
+                    Status::Warning,
+                    format!("Couldn't look up the public IP address: {}", e),
+                )
+                .hint("Check the internet connection; the clock wasn't checked either")];
+            }
+        };
+        let mut checks = vec![Check::new(
+            "internet",
+            Status::Ok,
+            format!("Public IP address is {}", ip),
+        )];
+        let echo_url = self
+            .check_config()
+            .1
+            .and_then(|config| config.reachability_url);
+        checks.extend(check_reachability(ip, running, echo_url.as_deref()).await);
+        if let Some(server_time) = server_time {
+            checks.push(check_clock(server_time, SystemTime::now()));
+        }
+        checks
+    }
+
+    fn check_config(&self) -> (Check, Option<CloudsConfig>) {
+        let path = self.config_path.display();
+        let content = match std::fs::read_to_string(&self.config_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let check = Check::new(
+                    "config",
+                    Status::Info,
+                    format!("No clouds config yet at {}", path),
+                )
+                .hint("It is created with the first folder or cloud added in the TUI");
+                return (check, Some(CloudsConfig::default()));
+            }
+            Err(e) => {
+                let check = Check::new(
+                    "config",
+                    Status::Error,
+                    format!("Can't read {}: {}", path, e),
+                )
+                .hint("Give the user running CloudHost access to the config directory");
+                return (check, None);
+            }
+        };
+        match CloudsConfig::parse(&content) {
+            Ok(config) => {
+                let check = Check::new(
+                    "config",
+                    Status::Ok,
+                    format!(
+                        "{} is valid: {} cloud(s), {} folder(s)",
+                        path,
+                        config.clouds.len(),
+                        config.cloud_folders.len()
+                    ),
+                );
+                (check, Some(config))
+            }
+            Err(e) => {
+                let check = Check::new("config", Status::Error, e.to_string()).hint(
+                    "Fix the file; until then the last good config (clouds-config.toml.bak) is used",
+                );
+                (check, None)
+            }
+        }
+    }
+
+    /// Ports the clouds would get: one per cloud, plus the ones skipped when taken
+    fn cloud_ports(&self, config: &CloudsConfig) -> std::ops::Range<u16> {
+        let count = config.clouds.len().max(1) as u16 + config.port_retries;
+        let defaults = config
+            .defaults
+            .with_overrides(&Overrides::from_env().unwrap_or_default());
+        let base_port = self.base_port.unwrap_or(defaults.base_port);
+        base_port..base_port.saturating_add(count)
+    }
+
+    fn check_ports(&self, config: &CloudsConfig) -> Vec<Check> {
+        let mut checks = Vec::new();
+        let range = self.cloud_ports(config);
+        let taken: Vec<u16> = range
+            .clone()
+            .filter(|port| !self.own_ports.contains(port) && !port_is_free(*port))
+            .collect();
+        let ours = range
+            .clone()
+            .filter(|port| self.own_ports.contains(port))
+            .count();
+        let free = range.len() - taken.len() - ours;
+        let waiting = config.clouds.len().saturating_sub(ours);
+        let span = format!("{}-{}", range.start, range.end - 1);
+        if free < waiting {
+            checks.push(
+                Check::new(
+                    "ports",
+                    Status::Error,
+                    format!(
+                        "Only {} of ports {} are free, for {} cloud(s) to start",
+                        free, span, waiting
+                    ),
+                )
+                .hint("Stop the programs using them, or raise port_retries in the clouds config"),
+            );
+        } else if !taken.is_empty() {
+            checks.push(
+                Check::new(
+                    "ports",
+                    Status::Warning,
+                    format!("Port(s) {} are used by other programs", join(&taken)),
+                )
+                .hint("Clouds skip them for the next free port, so their URLs move"),
+            );
+        } else {
+            checks.push(Check::new(
+                "ports",
+                Status::Ok,
+                format!("Ports {} are free", span),
+            ));
+        }
+
+        if let Some(port) = config.landing_port {
+            if !self.own_ports.contains(&port) && !port_is_free(port) {
+                checks.push(
+                    Check::new(
+                        "ports",
+                        Status::Error,
+                        format!("landing_port {} is used by another program", port),
+                    )
+                    .hint("Pick another landing_port in the clouds config"),
+                );
+            }
+        }
+        for cloud in &config.clouds {
+            let listeners = [
+                ("FTP", "ftp", cloud.ftp.as_ref().map(|ftp| ftp.port)),
+                ("SFTP", "sftp", cloud.sftp.as_ref().map(|sftp| sftp.port)),
+            ];
+            for (protocol, section, port) in listeners {
+                let Some(port) = port else {
+                    continue;
+                };
+                if !self.own_ports.contains(&port) && !port_is_free(port) {
+                    checks.push(
+                        Check::new(
+                            "ports",
+                            Status::Error,
+                            format!(
+                                "{} port {} of '{}' is used by another program",
+                                protocol, port, cloud.name
+                            ),
+                        )
+                        .hint(format!("Pick another port under [clouds.{}]", section)),
+                    );
+                }
+            }
+        }
+        checks
+    }
+
+    /// How to let other devices in, for the firewall this system has
+    fn firewall_hint(&self, config: &CloudsConfig) -> Check {
+        let range = self.cloud_ports(config);
+        let (first, last) = (range.start, range.end - 1);
+        let check = Check::new(
+            "firewall",
+            Status::Info,
+            format!(
+                "Other devices reach the clouds on TCP ports {}-{}, if the firewall lets them",
+                first, last
+            ),
+        );
+        match firewall_command(first, last) {
+            Some(command) => check.hint(command),
+            None => check,
+        }
+    }
+}
+
+/// How to let TCP ports `first` to `last` through the firewall of this system
+fn firewall_command(first: u16, last: u16) -> Option<String> {
+    if cfg!(target_os = "windows") {
+        Some(format!(
+            "netsh advfirewall firewall add rule name=CloudHost dir=in action=allow protocol=TCP localport={}-{}",
+            first, last
+        ))
+    } else if cfg!(target_os = "macos") {
+        Some(
+            "Allow incoming connections for cloudhost-tui in System Settings > Network > Firewall"
+                .to_string(),
+        )
+    } else if find_program("ufw") {
+        Some(format!("sudo ufw allow {}:{}/tcp", first, last))
+    } else if find_program("firewall-cmd") {
+        Some(format!(
+            "sudo firewall-cmd --permanent --add-port={}-{}/tcp && sudo firewall-cmd --reload",
+            first, last
+        ))
+    } else {
+        None
+    }
+}
+
+/// The server defaults set by environment variables, when any are
+fn check_overrides() -> Option<Check> {
+    match Overrides::from_env() {
+        Ok(overrides) if overrides == Overrides::default() => None,
+        Ok(overrides) => Some(Check::new(
+            "overrides",
+            Status::Info,
+            format!(
+                "Set over the config by flags or the environment: {}",
+                overrides.describe().join(", ")
+            ),
+        )),
+        Err(e) => Some(
+            Check::new("overrides", Status::Error, e)
+                .hint("Fix the variable or unset it to use the config's value"),
+        ),
+    }
+}
+
+fn check_folders(config: &CloudsConfig) -> Vec<Check> {
+    if config.cloud_folders.is_empty() {
+        return vec![Check::new("folders", Status::Info, "No cloud folders yet")];
+    }
+    config
+        .cloud_folders
+        .iter()
+        .map(|folder| {
+            let path = &folder.folder_path;
+            let label = format!("'{}' ({})", folder.name, path.display());
+            match std::fs::metadata(path) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    Check::new("folders", Status::Error, format!("{} doesn't exist", label))
+                        .hint("Create it, or change its folder_path in the clouds config")
+                }
+                Err(e) => Check::new(
+                    "folders",
+                    Status::Error,
+                    format!("{} can't be accessed: {}", label, e),
+                )
+                .hint("Give the user running CloudHost access to it"),
+                Ok(metadata) if !metadata.is_dir() => Check::new(
+                    "folders",
+                    Status::Error,
+                    format!("{} is not a directory", label),
+                )
+                .hint("Point its folder_path at a directory"),
+                Ok(metadata) => match std::fs::read_dir(path) {
+                    Err(e) => Check::new(
+                        "folders",
+                        Status::Error,
+                        format!("{} can't be listed: {}", label, e),
+                    )
+                    .hint("Give the user running CloudHost read access to it"),
+                    Ok(_) if metadata.permissions().readonly() => Check::new(
+                        "folders",
+                        Status::Warning,
+                        format!("{} is read-only, uploads into it will fail", label),
+                    )
+                    .hint(
+                        "Give the user running CloudHost write access, if it should take uploads",
+                    ),
+                    Ok(_) => Check::new("folders", Status::Ok, format!("{} is readable", label)),
+                },
+            }
+        })
+        .collect()
+}
+
+/// Expiry of the FTPS certificates
+fn check_certificates(config: &CloudsConfig) -> Vec<Check> {
+    config
+        .clouds
+        .iter()
+        .filter_map(|cloud| {
+            let path = cloud.ftp.as_ref()?.tls_cert.as_ref()?;
+            let what = format!("FTPS certificate of '{}'", cloud.name);
+            Some(match certificate_expiry(path) {
+                Ok(expiry) => expiry_check(&what, expiry, Utc::now()),
+                Err(e) => Check::new(
+                    "tls",
+                    Status::Error,
+                    format!("{} ({}) can't be read: {}", what, path.display(), e),
+                )
+                .hint("Point tls_cert at a PEM certificate chain"),
+            })
+        })
+        .collect()
+}
+
+/// When the first certificate of the PEM chain at `path` expires
+pub fn certificate_expiry(path: &Path) -> Result<DateTime<Utc>, String> {
+    let der = CertificateDer::pem_file_iter(path)
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or("no certificate in the file")?
+        .map_err(|e| e.to_string())?;
+    let (_, certificate) = x509_parser::parse_x509_certificate(&der).map_err(|e| e.to_string())?;
+    let not_after = certificate.validity().not_after.timestamp();
+    DateTime::from_timestamp(not_after, 0).ok_or_else(|| "invalid expiry date".to_string())
+}
+
+/// How soon `expiry` is, seen at `now`
+pub fn expiry_check(what: &str, expiry: DateTime<Utc>, now: DateTime<Utc>) -> Check {
+    let date = expiry.format("%Y-%m-%d");
+    let days = (expiry - now).num_days();
+    if expiry <= now {
+        Check::new(
+            "tls",
+            Status::Error,
+            format!("{} expired on {}", what, date),
+        )
+        .hint("Renew it; clients refuse expired certificates")
+    } else if days < CERT_WARN_DAYS {
+        Check::new(
+            "tls",
+            Status::Warning,
+            format!("{} expires in {} day(s), on {}", what, days, date),
+        )
+        .hint("Renew it soon")
+    } else {
+        Check::new(
+            "tls",
+            Status::Ok,
+            format!("{} is valid until {}", what, date),
+        )
+    }
+}
+
+/// The difference between this machine's clock and `reference`
+pub fn check_clock(reference: SystemTime, now: SystemTime) -> Check {
+    let skew = match now.duration_since(reference) {
+        Ok(ahead) => ahead.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    };
+    let direction = if skew > 0 { "ahead" } else { "behind" };
+    if skew.abs() > CLOCK_ERROR_SECS {
+        Check::new(
+            "clock",
+            Status::Error,
+            format!("The clock is {}s {}", skew.abs(), direction),
+        )
+        .hint("Turn on time synchronization (NTP); logins and links expire at the wrong time")
+    } else if skew.abs() > CLOCK_WARN_SECS {
+        Check::new(
+            "clock",
+            Status::Warning,
+            format!("The clock is {}s {}", skew.abs(), direction),
+        )
+        .hint("Turn on time synchronization (NTP)")
+    } else {
+        Check::new(
+            "clock",
+            Status::Ok,
+            format!("The clock is within {}s of the internet's", CLOCK_WARN_SECS),
+        )
+    }
+}
+
+/// The public IP, and the time of the service that told it
+async fn lookup_public_ip() -> Result<(IpAddr, Option<SystemTime>), String> {
+    let client = reqwest::Client::builder()
+        .timeout(LOOKUP_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = client
+        .get(PUBLIC_IP_URL)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| e.to_string())?;
+    let server_time = response
+        .headers()
+        .get(reqwest::header::DATE)
+        .and_then(|date| date.to_str().ok())
+        .and_then(|date| httpdate::parse_http_date(date).ok());
+    let body = response.text().await.map_err(|e| e.to_string())?;
+    let ip = body
+        .trim()
+        .parse()
+        .map_err(|_| format!("unexpected answer '{}'", body.trim()))?;
+    Ok((ip, server_time))
+}
+
+async fn check_reachability(
+    ip: IpAddr,
+    running: &[(String, u16)],
+    echo_url: Option<&str>,
+) -> Vec<Check> {
+    if running.is_empty() {
+        return vec![Check::new(
+            "internet",
+            Status::Info,
+            "No cloud is running here, so none could be reached from the internet",
+        )
+        .hint("Start one and run the Diagnostics of the TUI's Settings tab")];
+    }
+    let mut checks = Vec::new();
+    for (cloud, port) in running {
+        checks.push(probe(ip, cloud, *port, echo_url).await);
+    }
+    checks
+}
+
+/// Whether `cloud`, running on `port`, can be reached from outside the LAN: `echo_url`
+/// (see `CloudsConfig::reachability_url`) is asked to connect back when given, otherwise
+/// this machine connects to its own public IP. Takes a few seconds.
+pub async fn reachability_test(cloud: &str, port: u16, echo_url: Option<&str>) -> Check {
+    match lookup_public_ip().await {
+        Ok((ip, _)) => probe(ip, cloud, port, echo_url).await,
+        Err(e) => Check::new(
+            "internet",
+            Status::Warning,
+            format!("Couldn't look up the public IP address: {}", e),
+        )
+        .hint("Check the internet connection"),
+    }
+}
+
+async fn probe(ip: IpAddr, cloud: &str, port: u16, echo_url: Option<&str>) -> Check {
+    let address = SocketAddr::new(ip, port);
+    let reachable = match echo_url {
+        Some(url) => match ask_echo_service(url, ip, port).await {
+            Ok(reachable) => reachable,
+            Err(e) => {
+                return Check::new(
+                    "internet",
+                    Status::Warning,
+                    format!("Couldn't ask {} about '{}': {}", url, cloud, e),
+                )
+                .hint("Check reachability_url in the clouds config")
+            }
+        },
+        None => tokio::time::timeout(CONNECT_TIMEOUT, tokio::net::TcpStream::connect(address))
+            .await
+            .is_ok_and(|result| result.is_ok()),
+    };
+    if reachable {
+        Check::new(
+            "internet",
+            Status::Ok,
+            format!("'{}' is reachable at http://{}", cloud, address),
+        )
+    } else {
+        unreachable_check(cloud, address, local_address(ip), echo_url.is_some())
+    }
+}
+
+/// Asks the service at `url` whether it can connect to `ip`:`port`
+async fn ask_echo_service(url: &str, ip: IpAddr, port: u16) -> Result<bool, String> {
+    let url = url
+        .replace("{ip}", &ip.to_string())
+        .replace("{port}", &port.to_string());
+    let client = reqwest::Client::builder()
+        .timeout(LOOKUP_TIMEOUT + CONNECT_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = client.get(&url).send().await.map_err(|e| e.to_string())?;
+    Ok(response.status().is_success())
+}
+
+/// Why `cloud` at the public `address` couldn't be reached, judging from the `local`
+/// address this machine goes out to the internet from. `from_outside` tells whether an
+/// echo service tried, rather than this machine through the router.
+pub fn unreachable_check(
+    cloud: &str,
+    address: SocketAddr,
+    local: Option<IpAddr>,
+    from_outside: bool,
+) -> Check {
+    let port = address.port();
+    let message = format!("'{}' isn't reachable at {}", cloud, address);
+    let check = match local {
+        Some(local) if local == address.ip() => Check::new(
+            "internet",
+            Status::Warning,
+            format!(
+                "{}: this machine has the public address itself, so a firewall blocks port {}",
+                message, port
+            ),
+        )
+        .hint(firewall_command(port, port).unwrap_or_else(|| {
+            format!("Allow incoming TCP connections on port {} in the firewall", port)
+        })),
+        Some(local) if is_shared_address(local) => Check::new(
+            "internet",
+            Status::Warning,
+            format!(
+                "{}: the internet provider shares the public address ({} is behind carrier-grade NAT)",
+                message, local
+            ),
+        )
+        .hint("Port forwarding can't work here; use a tunnel (see Internet Access in the README) or ask the provider for a public IPv4 address"),
+        Some(local) => Check::new(
+            "internet",
+            Status::Warning,
+            format!("{}: this machine ({}) is behind a router (NAT)", message, local),
+        )
+        .hint(format!(
+            "Forward TCP port {} on the router to {}:{} and allow it in the firewall, or use a tunnel (see Internet Access in the README)",
+            port, local, port
+        )),
+        None => Check::new("internet", Status::Warning, message).hint(format!(
+            "Forward port {} on the router to this machine, or use a tunnel (see Internet Access in the README)",
+            port
+        )),
+    };
+    if from_outside {
+        check
+    } else {
+        let hint = check.hint.clone().unwrap_or_default();
+        check.hint(format!(
+            "{}. Some routers can't connect to their own public IP, so set reachability_url in the clouds config or try from another network",
+            hint
+        ))
+    }
+}
+
+/// The address this machine sends from to reach `remote`; nothing is sent
+fn local_address(remote: IpAddr) -> Option<IpAddr> {
+    let unspecified: IpAddr = match remote {
+        IpAddr::V4(_) => std::net::Ipv4Addr::UNSPECIFIED.into(),
+        IpAddr::V6(_) => std::net::Ipv6Addr::UNSPECIFIED.into(),
+    };
+    let socket = std::net::UdpSocket::bind((unspecified, 0)).ok()?;
+    socket.connect((remote, 80)).ok()?;
+    socket.local_addr().ok().map(|address| address.ip())
+}
+
+/// 100.64.0.0/10, which providers use for carrier-grade NAT
+fn is_shared_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            a == 100 && (64..128).contains(&b)
+        }
+        IpAddr::V6(_) => false,
+    }
+}
+
+fn port_is_free(port: u16) -> bool {
+    TcpListener::bind(("0.0.0.0", port)).is_ok()
+}
+
+fn find_program(name: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(name).is_file()))
+}
+
+fn join(ports: &[u16]) -> String {
+    ports
+        .iter()
+        .map(|port| port.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
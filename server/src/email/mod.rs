@@ -0,0 +1,313 @@
+/// Optional email notifications of a cloud, sent over SMTP on the events the cloud
+/// chooses: a burst of failed logins, a file request running out of room, a failed
+/// backup or the first visit of a file request link. Subjects and bodies come from
+/// templates with `{cloud}`, `{event}`, `{details}` and `{time}` placeholders.
+///
+/// Sending happens in the background; a failure is logged and doesn't affect the
+/// request that caused the event.
+mod smtp;
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::{ServerError, ServerResult};
+
+/// Events a cloud can be notified about, `[clouds.email] events = [...]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyEvent {
+    /// `failed_login_burst` failed logins within `failed_login_window_minutes`
+    FailedLogins,
+    /// A file request took its last file
+    QuotaExceeded,
+    /// A scheduled `backup` task failed
+    BackupFailed,
+    /// A file request link was opened for the first time
+    ShareAccessed,
+}
+
+impl NotifyEvent {
+    pub const ALL: [NotifyEvent; 4] = [
+        Self::FailedLogins,
+        Self::QuotaExceeded,
+        Self::BackupFailed,
+        Self::ShareAccessed,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::FailedLogins => "failed_logins",
+            Self::QuotaExceeded => "quota_exceeded",
+            Self::BackupFailed => "backup_failed",
+            Self::ShareAccessed => "share_accessed",
+        }
+    }
+
+    fn default_subject(self) -> &'static str {
+        match self {
+            Self::FailedLogins => "[{cloud}] Failed logins",
+            Self::QuotaExceeded => "[{cloud}] Quota exceeded",
+            Self::BackupFailed => "[{cloud}] Backup failed",
+            Self::ShareAccessed => "[{cloud}] Share opened",
+        }
+    }
+}
+
+impl fmt::Display for NotifyEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+const DEFAULT_BODY: &str =
+    "{details}\n\nCloud: {cloud}\nEvent: {event}\nTime: {time}\n\n-- \nSent by CloudHost\n";
+
+/// How the connection to the SMTP server is secured
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Plain connection upgraded with STARTTLS, usually port 587
+    #[default]
+    StartTls,
+    /// TLS from the start, usually port 465
+    Tls,
+    /// No encryption, for a relay on the same machine
+    None,
+}
+
+impl SmtpSecurity {
+    fn default_port(self) -> u16 {
+        match self {
+            Self::StartTls => 587,
+            Self::Tls => 465,
+            Self::None => 25,
+        }
+    }
+}
+
+/// Subject and body of one event's emails, the defaults where unset
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmailTemplate {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+}
+
+fn default_events() -> Vec<NotifyEvent> {
+    NotifyEvent::ALL.to_vec()
+}
+
+fn default_failed_login_burst() -> u32 {
+    5
+}
+
+fn default_failed_login_window_minutes() -> u32 {
+    10
+}
+
+/// `[clouds.email]` in the clouds config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmailConfig {
+    pub smtp_host: String,
+    /// 587, 465 or 25 depending on `security` when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub smtp_port: Option<u16>,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// `me@example.com` or `CloudHost <me@example.com>`
+    pub from: String,
+    pub to: Vec<String>,
+    /// Events that send an email, all of them when unset
+    #[serde(default = "default_events")]
+    pub events: Vec<NotifyEvent>,
+    #[serde(default = "default_failed_login_burst")]
+    pub failed_login_burst: u32,
+    #[serde(default = "default_failed_login_window_minutes")]
+    pub failed_login_window_minutes: u32,
+    /// Per event, e.g. `[clouds.email.templates.failed_logins]`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub templates: BTreeMap<NotifyEvent, EmailTemplate>,
+}
+
+impl EmailConfig {
+    /// Problems with the config, as `field: message`
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.smtp_host.trim().is_empty() {
+            problems.push("smtp_host: must not be empty".to_string());
+        }
+        if smtp::address(&self.from).is_none() {
+            problems.push(format!("from: '{}' is not an email address", self.from));
+        }
+        if self.to.is_empty() {
+            problems.push("to: needs at least one address".to_string());
+        }
+        for to in &self.to {
+            if smtp::address(to).is_none() {
+                problems.push(format!("to: '{}' is not an email address", to));
+            }
+        }
+        if self.username.is_some() != self.password.is_some() {
+            problems.push("password: must be set together with username".to_string());
+        }
+        if self.failed_login_burst == 0 {
+            problems.push("failed_login_burst: must be at least 1".to_string());
+        }
+        if self.failed_login_window_minutes == 0 {
+            problems.push("failed_login_window_minutes: must be at least 1".to_string());
+        }
+        problems
+    }
+
+    fn port(&self) -> u16 {
+        self.smtp_port
+            .unwrap_or_else(|| self.security.default_port())
+    }
+
+    /// Subject and body of an event's email; `None` for a test email
+    fn render(
+        &self,
+        cloud: &str,
+        event: Option<NotifyEvent>,
+        subject: &str,
+        details: &str,
+    ) -> (String, String) {
+        let template = event.and_then(|event| self.templates.get(&event));
+        let event = event.map_or("test", NotifyEvent::as_str);
+        let subject = template
+            .and_then(|t| t.subject.as_deref())
+            .unwrap_or(subject);
+        let body = template
+            .and_then(|t| t.body.as_deref())
+            .unwrap_or(DEFAULT_BODY);
+        let time = chrono::Utc::now()
+            .format("%Y-%m-%d %H:%M:%S UTC")
+            .to_string();
+        let fill = |text: &str| {
+            text.replace("{cloud}", cloud)
+                .replace("{event}", event)
+                .replace("{time}", &time)
+                .replace("{details}", details)
+        };
+        // Subjects are one line
+        (fill(subject).replace(['\r', '\n'], " "), fill(body))
+    }
+}
+
+/// Send a test email with the given config, for the TUI's Settings tab
+pub async fn send_test(config: &EmailConfig, cloud: &str) -> ServerResult<()> {
+    let (subject, body) = config.render(
+        cloud,
+        None,
+        "[{cloud}] Test email",
+        "This is a test email. Notifications of this cloud will arrive like this one.",
+    );
+    smtp::send(config, &subject, &body)
+        .await
+        .map_err(|e| ServerError::Network(format!("Sending email failed: {}", e)))
+}
+
+/// The email notifications of a running cloud
+#[derive(Debug)]
+pub struct EmailNotifier {
+    cloud: String,
+    config: EmailConfig,
+    /// Recent failed logins, for the burst
+    failed_logins: Mutex<VecDeque<Instant>>,
+    /// When the last burst email went out; one per window at most
+    last_burst: Mutex<Option<Instant>>,
+    /// File request links whose first visit was reported
+    accessed_shares: Mutex<HashSet<String>>,
+}
+
+impl EmailNotifier {
+    pub fn new(cloud: &str, config: &EmailConfig) -> Self {
+        Self {
+            cloud: cloud.to_string(),
+            config: config.clone(),
+            failed_logins: Mutex::new(VecDeque::new()),
+            last_burst: Mutex::new(None),
+            accessed_shares: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Send an email about `event` in the background, if the cloud wants to hear about it
+    pub fn notify(&self, event: NotifyEvent, details: String) {
+        if !self.config.events.contains(&event) {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let (subject, body) =
+            self.config
+                .render(&self.cloud, Some(event), event.default_subject(), &details);
+        let config = self.config.clone();
+        runtime.spawn(async move {
+            match smtp::send(&config, &subject, &body).await {
+                Ok(()) => tracing::info!("Sent {} email to {}", event, config.to.join(", ")),
+                Err(e) => tracing::warn!("Failed to send {} email: {}", event, e),
+            }
+        });
+    }
+
+    /// Count a failed login; emails once the burst is reached, then stays quiet for a window
+    pub fn record_failed_login(&self, source: &str) {
+        let window = Duration::from_secs(u64::from(self.config.failed_login_window_minutes) * 60);
+        let now = Instant::now();
+        let count = {
+            let Ok(mut failed_logins) = self.failed_logins.lock() else {
+                return;
+            };
+            failed_logins.push_back(now);
+            while failed_logins
+                .front()
+                .is_some_and(|at| now.duration_since(*at) > window)
+            {
+                failed_logins.pop_front();
+            }
+            failed_logins.len()
+        };
+        if count < self.config.failed_login_burst as usize {
+            return;
+        }
+        {
+            let Ok(mut last_burst) = self.last_burst.lock() else {
+                return;
+            };
+            if last_burst.is_some_and(|at| now.duration_since(at) < window) {
+                return;
+            }
+            *last_burst = Some(now);
+        }
+        self.notify(
+            NotifyEvent::FailedLogins,
+            format!(
+                "{} failed logins in the last {} minutes, the latest from {}.",
+                count, self.config.failed_login_window_minutes, source
+            ),
+        );
+    }
+
+    /// Report the first visit of a file request link since the cloud started
+    pub fn record_share_access(&self, id: &str, name: &str) {
+        let first = self
+            .accessed_shares
+            .lock()
+            .is_ok_and(|mut accessed| accessed.insert(id.to_string()));
+        if first {
+            self.notify(
+                NotifyEvent::ShareAccessed,
+                format!("The file request link '{}' was opened.", name),
+            );
+        }
+    }
+}
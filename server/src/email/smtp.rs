@@ -0,0 +1,214 @@
+/// Just enough SMTP to hand a plain text message to a mail server: EHLO, STARTTLS or
+/// implicit TLS, AUTH PLAIN, MAIL, RCPT and DATA.
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::{self, pki_types::ServerName};
+use tokio_rustls::TlsConnector;
+
+use super::{EmailConfig, SmtpSecurity};
+
+/// Longest a whole delivery may take
+const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The bare address of `name@host` or `Name <name@host>`
+pub(super) fn address(mailbox: &str) -> Option<&str> {
+    let mailbox = mailbox.trim();
+    let address = match (mailbox.rfind('<'), mailbox.ends_with('>')) {
+        (Some(start), true) => &mailbox[start + 1..mailbox.len() - 1],
+        _ => mailbox,
+    };
+    let (local, domain) = address.split_once('@')?;
+    let valid = !local.is_empty()
+        && !domain.is_empty()
+        && !address
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || matches!(c, '<' | '>'));
+    valid.then_some(address)
+}
+
+pub(super) async fn send(config: &EmailConfig, subject: &str, body: &str) -> Result<(), String> {
+    tokio::time::timeout(SEND_TIMEOUT, deliver(config, subject, body))
+        .await
+        .map_err(|_| "the mail server took too long".to_string())?
+}
+
+async fn deliver(config: &EmailConfig, subject: &str, body: &str) -> Result<(), String> {
+    let host = config.smtp_host.trim();
+    let tcp = TcpStream::connect((host, config.port()))
+        .await
+        .map_err(|e| format!("connecting to {}:{}: {}", host, config.port(), e))?;
+
+    match config.security {
+        SmtpSecurity::Tls => {
+            let tls = connect_tls(host, tcp).await?;
+            let mut session = Session::new(tls);
+            session.expect(220).await?;
+            session.ehlo().await?;
+            session.send_message(config, subject, body).await
+        }
+        SmtpSecurity::StartTls => {
+            let mut session = Session::new(tcp);
+            session.expect(220).await?;
+            session.ehlo().await?;
+            session.command("STARTTLS", 220).await?;
+            let tls = connect_tls(host, session.into_inner()).await?;
+            let mut session = Session::new(tls);
+            session.ehlo().await?;
+            session.send_message(config, subject, body).await
+        }
+        SmtpSecurity::None => {
+            let mut session = Session::new(tcp);
+            session.expect(220).await?;
+            session.ehlo().await?;
+            session.send_message(config, subject, body).await
+        }
+    }
+}
+
+async fn connect_tls(
+    host: &str,
+    tcp: TcpStream,
+) -> Result<tokio_rustls::client::TlsStream<TcpStream>, String> {
+    let roots = rustls::RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .map_err(|e| format!("TLS setup: {}", e))?
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+    let name = ServerName::try_from(host.to_string())
+        .map_err(|e| format!("'{}' is not a valid TLS name: {}", host, e))?;
+    TlsConnector::from(Arc::new(config))
+        .connect(name, tcp)
+        .await
+        .map_err(|e| format!("TLS handshake with {}: {}", host, e))
+}
+
+struct Session<S> {
+    stream: BufReader<S>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Session<S> {
+    fn new(stream: S) -> Self {
+        Self {
+            stream: BufReader::new(stream),
+        }
+    }
+
+    fn into_inner(self) -> S {
+        self.stream.into_inner()
+    }
+
+    /// Read a (possibly multi-line) reply and check its code
+    async fn expect(&mut self, code: u16) -> Result<String, String> {
+        let mut reply = String::new();
+        loop {
+            let mut line = String::new();
+            let read = self
+                .stream
+                .read_line(&mut line)
+                .await
+                .map_err(|e| format!("reading from the mail server: {}", e))?;
+            if read == 0 {
+                return Err("the mail server closed the connection".to_string());
+            }
+            reply.push_str(&line);
+            // `250-...` continues, `250 ...` ends the reply
+            if line.as_bytes().get(3) != Some(&b'-') {
+                break;
+            }
+        }
+        let reply = reply.trim_end().to_string();
+        match reply.get(..3).and_then(|code| code.parse::<u16>().ok()) {
+            Some(received) if received == code => Ok(reply),
+            _ => Err(format!("the mail server answered: {}", reply)),
+        }
+    }
+
+    async fn write(&mut self, data: &str) -> Result<(), String> {
+        let stream = self.stream.get_mut();
+        stream
+            .write_all(data.as_bytes())
+            .await
+            .map_err(|e| format!("writing to the mail server: {}", e))?;
+        stream
+            .flush()
+            .await
+            .map_err(|e| format!("writing to the mail server: {}", e))
+    }
+
+    async fn command(&mut self, line: &str, code: u16) -> Result<String, String> {
+        self.write(&format!("{}\r\n", line)).await?;
+        self.expect(code).await
+    }
+
+    async fn ehlo(&mut self) -> Result<String, String> {
+        self.command("EHLO cloudhost", 250).await
+    }
+
+    async fn send_message(
+        &mut self,
+        config: &EmailConfig,
+        subject: &str,
+        body: &str,
+    ) -> Result<(), String> {
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            let credentials = BASE64.encode(format!("\0{}\0{}", username, password));
+            self.command(&format!("AUTH PLAIN {}", credentials), 235)
+                .await
+                .map_err(|e| format!("login failed, {}", e))?;
+        }
+
+        let from = address(&config.from).ok_or("invalid sender address")?;
+        self.command(&format!("MAIL FROM:<{}>", from), 250).await?;
+        for to in &config.to {
+            let to = address(to).ok_or_else(|| format!("invalid recipient '{}'", to))?;
+            self.command(&format!("RCPT TO:<{}>", to), 250).await?;
+        }
+        self.command("DATA", 354).await?;
+        self.write(&message(config, subject, body)).await?;
+        self.expect(250).await?;
+        // The message is accepted, a failing QUIT doesn't matter
+        let _ = self.command("QUIT", 221).await;
+        Ok(())
+    }
+}
+
+/// Headers and body, dot-stuffed and ended with `<CRLF>.<CRLF>`
+fn message(config: &EmailConfig, subject: &str, body: &str) -> String {
+    let domain = address(&config.from)
+        .and_then(|from| from.split_once('@'))
+        .map_or("localhost", |(_, domain)| domain);
+    let subject = if subject.is_ascii() {
+        subject.to_string()
+    } else {
+        format!("=?UTF-8?B?{}?=", BASE64.encode(subject))
+    };
+
+    let mut message = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMessage-ID: <{}@{}>\r\n\
+         MIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\n\
+         Content-Transfer-Encoding: 8bit\r\n\r\n",
+        config.from.trim(),
+        config.to.join(", "),
+        subject,
+        chrono::Utc::now().to_rfc2822(),
+        uuid::Uuid::new_v4().simple(),
+        domain
+    );
+    for line in body.lines() {
+        if line.starts_with('.') {
+            message.push('.');
+        }
+        message.push_str(line);
+        message.push_str("\r\n");
+    }
+    message.push_str(".\r\n");
+    message
+}
@@ -3,7 +3,7 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
-use serde_json::json;
+use serde_json::{Map, Value};
 use std::fmt;
 
 /// Server error types
@@ -20,6 +20,10 @@ pub enum ServerError {
     ServerNotRunning,
     ServerError(String),
     InvalidPath(String),
+    /// The config file changed on disk since it was loaded
+    ConfigConflict(String),
+    /// Another program listens on the port a cloud was to use
+    PortInUse(u16),
 }
 
 impl fmt::Display for ServerError {
@@ -36,50 +40,224 @@ impl fmt::Display for ServerError {
             ServerError::ServerNotRunning => write!(f, "Server not running"),
             ServerError::ServerError(msg) => write!(f, "Server error: {}", msg),
             ServerError::InvalidPath(msg) => write!(f, "Invalid path: {}", msg),
+            ServerError::ConfigConflict(msg) => write!(f, "Config conflict: {}", msg),
+            ServerError::PortInUse(port) => write!(f, "Port {} is already in use", port),
         }
     }
 }
 
 impl std::error::Error for ServerError {}
 
-/// Convert to HTTP response
-impl IntoResponse for ServerError {
-    fn into_response(self) -> Response {
-        let (status, error_code, message) = match self {
-            ServerError::Authentication(msg) => (StatusCode::UNAUTHORIZED, "UNAUTHORIZED", msg),
-            ServerError::Configuration(msg) => {
-                (StatusCode::INTERNAL_SERVER_ERROR, "CONFIG_ERROR", msg)
+/// What an API error is about, for clients to act on without parsing the message. Each
+/// code answers with its own status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// A value in the request is missing or invalid
+    Validation,
+    /// A path names something that can't be in a cloud folder, like `..`
+    InvalidPath,
+    /// The server isn't running, for requests that need it to be
+    ServerNotRunning,
+    /// No credentials, or ones that aren't valid anymore
+    Unauthorized,
+    WrongPassword,
+    Forbidden,
+    /// A path leads out of its cloud folder through a symlink
+    OutsideCloudFolder,
+    /// The API token's scope doesn't allow the request
+    TokenOutOfScope,
+    /// The CSRF token of a cookie-authenticated request is missing or wrong
+    CsrfToken,
+    NotFound,
+    CloudFolderNotFound,
+    Conflict,
+    /// A file of that name is in the way
+    FileExists,
+    ServerAlreadyRunning,
+    PortInUse,
+    /// The config changed on disk since it was loaded
+    ConfigConflict,
+    /// Something that existed and won't again, like a closed file request
+    Gone,
+    PreconditionFailed,
+    PayloadTooLarge,
+    UnsupportedMediaType,
+    RangeNotSatisfiable,
+    TooManyRequests,
+    Internal,
+    FileSystem,
+    Configuration,
+    /// A server this one depends on failed
+    Network,
+    Unavailable,
+    InsufficientStorage,
+}
+
+impl ErrorCode {
+    /// As it's sent in the `code` of the body
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::Validation => "VALIDATION_ERROR",
+            ErrorCode::InvalidPath => "INVALID_PATH",
+            ErrorCode::ServerNotRunning => "SERVER_NOT_RUNNING",
+            ErrorCode::Unauthorized => "UNAUTHORIZED",
+            ErrorCode::WrongPassword => "WRONG_PASSWORD",
+            ErrorCode::Forbidden => "FORBIDDEN",
+            ErrorCode::OutsideCloudFolder => "OUTSIDE_CLOUD_FOLDER",
+            ErrorCode::TokenOutOfScope => "TOKEN_OUT_OF_SCOPE",
+            ErrorCode::CsrfToken => "CSRF_TOKEN_INVALID",
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::CloudFolderNotFound => "CLOUD_FOLDER_NOT_FOUND",
+            ErrorCode::Conflict => "CONFLICT",
+            ErrorCode::FileExists => "FILE_EXISTS",
+            ErrorCode::ServerAlreadyRunning => "SERVER_RUNNING",
+            ErrorCode::PortInUse => "PORT_IN_USE",
+            ErrorCode::ConfigConflict => "CONFIG_CONFLICT",
+            ErrorCode::Gone => "GONE",
+            ErrorCode::PreconditionFailed => "PRECONDITION_FAILED",
+            ErrorCode::PayloadTooLarge => "PAYLOAD_TOO_LARGE",
+            ErrorCode::UnsupportedMediaType => "UNSUPPORTED_MEDIA_TYPE",
+            ErrorCode::RangeNotSatisfiable => "RANGE_NOT_SATISFIABLE",
+            ErrorCode::TooManyRequests => "TOO_MANY_REQUESTS",
+            ErrorCode::Internal => "INTERNAL_ERROR",
+            ErrorCode::FileSystem => "FILE_ERROR",
+            ErrorCode::Configuration => "CONFIG_ERROR",
+            ErrorCode::Network => "NETWORK_ERROR",
+            ErrorCode::Unavailable => "UNAVAILABLE",
+            ErrorCode::InsufficientStorage => "INSUFFICIENT_STORAGE",
+        }
+    }
+
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::Validation | ErrorCode::InvalidPath | ErrorCode::ServerNotRunning => {
+                StatusCode::BAD_REQUEST
             }
-            ServerError::FileSystem(msg) => (StatusCode::INTERNAL_SERVER_ERROR, "FILE_ERROR", msg),
-            ServerError::Network(msg) => (StatusCode::BAD_GATEWAY, "NETWORK_ERROR", msg),
-            ServerError::Internal(msg) => {
-                (StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR", msg)
+            ErrorCode::Unauthorized | ErrorCode::WrongPassword => StatusCode::UNAUTHORIZED,
+            ErrorCode::Forbidden
+            | ErrorCode::OutsideCloudFolder
+            | ErrorCode::TokenOutOfScope
+            | ErrorCode::CsrfToken => StatusCode::FORBIDDEN,
+            ErrorCode::NotFound | ErrorCode::CloudFolderNotFound => StatusCode::NOT_FOUND,
+            ErrorCode::Conflict
+            | ErrorCode::FileExists
+            | ErrorCode::ServerAlreadyRunning
+            | ErrorCode::PortInUse
+            | ErrorCode::ConfigConflict => StatusCode::CONFLICT,
+            ErrorCode::Gone => StatusCode::GONE,
+            ErrorCode::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+            ErrorCode::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorCode::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ErrorCode::RangeNotSatisfiable => StatusCode::RANGE_NOT_SATISFIABLE,
+            ErrorCode::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::Internal | ErrorCode::FileSystem | ErrorCode::Configuration => {
+                StatusCode::INTERNAL_SERVER_ERROR
             }
-            ServerError::Validation(msg) => (StatusCode::BAD_REQUEST, "VALIDATION_ERROR", msg),
-            ServerError::CloudFolder(msg) => (StatusCode::NOT_FOUND, "CLOUD_FOLDER_ERROR", msg),
-            ServerError::ServerAlreadyRunning => (
-                StatusCode::CONFLICT,
-                "SERVER_RUNNING",
-                "Server already running".to_string(),
-            ),
-            ServerError::ServerNotRunning => (
-                StatusCode::BAD_REQUEST,
-                "SERVER_NOT_RUNNING",
-                "Server not running".to_string(),
-            ),
-            ServerError::ServerError(msg) => {
-                (StatusCode::INTERNAL_SERVER_ERROR, "SERVER_ERROR", msg)
+            ErrorCode::Network => StatusCode::BAD_GATEWAY,
+            ErrorCode::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::InsufficientStorage => StatusCode::INSUFFICIENT_STORAGE,
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An error answered by the JSON API. Every endpoint answers errors with the same body:
+/// `{"error": "<message for people>", "code": "<ErrorCode>"}`, plus any details, like the
+/// `retry_after` of a lockout.
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub details: Map<String, Value>,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: Map::new(),
+        }
+    }
+
+    /// Add `key` to the body
+    pub fn with(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.details.insert(key.to_string(), value.into());
+        self
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.code.status()
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Validation, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Forbidden, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let mut body = self.details;
+        body.insert("error".to_string(), Value::String(self.message));
+        body.insert(
+            "code".to_string(),
+            Value::String(self.code.as_str().to_string()),
+        );
+        (self.code.status(), Json(Value::Object(body))).into_response()
+    }
+}
+
+impl From<ServerError> for ApiError {
+    fn from(err: ServerError) -> Self {
+        let message = err.to_string();
+        let (code, message) = match err {
+            ServerError::Authentication(msg) => (ErrorCode::Unauthorized, msg),
+            ServerError::Configuration(msg) => (ErrorCode::Configuration, msg),
+            ServerError::FileSystem(msg) => (ErrorCode::FileSystem, msg),
+            ServerError::Network(msg) => (ErrorCode::Network, msg),
+            ServerError::Internal(msg) | ServerError::ServerError(msg) => {
+                (ErrorCode::Internal, msg)
             }
-            ServerError::InvalidPath(msg) => (StatusCode::BAD_REQUEST, "INVALID_PATH", msg),
+            ServerError::Validation(msg) => (ErrorCode::Validation, msg),
+            ServerError::CloudFolder(msg) => (ErrorCode::CloudFolderNotFound, msg),
+            ServerError::InvalidPath(msg) => (ErrorCode::InvalidPath, msg),
+            ServerError::ConfigConflict(msg) => (ErrorCode::ConfigConflict, msg),
+            ServerError::ServerAlreadyRunning => (ErrorCode::ServerAlreadyRunning, message),
+            ServerError::ServerNotRunning => (ErrorCode::ServerNotRunning, message),
+            ServerError::PortInUse(_) => (ErrorCode::PortInUse, message),
         };
+        Self::new(code, message)
+    }
+}
 
-        let body = Json(json!({
-            "error": error_code,
-            "message": message,
-            "timestamp": chrono::Utc::now()
-        }));
-
-        (status, body).into_response()
+impl IntoResponse for ServerError {
+    fn into_response(self) -> Response {
+        ApiError::from(self).into_response()
     }
 }
 
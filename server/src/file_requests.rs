@@ -0,0 +1,214 @@
+/// File requests: links that let anyone upload files into one directory of a cloud,
+/// without logging in and without seeing what's there. Each has optional limits on
+/// file size, number of files and lifetime.
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+
+/// `[[clouds.file_requests]]` in the clouds config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileRequest {
+    /// Secret part of the link, `/drop/<id>`
+    pub id: String,
+    /// Shown on the upload page, e.g. "Photos from the wedding"
+    pub name: String,
+    pub cloud_folder: String,
+    /// Directory inside the cloud folder, `""` for its root
+    #[serde(default)]
+    pub path: String,
+    /// Largest accepted file in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_file_size: Option<u64>,
+    /// How many files the link accepts in total
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_files: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// Files received so far
+    #[serde(default)]
+    pub uploads: u32,
+}
+
+/// Why a file request doesn't take uploads anymore
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Closed {
+    Expired,
+    Full,
+}
+
+impl Closed {
+    pub fn message(self) -> &'static str {
+        match self {
+            Closed::Expired => "This link has expired",
+            Closed::Full => "This link doesn't accept any more files",
+        }
+    }
+}
+
+impl FileRequest {
+    pub fn new(name: &str, cloud_folder: &str, path: &str) -> Self {
+        let mut id = [0u8; 16];
+        OsRng.fill_bytes(&mut id);
+        Self {
+            id: id.iter().map(|byte| format!("{:02x}", byte)).collect(),
+            name: name.trim().to_string(),
+            cloud_folder: cloud_folder.to_string(),
+            path: path.trim_matches('/').to_string(),
+            max_file_size: None,
+            max_files: None,
+            expires_at: None,
+            created_at: chrono::Utc::now(),
+            uploads: 0,
+        }
+    }
+
+    /// Whether the link still takes files
+    pub fn check_open(&self) -> Result<(), Closed> {
+        if self
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= chrono::Utc::now())
+        {
+            return Err(Closed::Expired);
+        }
+        if self.max_files.is_some_and(|max| self.uploads >= max) {
+            return Err(Closed::Full);
+        }
+        Ok(())
+    }
+
+    /// Files the link still accepts, `None` without a limit
+    pub fn remaining(&self) -> Option<u32> {
+        self.max_files.map(|max| max.saturating_sub(self.uploads))
+    }
+
+    /// Problems with a configured request, as `field: message`
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.id.len() < 16 || !self.id.bytes().all(|byte| byte.is_ascii_alphanumeric()) {
+            problems.push("id: must be at least 16 letters and digits".to_string());
+        }
+        if self.name.trim().is_empty() {
+            problems.push("name: must not be empty".to_string());
+        }
+        if self.cloud_folder.is_empty() {
+            problems.push("cloud_folder: must not be empty".to_string());
+        }
+        if self
+            .path
+            .split('/')
+            .any(|part| part == ".." || part.contains('\\'))
+        {
+            problems.push(format!("path: '{}' must stay inside the folder", self.path));
+        }
+        if self.max_files == Some(0) {
+            problems.push("max_files: must be at least 1".to_string());
+        }
+        if self.max_file_size == Some(0) {
+            problems.push("max_file_size: must be at least 1".to_string());
+        }
+        problems
+    }
+}
+
+/// The file requests of a running cloud. Uploads count against the limits as they
+/// happen; the orchestrator saves the counts and the requests created or removed
+/// through the API, picking them up with `take_changes`.
+#[derive(Debug, Default)]
+pub struct FileRequests {
+    requests: Mutex<Vec<FileRequest>>,
+    changed: Mutex<bool>,
+}
+
+impl FileRequests {
+    pub fn new(requests: Vec<FileRequest>) -> Self {
+        Self {
+            requests: Mutex::new(requests),
+            changed: Mutex::new(false),
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<FileRequest> {
+        self.requests
+            .lock()
+            .ok()?
+            .iter()
+            .find(|request| request.id == id)
+            .cloned()
+    }
+
+    pub fn list(&self) -> Vec<FileRequest> {
+        self.requests
+            .lock()
+            .map(|requests| requests.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn add(&self, request: FileRequest) {
+        if let Ok(mut requests) = self.requests.lock() {
+            requests.push(request);
+        }
+        self.mark_changed();
+    }
+
+    /// Remove a request by id; false if there's none
+    pub fn remove(&self, id: &str) -> bool {
+        let removed = self.requests.lock().is_ok_and(|mut requests| {
+            let before = requests.len();
+            requests.retain(|request| request.id != id);
+            requests.len() != before
+        });
+        if removed {
+            self.mark_changed();
+        }
+        removed
+    }
+
+    /// Count a file against the request before it's stored, so concurrent uploads
+    /// can't go over `max_files`. Returns the request as it was before.
+    pub fn reserve(&self, id: &str) -> Option<Result<FileRequest, Closed>> {
+        let mut requests = self.requests.lock().ok()?;
+        let request = requests.iter_mut().find(|request| request.id == id)?;
+        if let Err(closed) = request.check_open() {
+            return Some(Err(closed));
+        }
+        let before = request.clone();
+        request.uploads += 1;
+        drop(requests);
+        self.mark_changed();
+        Some(Ok(before))
+    }
+
+    /// Give back a reservation of a file that wasn't stored
+    pub fn release(&self, id: &str) {
+        if let Ok(mut requests) = self.requests.lock() {
+            if let Some(request) = requests.iter_mut().find(|request| request.id == id) {
+                request.uploads = request.uploads.saturating_sub(1);
+            }
+        }
+    }
+
+    /// Replace the requests with the saved ones, after they were changed outside the server
+    pub fn replace(&self, new_requests: Vec<FileRequest>) {
+        if let Ok(mut requests) = self.requests.lock() {
+            *requests = new_requests;
+        }
+    }
+
+    /// All requests, if they changed since the last call
+    pub fn take_changes(&self) -> Option<Vec<FileRequest>> {
+        let mut changed = self.changed.lock().ok()?;
+        if !*changed {
+            return None;
+        }
+        *changed = false;
+        Some(self.list())
+    }
+
+    fn mark_changed(&self) {
+        if let Ok(mut changed) = self.changed.lock() {
+            *changed = true;
+        }
+    }
+}
@@ -0,0 +1,139 @@
+/// Whether the cloud folders are still there. External drives get unplugged, and network
+/// shares and mounts go away, while clouds serve them: the orchestrator checks every
+/// folder's path regularly, requests to a folder that is gone answer 503 until it is back,
+/// and the changes are logged and sent to webhooks.
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::cloud::CloudFolder;
+
+/// How often the callers of `Orchestrator::check_folder_health` should call it
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A folder that went away or came back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FolderChange {
+    pub folder: String,
+    pub path: PathBuf,
+    /// Why the folder is unavailable, None when it is back
+    pub problem: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct FolderState {
+    path: PathBuf,
+    /// A drive was mounted on the folder when last seen. Unmounting it leaves an empty
+    /// directory behind, which would otherwise look fine.
+    mount_point: bool,
+    problem: Option<String>,
+}
+
+/// What was last found about each cloud folder, by name
+#[derive(Debug, Default)]
+pub struct FolderHealth {
+    folders: Mutex<HashMap<String, FolderState>>,
+}
+
+impl FolderHealth {
+    /// Check `folders`, forgetting any others; returns the folders that went away or came
+    /// back since the last check
+    pub fn check(&self, folders: &[CloudFolder]) -> Vec<FolderChange> {
+        let Ok(mut states) = self.folders.lock() else {
+            return Vec::new();
+        };
+        states.retain(|name, _| folders.iter().any(|folder| folder.name == *name));
+
+        let mut changes = Vec::new();
+        for folder in folders {
+            let state = states.entry(folder.name.clone()).or_default();
+            // A folder given another path starts over
+            if state.path != folder.folder_path {
+                state.path = folder.folder_path.clone();
+                state.mount_point = false;
+            }
+            let problem = match check_path(&folder.folder_path, state.mount_point) {
+                Ok(mount_point) => {
+                    state.mount_point = mount_point;
+                    None
+                }
+                Err(problem) => Some(problem),
+            };
+            if problem != state.problem {
+                changes.push(FolderChange {
+                    folder: folder.name.clone(),
+                    path: folder.folder_path.clone(),
+                    problem: problem.clone(),
+                });
+                state.problem = problem;
+            }
+        }
+        changes
+    }
+
+    /// Why the folder `name` is unavailable, None when it was fine or never checked
+    pub fn problem(&self, name: &str) -> Option<String> {
+        self.folders
+            .lock()
+            .ok()?
+            .get(name)
+            .and_then(|state| state.problem.clone())
+    }
+
+    /// What requests to the folder `name` are refused with while it is unavailable
+    pub fn ensure_available(&self, name: &str) -> Result<(), String> {
+        match self.problem(name) {
+            Some(problem) => Err(format!(
+                "Cloud folder '{}' is unavailable: {}",
+                name, problem
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Check that `path` is a directory that can be listed, and still a mount point when it
+/// `was_mount_point`. Returns whether it is one, where that can be told.
+pub fn check_path(path: &Path, was_mount_point: bool) -> Result<bool, String> {
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(format!(
+                "{} doesn't exist; is its drive connected?",
+                path.display()
+            ))
+        }
+        Err(e) => return Err(format!("{} can't be read: {}", path.display(), e)),
+    };
+    if !metadata.is_dir() {
+        return Err(format!("{} is not a directory", path.display()));
+    }
+    if let Err(e) = std::fs::read_dir(path) {
+        return Err(format!("{} can't be listed: {}", path.display(), e));
+    }
+
+    let mount_point = is_mount_point(path, &metadata);
+    if was_mount_point && !mount_point {
+        return Err(format!(
+            "{} is no longer a mounted drive; is it unmounted?",
+            path.display()
+        ));
+    }
+    Ok(mount_point)
+}
+
+/// Whether `path` is on another filesystem than its parent directory
+#[cfg(unix)]
+fn is_mount_point(path: &Path, metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    let parent = path
+        .parent()
+        .and_then(|parent| std::fs::metadata(parent).ok());
+    parent.is_some_and(|parent| parent.dev() != metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn is_mount_point(_path: &Path, _metadata: &std::fs::Metadata) -> bool {
+    false
+}
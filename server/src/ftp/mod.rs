@@ -0,0 +1,169 @@
+/// Optional FTP(S) listener of a cloud, for scanners and cameras that can only upload
+/// over FTP. The cloud folders are the top-level directories and clients log in with the
+/// cloud's password (the user name is not checked). With a certificate configured,
+/// clients can switch to TLS with `AUTH TLS` (explicit FTPS); `require_tls` refuses
+/// logins over plain FTP.
+///
+/// Uploads follow the cloud's `upload_conflict` policy like HTTP uploads do, and deletes
+/// go to the OS trash where the HTTP API uses it.
+mod session;
+
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tokio_rustls::rustls::{
+    self,
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+};
+use tokio_rustls::TlsAcceptor;
+use tracing::Instrument;
+
+use crate::auth::AuthState;
+use crate::cloud::Cloud;
+use crate::error::{ServerError, ServerResult};
+use crate::folder_health::FolderHealth;
+use crate::request_id;
+
+/// `[clouds.ftp]` in the clouds config; the listener runs while the cloud does
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FtpConfig {
+    /// Port of the control connection
+    pub port: u16,
+    /// Ports for passive data connections, e.g. `[50000, 50100]` to open in a firewall.
+    /// Any free port when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passive_ports: Option<[u16; 2]>,
+    /// Address announced for passive data connections when clients come through NAT
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_address: Option<Ipv4Addr>,
+    /// PEM certificate chain for FTPS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_cert: Option<PathBuf>,
+    /// PEM private key for FTPS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_key: Option<PathBuf>,
+    /// Refuse logins until the client switched to TLS
+    #[serde(default)]
+    pub require_tls: bool,
+}
+
+impl FtpConfig {
+    /// Problems with the settings, as `field: message`
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.port == 0 {
+            problems.push("port: must not be 0".to_string());
+        }
+        if let Some([first, last]) = self.passive_ports {
+            if first == 0 || first > last {
+                problems.push(format!(
+                    "passive_ports: [{}, {}] is not a range of ports",
+                    first, last
+                ));
+            }
+        }
+        if self.tls_cert.is_some() != self.tls_key.is_some() {
+            problems.push("tls_cert and tls_key must be set together".to_string());
+        }
+        if self.require_tls && self.tls_cert.is_none() {
+            problems.push("require_tls: needs tls_cert and tls_key".to_string());
+        }
+        problems
+    }
+}
+
+/// What every session of a cloud's listener shares
+pub(crate) struct FtpContext {
+    pub config: FtpConfig,
+    /// Latest version of the cloud, which sessions take before each command
+    pub cloud: watch::Receiver<Arc<Cloud>>,
+    pub auth_state: Arc<AuthState>,
+    /// Folders whose path went away are refused with 450 until they are back
+    pub folder_health: Arc<FolderHealth>,
+    pub tls: Option<TlsAcceptor>,
+}
+
+/// A bound FTP listener, ready to serve
+pub struct FtpListener {
+    listener: TcpListener,
+    context: Arc<FtpContext>,
+}
+
+impl FtpListener {
+    /// Load the certificate and bind the control port, so configuration mistakes
+    /// stop the cloud from starting instead of failing later
+    pub async fn bind(
+        config: &FtpConfig,
+        address: IpAddr,
+        cloud: watch::Receiver<Arc<Cloud>>,
+        auth_state: Arc<AuthState>,
+        folder_health: Arc<FolderHealth>,
+    ) -> ServerResult<Self> {
+        let tls = match (&config.tls_cert, &config.tls_key) {
+            (Some(cert), Some(key)) => Some(load_tls(cert, key)?),
+            _ => None,
+        };
+        let addr = SocketAddr::new(address, config.port);
+        let listener = TcpListener::bind(addr).await.map_err(|e| {
+            ServerError::Network(format!("Failed to bind FTP port {}: {}", config.port, e))
+        })?;
+        Ok(Self {
+            listener,
+            context: Arc::new(FtpContext {
+                config: config.clone(),
+                cloud,
+                auth_state,
+                folder_health,
+                tls,
+            }),
+        })
+    }
+
+    /// Accept clients until `stopped` changes or its sender is dropped
+    pub async fn run(self, mut stopped: watch::Receiver<()>) {
+        tracing::info!(
+            "FTP{} listening on port {}",
+            if self.context.tls.is_some() { "S" } else { "" },
+            self.context.config.port
+        );
+        loop {
+            tokio::select! {
+                accepted = self.listener.accept() => match accepted {
+                    Ok((stream, peer)) => {
+                        let span = tracing::info_span!(
+                            "request",
+                            request_id = %request_id::generate(),
+                            method = "FTP"
+                        );
+                        let session = session::Session::new(self.context.clone(), peer);
+                        tokio::spawn(session.run(stream, stopped.clone()).instrument(span));
+                    }
+                    Err(e) => tracing::warn!("FTP accept failed: {}", e),
+                },
+                _ = stopped.changed() => break,
+            }
+        }
+        tracing::info!("FTP listener on port {} stopped", self.context.config.port);
+    }
+}
+
+fn load_tls(cert: &Path, key: &Path) -> ServerResult<TlsAcceptor> {
+    let tls_error = |what: &str, e: &dyn std::fmt::Display| {
+        ServerError::Configuration(format!("FTPS {}: {}", what, e))
+    };
+    let certs = CertificateDer::pem_file_iter(cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| tls_error(&format!("certificate '{}'", cert.display()), &e))?;
+    let key = PrivateKeyDer::from_pem_file(key)
+        .map_err(|e| tls_error(&format!("key '{}'", key.display()), &e))?;
+    let config = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+    .map_err(|e| tls_error("setup", &e))?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
@@ -0,0 +1,1195 @@
+/// One FTP control connection: login, navigation and the data transfers it starts
+use axum::http::HeaderMap;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::io;
+use std::io::SeekFrom;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::fs;
+use tokio::io::{
+    AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+use super::FtpContext;
+use crate::api_routes::delete::delete_file;
+use crate::api_routes::upload::{place_upload, OnConflict};
+use crate::auth_log::AuthFailure;
+use crate::cloud::{Cloud, CloudFolder};
+use crate::disk_space;
+use crate::error::ErrorCode;
+use crate::hooks::{self, HookFile};
+use crate::login_limiter::FailureOutcome;
+use crate::utils::virtual_path::{check_inside, locate, Location, VirtualPath};
+use crate::utils::{entry_name, is_upload_temp, set_file_mtime, upload_temp_path};
+use crate::webhooks::WebhookEvent;
+
+const IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+/// How long a client has to open the data connection
+const DATA_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_LINE_BYTES: u64 = 4096;
+/// Failed passwords before the connection is closed
+const MAX_LOGIN_ATTEMPTS: u32 = 3;
+const FAILED_LOGIN_DELAY: Duration = Duration::from_secs(1);
+
+/// A data connection, plain or TLS
+trait DataStream: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> DataStream for T {}
+
+type Reply = (u16, String);
+
+fn reply(code: u16, text: impl Into<String>) -> Reply {
+    (code, text.into())
+}
+
+/// A password refused because the address is locked out
+fn locked_out(lockout: Duration) -> Reply {
+    let seconds = lockout.as_secs().max(1);
+    reply(
+        530,
+        format!("Too many failed logins, try again in {} seconds", seconds),
+    )
+}
+
+/// Multi-line replies are written by the command itself
+const ALREADY_SENT: u16 = 0;
+
+fn already_sent() -> Reply {
+    (ALREADY_SENT, String::new())
+}
+
+/// How the command loop ended
+enum Flow {
+    Quit,
+    StartTls,
+}
+
+/// Where the next data connection comes from
+enum DataChannel {
+    None,
+    Passive(TcpListener),
+    Active(SocketAddr),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ListFormat {
+    /// `ls -l` lines for LIST
+    Long,
+    /// Names only for NLST
+    Names,
+    /// Machine-readable facts for MLSD
+    Facts,
+}
+
+struct ListEntry {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+pub struct Session {
+    context: Arc<FtpContext>,
+    /// The cloud as of the current command
+    cloud: Arc<Cloud>,
+    peer: SocketAddr,
+    local: Option<SocketAddr>,
+    user: Option<String>,
+    logged_in: bool,
+    failed_logins: u32,
+    tls: bool,
+    protect_data: bool,
+    cwd: VirtualPath,
+    data: DataChannel,
+    rename_from: Option<VirtualPath>,
+    /// Offset from REST for the next RETR or STOR
+    restart_at: u64,
+}
+
+impl Session {
+    pub(crate) fn new(context: Arc<FtpContext>, peer: SocketAddr) -> Self {
+        let cloud = context.cloud.borrow().clone();
+        Self {
+            context,
+            cloud,
+            peer,
+            local: None,
+            user: None,
+            logged_in: false,
+            failed_logins: 0,
+            tls: false,
+            protect_data: false,
+            cwd: VirtualPath::default(),
+            data: DataChannel::None,
+            rename_from: None,
+            restart_at: 0,
+        }
+    }
+
+    pub async fn run(mut self, stream: TcpStream, mut stopped: watch::Receiver<()>) {
+        self.local = stream.local_addr().ok();
+        tracing::info!("FTP connection from {}", self.peer);
+
+        let mut control = BufReader::new(stream);
+        let flow = tokio::select! {
+            flow = self.serve(&mut control) => flow,
+            _ = stopped.changed() => return,
+        };
+        if let (Ok(Flow::StartTls), Some(acceptor)) = (flow, self.context.tls.clone()) {
+            match acceptor.accept(control.into_inner()).await {
+                Ok(stream) => {
+                    self.tls = true;
+                    let mut control = BufReader::new(stream);
+                    tokio::select! {
+                        _ = self.serve(&mut control) => {}
+                        _ = stopped.changed() => {}
+                    }
+                }
+                Err(e) => tracing::debug!("FTPS handshake with {} failed: {}", self.peer, e),
+            }
+        }
+        tracing::debug!("FTP connection from {} closed", self.peer);
+    }
+
+    async fn serve<S>(&mut self, control: &mut BufReader<S>) -> io::Result<Flow>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        if !self.tls {
+            send(control, reply(220, "CloudHost FTP ready")).await?;
+        }
+        loop {
+            let mut line = String::new();
+            let read = tokio::time::timeout(
+                IDLE_TIMEOUT,
+                (&mut *control).take(MAX_LINE_BYTES).read_line(&mut line),
+            )
+            .await;
+            match read {
+                Err(_) => {
+                    send(control, reply(421, "Idle timeout, closing connection")).await?;
+                    return Ok(Flow::Quit);
+                }
+                Ok(Ok(0)) => return Ok(Flow::Quit),
+                Ok(Ok(_)) if !line.ends_with('\n') => {
+                    send(control, reply(500, "Line too long")).await?;
+                    return Ok(Flow::Quit);
+                }
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => return Err(e),
+            }
+
+            let line = line.trim_end_matches(['\r', '\n']);
+            let (command, arg) = line.split_once(' ').unwrap_or((line, ""));
+            let command = command.to_ascii_uppercase();
+            if command == "PASS" {
+                tracing::debug!("PASS ****");
+            } else {
+                tracing::debug!("{} {}", command, arg);
+            }
+
+            let flow = match command.as_str() {
+                "QUIT" => Some(Flow::Quit),
+                "AUTH" if self.context.tls.is_some() && !self.tls => {
+                    if arg.eq_ignore_ascii_case("TLS") || arg.eq_ignore_ascii_case("SSL") {
+                        Some(Flow::StartTls)
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            };
+            match flow {
+                Some(Flow::Quit) => {
+                    send(control, reply(221, "Goodbye")).await?;
+                    return Ok(Flow::Quit);
+                }
+                Some(Flow::StartTls) => {
+                    send(control, reply(234, "Starting TLS")).await?;
+                    return Ok(Flow::StartTls);
+                }
+                None => {}
+            }
+
+            self.cloud = self.context.cloud.borrow().clone();
+            let answer = self.handle(control, &command, arg).await?;
+            send(control, answer).await?;
+            if self.failed_logins >= MAX_LOGIN_ATTEMPTS {
+                send(control, reply(421, "Too many failed logins")).await?;
+                return Ok(Flow::Quit);
+            }
+        }
+    }
+
+    /// Run one command. Transfers send their preliminary reply on `control` themselves;
+    /// the returned reply is the final one.
+    async fn handle<C>(&mut self, control: &mut C, command: &str, arg: &str) -> io::Result<Reply>
+    where
+        C: AsyncWrite + Unpin,
+    {
+        let config = &self.context.config;
+        let answer = match command {
+            "NOOP" => reply(200, "OK"),
+            "SYST" => reply(215, "UNIX Type: L8"),
+            "FEAT" => {
+                let mut features = vec![
+                    "EPSV",
+                    "MDTM",
+                    "MFMT",
+                    "REST STREAM",
+                    "SIZE",
+                    "UTF8",
+                    "MLST type*;size*;modify*;",
+                ];
+                if self.context.tls.is_some() {
+                    features.extend(["AUTH TLS", "PBSZ", "PROT"]);
+                }
+                let lines: Vec<String> = features.iter().map(|f| format!(" {}", f)).collect();
+                send_multiline(control, 211, "Features:", &lines, "End").await?;
+                already_sent()
+            }
+            "OPTS" if arg.eq_ignore_ascii_case("UTF8 ON") => reply(200, "UTF8 is always on"),
+            "OPTS" => reply(501, "Option not supported"),
+            "AUTH" if self.tls => reply(503, "Already using TLS"),
+            "AUTH" if self.context.tls.is_none() => reply(502, "TLS is not configured"),
+            "AUTH" => reply(504, "Only AUTH TLS is supported"),
+            "PBSZ" if self.tls => reply(200, "PBSZ=0"),
+            "PROT" if !self.tls => reply(503, "Use AUTH TLS first"),
+            "PROT" if arg.eq_ignore_ascii_case("P") => {
+                self.protect_data = true;
+                reply(200, "Data connections are protected")
+            }
+            "PROT" if arg.eq_ignore_ascii_case("C") && !config.require_tls => {
+                self.protect_data = false;
+                reply(200, "Data connections are clear")
+            }
+            "PROT" if arg.eq_ignore_ascii_case("C") => {
+                reply(536, "Data connections must be protected")
+            }
+            "PROT" => reply(504, "Unknown protection level"),
+            "PBSZ" => reply(503, "Use AUTH TLS first"),
+            "USER" if config.require_tls && !self.tls => {
+                reply(530, "Use AUTH TLS before logging in")
+            }
+            "USER" => {
+                self.user = Some(arg.to_string());
+                self.logged_in = false;
+                reply(331, "Password required")
+            }
+            "PASS" => self.login(arg).await,
+            _ if !self.logged_in => reply(530, "Not logged in"),
+            _ => self.handle_logged_in(control, command, arg).await?,
+        };
+        Ok(answer)
+    }
+
+    async fn login(&mut self, password: &str) -> Reply {
+        let Some(user) = &self.user else {
+            return reply(503, "Send USER first");
+        };
+        // The same lockout as the login page, so FTP is no way around it
+        let limiter = &self.context.auth_state.login_limiter;
+        let address = self.peer.ip();
+        if let Some(lockout) = limiter.locked_for(address) {
+            return locked_out(lockout);
+        }
+        if self.context.auth_state.verify_password(password) {
+            limiter.record_success(address);
+            self.logged_in = true;
+            tracing::info!("FTP login from {} as '{}'", self.peer, user);
+            return reply(230, "Logged in");
+        }
+
+        self.failed_logins += 1;
+        self.context.auth_state.record_failure(&AuthFailure::new(
+            address,
+            &HeaderMap::new(),
+            &self.cloud.name,
+            "ftp_bad_password",
+        ));
+        let outcome = limiter.record_failure(address);
+        // Slows down guessing without holding anyone else up
+        tokio::time::sleep(FAILED_LOGIN_DELAY).await;
+        match outcome {
+            FailureOutcome::LockedOut(lockout) => locked_out(lockout),
+            FailureOutcome::AttemptsLeft(_) => reply(530, "Login incorrect"),
+        }
+    }
+
+    async fn handle_logged_in<C>(
+        &mut self,
+        control: &mut C,
+        command: &str,
+        arg: &str,
+    ) -> io::Result<Reply>
+    where
+        C: AsyncWrite + Unpin,
+    {
+        let answer = match command {
+            "PWD" | "XPWD" => reply(
+                257,
+                format!(
+                    "\"{}\" is the current directory",
+                    quote(&self.cwd.display())
+                ),
+            ),
+            "CWD" | "XCWD" => self.change_directory(arg).await,
+            "CDUP" | "XCUP" => {
+                self.cwd = self.cwd.parent();
+                reply(250, format!("Directory is now {}", self.cwd.display()))
+            }
+            "TYPE" => match arg.to_ascii_uppercase().as_str() {
+                // Files are always sent as they are
+                "I" | "L 8" | "A" | "A N" => reply(200, "Type set"),
+                _ => reply(504, "Type not supported"),
+            },
+            "MODE" if arg.eq_ignore_ascii_case("S") => reply(200, "Mode set"),
+            "MODE" => reply(504, "Only stream mode is supported"),
+            "STRU" if arg.eq_ignore_ascii_case("F") => reply(200, "Structure set"),
+            "STRU" => reply(504, "Only file structure is supported"),
+            "PASV" => self.passive(false).await,
+            "EPSV" => self.passive(true).await,
+            "PORT" => self.active(parse_port(arg)),
+            "EPRT" => self.active(parse_eprt(arg)),
+            "LIST" => self.send_listing(control, arg, ListFormat::Long).await?,
+            "NLST" => self.send_listing(control, arg, ListFormat::Names).await?,
+            "MLSD" => self.send_listing(control, arg, ListFormat::Facts).await?,
+            "MLST" => match self.entry_facts(arg).await {
+                Ok(facts) => {
+                    send_multiline(control, 250, "Listing", &[facts], "End").await?;
+                    already_sent()
+                }
+                Err(answer) => answer,
+            },
+            "REST" => match arg.parse() {
+                Ok(offset) => {
+                    self.restart_at = offset;
+                    reply(350, format!("Restarting at {}", offset))
+                }
+                Err(_) => reply(501, "Expected REST <byte offset>"),
+            },
+            // A restart offset applies to the next transfer only
+            "RETR" => {
+                let restart_at = std::mem::take(&mut self.restart_at);
+                self.retrieve(control, arg, restart_at).await?
+            }
+            "STOR" => {
+                let restart_at = std::mem::take(&mut self.restart_at);
+                self.store(control, arg, false, restart_at).await?
+            }
+            "APPE" => {
+                self.restart_at = 0;
+                self.store(control, arg, true, 0).await?
+            }
+            "DELE" => self.delete(arg).await,
+            "RMD" | "XRMD" => self.remove_directory(arg).await,
+            "MKD" | "XMKD" => self.make_directory(arg).await,
+            "RNFR" => self.rename_from(arg).await,
+            "RNTO" => self.rename_to(arg).await,
+            "SIZE" => match self.existing_file(arg).await {
+                Ok((_, metadata)) => reply(213, metadata.len().to_string()),
+                Err(answer) => answer,
+            },
+            "MDTM" => match self.existing_file(arg).await {
+                Ok((_, metadata)) => match metadata.modified() {
+                    Ok(modified) => reply(213, fact_time(modified)),
+                    Err(_) => reply(550, "Modification time not available"),
+                },
+                Err(answer) => answer,
+            },
+            "MFMT" => self.set_modified(arg).await,
+            _ => reply(502, "Command not implemented"),
+        };
+        Ok(answer)
+    }
+
+    // ========== Navigation ==========
+
+    /// The path `arg` names, refused with 450 while its cloud folder is unavailable
+    fn resolve(&self, arg: &str) -> Result<VirtualPath, Reply> {
+        let path = self
+            .cwd
+            .resolve(arg)
+            .map_err(|e| reply(553, format!("Invalid path: {}", e)))?;
+        if let Some(folder) = path.segments().first() {
+            self.context
+                .folder_health
+                .ensure_available(folder)
+                .map_err(|e| reply(450, e))?;
+        }
+        Ok(path)
+    }
+
+    async fn change_directory(&mut self, arg: &str) -> Reply {
+        let path = match self.resolve(arg) {
+            Ok(path) => path,
+            Err(answer) => return answer,
+        };
+        match locate(&self.cloud, &path) {
+            Ok(Location::Root) => {}
+            Ok(Location::InFolder {
+                folder, path: real, ..
+            }) => {
+                if !fs::metadata(&real).await.is_ok_and(|m| m.is_dir()) {
+                    return reply(550, "No such directory");
+                }
+                if let Err(e) = check_inside(folder, &real).await {
+                    return reply(550, e);
+                }
+            }
+            Err(e) => return reply(550, e),
+        }
+        self.cwd = path;
+        reply(250, format!("Directory is now {}", self.cwd.display()))
+    }
+
+    /// An existing file inside a cloud folder
+    async fn existing_file(
+        &self,
+        arg: &str,
+    ) -> Result<(std::path::PathBuf, std::fs::Metadata), Reply> {
+        let path = self.resolve(arg)?;
+        let Ok(Location::InFolder {
+            folder, path: real, ..
+        }) = locate(&self.cloud, &path)
+        else {
+            return Err(reply(550, "No such file"));
+        };
+        let metadata = match fs::metadata(&real).await {
+            Ok(metadata) if metadata.is_file() => metadata,
+            _ => return Err(reply(550, "No such file")),
+        };
+        check_inside(folder, &real)
+            .await
+            .map_err(|e| reply(550, e))?;
+        Ok((real, metadata))
+    }
+
+    /// The cloud folder `path` is in, and the logged in user, for the folder hooks
+    fn hook_context(&self, path: &VirtualPath) -> Option<(&CloudFolder, &str)> {
+        match locate(&self.cloud, path) {
+            Ok(Location::InFolder { folder, .. }) => Some((folder, self.user.as_deref()?)),
+            _ => None,
+        }
+    }
+
+    /// Where a new entry named by `arg` goes: inside a cloud folder, in an existing directory
+    async fn new_entry(&self, arg: &str) -> Result<(VirtualPath, std::path::PathBuf), Reply> {
+        let path = self.resolve(arg)?;
+        match locate(&self.cloud, &path) {
+            Ok(Location::InFolder {
+                folder,
+                is_folder_root: false,
+                path: real,
+            }) => {
+                let parent = real.parent().unwrap_or(&real);
+                if !fs::metadata(parent).await.is_ok_and(|m| m.is_dir()) {
+                    return Err(reply(550, "No such directory"));
+                }
+                check_inside(folder, parent)
+                    .await
+                    .map_err(|e| reply(550, e))?;
+                Ok((path, real))
+            }
+            Ok(_) => Err(reply(550, "Files go inside a cloud folder")),
+            Err(e) => Err(reply(550, e)),
+        }
+    }
+
+    // ========== Data connections ==========
+
+    async fn passive(&mut self, extended: bool) -> Reply {
+        let local_ip = self
+            .local
+            .map(|addr| addr.ip())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        let listener = match self.context.config.passive_ports {
+            Some([first, last]) => {
+                let mut bound = None;
+                for port in first..=last {
+                    if let Ok(listener) = TcpListener::bind((local_ip, port)).await {
+                        bound = Some(listener);
+                        break;
+                    }
+                }
+                bound
+            }
+            None => TcpListener::bind((local_ip, 0)).await.ok(),
+        };
+        let Some(port) = listener
+            .as_ref()
+            .and_then(|listener| listener.local_addr().ok())
+            .map(|addr| addr.port())
+        else {
+            return reply(425, "No free passive port");
+        };
+        self.data = DataChannel::Passive(listener.expect("bound listener"));
+
+        if extended {
+            return reply(
+                229,
+                format!("Entering Extended Passive Mode (|||{}|)", port),
+            );
+        }
+        let announced = self.context.config.public_address.or(match local_ip {
+            IpAddr::V4(ip) => Some(ip),
+            IpAddr::V6(ip) => ip.to_ipv4_mapped(),
+        });
+        let Some(ip) = announced else {
+            self.data = DataChannel::None;
+            return reply(425, "Use EPSV over IPv6");
+        };
+        let [a, b, c, d] = ip.octets();
+        reply(
+            227,
+            format!(
+                "Entering Passive Mode ({},{},{},{},{},{})",
+                a,
+                b,
+                c,
+                d,
+                port >> 8,
+                port & 0xff
+            ),
+        )
+    }
+
+    /// Data connections only go back to the client, so the server can't be used
+    /// to reach other hosts (FTP bounce)
+    fn active(&mut self, target: Option<SocketAddr>) -> Reply {
+        match target {
+            Some(addr) if addr.ip() == self.peer.ip() && addr.port() >= 1024 => {
+                self.data = DataChannel::Active(addr);
+                reply(200, "Active mode set")
+            }
+            Some(_) => reply(
+                504,
+                "Data connections only go to the client, port 1024 and up",
+            ),
+            None => reply(501, "Invalid address"),
+        }
+    }
+
+    async fn open_data(&mut self) -> Result<Box<dyn DataStream>, String> {
+        let stream = match std::mem::replace(&mut self.data, DataChannel::None) {
+            DataChannel::Passive(listener) => {
+                let (stream, addr) = tokio::time::timeout(DATA_TIMEOUT, listener.accept())
+                    .await
+                    .map_err(|_| "Timed out waiting for the data connection".to_string())?
+                    .map_err(|e| e.to_string())?;
+                if addr.ip() != self.peer.ip() {
+                    return Err("Data connection came from another address".to_string());
+                }
+                stream
+            }
+            DataChannel::Active(addr) => {
+                tokio::time::timeout(DATA_TIMEOUT, TcpStream::connect(addr))
+                    .await
+                    .map_err(|_| "Timed out opening the data connection".to_string())?
+                    .map_err(|e| e.to_string())?
+            }
+            DataChannel::None => return Err("Use PASV, EPSV or PORT first".to_string()),
+        };
+
+        match (&self.context.tls, self.protect_data) {
+            (Some(acceptor), true) => {
+                let stream = acceptor
+                    .accept(stream)
+                    .await
+                    .map_err(|e| format!("TLS on the data connection failed: {}", e))?;
+                Ok(Box::new(stream))
+            }
+            _ => Ok(Box::new(stream)),
+        }
+    }
+
+    // ========== Listings ==========
+
+    async fn list_entries(&self, arg: &str) -> Result<Vec<ListEntry>, Reply> {
+        // `LIST -la` and the like: options aren't supported, hidden files are always shown
+        let arg = arg
+            .split(' ')
+            .filter(|part| !part.starts_with('-'))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let path = self.resolve(&arg)?;
+        let (folder, real) = match locate(&self.cloud, &path) {
+            Ok(Location::Root) => {
+                let mut entries = Vec::new();
+                for folder in &self.cloud.cloud_folders {
+                    let metadata = fs::metadata(&folder.folder_path).await.ok();
+                    entries.push(ListEntry {
+                        name: folder.name.clone(),
+                        is_dir: true,
+                        size: 0,
+                        modified: metadata.and_then(|m| m.modified().ok()),
+                    });
+                }
+                return Ok(entries);
+            }
+            Ok(Location::InFolder {
+                folder, path: real, ..
+            }) => (folder, real),
+            Err(e) => return Err(reply(550, e)),
+        };
+        let metadata = fs::metadata(&real)
+            .await
+            .map_err(|_| reply(550, "No such file or directory"))?;
+        check_inside(folder, &real)
+            .await
+            .map_err(|e| reply(550, e))?;
+        if metadata.is_file() {
+            return Ok(vec![list_entry(
+                path.name().unwrap_or_default().to_string(),
+                &metadata,
+            )]);
+        }
+
+        let mut entries = Vec::new();
+        let mut dir = fs::read_dir(&real)
+            .await
+            .map_err(|e| reply(550, format!("Can't read directory: {}", e)))?;
+        while let Ok(Some(entry)) = dir.next_entry().await {
+            let Some(name) = entry_name(entry.file_name()) else {
+                continue;
+            };
+            if is_upload_temp(&name) {
+                continue;
+            }
+            if let Ok(metadata) = fs::metadata(entry.path()).await {
+                entries.push(list_entry(name, &metadata));
+            }
+        }
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
+
+    async fn send_listing<C>(
+        &mut self,
+        control: &mut C,
+        arg: &str,
+        format: ListFormat,
+    ) -> io::Result<Reply>
+    where
+        C: AsyncWrite + Unpin,
+    {
+        let entries = match self.list_entries(arg).await {
+            Ok(entries) => entries,
+            Err(answer) => return Ok(answer),
+        };
+        let now = SystemTime::now();
+        let listing: String = entries
+            .iter()
+            .map(|entry| match format {
+                ListFormat::Long => long_line(entry, now),
+                ListFormat::Names => format!("{}\r\n", entry.name),
+                ListFormat::Facts => format!("{} {}\r\n", facts(entry), entry.name),
+            })
+            .collect();
+
+        send(control, reply(150, "Sending directory listing")).await?;
+        let mut data = match self.open_data().await {
+            Ok(data) => data,
+            Err(e) => return Ok(reply(425, e)),
+        };
+        let sent = async {
+            data.write_all(listing.as_bytes()).await?;
+            data.shutdown().await
+        }
+        .await;
+        Ok(match sent {
+            Ok(()) => reply(226, "Listing sent"),
+            Err(e) => reply(426, format!("Transfer aborted: {}", e)),
+        })
+    }
+
+    async fn entry_facts(&self, arg: &str) -> Result<String, Reply> {
+        let path = self.resolve(arg)?;
+        let entry = match locate(&self.cloud, &path) {
+            Ok(Location::Root) => ListEntry {
+                name: "/".to_string(),
+                is_dir: true,
+                size: 0,
+                modified: None,
+            },
+            Ok(Location::InFolder {
+                folder, path: real, ..
+            }) => {
+                let metadata = fs::metadata(&real)
+                    .await
+                    .map_err(|_| reply(550, "No such file or directory"))?;
+                check_inside(folder, &real)
+                    .await
+                    .map_err(|e| reply(550, e))?;
+                list_entry(path.display(), &metadata)
+            }
+            Err(e) => return Err(reply(550, e)),
+        };
+        Ok(format!(" {} {}", facts(&entry), path.display()))
+    }
+
+    // ========== Transfers ==========
+
+    /// RETR, from `restart_at` on to resume an interrupted download
+    async fn retrieve<C>(
+        &mut self,
+        control: &mut C,
+        arg: &str,
+        restart_at: u64,
+    ) -> io::Result<Reply>
+    where
+        C: AsyncWrite + Unpin,
+    {
+        let (real, metadata) = match self.existing_file(arg).await {
+            Ok(found) => found,
+            Err(answer) => return Ok(answer),
+        };
+        if restart_at > metadata.len() {
+            return Ok(reply(554, "Restart offset is past the end of the file"));
+        }
+        let mut file = match fs::File::open(&real).await {
+            Ok(file) => file,
+            Err(e) => return Ok(reply(550, format!("Can't open file: {}", e))),
+        };
+        if let Err(e) = file.seek(SeekFrom::Start(restart_at)).await {
+            return Ok(reply(550, format!("Can't open file: {}", e)));
+        }
+
+        send(
+            control,
+            reply(
+                150,
+                format!("Sending {} bytes", metadata.len() - restart_at),
+            ),
+        )
+        .await?;
+        let mut data = match self.open_data().await {
+            Ok(data) => data,
+            Err(e) => return Ok(reply(425, e)),
+        };
+        let sent = async {
+            tokio::io::copy(&mut file, &mut data).await?;
+            data.shutdown().await
+        }
+        .await;
+        Ok(match sent {
+            Ok(()) => reply(226, "Transfer complete"),
+            Err(e) => reply(426, format!("Transfer aborted: {}", e)),
+        })
+    }
+
+    /// STOR (or APPE with `append`). The data is received into a temporary file first, so
+    /// the `pre_upload` hook sees it and a refused or cut off upload leaves nothing behind.
+    /// A taken name is handled by the cloud's upload conflict policy, like HTTP uploads
+    /// without `?on_conflict`. After REST, STOR resumes the existing file: it is cut at
+    /// `restart_at` and the data appended.
+    async fn store<C>(
+        &mut self,
+        control: &mut C,
+        arg: &str,
+        append: bool,
+        restart_at: u64,
+    ) -> io::Result<Reply>
+    where
+        C: AsyncWrite + Unpin,
+    {
+        let (path, real) = match self.new_entry(arg).await {
+            Ok(entry) => entry,
+            Err(answer) => return Ok(answer),
+        };
+        let existing = fs::metadata(&real).await.ok();
+        if existing.as_ref().is_some_and(|m| m.is_dir()) {
+            return Ok(reply(550, "That is a directory"));
+        }
+        let resume_at = (restart_at > 0).then_some(restart_at);
+        if resume_at.is_some_and(|offset| existing.as_ref().is_none_or(|m| offset > m.len())) {
+            return Ok(reply(554, "Restart offset is past the end of the file"));
+        }
+        let append = append || resume_at.is_some();
+        let on_conflict = self.cloud.upload_conflict;
+        if !append && existing.is_some() && on_conflict == OnConflict::Reject {
+            return Ok(reply(553, "File exists"));
+        }
+        let Some((folder, user)) = self.hook_context(&path) else {
+            return Ok(reply(550, "Files go inside a cloud folder"));
+        };
+        if let Err(e) = disk_space::check_upload(&self.cloud.disk_space, folder, None) {
+            return Ok(reply(452, e));
+        }
+        let (folder, user) = (folder.clone(), user.to_string());
+
+        let directory = real.parent().unwrap_or(&real).to_path_buf();
+        let temp_path = upload_temp_path(&directory);
+        let mut file = match fs::File::create_new(&temp_path).await {
+            Ok(file) => file,
+            Err(e) => return Ok(reply(550, format!("Can't create file: {}", e))),
+        };
+        send(control, reply(150, format!("Receiving {}", path.display()))).await?;
+        let received = match self.open_data().await {
+            Ok(mut data) => {
+                let received = receive_upload(&mut data, &mut file, &self.cloud.disk_space).await;
+                // Answers the client's TLS close_notify
+                let _ = data.shutdown().await;
+                received
+            }
+            Err(e) => Err(reply(426, e)),
+        };
+        drop(file);
+        let bytes = match received {
+            Ok(bytes) => bytes,
+            Err(answer) => {
+                let _ = fs::remove_file(&temp_path).await;
+                return Ok(answer);
+            }
+        };
+
+        let hook_file = HookFile {
+            path: &temp_path,
+            display_path: &path.display(),
+            size: Some(bytes),
+            user: &user,
+            source: "ftp",
+        };
+        if let Err(e) = hooks::pre_upload(&self.cloud.name, &folder, &hook_file).await {
+            let _ = fs::remove_file(&temp_path).await;
+            return Ok(reply(553, e));
+        }
+        let stored = if append {
+            append_upload(&temp_path, &real, resume_at)
+                .await
+                .map(|()| real.clone())
+        } else {
+            let filename = path.name().unwrap_or_default().to_string();
+            place_upload(&directory, &temp_path, &filename, on_conflict)
+                .await
+                .map(|(name, _)| directory.join(name))
+                .map_err(|e| match e.code {
+                    ErrorCode::FileExists => reply(553, "File exists"),
+                    _ => reply(550, format!("Can't store file: {}", e.message)),
+                })
+        };
+        // Already gone when it was moved into place
+        let _ = fs::remove_file(&temp_path).await;
+        let stored = match stored {
+            Ok(stored) => stored,
+            Err(answer) => return Ok(answer),
+        };
+
+        let stored_name = stored
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let stored_path = path.parent().resolve(&stored_name).unwrap_or(path);
+        tracing::info!(bytes, "FTP upload '{}'", stored_path.display());
+        self.context.auth_state.webhooks.file_event(
+            WebhookEvent::FileUploaded,
+            &stored_path.display(),
+            Some(bytes),
+            "ftp",
+        );
+        hooks::post_upload(
+            &self.cloud.name,
+            &folder,
+            &HookFile {
+                path: &stored,
+                display_path: &stored_path.display(),
+                size: Some(bytes),
+                user: &user,
+                source: "ftp",
+            },
+        );
+        Ok(reply(226, format!("Stored as {}", stored_path.display())))
+    }
+
+    // ========== File management ==========
+
+    async fn delete(&self, arg: &str) -> Reply {
+        let (real, metadata) = match self.existing_file(arg).await {
+            Ok(found) => found,
+            Err(answer) => return answer,
+        };
+        match delete_file(&real).await {
+            Ok(_) => {
+                let path = self.cwd.resolve(arg).unwrap_or_default();
+                tracing::info!("FTP delete '{}'", path.display());
+                self.context.auth_state.webhooks.file_event(
+                    WebhookEvent::FileDeleted,
+                    &path.display(),
+                    None,
+                    "ftp",
+                );
+                if let Some((folder, user)) = self.hook_context(&path) {
+                    hooks::post_delete(
+                        &self.cloud.name,
+                        folder,
+                        &HookFile {
+                            path: &real,
+                            display_path: &path.display(),
+                            size: Some(metadata.len()),
+                            user,
+                            source: "ftp",
+                        },
+                    );
+                }
+                reply(250, "Deleted")
+            }
+            Err(e) => reply(550, e.to_string()),
+        }
+    }
+
+    async fn remove_directory(&self, arg: &str) -> Reply {
+        let path = match self.resolve(arg) {
+            Ok(path) => path,
+            Err(answer) => return answer,
+        };
+        let Ok(Location::InFolder {
+            folder,
+            is_folder_root: false,
+            path: real,
+        }) = locate(&self.cloud, &path)
+        else {
+            return reply(550, "Cloud folders can't be removed");
+        };
+        if let Err(e) = check_inside(folder, &real).await {
+            return reply(550, e);
+        }
+        match fs::remove_dir(&real).await {
+            Ok(()) => reply(250, "Directory removed"),
+            Err(e) => reply(550, format!("Can't remove directory: {}", e)),
+        }
+    }
+
+    async fn make_directory(&self, arg: &str) -> Reply {
+        let (path, real) = match self.new_entry(arg).await {
+            Ok(entry) => entry,
+            Err(answer) => return answer,
+        };
+        match fs::create_dir(&real).await {
+            Ok(()) => reply(257, format!("\"{}\" created", quote(&path.display()))),
+            Err(e) => reply(550, format!("Can't create directory: {}", e)),
+        }
+    }
+
+    async fn rename_from(&mut self, arg: &str) -> Reply {
+        let path = match self.resolve(arg) {
+            Ok(path) => path,
+            Err(answer) => return answer,
+        };
+        let Ok(Location::InFolder {
+            folder,
+            is_folder_root: false,
+            path: real,
+        }) = locate(&self.cloud, &path)
+        else {
+            return reply(550, "Cloud folders can't be renamed");
+        };
+        if fs::symlink_metadata(&real).await.is_err() {
+            return reply(550, "No such file or directory");
+        }
+        if let Err(e) = check_inside(folder, real.parent().unwrap_or(&real)).await {
+            return reply(550, e);
+        }
+        self.rename_from = Some(path);
+        reply(350, "Ready for RNTO")
+    }
+
+    /// Renames never replace an existing entry
+    async fn rename_to(&mut self, arg: &str) -> Reply {
+        let Some(from) = self.rename_from.take() else {
+            return reply(503, "Send RNFR first");
+        };
+        let Ok(Location::InFolder { path: source, .. }) = locate(&self.cloud, &from) else {
+            return reply(550, "No such file or directory");
+        };
+        let (_, target) = match self.new_entry(arg).await {
+            Ok(entry) => entry,
+            Err(answer) => return answer,
+        };
+        if fs::symlink_metadata(&target).await.is_ok() {
+            return reply(553, "Target exists");
+        }
+        match fs::rename(&source, &target).await {
+            Ok(()) => reply(250, "Renamed"),
+            Err(e) => reply(550, format!("Can't rename: {}", e)),
+        }
+    }
+
+    /// `MFMT 20240102030405 path` with the time in UTC
+    async fn set_modified(&self, arg: &str) -> Reply {
+        let Some((time, arg)) = arg.split_once(' ') else {
+            return reply(501, "Expected MFMT YYYYMMDDHHMMSS path");
+        };
+        let Ok(time) = NaiveDateTime::parse_from_str(time, "%Y%m%d%H%M%S") else {
+            return reply(501, "Expected MFMT YYYYMMDDHHMMSS path");
+        };
+        let (real, _) = match self.existing_file(arg).await {
+            Ok(found) => found,
+            Err(answer) => return answer,
+        };
+        let modified: SystemTime = time.and_utc().into();
+        match set_file_mtime(&real, modified).await {
+            Ok(()) => reply(213, format!("Modify={}; {}", fact_time(modified), arg)),
+            Err(e) => reply(550, format!("Can't set modification time: {}", e)),
+        }
+    }
+}
+
+/// Copy an upload from the data connection to `file`, stopping with 552 once it gets
+/// larger than the cloud's upload limit; returns its size
+async fn receive_upload<D, F>(
+    data: &mut D,
+    file: &mut F,
+    config: &disk_space::DiskSpaceConfig,
+) -> Result<u64, Reply>
+where
+    D: AsyncRead + Unpin + ?Sized,
+    F: AsyncWrite + Unpin,
+{
+    let aborted = |e: io::Error| reply(426, format!("Transfer aborted: {}", e));
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut bytes = 0u64;
+    loop {
+        let read = data.read(&mut buffer).await.map_err(aborted)?;
+        if read == 0 {
+            break;
+        }
+        bytes += read as u64;
+        disk_space::check_upload_size(config, bytes).map_err(|e| reply(552, e))?;
+        file.write_all(&buffer[..read]).await.map_err(aborted)?;
+    }
+    file.flush().await.map_err(aborted)?;
+    Ok(bytes)
+}
+
+/// Append a received upload to `path`, creating it if needed; a resumed upload first cuts
+/// the file at `resume_at`
+async fn append_upload(temp_path: &Path, path: &Path, resume_at: Option<u64>) -> Result<(), Reply> {
+    let failed = |e: io::Error| reply(550, format!("Can't append to file: {}", e));
+    let mut received = fs::File::open(temp_path).await.map_err(failed)?;
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .map_err(failed)?;
+    if let Some(offset) = resume_at {
+        file.set_len(offset).await.map_err(failed)?;
+    }
+    tokio::io::copy(&mut received, &mut file)
+        .await
+        .map_err(failed)?;
+    file.flush().await.map_err(failed)
+}
+
+fn list_entry(name: String, metadata: &std::fs::Metadata) -> ListEntry {
+    ListEntry {
+        name,
+        is_dir: metadata.is_dir(),
+        size: if metadata.is_dir() { 0 } else { metadata.len() },
+        modified: metadata.modified().ok(),
+    }
+}
+
+/// `-rw-r--r-- 1 cloudhost cloudhost 1234 Jan  2 03:04 name`, with the year instead of
+/// the time for entries older than six months
+fn long_line(entry: &ListEntry, now: SystemTime) -> String {
+    let modified: DateTime<Utc> = entry.modified.unwrap_or(now).into();
+    let recent = now
+        .duration_since(entry.modified.unwrap_or(now))
+        .is_ok_and(|age| age < Duration::from_secs(180 * 24 * 3600));
+    let date = if recent {
+        modified.format("%b %e %H:%M")
+    } else {
+        modified.format("%b %e  %Y")
+    };
+    format!(
+        "{} 1 cloudhost cloudhost {:>12} {} {}\r\n",
+        if entry.is_dir {
+            "drwxr-xr-x"
+        } else {
+            "-rw-r--r--"
+        },
+        entry.size,
+        date,
+        entry.name
+    )
+}
+
+/// MLSD/MLST facts: `type=file;size=5;modify=20240102030405;`
+fn facts(entry: &ListEntry) -> String {
+    let mut facts = if entry.is_dir {
+        "type=dir;".to_string()
+    } else {
+        format!("type=file;size={};", entry.size)
+    };
+    if let Some(modified) = entry.modified {
+        facts.push_str(&format!("modify={};", fact_time(modified)));
+    }
+    facts
+}
+
+fn fact_time(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time)
+        .format("%Y%m%d%H%M%S")
+        .to_string()
+}
+
+/// Double quotes are doubled inside a quoted 257 path
+fn quote(path: &str) -> String {
+    path.replace('"', "\"\"")
+}
+
+/// `h1,h2,h3,h4,p1,p2`
+fn parse_port(arg: &str) -> Option<SocketAddr> {
+    let numbers: Vec<u8> = arg
+        .split(',')
+        .map(|part| part.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    let [a, b, c, d, high, low] = numbers[..] else {
+        return None;
+    };
+    Some(SocketAddr::from((
+        [a, b, c, d],
+        u16::from(high) << 8 | u16::from(low),
+    )))
+}
+
+/// `|1|203.0.113.7|50000|` or `|2|::1|50000|`
+fn parse_eprt(arg: &str) -> Option<SocketAddr> {
+    let delimiter = arg.chars().next()?;
+    let parts: Vec<&str> = arg.split(delimiter).collect();
+    let [_, _, ip, port, _] = parts[..] else {
+        return None;
+    };
+    Some(SocketAddr::new(ip.parse().ok()?, port.parse().ok()?))
+}
+
+async fn send<C: AsyncWrite + Unpin>(control: &mut C, (code, text): Reply) -> io::Result<()> {
+    if code == ALREADY_SENT {
+        return Ok(());
+    }
+    control
+        .write_all(format!("{} {}\r\n", code, text).as_bytes())
+        .await?;
+    control.flush().await
+}
+
+async fn send_multiline<C: AsyncWrite + Unpin>(
+    control: &mut C,
+    code: u16,
+    first: &str,
+    lines: &[String],
+    last: &str,
+) -> io::Result<()> {
+    let mut text = format!("{}-{}\r\n", code, first);
+    for line in lines {
+        text.push_str(line);
+        text.push_str("\r\n");
+    }
+    text.push_str(&format!("{} {}\r\n", code, last));
+    control.write_all(text.as_bytes()).await?;
+    control.flush().await
+}
@@ -0,0 +1,218 @@
+/// Folder hooks: shell commands a cloud folder runs before an upload is stored, after
+/// it was stored and after a file was deleted, whichever way the file came (web UI,
+/// API, camera uploads, file requests, Nextcloud apps or FTP). The file is described
+/// in `CLOUDHOST_*` environment variables. Their output goes to the cloud's log.
+///
+/// A `pre_upload` hook that fails refuses the upload. The other hooks run in the
+/// background and can't change what already happened.
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::Instrument;
+
+use crate::cloud::CloudFolder;
+
+fn default_timeout_seconds() -> u64 {
+    60
+}
+
+/// `hooks = { ... }` on a cloud folder
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FolderHooks {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_upload: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_upload: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_delete: Option<String>,
+    /// A hook still running after this long is killed
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+impl FolderHooks {
+    /// Problems with the hooks, as `field: message`
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (field, command) in [
+            ("pre_upload", &self.pre_upload),
+            ("post_upload", &self.post_upload),
+            ("post_delete", &self.post_delete),
+        ] {
+            if command.as_ref().is_some_and(|c| c.trim().is_empty()) {
+                problems.push(format!("{}: must not be empty", field));
+            }
+        }
+        if self.timeout_seconds == 0 {
+            problems.push("timeout_seconds: must be at least 1".to_string());
+        }
+        problems
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    PreUpload,
+    PostUpload,
+    PostDelete,
+}
+
+impl HookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::PreUpload => "pre_upload",
+            Self::PostUpload => "post_upload",
+            Self::PostDelete => "post_delete",
+        }
+    }
+}
+
+/// The file a hook runs for
+#[derive(Debug, Clone)]
+pub struct HookFile<'a> {
+    /// Where it is on disk; for `pre_upload`, always the received file, still under its
+    /// temporary name. `display_path` says where it is going to be stored.
+    pub path: &'a Path,
+    /// `cloud_folder/sub/dir/name`, as the cloud shows it
+    pub display_path: &'a str,
+    /// In bytes, when known
+    pub size: Option<u64>,
+    /// `owner`, `token:<id>`, the FTP or SFTP user, or `anonymous` for file requests
+    pub user: &'a str,
+    /// `api`, `camera`, `file_request`, `nextcloud`, `ftp` or `sftp`
+    pub source: &'a str,
+}
+
+/// Run the folder's `pre_upload` hook, if it has one. `Err` is the reason to refuse
+/// the upload with.
+pub async fn pre_upload(
+    cloud: &str,
+    folder: &CloudFolder,
+    file: &HookFile<'_>,
+) -> Result<(), String> {
+    let Some(hooks) = &folder.hooks else {
+        return Ok(());
+    };
+    let Some(command) = &hooks.pre_upload else {
+        return Ok(());
+    };
+    let command = build(command, HookEvent::PreUpload, cloud, folder, file);
+    execute(
+        command,
+        HookEvent::PreUpload,
+        Duration::from_secs(hooks.timeout_seconds),
+    )
+    .await
+    .map_err(|e| format!("Refused by the folder's pre_upload hook: {}", e))
+}
+
+/// Start the folder's `post_upload` hook in the background, if it has one
+pub fn post_upload(cloud: &str, folder: &CloudFolder, file: &HookFile<'_>) {
+    spawn(HookEvent::PostUpload, cloud, folder, file);
+}
+
+/// Start the folder's `post_delete` hook in the background, if it has one
+pub fn post_delete(cloud: &str, folder: &CloudFolder, file: &HookFile<'_>) {
+    spawn(HookEvent::PostDelete, cloud, folder, file);
+}
+
+fn spawn(event: HookEvent, cloud: &str, folder: &CloudFolder, file: &HookFile<'_>) {
+    let Some(hooks) = &folder.hooks else {
+        return;
+    };
+    let command = match event {
+        HookEvent::PreUpload => &hooks.pre_upload,
+        HookEvent::PostUpload => &hooks.post_upload,
+        HookEvent::PostDelete => &hooks.post_delete,
+    };
+    let Some(command) = command.clone() else {
+        return;
+    };
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+
+    let command = build(&command, event, cloud, folder, file);
+    let timeout = Duration::from_secs(hooks.timeout_seconds);
+    // In the request's span, so the output lands in the cloud's log
+    runtime.spawn(
+        async move {
+            if let Err(e) = execute(command, event, timeout).await {
+                tracing::warn!("Folder hook {} failed: {}", event.as_str(), e);
+            }
+        }
+        .in_current_span(),
+    );
+}
+
+/// The command, run by the platform's shell in the folder's directory
+fn build(
+    command: &str,
+    event: HookEvent,
+    cloud: &str,
+    folder: &CloudFolder,
+    file: &HookFile<'_>,
+) -> Command {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell
+        .arg(command)
+        .current_dir(&folder.folder_path)
+        .env("CLOUDHOST_EVENT", event.as_str())
+        .env("CLOUDHOST_CLOUD", cloud)
+        .env("CLOUDHOST_FOLDER", &folder.name)
+        .env("CLOUDHOST_FOLDER_PATH", &folder.folder_path)
+        .env("CLOUDHOST_FILE", file.path)
+        .env("CLOUDHOST_PATH", file.display_path)
+        .env(
+            "CLOUDHOST_SIZE",
+            file.size.map(|size| size.to_string()).unwrap_or_default(),
+        )
+        .env("CLOUDHOST_USER", file.user)
+        .env("CLOUDHOST_SOURCE", file.source);
+    shell
+}
+
+/// Run a hook and log its output line by line. Returns the end of its output if it
+/// fails. It's killed once `timeout` has passed.
+async fn execute(mut command: Command, event: HookEvent, timeout: Duration) -> Result<(), String> {
+    let output = command.stdin(Stdio::null()).kill_on_drop(true).output();
+    let output = tokio::time::timeout(timeout, output)
+        .await
+        .map_err(|_| format!("still running after {}s", timeout.as_secs()))?
+        .map_err(|e| format!("couldn't start the shell: {}", e))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    for line in stdout.lines().filter(|line| !line.trim().is_empty()) {
+        tracing::info!("[{}] {}", event.as_str(), line);
+    }
+    for line in stderr.lines().filter(|line| !line.trim().is_empty()) {
+        tracing::warn!("[{}] {}", event.as_str(), line);
+    }
+    if output.status.success() {
+        tracing::debug!("Folder hook {} finished", event.as_str());
+        return Ok(());
+    }
+    let last_line = stderr
+        .lines()
+        .rev()
+        .chain(stdout.lines().rev())
+        .find(|line| !line.trim().is_empty());
+    Err(format!(
+        "exited with {}{}",
+        output.status,
+        last_line
+            .map(|line| format!(": {}", line.trim()))
+            .unwrap_or_default()
+    ))
+}
@@ -0,0 +1,62 @@
+//! Escaping for the strings pages are built from. File names, folder and cloud names and
+//! paths can contain anything a file system allows, `<script>` included, so nothing of
+//! them goes into a page without passing through one of these first: `escape_html` for
+//! text and attribute values, `encode_path` for URLs, and `js_string` for values a
+//! script uses.
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+/// Everything but unreserved characters is escaped in path segments of links
+pub const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// `text` for HTML text or a quoted attribute value
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A `/`-separated path for a URL, each segment percent-encoded. The result needs no
+/// further escaping in HTML, since `&`, quotes and angle brackets are all encoded.
+pub fn encode_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| utf8_percent_encode(segment, PATH_SEGMENT).to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// `text` as a JavaScript string literal, quotes included, for a `<script>` element.
+/// It can't end the script, whatever `text` holds.
+pub fn js_string(text: &str) -> String {
+    let mut literal = String::with_capacity(text.len() + 2);
+    literal.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            // `</script>` and `<!--` would be read by the HTML parser before the
+            // script runs
+            '<' | '>' | '&' | '\'' | '\u{2028}' | '\u{2029}' => {
+                literal.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c if c.is_control() => literal.push_str(&format!("\\u{:04x}", c as u32)),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
@@ -0,0 +1,109 @@
+/// HTTP caching of files: an `ETag` from the modification time and size, `Last-Modified`,
+/// 304 answers to `If-None-Match` and `If-Modified-Since`, and `Cache-Control`. Images, which
+/// the photo timeline shows as thumbnails, stay fresh for an hour; everything else is
+/// revalidated on each use, which costs a 304 rather than the whole file when unchanged.
+use axum::{
+    http::{
+        header::{CACHE_CONTROL, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+use std::fs::Metadata;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How long images are used without asking the server again
+const IMAGE_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Strong entity tag of a file's contents, as far as its modification time and size tell
+pub fn etag(modified: Option<SystemTime>, size: u64) -> String {
+    let nanos = modified
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_nanos());
+    format!("\"{:x}-{:x}\"", nanos, size)
+}
+
+/// `Cache-Control` for a file of type `content_type`. Files of clouds are `private`, only
+/// public website folders may be kept by shared caches.
+pub fn cache_control(content_type: &str, public: bool) -> HeaderValue {
+    let scope = if public { "public" } else { "private" };
+    let value = if content_type.starts_with("image/") {
+        format!("{}, max-age={}", scope, IMAGE_MAX_AGE.as_secs())
+    } else {
+        format!("{}, no-cache", scope)
+    };
+    HeaderValue::from_str(&value).unwrap_or_else(|_| HeaderValue::from_static("no-cache"))
+}
+
+/// The validators of one file
+#[derive(Debug, Clone)]
+pub struct Validators {
+    pub etag: String,
+    pub last_modified: Option<SystemTime>,
+}
+
+impl Validators {
+    pub fn new(metadata: &Metadata) -> Self {
+        let modified = metadata.modified().ok();
+        Self {
+            etag: etag(modified, metadata.len()),
+            last_modified: modified,
+        }
+    }
+
+    /// Whether the client's copy is current. `If-None-Match` wins over `If-Modified-Since`
+    /// when both are sent (RFC 9110, 13.2.2).
+    pub fn is_fresh(&self, headers: &HeaderMap) -> bool {
+        if headers.contains_key(IF_NONE_MATCH) {
+            return headers
+                .get_all(IF_NONE_MATCH)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .map(str::trim)
+                .any(|tag| tag == "*" || weak_match(tag, &self.etag));
+        }
+        let (Some(modified), Some(since)) = (
+            self.last_modified,
+            headers
+                .get(IF_MODIFIED_SINCE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| httpdate::parse_http_date(value).ok()),
+        ) else {
+            return false;
+        };
+        // HTTP dates have whole seconds
+        let modified = modified
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        let since = since
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        modified <= since
+    }
+
+    /// Add the validators and `cache_control` to a response's headers
+    pub fn apply(&self, headers: &mut HeaderMap, cache_control: HeaderValue) {
+        if let Ok(value) = HeaderValue::from_str(&self.etag) {
+            headers.insert(ETAG, value);
+        }
+        if let Some(modified) = self.last_modified {
+            if let Ok(value) = HeaderValue::from_str(&httpdate::fmt_http_date(modified)) {
+                headers.insert(LAST_MODIFIED, value);
+            }
+        }
+        headers.insert(CACHE_CONTROL, cache_control);
+    }
+
+    /// 304 with the headers the full response would have had for caching
+    pub fn not_modified(&self, cache_control: HeaderValue) -> Response {
+        let mut response = StatusCode::NOT_MODIFIED.into_response();
+        self.apply(response.headers_mut(), cache_control);
+        response
+    }
+}
+
+/// Weak comparison: compressed responses carry the tag as `W/"..."`
+fn weak_match(a: &str, b: &str) -> bool {
+    a.strip_prefix("W/").unwrap_or(a) == b.strip_prefix("W/").unwrap_or(b)
+}
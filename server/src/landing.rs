@@ -0,0 +1,238 @@
+/// Optional landing page on its own port (`landing_port`): one address listing the
+/// clouds with their status, linking to each cloud's own port to log in or open it.
+/// Clouds on one host share its cookies, so the page sees which clouds the visitor is
+/// logged in to, and shows their storage used. Other visitors only see the clouds that
+/// let guests in.
+use axum::{
+    extract::State,
+    http::{header::HOST, HeaderMap, Uri},
+    middleware,
+    response::Html,
+    routing::get,
+    Router,
+};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, RwLock};
+
+use crate::auth::{cookie_value, AuthState};
+use crate::cloud::CloudStatus;
+use crate::error::{ServerError, ServerResult};
+use crate::html::escape_html;
+use crate::security_headers::{
+    self, is_https, ResponseKind, SecurityHeaders, SecurityHeadersConfig,
+};
+use crate::sessions;
+use crate::utils::format_bytes;
+
+/// A cloud as the landing page shows it
+#[derive(Clone)]
+pub struct LandingCloud {
+    pub name: String,
+    pub status: CloudStatus,
+    /// Set while the cloud is running
+    pub port: Option<u16>,
+    /// As last measured by the `refresh_usage` task
+    pub used_bytes: Option<u64>,
+    /// Checks the visitor's login cookie for the cloud, while it's running
+    pub auth_state: Option<Arc<AuthState>>,
+    /// Listed to visitors who aren't logged in to it
+    pub guest_access: bool,
+}
+
+/// The clouds listed on the landing page, kept up to date by the orchestrator
+pub type LandingClouds = Arc<RwLock<Vec<LandingCloud>>>;
+
+/// The landing page's server; stops when dropped
+pub struct LandingServer {
+    pub address: IpAddr,
+    pub port: u16,
+    pub clouds: LandingClouds,
+    _shutdown_tx: tokio::sync::oneshot::Sender<()>,
+}
+
+impl LandingServer {
+    pub async fn start(address: IpAddr, port: u16) -> ServerResult<Self> {
+        let addr = SocketAddr::new(address, port);
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::AddrInUse => ServerError::PortInUse(port),
+                _ => ServerError::Network(format!("Failed to bind to {}: {}", addr, e)),
+            })?;
+
+        let clouds = LandingClouds::default();
+        let page_headers = Arc::new(SecurityHeaders::new(
+            ResponseKind::Page,
+            &SecurityHeadersConfig::default(),
+        ));
+        let app = Router::new()
+            .route("/", get(landing_page))
+            .with_state(clouds.clone())
+            .layer(middleware::from_fn_with_state(
+                page_headers,
+                security_headers::apply,
+            ));
+
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+        tokio::spawn(async move {
+            tracing::info!("Landing page listening on {}", addr);
+            let server = axum::serve(listener, app).with_graceful_shutdown(async move {
+                shutdown_rx.await.ok();
+            });
+            if let Err(e) = server.await {
+                tracing::error!("Landing page server error: {}", e);
+            }
+        });
+
+        Ok(Self {
+            address,
+            port,
+            clouds,
+            _shutdown_tx: shutdown_tx,
+        })
+    }
+
+    pub fn set_clouds(&self, clouds: Vec<LandingCloud>) {
+        if let Ok(mut current) = self.clouds.write() {
+            *current = clouds;
+        }
+    }
+}
+
+/// The host the visitor used, without the port, for links to the clouds' ports
+fn host_name(headers: &HeaderMap) -> String {
+    let host = headers
+        .get(HOST)
+        .and_then(|host| host.to_str().ok())
+        .unwrap_or("localhost");
+    match host.rsplit_once(':') {
+        Some((name, port)) if !port.contains(']') => name.to_string(),
+        _ => host.to_string(),
+    }
+}
+
+fn logged_in(cloud: &LandingCloud, headers: &HeaderMap) -> bool {
+    let (Some(port), Some(auth_state)) = (cloud.port, &cloud.auth_state) else {
+        return false;
+    };
+    let mut cookie_headers = headers.clone();
+    if let Ok(host) = format!("{}:{}", host_name(headers), port).parse() {
+        cookie_headers.insert(HOST, host);
+    }
+    cookie_value(headers, &sessions::cookie_name(&cookie_headers))
+        .is_some_and(|token| auth_state.verify_token(&token).is_ok())
+}
+
+/// `GET /` on the landing port
+async fn landing_page(
+    State(clouds): State<LandingClouds>,
+    uri: Uri,
+    headers: HeaderMap,
+) -> Html<String> {
+    let host = escape_html(&host_name(&headers));
+    // The clouds are reached the way the visitor reached this page
+    let scheme = if is_https(&uri, &headers) {
+        "https"
+    } else {
+        "http"
+    };
+    let clouds: Vec<(LandingCloud, bool)> = clouds
+        .read()
+        .map(|clouds| clouds.clone())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|cloud| {
+            let logged_in = logged_in(&cloud, &headers);
+            (cloud, logged_in)
+        })
+        .filter(|(cloud, logged_in)| *logged_in || cloud.guest_access)
+        .collect();
+
+    let rows = if clouds.is_empty() {
+        r#"<li class="empty">No clouds to show. Clouds you're logged in to are listed here.</li>"#
+            .to_string()
+    } else {
+        clouds
+            .iter()
+            .map(|(cloud, logged_in)| {
+                let name = escape_html(&cloud.name);
+                let logged_in = *logged_in;
+                let (status_class, status) = match &cloud.status {
+                    CloudStatus::Running => ("running", "Running"),
+                    CloudStatus::Starting => ("starting", "Starting"),
+                    CloudStatus::Failed(_) => ("failed", "Failed"),
+                    CloudStatus::Stopped => ("stopped", "Stopped"),
+                };
+                let usage = match (logged_in, cloud.used_bytes) {
+                    (true, Some(bytes)) => format!("{} used", format_bytes(bytes)),
+                    (true, None) => "Not measured yet".to_string(),
+                    (false, _) => String::new(),
+                };
+                let action = match cloud.port {
+                    Some(port) if cloud.status == CloudStatus::Running => {
+                        let (path, label) = if logged_in {
+                            ("/", "Open")
+                        } else {
+                            ("/login", "Log in")
+                        };
+                        format!(
+                            r#"<a class="open" href="{}://{}:{}{}" aria-label="{} {}">{}</a>"#,
+                            scheme, host, port, path, label, name, label
+                        )
+                    }
+                    _ => String::new(),
+                };
+                format!(
+                    r#"<li class="cloud">
+                    <div>
+                        <div class="cloud-name">🌩️ {}</div>
+                        <div class="usage">{}</div>
+                    </div>
+                    <span class="status {}">{}</span>
+                    {}
+                </li>"#,
+                    name, usage, status_class, status, action
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("")
+    };
+
+    Html(format!(
+        r#"
+    <!DOCTYPE html>
+    <html lang="en">
+    <head>
+        <meta name="viewport" content="width=device-width, initial-scale=1">
+        <title>CloudHost</title>
+        <style>
+            body {{ font-family: Arial, sans-serif; margin: 40px; }}
+            .container {{ max-width: 700px; margin: 0 auto; }}
+            h1 {{ text-align: center; }}
+            ul {{ list-style: none; padding: 0; }}
+            .cloud {{ display: flex; align-items: center; gap: 16px; background: #f9f9f9;
+                padding: 15px; margin: 10px 0; border-radius: 5px; border-left: 4px solid #667eea; }}
+            .cloud > div {{ flex: 1; }}
+            .cloud-name {{ font-weight: bold; }}
+            .usage {{ color: #666; font-size: 0.9em; margin-top: 4px; }}
+            .status {{ padding: 3px 10px; border-radius: 10px; font-size: 0.85em; }}
+            .status.running {{ background: #dcfce7; color: #166534; }}
+            .status.starting {{ background: #fef3c7; color: #92400e; }}
+            .status.failed {{ background: #fee2e2; color: #991b1b; }}
+            .status.stopped {{ background: #e5e7eb; color: #374151; }}
+            .open {{ background: #667eea; color: white; padding: 8px 16px; border-radius: 5px;
+                text-decoration: none; }}
+            .empty {{ text-align: center; color: #666; }}
+        </style>
+    </head>
+    <body>
+        <main class="container">
+            <h1>🌩️ CloudHost</h1>
+            <ul>{}</ul>
+        </main>
+    </body>
+    </html>
+    "#,
+        rows
+    ))
+}
@@ -1,18 +1,57 @@
 pub mod api_routes;
+pub mod api_tokens;
 pub mod auth;
+pub mod auth_log;
+pub mod branding;
+pub mod clients;
 pub mod cloud;
 pub mod clouds_config;
+pub mod compression;
+pub mod config_migration;
 pub mod config_paths;
+pub mod config_transfer;
+pub mod csrf;
 pub mod debug_stream;
+pub mod defaults;
+pub mod disk_space;
+pub mod doctor;
+pub mod email;
 pub mod error;
+pub mod file_requests;
+pub mod folder_health;
+pub mod ftp;
+pub mod hooks;
+pub mod html;
+pub mod http_cache;
+pub mod landing;
+pub mod listing_cache;
+pub mod log_store;
+pub mod login_limiter;
+pub mod nextcloud;
+pub mod office_preview;
 pub mod orchestrator;
+pub mod password;
+pub mod photo_cache;
+pub mod recovery;
+pub mod request_id;
 pub mod routes;
+pub mod scheduler;
+pub mod secrets;
+pub mod security_headers;
+pub mod sessions;
+pub mod sftp;
+pub mod stats;
+pub mod tracing_layer;
+pub mod transcode;
 pub mod utils;
 pub mod web_routes;
+pub mod webhooks;
 
 pub use cloud::{Cloud, CloudFolder};
 pub use clouds_config::CloudsConfig;
 pub use config_paths::*;
 pub use debug_stream::*;
-pub use error::{ServerError, ServerResult};
-pub use orchestrator::Orchestrator;
+pub use error::{ApiError, ErrorCode, ServerError, ServerResult};
+pub use orchestrator::{ConflictResolution, Orchestrator};
+pub use password::PasswordHashParams;
+pub use secrets::SecretsProtection;
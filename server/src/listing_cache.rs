@@ -0,0 +1,166 @@
+/// Per-cloud cache of the directory listings of the JSON API, so directories with
+/// thousands of entries aren't read and sorted again on every request. Each cached
+/// directory is watched, and its listing dropped as soon as anything in it changes,
+/// through the cloud or outside it. Directories that can't be watched aren't cached.
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Listings kept at most; one is dropped to make room for another
+const MAX_LISTINGS: usize = 512;
+
+struct Listing {
+    /// Of the directory, which also changes when it's replaced by another one
+    modified: SystemTime,
+    /// Sorted
+    items: Arc<Vec<Value>>,
+}
+
+#[derive(Default)]
+struct Listings {
+    /// By the directory's path on disk
+    listings: HashMap<PathBuf, Listing>,
+    /// Counts changes, so a listing read while one happened isn't kept
+    generation: u64,
+}
+
+impl Listings {
+    /// Drop the listing of the directory `path` is in, and of `path` and everything below
+    /// it in case it's a directory that was removed or renamed
+    fn invalidate(&mut self, path: &Path) {
+        let parent = path.parent();
+        self.listings.retain(|directory, _| {
+            !directory.starts_with(path) && Some(directory.as_path()) != parent
+        });
+    }
+}
+
+struct Watched {
+    watcher: RecommendedWatcher,
+    directories: HashSet<PathBuf>,
+}
+
+pub struct ListingCache {
+    listings: Arc<Mutex<Listings>>,
+    /// Unset if no watcher could be started
+    watched: Option<Mutex<Watched>>,
+}
+
+impl Default for ListingCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ListingCache {
+    pub fn new() -> Self {
+        let listings = Arc::new(Mutex::new(Listings::default()));
+        let events = listings.clone();
+        let watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+            let Ok(mut listings) = events.lock() else {
+                return;
+            };
+            match result {
+                Ok(event) if event.kind.is_access() => return,
+                // Events were lost, so any listing may be stale
+                Ok(event) if event.need_rescan() => listings.listings.clear(),
+                Ok(event) => {
+                    for path in &event.paths {
+                        listings.invalidate(path);
+                    }
+                }
+                Err(_) => listings.listings.clear(),
+            }
+            listings.generation += 1;
+        });
+        let watched = match watcher {
+            Ok(watcher) => Some(Mutex::new(Watched {
+                watcher,
+                directories: HashSet::new(),
+            })),
+            Err(e) => {
+                tracing::warn!("Directory listings won't be cached: {}", e);
+                None
+            }
+        };
+        Self { listings, watched }
+    }
+
+    /// The cached items of `directory`, modified at `modified`, if they're still current
+    pub fn get(&self, directory: &Path, modified: SystemTime) -> Option<Arc<Vec<Value>>> {
+        let listings = self.listings.lock().ok()?;
+        listings
+            .listings
+            .get(directory)
+            .filter(|listing| listing.modified == modified)
+            .map(|listing| listing.items.clone())
+    }
+
+    /// Start watching `directory` before it's read. Gives the generation to `insert` its
+    /// items with, or None if it can't be watched and so can't be cached.
+    pub fn watch(&self, directory: &Path) -> Option<u64> {
+        let mut watched = self.watched.as_ref()?.lock().ok()?;
+        if !watched.directories.contains(directory) {
+            if let Err(e) = watched
+                .watcher
+                .watch(directory, RecursiveMode::NonRecursive)
+            {
+                tracing::debug!("Not caching the listing of {}: {}", directory.display(), e);
+                return None;
+            }
+            watched.directories.insert(directory.to_path_buf());
+        }
+        // Stop watching directories whose listings are gone. The listings lock is only
+        // held for the snapshot: the watcher's thread takes it to deliver events.
+        if watched.directories.len() > MAX_LISTINGS * 2 {
+            let kept: HashSet<PathBuf> = self
+                .listings
+                .lock()
+                .map(|listings| listings.listings.keys().cloned().collect())
+                .unwrap_or_default();
+            let stale: Vec<PathBuf> = watched
+                .directories
+                .iter()
+                .filter(|path| path.as_path() != directory && !kept.contains(*path))
+                .cloned()
+                .collect();
+            for path in stale {
+                let _ = watched.watcher.unwatch(&path);
+                watched.directories.remove(&path);
+            }
+        }
+        drop(watched);
+        self.listings
+            .lock()
+            .ok()
+            .map(|listings| listings.generation)
+    }
+
+    /// Keep the items of `directory`, modified at `modified`, unless something changed
+    /// since `watch` gave `generation`
+    pub fn insert(
+        &self,
+        directory: PathBuf,
+        modified: SystemTime,
+        generation: u64,
+        items: Arc<Vec<Value>>,
+    ) {
+        let Ok(mut listings) = self.listings.lock() else {
+            return;
+        };
+        if listings.generation != generation {
+            return;
+        }
+        if listings.listings.len() >= MAX_LISTINGS && !listings.listings.contains_key(&directory) {
+            if let Some(evicted) = listings.listings.keys().next().cloned() {
+                listings.listings.remove(&evicted);
+            }
+        }
+        listings
+            .listings
+            .insert(directory, Listing { modified, items });
+    }
+}
@@ -0,0 +1,241 @@
+/// Persistent per-cloud logs: every debug message is appended as a JSON line to
+/// `logs/<cloud>.log` in the data directory. When the file grows past
+/// `MAX_FILE_BYTES` it is rotated to `<cloud>.log.1` (older ones shift to `.2`, ...)
+/// and only `MAX_ROTATED_FILES` old files are kept.
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::config_paths;
+use crate::debug_stream::{DebugMessage, LogLevel};
+use crate::error::ServerResult;
+
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+const MAX_ROTATED_FILES: usize = 5;
+
+/// Which messages to read back
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub min_level: Option<LogLevel>,
+    /// Only the newest `limit` matching messages
+    pub limit: Option<usize>,
+}
+
+impl LogFilter {
+    /// The newest `limit` messages, whatever their time or level
+    pub fn last(limit: usize) -> Self {
+        Self {
+            limit: Some(limit),
+            ..Default::default()
+        }
+    }
+
+    pub fn matches(&self, message: &DebugMessage) -> bool {
+        self.since.is_none_or(|since| message.timestamp >= since)
+            && self.until.is_none_or(|until| message.timestamp <= until)
+            && self
+                .min_level
+                .as_ref()
+                .is_none_or(|min| message.level.at_least(min))
+    }
+}
+
+pub struct LogStore {
+    path: PathBuf,
+    /// Size of the current file, to know when to rotate without a stat per message
+    size: Mutex<u64>,
+}
+
+impl LogStore {
+    pub fn for_cloud(cloud_name: &str) -> Self {
+        let path = get_cloud_log_path(cloud_name);
+        let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        Self {
+            path,
+            size: Mutex::new(size),
+        }
+    }
+
+    /// Append a message, rotating first if the file is full.
+    /// Errors are ignored: losing a log line must not break the server.
+    pub fn append(&self, message: &DebugMessage) {
+        let Ok(line) = serde_json::to_string(message) else {
+            return;
+        };
+        let Ok(mut size) = self.size.lock() else {
+            return;
+        };
+        if *size >= MAX_FILE_BYTES && self.rotate().is_ok() {
+            *size = 0;
+        }
+        if let Some(dir) = self.path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let written = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| writeln!(file, "{}", line));
+        if written.is_ok() {
+            *size += line.len() as u64 + 1;
+        }
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        PathBuf::from(path)
+    }
+
+    /// Rotate now, whatever the size. Returns false if there was nothing to rotate.
+    pub fn rotate_now(&self) -> std::io::Result<bool> {
+        let Ok(mut size) = self.size.lock() else {
+            return Ok(false);
+        };
+        if !self.path.exists() {
+            return Ok(false);
+        }
+        self.rotate()?;
+        *size = 0;
+        Ok(true)
+    }
+
+    fn rotate(&self) -> std::io::Result<()> {
+        let _ = fs::remove_file(self.rotated_path(MAX_ROTATED_FILES));
+        for index in (1..MAX_ROTATED_FILES).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))
+    }
+
+    /// Read matching messages from all files, oldest first
+    pub fn read(&self, filter: &LogFilter) -> ServerResult<Vec<DebugMessage>> {
+        let mut files: Vec<PathBuf> = (1..=MAX_ROTATED_FILES)
+            .rev()
+            .map(|index| self.rotated_path(index))
+            .collect();
+        files.push(self.path.clone());
+
+        let mut messages = Vec::new();
+        for path in files.iter().filter(|path| path.exists()) {
+            let file = fs::File::open(path)?;
+            for line in BufReader::new(file).lines() {
+                // Skip lines cut short by a crash
+                let Ok(message) = serde_json::from_str::<DebugMessage>(&line?) else {
+                    continue;
+                };
+                if filter.matches(&message) {
+                    messages.push(message);
+                }
+            }
+        }
+
+        if let Some(limit) = filter.limit {
+            let skip = messages.len().saturating_sub(limit);
+            messages.drain(..skip);
+        }
+        Ok(messages)
+    }
+}
+
+/// `logs/<cloud>.log` in the data directory, with unusual characters replaced
+pub fn get_cloud_log_path(cloud_name: &str) -> PathBuf {
+    config_paths::get_data_dir()
+        .join("logs")
+        .join(format!("{}.log", config_paths::cloud_file_stem(cloud_name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A store writing to `<temp>/<name>/cloud.log`, starting empty
+    fn store(name: &str) -> LogStore {
+        let dir = std::env::temp_dir().join(format!(
+            "cloudhost-log-store-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        LogStore {
+            path: dir.join("cloud.log"),
+            size: Mutex::new(0),
+        }
+    }
+
+    fn message(text: &str) -> DebugMessage {
+        DebugMessage {
+            timestamp: Utc::now(),
+            level: LogLevel::Info,
+            source: "test".to_string(),
+            message: text.to_string(),
+        }
+    }
+
+    fn first_message(path: &std::path::Path) -> String {
+        let contents = fs::read_to_string(path).unwrap();
+        let line = contents.lines().next().unwrap();
+        serde_json::from_str::<DebugMessage>(line).unwrap().message
+    }
+
+    #[test]
+    fn a_full_file_is_rotated_before_the_next_message() {
+        let store = store("size");
+        let filler = "x".repeat(1000);
+        let mut written = 0;
+        while *store.size.lock().unwrap() < MAX_FILE_BYTES {
+            store.append(&message(&format!("{} {}", written, filler)));
+            written += 1;
+        }
+        assert_eq!(
+            fs::metadata(&store.path).unwrap().len(),
+            *store.size.lock().unwrap()
+        );
+        assert!(!store.rotated_path(1).exists());
+
+        store.append(&message("after"));
+        assert!(store.rotated_path(1).exists());
+        assert_eq!(
+            first_message(&store.rotated_path(1)),
+            format!("0 {}", filler)
+        );
+        assert_eq!(first_message(&store.path), "after");
+        // Nothing is lost by rotating
+        assert_eq!(
+            store.read(&LogFilter::default()).unwrap().len(),
+            written + 1
+        );
+        let _ = fs::remove_dir_all(store.path.parent().unwrap());
+    }
+
+    #[test]
+    fn only_the_newest_rotated_files_are_kept() {
+        let store = store("age");
+        assert!(!store.rotate_now().unwrap());
+
+        for generation in 0..MAX_ROTATED_FILES + 2 {
+            store.append(&message(&format!("generation {}", generation)));
+            assert!(store.rotate_now().unwrap());
+            assert!(!store.path.exists());
+        }
+        // `.1` is the newest, the two oldest generations were dropped
+        for index in 1..=MAX_ROTATED_FILES {
+            assert_eq!(
+                first_message(&store.rotated_path(index)),
+                format!("generation {}", MAX_ROTATED_FILES + 2 - index)
+            );
+        }
+        assert!(!store.rotated_path(MAX_ROTATED_FILES + 1).exists());
+        let messages = store.read(&LogFilter::default()).unwrap();
+        assert_eq!(messages.len(), MAX_ROTATED_FILES);
+        assert_eq!(messages[0].message, "generation 2");
+        let _ = fs::remove_dir_all(store.path.parent().unwrap());
+    }
+}
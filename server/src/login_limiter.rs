@@ -0,0 +1,106 @@
+/// Slows down password guessing on the login page. After `MAX_FAILURES` failed logins
+/// from one address within `FAILURE_WINDOW`, logins from it are refused for a lockout that
+/// starts at 30 seconds and doubles with every further lockout, up to an hour. A
+/// successful login forgets the address.
+use axum::http::HeaderMap;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::auth_log::forwarded_client_ip;
+
+pub const MAX_FAILURES: u32 = 5;
+const FAILURE_WINDOW: Duration = Duration::from_secs(15 * 60);
+const FIRST_LOCKOUT: Duration = Duration::from_secs(30);
+const MAX_LOCKOUT: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug)]
+struct Entry {
+    failures: u32,
+    first_failure: Instant,
+    locked_until: Option<Instant>,
+    /// Lockouts so far, each one twice as long as the last
+    lockouts: u32,
+}
+
+/// What a failed login leads to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureOutcome {
+    /// More tries before a lockout
+    AttemptsLeft(u32),
+    LockedOut(Duration),
+}
+
+#[derive(Debug, Default)]
+pub struct LoginLimiter {
+    entries: Mutex<HashMap<IpAddr, Entry>>,
+}
+
+/// The address to limit. Behind a proxy on the same machine (such as a Cloudflare tunnel)
+/// every request comes from localhost, so the address the proxy reports counts instead.
+pub fn client_address(peer: SocketAddr, headers: &HeaderMap) -> IpAddr {
+    if peer.ip().is_loopback() {
+        forwarded_client_ip(headers).unwrap_or(peer.ip())
+    } else {
+        peer.ip()
+    }
+}
+
+impl LoginLimiter {
+    /// How long `ip` is still locked out, if it is
+    pub fn locked_for(&self, ip: IpAddr) -> Option<Duration> {
+        let entries = self.entries.lock().ok()?;
+        let locked_until = entries.get(&ip)?.locked_until?;
+        locked_until
+            .checked_duration_since(Instant::now())
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    pub fn record_failure(&self, ip: IpAddr) -> FailureOutcome {
+        let Ok(mut entries) = self.entries.lock() else {
+            return FailureOutcome::AttemptsLeft(0);
+        };
+        let now = Instant::now();
+        // Forget addresses that have been quiet for a while
+        entries.retain(|_, entry| {
+            now.duration_since(entry.first_failure) < FAILURE_WINDOW
+                || entry.locked_until.is_some_and(|until| until > now)
+        });
+
+        let entry = entries.entry(ip).or_insert(Entry {
+            failures: 0,
+            first_failure: now,
+            locked_until: None,
+            lockouts: 0,
+        });
+        if now.duration_since(entry.first_failure) >= FAILURE_WINDOW {
+            entry.failures = 0;
+            entry.first_failure = now;
+        }
+        entry.failures += 1;
+        if entry.failures < MAX_FAILURES {
+            return FailureOutcome::AttemptsLeft(MAX_FAILURES - entry.failures);
+        }
+
+        let lockout = FIRST_LOCKOUT
+            .saturating_mul(2u32.saturating_pow(entry.lockouts))
+            .min(MAX_LOCKOUT);
+        entry.lockouts += 1;
+        entry.failures = 0;
+        entry.first_failure = now;
+        entry.locked_until = Some(now + lockout);
+        tracing::warn!(
+            ip = %ip,
+            lockout_secs = lockout.as_secs(),
+            "Too many failed logins, locking the address out"
+        );
+        FailureOutcome::LockedOut(lockout)
+    }
+
+    pub fn record_success(&self, ip: IpAddr) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.remove(&ip);
+        }
+    }
+}
@@ -0,0 +1,1241 @@
+/// The WebDAV subset the Nextcloud apps and OS clients use: OPTIONS, PROPFIND, GET/HEAD,
+/// PUT, MKCOL, DELETE, MOVE, COPY, LOCK and UNLOCK on the cloud folders, and chunked
+/// uploads (Nextcloud chunking v2).
+///
+/// PUT and MOVE replace existing files, as sync clients expect, unless the request says
+/// otherwise (`If-None-Match: *`, `Overwrite: F`) or the cloud's `upload_conflict` is
+/// `reject`. Folders are never replaced; deleting one moves it to the OS trash, contents
+/// included. Locks are granted but not enforced, which is all Finder and Windows Explorer
+/// need before they write.
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+};
+use futures_util::StreamExt;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Write as _;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tower_http::services::ServeFile;
+
+use crate::api_routes::delete::delete_file;
+use crate::api_routes::upload::OnConflict;
+use crate::auth::AuthenticatedUser;
+use crate::cloud::{CloudFolder, CloudServerState};
+use crate::config_paths;
+use crate::disk_space;
+use crate::hooks::{self, HookFile};
+use crate::http_cache::etag;
+use crate::utils::virtual_path::{check_inside, locate, Location, VirtualPath};
+use crate::utils::{
+    is_upload_temp, mime_from_extension, parse_mtime, set_file_mtime, upload_temp_path,
+    validate_path_component, MTIME_HEADER, OCTET_STREAM,
+};
+use crate::webhooks::WebhookEvent;
+
+/// Everything but unreserved characters is escaped in hrefs
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Chunk staging folders older than this are abandoned uploads
+const STALE_UPLOAD_SECS: u64 = 24 * 60 * 60;
+
+/// A failed DAV request, answered with a Sabre-style XML error like Nextcloud's
+struct DavError {
+    status: StatusCode,
+    message: String,
+}
+
+fn dav_error(status: StatusCode, message: impl Into<String>) -> DavError {
+    DavError {
+        status,
+        message: message.into(),
+    }
+}
+
+impl IntoResponse for DavError {
+    fn into_response(self) -> Response {
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<d:error xmlns:d="DAV:" xmlns:s="http://sabredav.org/ns"><s:message>{}</s:message></d:error>
+"#,
+            escape_xml(&self.message)
+        );
+        (
+            self.status,
+            [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+            body,
+        )
+            .into_response()
+    }
+}
+
+type DavResult = Result<Response, DavError>;
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// ========== Request paths ==========
+
+/// Split a files URL path into its href prefix (as sent, still encoded) and the
+/// virtual path below it
+fn files_target(uri_path: &str) -> Result<(String, VirtualPath), DavError> {
+    let webdav = uri_path
+        .strip_prefix("/remote.php/webdav")
+        .filter(|rest| rest.is_empty() || rest.starts_with('/'));
+    let (base, rest) = if let Some(rest) = webdav {
+        ("/remote.php/webdav".to_string(), rest)
+    } else if let Some(after) = uri_path.strip_prefix("/remote.php/dav/files/") {
+        let (user, rest) = after.split_once('/').unwrap_or((after, ""));
+        (format!("/remote.php/dav/files/{}", user), rest)
+    } else {
+        return Err(dav_error(StatusCode::NOT_FOUND, "Not a files URL"));
+    };
+    Ok((base, decode_path(rest)?))
+}
+
+fn decode_path(raw: &str) -> Result<VirtualPath, DavError> {
+    let mut path = VirtualPath::default();
+    for segment in raw.split('/').filter(|segment| !segment.is_empty()) {
+        let name = percent_decode_str(segment)
+            .decode_utf8()
+            .map_err(|_| dav_error(StatusCode::BAD_REQUEST, "Path is not UTF-8"))?;
+        path = path
+            .join(&name)
+            .map_err(|e| dav_error(StatusCode::BAD_REQUEST, e))?;
+    }
+    Ok(path)
+}
+
+/// The path of the `Destination` header of a MOVE or COPY, which is a full URL.
+/// One on another server is refused with 502, as RFC 4918 asks.
+fn destination(headers: &HeaderMap) -> Result<&str, DavError> {
+    let value = headers
+        .get("destination")
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| dav_error(StatusCode::BAD_REQUEST, "Missing Destination header"))?;
+    let path = match value.split_once("://") {
+        Some((_, rest)) => {
+            let (authority, path) = rest
+                .find('/')
+                .map_or((rest, "/"), |slash| (&rest[..slash], &rest[slash..]));
+            let host = headers
+                .get(header::HOST)
+                .and_then(|value| value.to_str().ok());
+            if host.is_some_and(|host| !host.eq_ignore_ascii_case(authority)) {
+                return Err(dav_error(
+                    StatusCode::BAD_GATEWAY,
+                    "The destination is on another server",
+                ));
+            }
+            path
+        }
+        None => value,
+    };
+    Ok(path.split('?').next().unwrap_or(path))
+}
+
+/// `base/a%20b/c/`
+fn href(base: &str, path: &VirtualPath, is_dir: bool) -> String {
+    let mut href = base.to_string();
+    for segment in path.segments() {
+        href.push('/');
+        href.extend(utf8_percent_encode(segment, PATH_SEGMENT));
+    }
+    if is_dir {
+        href.push('/');
+    }
+    href
+}
+
+/// 503 for paths in a cloud folder whose path went away, until it is back
+fn check_available(state: &CloudServerState, path: &VirtualPath) -> Result<(), DavError> {
+    match path.segments().first() {
+        Some(folder) => state
+            .folder_health
+            .ensure_available(folder)
+            .map_err(|e| dav_error(StatusCode::SERVICE_UNAVAILABLE, e)),
+        None => Ok(()),
+    }
+}
+
+/// Resolve a virtual path to a file or folder inside a cloud folder (not the folder itself)
+fn entry_in_folder<'a>(
+    state: &'a CloudServerState,
+    path: &VirtualPath,
+    action: &str,
+) -> Result<(&'a CloudFolder, PathBuf), DavError> {
+    match locate(&state.cloud, path) {
+        Ok(Location::InFolder {
+            folder,
+            is_folder_root: false,
+            path,
+        }) => Ok((folder, path)),
+        Err(e) if path.segments().len() > 1 => Err(dav_error(StatusCode::NOT_FOUND, e)),
+        // Unknown cloud folders included, those can't be created either
+        _ => Err(dav_error(
+            StatusCode::FORBIDDEN,
+            format!("Cloud folders and the root can't be {}", action),
+        )),
+    }
+}
+
+/// The directory a new entry goes in must exist and be inside the cloud folder
+async fn check_parent(folder: &CloudFolder, path: &Path) -> Result<(), DavError> {
+    let parent = path.parent().unwrap_or(path);
+    if !fs::metadata(parent).await.is_ok_and(|m| m.is_dir()) {
+        return Err(dav_error(
+            StatusCode::CONFLICT,
+            "The parent folder doesn't exist",
+        ));
+    }
+    check_inside(folder, parent)
+        .await
+        .map_err(|e| dav_error(StatusCode::FORBIDDEN, e))
+}
+
+/// Whether an existing file may be replaced by this request
+fn check_overwrite(state: &CloudServerState, headers: &HeaderMap) -> Result<(), DavError> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    if header("if-none-match").is_some_and(|value| value.trim() == "*")
+        || header("overwrite").is_some_and(|value| value.trim().eq_ignore_ascii_case("f"))
+    {
+        return Err(dav_error(
+            StatusCode::PRECONDITION_FAILED,
+            "The target exists",
+        ));
+    }
+    if state.cloud.upload_conflict == OnConflict::Reject {
+        return Err(dav_error(
+            StatusCode::PRECONDITION_FAILED,
+            "Files can't be replaced in this cloud",
+        ));
+    }
+    Ok(())
+}
+
+fn mtime_from_headers(headers: &HeaderMap) -> Result<Option<SystemTime>, DavError> {
+    headers
+        .get(MTIME_HEADER)
+        .map(|value| {
+            value
+                .to_str()
+                .map_err(|_| "not text".to_string())
+                .and_then(parse_mtime)
+                .map_err(|e| {
+                    dav_error(
+                        StatusCode::BAD_REQUEST,
+                        format!("Invalid X-OC-Mtime header: {}", e),
+                    )
+                })
+        })
+        .transpose()
+}
+
+// ========== Properties ==========
+
+/// One `<d:response>` of a PROPFIND
+struct Resource {
+    href: String,
+    is_dir: bool,
+    size: u64,
+    modified: Option<SystemTime>,
+    /// ownCloud permission letters: R share, G read, D delete, N rename, V move,
+    /// W write, C create file, K create folder
+    permissions: &'static str,
+    id: u32,
+}
+
+impl Resource {
+    fn new(href: String, metadata: &std::fs::Metadata, permissions: &'static str, id: u32) -> Self {
+        Self {
+            href,
+            is_dir: metadata.is_dir(),
+            // Nextcloud reports the size of folder contents, which would take a scan
+            size: if metadata.is_dir() { 0 } else { metadata.len() },
+            modified: metadata.modified().ok(),
+            permissions,
+            id,
+        }
+    }
+
+    fn write(&self, xml: &mut String) {
+        let _ = write!(
+            xml,
+            "<d:response><d:href>{}</d:href><d:propstat><d:prop>",
+            escape_xml(&self.href)
+        );
+        if let Some(modified) = self.modified {
+            let _ = write!(
+                xml,
+                "<d:getlastmodified>{}</d:getlastmodified>",
+                httpdate::fmt_http_date(modified)
+            );
+        }
+        let _ = write!(
+            xml,
+            "<d:getetag>{}</d:getetag><oc:id>{:08}ocloudhost</oc:id><oc:fileid>{}</oc:fileid>\
+             <oc:permissions>{}</oc:permissions><oc:size>{}</oc:size><oc:favorite>0</oc:favorite>",
+            etag(self.modified, self.size),
+            self.id,
+            self.id,
+            self.permissions,
+            self.size,
+        );
+        if self.is_dir {
+            // -3: unlimited
+            xml.push_str(
+                "<d:resourcetype><d:collection/></d:resourcetype>\
+                 <d:quota-available-bytes>-3</d:quota-available-bytes>",
+            );
+        } else {
+            // By extension only; sniffing every file of a folder would slow PROPFIND down
+            let content_type = mime_from_extension(Path::new(self.href.trim_end_matches('/')))
+                .unwrap_or(OCTET_STREAM);
+            let _ = write!(
+                xml,
+                "<d:resourcetype/><d:getcontentlength>{}</d:getcontentlength>\
+                 <d:getcontenttype>{}</d:getcontenttype>",
+                self.size,
+                escape_xml(content_type)
+            );
+        }
+        xml.push_str("</d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>");
+    }
+}
+
+/// Stable for a path, which is all the apps need from `oc:fileid`
+fn file_id(key: &str) -> u32 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish() as u32
+}
+
+fn multistatus(resources: &[Resource]) -> Response {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<d:multistatus xmlns:d="DAV:" xmlns:oc="http://owncloud.org/ns" xmlns:nc="http://nextcloud.org/ns">"#,
+    );
+    for resource in resources {
+        resource.write(&mut xml);
+    }
+    xml.push_str("</d:multistatus>\n");
+    (
+        StatusCode::MULTI_STATUS,
+        [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+        xml,
+    )
+        .into_response()
+}
+
+/// `Depth: 0` lists the resource alone, anything else one level. `infinity` is refused
+/// where it matters, in `propfind`.
+fn lists_children(headers: &HeaderMap) -> bool {
+    headers
+        .get("depth")
+        .and_then(|value| value.to_str().ok())
+        .is_none_or(|depth| depth.trim() != "0")
+}
+
+/// The entries of a directory that are valid path components, with their metadata
+async fn read_children(directory: &Path) -> Result<Vec<(String, std::fs::Metadata)>, DavError> {
+    let mut entries = fs::read_dir(directory).await.map_err(|e| {
+        dav_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to read folder: {}", e),
+        )
+    })?;
+    let mut children = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        // Broken links and unreadable entries are left out
+        let Ok(metadata) = fs::metadata(entry.path()).await else {
+            continue;
+        };
+        if validate_path_component(&name).is_ok() && !is_upload_temp(&name) {
+            children.push((name, metadata));
+        }
+    }
+    children.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(children)
+}
+
+async fn propfind(
+    state: &CloudServerState,
+    base: &str,
+    path: &VirtualPath,
+    headers: &HeaderMap,
+) -> DavResult {
+    let infinity = headers
+        .get("depth")
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|depth| depth.trim().eq_ignore_ascii_case("infinity"));
+    if infinity {
+        // A whole cloud folder in one answer; clients walk the tree one level at a time
+        return Err(dav_error(
+            StatusCode::FORBIDDEN,
+            "Depth: infinity is not supported",
+        ));
+    }
+    let children = lists_children(headers);
+    let mut resources = Vec::new();
+
+    let location = locate(&state.cloud, path).map_err(|e| dav_error(StatusCode::NOT_FOUND, e))?;
+    let Location::InFolder {
+        folder,
+        is_folder_root,
+        path: real,
+    } = location
+    else {
+        let mut folders = Vec::new();
+        for folder in &state.cloud.cloud_folders {
+            if let Ok(metadata) = fs::metadata(&folder.folder_path).await {
+                folders.push((folder, metadata));
+            }
+        }
+        // The root changes when one of the cloud folders does
+        let modified = folders
+            .iter()
+            .filter_map(|(_, metadata)| metadata.modified().ok())
+            .max();
+        resources.push(Resource {
+            href: href(base, path, true),
+            is_dir: true,
+            size: 0,
+            modified,
+            permissions: "RG",
+            id: file_id(&path.display()),
+        });
+        if children {
+            for (folder, metadata) in folders {
+                let Ok(child) = path.join(&folder.name) else {
+                    continue;
+                };
+                resources.push(Resource::new(
+                    href(base, &child, true),
+                    &metadata,
+                    "RGCK",
+                    file_id(&child.display()),
+                ));
+            }
+        }
+        return Ok(multistatus(&resources));
+    };
+
+    let metadata = fs::metadata(&real)
+        .await
+        .map_err(|_| dav_error(StatusCode::NOT_FOUND, "No such file or folder"))?;
+    check_inside(folder, &real)
+        .await
+        .map_err(|e| dav_error(StatusCode::FORBIDDEN, e))?;
+    let (file_permissions, dir_permissions) = ("RGDNVW", "RGDNVCK");
+    let own_permissions = match (is_folder_root, metadata.is_dir()) {
+        (true, _) => "RGCK",
+        (false, true) => dir_permissions,
+        (false, false) => file_permissions,
+    };
+    resources.push(Resource::new(
+        href(base, path, metadata.is_dir()),
+        &metadata,
+        own_permissions,
+        file_id(&path.display()),
+    ));
+
+    if children && metadata.is_dir() {
+        for (name, metadata) in read_children(&real).await? {
+            let Ok(child) = path.join(&name) else {
+                continue;
+            };
+            let permissions = if metadata.is_dir() {
+                dir_permissions
+            } else {
+                file_permissions
+            };
+            resources.push(Resource::new(
+                href(base, &child, metadata.is_dir()),
+                &metadata,
+                permissions,
+                file_id(&child.display()),
+            ));
+        }
+    }
+    Ok(multistatus(&resources))
+}
+
+// ========== Files ==========
+
+/// Stream a request body into `file`, stopping once it gets larger than the cloud's
+/// upload limit whatever length was announced. Returns the number of bytes written.
+async fn write_body(
+    state: &CloudServerState,
+    body: Body,
+    file: &mut fs::File,
+) -> Result<u64, DavError> {
+    let aborted =
+        |e: io::Error| dav_error(StatusCode::BAD_REQUEST, format!("Upload aborted: {}", e));
+    let mut written = 0u64;
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| aborted(io::Error::other(e)))?;
+        written += chunk.len() as u64;
+        check_upload_size(state, Some(written))?;
+        file.write_all(&chunk).await.map_err(aborted)?;
+    }
+    file.flush().await.map_err(aborted)?;
+    Ok(written)
+}
+
+/// Refuse an upload larger than the cloud's upload limit
+fn check_upload_size(state: &CloudServerState, size: Option<u64>) -> Result<(), DavError> {
+    match size {
+        Some(size) => disk_space::check_upload_size(&state.cloud.disk_space, size)
+            .map_err(|e| dav_error(StatusCode::PAYLOAD_TOO_LARGE, e)),
+        None => Ok(()),
+    }
+}
+
+/// A temporary file next to `path`, renamed over it once complete
+fn temp_path_for(path: &Path) -> PathBuf {
+    upload_temp_path(path.parent().unwrap_or(path))
+}
+
+/// Move a finished temporary file into place and apply the client's modification time
+async fn finish_upload(
+    temp_path: &Path,
+    path: &Path,
+    mtime: Option<SystemTime>,
+) -> Result<(), DavError> {
+    let write_error = |e: io::Error| {
+        dav_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to write file: {}", e),
+        )
+    };
+    if let Some(mtime) = mtime {
+        if let Err(e) = set_file_mtime(temp_path, mtime).await {
+            let _ = fs::remove_file(temp_path).await;
+            return Err(write_error(e));
+        }
+    }
+    if let Err(e) = fs::rename(temp_path, path).await {
+        let _ = fs::remove_file(temp_path).await;
+        return Err(write_error(e));
+    }
+    Ok(())
+}
+
+/// 201 for a new file, 204 for a replaced one, with the headers ownCloud clients read
+async fn uploaded(
+    path: &Path,
+    virtual_path: &VirtualPath,
+    replaced: bool,
+    mtime: bool,
+) -> Response {
+    let status = if replaced {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::CREATED
+    };
+    let mut response = status.into_response();
+    let headers = response.headers_mut();
+    if let Ok(metadata) = fs::metadata(path).await {
+        let etag = etag(metadata.modified().ok(), metadata.len());
+        if let Ok(value) = HeaderValue::from_str(&etag) {
+            headers.insert(header::ETAG, value.clone());
+            headers.insert("oc-etag", value);
+        }
+    }
+    let id = format!("{:08}ocloudhost", file_id(&virtual_path.display()));
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        headers.insert("oc-fileid", value);
+    }
+    if mtime {
+        headers.insert("x-oc-mtime", HeaderValue::from_static("accepted"));
+    }
+    response
+}
+
+async fn get(state: &CloudServerState, path: &VirtualPath, request: Request) -> DavResult {
+    let (folder, real) = match locate(&state.cloud, path) {
+        Ok(Location::InFolder { folder, path, .. }) => (folder, path),
+        Ok(Location::Root) => {
+            return Err(dav_error(
+                StatusCode::METHOD_NOT_ALLOWED,
+                "Folders can't be downloaded",
+            ))
+        }
+        Err(e) => return Err(dav_error(StatusCode::NOT_FOUND, e)),
+    };
+    let metadata = fs::metadata(&real)
+        .await
+        .map_err(|_| dav_error(StatusCode::NOT_FOUND, "No such file"))?;
+    if metadata.is_dir() {
+        return Err(dav_error(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "Folders can't be downloaded",
+        ));
+    }
+    check_inside(folder, &real)
+        .await
+        .map_err(|e| dav_error(StatusCode::FORBIDDEN, e))?;
+
+    // Ranges, conditional requests and HEAD come with ServeFile
+    let etag = etag(metadata.modified().ok(), metadata.len());
+    let mut response = ServeFile::new(&real)
+        .try_call(request)
+        .await
+        .map_err(|e| {
+            dav_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to read file: {}", e),
+            )
+        })?
+        .map(Body::new);
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        response.headers_mut().insert(header::ETAG, value);
+    }
+    Ok(response)
+}
+
+async fn put(
+    state: &CloudServerState,
+    path: &VirtualPath,
+    request: Request,
+    user: &str,
+) -> DavResult {
+    let (folder, real) = entry_in_folder(state, path, "written")?;
+    check_parent(folder, &real).await?;
+    let headers = request.headers();
+    let mtime = mtime_from_headers(headers)?;
+    let replaced = match fs::metadata(&real).await {
+        Ok(metadata) if metadata.is_dir() => {
+            return Err(dav_error(
+                StatusCode::METHOD_NOT_ALLOWED,
+                "That is a folder",
+            ))
+        }
+        Ok(_) => {
+            check_overwrite(state, headers)?;
+            true
+        }
+        Err(_) => false,
+    };
+    let size = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok());
+    check_upload_size(state, size)?;
+    disk_space::check_upload(&state.cloud.disk_space, folder, size)
+        .map_err(|e| dav_error(StatusCode::INSUFFICIENT_STORAGE, e))?;
+    let display_path = path.display();
+
+    let temp_path = temp_path_for(&real);
+    let mut file = fs::File::create_new(&temp_path).await.map_err(|e| {
+        dav_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to create file: {}", e),
+        )
+    })?;
+    let written = match write_body(state, request.into_body(), &mut file).await {
+        Ok(written) => written,
+        Err(e) => {
+            drop(file);
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(e);
+        }
+    };
+    drop(file);
+    let hook_file = HookFile {
+        path: &temp_path,
+        display_path: &display_path,
+        size: Some(written),
+        user,
+        source: "nextcloud",
+    };
+    if let Err(e) = hooks::pre_upload(&state.cloud.name, folder, &hook_file).await {
+        let _ = fs::remove_file(&temp_path).await;
+        return Err(dav_error(StatusCode::FORBIDDEN, e));
+    }
+    finish_upload(&temp_path, &real, mtime).await?;
+
+    tracing::info!(bytes = written, "Nextcloud upload '{}'", display_path);
+    state.webhooks.file_event(
+        WebhookEvent::FileUploaded,
+        &display_path,
+        Some(written),
+        "nextcloud",
+    );
+    hooks::post_upload(
+        &state.cloud.name,
+        folder,
+        &HookFile {
+            path: &real,
+            display_path: &display_path,
+            size: Some(written),
+            user,
+            source: "nextcloud",
+        },
+    );
+    Ok(uploaded(&real, path, replaced, mtime.is_some()).await)
+}
+
+async fn mkcol(state: &CloudServerState, path: &VirtualPath) -> DavResult {
+    let (folder, real) = entry_in_folder(state, path, "created")?;
+    check_parent(folder, &real).await?;
+    match fs::create_dir(&real).await {
+        Ok(()) => Ok(StatusCode::CREATED.into_response()),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Err(dav_error(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "The folder already exists",
+        )),
+        Err(e) => Err(dav_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to create folder: {}", e),
+        )),
+    }
+}
+
+async fn delete(state: &CloudServerState, path: &VirtualPath, user: &str) -> DavResult {
+    let (folder, real) = entry_in_folder(state, path, "deleted")?;
+    let metadata = fs::symlink_metadata(&real)
+        .await
+        .map_err(|_| dav_error(StatusCode::NOT_FOUND, "No such file or folder"))?;
+    check_inside(folder, real.parent().unwrap_or(&real))
+        .await
+        .map_err(|e| dav_error(StatusCode::FORBIDDEN, e))?;
+
+    // Folders go to the trash with everything in them, as RFC 4918 asks of DELETE
+    delete_file(&real)
+        .await
+        .map_err(|e| dav_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if !metadata.is_dir() {
+        state.webhooks.file_event(
+            WebhookEvent::FileDeleted,
+            &path.display(),
+            None,
+            "nextcloud",
+        );
+        hooks::post_delete(
+            &state.cloud.name,
+            folder,
+            &HookFile {
+                path: &real,
+                display_path: &path.display(),
+                size: Some(metadata.len()),
+                user,
+                source: "nextcloud",
+            },
+        );
+    }
+    tracing::info!("Nextcloud delete '{}'", path.display());
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// Copy a file or a whole folder
+fn copy_recursive(from: &Path, to: &Path) -> io::Result<()> {
+    if std::fs::metadata(from)?.is_dir() {
+        std::fs::create_dir(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        std::fs::copy(from, to).map(|_| ())
+    }
+}
+
+/// MOVE, or COPY with `copy`. Only files replace existing files.
+async fn transfer(
+    state: &CloudServerState,
+    path: &VirtualPath,
+    headers: &HeaderMap,
+    copy: bool,
+) -> DavResult {
+    let (_, target_path) = files_target(destination(headers)?)?;
+    check_available(state, &target_path)?;
+    let (folder, source) = entry_in_folder(state, path, if copy { "copied" } else { "moved" })?;
+    let source_metadata = fs::symlink_metadata(&source)
+        .await
+        .map_err(|_| dav_error(StatusCode::NOT_FOUND, "No such file or folder"))?;
+    check_inside(folder, source.parent().unwrap_or(&source))
+        .await
+        .map_err(|e| dav_error(StatusCode::FORBIDDEN, e))?;
+    let (target_folder, target) = entry_in_folder(state, &target_path, "replaced")?;
+    check_parent(target_folder, &target).await?;
+    if target == source {
+        return Err(dav_error(
+            StatusCode::FORBIDDEN,
+            "Source and destination are the same",
+        ));
+    }
+
+    let replaced = match fs::symlink_metadata(&target).await {
+        Ok(metadata) if metadata.is_dir() || source_metadata.is_dir() => {
+            return Err(dav_error(
+                StatusCode::PRECONDITION_FAILED,
+                "Folders can't be replaced",
+            ))
+        }
+        Ok(_) => {
+            check_overwrite(state, headers)?;
+            true
+        }
+        Err(_) => false,
+    };
+
+    let result = if copy {
+        let (from, to) = (source.clone(), target.clone());
+        tokio::task::spawn_blocking(move || copy_recursive(&from, &to))
+            .await
+            .map_err(io::Error::other)
+            .and_then(|result| result)
+    } else {
+        fs::rename(&source, &target).await
+    };
+    result.map_err(|e| {
+        dav_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to {}: {}", if copy { "copy" } else { "move" }, e),
+        )
+    })?;
+
+    tracing::info!(
+        "Nextcloud {} '{}' to '{}'",
+        if copy { "copy" } else { "move" },
+        path.display(),
+        target_path.display()
+    );
+    Ok(if replaced {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::CREATED
+    }
+    .into_response())
+}
+
+/// The methods `files` answers, for OPTIONS
+const ALLOWED_METHODS: &str =
+    "OPTIONS, GET, HEAD, PUT, DELETE, PROPFIND, MKCOL, MOVE, COPY, LOCK, UNLOCK";
+
+/// Class 2 is advertised for the sake of Finder and Windows Explorer, which only write
+/// to servers that take locks
+fn options() -> Response {
+    (
+        StatusCode::OK,
+        [
+            (header::ALLOW, ALLOWED_METHODS),
+            (header::HeaderName::from_static("dav"), "1, 2"),
+            (header::HeaderName::from_static("ms-author-via"), "DAV"),
+        ],
+    )
+        .into_response()
+}
+
+/// Grant a lock without keeping it. A missing file is created empty, as the lock-null
+/// resources of RFC 4918 were, since Finder locks a file before its first PUT.
+async fn lock(
+    state: &CloudServerState,
+    base: &str,
+    path: &VirtualPath,
+    request: Request,
+) -> DavResult {
+    let (folder, real) = entry_in_folder(state, path, "locked")?;
+    let created = match fs::metadata(&real).await {
+        Ok(_) => false,
+        Err(_) => {
+            check_parent(folder, &real).await?;
+            fs::File::create_new(&real).await.map_err(|e| {
+                dav_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Failed to create file: {}", e),
+                )
+            })?;
+            true
+        }
+    };
+    let is_dir = fs::metadata(&real).await.is_ok_and(|m| m.is_dir());
+    // Up to an hour; clients refresh the locks they hold
+    let timeout = request
+        .headers()
+        .get("timeout")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .and_then(|value| value.trim().strip_prefix("Second-"))
+        .and_then(|seconds| seconds.parse::<u64>().ok())
+        .map_or(3600, |seconds| seconds.min(3600));
+    let body = axum::body::to_bytes(request.into_body(), 64 * 1024)
+        .await
+        .unwrap_or_default();
+    let scope = if String::from_utf8_lossy(&body).contains("shared") {
+        "shared"
+    } else {
+        "exclusive"
+    };
+    let token = format!("opaquelocktoken:{}", uuid::Uuid::new_v4());
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<d:prop xmlns:d="DAV:"><d:lockdiscovery><d:activelock><d:locktype><d:write/></d:locktype><d:lockscope><d:{}/></d:lockscope><d:depth>0</d:depth><d:timeout>Second-{}</d:timeout><d:locktoken><d:href>{}</d:href></d:locktoken><d:lockroot><d:href>{}</d:href></d:lockroot></d:activelock></d:lockdiscovery></d:prop>
+"#,
+        scope,
+        timeout,
+        token,
+        escape_xml(&href(base, path, is_dir))
+    );
+    let status = if created {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+    let mut response = (
+        status,
+        [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+        xml,
+    )
+        .into_response();
+    if let Ok(value) = HeaderValue::from_str(&format!("<{}>", token)) {
+        response.headers_mut().insert("lock-token", value);
+    }
+    Ok(response)
+}
+
+fn unlock(headers: &HeaderMap) -> DavResult {
+    if headers.contains_key("lock-token") {
+        Ok(StatusCode::NO_CONTENT.into_response())
+    } else {
+        Err(dav_error(
+            StatusCode::BAD_REQUEST,
+            "Missing Lock-Token header",
+        ))
+    }
+}
+
+/// Who the app logged in as, for the folder hooks
+fn hook_user(request: &Request) -> String {
+    request
+        .extensions()
+        .get::<AuthenticatedUser>()
+        .map_or_else(|| "owner".to_string(), AuthenticatedUser::name)
+}
+
+/// `/remote.php/dav/files/<user>/...` and `/remote.php/webdav/...`
+pub async fn files(State(state): State<CloudServerState>, request: Request) -> Response {
+    let (base, path) = match files_target(request.uri().path()) {
+        Ok(target) => target,
+        Err(e) => return e.into_response(),
+    };
+    if let Err(e) = check_available(&state, &path) {
+        return e.into_response();
+    }
+    let method = request.method().clone();
+    let user = hook_user(&request);
+    let result = match method.as_str() {
+        "OPTIONS" => Ok(options()),
+        "PROPFIND" => propfind(&state, &base, &path, request.headers()).await,
+        "GET" | "HEAD" => get(&state, &path, request).await,
+        "PUT" => put(&state, &path, request, &user).await,
+        "MKCOL" => mkcol(&state, &path).await,
+        "DELETE" => delete(&state, &path, &user).await,
+        "MOVE" => transfer(&state, &path, request.headers(), false).await,
+        "COPY" => transfer(&state, &path, request.headers(), true).await,
+        "LOCK" => lock(&state, &base, &path, request).await,
+        "UNLOCK" => unlock(request.headers()),
+        _ => Err(dav_error(
+            StatusCode::METHOD_NOT_ALLOWED,
+            format!("{} is not supported", method),
+        )),
+    };
+    result.unwrap_or_else(IntoResponse::into_response)
+}
+
+// ========== Chunked uploads ==========
+
+/// Where chunks are staged until the upload is assembled, per cloud
+fn uploads_dir(state: &CloudServerState) -> PathBuf {
+    cloud_uploads_dir(&state.cloud.name)
+}
+
+pub(super) fn cloud_uploads_dir(cloud_name: &str) -> PathBuf {
+    config_paths::get_data_dir()
+        .join("nextcloud-uploads")
+        .join(config_paths::cloud_file_stem(cloud_name))
+}
+
+/// Remove staging folders of uploads that were never finished
+pub(super) async fn remove_stale_uploads(uploads: &Path) {
+    let Ok(mut entries) = fs::read_dir(uploads).await else {
+        return;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let stale = entry
+            .metadata()
+            .await
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age.as_secs() > STALE_UPLOAD_SECS);
+        if stale {
+            let _ = fs::remove_dir_all(entry.path()).await;
+        }
+    }
+}
+
+/// The chunks of an upload in order. Clients number them, some with leading zeros.
+async fn chunk_files(upload: &Path) -> Result<Vec<PathBuf>, DavError> {
+    let mut chunks: Vec<(String, PathBuf)> = read_children(upload)
+        .await?
+        .into_iter()
+        .filter(|(name, metadata)| name != ".file" && metadata.is_file())
+        .map(|(name, _)| {
+            let path = upload.join(&name);
+            (name, path)
+        })
+        .collect();
+    chunks.sort_by(
+        |(a, _), (b, _)| match (a.parse::<u64>(), b.parse::<u64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            _ => a.cmp(b),
+        },
+    );
+    Ok(chunks.into_iter().map(|(_, path)| path).collect())
+}
+
+/// `MOVE <upload>/.file`: concatenate the chunks into the destination
+async fn assemble(
+    state: &CloudServerState,
+    upload: &Path,
+    headers: &HeaderMap,
+    user: &str,
+) -> DavResult {
+    let (_, target_path) = files_target(destination(headers)?)?;
+    check_available(state, &target_path)?;
+    let (folder, target) = entry_in_folder(state, &target_path, "written")?;
+    check_parent(folder, &target).await?;
+    let mtime = mtime_from_headers(headers)?;
+    let replaced = match fs::metadata(&target).await {
+        Ok(metadata) if metadata.is_dir() => {
+            return Err(dav_error(
+                StatusCode::METHOD_NOT_ALLOWED,
+                "That is a folder",
+            ))
+        }
+        Ok(_) => {
+            check_overwrite(state, headers)?;
+            true
+        }
+        Err(_) => false,
+    };
+    let chunks = chunk_files(upload).await?;
+    if chunks.is_empty() {
+        return Err(dav_error(
+            StatusCode::BAD_REQUEST,
+            "The upload has no chunks",
+        ));
+    }
+    let total_length = headers
+        .get("oc-total-length")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    check_upload_size(state, total_length)?;
+    // Each chunk is within the limit, all of them together may not be
+    let mut chunks_length = 0;
+    for chunk in &chunks {
+        if let Ok(metadata) = fs::metadata(chunk).await {
+            chunks_length += metadata.len();
+        }
+    }
+    check_upload_size(state, Some(chunks_length))?;
+    disk_space::check_upload(&state.cloud.disk_space, folder, total_length)
+        .map_err(|e| dav_error(StatusCode::INSUFFICIENT_STORAGE, e))?;
+
+    let temp_path = temp_path_for(&target);
+    let assembled = async {
+        let mut file = fs::File::create_new(&temp_path).await?;
+        let mut size = 0;
+        for chunk in &chunks {
+            size += tokio::io::copy(&mut fs::File::open(chunk).await?, &mut file).await?;
+        }
+        file.flush().await?;
+        Ok::<_, io::Error>(size)
+    }
+    .await;
+    let size = match assembled {
+        Ok(size) => size,
+        Err(e) => {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(dav_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to assemble the upload: {}", e),
+            ));
+        }
+    };
+    if total_length.is_some_and(|expected| expected != size) {
+        let _ = fs::remove_file(&temp_path).await;
+        return Err(dav_error(
+            StatusCode::BAD_REQUEST,
+            format!("Chunks add up to {} bytes, not OC-Total-Length", size),
+        ));
+    }
+    let display_path = target_path.display();
+    let hook_file = HookFile {
+        path: &temp_path,
+        display_path: &display_path,
+        size: Some(size),
+        user,
+        source: "nextcloud",
+    };
+    if let Err(e) = hooks::pre_upload(&state.cloud.name, folder, &hook_file).await {
+        let _ = fs::remove_file(&temp_path).await;
+        return Err(dav_error(StatusCode::FORBIDDEN, e));
+    }
+    finish_upload(&temp_path, &target, mtime).await?;
+    let _ = fs::remove_dir_all(upload).await;
+
+    tracing::info!(
+        bytes = size,
+        chunks = chunks.len(),
+        "Nextcloud chunked upload '{}'",
+        display_path
+    );
+    state.webhooks.file_event(
+        WebhookEvent::FileUploaded,
+        &display_path,
+        Some(size),
+        "nextcloud",
+    );
+    hooks::post_upload(
+        &state.cloud.name,
+        folder,
+        &HookFile {
+            path: &target,
+            ..hook_file
+        },
+    );
+    Ok(uploaded(&target, &target_path, replaced, mtime.is_some()).await)
+}
+
+/// `/remote.php/dav/uploads/<user>/<upload id>/<chunk>`
+pub async fn uploads(State(state): State<CloudServerState>, request: Request) -> Response {
+    let uri_path = request.uri().path().to_string();
+    let after = uri_path
+        .strip_prefix("/remote.php/dav/uploads/")
+        .unwrap_or_default();
+    let (user, rest) = after.split_once('/').unwrap_or((after, ""));
+    let base = format!("/remote.php/dav/uploads/{}", user);
+    let path = match decode_path(rest) {
+        Ok(path) => path,
+        Err(e) => return e.into_response(),
+    };
+    let uploads = uploads_dir(&state);
+    let mut real = uploads.clone();
+    real.extend(path.segments());
+    let method = request.method().clone();
+
+    let result = match (method.as_str(), path.segments()) {
+        ("PROPFIND", _) => propfind_uploads(&base, &path, &real, request.headers()).await,
+        ("MKCOL", [_]) => {
+            remove_stale_uploads(&uploads).await;
+            let created = fs::create_dir_all(&uploads).await;
+            match created.and(fs::create_dir(&real).await) {
+                Ok(()) => Ok(StatusCode::CREATED.into_response()),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Err(dav_error(
+                    StatusCode::METHOD_NOT_ALLOWED,
+                    "The upload already exists",
+                )),
+                Err(e) => Err(dav_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Failed to start the upload: {}", e),
+                )),
+            }
+        }
+        ("PUT", [_, _]) => put_chunk(&state, &real, request.into_body()).await,
+        ("MOVE", [_, name]) if name == ".file" => {
+            let upload = real.parent().unwrap_or(&real);
+            assemble(&state, upload, request.headers(), &hook_user(&request)).await
+        }
+        ("DELETE", [_]) => fs::remove_dir_all(&real)
+            .await
+            .map(|()| StatusCode::NO_CONTENT.into_response())
+            .map_err(|_| dav_error(StatusCode::NOT_FOUND, "No such upload")),
+        ("DELETE", [_, _]) => fs::remove_file(&real)
+            .await
+            .map(|()| StatusCode::NO_CONTENT.into_response())
+            .map_err(|_| dav_error(StatusCode::NOT_FOUND, "No such chunk")),
+        _ if method == Method::GET || method == Method::HEAD => Err(dav_error(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "Uploads can't be downloaded",
+        )),
+        _ => Err(dav_error(
+            StatusCode::METHOD_NOT_ALLOWED,
+            format!("{} is not supported here", method),
+        )),
+    };
+    result.unwrap_or_else(IntoResponse::into_response)
+}
+
+async fn put_chunk(state: &CloudServerState, chunk: &Path, body: Body) -> DavResult {
+    let upload = chunk.parent().unwrap_or(chunk);
+    if !fs::metadata(upload).await.is_ok_and(|m| m.is_dir()) {
+        return Err(dav_error(StatusCode::NOT_FOUND, "No such upload"));
+    }
+    let mut file = fs::File::create(chunk).await.map_err(|e| {
+        dav_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to store chunk: {}", e),
+        )
+    })?;
+    if let Err(e) = write_body(state, body, &mut file).await {
+        drop(file);
+        let _ = fs::remove_file(chunk).await;
+        return Err(e);
+    }
+    Ok(StatusCode::CREATED.into_response())
+}
+
+/// Lets clients see which chunks arrived, to resume an interrupted upload
+async fn propfind_uploads(
+    base: &str,
+    path: &VirtualPath,
+    real: &Path,
+    headers: &HeaderMap,
+) -> DavResult {
+    let metadata = fs::metadata(real)
+        .await
+        .map_err(|_| dav_error(StatusCode::NOT_FOUND, "No such upload"))?;
+    let mut resources = vec![Resource::new(
+        href(base, path, metadata.is_dir()),
+        &metadata,
+        "RGDNVWCK",
+        file_id(&path.display()),
+    )];
+    if lists_children(headers) && metadata.is_dir() {
+        for (name, metadata) in read_children(real).await? {
+            let Ok(child) = path.join(&name) else {
+                continue;
+            };
+            resources.push(Resource::new(
+                href(base, &child, metadata.is_dir()),
+                &metadata,
+                "RGDNVWCK",
+                file_id(&child.display()),
+            ));
+        }
+    }
+    Ok(multistatus(&resources))
+}
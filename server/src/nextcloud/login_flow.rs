@@ -0,0 +1,301 @@
+/// Nextcloud Login Flow v2: the app starts a flow, opens the login URL in a browser and
+/// polls until the user has entered the cloud password there, then gets an app password:
+/// an API token of its own with the `nextcloud` scope, listed and revoked like the others.
+use axum::{
+    extract::{ConnectInfo, Form, Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Json, Response},
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::base_url;
+use crate::api_tokens::ApiTokenScope;
+use crate::auth_log::AuthFailure;
+use crate::branding::Branding;
+use crate::cloud::CloudServerState;
+use crate::error::{ApiError, ErrorCode};
+use crate::login_limiter::{self, FailureOutcome};
+
+/// Flows not finished by then are dropped, like Nextcloud does
+const FLOW_LIFETIME: Duration = Duration::from_secs(20 * 60);
+/// Starting a flow needs no login, so cap how many can pile up
+const MAX_PENDING_FLOWS: usize = 64;
+/// Characters of the User-Agent kept in the app password's name
+const MAX_CLIENT_NAME: usize = 80;
+
+struct LoginFlow {
+    /// The secret the app polls with; the login token is in the browser URL
+    poll_token: String,
+    started: Instant,
+    /// The app's User-Agent, which names its app password
+    client: String,
+    /// Set once the password was entered
+    app_password: Option<String>,
+}
+
+/// Pending login flows of a cloud, by login token
+#[derive(Default)]
+pub struct LoginFlows(Mutex<HashMap<String, LoginFlow>>);
+
+impl LoginFlows {
+    /// Start a flow, returns the login and poll tokens
+    fn start(&self, client: String) -> Option<(String, String)> {
+        let mut flows = self.0.lock().ok()?;
+        flows.retain(|_, flow| flow.started.elapsed() < FLOW_LIFETIME);
+        if flows.len() >= MAX_PENDING_FLOWS {
+            return None;
+        }
+        let login_token = uuid::Uuid::new_v4().simple().to_string();
+        let poll_token = uuid::Uuid::new_v4().simple().to_string();
+        flows.insert(
+            login_token.clone(),
+            LoginFlow {
+                poll_token: poll_token.clone(),
+                started: Instant::now(),
+                client,
+                app_password: None,
+            },
+        );
+        Some((login_token, poll_token))
+    }
+
+    fn is_pending(&self, login_token: &str) -> bool {
+        self.0.lock().is_ok_and(|flows| {
+            flows.get(login_token).is_some_and(|flow| {
+                flow.app_password.is_none() && flow.started.elapsed() < FLOW_LIFETIME
+            })
+        })
+    }
+
+    /// What the app password of a pending flow is called
+    fn client(&self, login_token: &str) -> Option<String> {
+        let flows = self.0.lock().ok()?;
+        flows.get(login_token).map(|flow| flow.client.clone())
+    }
+
+    fn grant(&self, login_token: &str, app_password: String) {
+        if let Ok(mut flows) = self.0.lock() {
+            if let Some(flow) = flows.get_mut(login_token) {
+                flow.app_password = Some(app_password);
+            }
+        }
+    }
+
+    /// The app password of a granted flow, which ends the flow
+    fn collect(&self, poll_token: &str) -> Option<String> {
+        let mut flows = self.0.lock().ok()?;
+        let login_token = flows
+            .iter()
+            .find(|(_, flow)| flow.poll_token == poll_token && flow.app_password.is_some())
+            .map(|(login_token, _)| login_token.clone())?;
+        flows
+            .remove(&login_token)
+            .and_then(|flow| flow.app_password)
+    }
+}
+
+/// `POST /index.php/login/v2`
+pub async fn start(State(server_state): State<CloudServerState>, headers: HeaderMap) -> Response {
+    let client = headers
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(|agent| agent.chars().take(MAX_CLIENT_NAME).collect::<String>())
+        .filter(|agent| !agent.trim().is_empty())
+        .unwrap_or_else(|| "unknown app".to_string());
+    let Some((login_token, poll_token)) = server_state.login_flows.start(client) else {
+        return ApiError::new(
+            ErrorCode::Unavailable,
+            "Too many pending logins, try again later",
+        )
+        .into_response();
+    };
+    let base = base_url(&headers);
+    Json(json!({
+        "poll": {
+            "token": poll_token,
+            "endpoint": format!("{}/index.php/login/v2/poll", base),
+        },
+        "login": format!("{}/index.php/login/v2/flow/{}", base, login_token),
+    }))
+    .into_response()
+}
+
+/// `GET /index.php/login/v2/flow/:token`, opened by the app in a browser
+pub async fn page(
+    State(server_state): State<CloudServerState>,
+    Path(login_token): Path<String>,
+) -> Response {
+    if !server_state.login_flows.is_pending(&login_token) {
+        return expired_page(&server_state.branding);
+    }
+    flow_page(&server_state.branding, PASSWORD_FORM, "").into_response()
+}
+
+#[derive(Deserialize)]
+pub struct PasswordForm {
+    password: String,
+}
+
+/// `POST /index.php/login/v2/flow/:token` with the password. Wrong passwords count
+/// towards the same lockout as the login page.
+pub async fn submit(
+    State(server_state): State<CloudServerState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Path(login_token): Path<String>,
+    headers: HeaderMap,
+    Form(form): Form<PasswordForm>,
+) -> Response {
+    if !server_state.login_flows.is_pending(&login_token) {
+        return expired_page(&server_state.branding);
+    }
+    let auth_state = &server_state.auth_state;
+    let address = login_limiter::client_address(peer, &headers);
+    if let Some(lockout) = auth_state.login_limiter.locked_for(address) {
+        return locked_out_page(&server_state.branding, lockout);
+    }
+    if !auth_state.verify_password(&form.password) {
+        auth_state.record_failure(&AuthFailure::new(
+            peer.ip(),
+            &headers,
+            &server_state.cloud.name,
+            "nextcloud_bad_password",
+        ));
+        if let FailureOutcome::LockedOut(lockout) = auth_state.login_limiter.record_failure(address)
+        {
+            return locked_out_page(&server_state.branding, lockout);
+        }
+        return (
+            StatusCode::UNAUTHORIZED,
+            flow_page(&server_state.branding, PASSWORD_FORM, "Wrong password"),
+        )
+            .into_response();
+    }
+    auth_state.login_limiter.record_success(address);
+
+    let client = server_state
+        .login_flows
+        .client(&login_token)
+        .unwrap_or_default();
+    let (token, app_password) = auth_state
+        .api_tokens
+        .create(&format!("Nextcloud: {}", client), ApiTokenScope::Nextcloud);
+    server_state.login_flows.grant(&login_token, app_password);
+    tracing::info!(
+        "Nextcloud app logged in from {} (API token {})",
+        peer.ip(),
+        token.id
+    );
+    flow_page(
+        &server_state.branding,
+        "<p>Access granted. You can close this window and return to the app.</p>",
+        "",
+    )
+    .into_response()
+}
+
+#[derive(Deserialize)]
+pub struct PollForm {
+    token: String,
+}
+
+/// `POST /index.php/login/v2/poll`: 404 until the flow is granted
+pub async fn poll(
+    State(server_state): State<CloudServerState>,
+    headers: HeaderMap,
+    Form(form): Form<PollForm>,
+) -> Response {
+    match server_state.login_flows.collect(&form.token) {
+        Some(app_password) => Json(json!({
+            "server": base_url(&headers),
+            "loginName": server_state.cloud.name,
+            "appPassword": app_password,
+        }))
+        .into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// 429 with `Retry-After`, the form stays so the user can try again later
+fn locked_out_page(branding: &Branding, lockout: Duration) -> Response {
+    let seconds = lockout.as_secs().max(1);
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, seconds.to_string())],
+        flow_page(
+            branding,
+            PASSWORD_FORM,
+            &format!("Too many failed logins, try again in {} seconds", seconds),
+        ),
+    )
+        .into_response()
+}
+
+fn expired_page(branding: &Branding) -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        flow_page(
+            branding,
+            "",
+            "This login link has expired. Start again from the app.",
+        ),
+    )
+        .into_response()
+}
+
+const PASSWORD_FORM: &str = r#"<form method="post">
+            <label for="password">Password:</label>
+            <input type="password" id="password" name="password" autofocus required>
+            <button type="submit">Grant access</button>
+        </form>"#;
+
+/// `error` is plain text without markup
+fn flow_page(branding: &Branding, content: &str, error: &str) -> Html<String> {
+    let error = if error.is_empty() {
+        String::new()
+    } else {
+        format!(r#"<p class="error">{}</p>"#, error)
+    };
+    Html(format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <title>{} - Connect app</title>
+    <style>
+        {}
+        body {{ font-family: Arial, sans-serif; margin: 0; min-height: 100vh; display: flex;
+            align-items: center; justify-content: center;
+            background: var(--accent-background); }}
+        .box {{ background: white; padding: 40px; border-radius: 10px; width: 100%; max-width: 400px;
+            box-sizing: border-box; box-shadow: 0 15px 35px rgba(0,0,0,0.1); }}
+        h1 {{ margin: 0 0 10px 0; color: #333; }}
+        label {{ display: block; margin: 20px 0 5px 0; font-weight: bold; color: #333; }}
+        input {{ width: 100%; padding: 12px; border: 2px solid #ddd; border-radius: 5px;
+            font-size: 16px; box-sizing: border-box; }}
+        button {{ width: 100%; margin-top: 20px; padding: 12px; border: none; border-radius: 5px;
+            color: white; font-size: 16px; cursor: pointer;
+            background: var(--accent-background); }}
+        .error {{ color: #e74c3c; background: #fdf2f2; padding: 10px; border-radius: 5px; }}
+    </style>
+</head>
+<body>
+    <div class="box">
+        <h1>Connect app</h1>
+        <p>A Nextcloud app wants to access this cloud. Enter the cloud password to allow it.</p>
+        {}
+        {}
+    </div>
+</body>
+</html>
+"#,
+        branding.name_html(),
+        branding.css_variables(),
+        error,
+        content
+    ))
+}
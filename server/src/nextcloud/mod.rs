@@ -0,0 +1,238 @@
+/// Just enough of the Nextcloud server API for the official Nextcloud (and ownCloud)
+/// apps to log in and sync files, mainly for phone camera uploads.
+///
+/// - `/status.php` and the OCS capabilities tell the apps what they're talking to
+/// - Login Flow v2 (`/index.php/login/v2`) asks for the cloud password in the browser
+///   and hands the app an app password, an API token only good for these routes
+/// - `/remote.php/dav/files/<user>/` and `/remote.php/webdav/` map the cloud folders
+///   like FTP does: the root lists them, each is a directory below it
+/// - `/remote.php/dav/uploads/<user>/` takes chunked uploads of big files
+///
+/// Everything but the status, capabilities and login flow needs HTTP Basic auth with an
+/// app password. The user name is ignored, there is only one account per cloud.
+/// Off unless `nextcloud_compat` is set for the cloud.
+mod dav;
+mod login_flow;
+
+pub use login_flow::LoginFlows;
+
+use axum::{
+    extract::{ConnectInfo, Path, Request, State},
+    http::{
+        header::{AUTHORIZATION, WWW_AUTHENTICATE},
+        HeaderMap, StatusCode,
+    },
+    middleware::{self, Next},
+    response::{IntoResponse, Json, Response},
+    routing::{any, get, post},
+    Router,
+};
+use base64::Engine;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use crate::api_tokens::ApiTokenScope;
+use crate::auth::{self, AuthMethod, AuthenticatedUser};
+use crate::auth_log::AuthFailure;
+use crate::cloud::CloudServerState;
+use crate::security_headers::{self, SecurityHeaders};
+
+/// The Nextcloud release whose API this imitates. Apps refuse servers that are too old.
+const VERSION: [u32; 4] = [28, 0, 4, 1];
+const VERSION_STRING: &str = "28.0.4";
+
+/// The routes of the compatibility layer, merged into the cloud's router
+pub(crate) fn router(
+    state: CloudServerState,
+    page_headers: Arc<SecurityHeaders>,
+    api_headers: Arc<SecurityHeaders>,
+) -> Router<CloudServerState> {
+    let public = Router::new()
+        .route("/status.php", get(status))
+        .route("/ocs/:version/cloud/capabilities", get(capabilities))
+        .route("/index.php/login/v2", post(login_flow::start))
+        .route(
+            "/index.php/login/v2/flow/:token",
+            get(login_flow::page).post(login_flow::submit),
+        )
+        .route("/index.php/login/v2/poll", post(login_flow::poll))
+        .layer(middleware::from_fn_with_state(
+            page_headers,
+            security_headers::apply,
+        ));
+
+    let authenticated = Router::new()
+        .route("/ocs/:version/cloud/user", get(user))
+        .route("/remote.php/webdav", any(dav::files))
+        .route("/remote.php/webdav/", any(dav::files))
+        .route("/remote.php/webdav/*path", any(dav::files))
+        .route("/remote.php/dav/files/:user", any(dav::files))
+        .route("/remote.php/dav/files/:user/", any(dav::files))
+        .route("/remote.php/dav/files/:user/*path", any(dav::files))
+        .route("/remote.php/dav/uploads/:user", any(dav::uploads))
+        .route("/remote.php/dav/uploads/:user/", any(dav::uploads))
+        .route("/remote.php/dav/uploads/:user/*path", any(dav::uploads))
+        .route_layer(middleware::from_fn_with_state(state, require_app_password))
+        .layer(middleware::from_fn_with_state(
+            api_headers,
+            security_headers::apply,
+        ));
+
+    public.merge(authenticated)
+}
+
+/// `http://host:port` as the client reached the server, for URLs handed to the apps.
+/// Behind a TLS-terminating proxy the scheme comes from `X-Forwarded-Proto`.
+fn base_url(headers: &HeaderMap) -> String {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    let scheme = match header("x-forwarded-proto") {
+        Some(proto) if proto.eq_ignore_ascii_case("https") => "https",
+        _ => "http",
+    };
+    let host = header("x-forwarded-host")
+        .or_else(|| header("host"))
+        .unwrap_or("localhost");
+    format!("{}://{}", scheme, host)
+}
+
+async fn status(State(server_state): State<CloudServerState>) -> Json<Value> {
+    Json(json!({
+        "installed": true,
+        "maintenance": false,
+        "needsDbUpgrade": false,
+        "version": VERSION.map(|part| part.to_string()).join("."),
+        "versionstring": VERSION_STRING,
+        "edition": "",
+        "productname": format!("CloudHost ({})", server_state.cloud.name),
+        "extendedSupport": false,
+    }))
+}
+
+/// OCS answers have the same envelope in v1 and v2, only the success code differs
+fn ocs_response(version: &str, data: Value) -> Response {
+    let statuscode = match version {
+        "v1.php" => 100,
+        "v2.php" => 200,
+        _ => return StatusCode::NOT_FOUND.into_response(),
+    };
+    Json(json!({
+        "ocs": {
+            "meta": { "status": "ok", "statuscode": statuscode, "message": "OK" },
+            "data": data,
+        }
+    }))
+    .into_response()
+}
+
+/// Only the capabilities the apps need to upload: the WebDAV root and chunking v2
+async fn capabilities(Path(version): Path<String>) -> Response {
+    ocs_response(
+        &version,
+        json!({
+            "version": {
+                "major": VERSION[0],
+                "minor": VERSION[1],
+                "micro": VERSION[2],
+                "string": VERSION_STRING,
+                "edition": "",
+                "extendedSupport": false,
+            },
+            "capabilities": {
+                "core": { "pollinterval": 60, "webdav-root": "remote.php/webdav" },
+                "dav": { "chunking": "1.0" },
+                "files": { "bigfilechunking": true, "undelete": false, "versioning": false },
+            },
+        }),
+    )
+}
+
+/// The account the app is logged in as: the cloud, without a quota
+async fn user(
+    State(server_state): State<CloudServerState>,
+    Path(version): Path<String>,
+) -> Response {
+    let name = &server_state.cloud.name;
+    ocs_response(
+        &version,
+        json!({
+            "id": name,
+            "display-name": name,
+            "displayname": name,
+            "email": null,
+            // -3 is Nextcloud's "unlimited"
+            "quota": { "free": -3, "used": 0, "total": -3, "relative": 0, "quota": -3 },
+        }),
+    )
+}
+
+/// Remove a cloud's chunked uploads that were never finished
+pub(crate) async fn remove_stale_uploads(cloud_name: &str) {
+    dav::remove_stale_uploads(&dav::cloud_uploads_dir(cloud_name)).await;
+}
+
+/// The password of a `Basic` Authorization header
+fn basic_auth_password(headers: &HeaderMap) -> Option<String> {
+    let encoded = headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Basic ")?;
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    decoded
+        .split_once(':')
+        .map(|(_, password)| password.to_string())
+}
+
+/// Middleware for the DAV and OCS routes: Basic auth with an app password, which is
+/// any API token of the cloud within its scope (the login flow's have the `nextcloud`
+/// scope) or a login token. The cloud password itself isn't accepted, hashing it on
+/// every request of a sync would be far too slow.
+async fn require_app_password(
+    State(server_state): State<CloudServerState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let password = basic_auth_password(request.headers());
+    if let Some(password) = &password {
+        if let Ok(claims) = server_state.auth_state.verify_token(password) {
+            // For the folder hooks
+            let user = AuthenticatedUser {
+                claims: Some(claims),
+                method: AuthMethod::Bearer,
+                scope: ApiTokenScope::Full,
+                api_token: None,
+            };
+            crate::clients::identify(&request, &user);
+            request.extensions_mut().insert(user);
+            return next.run(request).await;
+        }
+        if let Some(token) = server_state.auth_state.api_tokens.verify(password) {
+            if !token.scope.allows(request.method(), request.uri().path()) {
+                return auth::out_of_scope(token.scope);
+            }
+            let user = AuthenticatedUser::from_api_token(token);
+            crate::clients::identify(&request, &user);
+            request.extensions_mut().insert(user);
+            return next.run(request).await;
+        }
+        server_state.auth_state.record_failure(&AuthFailure::new(
+            peer.ip(),
+            request.headers(),
+            &server_state.cloud.name,
+            "nextcloud_bad_app_password",
+        ));
+    }
+    (
+        StatusCode::UNAUTHORIZED,
+        [(
+            WWW_AUTHENTICATE,
+            "Basic realm=\"CloudHost\", charset=\"UTF-8\"",
+        )],
+    )
+        .into_response()
+}
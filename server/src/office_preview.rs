@@ -0,0 +1,417 @@
+/// Office document previews: Word, Excel and PowerPoint files (and their OpenDocument
+/// counterparts) are converted to PDF by an external converter, so browsers can show them
+/// inline. The converter is configured per cloud: LibreOffice in headless mode, an
+/// OnlyOffice Document Server, or any command. PDFs are cached in
+/// `preview-cache/<cloud>/` in the data directory by the SHA-256 of the document, so a
+/// document is converted once however often it's viewed, moved or copied.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::process::Command;
+
+use crate::api_routes::camera::sha256_file;
+use crate::config_paths;
+
+/// Extensions of the documents that can be previewed
+pub const PREVIEW_EXTENSIONS: &[&str] = &[
+    "doc", "docx", "odt", "rtf", "xls", "xlsx", "ods", "ppt", "pptx", "odp",
+];
+/// A converter taking longer is killed
+const CONVERT_TIMEOUT: Duration = Duration::from_secs(120);
+/// How long the OnlyOffice server may fetch a document after it was handed the link
+const SOURCE_LINK_LIFETIME: Duration = Duration::from_secs(5 * 60);
+/// PDFs not viewed for this long are deleted
+const CACHE_LIFETIME: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+fn default_soffice_path() -> PathBuf {
+    PathBuf::from("soffice")
+}
+
+fn is_default_soffice_path(path: &Path) -> bool {
+    path == default_soffice_path()
+}
+
+/// `[clouds.office_preview]` in the clouds config, picked by `converter`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "converter", rename_all = "lowercase")]
+pub enum OfficePreviewConfig {
+    /// `soffice --headless --convert-to pdf`
+    LibreOffice {
+        /// Looked up in `PATH` unless it's a path
+        #[serde(
+            default = "default_soffice_path",
+            skip_serializing_if = "is_default_soffice_path"
+        )]
+        soffice_path: PathBuf,
+    },
+    /// The conversion API of an OnlyOffice Document Server
+    OnlyOffice {
+        /// Where the Document Server is, e.g. `http://localhost:8080`
+        url: String,
+        /// Where the Document Server reaches this cloud, to fetch the documents
+        public_url: String,
+        /// The Document Server's JWT secret, if it has JWT enabled
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        jwt_secret: Option<String>,
+    },
+    /// Any program; `{input}` and `{output}` in the arguments are replaced with the
+    /// document and the PDF to write
+    Command { command: Vec<String> },
+}
+
+impl OfficePreviewConfig {
+    /// Problems with the settings, as `field: message`
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let is_http = |url: &str| url.starts_with("http://") || url.starts_with("https://");
+        match self {
+            Self::LibreOffice { soffice_path } => {
+                if soffice_path.as_os_str().is_empty() {
+                    problems.push("soffice_path: must not be empty".to_string());
+                }
+            }
+            Self::OnlyOffice {
+                url, public_url, ..
+            } => {
+                if !is_http(url) {
+                    problems.push(format!("url: '{}' is not an http(s) URL", url));
+                }
+                if !is_http(public_url) {
+                    problems.push(format!(
+                        "public_url: '{}' is not an http(s) URL",
+                        public_url
+                    ));
+                }
+            }
+            Self::Command { command } => {
+                if command.is_empty() {
+                    problems.push("command: must not be empty".to_string());
+                }
+                for placeholder in ["{input}", "{output}"] {
+                    if !command.iter().any(|arg| arg.contains(placeholder)) {
+                        problems.push(format!("command: must contain {}", placeholder));
+                    }
+                }
+            }
+        }
+        problems
+    }
+}
+
+/// Whether `path` is a document the previews handle, by its extension
+pub fn is_previewable(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            PREVIEW_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+        })
+}
+
+pub struct OfficePreview {
+    config: OfficePreviewConfig,
+    cache_dir: PathBuf,
+    /// One conversion at a time: LibreOffice can't run twice on one profile, and
+    /// waiting requests for the same document then find it cached
+    converting: tokio::sync::Mutex<()>,
+    /// Documents the OnlyOffice server may fetch, by link token
+    source_links: Mutex<HashMap<String, (PathBuf, Instant)>>,
+}
+
+impl OfficePreview {
+    pub fn new(cloud_name: &str, config: &OfficePreviewConfig) -> Self {
+        Self {
+            config: config.clone(),
+            cache_dir: get_preview_cache_dir(cloud_name),
+            converting: tokio::sync::Mutex::new(()),
+            source_links: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The PDF of the document at `source`, converted unless it's cached
+    pub async fn pdf(&self, source: &Path) -> Result<PathBuf, String> {
+        let hash_source = source.to_path_buf();
+        let hash = tokio::task::spawn_blocking(move || sha256_file(&hash_source))
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| format!("Failed to read the document: {}", e))?;
+        let pdf = self.cache_dir.join(format!("{}.pdf", hash));
+        if pdf.is_file() {
+            touch(&pdf);
+            return Ok(pdf);
+        }
+
+        let _converting = self.converting.lock().await;
+        if pdf.is_file() {
+            return Ok(pdf);
+        }
+        tokio::fs::create_dir_all(&self.cache_dir)
+            .await
+            .map_err(|e| format!("Failed to create the preview cache: {}", e))?;
+        self.prune_cache();
+
+        let partial = self.cache_dir.join(format!("{}.pdf.part", hash));
+        let started = Instant::now();
+        let converted =
+            tokio::time::timeout(CONVERT_TIMEOUT, self.convert(source, &hash, &partial))
+                .await
+                .unwrap_or_else(|_| Err("The converter took too long".to_string()));
+        match converted {
+            Ok(()) => {
+                tokio::fs::rename(&partial, &pdf)
+                    .await
+                    .map_err(|e| format!("Failed to store the preview: {}", e))?;
+                tracing::info!(
+                    elapsed_ms = started.elapsed().as_millis() as u64,
+                    "Converted '{}' to PDF",
+                    source.display()
+                );
+                Ok(pdf)
+            }
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&partial).await;
+                tracing::warn!("Converting '{}' to PDF failed: {}", source.display(), e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Write the PDF of `source` to `output`
+    async fn convert(&self, source: &Path, hash: &str, output: &Path) -> Result<(), String> {
+        match &self.config {
+            OfficePreviewConfig::LibreOffice { soffice_path } => {
+                self.convert_libreoffice(soffice_path, source, hash, output)
+                    .await
+            }
+            OfficePreviewConfig::OnlyOffice {
+                url,
+                public_url,
+                jwt_secret,
+            } => {
+                self.convert_onlyoffice(
+                    url,
+                    public_url,
+                    jwt_secret.as_deref(),
+                    source,
+                    hash,
+                    output,
+                )
+                .await
+            }
+            OfficePreviewConfig::Command { command } => {
+                let args: Vec<String> = command
+                    .iter()
+                    .map(|arg| {
+                        arg.replace("{input}", &source.to_string_lossy())
+                            .replace("{output}", &output.to_string_lossy())
+                    })
+                    .collect();
+                let (program, args) = args
+                    .split_first()
+                    .ok_or_else(|| "No command configured".to_string())?;
+                run(Command::new(program).args(args)).await?;
+                if !output.is_file() {
+                    return Err(format!("'{}' didn't write the PDF", program));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// LibreOffice names the PDF after the document, so it's written to a directory of
+    /// its own. It gets a profile of its own too, so it doesn't clash with a LibreOffice
+    /// the user has open.
+    async fn convert_libreoffice(
+        &self,
+        soffice_path: &Path,
+        source: &Path,
+        hash: &str,
+        output: &Path,
+    ) -> Result<(), String> {
+        let out_dir = self.cache_dir.join(format!("{}.out", hash));
+        let profile = self.cache_dir.join("libreoffice-profile");
+        let result = async {
+            run(Command::new(soffice_path)
+                .arg(format!("-env:UserInstallation={}", file_url(&profile)))
+                .args([
+                    "--headless",
+                    "--norestore",
+                    "--convert-to",
+                    "pdf",
+                    "--outdir",
+                ])
+                .arg(&out_dir)
+                .arg(source))
+            .await?;
+            let pdf = out_dir
+                .join(source.file_stem().unwrap_or_default())
+                .with_extension("pdf");
+            tokio::fs::rename(&pdf, output)
+                .await
+                .map_err(|_| "LibreOffice didn't write the PDF".to_string())
+        }
+        .await;
+        let _ = tokio::fs::remove_dir_all(&out_dir).await;
+        result
+    }
+
+    /// Hand the Document Server a short-lived link to the document and download the
+    /// PDF it makes
+    async fn convert_onlyoffice(
+        &self,
+        url: &str,
+        public_url: &str,
+        jwt_secret: Option<&str>,
+        source: &Path,
+        hash: &str,
+        output: &Path,
+    ) -> Result<(), String> {
+        let link_token = uuid::Uuid::new_v4().simple().to_string();
+        if let Ok(mut links) = self.source_links.lock() {
+            links.retain(|_, (_, created)| created.elapsed() < SOURCE_LINK_LIFETIME);
+            links.insert(link_token.clone(), (source.to_path_buf(), Instant::now()));
+        }
+        let result = async {
+            let extension = source
+                .extension()
+                .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+                .unwrap_or_default();
+            let mut request = serde_json::json!({
+                "async": false,
+                "filetype": extension,
+                // The Document Server caches by key, the content hash is exactly that
+                "key": hash,
+                "outputtype": "pdf",
+                "title": source.file_name().map(|name| name.to_string_lossy()),
+                "url": format!(
+                    "{}/preview-source/{}",
+                    public_url.trim_end_matches('/'),
+                    link_token
+                ),
+            });
+            if let Some(secret) = jwt_secret {
+                let token = jsonwebtoken::encode(
+                    &jsonwebtoken::Header::default(),
+                    &request,
+                    &jsonwebtoken::EncodingKey::from_secret(secret.as_bytes()),
+                )
+                .map_err(|e| format!("Failed to sign the request: {}", e))?;
+                request["token"] = serde_json::Value::String(token);
+            }
+
+            let client = reqwest::Client::new();
+            let response: serde_json::Value = client
+                .post(format!("{}/ConvertService.ashx", url.trim_end_matches('/')))
+                .header(reqwest::header::ACCEPT, "application/json")
+                .json(&request)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(|e| format!("OnlyOffice request failed: {}", e))?
+                .json()
+                .await
+                .map_err(|e| format!("Unexpected OnlyOffice response: {}", e))?;
+            if let Some(code) = response.get("error") {
+                return Err(format!("OnlyOffice conversion failed with error {}", code));
+            }
+            let file_url = response
+                .get("fileUrl")
+                .and_then(|url| url.as_str())
+                .ok_or_else(|| "OnlyOffice didn't finish the conversion".to_string())?;
+            let pdf = client
+                .get(file_url)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(|e| format!("Failed to download the PDF: {}", e))?
+                .bytes()
+                .await
+                .map_err(|e| format!("Failed to download the PDF: {}", e))?;
+            tokio::fs::write(output, pdf)
+                .await
+                .map_err(|e| format!("Failed to store the PDF: {}", e))
+        }
+        .await;
+        if let Ok(mut links) = self.source_links.lock() {
+            links.remove(&link_token);
+        }
+        result
+    }
+
+    /// The document behind an OnlyOffice source link, while the link is valid
+    pub fn source_link(&self, link_token: &str) -> Option<PathBuf> {
+        let links = self.source_links.lock().ok()?;
+        let (path, created) = links.get(link_token)?;
+        (created.elapsed() < SOURCE_LINK_LIFETIME).then(|| path.clone())
+    }
+
+    /// Delete PDFs not viewed for a while. Errors are ignored.
+    fn prune_cache(&self) {
+        let Ok(entries) = std::fs::read_dir(&self.cache_dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let unused = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age > CACHE_LIFETIME);
+            if unused && path.extension().is_some_and(|extension| extension == "pdf") {
+                let _ = std::fs::remove_file(&path);
+            }
+        }
+    }
+}
+
+/// Run a converter, returns the end of its output if it fails. It's killed if this
+/// future is dropped.
+async fn run(command: &mut Command) -> Result<(), String> {
+    let program = command.as_std().get_program().to_string_lossy().to_string();
+    let output = command
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run '{}': {}", program, e))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let last_line = stderr.lines().rev().find(|line| !line.trim().is_empty());
+    Err(format!(
+        "'{}' exited with {}{}",
+        program,
+        output.status,
+        last_line
+            .map(|line| format!(": {}", line.trim()))
+            .unwrap_or_default()
+    ))
+}
+
+/// `file://` URL of a local path, as LibreOffice wants its profile directory
+fn file_url(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    if path.starts_with('/') {
+        format!("file://{}", path)
+    } else {
+        format!("file:///{}", path)
+    }
+}
+
+/// Mark a PDF as viewed, so pruning keeps it
+fn touch(path: &Path) {
+    if let Ok(file) = std::fs::File::options().append(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+/// `preview-cache/<cloud>` in the data directory, with unusual characters replaced
+pub fn get_preview_cache_dir(cloud_name: &str) -> PathBuf {
+    config_paths::get_data_dir()
+        .join("preview-cache")
+        .join(config_paths::cloud_file_stem(cloud_name))
+}
@@ -1,20 +1,62 @@
 use crate::debug_stream::DebugStream;
 use crate::{
+    api_tokens::{ApiToken, ApiTokenScope},
     auth::AuthState,
-    cloud::{Cloud, CloudFolder, CloudServer},
+    auth_log::AuthFailureLog,
+    clients::ClientInfo,
+    cloud::{Cloud, CloudFolder, CloudServer, CloudStatus},
     clouds_config::CloudsConfig,
+    config_paths::Overrides,
+    config_transfer::{self, ConfigExport, ImportSummary},
+    defaults::ServerDefaults,
+    disk_space::{self, FolderSpace, SpaceLevel},
+    email::{EmailConfig, EmailNotifier},
     error::{ServerError, ServerResult},
+    file_requests::FileRequest,
+    folder_health::{FolderChange, FolderHealth},
+    landing::{LandingCloud, LandingServer},
+    log_store::{LogFilter, LogStore},
+    scheduler::{DiskUsage, RunningCloud, Scheduler, TaskContext, TaskKind, TaskStatus},
+    secrets::{SecretsKey, SecretsProtection},
+    sessions::RevokedSession,
+    webhooks::{WebhookDelivery, WebhookEvent, Webhooks},
 };
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 
-const BASE_PORT: u16 = 3000;
+/// Port of the first cloud started; the next ones get the following ports
+pub const BASE_PORT: u16 = 3000;
+
+/// How to settle unsaved changes when the clouds config changed on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictResolution {
+    /// Apply our changes on top of the file on disk
+    Merge,
+    /// Overwrite the file with our version
+    KeepMine,
+    /// Drop our changes and load the file
+    KeepTheirs,
+}
 
 /// Orchestrator - manages multiple clouds and their server lifecycle
 pub struct Orchestrator {
     pub running_clouds: HashMap<String, CloudServer>, // cloud_name -> CloudServer (running)
     pub clouds_config: CloudsConfig,
     pub next_port: u16,
+    /// Why the clouds config couldn't be loaded at startup, until the UI takes it
+    config_error: Option<ServerError>,
+    /// The config as it was on disk when last loaded or saved, base for merging conflicts
+    base_config: CloudsConfig,
+    /// A save failed because the file changed on disk; the change is only in memory
+    config_conflict: bool,
+    scheduler: Scheduler,
+    /// Why the last start of a cloud failed, until it's started again
+    failed_starts: HashMap<String, String>,
+    /// Page listing all clouds, when `landing_port` is set
+    landing: Option<LandingServer>,
+    /// Cloud folders found unavailable, shared with the running clouds
+    folder_health: Arc<FolderHealth>,
 }
 
 impl Default for Orchestrator {
@@ -25,31 +67,100 @@ impl Default for Orchestrator {
 
 impl Orchestrator {
     pub fn new() -> Self {
-        let clouds_config =
-            CloudsConfig::load_from_file().unwrap_or_else(|_e| CloudsConfig::default());
-
-        // Note: data directory check removed as it's not critical
+        let (clouds_config, config_error) = match CloudsConfig::load_from_file() {
+            Ok(config) => (config, None),
+            Err(e) => {
+                let (config, source) = Self::fallback_config();
+                let error = ServerError::Configuration(format!(
+                    "{}\nRunning with {}; the broken file was kept as clouds-config.toml.invalid",
+                    match e {
+                        ServerError::Configuration(msg) => msg,
+                        other => other.to_string(),
+                    },
+                    source
+                ));
+                (config, Some(error))
+            }
+        };
 
         Self {
             running_clouds: HashMap::new(),
+            base_config: clouds_config.clone(),
+            next_port: effective_defaults(&clouds_config).base_port,
             clouds_config,
-            next_port: BASE_PORT,
+            config_error,
+            config_conflict: false,
+            scheduler: Scheduler::default(),
+            failed_starts: HashMap::new(),
+            landing: None,
+            folder_health: Arc::new(FolderHealth::default()),
         }
     }
 
+    /// Config to run with when the file is broken: the last good backup, or an empty one.
+    /// The broken file is kept next to it so the next save doesn't lose it.
+    fn fallback_config() -> (CloudsConfig, &'static str) {
+        let config_path = crate::config_paths::get_clouds_config_path();
+        let _ = std::fs::copy(&config_path, config_path.with_extension("toml.invalid"));
+
+        let (mut config, source) = match std::fs::read_to_string(CloudsConfig::get_backup_path())
+            .ok()
+            .and_then(|content| CloudsConfig::parse(&content).ok())
+        {
+            Some(config) => (config, "the last good config (clouds-config.toml.bak)"),
+            None => (CloudsConfig::default(), "an empty config"),
+        };
+        // The broken file is meant to be replaced by the next save
+        config.adopt_disk_file();
+        (config, source)
+    }
+
+    /// Take the error from loading the clouds config at startup, if there was one
+    pub fn take_config_error(&mut self) -> Option<ServerError> {
+        self.config_error.take()
+    }
+
     /// Start a server for a specific cloud
     pub async fn start_cloud(&mut self, cloud_name: &str) -> ServerResult<u16> {
-        // Check if already running
-        if self.running_clouds.contains_key(cloud_name) {
-            return Err(ServerError::ServerAlreadyRunning);
+        let result = self.try_start_cloud(cloud_name).await;
+        match &result {
+            Ok(_) => self.failed_starts.remove(cloud_name),
+            Err(ServerError::ServerAlreadyRunning) => None,
+            Err(e) => self
+                .failed_starts
+                .insert(cloud_name.to_string(), e.to_string()),
+        };
+        result
+    }
+
+    async fn try_start_cloud(&mut self, cloud_name: &str) -> ServerResult<u16> {
+        match self.running_clouds.get(cloud_name) {
+            Some(cloud_server) if cloud_server.is_server_running() => {
+                return Err(ServerError::ServerAlreadyRunning)
+            }
+            // Its server ended on its own; clean up before starting again
+            Some(_) => {
+                if let Some(mut cloud_server) = self.running_clouds.remove(cloud_name) {
+                    let _ = cloud_server.stop_server().await;
+                }
+            }
+            None => {}
         }
 
-        // Get the cloud from config
+        // Get the cloud from config, with the server defaults filled in
         let cloud = self
             .clouds_config
             .get_cloud(cloud_name)
+            .map(|cloud| self.defaults().apply(cloud))
             .ok_or_else(|| ServerError::Validation(format!("Cloud '{}' not found", cloud_name)))?;
 
+        if self.clouds_config.secrets_locked() {
+            return Err(ServerError::Authentication(
+                "Cloud secrets are locked. Unlock them with the master passphrase first."
+                    .to_string(),
+            ));
+        }
+
         // Check if cloud has password
         if !cloud.has_password() {
             return Err(ServerError::Validation(format!(
@@ -67,30 +178,67 @@ impl Orchestrator {
         }
 
         // Create AuthState for this cloud
-        let auth_state = Arc::new(AuthState::new(
+        let mut auth_state = AuthState::new(
             cloud.jwt_secret.clone(),
             cloud.password.clone(),
             cloud.password_changed_at,
-        ));
+            self.clouds_config.password_hashing,
+        )
+        .with_failure_log(Arc::new(AuthFailureLog::new(
+            self.clouds_config.auth_failure_hook.clone(),
+        )))
+        .with_api_tokens(cloud.api_tokens.clone())
+        .with_sessions(cloud.sessions)
+        .with_revoked_sessions(cloud.revoked_sessions.clone())
+        .with_webhooks(Webhooks::new(cloud_name, cloud.webhooks.clone()));
+        if let Some(email) = &cloud.email {
+            auth_state = auth_state.with_notifier(Arc::new(EmailNotifier::new(cloud_name, email)));
+        }
+        let auth_state = Arc::new(auth_state);
 
-        // Assign port
-        let port = self.next_port;
-        self.next_port += 1;
+        let debug_stream = Arc::new(
+            DebugStream::new(100)
+                .with_store(LogStore::for_cloud(cloud_name))
+                .with_levels(cloud.logging.clone()),
+        );
 
-        // Create and start the cloud server
-        let mut cloud_server = CloudServer::new(cloud.clone(), port);
-        cloud_server
-            .start_server(auth_state, Arc::new(DebugStream::new(100)))
-            .await?;
+        // Assign a port; one taken by another program is skipped for the next
+        let mut retries = self.clouds_config.port_retries;
+        loop {
+            let port = self.next_port;
+            self.next_port = self.next_port.saturating_add(1);
 
-        self.running_clouds
-            .insert(cloud_name.to_string(), cloud_server);
-
-        Ok(port)
+            let mut cloud_server = CloudServer::new(cloud.clone(), port)
+                .with_bind_address(self.defaults().bind_address)
+                .with_folder_health(self.folder_health.clone());
+            match cloud_server
+                .start_server(auth_state.clone(), debug_stream.clone())
+                .await
+            {
+                Ok(()) => {
+                    self.running_clouds
+                        .insert(cloud_name.to_string(), cloud_server);
+                    return Ok(port);
+                }
+                Err(ServerError::PortInUse(port)) if retries > 0 => {
+                    retries -= 1;
+                    tracing::warn!(
+                        "Port {} is in use, trying {} for cloud '{}'",
+                        port,
+                        self.next_port,
+                        cloud_name
+                    );
+                }
+                Err(e) => return Err(e),
+            }
+        }
     }
 
     /// Stop a specific cloud's server
     pub async fn stop_cloud(&mut self, cloud_name: &str) -> ServerResult<()> {
+        // Uploads counted since the last save would be forgotten with the server
+        self.persist_file_request_changes()?;
+        self.failed_starts.remove(cloud_name);
         if let Some(mut cloud_server) = self.running_clouds.remove(cloud_name) {
             cloud_server.stop_server().await?;
             Ok(())
@@ -108,19 +256,34 @@ impl Orchestrator {
         }
 
         self.running_clouds.clear();
-        self.next_port = BASE_PORT;
+        self.next_port = self.defaults().base_port;
 
         Ok(())
     }
 
     /// Check if a specific cloud is running
     pub fn is_cloud_running(&self, cloud_name: &str) -> bool {
-        self.running_clouds.contains_key(cloud_name)
+        self.running_clouds
+            .get(cloud_name)
+            .is_some_and(CloudServer::is_server_running)
+    }
+
+    /// Whether a cloud is starting, running, stopped or failed, and why
+    pub fn cloud_status(&self, cloud_name: &str) -> CloudStatus {
+        match self.running_clouds.get(cloud_name) {
+            Some(cloud_server) => cloud_server.status(),
+            None => match self.failed_starts.get(cloud_name) {
+                Some(error) => CloudStatus::Failed(error.clone()),
+                None => CloudStatus::Stopped,
+            },
+        }
     }
 
     /// Check if any cloud is running
     pub fn is_any_running(&self) -> bool {
-        !self.running_clouds.is_empty()
+        self.running_clouds
+            .values()
+            .any(CloudServer::is_server_running)
     }
 
     /// Get port for a specific cloud
@@ -149,6 +312,109 @@ impl Orchestrator {
             .collect()
     }
 
+    /// Ports this process listens on: running clouds, their FTP and SFTP listeners and the landing
+    /// page, for the doctor to tell apart from ports other programs took
+    pub fn own_ports(&self) -> Vec<u16> {
+        let mut ports: Vec<u16> = self.get_running_clouds().into_values().collect();
+        ports.extend(
+            self.running_clouds
+                .values()
+                .filter_map(|cloud_server| cloud_server.cloud.ftp.as_ref())
+                .map(|ftp| ftp.port),
+        );
+        ports.extend(
+            self.running_clouds
+                .values()
+                .filter_map(|cloud_server| cloud_server.cloud.sftp.as_ref())
+                .map(|sftp| sftp.port),
+        );
+        ports.extend(self.landing.as_ref().map(|landing| landing.port));
+        ports
+    }
+
+    // ========== Server Defaults ==========
+
+    /// The server defaults in effect: those of the clouds config with the environment
+    /// overrides on top
+    pub fn defaults(&self) -> ServerDefaults {
+        effective_defaults(&self.clouds_config)
+    }
+
+    /// Replace the server defaults. Running clouds take a new free space limit right away,
+    /// the rest applies to clouds started from now on, and the base port once none runs.
+    pub fn set_defaults(&mut self, defaults: ServerDefaults) -> ServerResult<()> {
+        let problems = defaults.validate();
+        if !problems.is_empty() {
+            return Err(ServerError::Validation(problems.join("; ")));
+        }
+        self.clouds_config.defaults = defaults;
+        self.save_config()?;
+        let defaults = self.defaults();
+        if !self.is_any_running() {
+            self.next_port = defaults.base_port;
+        }
+        for (cloud_name, cloud_server) in self.running_clouds.iter_mut() {
+            let Some(cloud) = self.clouds_config.get_cloud(cloud_name) else {
+                continue;
+            };
+            if let Err(e) = cloud_server.update_cloud(&defaults.apply(cloud)) {
+                tracing::warn!(
+                    "Cloud '{}' couldn't take the new defaults: {}",
+                    cloud_name,
+                    e
+                );
+            }
+        }
+        Ok(())
+    }
+
+    // ========== Landing Page ==========
+
+    /// Start the landing page when `landing_port` is set; returns its port
+    pub async fn start_landing(&mut self) -> ServerResult<Option<u16>> {
+        let Some(port) = self.clouds_config.landing_port else {
+            self.landing = None;
+            return Ok(None);
+        };
+        let address = self.defaults().bind_address;
+        if self
+            .landing
+            .as_ref()
+            .is_some_and(|landing| landing.port == port && landing.address == address)
+        {
+            return Ok(Some(port));
+        }
+        // Free the old port first when it changed
+        self.landing = None;
+        self.landing = Some(LandingServer::start(address, port).await?);
+        self.refresh_landing();
+        Ok(Some(port))
+    }
+
+    /// Update the clouds the landing page lists, their status and storage used
+    pub fn refresh_landing(&self) {
+        let Some(landing) = &self.landing else {
+            return;
+        };
+        let clouds = self
+            .clouds_config
+            .clouds
+            .iter()
+            .map(|cloud| {
+                let running = self.running_clouds.get(&cloud.name);
+                LandingCloud {
+                    name: cloud.name.clone(),
+                    status: self.cloud_status(&cloud.name),
+                    port: running.and_then(CloudServer::get_server_port),
+                    used_bytes: self.disk_usage(&cloud.name).map(|usage| usage.bytes),
+                    auth_state: running.and_then(|cloud_server| cloud_server.auth_state.clone()),
+                    guest_access: cloud.guest_access,
+                }
+            })
+            .collect();
+        landing.set_clouds(clouds);
+    }
+
     // ========== Clouds Management ==========
 
     /// Get all clouds
@@ -164,24 +430,108 @@ impl Orchestrator {
     /// Add a cloud
     pub fn add_cloud(&mut self, cloud: Cloud) -> ServerResult<()> {
         self.clouds_config.add_cloud(cloud)?;
-        self.clouds_config.save_to_file()?;
+        self.save_config()?;
         Ok(())
     }
 
     /// Remove a cloud
     pub fn remove_cloud(&mut self, cloud_name: &str) -> ServerResult<()> {
         self.clouds_config.remove_cloud(cloud_name)?;
-        self.clouds_config.save_to_file()?;
+        self.save_config()?;
         Ok(())
     }
 
-    /// Update a cloud
+    /// Update a cloud. A running server takes the change right away when it can,
+    /// otherwise when it's restarted.
     pub fn update_cloud(&mut self, old_name: &str, new_cloud: Cloud) -> ServerResult<()> {
+        let previous = self.clouds_config.get_cloud(old_name).cloned();
         self.clouds_config.update_cloud(old_name, new_cloud)?;
-        self.clouds_config.save_to_file()?;
+        self.save_config()?;
+        if let Some(previous) = previous {
+            self.update_running_cloud(&previous);
+        }
         Ok(())
     }
 
+    /// Bring the running server of a cloud up to date with the config after the cloud
+    /// changed from `previous`. Changes `Cloud::is_live_update` allows are handed to the
+    /// server, whose connections carry on. Returns false if the server has to be restarted
+    /// for the change instead; a cloud that isn't running picks it up when it starts.
+    pub fn apply_cloud_changes(&mut self, previous: &Cloud) -> ServerResult<bool> {
+        let defaults = self.defaults();
+        let Some(cloud_server) = self.running_clouds.get_mut(&previous.name) else {
+            return Ok(true);
+        };
+        // Renamed or removed
+        let Some(cloud) = self.clouds_config.get_cloud(&previous.name) else {
+            return Ok(false);
+        };
+        if !previous.is_live_update(cloud) {
+            return Ok(false);
+        }
+        cloud_server.update_cloud(&defaults.apply(cloud))?;
+        Ok(true)
+    }
+
+    /// `apply_cloud_changes` after an edit, which is saved whether or not it could be applied
+    fn update_running_cloud(&mut self, previous: &Cloud) {
+        match self.apply_cloud_changes(previous) {
+            Ok(true) => {}
+            Ok(false) => tracing::info!(
+                "Cloud '{}' changed, restart it for the change to take effect",
+                previous.name
+            ),
+            Err(e) => tracing::warn!(
+                "Cloud '{}' changed, but its server couldn't take the change: {}",
+                previous.name,
+                e
+            ),
+        }
+    }
+
+    /// Replace the tags of a cloud
+    pub fn set_cloud_tags(&mut self, cloud_name: &str, tags: Vec<String>) -> ServerResult<()> {
+        let mut cloud = self
+            .clouds_config
+            .get_cloud(cloud_name)
+            .ok_or_else(|| ServerError::Validation(format!("Cloud '{}' not found", cloud_name)))?
+            .clone();
+        if let Some(tag) = tags
+            .iter()
+            .find(|tag| tag.is_empty() || tag.contains(char::is_whitespace))
+        {
+            return Err(ServerError::Validation(format!(
+                "'{}' is not a valid tag, tags are single words",
+                tag
+            )));
+        }
+        let mut unique: Vec<String> = Vec::new();
+        for tag in tags {
+            if !unique.contains(&tag) {
+                unique.push(tag);
+            }
+        }
+        let previous = cloud.clone();
+        cloud.tags = unique;
+        self.clouds_config.update_cloud(cloud_name, cloud)?;
+        self.save_config()?;
+        self.update_running_cloud(&previous);
+        Ok(())
+    }
+
+    /// All tags used by the clouds, sorted
+    pub fn cloud_tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .clouds_config
+            .clouds
+            .iter()
+            .flat_map(|cloud| cloud.tags.iter().cloned())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
     // ========== Cloud Folders Management ==========
 
     /// Get all cloud folders
@@ -192,14 +542,14 @@ impl Orchestrator {
     /// Add a cloud folder
     pub fn add_cloud_folder(&mut self, cloud_folder: CloudFolder) -> ServerResult<()> {
         self.clouds_config.add_cloud_folder(cloud_folder)?;
-        self.clouds_config.save_to_file()?;
+        self.save_config()?;
         Ok(())
     }
 
     /// Remove a cloud folder
     pub fn remove_cloud_folder(&mut self, cloud_folder_name: &str) -> ServerResult<()> {
         self.clouds_config.remove_cloud_folder(cloud_folder_name)?;
-        self.clouds_config.save_to_file()?;
+        self.save_config()?;
         Ok(())
     }
 
@@ -211,7 +561,7 @@ impl Orchestrator {
     ) -> ServerResult<()> {
         self.clouds_config
             .update_cloud_folder(old_name, new_cloud_folder)?;
-        self.clouds_config.save_to_file()?;
+        self.save_config()?;
         Ok(())
     }
 
@@ -227,17 +577,496 @@ impl Orchestrator {
             .clone();
 
         // Set the password
-        cloud
-            .set_password(password)
-            .map_err(|e| ServerError::Internal(format!("Failed to set password: {}", e)))?;
+        cloud.set_password(password, &self.clouds_config.password_hashing)?;
 
         // Update the cloud in config
         self.clouds_config.update_cloud(cloud_name, cloud)?;
-        self.clouds_config.save_to_file()?;
+        self.save_config()?;
 
         Ok(())
     }
 
+    /// Change a cloud's password, checking the current one first. Sessions and Nextcloud
+    /// app passwords made with the old password stop working right away, also on a running
+    /// server; API tokens are left alone.
+    pub fn change_cloud_password(
+        &mut self,
+        cloud_name: &str,
+        current_password: &str,
+        new_password: &str,
+    ) -> ServerResult<()> {
+        let mut cloud = self
+            .clouds_config
+            .get_cloud(cloud_name)
+            .ok_or_else(|| ServerError::Validation(format!("Cloud '{}' not found", cloud_name)))?
+            .clone();
+        if !cloud.has_password() {
+            return Err(ServerError::Validation(format!(
+                "Cloud '{}' has no password yet, set one instead",
+                cloud_name
+            )));
+        }
+        if !cloud.verify_password(current_password) {
+            return Err(ServerError::Authentication(
+                "The current password is wrong".to_string(),
+            ));
+        }
+        if new_password.len() < 8 {
+            return Err(ServerError::Validation(
+                "Password must be at least 8 characters".to_string(),
+            ));
+        }
+        if new_password == current_password {
+            return Err(ServerError::Validation(
+                "The new password is the same as the current one".to_string(),
+            ));
+        }
+
+        cloud.set_password(new_password, &self.clouds_config.password_hashing)?;
+        let (password, changed_at) = (cloud.password.clone(), cloud.password_changed_at);
+        self.clouds_config.update_cloud(cloud_name, cloud)?;
+        self.save_config()?;
+
+        if let Some(auth_state) = self
+            .running_clouds
+            .get(cloud_name)
+            .and_then(|cloud_server| cloud_server.auth_state.as_ref())
+        {
+            auth_state.replace_password(password, changed_at);
+        }
+        Ok(())
+    }
+
+    /// Save password hashes that logins upgraded from plaintext or outdated parameters.
+    /// `password_changed_at` stays the same so existing sessions remain valid.
+    /// Returns the names of the clouds whose hash was saved.
+    pub fn persist_password_upgrades(&mut self) -> ServerResult<Vec<String>> {
+        let upgrades: Vec<(String, String)> = self
+            .running_clouds
+            .iter()
+            .filter_map(|(name, cloud_server)| {
+                let hash = cloud_server.auth_state.as_ref()?.take_upgraded_password()?;
+                Some((name.clone(), hash))
+            })
+            .collect();
+
+        let mut upgraded = Vec::new();
+        for (name, hash) in upgrades {
+            if let Some(cloud) = self
+                .clouds_config
+                .clouds
+                .iter_mut()
+                .find(|c| c.name == name)
+            {
+                cloud.password = Some(hash);
+                upgraded.push(name);
+            }
+        }
+        if !upgraded.is_empty() {
+            self.save_config()?;
+        }
+        Ok(upgraded)
+    }
+
+    /// Issue a one-time code for setting a new password on the running cloud's login
+    /// page. Only shown locally, in the TUI.
+    pub fn issue_recovery_code(&self, cloud_name: &str) -> ServerResult<String> {
+        let auth_state = self
+            .running_clouds
+            .get(cloud_name)
+            .filter(|cloud_server| cloud_server.is_server_running())
+            .and_then(|cloud_server| cloud_server.auth_state.as_ref())
+            .ok_or(ServerError::ServerNotRunning)?;
+        Ok(auth_state.recovery.issue())
+    }
+
+    /// Save passwords that were set with a recovery code.
+    /// Returns the names of the clouds whose password was saved.
+    pub fn persist_recovered_passwords(&mut self) -> ServerResult<Vec<String>> {
+        let recovered: Vec<(String, (String, chrono::DateTime<chrono::Utc>))> = self
+            .running_clouds
+            .iter()
+            .filter_map(|(name, cloud_server)| {
+                let password = cloud_server
+                    .auth_state
+                    .as_ref()?
+                    .take_recovered_password()?;
+                Some((name.clone(), password))
+            })
+            .collect();
+
+        let mut saved = Vec::new();
+        for (name, (hash, changed_at)) in recovered {
+            if let Some(cloud) = self
+                .clouds_config
+                .clouds
+                .iter_mut()
+                .find(|c| c.name == name)
+            {
+                cloud.password = Some(hash);
+                cloud.password_changed_at = Some(changed_at);
+                saved.push(name);
+            }
+        }
+        if !saved.is_empty() {
+            self.save_config()?;
+        }
+        Ok(saved)
+    }
+
+    /// Save the login tokens revoked by logging out of running clouds.
+    /// Returns the names of the clouds whose revoked tokens were saved.
+    pub fn persist_revoked_sessions(&mut self) -> ServerResult<Vec<String>> {
+        let changes: Vec<(String, Vec<RevokedSession>)> = self
+            .running_clouds
+            .iter()
+            .filter_map(|(name, cloud_server)| {
+                let revoked = cloud_server
+                    .auth_state
+                    .as_ref()?
+                    .revoked_sessions
+                    .take_changes()?;
+                Some((name.clone(), revoked))
+            })
+            .collect();
+
+        let mut changed = Vec::new();
+        for (name, revoked) in changes {
+            if let Some(cloud) = self
+                .clouds_config
+                .clouds
+                .iter_mut()
+                .find(|c| c.name == name)
+            {
+                cloud.revoked_sessions = revoked;
+                changed.push(name);
+            }
+        }
+        if !changed.is_empty() {
+            self.save_config()?;
+        }
+        Ok(changed)
+    }
+
+    // ========== API Tokens ==========
+
+    /// Save API tokens that were created or revoked through the API of running clouds.
+    /// Returns the names of the clouds whose tokens were saved.
+    pub fn persist_api_token_changes(&mut self) -> ServerResult<Vec<String>> {
+        let changes: Vec<(String, Vec<ApiToken>)> = self
+            .running_clouds
+            .iter()
+            .filter_map(|(name, cloud_server)| {
+                let tokens = cloud_server
+                    .auth_state
+                    .as_ref()?
+                    .api_tokens
+                    .take_changes()?;
+                Some((name.clone(), tokens))
+            })
+            .collect();
+
+        let mut changed = Vec::new();
+        for (name, tokens) in changes {
+            if let Some(cloud) = self
+                .clouds_config
+                .clouds
+                .iter_mut()
+                .find(|c| c.name == name)
+            {
+                cloud.api_tokens = tokens;
+                changed.push(name);
+            }
+        }
+        if !changed.is_empty() {
+            self.save_config()?;
+        }
+        Ok(changed)
+    }
+
+    /// The API tokens of a cloud, without their secrets
+    pub fn list_api_tokens(&self, cloud_name: &str) -> ServerResult<Vec<ApiToken>> {
+        if let Some(auth_state) = self
+            .running_clouds
+            .get(cloud_name)
+            .and_then(|cloud_server| cloud_server.auth_state.as_ref())
+        {
+            return Ok(auth_state.api_tokens.list());
+        }
+        self.clouds_config
+            .get_cloud(cloud_name)
+            .map(|cloud| cloud.api_tokens.clone())
+            .ok_or_else(|| ServerError::Validation(format!("Cloud '{}' not found", cloud_name)))
+    }
+
+    /// Create an API token for a cloud; returns it with its secret value, which isn't
+    /// stored anywhere
+    pub fn create_api_token(
+        &mut self,
+        cloud_name: &str,
+        name: &str,
+        scope: ApiTokenScope,
+    ) -> ServerResult<(ApiToken, String)> {
+        if name.trim().is_empty() {
+            return Err(ServerError::Validation(
+                "Token name must not be empty".to_string(),
+            ));
+        }
+        let (token, value) = ApiToken::generate(name, scope);
+        self.update_api_tokens(cloud_name, |tokens| tokens.push(token.clone()))?;
+        Ok((token, value))
+    }
+
+    /// Revoke an API token of a cloud by id
+    pub fn revoke_api_token(&mut self, cloud_name: &str, id: &str) -> ServerResult<()> {
+        let mut found = false;
+        self.update_api_tokens(cloud_name, |tokens| {
+            let before = tokens.len();
+            tokens.retain(|token| token.id != id);
+            found = tokens.len() != before;
+        })?;
+        if !found {
+            return Err(ServerError::Validation(format!(
+                "Cloud '{}' has no API token '{}'",
+                cloud_name, id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Change the saved tokens of a cloud and hand them to its server if it's running
+    fn update_api_tokens(
+        &mut self,
+        cloud_name: &str,
+        change: impl FnOnce(&mut Vec<ApiToken>),
+    ) -> ServerResult<()> {
+        // Changes made through the API first, so they aren't overwritten
+        self.persist_api_token_changes()?;
+        let cloud = self
+            .clouds_config
+            .clouds
+            .iter_mut()
+            .find(|c| c.name == cloud_name)
+            .ok_or_else(|| ServerError::Validation(format!("Cloud '{}' not found", cloud_name)))?;
+        change(&mut cloud.api_tokens);
+        let tokens = cloud.api_tokens.clone();
+        self.save_config()?;
+
+        if let Some(auth_state) = self
+            .running_clouds
+            .get(cloud_name)
+            .and_then(|cloud_server| cloud_server.auth_state.as_ref())
+        {
+            auth_state.api_tokens.replace(tokens);
+        }
+        Ok(())
+    }
+
+    // ========== Email ==========
+
+    /// Clouds with email notifications and their settings, for sending test emails
+    pub fn email_configs(&self) -> Vec<(String, EmailConfig)> {
+        self.clouds_config
+            .clouds
+            .iter()
+            .filter_map(|cloud| Some((cloud.name.clone(), cloud.email.clone()?)))
+            .collect()
+    }
+
+    // ========== Webhooks ==========
+
+    /// The last webhook deliveries of a running cloud, newest first
+    pub fn webhook_deliveries(&self, cloud_name: &str) -> ServerResult<Vec<WebhookDelivery>> {
+        if !self
+            .clouds_config
+            .clouds
+            .iter()
+            .any(|c| c.name == cloud_name)
+        {
+            return Err(ServerError::Validation(format!(
+                "Cloud '{}' not found",
+                cloud_name
+            )));
+        }
+        Ok(self
+            .running_clouds
+            .get(cloud_name)
+            .and_then(|cloud_server| cloud_server.auth_state.as_ref())
+            .map(|auth_state| auth_state.webhooks.deliveries())
+            .unwrap_or_default())
+    }
+
+    // ========== Disk space ==========
+
+    /// Folders of all clouds whose filesystem is below the cloud's `warn_free_mb`
+    pub fn low_disk_space(&self) -> Vec<(String, FolderSpace)> {
+        self.clouds_config
+            .clouds
+            .iter()
+            .flat_map(|cloud| {
+                disk_space::folder_space(&cloud.disk_space, &cloud.cloud_folders)
+                    .into_iter()
+                    .filter(|space| space.level != SpaceLevel::Ok)
+                    .map(|space| (cloud.name.clone(), space))
+            })
+            .collect()
+    }
+
+    // ========== Folder health ==========
+
+    /// Check that every cloud folder is still there, e.g. that its drive wasn't unplugged;
+    /// call this every `folder_health::CHECK_INTERVAL`. The running clouds serving a folder
+    /// that went away or came back log it and send it to their webhooks. Returns the changes.
+    pub fn check_folder_health(&self) -> Vec<FolderChange> {
+        let changes = self.folder_health.check(&self.clouds_config.cloud_folders);
+        for change in &changes {
+            for (cloud_name, cloud_server) in &self.running_clouds {
+                if !cloud_server.cloud.has_cloud_folder(&change.folder) {
+                    continue;
+                }
+                let span = tracing::info_span!("cloud", cloud = %cloud_name);
+                let _entered = span.enter();
+                let event = match &change.problem {
+                    Some(problem) => {
+                        tracing::warn!(
+                            "Cloud folder '{}' is unavailable: {}",
+                            change.folder,
+                            problem
+                        );
+                        WebhookEvent::FolderUnavailable
+                    }
+                    None => {
+                        tracing::info!("Cloud folder '{}' is available again", change.folder);
+                        WebhookEvent::FolderAvailable
+                    }
+                };
+                if let Some(auth_state) = &cloud_server.auth_state {
+                    auth_state.webhooks.emit(
+                        event,
+                        serde_json::json!({
+                            "folder": change.folder,
+                            "path": change.path,
+                            "reason": change.problem,
+                        }),
+                    );
+                }
+            }
+        }
+        changes
+    }
+
+    /// Why the cloud folder `name` is unavailable, as of the last check
+    pub fn folder_problem(&self, name: &str) -> Option<String> {
+        self.folder_health.problem(name)
+    }
+
+    /// The folders of cloud `cloud_name` that are unavailable, as of the last check
+    pub fn unavailable_folders(&self, cloud_name: &str) -> Vec<String> {
+        self.clouds_config
+            .clouds
+            .iter()
+            .find(|cloud| cloud.name == cloud_name)
+            .map(|cloud| {
+                cloud
+                    .cloud_folders
+                    .iter()
+                    .filter(|folder| self.folder_health.problem(&folder.name).is_some())
+                    .map(|folder| folder.name.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    // ========== Scheduled tasks ==========
+
+    /// Start the configured tasks whose time has come; call this regularly.
+    /// Returns the tasks that were started.
+    pub fn run_due_tasks(&mut self) -> Vec<TaskKind> {
+        self.scheduler.sync(&self.clouds_config.tasks);
+        let due = self.scheduler.take_due(chrono::Local::now());
+        let statuses = self.scheduler.statuses();
+        due.into_iter()
+            .filter(|&index| self.scheduler.start(index, self.task_context()).is_ok())
+            .map(|index| statuses[index].task)
+            .collect()
+    }
+
+    /// Start task `index` of the config now, outside its schedule
+    pub fn run_task_now(&mut self, index: usize) -> Result<(), String> {
+        self.scheduler.sync(&self.clouds_config.tasks);
+        self.scheduler.start(index, self.task_context())
+    }
+
+    /// The configured tasks with their next and last run
+    pub fn task_statuses(&mut self) -> Vec<TaskStatus> {
+        self.scheduler.sync(&self.clouds_config.tasks);
+        self.scheduler.statuses()
+    }
+
+    /// Disk usage of a cloud, as last measured by the `refresh_usage` task
+    pub fn disk_usage(&self, cloud_name: &str) -> Option<DiskUsage> {
+        self.scheduler.usage(cloud_name)
+    }
+
+    fn task_context(&self) -> TaskContext {
+        TaskContext {
+            clouds: self
+                .clouds_config
+                .clouds
+                .iter()
+                .map(|cloud| (cloud.name.clone(), cloud.cloud_folders.clone()))
+                .collect(),
+            running: self
+                .running_clouds
+                .values()
+                .map(|cloud_server| RunningCloud {
+                    name: cloud_server.cloud.name.clone(),
+                    folders: cloud_server.cloud.cloud_folders.clone(),
+                    photo_cache: cloud_server.photo_cache.clone(),
+                    transcoder: cloud_server.transcoder.clone(),
+                    debug_stream: cloud_server.debug_stream.clone(),
+                    notifier: cloud_server
+                        .auth_state
+                        .as_ref()
+                        .and_then(|auth_state| auth_state.notifier.clone()),
+                })
+                .collect(),
+            config_path: crate::config_paths::get_clouds_config_path(),
+        }
+    }
+
+    // ========== File Requests ==========
+
+    /// Save file requests of running clouds that were created, removed or uploaded to
+    /// through the API. Returns the names of the clouds whose requests were saved.
+    pub fn persist_file_request_changes(&mut self) -> ServerResult<Vec<String>> {
+        let changes: Vec<(String, Vec<FileRequest>)> = self
+            .running_clouds
+            .iter()
+            .filter_map(|(name, cloud_server)| {
+                let requests = cloud_server.file_requests.as_ref()?.take_changes()?;
+                Some((name.clone(), requests))
+            })
+            .collect();
+
+        let mut changed = Vec::new();
+        for (name, requests) in changes {
+            if let Some(cloud) = self
+                .clouds_config
+                .clouds
+                .iter_mut()
+                .find(|c| c.name == name)
+            {
+                cloud.file_requests = requests;
+                changed.push(name);
+            }
+        }
+        if !changed.is_empty() {
+            self.save_config()?;
+        }
+        Ok(changed)
+    }
+
     /// Check if a cloud has a password
     pub fn cloud_has_password(&self, cloud_name: &str) -> bool {
         self.clouds_config
@@ -255,59 +1084,258 @@ impl Orchestrator {
     }
 
     /// Get debug logs for a specific cloud
-    pub async fn get_cloud_debug_logs(
+    /// Subscribe to new debug messages of a running cloud
+    pub fn subscribe_cloud_logs(
+        &self,
+        cloud_name: &str,
+    ) -> Option<tokio::sync::broadcast::Receiver<crate::debug_stream::DebugMessage>> {
+        self.running_clouds
+            .get(cloud_name)
+            .and_then(|cloud_server| cloud_server.debug_stream.as_ref())
+            .map(|debug_stream| debug_stream.subscribe())
+    }
+
+    /// Runtime counters of a running cloud
+    pub fn cloud_stats(&self, cloud_name: &str) -> Option<Arc<crate::stats::CloudStats>> {
+        self.running_clouds
+            .get(cloud_name)
+            .and_then(|cloud_server| cloud_server.stats.clone())
+    }
+
+    /// Clients a running cloud is serving
+    pub fn cloud_clients(&self, cloud_name: &str) -> Vec<ClientInfo> {
+        self.cloud_stats(cloud_name)
+            .map(|stats| stats.clients.list())
+            .unwrap_or_default()
+    }
+
+    /// End a client's transfer and close its connection
+    pub fn kill_client(&self, cloud_name: &str, id: u64) -> ServerResult<()> {
+        let stats = self
+            .cloud_stats(cloud_name)
+            .ok_or(ServerError::ServerNotRunning)?;
+        if !stats.clients.kill(id) {
+            return Err(ServerError::Validation(format!(
+                "Client {} is no longer connected to '{}'",
+                id, cloud_name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Read a cloud's persisted logs, running or not, oldest first
+    pub fn get_cloud_debug_logs(
         &self,
         cloud_name: &str,
-    ) -> Vec<crate::debug_stream::DebugMessage> {
-        if let Some(cloud_server) = self.running_clouds.get(cloud_name) {
-            if let Some(debug_stream) = &cloud_server.debug_stream {
-                debug_stream.get_history().await
-            } else {
-                Vec::new()
+        filter: &LogFilter,
+    ) -> ServerResult<Vec<crate::debug_stream::DebugMessage>> {
+        LogStore::for_cloud(cloud_name).read(filter)
+    }
+    // ========== Config Saving ==========
+
+    /// Save the clouds config, remembering a conflict with changes made by another program
+    fn save_config(&mut self) -> ServerResult<()> {
+        match self.clouds_config.save_to_file() {
+            Ok(()) => {
+                self.base_config = self.clouds_config.clone();
+                self.config_conflict = false;
+                Ok(())
+            }
+            Err(e) => {
+                if matches!(e, ServerError::ConfigConflict(_)) {
+                    self.config_conflict = true;
+                }
+                Err(e)
             }
-        } else {
-            Vec::new()
         }
     }
+
+    /// Whether unsaved changes are waiting for `resolve_config_conflict`
+    pub fn has_config_conflict(&self) -> bool {
+        self.config_conflict
+    }
+
+    /// Settle a conflict between our unsaved changes and the config on disk
+    pub fn resolve_config_conflict(&mut self, resolution: ConflictResolution) -> ServerResult<()> {
+        match resolution {
+            ConflictResolution::Merge => {
+                let theirs = self.load_config_file()?;
+                self.clouds_config =
+                    CloudsConfig::merge(&self.base_config, &self.clouds_config, &theirs);
+                self.save_config()
+            }
+            ConflictResolution::KeepMine => {
+                self.clouds_config.overwrite_file()?;
+                self.base_config = self.clouds_config.clone();
+                self.config_conflict = false;
+                Ok(())
+            }
+            ConflictResolution::KeepTheirs => {
+                self.clouds_config = self.load_config_file()?;
+                self.base_config = self.clouds_config.clone();
+                self.config_conflict = false;
+                Ok(())
+            }
+        }
+    }
+
+    /// Load the clouds config file, decrypting its secrets with the key already in use
+    fn load_config_file(&self) -> ServerResult<CloudsConfig> {
+        let mut config = CloudsConfig::load_from_file()?;
+        if let Some(key) = self.clouds_config.secrets_key() {
+            config.unlock_secrets(key.clone())?;
+        }
+        Ok(config)
+    }
+
+    // ========== Secrets Protection ==========
+
+    /// How cloud secrets are encrypted at rest, `None` for plain text
+    pub fn secrets_protection(&self) -> Option<SecretsProtection> {
+        self.clouds_config.secrets_protection()
+    }
+
+    /// Secrets are encrypted and still need the passphrase (or keyring) to be read
+    pub fn secrets_locked(&self) -> bool {
+        self.clouds_config.secrets_locked()
+    }
+
+    pub fn unlock_secrets_with_passphrase(&mut self, passphrase: &str) -> ServerResult<()> {
+        let Some(encrypted) = self.clouds_config.encrypted_secrets.as_ref() else {
+            return Ok(());
+        };
+        let key = SecretsKey::from_passphrase(passphrase, encrypted)?;
+        self.unlock_secrets(key)
+    }
+
+    pub fn unlock_secrets_with_keyring(&mut self) -> ServerResult<()> {
+        let key = SecretsKey::from_keyring(false)?;
+        self.unlock_secrets(key)
+    }
+
+    fn unlock_secrets(&mut self, key: SecretsKey) -> ServerResult<()> {
+        self.clouds_config.unlock_secrets(key.clone())?;
+        self.base_config.unlock_secrets(key)?;
+        Ok(())
+    }
+
+    /// Encrypt cloud secrets at rest with a master passphrase or a key kept in the OS keyring
+    pub fn protect_secrets(
+        &mut self,
+        protection: SecretsProtection,
+        passphrase: Option<&str>,
+    ) -> ServerResult<()> {
+        let key = match (protection, passphrase) {
+            (SecretsProtection::Passphrase, Some(passphrase)) => {
+                SecretsKey::new_from_passphrase(passphrase)?
+            }
+            (SecretsProtection::Passphrase, None) => {
+                return Err(ServerError::validation("A master passphrase is required"))
+            }
+            (SecretsProtection::Keyring, _) => SecretsKey::from_keyring(true)?,
+        };
+        self.clouds_config.set_secrets_key(Some(key))?;
+        self.save_config()
+    }
+
+    /// Store cloud secrets in plain text again
+    pub fn unprotect_secrets(&mut self) -> ServerResult<()> {
+        self.clouds_config.set_secrets_key(None)?;
+        self.save_config()
+    }
+
+    // ========== Config Import/Export ==========
+
+    /// Export the clouds configuration to `path`.
+    /// Without a passphrase secrets are left out, with one everything is encrypted.
+    pub fn export_config(&self, path: &Path, passphrase: Option<&str>) -> ServerResult<()> {
+        let export = match passphrase {
+            Some(passphrase) => ConfigExport::encrypted(&self.clouds_config, passphrase)?,
+            None => ConfigExport::without_secrets(&self.clouds_config),
+        };
+        export.write_to_file(path)
+    }
+
+    /// Check whether an export file needs a passphrase to import
+    pub fn is_export_encrypted(path: &Path) -> ServerResult<bool> {
+        Ok(ConfigExport::read_from_file(path)?.is_encrypted())
+    }
+
+    /// Import an exported configuration, adding folders and clouds that don't exist yet
+    pub fn import_config(
+        &mut self,
+        path: &Path,
+        passphrase: Option<&str>,
+    ) -> ServerResult<ImportSummary> {
+        let imported = ConfigExport::read_from_file(path)?.into_config(passphrase)?;
+
+        let summary = config_transfer::merge_into(&mut self.clouds_config, imported);
+        self.save_config()?;
+
+        Ok(summary)
+    }
+
     // ========== Cloud config Management ==========
 
-    /// Reload the clouds config and restart affected clouds
+    /// Reload the clouds config and restart affected clouds.
+    /// An invalid config is reported and the current one is kept.
     pub async fn reload_config(&mut self) -> ServerResult<()> {
-        let running_clouds: Vec<String> = self.running_clouds.keys().cloned().collect();
-        self.clouds_config =
-            CloudsConfig::load_from_file().unwrap_or_else(|_e| CloudsConfig::default()); // Check which clouds are still valid after reload
-        let valid_clouds: std::collections::HashSet<String> = self
-            .clouds_config
-            .clouds
-            .iter()
-            .map(|c| c.name.clone())
-            .collect();
+        let new_config = self.load_config_file()?;
+        self.apply_config(new_config).await;
+        Ok(())
+    }
 
-        // Stop clouds that are no longer in config
+    /// Reload the clouds config only if the file differs from the loaded one,
+    /// e.g. after it was edited externally. Returns a summary of what changed.
+    pub async fn reload_config_if_changed(&mut self) -> ServerResult<Vec<String>> {
+        // Our own saves and unsaved conflicting changes are not external edits to apply
+        if self.config_conflict || !self.clouds_config.is_stale() {
+            return Ok(Vec::new());
+        }
+        let new_config = self.load_config_file()?;
+        let changes = CloudsConfig::diff(&self.clouds_config, &new_config);
+        self.apply_config(new_config).await;
+        Ok(changes)
+    }
+
+    /// Switch to `new_config`, stopping removed clouds and restarting running clouds that
+    /// changed, unless the change can be handed to the running server
+    async fn apply_config(&mut self, new_config: CloudsConfig) {
+        let old_config = std::mem::replace(&mut self.clouds_config, new_config);
+        self.base_config = self.clouds_config.clone();
+        self.config_conflict = false;
+
+        let running_clouds: Vec<String> = self.running_clouds.keys().cloned().collect();
         for cloud_name in &running_clouds {
-            if !valid_clouds.contains(cloud_name) {
-                if let Some(mut cloud_server) = self.running_clouds.remove(cloud_name) {
-                    if let Err(e) = cloud_server.stop_server().await {
-                        eprintln!(
-                            "Failed to stop cloud '{}' during config reload: {}",
-                            cloud_name, e
-                        );
-                    }
+            let new_cloud = self.clouds_config.get_cloud(cloud_name);
+            let old_cloud = old_config.get_cloud(cloud_name);
+            let still_configured = new_cloud.is_some();
+            if still_configured && new_cloud == old_cloud {
+                continue;
+            }
+            // Folder and access changes are handed to the running server
+            if let Some(old_cloud) = old_cloud {
+                match self.apply_cloud_changes(old_cloud) {
+                    Ok(true) => continue,
+                    Ok(false) => {}
+                    Err(e) => eprintln!(
+                        "Failed to update cloud '{}' during config reload, restarting it: {}",
+                        cloud_name, e
+                    ),
                 }
             }
-        }
 
-        // Restart clouds that were running and are still valid
-        for cloud_name in &running_clouds {
-            if valid_clouds.contains(cloud_name) {
-                // Stop the existing server
-                if let Some(mut cloud_server) = self.running_clouds.remove(cloud_name) {
-                    if let Err(e) = cloud_server.stop_server().await {
-                        eprintln!("Failed to stop cloud '{}' for restart: {}", cloud_name, e);
-                    }
+            if let Some(mut cloud_server) = self.running_clouds.remove(cloud_name) {
+                if let Err(e) = cloud_server.stop_server().await {
+                    eprintln!(
+                        "Failed to stop cloud '{}' during config reload: {}",
+                        cloud_name, e
+                    );
                 }
+            }
 
-                // Start the server with new config
+            // Start the server again with the new definition
+            if still_configured {
                 if let Err(e) = self.start_cloud(cloud_name).await {
                     eprintln!(
                         "Failed to restart cloud '{}' after config reload: {}",
@@ -316,7 +1344,13 @@ impl Orchestrator {
                 }
             }
         }
-
-        Ok(())
     }
 }
+
+/// The server defaults of `clouds_config` with the environment overrides on top. Overrides
+/// that don't parse are left out here; the binary refuses to start with them.
+fn effective_defaults(clouds_config: &CloudsConfig) -> ServerDefaults {
+    clouds_config
+        .defaults
+        .with_overrides(&Overrides::from_env().unwrap_or_default())
+}
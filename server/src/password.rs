@@ -0,0 +1,92 @@
+/// Cloud password hashing: Argon2id PHC strings with a random salt per hash.
+/// Configs written before hashing store the password itself; those still verify
+/// and are replaced with a hash on the next successful login.
+use crate::error::{ServerError, ServerResult};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use serde::{Deserialize, Serialize};
+
+/// Argon2id cost parameters, tunable in the clouds config (`[password_hashing]`).
+/// Changing them rehashes each password on its next successful login.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PasswordHashParams {
+    /// Memory cost in KiB
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for PasswordHashParams {
+    /// OWASP's recommended minimum for Argon2id
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+impl PasswordHashParams {
+    fn argon2(&self) -> ServerResult<Argon2<'static>> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+            .map_err(|e| {
+                ServerError::configuration(format!("Invalid password hashing parameters: {}", e))
+            })?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+
+    /// Why these parameters can't be used, if they can't
+    pub fn problem(&self) -> Option<String> {
+        self.argon2().err().map(|e| e.to_string())
+    }
+}
+
+/// Whether a stored password is a hash rather than a plaintext password from an older config
+pub fn is_hash(stored: &str) -> bool {
+    stored.starts_with("$argon2")
+}
+
+pub fn hash_password(password: &str, params: &PasswordHashParams) -> ServerResult<String> {
+    let salt = SaltString::encode_b64(&crate::secrets::random_salt())
+        .map_err(|e| ServerError::internal(format!("Failed to encode salt: {}", e)))?;
+    let hash = params
+        .argon2()?
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| ServerError::internal(format!("Failed to hash password: {}", e)))?;
+    Ok(hash.to_string())
+}
+
+/// Check `password` against a stored hash (or legacy plaintext password)
+pub fn verify_password(password: &str, stored: &str) -> bool {
+    if !is_hash(stored) {
+        return password == stored;
+    }
+    let Ok(hash) = PasswordHash::new(stored) else {
+        return false;
+    };
+    // The hash carries its own algorithm and parameters
+    Argon2::default()
+        .verify_password(password.as_bytes(), &hash)
+        .is_ok()
+}
+
+/// Whether a stored password should be replaced: it is plaintext, or was hashed
+/// with another algorithm or other parameters than `params`
+pub fn needs_rehash(stored: &str, params: &PasswordHashParams) -> bool {
+    let Ok(hash) = PasswordHash::new(stored) else {
+        return true;
+    };
+    if hash.algorithm != argon2::ARGON2ID_IDENT {
+        return true;
+    }
+    match Params::try_from(&hash) {
+        Ok(current) => {
+            current.m_cost() != params.memory_kib
+                || current.t_cost() != params.iterations
+                || current.p_cost() != params.parallelism
+        }
+        Err(_) => true,
+    }
+}
@@ -0,0 +1,113 @@
+/// Per-cloud cache of photo metadata, so the timeline doesn't re-read the EXIF data of
+/// every photo on every request. Stored as JSON in `photo-cache/<cloud>.json` in the
+/// data directory. An entry is used only while the file's size and modification time
+/// still match; entries of files that are gone are dropped on the next full scan.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::config_paths;
+use crate::utils::photo_metadata::PhotoMetadata;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedPhoto {
+    size: u64,
+    /// Unix seconds
+    modified: i64,
+    #[serde(flatten)]
+    metadata: PhotoMetadata,
+}
+
+#[derive(Default)]
+struct Entries {
+    /// By `<cloud folder>/<relative path>`
+    photos: HashMap<String, CachedPhoto>,
+    /// Changed since the last save
+    dirty: bool,
+}
+
+pub struct PhotoCache {
+    path: PathBuf,
+    entries: Mutex<Entries>,
+}
+
+impl PhotoCache {
+    /// Load the cache of a cloud. A missing or unreadable file is an empty cache.
+    pub fn for_cloud(cloud_name: &str) -> Self {
+        let path = get_photo_cache_path(cloud_name);
+        let photos = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            entries: Mutex::new(Entries {
+                photos,
+                dirty: false,
+            }),
+        }
+    }
+
+    /// The cached metadata of `key`, if the file hasn't changed since
+    pub fn get(&self, key: &str, size: u64, modified: i64) -> Option<PhotoMetadata> {
+        let entries = self.entries.lock().ok()?;
+        let cached = entries.photos.get(key)?;
+        (cached.size == size && cached.modified == modified).then(|| cached.metadata.clone())
+    }
+
+    pub fn insert(&self, key: String, size: u64, modified: i64, metadata: PhotoMetadata) {
+        if let Ok(mut entries) = self.entries.lock() {
+            let cached = CachedPhoto {
+                size,
+                modified,
+                metadata,
+            };
+            entries.photos.insert(key, cached);
+            entries.dirty = true;
+        }
+    }
+
+    /// Drop the entries `keep` says no to, e.g. of files a scan didn't find anymore
+    pub fn retain(&self, keep: impl Fn(&str) -> bool) {
+        if let Ok(mut entries) = self.entries.lock() {
+            let before = entries.photos.len();
+            entries.photos.retain(|key, _| keep(key));
+            if entries.photos.len() != before {
+                entries.dirty = true;
+            }
+        }
+    }
+
+    /// Write the cache if it changed. Errors are ignored: the cache can always be rebuilt.
+    pub fn save(&self) {
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+        if !entries.dirty {
+            return;
+        }
+        let Ok(json) = serde_json::to_string(&entries.photos) else {
+            return;
+        };
+        if let Some(dir) = self.path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        // Written next to it and renamed, so a crash can't leave half a file
+        let temp_path = self.path.with_extension("json.tmp");
+        if fs::write(&temp_path, json).is_ok() && fs::rename(&temp_path, &self.path).is_ok() {
+            entries.dirty = false;
+        }
+    }
+}
+
+/// `photo-cache/<cloud>.json` in the data directory, with unusual characters replaced
+pub fn get_photo_cache_path(cloud_name: &str) -> PathBuf {
+    config_paths::get_data_dir()
+        .join("photo-cache")
+        .join(format!(
+            "{}.json",
+            config_paths::cloud_file_stem(cloud_name)
+        ))
+}
@@ -0,0 +1,87 @@
+/// Password recovery with one-time codes. The TUI issues a code for a running cloud and
+/// shows it only locally; the login page's "Forgot password?" form takes it with a new
+/// password. A code works once, for 15 minutes and for 5 tries, and only its SHA-256
+/// hash is kept.
+use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+use chrono::{DateTime, Duration, Utc};
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+
+/// How long a code can be used
+pub const CODE_LIFETIME_MINUTES: i64 = 15;
+
+/// Wrong codes before the pending code is thrown away
+const MAX_ATTEMPTS: u32 = 5;
+
+/// 32 characters without the look-alikes 0/O and 1/I, so every byte maps evenly
+const ALPHABET: &[u8; 32] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+const CODE_LENGTH: usize = 10;
+
+struct PendingCode {
+    hash: [u8; 32],
+    expires_at: DateTime<Utc>,
+    attempts: u32,
+}
+
+/// The pending recovery code of a cloud, at most one at a time
+#[derive(Default)]
+pub struct RecoveryCodes {
+    pending: Mutex<Option<PendingCode>>,
+}
+
+/// Codes are typed by hand: case, dashes and spaces don't matter
+fn normalize(code: &str) -> String {
+    code.chars()
+        .filter(|c| !matches!(c, '-' | ' '))
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn hash(code: &str) -> [u8; 32] {
+    Sha256::digest(normalize(code).as_bytes()).into()
+}
+
+impl RecoveryCodes {
+    /// Issue a new code, replacing a pending one. Returns it as `ABCDE-FGHJK`.
+    pub fn issue(&self) -> String {
+        let mut bytes = [0u8; CODE_LENGTH];
+        OsRng.fill_bytes(&mut bytes);
+        let code: String = bytes
+            .iter()
+            .map(|byte| ALPHABET[(*byte as usize) % ALPHABET.len()] as char)
+            .collect();
+        if let Ok(mut pending) = self.pending.lock() {
+            *pending = Some(PendingCode {
+                hash: hash(&code),
+                expires_at: Utc::now() + Duration::minutes(CODE_LIFETIME_MINUTES),
+                attempts: 0,
+            });
+        }
+        format!("{}-{}", &code[..CODE_LENGTH / 2], &code[CODE_LENGTH / 2..])
+    }
+
+    /// Use up the pending code if `code` matches it
+    pub fn redeem(&self, code: &str) -> bool {
+        let Ok(mut pending) = self.pending.lock() else {
+            return false;
+        };
+        let Some(current) = pending.as_mut() else {
+            return false;
+        };
+        if current.expires_at < Utc::now() {
+            *pending = None;
+            return false;
+        }
+        if current.hash == hash(code) {
+            *pending = None;
+            return true;
+        }
+        current.attempts += 1;
+        if current.attempts >= MAX_ATTEMPTS {
+            tracing::warn!("Too many wrong recovery codes, the pending code was discarded");
+            *pending = None;
+        }
+        false
+    }
+}
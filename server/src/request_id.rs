@@ -0,0 +1,60 @@
+/// Request IDs for correlating a response with the server log.
+/// Every request gets an ID, returned in the `X-Request-Id` response header and used in the
+/// request's log lines and auth failure entries. A well-formed `X-Request-Id` sent by the
+/// client or a proxy is kept, so IDs can be followed across hops.
+use axum::{
+    extract::Request,
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+const MAX_LEN: usize = 64;
+
+/// The ID of the current request, in the request extensions
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+pub(crate) fn generate() -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..8].to_string()
+}
+
+/// IDs end up in log lines, so only accept short, plain tokens
+fn is_well_formed(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_LEN
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_' || b == b'.')
+}
+
+/// The request ID assigned by `assign`, from the request headers
+pub fn from_headers(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_well_formed(id))
+}
+
+/// Outermost middleware: pick the request's ID and echo it in the response
+pub async fn assign(mut request: Request, next: Next) -> Response {
+    let id = from_headers(request.headers())
+        .map(str::to_string)
+        .unwrap_or_else(generate);
+    // Well-formed IDs are always valid header values
+    let value = HeaderValue::from_str(&id).ok();
+    if let Some(value) = &value {
+        request
+            .headers_mut()
+            .insert(REQUEST_ID_HEADER, value.clone());
+    }
+    request.extensions_mut().insert(RequestId(id));
+
+    let mut response = next.run(request).await;
+    if let Some(value) = value {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
@@ -1,9 +1,19 @@
-use axum::{extract::State, Json};
-use serde_json::json;
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{header, HeaderMap, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::net::SocketAddr;
+use std::time::Duration;
 
 use crate::{
-    auth::{LoginRequest, LoginResponse},
+    auth::{AuthenticatedUser, LoginRequest, LoginResponse, RecoverRequest},
+    auth_log::AuthFailure,
     cloud::CloudServerState,
+    error::{ApiError, ErrorCode},
+    login_limiter::{self, FailureOutcome},
+    sessions,
 };
 
 // Re-export web routes
@@ -13,30 +23,169 @@ pub use crate::web_routes::*;
 pub use crate::web_routes::cloud_folder::{browse_file_or_directory, serve_static_file};
 
 // Re-export API routes
+pub use crate::api_routes::camera::api_camera_upload;
+pub use crate::api_routes::clients::{api_kill_client, api_list_clients};
 pub use crate::api_routes::cloud::{
     api_browse_file_or_directory, api_list_cloud_folder_files, get_cloud_folder_info,
 };
 pub use crate::api_routes::delete::api_delete_file;
+pub use crate::api_routes::file::api_patch_file;
+pub use crate::api_routes::file_requests::{
+    api_create_file_request, api_delete_file_request, api_list_file_requests,
+};
 pub use crate::api_routes::index::api_index;
+pub use crate::api_routes::logs::api_stream_logs;
+pub use crate::api_routes::metadata::api_set_file_metadata;
+pub use crate::api_routes::photos::api_photo_timeline;
+pub use crate::api_routes::preview::{api_preview_document, preview_source};
+pub use crate::api_routes::stream::api_stream_video;
+pub use crate::api_routes::tokens::{api_create_token, api_list_tokens, api_revoke_token};
 pub use crate::api_routes::upload::api_upload_file;
+pub use crate::api_routes::usage::{api_metrics, api_stats, api_usage};
+
+/// `POST /api/login/recover`: set a new password with a recovery code from the TUI,
+/// logging in with it
+pub async fn recover_password(
+    State(server_state): State<CloudServerState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    uri: Uri,
+    headers: HeaderMap,
+    Json(payload): Json<RecoverRequest>,
+) -> Result<Response, ApiError> {
+    let auth_state = &server_state.auth_state;
+
+    let address = login_limiter::client_address(peer, &headers);
+    if let Some(lockout) = auth_state.login_limiter.locked_for(address) {
+        let seconds = lockout.as_secs().max(1);
+        return Err(ApiError::new(
+            ErrorCode::TooManyRequests,
+            format!("Too many failed logins, try again in {} seconds", seconds),
+        )
+        .with("retry_after", seconds));
+    }
+    if payload.password.len() < 8 {
+        return Err(ApiError::validation(
+            "Password must be at least 8 characters",
+        ));
+    }
+    if !auth_state.recovery.redeem(&payload.code) {
+        auth_state.record_failure(&AuthFailure::new(
+            peer.ip(),
+            &headers,
+            &server_state.cloud.name,
+            "bad_recovery_code",
+        ));
+        auth_state.login_limiter.record_failure(address);
+        return Err(ApiError::new(
+            ErrorCode::Unauthorized,
+            "The recovery code is wrong or has expired",
+        ));
+    }
+    auth_state
+        .reset_password(&payload.password)
+        .map_err(|e| ApiError::internal(e.to_string()))?;
+    tracing::warn!("The password was reset with a recovery code");
+
+    auth_state
+        .generate_token()
+        .map(|token| login_response(&uri, &headers, token, auth_state.sessions.lifetime(false)))
+        .map_err(|_| ApiError::internal("The password was changed, but logging in failed"))
+}
 
-// Wrapper for login function to work with CloudServerState
+/// Refuse a login from a locked out address, with the seconds left in `Retry-After`
+fn locked_out(lockout: Duration) -> Response {
+    // Round up, so the countdown on the page never reaches 0 too early
+    let seconds = lockout.as_secs() + u64::from(lockout.subsec_nanos() > 0);
+    (
+        [(header::RETRY_AFTER, seconds.to_string())],
+        ApiError::new(
+            ErrorCode::TooManyRequests,
+            format!("Too many failed logins, try again in {} seconds", seconds),
+        )
+        .with("retry_after", seconds),
+    )
+        .into_response()
+}
+
+/// The token in the body for scripts, and in the login cookie for browsers
+fn login_response(
+    uri: &Uri,
+    headers: &HeaderMap,
+    token: String,
+    lifetime: chrono::Duration,
+) -> Response {
+    let expires_in = lifetime.num_seconds();
+    let cookie = sessions::login_cookie(uri, headers, &token, expires_in);
+    (
+        [(header::SET_COOKIE, cookie)],
+        Json(LoginResponse { token, expires_in }),
+    )
+        .into_response()
+}
+
+/// `POST /api/login`: a session token for the cloud password, with lockouts after
+/// repeated failures
 pub async fn login(
     State(server_state): State<CloudServerState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    uri: Uri,
+    headers: HeaderMap,
     Json(payload): Json<LoginRequest>,
-) -> Result<Json<LoginResponse>, (axum::http::StatusCode, Json<serde_json::Value>)> {
+) -> Response {
     let auth_state = &server_state.auth_state;
+    let address = login_limiter::client_address(peer, &headers);
+    if let Some(lockout) = auth_state.login_limiter.locked_for(address) {
+        return locked_out(lockout);
+    }
 
-    if auth_state.verify_password(&payload.password) {
-        if let Ok(token) = auth_state.generate_token() {
-            return Ok(Json(LoginResponse { token }));
-        }
+    if !auth_state.verify_password(&payload.password) {
+        auth_state.record_failure(&AuthFailure::new(
+            peer.ip(),
+            &headers,
+            &server_state.cloud.name,
+            "bad_password",
+        ));
+        return match auth_state.login_limiter.record_failure(address) {
+            FailureOutcome::LockedOut(lockout) => locked_out(lockout),
+            FailureOutcome::AttemptsLeft(attempts_left) => {
+                ApiError::new(ErrorCode::WrongPassword, "Wrong password")
+                    .with("attempts_left", attempts_left)
+                    .into_response()
+            }
+        };
     }
+    auth_state.login_limiter.record_success(address);
 
-    Err((
-        axum::http::StatusCode::UNAUTHORIZED,
-        Json(json!({
-            "error": "Invalid credentials"
-        })),
-    ))
+    match auth_state.generate_session_token(payload.remember) {
+        Ok(token) => login_response(
+            &uri,
+            &headers,
+            token,
+            auth_state.sessions.lifetime(payload.remember),
+        ),
+        Err(_) => ApiError::internal("Logging in failed").into_response(),
+    }
+}
+
+/// `POST /api/logout`: revoke the login token the request came with and remove the
+/// login cookie. API tokens are revoked with `DELETE /api/tokens/:id` instead.
+pub async fn api_logout(
+    State(server_state): State<CloudServerState>,
+    user: AuthenticatedUser,
+    uri: Uri,
+    headers: HeaderMap,
+) -> Response {
+    let Some(claims) = &user.claims else {
+        return ApiError::validation("API tokens are revoked with DELETE /api/tokens/:id")
+            .into_response();
+    };
+    // Tokens from before logout existed have no id, only the cookie can go
+    if !claims.jti.is_empty() {
+        server_state.auth_state.revoke_session(claims);
+    }
+    (
+        StatusCode::NO_CONTENT,
+        [(header::SET_COOKIE, sessions::logout_cookie(&uri, &headers))],
+    )
+        .into_response()
 }
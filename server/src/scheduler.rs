@@ -0,0 +1,550 @@
+/// Scheduled tasks: maintenance jobs the orchestrator runs on cron schedules from
+/// `[[tasks]]` in the clouds config. The owner of the orchestrator (the TUI) calls
+/// `Orchestrator::run_due_tasks` regularly; due tasks run in the background and the
+/// outcome of their last run is kept for the Tasks panel.
+///
+/// Schedules are standard 5-field cron expressions (`minute hour day month weekday`)
+/// in local time, or shorthands like `@daily` and `@hourly`.
+use chrono::{DateTime, Local, Utc};
+use cron::Schedule;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use crate::cloud::CloudFolder;
+use crate::config_paths;
+use crate::debug_stream::DebugStream;
+use crate::email::{EmailNotifier, NotifyEvent};
+use crate::log_store::LogStore;
+use crate::photo_cache::PhotoCache;
+use crate::transcode::{self, Transcoder};
+use crate::utils::format_bytes;
+
+const BACKUPS_DIR: &str = "backups";
+
+fn default_keep() -> usize {
+    7
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskKind {
+    /// Read the EXIF data of new and changed photos into the timeline's cache, and drop
+    /// the entries of deleted ones
+    ReindexPhotos,
+    /// Delete transcoded videos not watched for 30 days and abandoned Nextcloud uploads
+    PurgeCaches,
+    /// Start new log files, keeping the usual number of old ones
+    RotateLogs,
+    /// Measure how much space every cloud's folders take
+    RefreshUsage,
+    /// Copy the clouds config into `backups/` in the data directory
+    Backup,
+}
+
+impl TaskKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ReindexPhotos => "reindex_photos",
+            Self::PurgeCaches => "purge_caches",
+            Self::RotateLogs => "rotate_logs",
+            Self::RefreshUsage => "refresh_usage",
+            Self::Backup => "backup",
+        }
+    }
+}
+
+impl fmt::Display for TaskKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// `[[tasks]]` in the clouds config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskConfig {
+    pub task: TaskKind,
+    /// e.g. `0 3 * * *` for every night at 3:00
+    pub schedule: String,
+    /// Backups to keep, the oldest are deleted
+    #[serde(default = "default_keep")]
+    pub keep: usize,
+}
+
+impl TaskConfig {
+    /// Problems with the task, as `field: message`
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if let Err(e) = parse_schedule(&self.schedule) {
+            problems.push(format!("schedule: {}", e));
+        }
+        if self.keep == 0 {
+            problems.push("keep: must be at least 1".to_string());
+        }
+        problems
+    }
+}
+
+/// A cron expression, 5 fields or a shorthand. The `cron` crate wants seconds too.
+fn parse_schedule(expression: &str) -> Result<Schedule, String> {
+    let expression = expression.trim();
+    let full = if expression.split_whitespace().count() == 5 {
+        format!("0 {}", expression)
+    } else {
+        expression.to_string()
+    };
+    // Its errors count the fields its own way, which would only confuse
+    Schedule::from_str(&full).map_err(|_| {
+        format!(
+            "'{}' is not a valid cron schedule (minute hour day month weekday)",
+            expression
+        )
+    })
+}
+
+/// How much space a cloud's folders take
+#[derive(Debug, Clone, Default)]
+pub struct DiskUsage {
+    pub bytes: u64,
+    pub files: u64,
+    pub folders: Vec<FolderUsage>,
+    pub measured_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FolderUsage {
+    pub name: String,
+    pub bytes: u64,
+    pub files: u64,
+}
+
+/// Add up the files below the folders. Blocking. Symlinks aren't followed.
+pub fn measure_usage(folders: &[CloudFolder]) -> DiskUsage {
+    let mut usage = DiskUsage {
+        measured_at: Some(Utc::now()),
+        ..Default::default()
+    };
+    for folder in folders {
+        let mut folder_usage = FolderUsage {
+            name: folder.name.clone(),
+            bytes: 0,
+            files: 0,
+        };
+        let mut pending = vec![folder.folder_path.clone()];
+        while let Some(directory) = pending.pop() {
+            let Ok(entries) = std::fs::read_dir(&directory) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Ok(metadata) = entry.path().symlink_metadata() else {
+                    continue;
+                };
+                if metadata.is_dir() {
+                    pending.push(entry.path());
+                } else if metadata.is_file() {
+                    folder_usage.bytes += metadata.len();
+                    folder_usage.files += 1;
+                }
+            }
+        }
+        usage.bytes += folder_usage.bytes;
+        usage.files += folder_usage.files;
+        usage.folders.push(folder_usage);
+    }
+    usage
+}
+
+/// What a task needs of a running cloud
+pub struct RunningCloud {
+    pub name: String,
+    pub folders: Vec<CloudFolder>,
+    pub photo_cache: Option<Arc<PhotoCache>>,
+    pub transcoder: Option<Arc<Transcoder>>,
+    pub debug_stream: Option<Arc<DebugStream>>,
+    pub notifier: Option<Arc<EmailNotifier>>,
+}
+
+/// Everything a run of a task may touch, taken from the orchestrator when it starts
+pub struct TaskContext {
+    /// All configured clouds with their folders
+    pub clouds: Vec<(String, Vec<CloudFolder>)>,
+    pub running: Vec<RunningCloud>,
+    pub config_path: PathBuf,
+}
+
+/// Outcome of the last run of a task
+#[derive(Debug, Clone, Default)]
+struct TaskRun {
+    running: bool,
+    last_run: Option<DateTime<Local>>,
+    last_result: Option<Result<String, String>>,
+}
+
+/// A task for the Tasks panel
+#[derive(Debug, Clone)]
+pub struct TaskStatus {
+    pub task: TaskKind,
+    pub schedule: String,
+    pub next_run: Option<DateTime<Local>>,
+    pub running: bool,
+    pub last_run: Option<DateTime<Local>>,
+    /// A summary on success, the error on failure
+    pub last_result: Option<Result<String, String>>,
+}
+
+struct ScheduledTask {
+    config: TaskConfig,
+    schedule: Option<Schedule>,
+    next_run: Option<DateTime<Local>>,
+    run: Arc<Mutex<TaskRun>>,
+}
+
+impl ScheduledTask {
+    fn new(config: TaskConfig) -> Self {
+        let schedule = parse_schedule(&config.schedule).ok();
+        let next_run = schedule
+            .as_ref()
+            .and_then(|schedule| schedule.upcoming(Local).next());
+        Self {
+            config,
+            schedule,
+            next_run,
+            run: Arc::default(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Scheduler {
+    tasks: Vec<ScheduledTask>,
+    /// Filled in by `refresh_usage`, by cloud name
+    usage: Arc<Mutex<HashMap<String, DiskUsage>>>,
+}
+
+impl Scheduler {
+    /// Follow the config: new tasks are added, removed ones dropped. Tasks that didn't
+    /// change keep their state.
+    pub fn sync(&mut self, configs: &[TaskConfig]) {
+        if self.tasks.len() == configs.len()
+            && self
+                .tasks
+                .iter()
+                .zip(configs)
+                .all(|(task, config)| task.config == *config)
+        {
+            return;
+        }
+        let mut old = std::mem::take(&mut self.tasks);
+        self.tasks = configs
+            .iter()
+            .map(
+                |config| match old.iter().position(|task| task.config == *config) {
+                    Some(index) => old.remove(index),
+                    None => ScheduledTask::new(config.clone()),
+                },
+            )
+            .collect();
+    }
+
+    /// Indexes of the tasks whose time has come. Their next run is moved on; a task
+    /// still running skips its turn.
+    pub fn take_due(&mut self, now: DateTime<Local>) -> Vec<usize> {
+        let mut due = Vec::new();
+        for (index, task) in self.tasks.iter_mut().enumerate() {
+            if task.next_run.is_none_or(|next_run| next_run > now) {
+                continue;
+            }
+            task.next_run = task
+                .schedule
+                .as_ref()
+                .and_then(|schedule| schedule.after(&now).next());
+            due.push(index);
+        }
+        due
+    }
+
+    /// Start task `index` in the background, unless it's still running
+    pub fn start(&self, index: usize, context: TaskContext) -> Result<(), String> {
+        let task = self
+            .tasks
+            .get(index)
+            .ok_or_else(|| format!("There is no task {}", index + 1))?;
+        {
+            let mut run = task.run.lock().map_err(|e| e.to_string())?;
+            if run.running {
+                return Err(format!("'{}' is still running", task.config.task));
+            }
+            run.running = true;
+        }
+        let runtime = tokio::runtime::Handle::try_current().map_err(|e| e.to_string())?;
+
+        let config = task.config.clone();
+        let run = task.run.clone();
+        let usage = self.usage.clone();
+        runtime.spawn(async move {
+            let started = Local::now();
+            let result = execute(&config, &context, &usage).await;
+            match &result {
+                Ok(summary) => tracing::info!("Task {} finished: {}", config.task, summary),
+                Err(e) => tracing::warn!("Task {} failed: {}", config.task, e),
+            }
+            if let (TaskKind::Backup, Err(e)) = (config.task, &result) {
+                for cloud in &context.running {
+                    if let Some(notifier) = &cloud.notifier {
+                        notifier.notify(
+                            NotifyEvent::BackupFailed,
+                            format!("The scheduled backup of the clouds config failed: {}", e),
+                        );
+                    }
+                }
+            }
+            if let Ok(mut run) = run.lock() {
+                run.running = false;
+                run.last_run = Some(started);
+                run.last_result = Some(result);
+            }
+        });
+        Ok(())
+    }
+
+    pub fn statuses(&self) -> Vec<TaskStatus> {
+        self.tasks
+            .iter()
+            .map(|task| {
+                let run = task.run.lock().map(|run| run.clone()).unwrap_or_default();
+                TaskStatus {
+                    task: task.config.task,
+                    schedule: task.config.schedule.clone(),
+                    next_run: task.next_run,
+                    running: run.running,
+                    last_run: run.last_run,
+                    last_result: run.last_result,
+                }
+            })
+            .collect()
+    }
+
+    /// The last measurement of `refresh_usage`
+    pub fn usage(&self, cloud_name: &str) -> Option<DiskUsage> {
+        self.usage
+            .lock()
+            .ok()
+            .and_then(|usage| usage.get(cloud_name).cloned())
+    }
+}
+
+async fn execute(
+    config: &TaskConfig,
+    context: &TaskContext,
+    usage: &Arc<Mutex<HashMap<String, DiskUsage>>>,
+) -> Result<String, String> {
+    match config.task {
+        TaskKind::ReindexPhotos => {
+            let clouds: Vec<(Vec<CloudFolder>, Arc<PhotoCache>)> = context
+                .running
+                .iter()
+                .filter_map(|cloud| {
+                    let cache = cloud.photo_cache.clone()?;
+                    Some((cloud.folders.clone(), cache))
+                })
+                .collect();
+            if clouds.is_empty() {
+                return Ok("no cloud is running".to_string());
+            }
+            let photos = tokio::task::spawn_blocking(move || {
+                clouds
+                    .iter()
+                    .map(|(folders, cache)| {
+                        crate::api_routes::photos::collect_photos(folders, cache, true).len()
+                    })
+                    .sum::<usize>()
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+            Ok(format!("{} photos indexed", photos))
+        }
+        TaskKind::PurgeCaches => {
+            for (name, _) in &context.clouds {
+                let running = context.running.iter().find(|cloud| cloud.name == *name);
+                match running.and_then(|cloud| cloud.transcoder.as_ref()) {
+                    Some(transcoder) => transcoder.prune_cache(),
+                    // Nothing is transcoding for a stopped cloud
+                    None => transcode::prune_cache_dir(&transcode::get_transcode_cache_dir(name)),
+                }
+                crate::nextcloud::remove_stale_uploads(name).await;
+            }
+            Ok(format!(
+                "cleaned the caches of {} clouds",
+                context.clouds.len()
+            ))
+        }
+        TaskKind::RotateLogs => {
+            let mut rotated = 0;
+            for (name, _) in &context.clouds {
+                let running = context.running.iter().find(|cloud| cloud.name == *name);
+                let result = match running.and_then(|cloud| cloud.debug_stream.as_ref()) {
+                    Some(stream) => stream.rotate_log(),
+                    None => LogStore::for_cloud(name).rotate_now(),
+                };
+                match result {
+                    Ok(true) => rotated += 1,
+                    Ok(false) => {}
+                    Err(e) => return Err(format!("rotating the log of '{}': {}", name, e)),
+                }
+            }
+            Ok(format!("rotated {} logs", rotated))
+        }
+        TaskKind::RefreshUsage => {
+            let clouds = context.clouds.clone();
+            let measured = tokio::task::spawn_blocking(move || {
+                clouds
+                    .iter()
+                    .map(|(name, folders)| (name.clone(), measure_usage(folders)))
+                    .collect::<Vec<_>>()
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+            let total: u64 = measured.iter().map(|(_, usage)| usage.bytes).sum();
+            let count = measured.len();
+            if let Ok(mut usage) = usage.lock() {
+                usage.extend(measured);
+            }
+            Ok(format!("{} in {} clouds", format_bytes(total), count))
+        }
+        TaskKind::Backup => backup(&context.config_path, config.keep).await,
+    }
+}
+
+/// Copy the clouds config to `backups/clouds-config-<time>.toml`, keeping the newest `keep`
+async fn backup(config_path: &std::path::Path, keep: usize) -> Result<String, String> {
+    if !tokio::fs::try_exists(config_path).await.unwrap_or(false) {
+        return Ok("there is no clouds config yet".to_string());
+    }
+    let directory = config_paths::get_data_dir().join(BACKUPS_DIR);
+    tokio::fs::create_dir_all(&directory)
+        .await
+        .map_err(|e| format!("creating {}: {}", directory.display(), e))?;
+    let name = format!(
+        "clouds-config-{}.toml",
+        Local::now().format("%Y%m%d-%H%M%S")
+    );
+    let target = directory.join(&name);
+    tokio::fs::copy(config_path, &target)
+        .await
+        .map_err(|e| format!("writing {}: {}", target.display(), e))?;
+
+    let mut backups = Vec::new();
+    let mut entries = tokio::fs::read_dir(&directory)
+        .await
+        .map_err(|e| e.to_string())?;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if file_name.starts_with("clouds-config-") && file_name.ends_with(".toml") {
+            backups.push(file_name);
+        }
+    }
+    // The names sort by time
+    backups.sort();
+    let excess = backups.len().saturating_sub(keep);
+    for old in &backups[..excess] {
+        let _ = tokio::fs::remove_file(directory.join(old)).await;
+    }
+    Ok(format!("saved {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    fn task(schedule: &str) -> TaskConfig {
+        TaskConfig {
+            task: TaskKind::RotateLogs,
+            schedule: schedule.to_string(),
+            keep: default_keep(),
+        }
+    }
+
+    fn next_fire(expression: &str, after: DateTime<Local>) -> DateTime<Local> {
+        parse_schedule(expression)
+            .unwrap()
+            .after(&after)
+            .next()
+            .unwrap()
+    }
+
+    #[test]
+    fn five_field_expressions_and_shorthands_parse() {
+        for expression in [
+            "0 3 * * *",
+            " */15 * * * * ",
+            "0 9 * * MON-FRI",
+            "@daily",
+            "@hourly",
+        ] {
+            assert!(parse_schedule(expression).is_ok(), "{}", expression);
+        }
+        for expression in ["", "not cron", "61 * * * *", "* * * *"] {
+            let error = parse_schedule(expression).unwrap_err();
+            assert!(error.contains(expression.trim()), "{}", error);
+        }
+        assert!(task("0 3 * * *").validate().is_empty());
+        assert_eq!(task("0 3 * *").validate().len(), 1);
+    }
+
+    #[test]
+    fn schedules_fire_at_the_next_matching_minute() {
+        let noon = at(2026, 1, 14, 12, 0);
+        assert_eq!(next_fire("30 3 * * *", noon), at(2026, 1, 15, 3, 30));
+        assert_eq!(next_fire("*/15 * * * *", noon), at(2026, 1, 14, 12, 15));
+        assert_eq!(next_fire("@hourly", noon), at(2026, 1, 14, 13, 0));
+        assert_eq!(next_fire("@daily", noon), at(2026, 1, 15, 0, 0));
+        // A Friday morning: the next weekday is Monday
+        assert_eq!(
+            next_fire("0 9 * * MON-FRI", at(2026, 1, 16, 10, 0)),
+            at(2026, 1, 19, 9, 0)
+        );
+        assert_eq!(next_fire("0 0 1 * *", noon), at(2026, 2, 1, 0, 0));
+    }
+
+    #[test]
+    fn due_tasks_move_on_to_their_next_run() {
+        let mut scheduler = Scheduler::default();
+        scheduler.sync(&[task("0 3 * * *"), task("0 4 * * *"), task("nonsense")]);
+        scheduler.tasks[0].next_run = Some(at(2026, 1, 14, 3, 0));
+        scheduler.tasks[1].next_run = Some(at(2026, 1, 14, 4, 0));
+
+        let now = at(2026, 1, 14, 3, 0);
+        assert_eq!(scheduler.take_due(now), [0]);
+        assert_eq!(scheduler.tasks[0].next_run, Some(at(2026, 1, 15, 3, 0)));
+        assert_eq!(scheduler.tasks[1].next_run, Some(at(2026, 1, 14, 4, 0)));
+        assert!(scheduler.take_due(now).is_empty());
+        // A task missed for a while runs once, then waits for its next time
+        assert_eq!(scheduler.take_due(at(2026, 1, 14, 9, 0)), [1]);
+        assert_eq!(scheduler.tasks[1].next_run, Some(at(2026, 1, 15, 4, 0)));
+        // An invalid schedule never comes due
+        assert!(scheduler.tasks[2].next_run.is_none());
+    }
+
+    #[test]
+    fn syncing_keeps_the_state_of_unchanged_tasks() {
+        let mut scheduler = Scheduler::default();
+        scheduler.sync(&[task("0 3 * * *"), task("0 4 * * *")]);
+        scheduler.tasks[1].next_run = Some(at(2026, 1, 14, 4, 0));
+
+        scheduler.sync(&[task("0 4 * * *"), task("0 5 * * *")]);
+        assert_eq!(scheduler.tasks.len(), 2);
+        assert_eq!(scheduler.tasks[0].config.schedule, "0 4 * * *");
+        assert_eq!(scheduler.tasks[0].next_run, Some(at(2026, 1, 14, 4, 0)));
+        assert_eq!(scheduler.tasks[1].config.schedule, "0 5 * * *");
+    }
+}
@@ -0,0 +1,263 @@
+/// Encryption of the secrets section (cloud passwords, JWT secrets, SMTP passwords, webhook
+/// secrets and OnlyOffice JWT secrets) of the clouds config.
+/// The key comes either from a master passphrase (Argon2id) or from the OS keyring;
+/// secrets are sealed with XChaCha20-Poly1305.
+use crate::cloud::Cloud;
+use crate::error::{ServerError, ServerResult};
+use crate::office_preview::OfficePreviewConfig;
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    XChaCha20Poly1305, XNonce,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+#[cfg(feature = "keyring")]
+const KEYRING_SERVICE: &str = "cloudhost";
+#[cfg(feature = "keyring")]
+const KEYRING_USER: &str = "clouds-config-secrets";
+
+/// Where the key protecting the secrets comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretsProtection {
+    Passphrase,
+    Keyring,
+}
+
+impl fmt::Display for SecretsProtection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretsProtection::Passphrase => write!(f, "master passphrase"),
+            SecretsProtection::Keyring => write!(f, "OS keyring"),
+        }
+    }
+}
+
+/// The encrypted secrets section as stored in the config file (base64 fields)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EncryptedSecrets {
+    pub protection: SecretsProtection,
+    /// Argon2id salt, only used with a passphrase
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+/// Secrets of a single cloud
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CloudSecrets {
+    pub password: Option<String>,
+    pub jwt_secret: String,
+    /// `email.password`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub smtp_password: Option<String>,
+    /// The `secret` of each of `webhooks`, in their order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub webhook_secrets: Vec<String>,
+    /// `office_preview.jwt_secret` of an OnlyOffice converter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub office_jwt_secret: Option<String>,
+}
+
+impl CloudSecrets {
+    /// Move the secrets out of `cloud`, leaving them empty
+    pub fn take_from(cloud: &mut Cloud) -> Self {
+        Self {
+            password: cloud.password.take(),
+            jwt_secret: std::mem::take(&mut cloud.jwt_secret),
+            smtp_password: cloud.email.as_mut().and_then(|email| email.password.take()),
+            webhook_secrets: cloud
+                .webhooks
+                .iter_mut()
+                .map(|webhook| std::mem::take(&mut webhook.secret))
+                .collect(),
+            office_jwt_secret: match &mut cloud.office_preview {
+                Some(OfficePreviewConfig::OnlyOffice { jwt_secret, .. }) => jwt_secret.take(),
+                _ => None,
+            },
+        }
+    }
+
+    /// Put the secrets back into `cloud`
+    pub fn restore_into(self, cloud: &mut Cloud) {
+        cloud.password = self.password;
+        cloud.jwt_secret = self.jwt_secret;
+        if let Some(email) = &mut cloud.email {
+            email.password = self.smtp_password;
+        }
+        for (webhook, secret) in cloud.webhooks.iter_mut().zip(self.webhook_secrets) {
+            webhook.secret = secret;
+        }
+        if let Some(OfficePreviewConfig::OnlyOffice { jwt_secret, .. }) = &mut cloud.office_preview
+        {
+            *jwt_secret = self.office_jwt_secret;
+        }
+    }
+}
+
+/// Key for the secrets section, kept in memory while the config is unlocked
+#[derive(Clone)]
+pub struct SecretsKey {
+    protection: SecretsProtection,
+    key: [u8; KEY_LEN],
+    salt: Vec<u8>,
+}
+
+impl fmt::Debug for SecretsKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretsKey")
+            .field("protection", &self.protection)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SecretsKey {
+    /// Derive a new key from `passphrase` with a fresh salt
+    pub fn new_from_passphrase(passphrase: &str) -> ServerResult<Self> {
+        let salt = random_salt();
+        Ok(Self {
+            protection: SecretsProtection::Passphrase,
+            key: derive_key(passphrase, &salt)?,
+            salt,
+        })
+    }
+
+    /// Derive the key for an existing secrets section
+    pub fn from_passphrase(passphrase: &str, secrets: &EncryptedSecrets) -> ServerResult<Self> {
+        let salt = decode(&secrets.salt, "salt")?;
+        Ok(Self {
+            protection: SecretsProtection::Passphrase,
+            key: derive_key(passphrase, &salt)?,
+            salt,
+        })
+    }
+
+    /// Get the key stored in the OS keyring, generating and storing one if `create` is set
+    #[cfg(feature = "keyring")]
+    pub fn from_keyring(create: bool) -> ServerResult<Self> {
+        let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)
+            .map_err(|e| ServerError::internal(format!("OS keyring unavailable: {}", e)))?;
+
+        let key = match entry.get_password() {
+            Ok(encoded) => {
+                let bytes = decode(&encoded, "keyring key")?;
+                <[u8; KEY_LEN]>::try_from(bytes.as_slice())
+                    .map_err(|_| ServerError::configuration("Invalid key in the OS keyring"))?
+            }
+            Err(keyring::Error::NoEntry) if create => {
+                let mut key = [0u8; KEY_LEN];
+                OsRng.fill_bytes(&mut key);
+                entry.set_password(&BASE64.encode(key)).map_err(|e| {
+                    ServerError::internal(format!("Failed to store key in OS keyring: {}", e))
+                })?;
+                key
+            }
+            Err(e) => {
+                return Err(ServerError::authentication(format!(
+                    "No secrets key in the OS keyring: {}",
+                    e
+                )))
+            }
+        };
+
+        Ok(Self {
+            protection: SecretsProtection::Keyring,
+            key,
+            salt: Vec::new(),
+        })
+    }
+
+    #[cfg(not(feature = "keyring"))]
+    pub fn from_keyring(_create: bool) -> ServerResult<Self> {
+        Err(ServerError::configuration(
+            "This build has no OS keyring support; use a master passphrase",
+        ))
+    }
+
+    pub fn protection(&self) -> SecretsProtection {
+        self.protection
+    }
+
+    pub fn seal(&self, secrets: &BTreeMap<String, CloudSecrets>) -> ServerResult<EncryptedSecrets> {
+        let plaintext = serde_json::to_vec(secrets)?;
+        let (nonce, ciphertext) = encrypt(&self.key, &plaintext)?;
+        Ok(EncryptedSecrets {
+            protection: self.protection,
+            salt: if self.salt.is_empty() {
+                String::new()
+            } else {
+                BASE64.encode(&self.salt)
+            },
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        })
+    }
+
+    pub fn open(&self, secrets: &EncryptedSecrets) -> ServerResult<BTreeMap<String, CloudSecrets>> {
+        let nonce = decode(&secrets.nonce, "nonce")?;
+        let ciphertext = decode(&secrets.ciphertext, "ciphertext")?;
+        let plaintext = decrypt(&self.key, &nonce, &ciphertext).map_err(|_| {
+            ServerError::authentication("Wrong passphrase or corrupted secrets section")
+        })?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+}
+
+// ========== Primitives (shared with config exports) ==========
+
+pub(crate) fn random_salt() -> Vec<u8> {
+    let mut salt = vec![0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    salt
+}
+
+pub(crate) fn derive_key(passphrase: &str, salt: &[u8]) -> ServerResult<[u8; KEY_LEN]> {
+    let mut key = [0u8; KEY_LEN];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| ServerError::internal(format!("Key derivation failed: {}", e)))?;
+    Ok(key)
+}
+
+/// Returns (nonce, ciphertext)
+pub(crate) fn encrypt(key: &[u8; KEY_LEN], plaintext: &[u8]) -> ServerResult<(Vec<u8>, Vec<u8>)> {
+    let cipher = XChaCha20Poly1305::new(key.into());
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| ServerError::internal("Encryption failed"))?;
+    Ok((nonce.to_vec(), ciphertext))
+}
+
+/// Fails on a wrong key or tampered data
+pub(crate) fn decrypt(
+    key: &[u8; KEY_LEN],
+    nonce: &[u8],
+    ciphertext: &[u8],
+) -> ServerResult<Vec<u8>> {
+    if nonce.len() != NONCE_LEN {
+        return Err(ServerError::configuration("Invalid nonce"));
+    }
+    XChaCha20Poly1305::new(key.into())
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| ServerError::authentication("Decryption failed"))
+}
+
+pub(crate) fn decode(value: &str, field: &str) -> ServerResult<Vec<u8>> {
+    BASE64
+        .decode(value)
+        .map_err(|_| ServerError::configuration(format!("Invalid {} (not base64)", field)))
+}
+
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    BASE64.encode(bytes)
+}
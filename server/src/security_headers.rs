@@ -0,0 +1,159 @@
+/// Security headers added to every response of a cloud server.
+/// HTML pages and API responses get different Content-Security-Policies: the pages need
+/// their inline styles and scripts, API responses (including served files) need nothing.
+/// Public websites are sandboxed, so their scripts can't act as a logged-in visitor.
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, Uri},
+    middleware::Next,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+const PAGE_CSP: &str = "default-src 'self'; script-src 'self' 'unsafe-inline'; \
+    style-src 'self' 'unsafe-inline'; img-src 'self' data:; base-uri 'none'; \
+    form-action 'self'; frame-ancestors 'none'";
+const API_CSP: &str = "default-src 'none'; frame-ancestors 'none'";
+/// Without `allow-same-origin` the site runs in an origin of its own: no access to the
+/// cloud's cookies, and its requests to the API don't carry them
+const WEBSITE_CSP: &str =
+    "sandbox allow-scripts allow-forms allow-popups allow-modals allow-downloads";
+const FRAME_OPTIONS: &str = "DENY";
+const REFERRER_POLICY: &str = "same-origin";
+/// One year; only sent over HTTPS (e.g. behind a Cloudflare tunnel)
+const HSTS_MAX_AGE: u64 = 31_536_000;
+
+/// Per-cloud overrides (`[clouds.security_headers]` in the clouds config).
+/// Unset fields keep the defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SecurityHeadersConfig {
+    /// Content-Security-Policy of the HTML pages
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_csp: Option<String>,
+    /// Content-Security-Policy of API responses and served files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_csp: Option<String>,
+    /// Content-Security-Policy of public website folders
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub website_csp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_options: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub referrer_policy: Option<String>,
+    /// Strict-Transport-Security max-age in seconds for HTTPS requests, 0 turns it off
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hsts_max_age: Option<u64>,
+}
+
+impl SecurityHeadersConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Problems with the overrides, as `field: message`
+    pub fn validate(&self) -> Vec<String> {
+        [
+            ("page_csp", &self.page_csp),
+            ("api_csp", &self.api_csp),
+            ("website_csp", &self.website_csp),
+            ("frame_options", &self.frame_options),
+            ("referrer_policy", &self.referrer_policy),
+        ]
+        .into_iter()
+        .filter_map(|(field, value)| {
+            let value = value.as_ref()?;
+            HeaderValue::from_str(value)
+                .is_err()
+                .then(|| format!("{}: not a valid header value", field))
+        })
+        .collect()
+    }
+}
+
+/// Which kind of responses a set of headers is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseKind {
+    Page,
+    Api,
+    /// Files of a public website folder
+    Website,
+}
+
+/// Headers for one router group, built once when the cloud starts
+#[derive(Debug, Clone)]
+pub struct SecurityHeaders {
+    headers: Vec<(HeaderName, HeaderValue)>,
+    hsts: Option<HeaderValue>,
+}
+
+impl SecurityHeaders {
+    pub fn new(kind: ResponseKind, config: &SecurityHeadersConfig) -> Self {
+        let csp = match kind {
+            ResponseKind::Page => config.page_csp.as_deref().unwrap_or(PAGE_CSP),
+            ResponseKind::Api => config.api_csp.as_deref().unwrap_or(API_CSP),
+            ResponseKind::Website => config.website_csp.as_deref().unwrap_or(WEBSITE_CSP),
+        };
+        let values = [
+            ("content-security-policy", csp),
+            ("x-content-type-options", "nosniff"),
+            (
+                "x-frame-options",
+                config.frame_options.as_deref().unwrap_or(FRAME_OPTIONS),
+            ),
+            (
+                "referrer-policy",
+                config.referrer_policy.as_deref().unwrap_or(REFERRER_POLICY),
+            ),
+            ("x-xss-protection", "1; mode=block"),
+            ("cross-origin-opener-policy", "same-origin"),
+        ];
+        // Invalid overrides are reported by config validation; skip them here
+        let headers = values
+            .into_iter()
+            .filter_map(|(name, value)| {
+                Some((
+                    HeaderName::from_static(name),
+                    HeaderValue::from_str(value).ok()?,
+                ))
+            })
+            .collect();
+
+        let hsts = match config.hsts_max_age.unwrap_or(HSTS_MAX_AGE) {
+            0 => None,
+            max_age => HeaderValue::from_str(&format!("max-age={}", max_age)).ok(),
+        };
+
+        Self { headers, hsts }
+    }
+}
+
+/// Whether the client reached us over HTTPS, directly or through a TLS-terminating proxy
+pub(crate) fn is_https(uri: &Uri, headers: &HeaderMap) -> bool {
+    uri.scheme_str() == Some("https")
+        || headers
+            .get("x-forwarded-proto")
+            .is_some_and(|proto| proto.as_bytes().eq_ignore_ascii_case(b"https"))
+}
+
+/// Middleware adding the headers to every response; headers set by a handler win
+pub async fn apply(
+    State(security_headers): State<Arc<SecurityHeaders>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let https = is_https(request.uri(), request.headers());
+    let mut response = next.run(request).await;
+
+    let headers = response.headers_mut();
+    for (name, value) in &security_headers.headers {
+        headers.entry(name).or_insert_with(|| value.clone());
+    }
+    if let (true, Some(hsts)) = (https, &security_headers.hsts) {
+        headers
+            .entry("strict-transport-security")
+            .or_insert_with(|| hsts.clone());
+    }
+    response
+}
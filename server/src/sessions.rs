@@ -0,0 +1,169 @@
+/// Web logins: how long they last, the cookie that carries them and the tokens ended by
+/// logging out. The server sets the cookie itself, `HttpOnly` so page scripts can't read
+/// the token, `SameSite=Lax` so links from other sites still open the cloud logged in, and
+/// `Secure` when the request came over HTTPS, directly or through a TLS-terminating proxy.
+use axum::http::{header::HOST, HeaderMap, HeaderValue, Uri};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, PoisonError};
+
+use crate::security_headers::is_https;
+
+fn default_lifetime_hours() -> u32 {
+    24
+}
+
+fn default_remember_days() -> u32 {
+    30
+}
+
+/// `[clouds.sessions]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionConfig {
+    /// How long a login lasts
+    #[serde(default = "default_lifetime_hours")]
+    pub lifetime_hours: u32,
+    /// How long a login with "remember me" lasts, 0 to hide the checkbox
+    #[serde(default = "default_remember_days")]
+    pub remember_days: u32,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            lifetime_hours: default_lifetime_hours(),
+            remember_days: default_remember_days(),
+        }
+    }
+}
+
+impl SessionConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Problems with the lifetimes, as `field: message`
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.lifetime_hours == 0 {
+            problems.push("lifetime_hours: must be at least 1".to_string());
+        }
+        if self.remember_days > 365 {
+            problems.push("remember_days: must be at most 365".to_string());
+        }
+        problems
+    }
+
+    pub fn remember_enabled(&self) -> bool {
+        self.remember_days > 0
+    }
+
+    /// How long a login lasts, without and with "remember me"
+    pub fn lifetime(&self, remember: bool) -> chrono::Duration {
+        if remember && self.remember_enabled() {
+            chrono::Duration::days(self.remember_days.into())
+        } else {
+            chrono::Duration::hours(self.lifetime_hours.into())
+        }
+    }
+}
+
+/// `auth_token_<port>`: clouds on one host share its cookies, whatever their port, so
+/// each cloud keeps its login under its own name. The port is the one the browser sees,
+/// empty behind a proxy on the default port.
+pub fn cookie_name(headers: &HeaderMap) -> String {
+    let host = headers
+        .get(HOST)
+        .and_then(|host| host.to_str().ok())
+        .unwrap_or_default();
+    // The port follows the last colon, unless that's inside an IPv6 address
+    let port = match host.rsplit_once(':') {
+        Some((_, port)) if !port.contains(']') => port,
+        _ => "",
+    };
+    format!("auth_token_{}", port)
+}
+
+/// `Set-Cookie` value removing the login cookie
+pub fn logout_cookie(uri: &Uri, headers: &HeaderMap) -> HeaderValue {
+    login_cookie(uri, headers, "", 0)
+}
+
+/// `Set-Cookie` value for a login token, `max_age` in seconds
+pub fn login_cookie(uri: &Uri, headers: &HeaderMap, token: &str, max_age: i64) -> HeaderValue {
+    let mut cookie = format!(
+        "{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Lax",
+        cookie_name(headers),
+        token,
+        max_age
+    );
+    if is_https(uri, headers) {
+        cookie.push_str("; Secure");
+    }
+    // Tokens are base64url and dots, always a valid header value
+    HeaderValue::from_str(&cookie).unwrap_or_else(|_| HeaderValue::from_static(""))
+}
+
+/// `[[clouds.revoked_sessions]]`: a login token ended by logging out, kept until it
+/// would have expired anyway
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RevokedSession {
+    /// The token's `jti`
+    pub id: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// The revoked login tokens of a running cloud. Logouts are saved by the orchestrator,
+/// which picks them up with `take_changes`, so they stay revoked across restarts.
+#[derive(Debug, Default)]
+pub struct RevokedSessions {
+    sessions: Mutex<Vec<RevokedSession>>,
+    changed: Mutex<bool>,
+}
+
+impl RevokedSessions {
+    pub fn new(sessions: Vec<RevokedSession>) -> Self {
+        Self {
+            sessions: Mutex::new(sessions),
+            changed: Mutex::new(false),
+        }
+    }
+
+    /// Revoke the token with id `id` until `expires_at`, forgetting tokens that expired
+    pub fn revoke(&self, id: &str, expires_at: DateTime<Utc>) {
+        // A poisoned lock must not let a logout through unrevoked
+        let mut sessions = self.sessions.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Utc::now();
+        sessions.retain(|session| session.expires_at > now && session.id != id);
+        sessions.push(RevokedSession {
+            id: id.to_string(),
+            expires_at,
+        });
+        drop(sessions);
+        *self.changed.lock().unwrap_or_else(PoisonError::into_inner) = true;
+    }
+
+    pub fn is_revoked(&self, id: &str) -> bool {
+        // Fails closed: a poisoned lock still knows which tokens were revoked
+        self.sessions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .any(|session| session.id == id)
+    }
+
+    /// The revoked tokens to save, if a logout happened since the last call
+    pub fn take_changes(&self) -> Option<Vec<RevokedSession>> {
+        let mut changed = self.changed.lock().unwrap_or_else(PoisonError::into_inner);
+        if !*changed {
+            return None;
+        }
+        *changed = false;
+        Some(
+            self.sessions
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone(),
+        )
+    }
+}
@@ -0,0 +1,755 @@
+/// The SFTP requests of one session. Paths are `/<cloud folder>/...` like over FTP, and
+/// uploads are written to a temporary file next to their target, so the `pre_upload`
+/// hook sees them whole and one cut off or refused leaves nothing behind.
+use russh_sftp::protocol::{
+    Attrs, Data, File, FileAttributes, Handle, Name, OpenFlags, Status, StatusCode,
+};
+use russh_sftp::server::StatusReply;
+use std::collections::HashMap;
+use std::io::{self, SeekFrom};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tracing::{Instrument, Span};
+
+use super::session::Login;
+use super::SftpContext;
+use crate::api_routes::delete::delete_file;
+use crate::api_routes::upload::{place_upload, OnConflict};
+use crate::api_tokens::ApiTokenScope;
+use crate::cloud::{Cloud, CloudFolder};
+use crate::disk_space;
+use crate::error::ErrorCode;
+use crate::hooks::{self, HookFile};
+use crate::utils::virtual_path::{check_inside, locate, Location, VirtualPath};
+use crate::utils::{entry_name, is_upload_temp, set_file_mtime, upload_temp_path};
+use crate::webhooks::WebhookEvent;
+
+/// Largest read answered at once; clients ask for less
+const MAX_READ_BYTES: u32 = 256 * 1024;
+
+fn failure(message: impl Into<String>) -> StatusReply {
+    StatusCode::Failure.with_message(message)
+}
+
+fn no_such_file() -> StatusReply {
+    StatusCode::NoSuchFile.with_message("No such file or directory")
+}
+
+fn invalid_handle() -> StatusReply {
+    failure("Invalid handle")
+}
+
+fn ok(id: u32) -> Status {
+    Status {
+        id,
+        status_code: StatusCode::Ok,
+        error_message: "Ok".to_string(),
+        language_tag: "en-US".to_string(),
+    }
+}
+
+/// What a request does, for the scope of API tokens
+#[derive(Clone, Copy, PartialEq)]
+enum Access {
+    Read,
+    Upload,
+    Change,
+}
+
+enum OpenHandle {
+    Read(fs::File),
+    Upload(Box<Upload>),
+    /// Entries not sent yet
+    Directory(Vec<File>),
+}
+
+/// A file being received
+struct Upload {
+    file: fs::File,
+    temp_path: PathBuf,
+    /// Where the client stores it
+    path: VirtualPath,
+    directory: PathBuf,
+    folder: CloudFolder,
+    /// Opened without truncating an existing file, to append to or resume it: the
+    /// received file replaces that one rather than going by the conflict policy
+    replaces: bool,
+    append: bool,
+    size: u64,
+    /// Modification time set by the client, applied once the file is stored
+    modified: Option<SystemTime>,
+}
+
+pub(crate) struct FileSession {
+    context: Arc<SftpContext>,
+    login: Login,
+    span: Span,
+    handles: HashMap<String, OpenHandle>,
+    next_handle: u64,
+}
+
+impl FileSession {
+    pub(crate) fn new(context: Arc<SftpContext>, login: Login, span: Span) -> Self {
+        Self {
+            context,
+            login,
+            span,
+            handles: HashMap::new(),
+            next_handle: 0,
+        }
+    }
+
+    /// The latest version of the cloud, taken for every request
+    fn cloud(&self) -> Arc<Cloud> {
+        self.context.cloud.borrow().clone()
+    }
+
+    fn allow(&self, access: Access) -> Result<(), StatusReply> {
+        let allowed = match self.login.scope {
+            ApiTokenScope::Full => true,
+            ApiTokenScope::ReadOnly => access == Access::Read,
+            ApiTokenScope::UploadOnly => access == Access::Upload,
+            ApiTokenScope::Nextcloud => false,
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(StatusCode::PermissionDenied
+                .with_message(format!("Not allowed with a {} API token", self.login.scope)))
+        }
+    }
+
+    fn insert(&mut self, handle: OpenHandle) -> String {
+        self.next_handle += 1;
+        let id = self.next_handle.to_string();
+        self.handles.insert(id.clone(), handle);
+        id
+    }
+
+    // ========== Paths ==========
+
+    /// The path a client names, refused while its cloud folder is unavailable
+    fn resolve(&self, path: &str) -> Result<VirtualPath, StatusReply> {
+        let path = VirtualPath::default()
+            .resolve(path)
+            .map_err(|e| failure(format!("Invalid path: {}", e)))?;
+        if let Some(folder) = path.segments().first() {
+            self.context
+                .folder_health
+                .ensure_available(folder)
+                .map_err(failure)?;
+        }
+        Ok(path)
+    }
+
+    async fn attributes(&self, path: &str) -> Result<FileAttributes, StatusReply> {
+        let path = self.resolve(path)?;
+        let cloud = self.cloud();
+        match locate(&cloud, &path).map_err(|e| StatusCode::NoSuchFile.with_message(e))? {
+            Location::Root => Ok(directory_attributes()),
+            Location::InFolder {
+                folder, path: real, ..
+            } => {
+                let metadata = fs::metadata(&real).await.map_err(|_| no_such_file())?;
+                check_inside(folder, &real).await.map_err(failure)?;
+                Ok(FileAttributes::from(&metadata))
+            }
+        }
+    }
+
+    /// An existing file inside a cloud folder, with the folder it is in
+    async fn existing_file(
+        &self,
+        path: &str,
+    ) -> Result<(VirtualPath, PathBuf, std::fs::Metadata, CloudFolder), StatusReply> {
+        let path = self.resolve(path)?;
+        let cloud = self.cloud();
+        let Ok(Location::InFolder {
+            folder, path: real, ..
+        }) = locate(&cloud, &path)
+        else {
+            return Err(no_such_file());
+        };
+        let metadata = match fs::metadata(&real).await {
+            Ok(metadata) if metadata.is_file() => metadata,
+            _ => return Err(no_such_file()),
+        };
+        check_inside(folder, &real).await.map_err(failure)?;
+        Ok((path, real, metadata, folder.clone()))
+    }
+
+    /// Where a new entry goes: inside a cloud folder, in an existing directory
+    async fn new_entry(
+        &self,
+        cloud: &Cloud,
+        path: &str,
+    ) -> Result<(VirtualPath, PathBuf, CloudFolder), StatusReply> {
+        let path = self.resolve(path)?;
+        match locate(cloud, &path) {
+            Ok(Location::InFolder {
+                folder,
+                is_folder_root: false,
+                path: real,
+            }) => {
+                let parent = real.parent().unwrap_or(&real);
+                if !fs::metadata(parent).await.is_ok_and(|m| m.is_dir()) {
+                    return Err(no_such_file());
+                }
+                check_inside(folder, parent).await.map_err(failure)?;
+                Ok((path, real, folder.clone()))
+            }
+            Ok(_) => {
+                Err(StatusCode::PermissionDenied.with_message("Files go inside a cloud folder"))
+            }
+            Err(e) => Err(StatusCode::NoSuchFile.with_message(e)),
+        }
+    }
+
+    async fn list(&self, path: &str) -> Result<Vec<File>, StatusReply> {
+        let path = self.resolve(path)?;
+        let cloud = self.cloud();
+        let (folder, real) = match locate(&cloud, &path) {
+            Ok(Location::Root) => {
+                let mut entries = Vec::new();
+                for folder in &cloud.cloud_folders {
+                    let attrs = match fs::metadata(&folder.folder_path).await {
+                        Ok(metadata) => FileAttributes::from(&metadata),
+                        Err(_) => directory_attributes(),
+                    };
+                    entries.push(File::new(folder.name.clone(), attrs));
+                }
+                return Ok(entries);
+            }
+            Ok(Location::InFolder {
+                folder, path: real, ..
+            }) => (folder, real),
+            Err(e) => return Err(StatusCode::NoSuchFile.with_message(e)),
+        };
+        if !fs::metadata(&real).await.is_ok_and(|m| m.is_dir()) {
+            return Err(no_such_file());
+        }
+        check_inside(folder, &real).await.map_err(failure)?;
+
+        let mut entries = Vec::new();
+        let mut dir = fs::read_dir(&real)
+            .await
+            .map_err(|e| failure(format!("Can't read directory: {}", e)))?;
+        while let Ok(Some(entry)) = dir.next_entry().await {
+            let Some(name) = entry_name(entry.file_name()) else {
+                continue;
+            };
+            if is_upload_temp(&name) {
+                continue;
+            }
+            if let Ok(metadata) = fs::metadata(entry.path()).await {
+                entries.push(File::new(name, FileAttributes::from(&metadata)));
+            }
+        }
+        entries.sort_by(|a, b| a.filename.cmp(&b.filename));
+        Ok(entries)
+    }
+
+    // ========== Transfers ==========
+
+    async fn open_file(
+        &mut self,
+        id: u32,
+        filename: &str,
+        flags: OpenFlags,
+    ) -> Result<Handle, StatusReply> {
+        let handle = if flags.intersects(OpenFlags::WRITE | OpenFlags::APPEND) {
+            OpenHandle::Upload(Box::new(self.start_upload(filename, flags).await?))
+        } else {
+            self.allow(Access::Read)?;
+            let (_, real, _, _) = self.existing_file(filename).await?;
+            let file = fs::File::open(&real)
+                .await
+                .map_err(|e| failure(format!("Can't open file: {}", e)))?;
+            OpenHandle::Read(file)
+        };
+        Ok(Handle {
+            id,
+            handle: self.insert(handle),
+        })
+    }
+
+    async fn start_upload(&self, filename: &str, flags: OpenFlags) -> Result<Upload, StatusReply> {
+        self.allow(Access::Upload)?;
+        let cloud = self.cloud();
+        let (path, real, folder) = self.new_entry(&cloud, filename).await?;
+        let existing = fs::metadata(&real).await.ok();
+        if existing.as_ref().is_some_and(|m| m.is_dir()) {
+            return Err(failure("That is a directory"));
+        }
+        let exists = existing.is_some();
+        if exists && flags.contains(OpenFlags::EXCLUDE) {
+            return Err(failure("File exists"));
+        }
+        if !exists && !flags.contains(OpenFlags::CREATE) {
+            return Err(no_such_file());
+        }
+        let replaces = exists && !flags.contains(OpenFlags::TRUNCATE);
+        if replaces {
+            self.allow(Access::Change)?;
+        } else if exists && cloud.upload_conflict == OnConflict::Reject {
+            return Err(failure("File exists"));
+        }
+        disk_space::check_upload(&cloud.disk_space, &folder, None).map_err(failure)?;
+
+        let directory = real.parent().unwrap_or(&real).to_path_buf();
+        let temp_path = upload_temp_path(&directory);
+        let created = if replaces {
+            match fs::copy(&real, &temp_path).await {
+                Ok(_) => fs::OpenOptions::new().write(true).open(&temp_path).await,
+                Err(e) => Err(e),
+            }
+        } else {
+            fs::File::create_new(&temp_path).await
+        };
+        let file = match created {
+            Ok(file) => file,
+            Err(e) => {
+                let _ = fs::remove_file(&temp_path).await;
+                return Err(failure(format!("Can't create file: {}", e)));
+            }
+        };
+        Ok(Upload {
+            file,
+            temp_path,
+            path,
+            directory,
+            folder,
+            replaces,
+            append: flags.contains(OpenFlags::APPEND),
+            size: existing.filter(|_| replaces).map_or(0, |m| m.len()),
+            modified: None,
+        })
+    }
+
+    async fn read_data(
+        &mut self,
+        id: u32,
+        handle: &str,
+        offset: u64,
+        len: u32,
+    ) -> Result<Data, StatusReply> {
+        let Some(OpenHandle::Read(file)) = self.handles.get_mut(handle) else {
+            return Err(invalid_handle());
+        };
+        let failed = |e: io::Error| failure(format!("Can't read file: {}", e));
+        file.seek(SeekFrom::Start(offset)).await.map_err(failed)?;
+        let mut data = vec![0u8; len.min(MAX_READ_BYTES) as usize];
+        let read = file.read(&mut data).await.map_err(failed)?;
+        if read == 0 {
+            return Err(StatusCode::Eof.into());
+        }
+        data.truncate(read);
+        Ok(Data { id, data })
+    }
+
+    /// Stops the upload once it gets larger than the cloud's upload limit, removing what
+    /// was received
+    async fn write_data(
+        &mut self,
+        id: u32,
+        handle: &str,
+        offset: u64,
+        data: &[u8],
+    ) -> Result<Status, StatusReply> {
+        let cloud = self.cloud();
+        let Some(OpenHandle::Upload(upload)) = self.handles.get_mut(handle) else {
+            return Err(invalid_handle());
+        };
+        let offset = if upload.append { upload.size } else { offset };
+        let end = offset.saturating_add(data.len() as u64);
+        if let Err(e) = disk_space::check_upload_size(&cloud.disk_space, end.max(upload.size)) {
+            let temp_path = upload.temp_path.clone();
+            self.handles.remove(handle);
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(failure(e));
+        }
+        let failed = |e: io::Error| failure(format!("Can't write file: {}", e));
+        upload
+            .file
+            .seek(SeekFrom::Start(offset))
+            .await
+            .map_err(failed)?;
+        upload.file.write_all(data).await.map_err(failed)?;
+        upload.size = upload.size.max(end);
+        Ok(ok(id))
+    }
+
+    async fn close_handle(&mut self, id: u32, handle: &str) -> Result<Status, StatusReply> {
+        match self.handles.remove(handle) {
+            Some(OpenHandle::Upload(upload)) => self.finish_upload(*upload).await?,
+            Some(_) => {}
+            None => return Err(invalid_handle()),
+        }
+        Ok(ok(id))
+    }
+
+    /// Run the `pre_upload` hook on the received file and store it. A taken name is
+    /// handled by the cloud's upload conflict policy, like HTTP uploads without
+    /// `?on_conflict`.
+    async fn finish_upload(&self, upload: Upload) -> Result<(), StatusReply> {
+        let Upload {
+            mut file,
+            temp_path,
+            path,
+            directory,
+            folder,
+            replaces,
+            size,
+            modified,
+            ..
+        } = upload;
+        let flushed = file.flush().await;
+        drop(file);
+        if let Err(e) = flushed {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(failure(format!("Can't write file: {}", e)));
+        }
+
+        let cloud = self.cloud();
+        let user = self.login.user.as_str();
+        let hook_file = HookFile {
+            path: &temp_path,
+            display_path: &path.display(),
+            size: Some(size),
+            user,
+            source: "sftp",
+        };
+        if let Err(e) = hooks::pre_upload(&cloud.name, &folder, &hook_file).await {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(StatusCode::PermissionDenied.with_message(e));
+        }
+        let on_conflict = if replaces {
+            OnConflict::Overwrite
+        } else {
+            cloud.upload_conflict
+        };
+        let filename = path.name().unwrap_or_default().to_string();
+        let placed = place_upload(&directory, &temp_path, &filename, on_conflict).await;
+        // Already gone when it was moved into place
+        let _ = fs::remove_file(&temp_path).await;
+        let stored_name = match placed {
+            Ok((name, _)) => name,
+            Err(e) if e.code == ErrorCode::FileExists => return Err(failure("File exists")),
+            Err(e) => return Err(failure(format!("Can't store file: {}", e.message))),
+        };
+
+        let stored = directory.join(&stored_name);
+        if let Some(modified) = modified {
+            if let Err(e) = set_file_mtime(&stored, modified).await {
+                tracing::debug!(
+                    "Can't set the modification time of '{}': {}",
+                    stored.display(),
+                    e
+                );
+            }
+        }
+        let stored_path = path.parent().join(&stored_name).unwrap_or(path);
+        tracing::info!(bytes = size, "SFTP upload '{}'", stored_path.display());
+        self.context.auth_state.webhooks.file_event(
+            WebhookEvent::FileUploaded,
+            &stored_path.display(),
+            Some(size),
+            "sftp",
+        );
+        hooks::post_upload(
+            &cloud.name,
+            &folder,
+            &HookFile {
+                path: &stored,
+                display_path: &stored_path.display(),
+                size: Some(size),
+                user,
+                source: "sftp",
+            },
+        );
+        Ok(())
+    }
+
+    // ========== File management ==========
+
+    async fn remove_file(&self, id: u32, filename: &str) -> Result<Status, StatusReply> {
+        self.allow(Access::Change)?;
+        let (path, real, metadata, folder) = self.existing_file(filename).await?;
+        delete_file(&real)
+            .await
+            .map_err(|e| failure(e.to_string()))?;
+        tracing::info!("SFTP delete '{}'", path.display());
+        self.context.auth_state.webhooks.file_event(
+            WebhookEvent::FileDeleted,
+            &path.display(),
+            None,
+            "sftp",
+        );
+        hooks::post_delete(
+            &self.cloud().name,
+            &folder,
+            &HookFile {
+                path: &real,
+                display_path: &path.display(),
+                size: Some(metadata.len()),
+                user: &self.login.user,
+                source: "sftp",
+            },
+        );
+        Ok(ok(id))
+    }
+
+    async fn make_directory(&self, id: u32, path: &str) -> Result<Status, StatusReply> {
+        self.allow(Access::Change)?;
+        let (_, real, _) = self.new_entry(&self.cloud(), path).await?;
+        fs::create_dir(&real)
+            .await
+            .map_err(|e| failure(format!("Can't create directory: {}", e)))?;
+        Ok(ok(id))
+    }
+
+    async fn remove_directory(&self, id: u32, path: &str) -> Result<Status, StatusReply> {
+        self.allow(Access::Change)?;
+        let path = self.resolve(path)?;
+        let cloud = self.cloud();
+        let Ok(Location::InFolder {
+            folder,
+            is_folder_root: false,
+            path: real,
+        }) = locate(&cloud, &path)
+        else {
+            return Err(StatusCode::PermissionDenied.with_message("Cloud folders can't be removed"));
+        };
+        check_inside(folder, &real).await.map_err(failure)?;
+        fs::remove_dir(&real)
+            .await
+            .map_err(|e| failure(format!("Can't remove directory: {}", e)))?;
+        Ok(ok(id))
+    }
+
+    /// Renames never replace an existing entry
+    async fn rename_entry(&self, id: u32, from: &str, to: &str) -> Result<Status, StatusReply> {
+        self.allow(Access::Change)?;
+        let from = self.resolve(from)?;
+        let cloud = self.cloud();
+        let Ok(Location::InFolder {
+            folder,
+            is_folder_root: false,
+            path: source,
+        }) = locate(&cloud, &from)
+        else {
+            return Err(StatusCode::PermissionDenied.with_message("Cloud folders can't be renamed"));
+        };
+        if fs::symlink_metadata(&source).await.is_err() {
+            return Err(no_such_file());
+        }
+        check_inside(folder, source.parent().unwrap_or(&source))
+            .await
+            .map_err(failure)?;
+        let (_, target, _) = self.new_entry(&cloud, to).await?;
+        if fs::symlink_metadata(&target).await.is_ok() {
+            return Err(failure("Target exists"));
+        }
+        fs::rename(&source, &target)
+            .await
+            .map_err(|e| failure(format!("Can't rename: {}", e)))?;
+        Ok(ok(id))
+    }
+
+    /// Only the modification time is taken; permissions and owners stay as they are
+    async fn set_attributes(
+        &self,
+        id: u32,
+        path: &str,
+        attrs: &FileAttributes,
+    ) -> Result<Status, StatusReply> {
+        self.allow(Access::Change)?;
+        let (_, real, _, _) = self.existing_file(path).await?;
+        if let Some(modified) = modified_time(attrs) {
+            set_file_mtime(&real, modified)
+                .await
+                .map_err(|e| failure(format!("Can't set modification time: {}", e)))?;
+        }
+        Ok(ok(id))
+    }
+}
+
+impl russh_sftp::server::Handler for FileSession {
+    type Error = StatusReply;
+
+    fn unimplemented(&self) -> Self::Error {
+        StatusCode::OpUnsupported.into()
+    }
+
+    async fn open(
+        &mut self,
+        id: u32,
+        filename: String,
+        pflags: OpenFlags,
+        _attrs: FileAttributes,
+    ) -> Result<Handle, Self::Error> {
+        self.open_file(id, &filename, pflags).await
+    }
+
+    async fn close(&mut self, id: u32, handle: String) -> Result<Status, Self::Error> {
+        let span = self.span.clone();
+        self.close_handle(id, &handle).instrument(span).await
+    }
+
+    async fn read(
+        &mut self,
+        id: u32,
+        handle: String,
+        offset: u64,
+        len: u32,
+    ) -> Result<Data, Self::Error> {
+        self.read_data(id, &handle, offset, len).await
+    }
+
+    async fn write(
+        &mut self,
+        id: u32,
+        handle: String,
+        offset: u64,
+        data: Vec<u8>,
+    ) -> Result<Status, Self::Error> {
+        self.write_data(id, &handle, offset, &data).await
+    }
+
+    async fn lstat(&mut self, id: u32, path: String) -> Result<Attrs, Self::Error> {
+        let attrs = self.attributes(&path).await?;
+        Ok(Attrs { id, attrs })
+    }
+
+    async fn stat(&mut self, id: u32, path: String) -> Result<Attrs, Self::Error> {
+        let attrs = self.attributes(&path).await?;
+        Ok(Attrs { id, attrs })
+    }
+
+    async fn fstat(&mut self, id: u32, handle: String) -> Result<Attrs, Self::Error> {
+        let metadata = match self.handles.get(&handle) {
+            Some(OpenHandle::Read(file)) => file.metadata().await,
+            Some(OpenHandle::Upload(upload)) => upload.file.metadata().await,
+            Some(OpenHandle::Directory(_)) => return Err(StatusCode::OpUnsupported.into()),
+            None => return Err(invalid_handle()),
+        };
+        let metadata = metadata.map_err(|e| failure(e.to_string()))?;
+        Ok(Attrs {
+            id,
+            attrs: FileAttributes::from(&metadata),
+        })
+    }
+
+    async fn setstat(
+        &mut self,
+        id: u32,
+        path: String,
+        attrs: FileAttributes,
+    ) -> Result<Status, Self::Error> {
+        self.set_attributes(id, &path, &attrs).await
+    }
+
+    /// Uploads take the modification time once stored; otherwise nothing is changed
+    async fn fsetstat(
+        &mut self,
+        id: u32,
+        handle: String,
+        attrs: FileAttributes,
+    ) -> Result<Status, Self::Error> {
+        match self.handles.get_mut(&handle) {
+            Some(OpenHandle::Upload(upload)) => {
+                upload.modified = modified_time(&attrs).or(upload.modified);
+            }
+            Some(_) => {}
+            None => return Err(invalid_handle()),
+        }
+        Ok(ok(id))
+    }
+
+    async fn opendir(&mut self, id: u32, path: String) -> Result<Handle, Self::Error> {
+        self.allow(Access::Read)?;
+        let entries = self.list(&path).await?;
+        Ok(Handle {
+            id,
+            handle: self.insert(OpenHandle::Directory(entries)),
+        })
+    }
+
+    async fn readdir(&mut self, id: u32, handle: String) -> Result<Name, Self::Error> {
+        let Some(OpenHandle::Directory(entries)) = self.handles.get_mut(&handle) else {
+            return Err(invalid_handle());
+        };
+        if entries.is_empty() {
+            return Err(StatusCode::Eof.into());
+        }
+        Ok(Name {
+            id,
+            files: std::mem::take(entries),
+        })
+    }
+
+    async fn remove(&mut self, id: u32, filename: String) -> Result<Status, Self::Error> {
+        let span = self.span.clone();
+        self.remove_file(id, &filename).instrument(span).await
+    }
+
+    async fn mkdir(
+        &mut self,
+        id: u32,
+        path: String,
+        _attrs: FileAttributes,
+    ) -> Result<Status, Self::Error> {
+        self.make_directory(id, &path).await
+    }
+
+    async fn rmdir(&mut self, id: u32, path: String) -> Result<Status, Self::Error> {
+        self.remove_directory(id, &path).await
+    }
+
+    async fn realpath(&mut self, id: u32, path: String) -> Result<Name, Self::Error> {
+        let path = self.resolve(&path)?;
+        Ok(Name {
+            id,
+            files: vec![File::dummy(path.display())],
+        })
+    }
+
+    async fn rename(
+        &mut self,
+        id: u32,
+        oldpath: String,
+        newpath: String,
+    ) -> Result<Status, Self::Error> {
+        self.rename_entry(id, &oldpath, &newpath).await
+    }
+}
+
+impl Drop for FileSession {
+    /// Uploads the client never closed leave nothing behind
+    fn drop(&mut self) {
+        for handle in self.handles.values() {
+            if let OpenHandle::Upload(upload) = handle {
+                let _ = std::fs::remove_file(&upload.temp_path);
+            }
+        }
+    }
+}
+
+/// The root and cloud folders whose path can't be read
+fn directory_attributes() -> FileAttributes {
+    let mut attrs = FileAttributes {
+        permissions: Some(0o755),
+        ..FileAttributes::default()
+    };
+    attrs.set_dir(true);
+    attrs
+}
+
+fn modified_time(attrs: &FileAttributes) -> Option<SystemTime> {
+    attrs
+        .mtime
+        .map(|mtime| UNIX_EPOCH + Duration::from_secs(u64::from(mtime)))
+}
@@ -0,0 +1,162 @@
+/// Optional SFTP listener of a cloud, for `sftp`, `scp` and FileZilla. It's an SSH server
+/// that offers nothing but the `sftp` subsystem. The cloud folders are the top-level
+/// directories and clients log in with the cloud's password or an API token (the user
+/// name is not checked); a token keeps to its scope, so a read-only token can't change
+/// anything and an upload-only one can only upload.
+///
+/// Every listener uses the same host key, made when the first one starts and kept in the
+/// config dir, so clients see one key across restarts. Uploads follow the cloud's
+/// `upload_conflict` policy and upload limit like HTTP and FTP uploads do, and deletes go
+/// to the OS trash where the HTTP API uses it.
+mod files;
+mod session;
+
+use russh::keys::key::safe_rng;
+use russh::keys::ssh_key::LineEnding;
+use russh::keys::{Algorithm, PrivateKey};
+use russh::{MethodKind, MethodSet};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tracing::Instrument;
+
+use crate::auth::AuthState;
+use crate::cloud::Cloud;
+use crate::config_paths;
+use crate::error::{ServerError, ServerResult};
+use crate::folder_health::FolderHealth;
+use crate::request_id;
+
+const IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+/// Failed passwords before the connection is closed
+const MAX_LOGIN_ATTEMPTS: usize = 3;
+const FAILED_LOGIN_DELAY: Duration = Duration::from_secs(1);
+
+/// `[clouds.sftp]` in the clouds config; the listener runs while the cloud does
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SftpConfig {
+    /// Port of the SSH server
+    pub port: u16,
+}
+
+impl SftpConfig {
+    /// Problems with the settings, as `field: message`
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.port == 0 {
+            problems.push("port: must not be 0".to_string());
+        }
+        problems
+    }
+}
+
+/// What every connection to a cloud's listener shares
+pub(crate) struct SftpContext {
+    /// Latest version of the cloud, which sessions take for every request
+    pub cloud: watch::Receiver<Arc<Cloud>>,
+    pub auth_state: Arc<AuthState>,
+    /// Folders whose path went away are refused until they are back
+    pub folder_health: Arc<FolderHealth>,
+}
+
+/// A bound SFTP listener, ready to serve
+pub struct SftpListener {
+    listener: TcpListener,
+    port: u16,
+    ssh_config: Arc<russh::server::Config>,
+    context: Arc<SftpContext>,
+}
+
+impl SftpListener {
+    /// Load (or make) the host key and bind the port, so problems with either stop the
+    /// cloud from starting instead of failing later
+    pub async fn bind(
+        config: &SftpConfig,
+        address: IpAddr,
+        cloud: watch::Receiver<Arc<Cloud>>,
+        auth_state: Arc<AuthState>,
+        folder_health: Arc<FolderHealth>,
+    ) -> ServerResult<Self> {
+        let host_key = load_host_key(&config_paths::get_sftp_host_key_path())?;
+        let addr = SocketAddr::new(address, config.port);
+        let listener = TcpListener::bind(addr).await.map_err(|e| {
+            ServerError::Network(format!("Failed to bind SFTP port {}: {}", config.port, e))
+        })?;
+        let ssh_config = russh::server::Config {
+            server_id: russh::SshId::Standard("SSH-2.0-CloudHost".to_string()),
+            methods: MethodSet::from(&[MethodKind::Password][..]),
+            keys: vec![host_key],
+            auth_rejection_time: FAILED_LOGIN_DELAY,
+            // Clients ask which methods there are before sending a password
+            auth_rejection_time_initial: Some(Duration::ZERO),
+            max_auth_attempts: MAX_LOGIN_ATTEMPTS,
+            inactivity_timeout: Some(IDLE_TIMEOUT),
+            ..Default::default()
+        };
+        Ok(Self {
+            listener,
+            port: config.port,
+            ssh_config: Arc::new(ssh_config),
+            context: Arc::new(SftpContext {
+                cloud,
+                auth_state,
+                folder_health,
+            }),
+        })
+    }
+
+    /// Accept clients until `stopped` changes or its sender is dropped
+    pub async fn run(self, mut stopped: watch::Receiver<()>) {
+        tracing::info!("SFTP listening on port {}", self.port);
+        loop {
+            tokio::select! {
+                accepted = self.listener.accept() => match accepted {
+                    Ok((stream, peer)) => {
+                        let span = tracing::info_span!(
+                            "request",
+                            request_id = %request_id::generate(),
+                            method = "SFTP"
+                        );
+                        let connection =
+                            session::Connection::new(self.context.clone(), peer, span.clone());
+                        tokio::spawn(
+                            session::serve(
+                                self.ssh_config.clone(),
+                                stream,
+                                connection,
+                                stopped.clone(),
+                            )
+                            .instrument(span),
+                        );
+                    }
+                    Err(e) => tracing::warn!("SFTP accept failed: {}", e),
+                },
+                _ = stopped.changed() => break,
+            }
+        }
+        tracing::info!("SFTP listener on port {} stopped", self.port);
+    }
+}
+
+/// The host key at `path`, made first if there is none yet
+fn load_host_key(path: &Path) -> ServerResult<PrivateKey> {
+    let key_error = |e: &dyn std::fmt::Display| {
+        ServerError::Configuration(format!("SFTP host key '{}': {}", path.display(), e))
+    };
+    if path.exists() {
+        return russh::keys::load_secret_key(path, None).map_err(|e| key_error(&e));
+    }
+    let key = PrivateKey::random(&mut safe_rng(), Algorithm::Ed25519).map_err(|e| key_error(&e))?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| key_error(&e))?;
+    }
+    // Readable by the owner only
+    key.write_openssh_file(path, LineEnding::LF)
+        .map_err(|e| key_error(&e))?;
+    tracing::info!("Created the SFTP host key '{}'", path.display());
+    Ok(key)
+}
@@ -0,0 +1,187 @@
+/// One SSH connection: the password login and the `sftp` subsystem it opens
+use axum::http::HeaderMap;
+use russh::server::{Auth, Msg, Session};
+use russh::{Channel, ChannelId, Disconnect};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio::sync::watch;
+use tracing::{Instrument, Span};
+
+use super::files::FileSession;
+use super::SftpContext;
+use crate::api_tokens::ApiTokenScope;
+use crate::auth_log::AuthFailure;
+
+/// Who logged in
+#[derive(Debug, Clone)]
+pub(crate) struct Login {
+    /// The SSH user for the cloud password, `token:<id>` for an API token
+    pub user: String,
+    pub scope: ApiTokenScope,
+}
+
+pub(crate) struct Connection {
+    context: Arc<SftpContext>,
+    peer: SocketAddr,
+    /// Handlers run in tasks of the SSH library, outside the connection's span
+    span: Span,
+    login: Option<Login>,
+    /// Session channels opened, until they start the `sftp` subsystem
+    channels: HashMap<ChannelId, Channel<Msg>>,
+}
+
+impl Connection {
+    pub(crate) fn new(context: Arc<SftpContext>, peer: SocketAddr, span: Span) -> Self {
+        Self {
+            context,
+            peer,
+            span,
+            login: None,
+            channels: HashMap::new(),
+        }
+    }
+
+    /// The cloud password logs in with full access, an API token within its scope. Failures
+    /// count towards the same lockout as the login page, so SFTP is no way around it.
+    async fn login(&mut self, user: &str, password: &str) -> Auth {
+        let auth_state = &self.context.auth_state;
+        let limiter = &auth_state.login_limiter;
+        let address = self.peer.ip();
+        if limiter.locked_for(address).is_some() {
+            tracing::debug!("SFTP login from locked out {} refused", self.peer);
+            return Auth::reject();
+        }
+        let login = if auth_state.verify_password(password) {
+            Some(Login {
+                user: user.to_string(),
+                scope: ApiTokenScope::Full,
+            })
+        } else {
+            // App passwords of the Nextcloud apps are only good for their routes
+            auth_state
+                .api_tokens
+                .verify(password)
+                .filter(|token| token.scope != ApiTokenScope::Nextcloud)
+                .map(|token| Login {
+                    user: format!("token:{}", token.id),
+                    scope: token.scope,
+                })
+        };
+        if let Some(login) = login {
+            limiter.record_success(address);
+            tracing::info!(
+                "SFTP login from {} as '{}' ({})",
+                self.peer,
+                login.user,
+                login.scope
+            );
+            self.login = Some(login);
+            return Auth::Accept;
+        }
+
+        let cloud_name = self.context.cloud.borrow().name.clone();
+        auth_state.record_failure(&AuthFailure::new(
+            address,
+            &HeaderMap::new(),
+            &cloud_name,
+            "sftp_bad_password",
+        ));
+        limiter.record_failure(address);
+        Auth::reject()
+    }
+}
+
+impl russh::server::Handler for Connection {
+    type Error = russh::Error;
+
+    async fn auth_password(&mut self, user: &str, password: &str) -> Result<Auth, Self::Error> {
+        let span = self.span.clone();
+        Ok(self.login(user, password).instrument(span).await)
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        self.channels.insert(channel.id(), channel);
+        Ok(true)
+    }
+
+    async fn subsystem_request(
+        &mut self,
+        channel_id: ChannelId,
+        name: &str,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        let channel = self.channels.remove(&channel_id);
+        match (name, channel, &self.login) {
+            ("sftp", Some(channel), Some(login)) => {
+                session.channel_success(channel_id)?;
+                let files =
+                    FileSession::new(self.context.clone(), login.clone(), self.span.clone());
+                russh_sftp::server::run(channel.into_stream(), files).await;
+            }
+            _ => session.channel_failure(channel_id)?,
+        }
+        Ok(())
+    }
+
+    /// Only SFTP is served, no shell or commands
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        session.channel_failure(channel)?;
+        Ok(())
+    }
+
+    async fn exec_request(
+        &mut self,
+        channel: ChannelId,
+        _data: &[u8],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        session.channel_failure(channel)?;
+        Ok(())
+    }
+}
+
+/// Run one SSH connection until the client leaves or the cloud stops
+pub(crate) async fn serve(
+    config: Arc<russh::server::Config>,
+    stream: TcpStream,
+    connection: Connection,
+    mut stopped: watch::Receiver<()>,
+) {
+    let peer = connection.peer;
+    tracing::info!("SFTP connection from {}", peer);
+    let session = match russh::server::run_stream(config, stream, connection).await {
+        Ok(session) => session,
+        Err(e) => {
+            tracing::debug!("SSH handshake with {} failed: {}", peer, e);
+            return;
+        }
+    };
+    let handle = session.handle();
+    tokio::select! {
+        ended = session => {
+            if let Err(e) = ended {
+                tracing::debug!("SFTP connection from {} failed: {}", peer, e);
+            }
+        }
+        _ = stopped.changed() => {
+            let _ = handle
+                .disconnect(
+                    Disconnect::ByApplication,
+                    "The cloud stopped".to_string(),
+                    "en".to_string(),
+                )
+                .await;
+        }
+    }
+    tracing::debug!("SFTP connection from {} closed", peer);
+}
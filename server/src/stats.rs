@@ -0,0 +1,167 @@
+/// Runtime statistics of a running cloud: requests served, bytes received and sent,
+/// connections being served and uptime. Shown in the TUI's Stats panel and at `/api/stats`.
+/// A request counts as an active connection, and is listed in `clients`, until its
+/// response body has been sent, so downloads, video streams and log streams count for as
+/// long as they run.
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use http_body::{Frame, SizeHint};
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use crate::auth_log::forwarded_client_ip;
+use crate::clients::{ClientConnection, Clients};
+
+/// Counters of one cloud, shared by its requests
+#[derive(Debug)]
+pub struct CloudStats {
+    started_at: Instant,
+    requests: AtomicU64,
+    bytes_received: AtomicU64,
+    bytes_sent: AtomicU64,
+    pub clients: Clients,
+}
+
+/// The counters at one point in time
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct StatsSnapshot {
+    pub requests: u64,
+    pub bytes_received: u64,
+    pub bytes_sent: u64,
+    pub active_connections: u64,
+    pub uptime_secs: u64,
+}
+
+impl Default for CloudStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CloudStats {
+    pub fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            requests: AtomicU64::new(0),
+            bytes_received: AtomicU64::new(0),
+            bytes_sent: AtomicU64::new(0),
+            clients: Clients::default(),
+        }
+    }
+
+    pub fn snapshot(&self) -> StatsSnapshot {
+        StatsSnapshot {
+            requests: self.requests.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            active_connections: self.clients.len() as u64,
+            uptime_secs: self.started_at.elapsed().as_secs(),
+        }
+    }
+}
+
+/// Leaves the clients when the response is done or dropped
+struct ConnectionGuard {
+    stats: Arc<CloudStats>,
+    id: u64,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.stats.clients.remove(self.id);
+    }
+}
+
+/// A body that adds the size of every data frame passing through to the counters,
+/// and fails once its client is killed
+struct CountingBody {
+    inner: Body,
+    stats: Arc<CloudStats>,
+    client: Arc<ClientConnection>,
+    sent: bool,
+    _guard: Option<ConnectionGuard>,
+}
+
+impl HttpBody for CountingBody {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, axum::Error>>> {
+        self.client.register_waker(cx.waker());
+        if self.client.is_killed() {
+            return Poll::Ready(Some(Err(axum::Error::new("The connection was killed"))));
+        }
+        let poll = Pin::new(&mut self.inner).poll_frame(cx);
+        if let Poll::Ready(Some(Ok(frame))) = &poll {
+            if let Some(data) = frame.data_ref() {
+                self.client.add_bytes(data.len() as u64);
+                let counter = if self.sent {
+                    &self.stats.bytes_sent
+                } else {
+                    &self.stats.bytes_received
+                };
+                counter.fetch_add(data.len() as u64, Ordering::Relaxed);
+            }
+        }
+        poll
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+/// Outer middleware: count the request and the bytes of its body and response, and
+/// list it as a client until the response is sent
+pub async fn track(
+    State(stats): State<Arc<CloudStats>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    stats.requests.fetch_add(1, Ordering::Relaxed);
+    let ip = forwarded_client_ip(request.headers()).unwrap_or(peer.ip());
+    let client = stats.clients.register(ip, &request);
+    let guard = ConnectionGuard {
+        stats: stats.clone(),
+        id: client.id,
+    };
+    // The auth middleware adds the user
+    request.extensions_mut().insert(client.clone());
+
+    let request = request.map(|body| {
+        Body::new(CountingBody {
+            inner: body,
+            stats: stats.clone(),
+            client: client.clone(),
+            sent: false,
+            _guard: None,
+        })
+    });
+    let response = next.run(request).await;
+    response.map(|body| {
+        Body::new(CountingBody {
+            inner: body,
+            stats,
+            client,
+            sent: true,
+            _guard: Some(guard),
+        })
+    })
+}
@@ -0,0 +1,179 @@
+/// Forwards `tracing` events into the DebugStream of the cloud they belong to.
+///
+/// Every cloud server runs inside a `cloud` span and every HTTP request gets a `request`
+/// span carrying `cloud`, `request_id`, `method` and `path` fields. Events are routed by
+/// the innermost `cloud` field in their span scope, so the same `tracing::info!` ends up in
+/// the TUI, the WebSocket log stream and the cloud's log file. Events inside a request are
+/// prefixed with `[request_id METHOD /path]`. Events outside any cloud are ignored.
+///
+/// `install()` sets a global subscriber with just this layer. Applications with a
+/// subscriber of their own add `DebugStreamLayer` to it instead.
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::{Arc, LazyLock, RwLock};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+use crate::debug_stream::{DebugStream, LogLevel};
+
+/// Debug streams of the running clouds, by cloud name
+static STREAMS: LazyLock<RwLock<HashMap<String, Arc<DebugStream>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Send events of `cloud`'s spans to `stream` (replaces an earlier registration)
+pub fn register(cloud: &str, stream: Arc<DebugStream>) {
+    if let Ok(mut streams) = STREAMS.write() {
+        streams.insert(cloud.to_string(), stream);
+    }
+}
+
+pub fn unregister(cloud: &str) {
+    if let Ok(mut streams) = STREAMS.write() {
+        streams.remove(cloud);
+    }
+}
+
+/// Install a global subscriber made of `DebugStreamLayer` alone.
+/// Does nothing if the application already set one.
+pub fn install() {
+    let subscriber = tracing_subscriber::registry().with(DebugStreamLayer);
+    let _ = tracing::subscriber::set_global_default(subscriber);
+}
+
+pub struct DebugStreamLayer;
+
+/// The fields of a span the layer cares about
+#[derive(Default)]
+struct SpanFields {
+    cloud: Option<String>,
+    request_id: Option<String>,
+    method: Option<String>,
+    path: Option<String>,
+}
+
+impl SpanFields {
+    /// `ab12cd34 GET /api/files` for request spans
+    fn request_label(&self) -> Option<String> {
+        let mut label = self.request_id.clone()?;
+        for part in [&self.method, &self.path].into_iter().flatten() {
+            label.push(' ');
+            label.push_str(part);
+        }
+        Some(label)
+    }
+}
+
+impl Visit for SpanFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "cloud" => self.cloud = Some(value.to_string()),
+            "request_id" => self.request_id = Some(value.to_string()),
+            "method" => self.method = Some(value.to_string()),
+            "path" => self.path = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        // `%value` fields arrive here
+        self.record_str(field, &format!("{:?}", value));
+    }
+}
+
+/// The event's message followed by its other fields as `key=value`
+#[derive(Default)]
+struct EventText {
+    message: String,
+    fields: String,
+}
+
+impl Visit for EventText {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+fn log_level(level: &Level) -> LogLevel {
+    match *level {
+        Level::ERROR => LogLevel::Error,
+        Level::WARN => LogLevel::Warning,
+        Level::INFO => LogLevel::Info,
+        _ => LogLevel::Debug,
+    }
+}
+
+/// `cloudhost_server::api_routes::files` -> `files`
+fn source(target: &str) -> &str {
+    target.rsplit("::").next().unwrap_or(target)
+}
+
+impl<S> Layer<S> for DebugStreamLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut fields = SpanFields::default();
+        attrs.record(&mut fields);
+        if fields.cloud.is_none() && fields.request_id.is_none() {
+            return;
+        }
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(fields);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if *metadata.level() == Level::TRACE {
+            return;
+        }
+
+        let (mut cloud, mut request) = (None, None);
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope {
+                if let Some(fields) = span.extensions().get::<SpanFields>() {
+                    cloud = cloud.or_else(|| fields.cloud.clone());
+                    request = request.or_else(|| fields.request_label());
+                }
+            }
+        }
+        let Some(cloud) = cloud else {
+            return;
+        };
+        let Some(stream) = STREAMS
+            .read()
+            .ok()
+            .and_then(|streams| streams.get(&cloud).cloned())
+        else {
+            return;
+        };
+
+        let mut text = EventText::default();
+        event.record(&mut text);
+        let mut message = text.message + &text.fields;
+        if let Some(request) = request {
+            message = format!("[{}] {}", request, message);
+        }
+        stream.push(
+            log_level(metadata.level()),
+            source(metadata.target()),
+            message,
+        );
+    }
+}
@@ -0,0 +1,382 @@
+/// Optional ffmpeg transcoding of videos browsers can't play (MKV, AVI, HEVC, ...) into
+/// H.264/AAC, as an HLS stream or a single MP4. Renditions are made on first request
+/// and cached in `transcode-cache/<cloud>/` in the data directory, keyed by the video's
+/// path, size and modification time, so an edited video is transcoded again. Renditions
+/// not used for `CACHE_LIFETIME` are deleted. The cache can be deleted at any time.
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+use tracing::Instrument;
+
+use crate::config_paths;
+
+const CACHE_LIFETIME: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+const PLAYLIST_NAME: &str = "index.m3u8";
+const MP4_NAME: &str = "video.mp4";
+/// Written by ffmpeg as `segment_00000.ts`, ...
+const SEGMENT_PREFIX: &str = "segment_";
+/// Last lines of ffmpeg's stderr kept for the error of a failed job
+const MAX_ERROR_CHARS: usize = 500;
+
+fn default_ffmpeg_path() -> PathBuf {
+    PathBuf::from("ffmpeg")
+}
+
+fn default_max_jobs() -> usize {
+    1
+}
+
+fn is_default_ffmpeg_path(path: &Path) -> bool {
+    path == default_ffmpeg_path()
+}
+
+fn is_default_max_jobs(max_jobs: &usize) -> bool {
+    *max_jobs == default_max_jobs()
+}
+
+/// `[clouds.transcoding]` in the clouds config; without it videos are only served as is
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscodeConfig {
+    /// The ffmpeg binary, looked up in `PATH` unless it's a path
+    #[serde(
+        default = "default_ffmpeg_path",
+        skip_serializing_if = "is_default_ffmpeg_path"
+    )]
+    pub ffmpeg_path: PathBuf,
+    /// Videos transcoded at the same time; more wait their turn
+    #[serde(
+        default = "default_max_jobs",
+        skip_serializing_if = "is_default_max_jobs"
+    )]
+    pub max_jobs: usize,
+}
+
+impl Default for TranscodeConfig {
+    fn default() -> Self {
+        Self {
+            ffmpeg_path: default_ffmpeg_path(),
+            max_jobs: default_max_jobs(),
+        }
+    }
+}
+
+impl TranscodeConfig {
+    /// Problems with the settings, as `field: message`
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.ffmpeg_path.as_os_str().is_empty() {
+            problems.push("ffmpeg_path: must not be empty".to_string());
+        }
+        if self.max_jobs == 0 {
+            problems.push("max_jobs: must be at least 1".to_string());
+        }
+        problems
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RenditionFormat {
+    /// H.264 segments with a playlist, playable while ffmpeg is still working
+    Hls,
+    /// One H.264 MP4, available once done
+    Mp4,
+}
+
+impl RenditionFormat {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Hls => "hls",
+            Self::Mp4 => "mp4",
+        }
+    }
+}
+
+/// What a rendition request found
+pub enum Rendition {
+    /// The playlist can be served; it may still grow while ffmpeg works
+    Playlist(PathBuf),
+    /// The finished MP4
+    File(PathBuf),
+    /// Queued or not far enough yet
+    Pending,
+    /// ffmpeg failed, with the end of its output
+    Failed(String),
+}
+
+#[derive(Default)]
+struct Jobs {
+    running: HashSet<PathBuf>,
+    /// Not retried until the server restarts, a broken video would fail every time
+    failed: HashMap<PathBuf, String>,
+}
+
+pub struct Transcoder {
+    config: TranscodeConfig,
+    cache_dir: PathBuf,
+    slots: Arc<Semaphore>,
+    jobs: Arc<Mutex<Jobs>>,
+}
+
+impl Transcoder {
+    pub fn new(cloud_name: &str, config: &TranscodeConfig) -> Self {
+        Self {
+            config: config.clone(),
+            cache_dir: get_transcode_cache_dir(cloud_name),
+            slots: Arc::new(Semaphore::new(config.max_jobs.max(1))),
+            jobs: Arc::new(Mutex::new(Jobs::default())),
+        }
+    }
+
+    /// Where the renditions of a video go. `id` identifies the video in the cloud,
+    /// e.g. `<cloud folder>/<relative path>`.
+    fn rendition_dir(
+        &self,
+        id: &str,
+        size: u64,
+        modified: SystemTime,
+        format: RenditionFormat,
+    ) -> PathBuf {
+        let modified = modified
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let key = format!(
+            "{:x}",
+            Sha256::digest(format!("{}\0{}\0{}", id, size, modified))
+        );
+        self.cache_dir
+            .join(format!("{}-{}", &key[..32], format.as_str()))
+    }
+
+    /// The rendition of `source`, starting ffmpeg if it doesn't exist yet.
+    /// `stopped` ends the job when the server stops.
+    pub fn rendition(
+        &self,
+        source: &Path,
+        id: &str,
+        format: RenditionFormat,
+        stopped: tokio::sync::watch::Receiver<()>,
+    ) -> std::io::Result<Rendition> {
+        let metadata = std::fs::metadata(source)?;
+        let dir = self.rendition_dir(id, metadata.len(), metadata.modified()?, format);
+
+        let Ok(mut jobs) = self.jobs.lock() else {
+            return Ok(Rendition::Failed(
+                "Transcoder state is poisoned".to_string(),
+            ));
+        };
+        if let Some(error) = jobs.failed.get(&dir) {
+            return Ok(Rendition::Failed(error.clone()));
+        }
+        let running = jobs.running.contains(&dir);
+        match format {
+            RenditionFormat::Hls => {
+                let playlist = dir.join(PLAYLIST_NAME);
+                // Finished, or far enough along to start playing
+                let ready = std::fs::read_to_string(&playlist).is_ok_and(|playlist| {
+                    playlist.contains("#EXT-X-ENDLIST") || (running && playlist.contains(".ts"))
+                });
+                if ready {
+                    touch(&dir);
+                    return Ok(Rendition::Playlist(playlist));
+                }
+            }
+            RenditionFormat::Mp4 => {
+                let file = dir.join(MP4_NAME);
+                if file.is_file() {
+                    touch(&dir);
+                    return Ok(Rendition::File(file));
+                }
+            }
+        }
+        if running {
+            return Ok(Rendition::Pending);
+        }
+
+        // Left over from a job the server didn't finish
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir)?;
+        jobs.running.insert(dir.clone());
+        drop(jobs);
+        self.prune_cache();
+
+        tracing::info!("Transcoding '{}' to {}", id, format.as_str());
+        // Logged to the cloud like the request that started it
+        tokio::spawn(
+            run_job(
+                self.config.ffmpeg_path.clone(),
+                source.to_path_buf(),
+                dir,
+                format,
+                self.slots.clone(),
+                self.jobs.clone(),
+                stopped,
+            )
+            .in_current_span(),
+        );
+        Ok(Rendition::Pending)
+    }
+
+    /// Delete renditions not used for a while, except the ones being made. Errors are
+    /// ignored.
+    pub fn prune_cache(&self) {
+        let running = self
+            .jobs
+            .lock()
+            .map(|jobs| jobs.running.clone())
+            .unwrap_or_default();
+        prune(&self.cache_dir, &running);
+    }
+}
+
+/// Delete renditions not used for a while from the cache of a cloud that isn't running
+pub fn prune_cache_dir(cache_dir: &Path) {
+    prune(cache_dir, &HashSet::new());
+}
+
+fn prune(cache_dir: &Path, running: &HashSet<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(cache_dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let unused = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > CACHE_LIFETIME);
+        if unused && !running.contains(&path) {
+            let _ = std::fs::remove_dir_all(&path);
+        }
+    }
+}
+
+/// A segment next to an HLS playlist, `None` unless `name` is one ffmpeg would write
+pub fn segment_path(playlist: &Path, name: &str) -> Option<PathBuf> {
+    let number = name.strip_prefix(SEGMENT_PREFIX)?.strip_suffix(".ts")?;
+    if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(playlist.parent()?.join(name))
+}
+
+/// Mark a rendition as used, so pruning keeps it
+fn touch(dir: &Path) {
+    if let Ok(file) = std::fs::File::open(dir) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+async fn run_job(
+    ffmpeg_path: PathBuf,
+    source: PathBuf,
+    dir: PathBuf,
+    format: RenditionFormat,
+    slots: Arc<Semaphore>,
+    jobs: Arc<Mutex<Jobs>>,
+    mut stopped: tokio::sync::watch::Receiver<()>,
+) {
+    let result = tokio::select! {
+        result = async {
+            let _permit = slots.acquire().await.map_err(|e| e.to_string())?;
+            transcode(&ffmpeg_path, &source, &dir, format).await
+        } => result,
+        _ = stopped.changed() => Err("The server stopped".to_string()),
+    };
+
+    let Ok(mut jobs) = jobs.lock() else {
+        return;
+    };
+    jobs.running.remove(&dir);
+    match result {
+        Ok(()) => tracing::info!("Transcoded '{}'", source.display()),
+        Err(error) => {
+            tracing::warn!("Transcoding '{}' failed: {}", source.display(), error);
+            let _ = std::fs::remove_dir_all(&dir);
+            if !stopped.has_changed().unwrap_or(true) {
+                jobs.failed.insert(dir, error);
+            }
+        }
+    }
+}
+
+/// Run ffmpeg, returns the end of its output if it fails. ffmpeg is killed if this
+/// future is dropped.
+async fn transcode(
+    ffmpeg_path: &Path,
+    source: &Path,
+    dir: &Path,
+    format: RenditionFormat,
+) -> Result<(), String> {
+    let mut command = Command::new(ffmpeg_path);
+    command
+        .args(["-nostdin", "-hide_banner", "-loglevel", "error", "-i"])
+        .arg(source)
+        // First video and audio stream, if there is audio
+        .args(["-map", "0:v:0", "-map", "0:a:0?"])
+        .args(["-c:v", "libx264", "-preset", "veryfast", "-crf", "23"])
+        .args([
+            "-pix_fmt", "yuv420p", "-c:a", "aac", "-b:a", "128k", "-ac", "2",
+        ]);
+    let partial_mp4 = dir.join(format!("{}.part", MP4_NAME));
+    match format {
+        RenditionFormat::Hls => {
+            command
+                .args(["-f", "hls", "-hls_time", "6", "-hls_playlist_type", "event"])
+                .arg("-hls_segment_filename")
+                .arg(dir.join(format!("{}%05d.ts", SEGMENT_PREFIX)))
+                .arg(dir.join(PLAYLIST_NAME));
+        }
+        RenditionFormat::Mp4 => {
+            command
+                .args(["-movflags", "+faststart", "-f", "mp4"])
+                .arg(&partial_mp4);
+        }
+    }
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to run '{}': {}", ffmpeg_path.display(), e))?;
+
+    let mut output = String::new();
+    if let Some(mut stderr) = child.stderr.take() {
+        let _ = stderr.read_to_string(&mut output).await;
+    }
+    let status = child.wait().await.map_err(|e| e.to_string())?;
+    if !status.success() {
+        let output = output.trim();
+        let start = output
+            .char_indices()
+            .rev()
+            .nth(MAX_ERROR_CHARS)
+            .map_or(0, |(index, _)| index);
+        return Err(format!(
+            "ffmpeg exited with {}: {}",
+            status,
+            &output[start..]
+        ));
+    }
+    if format == RenditionFormat::Mp4 {
+        std::fs::rename(&partial_mp4, dir.join(MP4_NAME)).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// `transcode-cache/<cloud>` in the data directory, with unusual characters replaced
+pub fn get_transcode_cache_dir(cloud_name: &str) -> PathBuf {
+    config_paths::get_data_dir()
+        .join("transcode-cache")
+        .join(config_paths::cloud_file_stem(cloud_name))
+}
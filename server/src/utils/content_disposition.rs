@@ -0,0 +1,26 @@
+/// `Content-Disposition` with a file name that survives any characters. Browsers that know
+/// RFC 6266 take the UTF-8 name of `filename*` (RFC 5987); older clients get `filename`,
+/// an ASCII stand-in with whatever it can't hold replaced by `_`.
+use axum::http::HeaderValue;
+use percent_encoding::utf8_percent_encode;
+
+use crate::html::PATH_SEGMENT;
+
+/// `disposition` (`inline` or `attachment`) with `file_name`
+pub fn content_disposition(disposition: &str, file_name: &str) -> HeaderValue {
+    let fallback: String = file_name
+        .chars()
+        .map(|c| match c {
+            ' '..='~' if c != '"' && c != '\\' && c != '%' => c,
+            _ => '_',
+        })
+        .collect();
+    let value = format!(
+        "{}; filename=\"{}\"; filename*=UTF-8''{}",
+        disposition,
+        fallback,
+        utf8_percent_encode(file_name, PATH_SEGMENT)
+    );
+    // Only visible ASCII is left, which is always a valid header value
+    HeaderValue::from_str(&value).unwrap_or_else(|_| HeaderValue::from_static("attachment"))
+}
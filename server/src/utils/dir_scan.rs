@@ -0,0 +1,90 @@
+/// Reading a directory with the metadata of its entries for listings. Each entry is
+/// stat'ed once, files without a known extension also have their first bytes read, and
+/// large directories are scanned on several threads, as that's what makes listing
+/// folders of thousands of files slow, on network drives above all.
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::mime::detect_mime_blocking;
+use super::mtime::to_unix_seconds;
+use super::path_utils::entry_name;
+use super::upload_temp::is_upload_temp;
+
+/// Directories with fewer entries are stat'ed on one thread
+const PARALLEL_FROM: usize = 256;
+/// Threads stat'ing one directory at most
+const MAX_THREADS: usize = 8;
+
+#[derive(Debug, Clone)]
+pub struct ScannedEntry {
+    pub name: String,
+    pub path: PathBuf,
+    /// Symlinks count as what they lead to
+    pub is_dir: bool,
+    /// Unset for directories and entries that couldn't be stat'ed
+    pub size: Option<u64>,
+    /// Unix seconds
+    pub modified: Option<i64>,
+    /// Content type of files, by extension or by the first bytes
+    pub mime_type: Option<&'static str>,
+}
+
+/// The entries of `directory`, directories first, then by name. Runs on the blocking pool.
+pub async fn scan_directory(directory: PathBuf) -> io::Result<Vec<ScannedEntry>> {
+    tokio::task::spawn_blocking(move || scan_directory_blocking(&directory))
+        .await
+        .map_err(io::Error::other)?
+}
+
+fn scan_directory_blocking(directory: &Path) -> io::Result<Vec<ScannedEntry>> {
+    let entries: Vec<(String, PathBuf)> = fs::read_dir(directory)?
+        .flatten()
+        .filter_map(|entry| Some((entry_name(entry.file_name())?, entry.path())))
+        // Uploads still being received
+        .filter(|(name, _)| !is_upload_temp(name))
+        .collect();
+
+    let threads = std::thread::available_parallelism()
+        .map_or(1, |threads| threads.get())
+        .min(MAX_THREADS)
+        .min(entries.len() / PARALLEL_FROM);
+    let mut scanned: Vec<ScannedEntry> = if threads <= 1 {
+        entries.into_iter().map(stat).collect()
+    } else {
+        let chunk_size = entries.len().div_ceil(threads);
+        std::thread::scope(|scope| {
+            let chunks: Vec<_> = entries
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || chunk.iter().cloned().map(stat).collect::<Vec<_>>())
+                })
+                .collect();
+            chunks
+                .into_iter()
+                .flat_map(|chunk| chunk.join().unwrap_or_default())
+                .collect()
+        })
+    };
+
+    scanned.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+    Ok(scanned)
+}
+
+fn stat((name, path): (String, PathBuf)) -> ScannedEntry {
+    let metadata = fs::metadata(&path).ok();
+    let is_dir = metadata.as_ref().is_some_and(|metadata| metadata.is_dir());
+    ScannedEntry {
+        size: metadata
+            .as_ref()
+            .filter(|_| !is_dir)
+            .map(|metadata| metadata.len()),
+        modified: metadata
+            .and_then(|metadata| metadata.modified().ok())
+            .map(to_unix_seconds),
+        mime_type: (!is_dir).then(|| detect_mime_blocking(&path)),
+        name,
+        path,
+        is_dir,
+    }
+}
@@ -0,0 +1,103 @@
+/// Content types of files, for downloads, listings and WebDAV. The extension decides when
+/// there is one `mime_guess` knows; a file without, such as a photo a phone saved as
+/// `IMG_0001`, is told by its first bytes. Source code is sent as plain text, so browsers
+/// show it instead of offering a download or, for scripts, running it.
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+pub const OCTET_STREAM: &str = "application/octet-stream";
+
+/// Bytes read from the start of a file to tell its type
+const SNIFF_BYTES: usize = 512;
+
+/// Extensions of source code, shown as text
+const SOURCE_CODE: &[&str] = &[
+    "rs", "py", "java", "c", "cc", "cpp", "cxx", "h", "hpp", "cs", "php", "rb", "go", "swift",
+    "kt", "scala", "sh", "bash", "zsh", "bat", "ps1", "toml", "yaml", "yml", "ini", "log",
+];
+
+/// The type of `path` by its extension, if it has one that's known
+pub fn mime_from_extension(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    if SOURCE_CODE.contains(&extension.as_str()) {
+        return Some("text/plain");
+    }
+    mime_guess::from_ext(&extension).first_raw()
+}
+
+/// The type of a file starting with `head`, from the magic numbers of common formats.
+/// Text is anything that's valid UTF-8 without NUL bytes.
+pub fn sniff_mime(head: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+        (b"Rar!\x1a\x07", "application/vnd.rar"),
+        (b"ID3\x03", "audio/mpeg"),
+        (b"ID3\x04", "audio/mpeg"),
+        (b"fLaC", "audio/flac"),
+        (b"OggS", "audio/ogg"),
+        (b"\x1a\x45\xdf\xa3", "video/webm"),
+    ];
+    if let Some((_, mime)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| head.starts_with(signature))
+    {
+        return Some(mime);
+    }
+    // Containers with the format's name after a size
+    match (head.get(..4), head.get(4..8), head.get(8..12)) {
+        (Some(b"RIFF"), _, Some(b"WEBP")) => return Some("image/webp"),
+        (Some(b"RIFF"), _, Some(b"WAVE")) => return Some("audio/wav"),
+        (Some(b"RIFF"), _, Some(b"AVI ")) => return Some("video/x-msvideo"),
+        (_, Some(b"ftyp"), Some(b"qt  ")) => return Some("video/quicktime"),
+        (_, Some(b"ftyp"), Some(b"heic" | b"heix" | b"mif1")) => return Some("image/heic"),
+        (_, Some(b"ftyp"), _) => return Some("video/mp4"),
+        _ => {}
+    }
+    let text = match std::str::from_utf8(head) {
+        Ok(text) => text,
+        // Cut off in the middle of a character
+        Err(e) if e.error_len().is_none() => {
+            std::str::from_utf8(&head[..e.valid_up_to()]).unwrap_or_default()
+        }
+        Err(_) => return None,
+    };
+    (!head.is_empty() && !text.contains('\0')).then_some("text/plain")
+}
+
+/// The type of the file at `path`, reading its start when the extension doesn't tell.
+/// Blocks; see `detect_mime`.
+pub fn detect_mime_blocking(path: &Path) -> &'static str {
+    if let Some(mime) = mime_from_extension(path) {
+        return mime;
+    }
+    read_head(path)
+        .ok()
+        .and_then(|head| sniff_mime(&head))
+        .unwrap_or(OCTET_STREAM)
+}
+
+/// `detect_mime_blocking` on the blocking pool
+pub async fn detect_mime(path: PathBuf) -> &'static str {
+    tokio::task::spawn_blocking(move || detect_mime_blocking(&path))
+        .await
+        .unwrap_or(OCTET_STREAM)
+}
+
+fn read_head(path: &Path) -> io::Result<Vec<u8>> {
+    // Opening a FIFO waits for a writer, which may never come
+    if !std::fs::metadata(path)?.is_file() {
+        return Err(io::ErrorKind::InvalidInput.into());
+    }
+    let mut head = Vec::with_capacity(SNIFF_BYTES);
+    std::fs::File::open(path)?
+        .take(SNIFF_BYTES as u64)
+        .read_to_end(&mut head)?;
+    Ok(head)
+}
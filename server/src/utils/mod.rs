@@ -1,3 +1,17 @@
+pub mod content_disposition;
+pub mod dir_scan;
+pub mod format;
+pub mod mime;
+pub mod mtime;
 pub mod path_utils;
+pub mod photo_metadata;
+pub mod upload_temp;
+pub mod virtual_path;
 
+pub use content_disposition::*;
+pub use dir_scan::*;
+pub use format::*;
+pub use mime::*;
+pub use mtime::*;
 pub use path_utils::*;
+pub use upload_temp::*;
@@ -0,0 +1,71 @@
+/// File modification times in the API: unix seconds, as sent by sync clients in the
+/// ownCloud-style `X-OC-Mtime` upload header and returned as `modified` in listings.
+use axum::http::HeaderMap;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::ApiError;
+
+pub const MTIME_HEADER: &str = "x-oc-mtime";
+
+/// `SystemTime` for unix seconds; fractions are accepted and dropped like ownCloud does
+pub fn parse_mtime(value: &str) -> Result<SystemTime, String> {
+    let value = value.trim();
+    let seconds = value.split_once('.').map_or(value, |(whole, _)| whole);
+    let seconds: i64 = seconds
+        .parse()
+        .map_err(|_| format!("'{}' is not a unix timestamp", value))?;
+    from_unix_seconds(seconds)
+}
+
+pub fn from_unix_seconds(seconds: i64) -> Result<SystemTime, String> {
+    let offset = Duration::from_secs(seconds.unsigned_abs());
+    let time = if seconds >= 0 {
+        UNIX_EPOCH.checked_add(offset)
+    } else {
+        UNIX_EPOCH.checked_sub(offset)
+    };
+    time.ok_or_else(|| format!("{} is out of range", seconds))
+}
+
+pub fn to_unix_seconds(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => after.as_secs() as i64,
+        Err(before) => -(before.duration().as_secs() as i64),
+    }
+}
+
+/// Modification time of `path` in unix seconds, if the platform reports one
+pub fn modified_unix_seconds(path: &Path) -> Option<i64> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .map(to_unix_seconds)
+}
+
+/// The `X-OC-Mtime` header of a request, `None` when absent
+pub fn mtime_from_headers(headers: &HeaderMap) -> Result<Option<SystemTime>, ApiError> {
+    let Some(value) = headers.get(MTIME_HEADER) else {
+        return Ok(None);
+    };
+    value
+        .to_str()
+        .map_err(|_| "not text".to_string())
+        .and_then(parse_mtime)
+        .map(Some)
+        .map_err(|e| ApiError::validation(format!("Invalid X-OC-Mtime header: {}", e)))
+}
+
+/// Set the modification time of an existing file
+pub async fn set_file_mtime(path: &Path, mtime: SystemTime) -> std::io::Result<()> {
+    let path = path.to_path_buf();
+    // Windows needs write access to change file times
+    tokio::task::spawn_blocking(move || {
+        std::fs::File::options()
+            .write(true)
+            .open(path)?
+            .set_modified(mtime)
+    })
+    .await
+    .map_err(std::io::Error::other)?
+}
@@ -1,148 +1,172 @@
-use axum::{http::StatusCode, response::Json};
-use serde_json::json;
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
 
-use crate::cloud::CloudServerState;
+use crate::cloud::{CloudFolder, CloudServerState};
+use crate::error::{ApiError, ErrorCode};
 
-/// Parsed path components for API operations
-#[derive(Debug)]
-pub struct ParsedPath {
-    pub cloud_folder_name: String,
-    pub subdirectory_path: String,
-    pub filename: Option<String>,
+/// Check that `part` is a plain file or directory name: not empty, `.` or `..`,
+/// and without separators or a drive prefix
+pub fn validate_path_component(part: &str) -> Result<(), String> {
+    if part.contains('\\') || part.contains('\0') {
+        return Err(format!("'{}' contains a forbidden character", part));
+    }
+    let mut components = Path::new(part).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(()),
+        _ => Err(format!("'{}' is not allowed in a path", part)),
+    }
 }
 
-/// Parse a target path into components
-/// Expected format: "cloud_folder_name/subdirectory/path" or "cloud_folder_name/subdirectory/path/filename"
-pub fn parse_target_path(
-    target_path: &str,
-) -> Result<ParsedPath, (StatusCode, Json<serde_json::Value>)> {
-    let path_parts: Vec<&str> = target_path.split('/').collect();
+/// The name of a directory entry, if clients can ask for it. Paths come to every route
+/// as UTF-8, so a name that isn't can't be opened, downloaded or deleted through the
+/// cloud; listings leave it out rather than show a mangled name that leads nowhere.
+pub fn entry_name(name: OsString) -> Option<String> {
+    name.into_string()
+        .map_err(|name| tracing::debug!("Leaving out {:?}, its name isn't UTF-8", name))
+        .ok()
+}
 
-    if path_parts.is_empty() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(json!({
-                "error": "Invalid path format. Expected: cloud_folder_name/subdirectory/path"
-            })),
-        ));
+/// Turn a path from a client, relative to `root`, into a path on disk. Every route that
+/// takes a path goes through here. The path is split on `/`; empty parts and `.` are
+/// skipped, and a part that isn't a plain name (`..`, a `\`, a drive prefix or a NUL)
+/// refuses the whole path. The result is `root` with plain names added, so it can't leave
+/// `root` lexically; `ensure_inside_cloud_folder` checks where symlinks lead.
+pub fn sanitize_path(root: &Path, relative: &str) -> Result<PathBuf, String> {
+    let mut path = root.to_path_buf();
+    for part in relative.split('/') {
+        if part.is_empty() || part == "." {
+            continue;
+        }
+        validate_path_component(part)?;
+        path.push(part);
     }
+    Ok(path)
+}
 
-    let cloud_folder_name = path_parts[0].to_string();
-    let remaining_parts = &path_parts[1..];
+/// Parse a target: "cloud_folder_name" or "cloud_folder_name/subdirectory/path". The rest
+/// is sanitized, so the result can't leave the cloud folder; it's empty for the folder itself.
+pub fn parse_directory_path(target_path: &str) -> Result<(String, PathBuf), ApiError> {
+    let invalid = |message: String| {
+        ApiError::new(
+            ErrorCode::InvalidPath,
+            format!("Invalid target path '{}': {}", target_path, message),
+        )
+    };
 
-    if remaining_parts.is_empty() {
-        // Just cloud folder name, no subdirectory or filename
-        return Ok(ParsedPath {
-            cloud_folder_name,
-            subdirectory_path: String::new(),
-            filename: None,
-        });
+    let target = target_path.trim_start_matches('/');
+    let (cloud_folder_name, rest) = target.split_once('/').unwrap_or((target, ""));
+    if cloud_folder_name.is_empty() {
+        return Err(invalid(
+            "expected cloud_folder_name/subdirectory/path".to_string(),
+        ));
     }
+    let subdirectory = sanitize_path(Path::new(""), rest).map_err(invalid)?;
+    Ok((cloud_folder_name.to_string(), subdirectory))
+}
 
-    // Check if last part looks like a filename (has extension or is a single word)
-    let last_part = remaining_parts.last().map_or("", |v| v);
-    let has_extension = last_part.contains('.');
-    let is_likely_filename = has_extension || remaining_parts.len() == 1;
-
-    if is_likely_filename {
-        // Last part is filename
-        let filename = last_part.to_string();
-        let subdirectory_path = if remaining_parts.len() > 1 {
-            remaining_parts[..remaining_parts.len() - 1].join("/")
-        } else {
-            String::new()
-        };
-
-        Ok(ParsedPath {
-            cloud_folder_name,
-            subdirectory_path,
-            filename: Some(filename),
-        })
-    } else {
-        // No filename, just subdirectory path
-        let subdirectory_path = remaining_parts.join("/");
-
-        Ok(ParsedPath {
-            cloud_folder_name,
-            subdirectory_path,
-            filename: None,
-        })
+/// Check that `path`, once symlinks are resolved, is inside the cloud folder `root`.
+/// `path` must exist; 403 otherwise, as a broken link could point anywhere.
+pub async fn ensure_inside_cloud_folder(
+    root: &Path,
+    path: &Path,
+    display_path: &str,
+) -> Result<(), ApiError> {
+    let resolved = tokio::fs::canonicalize(path).await.ok();
+    let resolved_root = tokio::fs::canonicalize(root).await.ok();
+    match (resolved, resolved_root) {
+        (Some(resolved), Some(resolved_root)) if resolved.starts_with(&resolved_root) => Ok(()),
+        _ => Err(ApiError::new(
+            ErrorCode::OutsideCloudFolder,
+            format!("'{}' is outside the cloud folder", display_path),
+        )),
     }
 }
 
-/// Find a cloud folder by name
+/// Find a cloud folder by name, if it is available
 pub fn find_cloud_folder<'a>(
     server_state: &'a CloudServerState,
     cloud_folder_name: &str,
-) -> Result<&'a crate::cloud::CloudFolder, (StatusCode, Json<serde_json::Value>)> {
-    server_state
+) -> Result<&'a CloudFolder, ApiError> {
+    let cloud_folder = server_state
         .cloud
-        .cloud_folders
-        .iter()
-        .find(|folder| folder.name == cloud_folder_name)
-        .ok_or_else(|| {
-            (
-                StatusCode::NOT_FOUND,
-                Json(json!({
-                    "error": "Cloud folder not found"
-                })),
-            )
-        })
+        .get_cloud_folder(cloud_folder_name)
+        .ok_or_else(|| ApiError::new(ErrorCode::CloudFolderNotFound, "Cloud folder not found"))?;
+    ensure_folder_available(server_state, cloud_folder_name)?;
+    Ok(cloud_folder)
 }
 
-/// Construct the full file path from parsed components
-pub fn construct_file_path(
-    cloud_folder: &crate::cloud::CloudFolder,
-    subdirectory_path: &str,
-    filename: &str,
-) -> PathBuf {
-    let base_path = cloud_folder.folder_path.clone();
-    if subdirectory_path.is_empty() {
-        base_path.join(filename)
-    } else {
-        base_path.join(subdirectory_path).join(filename)
-    }
+/// 503 for a cloud folder whose path went away, e.g. with its drive, until it is back
+pub fn ensure_folder_available(
+    server_state: &CloudServerState,
+    cloud_folder_name: &str,
+) -> Result<(), ApiError> {
+    server_state
+        .folder_health
+        .ensure_available(cloud_folder_name)
+        .map_err(|e| ApiError::new(ErrorCode::Unavailable, e))
 }
 
-/// Construct the full directory path from parsed components
-pub fn construct_directory_path(
-    cloud_folder: &crate::cloud::CloudFolder,
-    subdirectory_path: &str,
-) -> PathBuf {
-    let base_path = cloud_folder.folder_path.clone();
-    if subdirectory_path.is_empty() {
-        base_path
-    } else {
-        base_path.join(subdirectory_path)
-    }
+/// A file or directory a request names, after the checks every route that reads one
+/// makes: the cloud folder exists, the path is made of plain names, something is there,
+/// and no symlink on the way leads out of the cloud folder
+#[derive(Debug)]
+pub struct ResolvedPath<'a> {
+    pub cloud_folder: &'a CloudFolder,
+    /// `path` on disk
+    pub full_path: PathBuf,
+    pub metadata: std::fs::Metadata,
+}
+
+/// `path` inside the cloud folder `cloud_folder_name` of the cloud, as it is on disk.
+/// The API answers the errors as they are, pages show them as error pages.
+pub async fn resolve_path<'a>(
+    server_state: &'a CloudServerState,
+    cloud_folder_name: &str,
+    path: &str,
+) -> Result<ResolvedPath<'a>, ApiError> {
+    let cloud_folder = find_cloud_folder(server_state, cloud_folder_name)?;
+    let full_path = sanitize_path(&cloud_folder.folder_path, path)
+        .map_err(|e| ApiError::new(ErrorCode::InvalidPath, e))?;
+    let metadata = tokio::fs::metadata(&full_path)
+        .await
+        .map_err(|_| ApiError::not_found("The requested resource was not found"))?;
+    let display_path = format!("{}/{}", cloud_folder_name, path.trim_matches('/'));
+    ensure_inside_cloud_folder(&cloud_folder.folder_path, &full_path, &display_path).await?;
+    Ok(ResolvedPath {
+        cloud_folder,
+        full_path,
+        metadata,
+    })
+}
+
+/// `Path::is_file` without blocking the runtime
+pub async fn is_file(path: &Path) -> bool {
+    tokio::fs::metadata(path)
+        .await
+        .is_ok_and(|metadata| metadata.is_file())
+}
+
+/// `Path::is_dir` without blocking the runtime
+pub async fn is_dir(path: &Path) -> bool {
+    tokio::fs::metadata(path)
+        .await
+        .is_ok_and(|metadata| metadata.is_dir())
 }
 
 /// Validate that a file exists
-pub async fn validate_file_exists(
-    file_path: &std::path::Path,
-) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
-    if !file_path.exists() {
-        return Err((
-            StatusCode::NOT_FOUND,
-            Json(json!({
-                "error": "File not found"
-            })),
-        ));
+pub async fn validate_file_exists(file_path: &std::path::Path) -> Result<(), ApiError> {
+    if tokio::fs::metadata(file_path).await.is_err() {
+        return Err(ApiError::not_found("File not found"));
     }
     Ok(())
 }
 
 /// Create directory if it doesn't exist
-pub async fn ensure_directory_exists(
-    dir_path: &std::path::Path,
-) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+pub async fn ensure_directory_exists(dir_path: &std::path::Path) -> Result<(), ApiError> {
     if let Err(e) = tokio::fs::create_dir_all(dir_path).await {
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": format!("Failed to create directory: {}", e)
-            })),
+        return Err(ApiError::new(
+            ErrorCode::FileSystem,
+            format!("Failed to create directory: {}", e),
         ));
     }
     Ok(())
@@ -0,0 +1,97 @@
+/// Metadata read from photos: when and where they were taken and with which camera.
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PhotoMetadata {
+    /// EXIF times are the camera's local time, without a zone
+    pub taken: Option<NaiveDateTime>,
+    /// Make and model, e.g. `Apple iPhone 12`
+    pub camera: Option<String>,
+    pub gps: Option<GpsPosition>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GpsPosition {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// Read the EXIF metadata of the file at `path`. Everything is `None` for files without
+/// EXIF data, which includes everything that isn't a photo. Blocking.
+pub fn read_photo_metadata(path: &Path) -> PhotoMetadata {
+    let Some(exif) = std::fs::File::open(path).ok().and_then(|file| {
+        exif::Reader::new()
+            .read_from_container(&mut std::io::BufReader::new(file))
+            .ok()
+    }) else {
+        return PhotoMetadata::default();
+    };
+    PhotoMetadata {
+        taken: [exif::Tag::DateTimeOriginal, exif::Tag::DateTime]
+            .into_iter()
+            .find_map(|tag| {
+                // Cameras without a clock write "0000:00:00 00:00:00", which doesn't parse
+                NaiveDateTime::parse_from_str(&ascii(&exif, tag)?, "%Y:%m:%d %H:%M:%S").ok()
+            }),
+        camera: camera(&exif),
+        gps: gps(&exif),
+    }
+}
+
+/// When the photo at `path` was taken according to its EXIF data (`DateTimeOriginal`,
+/// falling back to `DateTime`)
+pub async fn date_taken(path: &Path) -> Option<NaiveDateTime> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || read_photo_metadata(&path).taken)
+        .await
+        .ok()
+        .flatten()
+}
+
+fn ascii(exif: &exif::Exif, tag: exif::Tag) -> Option<String> {
+    let field = exif.get_field(tag, exif::In::PRIMARY)?;
+    let exif::Value::Ascii(ref values) = field.value else {
+        return None;
+    };
+    let text = String::from_utf8_lossy(values.first()?);
+    let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+fn camera(exif: &exif::Exif) -> Option<String> {
+    let make = ascii(exif, exif::Tag::Make);
+    let model = ascii(exif, exif::Tag::Model);
+    match (make, model) {
+        // Many models already start with the make ("Canon EOS 80D")
+        (Some(make), Some(model)) if model.starts_with(&make) => Some(model),
+        (Some(make), Some(model)) => Some(format!("{} {}", make, model)),
+        (make, model) => make.or(model),
+    }
+}
+
+fn gps(exif: &exif::Exif) -> Option<GpsPosition> {
+    let coordinate = |tag, reference, negative: &str| {
+        let field = exif.get_field(tag, exif::In::PRIMARY)?;
+        let exif::Value::Rational(ref parts) = field.value else {
+            return None;
+        };
+        // Degrees, minutes, seconds
+        let value = parts
+            .iter()
+            .zip([1.0, 60.0, 3600.0])
+            .map(|(part, divisor)| part.to_f64() / divisor)
+            .sum::<f64>();
+        let sign = if ascii(exif, reference).as_deref() == Some(negative) {
+            -1.0
+        } else {
+            1.0
+        };
+        value.is_finite().then_some(sign * value)
+    };
+    Some(GpsPosition {
+        latitude: coordinate(exif::Tag::GPSLatitude, exif::Tag::GPSLatitudeRef, "S")?,
+        longitude: coordinate(exif::Tag::GPSLongitude, exif::Tag::GPSLongitudeRef, "W")?,
+    })
+}
@@ -0,0 +1,61 @@
+/// Temporary files of uploads in progress. Uploads are received next to where they end
+/// up, so moving them into place is a rename on the same filesystem, under a hidden
+/// `.upload-<uuid>.tmp` name that listings leave out. Ones left behind by a crash are
+/// removed when a cloud starts.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const PREFIX: &str = ".upload-";
+const SUFFIX: &str = ".tmp";
+/// Files not written to for this long belong to no upload in progress, of this cloud or
+/// of another one serving the same folder
+const STALE_AFTER: Duration = Duration::from_secs(60 * 60);
+
+/// A fresh temporary file name in `directory`
+pub fn upload_temp_path(directory: &Path) -> PathBuf {
+    directory.join(format!(
+        "{}{}{}",
+        PREFIX,
+        uuid::Uuid::new_v4().simple(),
+        SUFFIX
+    ))
+}
+
+pub fn is_upload_temp(name: &str) -> bool {
+    name.starts_with(PREFIX) && name.ends_with(SUFFIX)
+}
+
+/// Remove stale upload temp files anywhere under `root`, without following symlinks.
+/// Returns how many were removed.
+pub fn remove_stale_upload_temps(root: &Path) -> usize {
+    let mut removed = 0;
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(directory) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&directory) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                pending.push(entry.path());
+                continue;
+            }
+            let temp =
+                file_type.is_file() && entry.file_name().to_str().is_some_and(is_upload_temp);
+            let stale = temp
+                && entry
+                    .metadata()
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+                    .and_then(|modified| modified.elapsed().ok())
+                    .is_some_and(|age| age > STALE_AFTER);
+            if stale && fs::remove_file(entry.path()).is_ok() {
+                removed += 1;
+            }
+        }
+    }
+    removed
+}
@@ -0,0 +1,95 @@
+/// Paths as FTP and WebDAV clients see them: `/` lists the cloud folders,
+/// `/<cloud folder>/...` is inside one.
+use std::path::PathBuf;
+
+use super::{ensure_inside_cloud_folder, sanitize_path, validate_path_component};
+use crate::cloud::{Cloud, CloudFolder};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VirtualPath(Vec<String>);
+
+impl VirtualPath {
+    /// Resolve a client argument against this directory; `..` stops at the root
+    pub fn resolve(&self, arg: &str) -> Result<VirtualPath, String> {
+        let mut parts = if arg.starts_with('/') {
+            Vec::new()
+        } else {
+            self.0.clone()
+        };
+        for part in arg.split('/') {
+            match part {
+                "" | "." => {}
+                ".." => {
+                    parts.pop();
+                }
+                part => {
+                    validate_path_component(part)?;
+                    parts.push(part.to_string());
+                }
+            }
+        }
+        Ok(VirtualPath(parts))
+    }
+
+    /// The entry `name` in this directory; `name` must be a single plain component
+    pub fn join(&self, name: &str) -> Result<VirtualPath, String> {
+        validate_path_component(name)?;
+        let mut parts = self.0.clone();
+        parts.push(name.to_string());
+        Ok(VirtualPath(parts))
+    }
+
+    pub fn segments(&self) -> &[String] {
+        &self.0
+    }
+
+    pub fn parent(&self) -> VirtualPath {
+        VirtualPath(self.0[..self.0.len().saturating_sub(1)].to_vec())
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.0.last().map(String::as_str)
+    }
+
+    /// `/photos/2024`
+    pub fn display(&self) -> String {
+        format!("/{}", self.0.join("/"))
+    }
+}
+
+/// Where a virtual path lives on disk
+pub enum Location<'a> {
+    /// The list of cloud folders
+    Root,
+    InFolder {
+        folder: &'a CloudFolder,
+        /// Whether this is the cloud folder itself, which can't be removed or renamed
+        is_folder_root: bool,
+        path: PathBuf,
+    },
+}
+
+pub fn locate<'a>(cloud: &'a Cloud, path: &VirtualPath) -> Result<Location<'a>, String> {
+    let Some((folder_name, rest)) = path.0.split_first() else {
+        return Ok(Location::Root);
+    };
+    let folder = cloud
+        .cloud_folders
+        .iter()
+        .find(|folder| &folder.name == folder_name)
+        .ok_or_else(|| format!("No cloud folder '{}'", folder_name))?;
+    let real = sanitize_path(&folder.folder_path, &rest.join("/"))?;
+    Ok(Location::InFolder {
+        folder,
+        is_folder_root: rest.is_empty(),
+        path: real,
+    })
+}
+
+/// Refuse paths that symlinks lead out of the cloud folder. `existing` is the path
+/// itself, or the directory a new entry is created in.
+pub async fn check_inside(folder: &CloudFolder, existing: &std::path::Path) -> Result<(), String> {
+    ensure_inside_cloud_folder(&folder.folder_path, existing, "Path")
+        .await
+        .map_err(|_| "Path is outside the cloud folder".to_string())
+}
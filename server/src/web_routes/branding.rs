@@ -0,0 +1,8 @@
+use axum::{extract::State, response::Response};
+
+use crate::cloud::CloudServerState;
+
+/// `GET /branding/logo`, public so the login page can show it
+pub async fn branding_logo(State(server_state): State<CloudServerState>) -> Response {
+    server_state.branding.logo_response()
+}
@@ -1,97 +1,37 @@
 use axum::{
     extract::{Path, State},
-    http::{header, HeaderMap, StatusCode},
-    response::{Html, Json, Response},
+    http::{header, HeaderMap},
+    response::{Html, Response},
 };
 use serde_json::json;
-use std::{fs, sync::Arc};
-
-use crate::{
-    auth::AuthState,
-    cloud::{Cloud, CloudServerState},
-};
-
-// Helper function to get authentication state from server state
-fn get_authentication_state(server_state: &CloudServerState) -> &Arc<AuthState> {
-    &server_state.auth_state
-}
-
-// Checks if request has valid authentication token
-fn has_valid_token(headers: &HeaderMap, auth_state: &AuthState) -> bool {
-    // Check Authorization header
-    if let Some(auth_header) = headers.get("Authorization") {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if let Some(token) = auth_str.strip_prefix("Bearer ") {
-                if auth_state.verify_token(token).is_ok() {
-                    return true;
-                }
-            }
-        }
-    }
-
-    // Check cookies
-    if let Some(cookie_header) = headers.get("Cookie") {
-        if let Ok(cookie_str) = cookie_header.to_str() {
-            for cookie in cookie_str.split(';') {
-                let cookie = cookie.trim();
-                if cookie.starts_with("auth_token_") {
-                    if let Some(token) = cookie.split('=').nth(1) {
-                        if auth_state.verify_token(token).is_ok() {
-                            return true;
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    false
-}
-
-// Verifies authentication and returns HTML redirect if unauthorized
-fn verify_authentication(headers: &HeaderMap, auth_state: &AuthState) -> Result<(), Html<String>> {
-    if has_valid_token(headers, auth_state) {
-        return Ok(());
-    }
-
-    // Return HTML redirect to login page
-    let redirect_html = r#"
-<!DOCTYPE html>
-<html>
-<head>
-    <meta http-equiv="refresh" content="0; url=/login">
-    <title>Redirecting to Login</title>
-</head>
-<body>
-    <p>Redirecting to login page...</p>
-    <script>window.location.href = '/login';</script>
-</body>
-</html>
-    "#;
-
-    Err(Html(redirect_html.to_string()))
-}
+use std::path::PathBuf;
+use tokio::fs;
+
+use crate::auth::AuthenticatedUser;
+use crate::branding::Branding;
+use crate::cloud::{Cloud, CloudServerState};
+use crate::csrf::{CsrfToken, CSRF_HEADER};
+use crate::error::{ApiError, ErrorCode};
+use crate::html::{encode_path, escape_html};
+use crate::http_cache::{self, Validators};
+use crate::office_preview::is_previewable;
+use crate::utils::{content_disposition, detect_mime, resolve_path, scan_directory, ResolvedPath};
+use crate::web_routes::login::session_controls;
 
 pub async fn show_cloud_folder_info(
     State(server_state): State<CloudServerState>,
-    headers: HeaderMap,
 ) -> Result<Html<String>, Html<String>> {
-    // Check authentication
-    let auth_state = get_authentication_state(&server_state);
-    match verify_authentication(&headers, auth_state) {
-        Ok(()) => (),
-        Err(redirect_html) => return Err(redirect_html),
-    }
-
     let cloud = &server_state.cloud;
+    let branding = &server_state.branding;
 
     let html = format!(
         r#"
     <!DOCTYPE html>
-    <html>
+    <html lang="en">
     <head>
-        <title>CloudTUI - {}</title>
+        <title>{} - {}</title>
         <style>
+            {}
             body {{ font-family: Arial, sans-serif; margin: 40px; }}
             .container {{ max-width: 800px; margin: 0 auto; }}
             .header {{ text-align: center; margin-bottom: 30px; }}
@@ -99,20 +39,20 @@ pub async fn show_cloud_folder_info(
             .actions {{ background: #f9f9f9; padding: 20px; border-radius: 5px; }}
             .btn {{ 
                 display: inline-block; 
-                background: #007bff; 
+                background: var(--accent); 
                 color: white; 
                 padding: 10px 20px; 
                 text-decoration: none; 
                 border-radius: 5px; 
                 margin: 5px;
             }}
-            .btn:hover {{ background: #0056b3; }}
+            .btn:hover {{ opacity: 0.85; }}
         </style>
     </head>
     <body>
-        <div class="container">
+        <main class="container">
             <div class="header">
-                <h1>🌩️ CloudTUI</h1>
+                <h1>{} {}</h1>
             </div>
             <div class="cloud-folder-info">
                 <h2>Cloud Folder Information</h2>
@@ -124,14 +64,18 @@ pub async fn show_cloud_folder_info(
                 <a href="/web/{}/files" class="btn">📁 Browse Files</a>
                 <a href="/" class="btn">🏠 Back to All Cloud Folders</a>
             </div>
-        </div>
+        </main>
     </body>
     </html>
     "#,
-        cloud.name,
-        cloud.name,
+        branding.name_html(),
+        escape_html(&cloud.name),
+        branding.css_variables(),
+        branding.logo_html(),
+        branding.name_html(),
+        escape_html(&cloud.name),
         cloud.cloud_folders.len(),
-        cloud.name,
+        encode_path(&cloud.name),
     );
 
     Ok(Html(html))
@@ -141,149 +85,193 @@ pub async fn show_cloud_folder_info(
 pub async fn list_cloud_folder_files(
     Path(cloud_folder_name): Path<String>,
     State(server_state): State<CloudServerState>,
-    headers: HeaderMap,
-) -> Result<Html<String>, Html<String>> {
-    // Check authentication
-    let auth_state = get_authentication_state(&server_state);
-    match verify_authentication(&headers, auth_state) {
-        Ok(()) => (),
-        Err(redirect_html) => return Err(redirect_html),
-    }
-    let cloud = &server_state.cloud;
-
-    browse_directory_internal(cloud.as_ref().clone(), cloud_folder_name, "".to_string())
-        .await
-        .map_err(|_| {
-            Html(
-                r#"
-<!DOCTYPE html>
-<html>
-<head>
-    <title>File Listing Error</title>
-</head>
-<body>
-    <h1>Error</h1>
-    <p>Failed to list files. Please try again later.</p>
-    <a href="/">Back to Home</a>
-</body>
-</html>
-            "#
-                .to_string(),
-            )
-        })
+    CsrfToken(csrf_token): CsrfToken,
+    user: AuthenticatedUser,
+) -> Result<Html<String>, Response> {
+    browse(&server_state, &cloud_folder_name, "", &csrf_token, &user).await
 }
 
-async fn browse_directory_internal(
-    cloud: Cloud,
-    cloud_folder_name: String,
-    requested_path: String,
-) -> Result<Html<String>, StatusCode> {
-    // Find the specific cloud folder
-    let base_path = if let Some(cloud_folder) = cloud
-        .cloud_folders
-        .iter()
-        .find(|cf| cf.name == cloud_folder_name)
-    {
-        &cloud_folder.folder_path
-    } else {
-        return Err(StatusCode::NOT_FOUND);
-    };
-    let full_path = base_path.join(&requested_path);
-
-    // Security check: ensure the requested path is within the cloud folder directory
-    if !full_path.starts_with(base_path) {
-        return Err(StatusCode::FORBIDDEN);
-    }
+pub async fn browse_file_or_directory(
+    Path((cloud_folder_name, path)): Path<(String, String)>,
+    State(server_state): State<CloudServerState>,
+    CsrfToken(csrf_token): CsrfToken,
+    user: AuthenticatedUser,
+) -> Result<Html<String>, Response> {
+    browse(&server_state, &cloud_folder_name, &path, &csrf_token, &user).await
+}
 
-    if !full_path.exists() {
-        return Err(StatusCode::NOT_FOUND);
-    }
+/// The page for a path: a listing for a directory, a download link for a file
+async fn browse(
+    server_state: &CloudServerState,
+    cloud_folder_name: &str,
+    path: &str,
+    csrf_token: &str,
+    user: &AuthenticatedUser,
+) -> Result<Html<String>, Response> {
+    let cloud = &server_state.cloud;
+    let branding = &server_state.branding;
+    let error_page = |e: ApiError| branding.error_page(e.status(), &e.message);
 
-    // If it's a file, redirect to the static file service
-    if full_path.is_file() {
-        let redirect_url = format!("/static/{}", requested_path);
-        return Ok(Html(format!(
-            r#"<html><head><meta http-equiv="refresh" content="0; url={}"></head><body>Redirecting to file...</body></html>"#,
-            redirect_url
-        )));
+    let resolved = resolve_path(server_state, cloud_folder_name, path)
+        .await
+        .map_err(error_page)?;
+    if resolved.metadata.is_dir() {
+        return render_directory(
+            cloud,
+            branding,
+            cloud_folder_name,
+            path,
+            resolved.full_path,
+            csrf_token,
+            user,
+        )
+        .await
+        .map_err(error_page);
     }
 
-    // Read directory contents
-    let entries = match fs::read_dir(&full_path) {
-        Ok(entries) => entries,
-        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
-    };
-
-    let mut items = Vec::new();
-    for entry in entries.flatten() {
-        let file_name = entry.file_name().to_string_lossy().to_string();
-        let file_path = entry.path();
-        let is_dir = file_path.is_dir();
-        let size = if is_dir {
-            "DIR".to_string()
-        } else {
-            match fs::metadata(&file_path) {
-                Ok(metadata) => format!("{} bytes", metadata.len()),
-                Err(_) => "Unknown".to_string(),
-            }
-        };
+    // It's a file, show a download link instead of serving directly
+    let file_name = resolved
+        .full_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("unknown");
+    let file_name = escape_html(file_name);
+    let html = format!(
+        r#"
+        <!DOCTYPE html>
+        <html lang="en">
+        <head>
+            <title>File: {}</title>
+            <style>
+                {}
+                body {{ font-family: Arial, sans-serif; margin: 40px; }}
+                .container {{ max-width: 800px; margin: 0 auto; }}
+                .file-info {{ background: #f0f0f0; padding: 20px; border-radius: 5px; }}
+                .download-btn {{ 
+                    display: inline-block; 
+                    background: var(--accent); 
+                    color: white; 
+                    padding: 10px 20px; 
+                    text-decoration: none; 
+                    border-radius: 5px; 
+                    margin-top: 10px;
+                }}
+            </style>
+        </head>
+        <body>
+            <main class="container">
+                <h1>📄 File: {}</h1>
+                <div class="file-info">
+                    <p><strong>File:</strong> {}</p>
+                    <p><strong>Cloud:</strong> {}</p>
+                    <a href="{}" class="download-btn">⬇️ Download File</a>
+                </div>
+            </main>
+        </body>
+        </html>
+        "#,
+        file_name,
+        branding.css_variables(),
+        file_name,
+        file_name,
+        escape_html(&cloud.name),
+        static_url(cloud_folder_name, path)
+    );
 
-        let item_path = if requested_path.is_empty() {
-            file_name.clone()
-        } else {
-            format!("{}/{}", requested_path, file_name)
-        };
+    Ok(Html(html))
+}
 
-        items.push(json!({
-            "name": file_name,
-            "is_directory": is_dir,
-            "size": size,
-            "path": item_path
-        }));
-    }
+async fn render_directory(
+    cloud: &Cloud,
+    branding: &Branding,
+    cloud_folder_name: &str,
+    requested_path: &str,
+    full_path: PathBuf,
+    csrf_token: &str,
+    user: &AuthenticatedUser,
+) -> Result<Html<String>, ApiError> {
+    // Read directory contents, sorted with directories first
+    let entries = scan_directory(full_path)
+        .await
+        .map_err(|_| ApiError::new(ErrorCode::FileSystem, "Failed to read the directory"))?;
+
+    let items: Vec<serde_json::Value> = entries
+        .into_iter()
+        .map(|entry| {
+            let size = match (entry.is_dir, entry.size) {
+                (true, _) => "DIR".to_string(),
+                (false, Some(size)) => format!("{} bytes", size),
+                (false, None) => "Unknown".to_string(),
+            };
+            let item_path = if requested_path.is_empty() {
+                entry.name.clone()
+            } else {
+                format!("{}/{}", requested_path, entry.name)
+            };
+            json!({
+                "name": entry.name,
+                "is_directory": entry.is_dir,
+                "size": size,
+                "path": item_path
+            })
+        })
+        .collect();
 
-    // Sort: directories first, then files, both alphabetically
-    items.sort_by(|a, b| {
-        let a_is_dir = a["is_directory"].as_bool().unwrap_or(false);
-        let b_is_dir = b["is_directory"].as_bool().unwrap_or(false);
-
-        match (a_is_dir, b_is_dir) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a["name"]
-                .as_str()
-                .unwrap_or("")
-                .cmp(b["name"].as_str().unwrap_or("")),
-        }
-    });
+    let location = if requested_path.is_empty() {
+        format!("/{}", cloud_folder_name)
+    } else {
+        format!("/{}/{}", cloud_folder_name, requested_path)
+    };
 
     let html = format!(
-        r#"
+        r##"
     <!DOCTYPE html>
-    <html>
+    <html lang="en">
     <head>
-        <title>CloudTUI - {}</title>
+        <meta name="viewport" content="width=device-width, initial-scale=1">
+        <title>{} - {}</title>
+        <meta name="csrf-token" content="{}">
         <style>
+            {}
             body {{ font-family: Arial, sans-serif; margin: 40px; }}
             .container {{ max-width: 1200px; margin: 0 auto; }}
             .header {{ text-align: center; margin-bottom: 30px; }}
+            a:focus-visible, button:focus-visible, input:focus-visible {{
+                outline: 3px solid var(--accent);
+                outline-offset: 2px;
+            }}
+            .skip-link {{
+                position: absolute;
+                left: -10000px;
+                top: 10px;
+                background: white;
+                padding: 8px 12px;
+                border-radius: 3px;
+            }}
+            .skip-link:focus {{ left: 10px; }}
+            .visually-hidden {{
+                position: absolute;
+                width: 1px;
+                height: 1px;
+                overflow: hidden;
+                clip: rect(0 0 0 0);
+                white-space: nowrap;
+            }}
             .breadcrumb {{ margin-bottom: 20px; }}
-            .breadcrumb a {{ color: #0066cc; text-decoration: none; }}
+            .breadcrumb ol {{ list-style: none; margin: 0; padding: 0; }}
+            .breadcrumb li {{ display: inline; }}
+            .breadcrumb li + li::before {{ content: " / "; color: #666; }}
+            .breadcrumb a {{ color: var(--accent); text-decoration: none; }}
             .breadcrumb a:hover {{ text-decoration: underline; }}
             .file-list {{ background: #f9f9f9; padding: 20px; border-radius: 5px; }}
-            .file-item {{ 
-                display: flex; 
-                align-items: center; 
-                padding: 8px 0; 
-                border-bottom: 1px solid #eee; 
-            }}
-            .file-item:last-child {{ border-bottom: none; }}
+            .file-table {{ width: 100%; border-collapse: collapse; }}
+            .file-table th {{ text-align: left; color: #555; font-size: 14px; padding: 8px 0; }}
+            .file-table td {{ padding: 8px 0; border-top: 1px solid #eee; }}
             .file-icon {{ margin-right: 10px; font-size: 18px; }}
-            .file-name {{ flex: 1; }}
-            .file-size {{ color: #666; margin-left: 10px; }}
-            .file-actions {{ margin-left: 10px; }}
-            .file-item a {{ color: #0066cc; text-decoration: none; }}
-            .file-item a:hover {{ text-decoration: underline; }}
+            .file-size {{ color: #666; white-space: nowrap; }}
+            .file-actions {{ text-align: right; white-space: nowrap; }}
+            .file-table a {{ color: var(--accent); text-decoration: none; }}
+            .file-table a:hover {{ text-decoration: underline; }}
             .directory {{ background: #e8f4fd; }}
             .delete-btn {{ 
                 background: #dc3545; 
@@ -295,6 +283,7 @@ async fn browse_directory_internal(
                 font-size: 12px;
             }}
             .delete-btn:hover {{ background: #c82333; }}
+            .preview-btn {{ margin-right: 8px; }}
             .upload-section {{ 
                 background: #f0f8ff; 
                 padding: 20px; 
@@ -321,42 +310,104 @@ async fn browse_directory_internal(
                 cursor: pointer; 
             }}
             .upload-section button:hover {{ background: #218838; }}
+            .message {{ margin-bottom: 20px; padding: 10px; border-radius: 5px; }}
+            .message:empty {{ display: none; }}
+            .message.success {{ background: #f0f9f0; color: #1e7e34; }}
+            .message.error {{ background: #fdf2f2; color: #c0392b; }}
+            dialog {{ border: none; border-radius: 10px; padding: 24px; max-width: 400px;
+                box-shadow: 0 15px 35px rgba(0,0,0,0.2); }}
+            dialog::backdrop {{ background: rgba(0,0,0,0.4); }}
+            dialog h2 {{ margin-top: 0; }}
+            .dialog-actions {{ display: flex; justify-content: flex-end; gap: 10px; }}
+            .dialog-actions button {{ padding: 8px 16px; border-radius: 3px; cursor: pointer;
+                border: 1px solid #ccc; background: white; }}
+            .dialog-actions .danger {{ background: #dc3545; border-color: #dc3545; color: white; }}
+            {}
         </style>
     </head>
     <body>
+        <a class="skip-link" href="#file-list">Skip to directory contents</a>
         <div class="container">
-            <div class="header">
-                <h1>🌩️ CloudTUI File Browser</h1>
-                <p>Cloud Folder: {}</p>
-            </div>
-            
-            <div class="breadcrumb">
-                <a href="/">🏠 Cloud Folders</a> / <a href="/web/{}/files">📁 Root</a>
-                /{}
-            </div>
-            
-            <div class="upload-section">
-                <h2>📤 Upload File</h2>
-                <form id="uploadForm" enctype="multipart/form-data">
-                    <input type="file" id="fileInput" name="file" required>
-                    <button type="submit">Upload</button>
-                </form>
-            </div>
-            
-            <div class="file-list">
-                <h2>📂 Directory Contents</h2>
+            <header class="header">
                 {}
-            </div>
+                <h1>{} {} File Browser</h1>
+                <p>Cloud Folder: {}</p>
+            </header>
+
+            <nav class="breadcrumb" aria-label="Breadcrumb">
+                <ol>
+                    <li><a href="/">🏠 Cloud Folders</a></li>
+                    <li><a href="/web/{}/files"{}>📁 Root</a></li>
+                    {}
+                </ol>
+            </nav>
+
+            <main>
+                <div id="message" class="message" role="status" aria-live="polite"></div>
+
+                <section class="upload-section" aria-labelledby="upload-heading">
+                    <h2 id="upload-heading">📤 Upload File</h2>
+                    <form id="uploadForm" enctype="multipart/form-data">
+                        <label for="fileInput" class="visually-hidden">File to upload</label>
+                        <input type="file" id="fileInput" name="file" required>
+                        <button type="submit">Upload</button>
+                    </form>
+                </section>
+
+                <section id="file-list" class="file-list" aria-labelledby="contents-heading" tabindex="-1">
+                    <h2 id="contents-heading">📂 Directory Contents</h2>
+                    {}
+                </section>
+            </main>
         </div>
+
+        <dialog id="confirmDialog" aria-labelledby="confirmTitle" aria-describedby="confirmText">
+            <h2 id="confirmTitle">Delete file</h2>
+            <p id="confirmText"></p>
+            <div class="dialog-actions">
+                <button type="button" id="confirmCancel">Cancel</button>
+                <button type="button" id="confirmDelete" class="danger">Delete</button>
+            </div>
+        </dialog>
         
         <script>
+            // Sent with every upload and delete, must match the CSRF cookie
+            const csrfToken = document.querySelector('meta[name="csrf-token"]').content;
+            const messageBox = document.getElementById('message');
+
+            // Lets a failure be matched with its line in the cloud log
+            function requestIdNote(response) {{
+                const id = response.headers.get('X-Request-Id');
+                return id ? ' (request ' + id + ')' : '';
+            }}
+
+            // Announced by screen readers through the live region
+            function showMessage(text, kind) {{
+                messageBox.className = 'message ' + kind;
+                messageBox.setAttribute('role', kind === 'error' ? 'alert' : 'status');
+                messageBox.textContent = text;
+            }}
+
+            // Shown after the reload that brings the listing up to date
+            function reloadWithMessage(text) {{
+                sessionStorage.setItem('fileBrowserMessage', text);
+                location.reload();
+            }}
+
+            const pending = sessionStorage.getItem('fileBrowserMessage');
+            if (pending) {{
+                sessionStorage.removeItem('fileBrowserMessage');
+                showMessage(pending, 'success');
+            }}
+
             document.getElementById('uploadForm').addEventListener('submit', async function(e) {{
                 e.preventDefault();
                 const fileInput = document.getElementById('fileInput');
                 const file = fileInput.files[0];
                 
                 if (!file) {{
-                    alert('Please select a file to upload');
+                    showMessage('Please select a file to upload', 'error');
+                    fileInput.focus();
                     return;
                 }}
                 
@@ -369,191 +420,116 @@ async fn browse_directory_internal(
                 
                 const formData = new FormData();
                 formData.append('file', file);
+                showMessage('Uploading ' + file.name + '…', 'success');
                 
                 try {{
                     const response = await fetch(uploadUrl, {{
                         method: 'POST',
+                        headers: {{ '{}': csrfToken }},
                         body: formData
                     }});
                     const result = await response.json();
                     if (response.ok) {{
-                        alert('File uploaded successfully!');
-                        location.reload();
+                        reloadWithMessage('Uploaded ' + file.name);
                     }} else {{
-                        alert('Upload failed: ' + result.error);
+                        showMessage('Upload failed: ' + result.error + requestIdNote(response), 'error');
                     }}
                 }} catch (error) {{
-                    alert('Upload failed: ' + error.message);
+                    showMessage('Upload failed: ' + error.message, 'error');
                 }}
             }});
 
-            // Delete file function
+            // Delete confirmation: the dialog keeps focus while open (Escape cancels) and
+            // gives it back to the button that opened it
+            const dialog = document.getElementById('confirmDialog');
+            let dialogOpener = null;
+            let deleteTarget = null;
+
+            function closeDialog() {{
+                dialog.close();
+            }}
+
+            dialog.addEventListener('close', () => {{
+                if (dialogOpener) dialogOpener.focus();
+                dialogOpener = null;
+            }});
+            document.getElementById('confirmCancel').addEventListener('click', closeDialog);
+            document.getElementById('confirmDelete').addEventListener('click', () => {{
+                const target = deleteTarget;
+                closeDialog();
+                deleteFile(target.cloudFolder, target.path);
+            }});
+
+            document.querySelectorAll('.delete-btn').forEach(button => {{
+                button.addEventListener('click', () => {{
+                    deleteTarget = {{ cloudFolder: button.dataset.cloudFolder, path: button.dataset.path }};
+                    dialogOpener = button;
+                    document.getElementById('confirmText').textContent =
+                        `Are you sure you want to delete "${{button.dataset.path}}"?`;
+                    dialog.showModal();
+                    document.getElementById('confirmCancel').focus();
+                }});
+            }});
+
             async function deleteFile(cloudFolder, filePath) {{
-                if (!confirm(`Are you sure you want to delete "${{filePath}}"?`)) {{
-                    return;
-                }}
-                
                 try {{
-                    const response = await fetch(`/api/delete/${{cloudFolder}}/${{filePath}}`, {{
-                        method: 'DELETE'
+                    // Names may hold anything a URL gives a meaning to, `?` and `#` included
+                    const encodedPath = filePath.split('/').map(encodeURIComponent).join('/');
+                    const response = await fetch(`/api/delete/${{encodeURIComponent(cloudFolder)}}/${{encodedPath}}`, {{
+                        method: 'DELETE',
+                        headers: {{ '{}': csrfToken }}
                     }});
                     
                     const result = await response.json();
                     
                     if (response.ok) {{
-                        alert(`File deleted successfully!\\nPlatform: ${{result.platform}}\\n${{result.trash_info}}`);
-                        location.reload();
+                        reloadWithMessage(`Deleted ${{filePath}} (${{result.platform}}). ${{result.trash_info}}`);
                     }} else {{
-                        alert('Delete failed: ' + result.error);
+                        showMessage('Delete failed: ' + result.error + requestIdNote(response), 'error');
                     }}
                 }} catch (error) {{
-                    alert('Delete failed: ' + error.message);
+                    showMessage('Delete failed: ' + error.message, 'error');
                 }}
             }}
         </script>
     </body>
     </html>
-    "#,
-        requested_path,
-        cloud.name,
-        cloud_folder_name,
-        generate_breadcrumb(&requested_path, &cloud_folder_name),
-        generate_file_list(&items, &cloud_folder_name)
+    "##,
+        escape_html(&location),
+        branding.name_html(),
+        escape_html(csrf_token),
+        branding.css_variables(),
+        // Guests can't upload or delete, the server refuses it anyway
+        if user.is_guest() {
+            ".upload-section, .delete-btn { display: none; }"
+        } else {
+            ""
+        },
+        session_controls(user, csrf_token),
+        branding.logo_html(),
+        branding.name_html(),
+        escape_html(&cloud.name),
+        encode_path(cloud_folder_name),
+        if requested_path.is_empty() {
+            r#" aria-current="page""#
+        } else {
+            ""
+        },
+        generate_breadcrumb(requested_path, cloud_folder_name),
+        generate_file_list(
+            &items,
+            cloud_folder_name,
+            &location,
+            cloud.office_preview.is_some()
+        ),
+        CSRF_HEADER,
+        CSRF_HEADER
     );
 
     Ok(Html(html))
 }
 
-pub async fn browse_file_or_directory(
-    Path((cloud_folder_name, path)): Path<(String, String)>,
-    State(server_state): State<CloudServerState>,
-    headers: HeaderMap,
-) -> Result<Html<String>, Html<String>> {
-    // Check authentication
-    let auth_state = get_authentication_state(&server_state);
-    match verify_authentication(&headers, auth_state) {
-        Ok(()) => (),
-        Err(redirect_html) => return Err(redirect_html),
-    }
-    let cloud = &server_state.cloud;
-
-    // Find the specific cloud folder
-    let base_path = cloud
-        .cloud_folders
-        .iter()
-        .find(|cf| cf.name == cloud_folder_name)
-        .ok_or_else(|| {
-            Html(
-                r#"
-<!DOCTYPE html>
-<html>
-<head>
-    <title>Not Found</title>
-</head>
-<body>
-    <h1>Cloud Folder Not Found</h1>
-    <p>Cloud folder '{}' not found.</p>
-    <a href="/">Back to Home</a>
-</body>
-</html>
-            "#
-                .replace("{}", &cloud_folder_name)
-                .to_string(),
-            )
-        })?
-        .folder_path
-        .clone();
-
-    let full_path = base_path.join(&path);
-
-    if !full_path.exists() {
-        return Err(Html(
-            r#"
-<!DOCTYPE html>
-<html>
-<head>
-    <title>Not Found</title>
-</head>
-<body>
-    <h1>Resource Not Found</h1>
-    <p>The requested resource was not found.</p>
-    <a href="/">Back to Home</a>
-</body>
-</html>
-        "#
-            .to_string(),
-        ));
-    }
-
-    if full_path.is_dir() {
-        // It's a directory, show directory listing
-        browse_directory_internal(cloud.as_ref().clone(), cloud_folder_name, path)
-            .await
-            .map_err(|_| {
-                Html(
-                    r#"
-<!DOCTYPE html>
-<html>
-<head>
-    <title>Directory Browsing Error</title>
-</head>
-<body>
-    <h1>Error</h1>
-    <p>Failed to browse directory. Please try again later.</p>
-    <a href="/">Back to Home</a>
-</body>
-</html>
-                "#
-                    .to_string(),
-                )
-            })
-    } else {
-        // It's a file, show a download link instead of serving directly
-        let file_name = full_path
-            .file_name()
-            .and_then(|name| name.to_str())
-            .unwrap_or("unknown");
-
-        let html = format!(
-            r#"
-            <!DOCTYPE html>
-            <html>
-            <head>
-                <title>File: {}</title>
-                <style>
-                    body {{ font-family: Arial, sans-serif; margin: 40px; }}
-                    .container {{ max-width: 800px; margin: 0 auto; }}
-                    .file-info {{ background: #f0f0f0; padding: 20px; border-radius: 5px; }}
-                    .download-btn {{ 
-                        display: inline-block; 
-                        background: #007bff; 
-                        color: white; 
-                        padding: 10px 20px; 
-                        text-decoration: none; 
-                        border-radius: 5px; 
-                        margin-top: 10px;
-                    }}
-                </style>
-            </head>
-            <body>
-                <div class="container">
-                    <h1>📄 File: {}</h1>
-                    <div class="file-info">
-                        <p><strong>File:</strong> {}</p>
-                        <p><strong>Cloud:</strong> {}</p>
-                        <a href="/static/{}" class="download-btn">⬇️ Download File</a>
-                    </div>
-                </div>
-            </body>
-            </html>
-            "#,
-            file_name, file_name, file_name, cloud.name, path
-        );
-
-        Ok(Html(html))
-    }
-}
-
+/// List items after "Root"; the last one is the current directory
 fn generate_breadcrumb(path: &str, cloud_folder_name: &str) -> String {
     if path.is_empty() {
         return String::new();
@@ -567,123 +543,145 @@ fn generate_breadcrumb(path: &str, cloud_folder_name: &str) -> String {
         current_path.push('/');
         current_path.push_str(part);
 
-        if i > 0 {
-            breadcrumb.push_str(" / ");
-        }
+        let current = if i == parts.len() - 1 {
+            r#" aria-current="page""#
+        } else {
+            ""
+        };
         breadcrumb.push_str(&format!(
-            "<a href=\"/web/{}/files{}\">📁 {}</a>",
-            cloud_folder_name, current_path, part
+            "<li><a href=\"/web/{}/files{}\"{}>📁 {}</a></li>",
+            encode_path(cloud_folder_name),
+            encode_path(&current_path),
+            current,
+            escape_html(part)
         ));
     }
 
     breadcrumb
 }
 
-fn generate_file_list(items: &[serde_json::Value], cloud_folder_name: &str) -> String {
+/// A table with a row per item, so screen readers can move by row and read the
+/// column headers. `office_preview` adds a preview link to office documents.
+fn generate_file_list(
+    items: &[serde_json::Value],
+    cloud_folder_name: &str,
+    location: &str,
+    office_preview: bool,
+) -> String {
     if items.is_empty() {
         return "<p>📭 This directory is empty</p>".to_string();
     }
 
-    let mut html = String::new();
+    let mut rows = String::new();
     for item in items {
         let name = item["name"].as_str().unwrap_or("");
         let is_dir = item["is_directory"].as_bool().unwrap_or(false);
         let size = item["size"].as_str().unwrap_or("");
         let path = item["path"].as_str().unwrap_or("");
 
-        let icon = if is_dir { "📁" } else { "📄" };
-        let class = if is_dir { "directory" } else { "" };
+        let (icon, kind, class) = if is_dir {
+            ("📁", "Folder", "directory")
+        } else {
+            ("📄", "File", "")
+        };
+        let size = if is_dir { "—" } else { size };
 
         let link_url = if is_dir {
-            format!("/web/{}/files/{}", cloud_folder_name, path)
+            format!(
+                "/web/{}/files/{}",
+                encode_path(cloud_folder_name),
+                encode_path(path)
+            )
+        } else {
+            static_url(cloud_folder_name, path)
+        };
+        let name_html = escape_html(name);
+
+        let preview_link = if office_preview
+            && !is_dir
+            && is_previewable(std::path::Path::new(name))
+        {
+            format!(
+                r#"<a class="preview-btn" href="/api/preview/{}/{}" title="Preview as PDF" aria-label="Preview {} as PDF"><span aria-hidden="true">👁️</span></a>"#,
+                encode_path(cloud_folder_name),
+                encode_path(path),
+                name_html
+            )
         } else {
-            format!("/api/{}/static/{}", cloud_folder_name, path)
+            String::new()
         };
 
         // Add delete button for files only
         let delete_button = if !is_dir {
             format!(
-                r#"<button class="delete-btn" onclick="deleteFile('{}', '{}')" title="Delete file">🗑️</button>"#,
-                cloud_folder_name, path
+                r#"<button type="button" class="delete-btn" data-cloud-folder="{}" data-path="{}" title="Delete file" aria-label="Delete {}"><span aria-hidden="true">🗑️</span></button>"#,
+                escape_html(cloud_folder_name),
+                escape_html(path),
+                name_html
             )
         } else {
             String::new()
         };
 
-        html.push_str(&format!(
-            r#"<div class="file-item {}">
-                <span class="file-icon">{}</span>
-                <span class="file-name"><a href="{}">{}</a></span>
-                <span class="file-size">{}</span>
-                <span class="file-actions">{}</span>
-            </div>"#,
-            class, icon, link_url, name, size, delete_button
+        rows.push_str(&format!(
+            r#"<tr class="{}">
+                <td><span class="file-icon" aria-hidden="true">{}</span><span class="visually-hidden">{}: </span><a href="{}">{}</a></td>
+                <td class="file-size">{}</td>
+                <td class="file-actions">{}{}</td>
+            </tr>"#,
+            class, icon, kind, link_url, name_html, size, preview_link, delete_button
         ));
     }
 
-    html
+    format!(
+        r#"<table class="file-table">
+            <caption class="visually-hidden">Contents of {}</caption>
+            <thead>
+                <tr><th scope="col">Name</th><th scope="col">Size</th><th scope="col"><span class="visually-hidden">Actions</span></th></tr>
+            </thead>
+            <tbody>{}</tbody>
+        </table>"#,
+        escape_html(location),
+        rows
+    )
+}
+
+/// Link to a file's contents, for downloads and the browser's own viewers
+fn static_url(cloud_folder_name: &str, path: &str) -> String {
+    format!(
+        "/api/{}/static/{}",
+        encode_path(cloud_folder_name),
+        encode_path(path)
+    )
 }
 
 pub async fn serve_static_file(
     State(server_state): State<CloudServerState>,
     Path((cloud_folder_name, path)): Path<(String, String)>,
-    headers: HeaderMap,
-) -> Result<Response<axum::body::Body>, Json<serde_json::Value>> {
-    // Check authentication for web routes (API routes can skip this)
-    let auth_state = get_authentication_state(&server_state);
-    if !has_valid_token(&headers, auth_state) {
-        return Err(Json(json!({
-            "error": "Unauthorized",
-            "message": "Authentication required"
-        })));
+    request_headers: HeaderMap,
+) -> Result<Response<axum::body::Body>, ApiError> {
+    let ResolvedPath {
+        full_path,
+        metadata,
+        ..
+    } = resolve_path(&server_state, &cloud_folder_name, &path).await?;
+    if metadata.is_dir() {
+        return Err(ApiError::not_found("The requested file was not found"));
     }
 
-    let cloud = &server_state.cloud;
+    let mime_type = detect_mime(full_path.clone()).await;
+    let cache_control = http_cache::cache_control(mime_type, false);
 
-    // Find the specific cloud folder
-    let base_path = cloud
-        .cloud_folders
-        .iter()
-        .find(|f| f.name == cloud_folder_name)
-        .ok_or_else(|| {
-            Json(json!({
-                "error": "Not Found",
-                "message": "Cloud folder not found."
-            }))
-        })?
-        .folder_path
-        .clone();
-
-    let full_path = base_path.join(&path);
-
-    // Security check: ensure the requested path is within the cloud folder directory
-    if !full_path.starts_with(&base_path) {
-        return Err(Json(json!({
-            "error": "Forbidden",
-            "message": "You don't have permission to access this file."
-        })));
-    }
-
-    if !full_path.exists() || full_path.is_dir() {
-        return Err(Json(json!({
-            "error": "Not Found",
-            "message": "The requested file was not found."
-        })));
+    // Answer 304 if the client's copy is still current
+    let validators = Validators::new(&metadata);
+    if validators.is_fresh(&request_headers) {
+        return Ok(validators.not_modified(cache_control));
     }
 
     // Read file content
-    let file_content = match fs::read(&full_path) {
-        Ok(content) => content,
-        Err(_) => {
-            return Err(Json(json!({
-                "error": "Error",
-                "message": "Failed to read the requested file."
-            })));
-        }
-    };
-
-    // Get file extension to determine MIME type
-    let mime_type = get_mime_type(&full_path);
+    let file_content = fs::read(&full_path)
+        .await
+        .map_err(|_| ApiError::new(ErrorCode::FileSystem, "Failed to read the requested file"))?;
 
     // Create response with proper headers
     let mut response = Response::new(axum::body::Body::from(file_content));
@@ -699,87 +697,10 @@ pub async fn serve_static_file(
     if let Some(file_name) = full_path.file_name().and_then(|n| n.to_str()) {
         headers.insert(
             header::CONTENT_DISPOSITION,
-            header::HeaderValue::from_str(&format!("inline; filename=\"{}\"", file_name))
-                .unwrap_or_else(|_| header::HeaderValue::from_static("inline")),
+            content_disposition("inline", file_name),
         );
     }
+    validators.apply(headers, cache_control);
 
     Ok(response)
 }
-
-fn get_mime_type(path: &std::path::Path) -> &'static str {
-    if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
-        match extension.to_lowercase().as_str() {
-            // Images
-            "jpg" | "jpeg" => "image/jpeg",
-            "png" => "image/png",
-            "gif" => "image/gif",
-            "webp" => "image/webp",
-            "svg" => "image/svg+xml",
-            "bmp" => "image/bmp",
-            "ico" => "image/x-icon",
-
-            // Text files
-            "txt" => "text/plain",
-            "html" | "htm" => "text/html",
-            "css" => "text/css",
-            "js" => "application/javascript",
-            "json" => "application/json",
-            "xml" => "application/xml",
-            "csv" => "text/csv",
-            "md" | "markdown" => "text/markdown",
-
-            // Documents
-            "pdf" => "application/pdf",
-            "doc" => "application/msword",
-            "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
-            "xls" => "application/vnd.ms-excel",
-            "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
-            "ppt" => "application/vnd.ms-powerpoint",
-            "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
-
-            // Archives
-            "zip" => "application/zip",
-            "rar" => "application/x-rar-compressed",
-            "7z" => "application/x-7z-compressed",
-            "tar" => "application/x-tar",
-            "gz" => "application/gzip",
-
-            // Audio
-            "mp3" => "audio/mpeg",
-            "wav" => "audio/wav",
-            "ogg" => "audio/ogg",
-            "flac" => "audio/flac",
-
-            // Video
-            "mp4" => "video/mp4",
-            "avi" => "video/x-msvideo",
-            "mov" => "video/quicktime",
-            "wmv" => "video/x-ms-wmv",
-            "webm" => "video/webm",
-
-            // Code files
-            "rs" => "text/plain",
-            "py" => "text/plain",
-            "java" => "text/plain",
-            "cpp" | "cc" | "cxx" => "text/plain",
-            "c" => "text/plain",
-            "h" => "text/plain",
-            "hpp" => "text/plain",
-            "cs" => "text/plain",
-            "php" => "text/plain",
-            "rb" => "text/plain",
-            "go" => "text/plain",
-            "swift" => "text/plain",
-            "kt" => "text/plain",
-            "scala" => "text/plain",
-            "sh" => "text/plain",
-            "bat" => "text/plain",
-            "ps1" => "text/plain",
-
-            _ => "application/octet-stream",
-        }
-    } else {
-        "application/octet-stream"
-    }
-}
@@ -0,0 +1,461 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Json, Response},
+};
+use axum_extra::extract::Multipart;
+use serde_json::json;
+use std::path::{Path as StdPath, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+use crate::api_routes::upload::{move_to_free_name, prepare_target_directory};
+use crate::cloud::{CloudFolder, CloudServerState};
+use crate::disk_space::{self, DiskSpaceConfig};
+use crate::email::NotifyEvent;
+use crate::error::{ApiError, ErrorCode};
+use crate::file_requests::FileRequest;
+use crate::hooks::{self, HookFile};
+use crate::html::escape_html;
+use crate::utils::{format_bytes, sanitize_path, upload_temp_path, validate_path_component};
+use crate::webhooks::WebhookEvent;
+
+/// Errors tell the uploader what went wrong with their files and nothing about the
+/// folder behind the link, along with the files stored before it
+fn drop_error(code: ErrorCode, message: &str, stored: &[String]) -> ApiError {
+    ApiError::new(code, message).with("stored", stored)
+}
+
+/// `GET /drop/:id`: the upload page of a file request
+pub async fn file_request_page(
+    State(server_state): State<CloudServerState>,
+    Path(id): Path<String>,
+) -> Response {
+    let branding = &server_state.branding;
+    let Some(request) = server_state.file_requests.get(&id) else {
+        return branding.error_page(StatusCode::NOT_FOUND, "This link doesn't exist");
+    };
+    if let Err(closed) = request.check_open() {
+        return branding.error_page(StatusCode::GONE, closed.message());
+    }
+    if let Some(notifier) = &server_state.notifier {
+        notifier.record_share_access(&request.id, &request.name);
+    }
+
+    let mut limits = Vec::new();
+    if let Some(max_file_size) = request.max_file_size {
+        limits.push(format!("Files up to {}", format_bytes(max_file_size)));
+    }
+    if let Some(remaining) = request.remaining() {
+        limits.push(format!(
+            "{} more file{}",
+            remaining,
+            if remaining == 1 { "" } else { "s" }
+        ));
+    }
+    if let Some(expires_at) = request.expires_at {
+        limits.push(format!(
+            "Open until {}",
+            expires_at.format("%Y-%m-%d %H:%M UTC")
+        ));
+    }
+    let limits = if limits.is_empty() {
+        String::new()
+    } else {
+        format!(r#"<p class="limits">{}</p>"#, limits.join(" · "))
+    };
+
+    let html = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <title>{title} - {name}</title>
+    <style>
+        {css}
+        body {
+            font-family: Arial, sans-serif;
+            margin: 0;
+            padding: 20px;
+            background: var(--accent-background);
+            min-height: 100vh;
+            box-sizing: border-box;
+            display: flex;
+            align-items: center;
+            justify-content: center;
+        }
+        .drop-container {
+            background: white;
+            padding: 40px;
+            border-radius: 10px;
+            box-shadow: 0 15px 35px rgba(0,0,0,0.1);
+            width: 100%;
+            max-width: 500px;
+        }
+        h1 { color: #333; margin: 0 0 10px 0; font-size: 24px; }
+        .from { color: #666; margin: 0 0 20px 0; }
+        .limits { color: #666; font-size: 14px; }
+        input[type="file"] { width: 100%; margin: 10px 0 20px 0; }
+        button {
+            width: 100%;
+            padding: 12px;
+            background: var(--accent-background);
+            color: white;
+            border: none;
+            border-radius: 5px;
+            font-size: 16px;
+            cursor: pointer;
+        }
+        button:disabled { opacity: 0.6; cursor: default; }
+        button:focus-visible, input:focus-visible { outline: 3px solid var(--accent); outline-offset: 2px; }
+        #results { list-style: none; padding: 0; margin: 20px 0 0 0; }
+        #results li { padding: 6px 0; border-bottom: 1px solid #eee; }
+        .error { color: #c0392b; }
+        .success { color: #27ae60; }
+    </style>
+</head>
+<body>
+    <main class="drop-container">
+        <h1>{title}</h1>
+        <p class="from">{logo} Send files to {name}. You won't see what others have sent.</p>
+        {limits}
+        <form id="dropForm" method="post" enctype="multipart/form-data">
+            <label for="files">Files to send</label>
+            <input type="file" id="files" name="files" multiple required>
+            <button type="submit" id="send">Send</button>
+        </form>
+        <ul id="results" aria-live="polite"></ul>
+    </main>
+
+    <script>
+        const form = document.getElementById('dropForm');
+        const results = document.getElementById('results');
+        const send = document.getElementById('send');
+
+        function report(text, ok) {
+            const item = document.createElement('li');
+            item.className = ok ? 'success' : 'error';
+            item.textContent = text;
+            results.appendChild(item);
+        }
+
+        form.addEventListener('submit', async function(e) {
+            e.preventDefault();
+            const files = Array.from(document.getElementById('files').files);
+            send.disabled = true;
+            // One request per file, so one that's too large doesn't fail the others
+            for (const file of files) {
+                const body = new FormData();
+                body.append('file', file, file.name);
+                try {
+                    const response = await fetch(window.location.pathname, { method: 'POST', body });
+                    const data = await response.json();
+                    if (response.ok) {
+                        report('Sent ' + file.name, true);
+                    } else {
+                        report(file.name + ': ' + data.error, false);
+                        if (response.status === 410) break;
+                    }
+                } catch (error) {
+                    report(file.name + ': upload failed', false);
+                }
+            }
+            form.reset();
+            send.disabled = false;
+        });
+    </script>
+</body>
+</html>
+"#;
+    Html(
+        html.replace("{css}", &branding.css_variables())
+            .replace("{logo}", &branding.logo_html())
+            .replace("{name}", &branding.name_html())
+            .replace("{limits}", &limits)
+            .replace("{title}", &escape_html(&request.name)),
+    )
+    .into_response()
+}
+
+/// `POST /drop/:id`: anonymous multipart upload into the request's directory. Files are
+/// always stored under a free name, never replacing anything.
+pub async fn file_request_upload(
+    State(server_state): State<CloudServerState>,
+    Path(id): Path<String>,
+    mut multipart: Multipart,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let request = match server_state.file_requests.get(&id) {
+        Some(request) => request,
+        None => {
+            return Err(drop_error(
+                ErrorCode::NotFound,
+                "This link doesn't exist",
+                &[],
+            ))
+        }
+    };
+    if let Err(closed) = request.check_open() {
+        return Err(drop_error(ErrorCode::Gone, closed.message(), &[]));
+    }
+    let (cloud_folder, directory) = target_directory(&server_state, &request).await?;
+
+    let mut stored = Vec::new();
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(_) => {
+                return Err(drop_error(
+                    ErrorCode::Validation,
+                    "The upload was interrupted",
+                    &stored,
+                ))
+            }
+        };
+        // Form fields besides the files
+        let Some(filename) = field.file_name().map(str::to_string) else {
+            continue;
+        };
+        if validate_path_component(&filename).is_err() || filename.starts_with('.') {
+            return Err(drop_error(
+                ErrorCode::Validation,
+                &format!("'{}' is not an allowed file name", filename),
+                &stored,
+            ));
+        }
+
+        let request = match server_state.file_requests.reserve(&id) {
+            Some(Ok(request)) => request,
+            Some(Err(closed)) => {
+                return Err(drop_error(ErrorCode::Gone, closed.message(), &stored))
+            }
+            None => {
+                return Err(drop_error(
+                    ErrorCode::NotFound,
+                    "This link doesn't exist",
+                    &stored,
+                ))
+            }
+        };
+        if disk_space::check_upload(&server_state.cloud.disk_space, &cloud_folder, None).is_err() {
+            server_state.file_requests.release(&id);
+            return Err(drop_error(
+                ErrorCode::InsufficientStorage,
+                "There is no space left for uploads",
+                &stored,
+            ));
+        }
+        let requested_path = format!(
+            "{}/{}",
+            request.cloud_folder,
+            StdPath::new(&request.path).join(&filename).display()
+        );
+        let received = receive_file(
+            field,
+            &directory,
+            &filename,
+            request.max_file_size,
+            &server_state.cloud.disk_space,
+        )
+        .await;
+        let (temp_path, bytes) = match received {
+            Ok(received) => received,
+            Err((code, message)) => {
+                server_state.file_requests.release(&id);
+                return Err(drop_error(code, &message, &stored));
+            }
+        };
+        let hook_file = HookFile {
+            path: &temp_path,
+            display_path: &requested_path,
+            size: Some(bytes),
+            user: "anonymous",
+            source: "file_request",
+        };
+        if let Err(e) = hooks::pre_upload(&server_state.cloud.name, &cloud_folder, &hook_file).await
+        {
+            // The reason is for the owner, not for whoever has the link
+            tracing::warn!("File request '{}': {}", request.name, e);
+            let _ = fs::remove_file(&temp_path).await;
+            server_state.file_requests.release(&id);
+            return Err(drop_error(
+                ErrorCode::Forbidden,
+                &format!("'{}' was refused", filename),
+                &stored,
+            ));
+        }
+
+        match store_received(&directory, &temp_path, &filename).await {
+            Ok(name) => {
+                let stored_path = format!(
+                    "{}/{}",
+                    request.cloud_folder,
+                    StdPath::new(&request.path).join(&name).display()
+                );
+                tracing::info!(
+                    bytes,
+                    "File request '{}' received '{}'",
+                    request.name,
+                    stored_path
+                );
+                server_state.webhooks.file_event(
+                    WebhookEvent::FileUploaded,
+                    &stored_path,
+                    Some(bytes),
+                    "file_request",
+                );
+                hooks::post_upload(
+                    &server_state.cloud.name,
+                    &cloud_folder,
+                    &HookFile {
+                        path: &directory.join(&name),
+                        display_path: &stored_path,
+                        size: Some(bytes),
+                        user: "anonymous",
+                        source: "file_request",
+                    },
+                );
+                stored.push(name);
+                if let (Some(notifier), Some(max_files)) =
+                    (&server_state.notifier, request.max_files)
+                {
+                    if request.uploads + 1 == max_files {
+                        notifier.notify(
+                            NotifyEvent::QuotaExceeded,
+                            format!(
+                                "The file request '{}' received the last of its {} files.",
+                                request.name, max_files
+                            ),
+                        );
+                    }
+                }
+            }
+            Err((code, message)) => {
+                server_state.file_requests.release(&id);
+                return Err(drop_error(code, &message, &stored));
+            }
+        }
+    }
+
+    if stored.is_empty() {
+        return Err(drop_error(
+            ErrorCode::Validation,
+            "The upload contains no file",
+            &stored,
+        ));
+    }
+    Ok(Json(json!({ "stored": stored })))
+}
+
+/// The request's cloud folder and directory, checked to be inside the folder
+async fn target_directory(
+    server_state: &CloudServerState,
+    request: &FileRequest,
+) -> Result<(CloudFolder, std::path::PathBuf), ApiError> {
+    let unavailable = || {
+        drop_error(
+            ErrorCode::Unavailable,
+            "This link can't take files right now",
+            &[],
+        )
+    };
+    let Some(cloud_folder) = server_state.cloud.get_cloud_folder(&request.cloud_folder) else {
+        tracing::warn!(
+            "File request '{}' points to missing cloud folder '{}'",
+            request.name,
+            request.cloud_folder
+        );
+        return Err(unavailable());
+    };
+    if let Some(problem) = server_state.folder_health.problem(&cloud_folder.name) {
+        tracing::warn!(
+            "File request '{}' can't take files: {}",
+            request.name,
+            problem
+        );
+        return Err(unavailable());
+    }
+    let directory = sanitize_path(&cloud_folder.folder_path, &request.path).map_err(|e| {
+        tracing::warn!("File request '{}' has an invalid path: {}", request.name, e);
+        unavailable()
+    })?;
+    let display_path = format!("{}/{}", request.cloud_folder, request.path);
+    // Recreated if it was removed since the link was handed out
+    if let Err(error) =
+        prepare_target_directory(&cloud_folder.folder_path, &directory, &display_path, true).await
+    {
+        tracing::warn!("File request '{}': {}", request.name, error.message);
+        return Err(unavailable());
+    }
+    Ok((cloud_folder.clone(), directory))
+}
+
+/// Stream one file to a temporary file next to its destination, stopping once it gets
+/// larger than the link's or the cloud's limit. Returns the temporary file and the size.
+async fn receive_file(
+    mut field: axum_extra::extract::multipart::Field,
+    directory: &StdPath,
+    filename: &str,
+    max_file_size: Option<u64>,
+    config: &DiskSpaceConfig,
+) -> Result<(PathBuf, u64), (ErrorCode, String)> {
+    let temp_path = upload_temp_path(directory);
+    let failed = |e: std::io::Error| {
+        tracing::warn!("Failed to store a file request upload: {}", e);
+        (
+            ErrorCode::FileSystem,
+            format!("'{}' couldn't be stored", filename),
+        )
+    };
+    let mut file = fs::File::create_new(&temp_path).await.map_err(failed)?;
+
+    let mut bytes = 0u64;
+    let result = async {
+        while let Some(chunk) = field.chunk().await.map_err(|_| {
+            (
+                ErrorCode::Validation,
+                format!("'{}' was interrupted", filename),
+            )
+        })? {
+            bytes += chunk.len() as u64;
+            if let Some(max) = max_file_size.filter(|max| bytes > *max) {
+                return Err((
+                    ErrorCode::PayloadTooLarge,
+                    format!("'{}' is larger than {}", filename, format_bytes(max)),
+                ));
+            }
+            disk_space::check_upload_size(config, bytes)
+                .map_err(|e| (ErrorCode::PayloadTooLarge, format!("'{}': {}", filename, e)))?;
+            file.write_all(&chunk).await.map_err(failed)?;
+        }
+        file.flush().await.map_err(failed)
+    }
+    .await;
+    drop(file);
+
+    match result {
+        Ok(()) => Ok((temp_path, bytes)),
+        Err(e) => {
+            let _ = fs::remove_file(&temp_path).await;
+            Err(e)
+        }
+    }
+}
+
+/// Move a received file to a free name. Returns the stored name.
+async fn store_received(
+    directory: &StdPath,
+    temp_path: &StdPath,
+    filename: &str,
+) -> Result<String, (ErrorCode, String)> {
+    match move_to_free_name(directory, temp_path, filename).await {
+        Ok(name) => Ok(name),
+        Err(error) => {
+            tracing::warn!("Failed to store a file request upload: {}", error.message);
+            let _ = fs::remove_file(temp_path).await;
+            Err((
+                ErrorCode::FileSystem,
+                format!("'{}' couldn't be stored", filename),
+            ))
+        }
+    }
+}
@@ -1,81 +1,39 @@
-use axum::{extract::State, http::HeaderMap, response::Html};
-use std::sync::Arc;
+use axum::{extract::State, response::Html};
 
-use crate::{auth::AuthState, cloud::CloudServerState};
+use crate::auth::AuthenticatedUser;
+use crate::cloud::CloudServerState;
+use crate::csrf::CsrfToken;
+use crate::disk_space::{self, SpaceLevel};
+use crate::html::{encode_path, escape_html};
+use crate::web_routes::login::session_controls;
 
-// Helper function to get authentication state from server state
-fn get_authentication_state(server_state: &CloudServerState) -> &Arc<AuthState> {
-    &server_state.auth_state
-}
-
-// Checks if request has valid authentication token
-fn has_valid_token(headers: &HeaderMap, auth_state: &AuthState) -> bool {
-    // Check Authorization header
-    if let Some(auth_header) = headers.get("Authorization") {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if let Some(token) = auth_str.strip_prefix("Bearer ") {
-                if auth_state.verify_token(token).is_ok() {
-                    return true;
-                }
-            }
-        }
-    }
-
-    // Check cookies
-    if let Some(cookie_header) = headers.get("Cookie") {
-        if let Ok(cookie_str) = cookie_header.to_str() {
-            for cookie in cookie_str.split(';') {
-                let cookie = cookie.trim();
-                if cookie.starts_with("auth_token_") {
-                    if let Some(token) = cookie.split('=').nth(1) {
-                        if auth_state.verify_token(token).is_ok() {
-                            return true;
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    false
-}
-
-// Verifies authentication and returns HTML redirect if unauthorized
-fn verify_authentication(headers: &HeaderMap, auth_state: &AuthState) -> Result<(), Html<String>> {
-    if has_valid_token(headers, auth_state) {
-        return Ok(());
+fn format_free(bytes: u64) -> String {
+    const GB: u64 = 1024 * 1024 * 1024;
+    if bytes >= GB {
+        format!("{:.1} GB", bytes as f64 / GB as f64)
+    } else {
+        format!("{} MB", bytes / (1024 * 1024))
     }
-
-    // Return HTML redirect to login page
-    let redirect_html = r#"
-<!DOCTYPE html>
-<html>
-<head>
-    <meta http-equiv="refresh" content="0; url=/login">
-    <title>Redirecting to Login</title>
-</head>
-<body>
-    <p>Redirecting to login page...</p>
-    <script>window.location.href = '/login';</script>
-</body>
-</html>
-    "#;
-
-    Err(Html(redirect_html.to_string()))
 }
 
 pub async fn index(
     State(server_state): State<CloudServerState>,
-    headers: HeaderMap,
-) -> Result<Html<String>, Html<String>> {
-    // Check authentication
-    let auth_state = get_authentication_state(&server_state);
-    match verify_authentication(&headers, auth_state) {
-        Ok(()) => (),
-        Err(redirect_html) => return Err(redirect_html),
-    }
-
+    CsrfToken(csrf_token): CsrfToken,
+    user: AuthenticatedUser,
+) -> Html<String> {
     let cloud = &server_state.cloud;
+    let branding = &server_state.branding;
+    let space = disk_space::folder_space(&cloud.disk_space, &cloud.cloud_folders);
+
+    let space_warning = match space.iter().map(|folder| folder.level).max() {
+        Some(SpaceLevel::Critical) => {
+            r#"<div class="space-warning critical" role="alert">⚠️ The disk is full: uploads are refused until space is freed.</div>"#
+        }
+        Some(SpaceLevel::Low) => {
+            r#"<div class="space-warning" role="status">⚠️ Disk space is running low.</div>"#
+        }
+        _ => "",
+    };
 
     let cloud_folders_html = if cloud.cloud_folders.is_empty() {
         "<p>No cloud folders configured for this cloud.</p>".to_string()
@@ -84,12 +42,28 @@ pub async fn index(
             .cloud_folders
             .iter()
             .map(|folder| {
+                let free = space
+                    .iter()
+                    .find(|space| space.folder == folder.name)
+                    .map(|space| {
+                        format!(
+                            r#"<div class="cloud-folder-space{}">{} free</div>"#,
+                            if space.level == SpaceLevel::Ok { "" } else { " low" },
+                            format_free(space.free_bytes)
+                        )
+                    })
+                    .unwrap_or_default();
+                let name = escape_html(&folder.name);
                 format!(
                     r#"<div class="cloud-folder-item">
                         <div class="cloud-folder-name">📁 {}</div>
-                        <a href="/web/{}/files" class="browse-btn">Browse Files</a>
+                        {}
+                        <a href="/web/{}/files" class="browse-btn" aria-label="Browse files in {}">Browse Files</a>
                     </div>"#,
-                    folder.name, folder.name
+                    name,
+                    free,
+                    encode_path(&folder.name),
+                    name
                 )
             })
             .collect::<Vec<_>>()
@@ -99,10 +73,11 @@ pub async fn index(
     let html = format!(
         r#"
     <!DOCTYPE html>
-    <html>
+    <html lang="en">
     <head>
-        <title>CloudTUI Server</title>
+        <title>{} Server</title>
         <style>
+            {}
             body {{ font-family: Arial, sans-serif; margin: 40px; }}
             .container {{ max-width: 800px; margin: 0 auto; }}
             .header {{ text-align: center; margin-bottom: 30px; }}
@@ -113,18 +88,24 @@ pub async fn index(
                 padding: 15px; 
                 margin: 10px 0; 
                 border-radius: 5px; 
-                border-left: 4px solid #007bff;
+                border-left: 4px solid var(--accent);
             }}
-            .cloud-folder-item a {{ color: #007bff; text-decoration: none; }}
+            .cloud-folder-item a {{ color: var(--accent); text-decoration: none; }}
             .cloud-folder-item a:hover {{ text-decoration: underline; }}
+            .cloud-folder-space {{ color: #666; font-size: 0.9em; margin: 4px 0; }}
+            .cloud-folder-space.low {{ color: #b45309; font-weight: bold; }}
+            .space-warning {{ background: #fef3c7; color: #92400e; padding: 12px 20px; border-radius: 5px; margin-bottom: 20px; }}
+            .space-warning.critical {{ background: #fee2e2; color: #991b1b; }}
         </style>
     </head>
     <body>
-        <div class="container">
+        <main class="container">
             <div class="header">
-                <h1>🌩️ CloudTUI Server</h1>
+                {}
+                <h1>{} {} Server</h1>
                 <p>Your personal cloud storage server</p>
             </div>
+            {}
             <div class="status">
                 <h2>Server Status</h2>
                 <p>✅ Server is running</p>
@@ -134,12 +115,20 @@ pub async fn index(
                 <h2>📂 Cloud Folders</h2>
                 {}
             </div>
-        </div>
+            <p><a href="/web/photos">📷 Photo timeline</a></p>
+        </main>
     </body>
     </html>
     "#,
-        cloud.name, cloud_folders_html
+        branding.name_html(),
+        branding.css_variables(),
+        session_controls(&user, &csrf_token),
+        branding.logo_html(),
+        branding.name_html(),
+        space_warning,
+        escape_html(&cloud.name),
+        cloud_folders_html
     );
 
-    Ok(Html(html))
+    Html(html)
 }
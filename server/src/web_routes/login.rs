@@ -1,18 +1,18 @@
-use axum::response::Html;
+use axum::{extract::State, response::Html};
 
-// Login page
-pub async fn login_page() -> Html<String> {
-    let html = r#"
-<!DOCTYPE html>
-<html>
-<head>
-    <title>CloudTUI Login</title>
-    <style>
+use crate::auth::AuthenticatedUser;
+use crate::cloud::CloudServerState;
+use crate::csrf::CSRF_HEADER;
+use crate::html::js_string;
+
+/// Shared by the login and recovery pages
+const LOGIN_STYLE: &str = r#"
+        {css}
         body { 
             font-family: Arial, sans-serif; 
             margin: 0; 
             padding: 0; 
-            background: linear-gradient(135deg, #667eea 0%, #764ba2 100%);
+            background: var(--accent-background);
             height: 100vh;
             display: flex;
             align-items: center;
@@ -58,12 +58,12 @@ pub async fn login_page() -> Html<String> {
         }
         .form-group input:focus {
             outline: none;
-            border-color: #667eea;
+            border-color: var(--accent);
         }
         .login-button {
             width: 100%;
             padding: 12px;
-            background: linear-gradient(135deg, #667eea 0%, #764ba2 100%);
+            background: var(--accent-background);
             color: white;
             border: none;
             border-radius: 5px;
@@ -92,64 +92,250 @@ pub async fn login_page() -> Html<String> {
             border-radius: 5px;
             display: none;
         }
+        .login-button:disabled {
+            opacity: 0.6;
+            cursor: not-allowed;
+            transform: none;
+        }
+        .field-error {
+            color: #c0392b;
+            margin-top: 6px;
+            font-size: 14px;
+            display: none;
+        }
+        .form-group input[aria-invalid="true"] {
+            border-color: #e74c3c;
+        }
+        .remember {
+            display: flex;
+            align-items: center;
+            gap: 8px;
+            margin-bottom: 20px;
+            color: #333;
+        }
+        .recover-link {
+            display: block;
+            text-align: center;
+            margin-top: 20px;
+            color: var(--accent);
+        }
+"#;
+
+/// Login page: the cloud's name and logo, "remember me", and errors shown next to the
+/// password field, with a countdown while the address is locked out
+pub async fn login_page(State(server_state): State<CloudServerState>) -> Html<String> {
+    let branding = &server_state.branding;
+    let sessions = &server_state.auth_state.sessions;
+    let remember = if sessions.remember_enabled() {
+        let days = sessions.remember_days;
+        format!(
+            r#"<div class="remember">
+                <input type="checkbox" id="remember" name="remember">
+                <label for="remember">Remember me for {} {}</label>
+            </div>"#,
+            days,
+            if days == 1 { "day" } else { "days" }
+        )
+    } else {
+        String::new()
+    };
+    let html = r#"
+<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <title>{name} Login</title>
+    <style>
+        {style}
     </style>
 </head>
 <body>
-    <div class="login-container">
+    <main class="login-container">
         <div class="login-header">
-            <h1>🌩️ CloudTUI</h1>
+            <h1>{logo} {name}</h1>
             <p>Enter your password to access your cloud storage</p>
         </div>
-        <form id="loginForm">
+        <form id="loginForm" novalidate>
             <div class="form-group">
                 <label for="password">Password:</label>
-                <input type="password" id="password" name="password" required>
+                <input type="password" id="password" name="password" autocomplete="current-password" aria-describedby="passwordError" autofocus required>
+                <div id="passwordError" class="field-error" role="alert"></div>
+            </div>
+            {remember}
+            <button type="submit" id="loginButton" class="login-button">Login</button>
+        </form>
+        <a class="recover-link" href="/login/recover">Forgot password?</a>
+        <div id="successMessage" class="success-message" role="status"></div>
+    </main>
+
+    <script>
+        const form = document.getElementById('loginForm');
+        const passwordInput = document.getElementById('password');
+        const errorDiv = document.getElementById('passwordError');
+        const successDiv = document.getElementById('successMessage');
+        const button = document.getElementById('loginButton');
+        let countdown = null;
+
+        function showError(message) {
+            errorDiv.textContent = message;
+            errorDiv.style.display = message ? 'block' : 'none';
+            passwordInput.setAttribute('aria-invalid', message ? 'true' : 'false');
+        }
+
+        // Keep the form disabled until the lockout is over
+        function lockOut(seconds) {
+            clearInterval(countdown);
+            button.disabled = true;
+            const tick = () => {
+                if (seconds <= 0) {
+                    clearInterval(countdown);
+                    button.disabled = false;
+                    showError('');
+                    passwordInput.focus();
+                    return;
+                }
+                showError(`Too many failed logins. Try again in ${seconds} s.`);
+                seconds -= 1;
+            };
+            tick();
+            countdown = setInterval(tick, 1000);
+        }
+
+        passwordInput.addEventListener('input', () => {
+            if (!button.disabled) showError('');
+        });
+
+        form.addEventListener('submit', async function(e) {
+            e.preventDefault();
+            if (button.disabled) return;
+            if (!passwordInput.value) {
+                showError('Enter the password');
+                return;
+            }
+            showError('');
+            successDiv.style.display = 'none';
+            button.disabled = true;
+
+            try {
+                const response = await fetch('/api/login', {
+                    method: 'POST',
+                    headers: { 'Content-Type': 'application/json' },
+                    body: JSON.stringify({
+                        password: passwordInput.value,
+                        remember: document.getElementById('remember')?.checked === true
+                    })
+                });
+                const data = await response.json();
+
+                if (response.ok) {
+                    successDiv.textContent = 'Login successful! Redirecting...';
+                    successDiv.style.display = 'block';
+                    window.location.href = '/';
+                    return;
+                }
+                button.disabled = false;
+                passwordInput.select();
+                if (response.status === 429) {
+                    lockOut(data.retry_after);
+                } else if (data.attempts_left !== undefined && data.attempts_left <= 2) {
+                    const tries = data.attempts_left === 1 ? 'try' : 'tries';
+                    showError(`Wrong password. ${data.attempts_left} more ${tries} before a short lockout.`);
+                } else {
+                    showError(data.error || 'Login failed. Please try again.');
+                }
+            } catch (error) {
+                button.disabled = false;
+                showError('Login failed. Please try again.');
+            }
+        });
+    </script>
+</body>
+</html>
+    "#;
+    Html(
+        html.replace("{style}", LOGIN_STYLE)
+            .replace("{remember}", &remember)
+            .replace("{css}", &branding.css_variables())
+            .replace("{logo}", &branding.logo_html())
+            .replace("{name}", &branding.name_html()),
+    )
+}
+
+/// Page for setting a new password with a recovery code issued in the TUI
+pub async fn recover_page(State(server_state): State<CloudServerState>) -> Html<String> {
+    let branding = &server_state.branding;
+    let html = r#"
+<!DOCTYPE html>
+<html lang="en">
+<head>
+    <title>{name} Password Recovery</title>
+    <style>
+        {style}
+    </style>
+</head>
+<body>
+    <main class="login-container">
+        <div class="login-header">
+            <h1>{logo} {name}</h1>
+            <p>In the CloudHost TUI, select this cloud in the Clouds tab and press <kbd>R</kbd> for a recovery code</p>
+        </div>
+        <form id="recoverForm">
+            <div class="form-group">
+                <label for="code">Recovery code:</label>
+                <input type="text" id="code" name="code" autocomplete="one-time-code" autocapitalize="characters" spellcheck="false" autofocus required>
+            </div>
+            <div class="form-group">
+                <label for="password">New password:</label>
+                <input type="password" id="password" name="password" autocomplete="new-password" minlength="8" required>
+            </div>
+            <div class="form-group">
+                <label for="confirm">Confirm new password:</label>
+                <input type="password" id="confirm" name="confirm" autocomplete="new-password" minlength="8" required>
             </div>
-            <button type="submit" class="login-button">Login</button>
+            <button type="submit" class="login-button">Set password</button>
         </form>
-        <div id="errorMessage" class="error-message"></div>
-        <div id="successMessage" class="success-message"></div>
-    </div>
+        <a class="recover-link" href="/login">Back to login</a>
+        <div id="errorMessage" class="error-message" role="alert"></div>
+        <div id="successMessage" class="success-message" role="status"></div>
+    </main>
 
     <script>
-        document.getElementById('loginForm').addEventListener('submit', async function(e) {
+        document.getElementById('recoverForm').addEventListener('submit', async function(e) {
             e.preventDefault();
-            
+
+            const code = document.getElementById('code').value;
             const password = document.getElementById('password').value;
             const errorDiv = document.getElementById('errorMessage');
             const successDiv = document.getElementById('successMessage');
-            
-            // Hide previous messages
+
             errorDiv.style.display = 'none';
             successDiv.style.display = 'none';
-            
+
+            if (password !== document.getElementById('confirm').value) {
+                errorDiv.textContent = 'Passwords do not match';
+                errorDiv.style.display = 'block';
+                return;
+            }
+
             try {
-                const response = await fetch('/api/login', {
+                const response = await fetch('/api/login/recover', {
                     method: 'POST',
-                    headers: {
-                        'Content-Type': 'application/json',
-                    },
-                    body: JSON.stringify({ password: password })
+                    headers: { 'Content-Type': 'application/json' },
+                    body: JSON.stringify({ code: code, password: password })
                 });
-                
+                const data = await response.json();
                 if (response.ok) {
-                    const data = await response.json();
-                    // Store token in cookie
-                    document.cookie = `auth_token_${window.location.port}=${data.token}; path=/; max-age=86400`; // 24 hours
-                    successDiv.textContent = 'Login successful! Redirecting...';
+                    successDiv.textContent = 'Password changed! Redirecting...';
                     successDiv.style.display = 'block';
-                    
-                    // Redirect to home page
                     setTimeout(() => {
                         window.location.href = '/';
                     }, 1000);
                 } else {
-                    const error = await response.message.text();
-                    errorDiv.textContent = error;
+                    errorDiv.textContent = data.error;
                     errorDiv.style.display = 'block';
                 }
             } catch (error) {
-                errorDiv.textContent = 'Login failed. Please try again.';
+                errorDiv.textContent = 'Setting the password failed. Please try again.';
                 errorDiv.style.display = 'block';
             }
         });
@@ -157,5 +343,53 @@ pub async fn login_page() -> Html<String> {
 </body>
 </html>
     "#;
-    Html(html.to_string())
+    Html(
+        html.replace("{style}", LOGIN_STYLE)
+            .replace("{css}", &branding.css_variables())
+            .replace("{logo}", &branding.logo_html())
+            .replace("{name}", &branding.name_html()),
+    )
+}
+
+/// Header controls of the logged-in pages: a "Guest" label and a login link for guests,
+/// otherwise a "Log out" button that revokes the session with `/api/logout`, then goes
+/// to the login page
+pub(crate) fn session_controls(user: &AuthenticatedUser, csrf_token: &str) -> String {
+    if user.is_guest() {
+        return r#"<style>
+            .session-controls { position: absolute; top: 16px; right: 16px; display: flex;
+                align-items: center; gap: 10px; }
+            .guest-badge { background: #fef3c7; color: #92400e; padding: 4px 10px;
+                border-radius: 10px; font-size: 14px; }
+            .session-controls a { color: var(--accent); }
+        </style>
+        <div class="session-controls">
+            <span class="guest-badge" title="You can browse and download, but not change anything">👤 Guest (read-only)</span>
+            <a href="/login">Log in</a>
+        </div>"#
+            .to_string();
+    }
+    format!(
+        r#"<style>
+            .logout-button {{ position: absolute; top: 16px; right: 16px; padding: 6px 14px;
+                border: 1px solid var(--accent); background: white; color: var(--accent);
+                border-radius: 5px; cursor: pointer; }}
+            .logout-button:hover {{ background: var(--accent); color: white; }}
+        </style>
+        <button type="button" id="logoutButton" class="logout-button">Log out</button>
+        <script>
+            document.getElementById('logoutButton').addEventListener('click', async () => {{
+                try {{
+                    await fetch('/api/logout', {{
+                        method: 'POST',
+                        headers: {{ '{}': {} }}
+                    }});
+                }} finally {{
+                    window.location.href = '/login';
+                }}
+            }});
+        </script>"#,
+        CSRF_HEADER,
+        js_string(csrf_token)
+    )
 }
@@ -1,7 +1,15 @@
+pub mod branding;
 pub mod cloud_folder;
+pub mod file_request;
 pub mod index;
 pub mod login;
+pub mod photos;
+pub mod website;
 
+pub use branding::*;
 pub use cloud_folder::*;
+pub use file_request::*;
 pub use index::*;
 pub use login::*;
+pub use photos::*;
+pub use website::*;
@@ -0,0 +1,147 @@
+use axum::{extract::State, response::Html};
+
+use crate::auth::AuthenticatedUser;
+use crate::cloud::CloudServerState;
+use crate::csrf::CsrfToken;
+use crate::web_routes::login::session_controls;
+
+/// The photo timeline: thumbnails of all photos grouped by day or month, filled in from
+/// `/api/photos/timeline`. Names come from the file system, so they're only ever set as
+/// text, never as markup.
+pub async fn photo_timeline(
+    State(server_state): State<CloudServerState>,
+    CsrfToken(csrf_token): CsrfToken,
+    user: AuthenticatedUser,
+) -> Html<String> {
+    let branding = &server_state.branding;
+    Html(
+        r#"
+    <!DOCTYPE html>
+    <html lang="en">
+    <head>
+        <meta name="viewport" content="width=device-width, initial-scale=1">
+        <title>Photo timeline - {name}</title>
+        <style>
+            {css}
+            body { font-family: Arial, sans-serif; margin: 40px; }
+            .container { max-width: 1100px; margin: 0 auto; }
+            .header { display: flex; align-items: center; justify-content: space-between; }
+            .header a { color: var(--accent); text-decoration: none; }
+            .grouping button { padding: 6px 14px; border: 1px solid var(--accent); background: white;
+                color: var(--accent); border-radius: 5px; cursor: pointer; }
+            .grouping button.active { background: var(--accent); color: white; }
+            .group h2 { font-size: 18px; border-bottom: 1px solid #ddd; padding-bottom: 5px; }
+            .group h2 span { color: #888; font-weight: normal; font-size: 14px; }
+            .photos { display: grid; grid-template-columns: repeat(auto-fill, minmax(160px, 1fr));
+                gap: 8px; }
+            .photo { display: block; aspect-ratio: 1; background: #f0f0f0; border-radius: 5px;
+                overflow: hidden; }
+            .photo:focus-visible { outline: 3px solid var(--accent); outline-offset: 2px; }
+            .photo img { width: 100%; height: 100%; object-fit: cover; }
+            .status { color: #888; }
+        </style>
+    </head>
+    <body>
+        <main class="container">
+            <div class="header">
+                <h1>📷 Photo timeline</h1>
+                <div class="grouping">
+                    <span role="group" aria-label="Group photos">
+                        <button type="button" data-group="day">By day</button>
+                        <button type="button" data-group="month">By month</button>
+                    </span>
+                    <a href="/">Home</a>
+                </div>
+            </div>
+            {logout}
+            <p id="status" class="status" role="status" aria-live="polite">Loading…</p>
+            <div id="timeline"></div>
+        </main>
+
+        <script>
+            const params = new URLSearchParams(window.location.search);
+            const grouping = params.get('group') === 'month' ? 'month' : 'day';
+
+            document.querySelectorAll('.grouping button').forEach(button => {
+                const active = button.dataset.group === grouping;
+                button.classList.toggle('active', active);
+                button.setAttribute('aria-pressed', active);
+                button.addEventListener('click', () => {
+                    params.set('group', button.dataset.group);
+                    window.location.search = params.toString();
+                });
+            });
+
+            function encodePath(path) {
+                return path.split('/').map(encodeURIComponent).join('/');
+            }
+
+            function describe(photo) {
+                const parts = [photo.name, photo.taken.replace('T', ' ')];
+                if (photo.camera) parts.push(photo.camera);
+                if (photo.gps) {
+                    parts.push(photo.gps.latitude.toFixed(5) + ', ' + photo.gps.longitude.toFixed(5));
+                }
+                return parts.join('\n');
+            }
+
+            async function load() {
+                const status = document.getElementById('status');
+                const query = new URLSearchParams({ group: grouping });
+                if (params.get('cloud_folder')) query.set('cloud_folder', params.get('cloud_folder'));
+                try {
+                    const response = await fetch('/api/photos/timeline?' + query);
+                    const result = await response.json();
+                    if (!response.ok) {
+                        status.textContent = 'Failed to load photos: ' + result.error;
+                        return;
+                    }
+                    status.textContent = result.total === 0
+                        ? 'No photos yet.'
+                        : result.total + (result.total === 1 ? ' photo' : ' photos');
+
+                    const timeline = document.getElementById('timeline');
+                    for (const group of result.groups) {
+                        const section = document.createElement('section');
+                        section.className = 'group';
+                        const heading = document.createElement('h2');
+                        heading.textContent = group.date + ' ';
+                        const count = document.createElement('span');
+                        count.textContent = '(' + group.count + ')';
+                        heading.appendChild(count);
+                        section.appendChild(heading);
+
+                        const grid = document.createElement('div');
+                        grid.className = 'photos';
+                        for (const photo of group.photos) {
+                            const url = '/api/' + encodeURIComponent(photo.cloud_folder)
+                                + '/static/' + encodePath(photo.path);
+                            const link = document.createElement('a');
+                            link.className = 'photo';
+                            link.href = url;
+                            link.title = describe(photo);
+                            const image = document.createElement('img');
+                            image.loading = 'lazy';
+                            image.src = url;
+                            image.alt = photo.name;
+                            link.appendChild(image);
+                            grid.appendChild(link);
+                        }
+                        section.appendChild(grid);
+                        timeline.appendChild(section);
+                    }
+                } catch (error) {
+                    status.textContent = 'Failed to load photos: ' + error.message;
+                }
+            }
+
+            load();
+        </script>
+    </body>
+    </html>
+    "#
+        .replace("{logout}", &session_controls(&user, &csrf_token))
+        .replace("{css}", &branding.css_variables())
+        .replace("{name}", &branding.name_html()),
+    )
+}
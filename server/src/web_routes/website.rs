@@ -0,0 +1,202 @@
+/// Public website folders: a cloud folder with `website` set is served without login
+/// under `/site/<name>/`, like a static web server. Directories show their `index.html`,
+/// or a listing if `directory_listing` is on. Hidden files (`.git`, `.env`, ...) are
+/// never served, and neither is anything a symlink leads to outside the folder.
+use axum::{
+    body::Body,
+    extract::{Path, Request, State},
+    http::{header, StatusCode},
+    response::{Html, IntoResponse, Redirect, Response},
+};
+use percent_encoding::utf8_percent_encode;
+use std::path::Path as StdPath;
+use tower_http::services::ServeFile;
+
+use crate::cloud::CloudServerState;
+use crate::html::{escape_html, PATH_SEGMENT};
+use crate::http_cache::{self, Validators};
+use crate::utils::{
+    detect_mime, ensure_folder_available, ensure_inside_cloud_folder, entry_name, is_dir, is_file,
+    sanitize_path,
+};
+
+/// `/site/<name>`: relative links only work below the trailing slash
+pub async fn website_root(Path(cloud_folder_name): Path<String>) -> Redirect {
+    let name = utf8_percent_encode(&cloud_folder_name, PATH_SEGMENT);
+    Redirect::permanent(&format!("/site/{}/", name))
+}
+
+/// `/site/<name>/`
+pub async fn website_index(
+    State(server_state): State<CloudServerState>,
+    Path(cloud_folder_name): Path<String>,
+    request: Request,
+) -> Response {
+    serve(&server_state, &cloud_folder_name, "", request).await
+}
+
+/// `/site/<name>/<path>`
+pub async fn website_file(
+    State(server_state): State<CloudServerState>,
+    Path((cloud_folder_name, path)): Path<(String, String)>,
+    request: Request,
+) -> Response {
+    serve(&server_state, &cloud_folder_name, &path, request).await
+}
+
+/// Everything that isn't served is the same 404, whether it's missing, hidden or private
+fn not_found(server_state: &CloudServerState) -> Response {
+    server_state
+        .branding
+        .error_page(StatusCode::NOT_FOUND, "The requested page was not found.")
+}
+
+async fn serve(
+    server_state: &CloudServerState,
+    cloud_folder_name: &str,
+    path: &str,
+    request: Request,
+) -> Response {
+    let Some(cloud_folder) = server_state
+        .cloud
+        .cloud_folders
+        .iter()
+        .find(|folder| folder.name == cloud_folder_name)
+    else {
+        return not_found(server_state);
+    };
+    let Some(website) = &cloud_folder.website else {
+        return not_found(server_state);
+    };
+    // A published folder whose drive went away is a 503 like everywhere else
+    if let Err(e) = ensure_folder_available(server_state, cloud_folder_name) {
+        return server_state.branding.error_page(e.status(), &e.message);
+    }
+
+    // Dotfiles such as `.git` aren't published
+    if path.split('/').any(|part| part.starts_with('.')) {
+        return not_found(server_state);
+    }
+    let Ok(target) = sanitize_path(&cloud_folder.folder_path, path) else {
+        return not_found(server_state);
+    };
+    if ensure_inside_cloud_folder(&cloud_folder.folder_path, &target, path)
+        .await
+        .is_err()
+    {
+        return not_found(server_state);
+    }
+
+    if !is_dir(&target).await {
+        return serve_file(&target, request).await;
+    }
+    if !path.is_empty() && !path.ends_with('/') {
+        return Redirect::permanent(&format!("{}/", request.uri().path())).into_response();
+    }
+    let index = target.join("index.html");
+    if is_file(&index).await {
+        // The index can be a symlink of its own
+        if ensure_inside_cloud_folder(&cloud_folder.folder_path, &index, path)
+            .await
+            .is_err()
+        {
+            return not_found(server_state);
+        }
+        return serve_file(&index, request).await;
+    }
+    if website.directory_listing {
+        return listing(server_state, cloud_folder_name, path, &target).await;
+    }
+    not_found(server_state)
+}
+
+/// Ranges and HEAD come with ServeFile, entity tags and caching with `http_cache`
+async fn serve_file(path: &StdPath, mut request: Request) -> Response {
+    let validators = tokio::fs::metadata(path)
+        .await
+        .ok()
+        .map(|metadata| Validators::new(&metadata));
+    let content_type = detect_mime(path.to_path_buf()).await;
+    let cache_control = http_cache::cache_control(content_type, true);
+    if let Some(validators) = &validators {
+        if validators.is_fresh(request.headers()) {
+            return validators.not_modified(cache_control);
+        }
+        // Already evaluated, and a tag that didn't match overrides the date
+        request.headers_mut().remove(header::IF_MODIFIED_SINCE);
+    }
+    let mut serve_file = match content_type.parse() {
+        Ok(mime) => ServeFile::new_with_mime(path, &mime),
+        Err(_) => ServeFile::new(path),
+    };
+    match serve_file.try_call(request).await {
+        Ok(response) => {
+            let mut response = response.map(Body::new);
+            if let Some(validators) = validators.filter(|_| response.status().is_success()) {
+                validators.apply(response.headers_mut(), cache_control);
+            }
+            response
+        }
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+async fn listing(
+    server_state: &CloudServerState,
+    cloud_folder_name: &str,
+    path: &str,
+    directory: &StdPath,
+) -> Response {
+    let Ok(mut entries) = tokio::fs::read_dir(directory).await else {
+        return not_found(server_state);
+    };
+    let mut items = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let Some(name) = entry_name(entry.file_name()) else {
+            continue;
+        };
+        if name.starts_with('.') {
+            continue;
+        }
+        let is_dir = entry.file_type().await.is_ok_and(|kind| kind.is_dir());
+        items.push((is_dir, name));
+    }
+    // Directories first, then by name
+    items.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+    let mut links = String::new();
+    if !path.is_empty() {
+        links.push_str(r#"<li><a href="../">../</a></li>"#);
+    }
+    for (is_dir, name) in &items {
+        let slash = if *is_dir { "/" } else { "" };
+        links.push_str(&format!(
+            r#"<li><a href="{}{}">{}{}</a></li>"#,
+            utf8_percent_encode(name, PATH_SEGMENT),
+            slash,
+            escape_html(name),
+            slash
+        ));
+    }
+    let title = escape_html(&format!(
+        "/{}/{}",
+        cloud_folder_name,
+        path.trim_end_matches('/')
+    ));
+    Html(format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <title>Index of {}</title>
+</head>
+<body>
+    <h1>Index of {}</h1>
+    <ul>{}</ul>
+</body>
+</html>
+"#,
+        title, title, links
+    ))
+    .into_response()
+}
@@ -0,0 +1,303 @@
+/// Webhooks: JSON POSTs to configured URLs when something happens in a cloud, for
+/// integrations. Every request carries the event in `X-CloudHost-Event`, a delivery id
+/// in `X-CloudHost-Delivery` and `X-CloudHost-Signature: sha256=<hex>`, the HMAC-SHA256
+/// of the body with the hook's secret, so receivers can check it came from CloudHost.
+///
+/// Failed deliveries (no connection, a timeout or a status other than 2xx) are retried
+/// with exponential backoff. The last deliveries are kept for the TUI's `:webhooks`.
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Sha256;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Attempts per delivery, the first one included
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Wait before the first retry, doubled for every further one
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How many deliveries are kept per cloud
+const MAX_HISTORY: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookEvent {
+    #[serde(rename = "file.uploaded")]
+    FileUploaded,
+    #[serde(rename = "file.deleted")]
+    FileDeleted,
+    #[serde(rename = "login.failed")]
+    LoginFailed,
+    #[serde(rename = "cloud.started")]
+    CloudStarted,
+    /// A cloud folder's path went away, e.g. its drive was unplugged
+    #[serde(rename = "folder.unavailable")]
+    FolderUnavailable,
+    #[serde(rename = "folder.available")]
+    FolderAvailable,
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 6] = [
+        Self::FileUploaded,
+        Self::FileDeleted,
+        Self::LoginFailed,
+        Self::CloudStarted,
+        Self::FolderUnavailable,
+        Self::FolderAvailable,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::FileUploaded => "file.uploaded",
+            Self::FileDeleted => "file.deleted",
+            Self::LoginFailed => "login.failed",
+            Self::CloudStarted => "cloud.started",
+            Self::FolderUnavailable => "folder.unavailable",
+            Self::FolderAvailable => "folder.available",
+        }
+    }
+}
+
+impl fmt::Display for WebhookEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+fn all_events() -> Vec<WebhookEvent> {
+    WebhookEvent::ALL.to_vec()
+}
+
+/// `[[clouds.webhooks]]` in the clouds config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Key of the payload signature
+    pub secret: String,
+    /// Events sent to this URL, all of them when unset
+    #[serde(default = "all_events")]
+    pub events: Vec<WebhookEvent>,
+}
+
+impl WebhookConfig {
+    /// Problems with the config, as `field: message`
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        match reqwest::Url::parse(&self.url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            _ => problems.push(format!("url: '{}' is not an http(s) URL", self.url)),
+        }
+        if self.secret.len() < 16 {
+            problems.push("secret: must be at least 16 characters".to_string());
+        }
+        if self.events.is_empty() {
+            problems.push("events: needs at least one event".to_string());
+        }
+        problems
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeliveryStatus {
+    /// Not sent yet, or waiting for a retry
+    Pending,
+    /// Answered with this 2xx status
+    Delivered(u16),
+    /// Gave up after the last attempt
+    Failed,
+}
+
+/// One event sent to one URL
+#[derive(Debug, Clone)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub event: WebhookEvent,
+    pub url: String,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// Error of the last failed attempt
+    pub last_error: Option<String>,
+}
+
+/// The webhooks of a running cloud
+#[derive(Debug, Default)]
+pub struct Webhooks {
+    cloud: String,
+    hooks: Vec<WebhookConfig>,
+    client: reqwest::Client,
+    history: Arc<Mutex<VecDeque<WebhookDelivery>>>,
+}
+
+impl Webhooks {
+    pub fn new(cloud: &str, hooks: Vec<WebhookConfig>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(concat!("CloudHost-Webhooks/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .unwrap_or_default();
+        Self {
+            cloud: cloud.to_string(),
+            hooks,
+            client,
+            history: Arc::default(),
+        }
+    }
+
+    /// Send `event` with `data` to every hook that wants it, in the background
+    pub fn emit(&self, event: WebhookEvent, data: serde_json::Value) {
+        let hooks: Vec<&WebhookConfig> = self
+            .hooks
+            .iter()
+            .filter(|hook| hook.events.contains(&event))
+            .collect();
+        if hooks.is_empty() {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let created_at = chrono::Utc::now();
+        let body = json!({
+            "id": id,
+            "event": event,
+            "cloud": self.cloud,
+            "timestamp": created_at,
+            "data": data,
+        })
+        .to_string();
+
+        for hook in hooks {
+            let delivery = WebhookDelivery {
+                id: id.clone(),
+                event,
+                url: hook.url.clone(),
+                status: DeliveryStatus::Pending,
+                attempts: 0,
+                created_at,
+                last_error: None,
+            };
+            if let Ok(mut history) = self.history.lock() {
+                history.push_front(delivery);
+                history.truncate(MAX_HISTORY);
+            }
+            runtime.spawn(deliver(
+                self.client.clone(),
+                self.history.clone(),
+                hook.clone(),
+                id.clone(),
+                event,
+                body.clone(),
+            ));
+        }
+    }
+
+    /// `file.uploaded` or `file.deleted` for `path` (`cloud_folder/sub/dir/name`),
+    /// with where it came from: `api`, `camera`, `file_request`, `nextcloud` or `ftp`
+    pub fn file_event(&self, event: WebhookEvent, path: &str, size: Option<u64>, source: &str) {
+        self.emit(
+            event,
+            json!({
+                "path": path.trim_start_matches('/'),
+                "size": size,
+                "source": source,
+            }),
+        );
+    }
+
+    /// The last deliveries, newest first
+    pub fn deliveries(&self) -> Vec<WebhookDelivery> {
+        self.history
+            .lock()
+            .map(|history| history.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Hex HMAC-SHA256 of `body`
+fn sign(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+async fn deliver(
+    client: reqwest::Client,
+    history: Arc<Mutex<VecDeque<WebhookDelivery>>>,
+    hook: WebhookConfig,
+    id: String,
+    event: WebhookEvent,
+    body: String,
+) {
+    let signature = format!("sha256={}", sign(&hook.secret, &body));
+    let update = |change: &dyn Fn(&mut WebhookDelivery)| {
+        if let Ok(mut history) = history.lock() {
+            if let Some(delivery) = history
+                .iter_mut()
+                .find(|delivery| delivery.id == id && delivery.url == hook.url)
+            {
+                change(delivery);
+            }
+        }
+    };
+
+    let mut delay = FIRST_RETRY_DELAY;
+    for attempt in 1..=MAX_ATTEMPTS {
+        let result = client
+            .post(&hook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-CloudHost-Event", event.as_str())
+            .header("X-CloudHost-Delivery", &id)
+            .header("X-CloudHost-Signature", &signature)
+            .body(body.clone())
+            .send()
+            .await;
+        let error = match result {
+            Ok(response) if response.status().is_success() => {
+                let status = response.status().as_u16();
+                update(&|delivery| {
+                    delivery.attempts = attempt;
+                    delivery.status = DeliveryStatus::Delivered(status);
+                });
+                tracing::debug!("Delivered {} webhook to {}", event, hook.url);
+                return;
+            }
+            Ok(response) => format!("HTTP {}", response.status()),
+            Err(e) => e.to_string(),
+        };
+
+        let last = attempt == MAX_ATTEMPTS;
+        update(&|delivery| {
+            delivery.attempts = attempt;
+            delivery.last_error = Some(error.clone());
+            if last {
+                delivery.status = DeliveryStatus::Failed;
+            }
+        });
+        if last {
+            tracing::warn!(
+                "Gave up on {} webhook to {} after {} attempts: {}",
+                event,
+                hook.url,
+                attempt,
+                error
+            );
+            return;
+        }
+        tokio::time::sleep(delay).await;
+        delay *= 2;
+    }
+}
@@ -0,0 +1,94 @@
+//! Error responses of the API: every failure answers with its status and a body of
+//! `{"error": <message>, "code": <CODE>}`, plus whatever details the error carries.
+
+mod common;
+
+use common::{TestResponse, TestServer, CLOUD_FOLDER};
+
+/// The status, code and message of an error response
+fn assert_error(response: &TestResponse, status: u16, code: &str) {
+    assert_eq!(response.status, status, "{}", response.text());
+    let body = response.json();
+    assert_eq!(body["code"], code, "{}", body);
+    assert!(
+        body["error"]
+            .as_str()
+            .is_some_and(|error| !error.is_empty()),
+        "{}",
+        body
+    );
+}
+
+#[tokio::test]
+async fn missing_things_are_not_found() {
+    let server = TestServer::start().await;
+
+    let response = server.request("GET", "/api/music/files").send().await;
+    assert_error(&response, 404, "CLOUD_FOLDER_NOT_FOUND");
+    let response = server
+        .request("GET", &format!("/api/{}/files/nowhere", CLOUD_FOLDER))
+        .send()
+        .await;
+    assert_error(&response, 404, "NOT_FOUND");
+    // Used to answer 200 with the error in the body
+    let response = server
+        .request("GET", &format!("/api/{}/static/nothing.txt", CLOUD_FOLDER))
+        .send()
+        .await;
+    assert_error(&response, 404, "NOT_FOUND");
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn errors_carry_their_details() {
+    let server = TestServer::start().await;
+    std::fs::write(server.file("notes.txt"), "first").unwrap();
+
+    let response = server
+        .request(
+            "POST",
+            &format!("/api/upload/{}?on_conflict=reject", CLOUD_FOLDER),
+        )
+        .file("notes.txt", b"second")
+        .send()
+        .await;
+    assert_error(&response, 409, "FILE_EXISTS");
+    assert_eq!(
+        response.json()["existing_path"],
+        format!("{}/notes.txt", CLOUD_FOLDER)
+    );
+    // The refused upload was received to a temporary file, which is gone
+    let names: Vec<_> = std::fs::read_dir(server.file(""))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(names, ["notes.txt"]);
+    assert_eq!(std::fs::read(server.file("notes.txt")).unwrap(), b"first");
+
+    let response = server.request("GET", "/api").anonymous().send().await;
+    assert_error(&response, 401, "UNAUTHORIZED");
+    assert_eq!(response.json()["login_url"], "/api/login");
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn bad_input_is_a_client_error() {
+    let server = TestServer::start().await;
+
+    let response = server
+        .request("GET", &format!("/api/{}/files/..%2F..%2Fetc", CLOUD_FOLDER))
+        .send()
+        .await;
+    assert_error(&response, 400, "INVALID_PATH");
+    let response = server
+        .request("POST", &format!("/api/upload/{}", CLOUD_FOLDER))
+        .header("X-OC-Mtime", "yesterday")
+        .file("notes.txt", b"text")
+        .send()
+        .await;
+    assert_error(&response, 400, "VALIDATION_ERROR");
+
+    server.stop().await;
+}
@@ -0,0 +1,136 @@
+//! API token scopes: a token only makes the requests its scope allows, on the API and
+//! over WebDAV, and never manages tokens itself; that takes a login.
+
+mod common;
+
+use common::orchestrator::TestOrchestrator;
+use common::request;
+
+/// Create a token with the login, returns its id and value
+async fn create_token(port: u16, login: &str, scope: &str) -> (String, String) {
+    let response = request(port, "POST", "/api/tokens")
+        .bearer(login)
+        .header("Content-Type", "application/json")
+        .body(serde_json::json!({ "name": scope, "scope": scope }).to_string())
+        .send()
+        .await;
+    assert_eq!(response.status, 201, "{}", response.text());
+    let body = response.json();
+    (
+        body["id"].as_str().unwrap().to_string(),
+        body["token"].as_str().unwrap().to_string(),
+    )
+}
+
+fn assert_out_of_scope(response: &common::TestResponse, scope: &str) {
+    assert_eq!(response.status, 403, "{}", response.text());
+    let body = response.json();
+    assert_eq!(body["code"], "TOKEN_OUT_OF_SCOPE");
+    assert_eq!(body["scope"], scope);
+}
+
+#[tokio::test]
+async fn tokens_only_make_requests_within_their_scope() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents"]);
+    harness.orchestrator.clouds_config.clouds[0].nextcloud_compat = true;
+    let port = harness.start("home").await;
+    let login = harness.login(port).await;
+    let notes = harness.folder_path("documents").join("notes.txt");
+    std::fs::write(&notes, "hello").unwrap();
+
+    let (_, read_only) = create_token(port, &login, "read-only").await;
+    let response = request(port, "GET", "/api/documents/static/notes.txt")
+        .bearer(&read_only)
+        .send()
+        .await;
+    assert_eq!(response.text(), "hello");
+    let writes = [
+        request(port, "DELETE", "/api/delete/documents/notes.txt"),
+        request(port, "POST", "/api/upload/documents").file("report.txt", b"numbers"),
+        request(port, "PATCH", "/api/file/documents/notes.txt")
+            .header("Content-Type", "application/json")
+            .body(r#"{"name": "renamed.txt"}"#),
+    ];
+    for write in writes {
+        assert_out_of_scope(&write.bearer(&read_only).send().await, "read-only");
+    }
+    // WebDAV checks the scope too
+    let response = request(port, "PUT", "/remote.php/webdav/documents/report.txt")
+        .basic("home", &read_only)
+        .body("numbers")
+        .send()
+        .await;
+    assert_out_of_scope(&response, "read-only");
+    let response = request(port, "PROPFIND", "/remote.php/webdav/documents")
+        .basic("home", &read_only)
+        .header("Depth", "1")
+        .send()
+        .await;
+    assert_eq!(response.status, 207, "{}", response.text());
+    assert_eq!(std::fs::read(&notes).unwrap(), b"hello");
+    assert!(!harness.folder_path("documents").join("report.txt").exists());
+
+    let (_, upload_only) = create_token(port, &login, "upload-only").await;
+    let response = request(port, "POST", "/api/upload/documents")
+        .bearer(&upload_only)
+        .file("report.txt", b"numbers")
+        .send()
+        .await;
+    assert_eq!(response.status, 200, "{}", response.text());
+    let response = request(port, "GET", "/api/documents/static/notes.txt")
+        .bearer(&upload_only)
+        .send()
+        .await;
+    assert_out_of_scope(&response, "upload-only");
+
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn tokens_are_managed_with_a_login_only() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents"]);
+    let port = harness.start("home").await;
+    let login = harness.login(port).await;
+    let (id, full) = create_token(port, &login, "full").await;
+    let (other_id, _) = create_token(port, &login, "read-only").await;
+
+    // Not even a full token mints, lists or revokes tokens, its own included
+    let attempts = [
+        request(port, "POST", "/api/tokens")
+            .header("Content-Type", "application/json")
+            .body(r#"{"name": "another", "scope": "full"}"#),
+        request(port, "GET", "/api/tokens"),
+        request(port, "DELETE", &format!("/api/tokens/{}", other_id)),
+        request(port, "DELETE", &format!("/api/tokens/{}", id)),
+    ];
+    for attempt in attempts {
+        let response = attempt.bearer(&full).send().await;
+        assert_eq!(response.status, 403, "{}", response.text());
+        assert_eq!(response.json()["code"], "FORBIDDEN");
+    }
+    let response = request(port, "GET", "/api/tokens")
+        .bearer(&login)
+        .send()
+        .await;
+    assert_eq!(response.json()["tokens"].as_array().unwrap().len(), 2);
+
+    // The full token is still good for everything else, until the login revokes it
+    let response = request(port, "GET", "/api/documents/files")
+        .bearer(&full)
+        .send()
+        .await;
+    assert_eq!(response.status, 200, "{}", response.text());
+    let response = request(port, "DELETE", &format!("/api/tokens/{}", id))
+        .bearer(&login)
+        .send()
+        .await;
+    assert_eq!(response.status, 200, "{}", response.text());
+    let response = request(port, "GET", "/api/documents/files")
+        .bearer(&full)
+        .send()
+        .await;
+    assert_eq!(response.status, 401, "{}", response.text());
+    harness.stop().await;
+}
@@ -0,0 +1,110 @@
+//! The client library against a running cloud: logging in, managing files and sharing
+//! directories through file requests.
+
+mod common;
+
+use cloudhost_client::{ClientError, CloudHostClient, NewFileRequest, UploadOptions};
+use common::orchestrator::{TestOrchestrator, PASSWORD};
+use common::request;
+use futures_util::StreamExt;
+
+async fn logged_in(harness: &mut TestOrchestrator) -> (u16, CloudHostClient) {
+    harness.add_cloud("home", &["documents"]);
+    let port = harness.start("home").await;
+    let client = CloudHostClient::new(&format!("http://127.0.0.1:{}", port)).unwrap();
+    client.login(PASSWORD).await.unwrap();
+    (port, client)
+}
+
+#[tokio::test]
+async fn files_are_managed_with_the_client() {
+    let mut harness = TestOrchestrator::new().await;
+    let (port, client) = logged_in(&mut harness).await;
+    let wrong = CloudHostClient::new(&format!("http://127.0.0.1:{}", port)).unwrap();
+    assert!(matches!(
+        wrong.login("wrong").await,
+        Err(ClientError::Authentication(_))
+    ));
+
+    assert_eq!(client.cloud_folders().await.unwrap(), ["documents"]);
+    client
+        .upload_bytes(
+            "documents",
+            "",
+            "notes.txt",
+            "hello",
+            &UploadOptions::default(),
+        )
+        .await
+        .unwrap();
+    client
+        .append("documents", "notes.txt", " world", false)
+        .await
+        .unwrap();
+    let names: Vec<String> = client
+        .list_dir("documents", "")
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.name)
+        .collect();
+    assert_eq!(names, ["notes.txt"]);
+    let mut stream = client
+        .download_stream("documents", "notes.txt")
+        .await
+        .unwrap();
+    let mut contents = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        contents.extend_from_slice(&chunk.unwrap());
+    }
+    assert_eq!(contents, b"hello world");
+
+    client.delete("documents", "notes.txt").await.unwrap();
+    assert!(!harness.folder_path("documents").join("notes.txt").exists());
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn file_requests_are_created_listed_and_revoked() {
+    let mut harness = TestOrchestrator::new().await;
+    let (port, client) = logged_in(&mut harness).await;
+    std::fs::create_dir(harness.folder_path("documents").join("inbox")).unwrap();
+
+    let created = client
+        .create_file_request(&NewFileRequest {
+            name: "Scans".to_string(),
+            path: "documents/inbox".to_string(),
+            max_files: Some(3),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(created.path, "documents/inbox");
+    assert_eq!(created.url, format!("/drop/{}", created.id));
+    assert!(created.open);
+    let response = request(port, "POST", &created.url)
+        .file("scan.pdf", b"scan")
+        .send()
+        .await;
+    assert_eq!(response.status, 200, "{}", response.text());
+
+    let listed = client.file_requests().await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].id, created.id);
+    assert_eq!(listed[0].uploads, 1);
+    assert_eq!(listed[0].max_files, Some(3));
+
+    client.revoke_file_request(&created.id).await.unwrap();
+    assert!(client.file_requests().await.unwrap().is_empty());
+    let response = request(port, "POST", &created.url)
+        .file("late.pdf", b"late")
+        .send()
+        .await;
+    assert_eq!(response.status, 404, "{}", response.text());
+    // Revoking what isn't there is an error
+    assert!(matches!(
+        client.revoke_file_request(&created.id).await,
+        Err(ClientError::Api { status: 404, .. })
+    ));
+    harness.stop().await;
+}
@@ -0,0 +1,202 @@
+//! Saving the clouds config while other programs (another TUI, the daemon) write it too:
+//! their changes are merged rather than lost, the lock file left by a program that went
+//! away doesn't block, and a write that fails leaves the previous file in place.
+
+mod common;
+
+use cloudhost_server::cloud::CloudFolder;
+use cloudhost_server::clouds_config::CloudsConfig;
+use cloudhost_server::config_paths;
+use cloudhost_server::error::ServerError;
+use cloudhost_server::orchestrator::ConflictResolution;
+use common::orchestrator::TestOrchestrator;
+use common::TempDir;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+
+fn folder_names(config: &CloudsConfig) -> Vec<&str> {
+    config
+        .cloud_folders
+        .iter()
+        .map(|folder| folder.name.as_str())
+        .collect()
+}
+
+fn lock_path() -> PathBuf {
+    let mut path = config_paths::get_clouds_config_path().into_os_string();
+    path.push(".lock");
+    PathBuf::from(path)
+}
+
+/// Add a folder to a copy of the config and save it in another thread; the result
+/// comes through the receiver
+fn save_in_background(config: &CloudsConfig, folder: CloudFolder) -> mpsc::Receiver<bool> {
+    let mut config = config.clone();
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        config.add_cloud_folder(folder).unwrap();
+        let _ = sender.send(config.save_to_file().is_ok());
+    });
+    receiver
+}
+
+#[tokio::test]
+async fn changes_made_by_another_program_are_merged_not_lost() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents", "music"]);
+
+    // Another program adds a folder and drops one of ours
+    let mut theirs = CloudsConfig::load_from_file().unwrap();
+    let videos = TempDir::new("videos");
+    theirs
+        .add_cloud_folder(CloudFolder::new(
+            "videos".to_string(),
+            videos.path().to_path_buf(),
+        ))
+        .unwrap();
+    theirs.remove_cloud_folder("music").unwrap();
+    theirs.save_to_file().unwrap();
+    assert!(harness.orchestrator.clouds_config.is_stale());
+
+    // Our save doesn't overwrite their file, it reports the conflict
+    let error = harness.orchestrator.remove_cloud_folder("documents");
+    assert!(
+        matches!(error, Err(ServerError::ConfigConflict(_))),
+        "{:?}",
+        error
+    );
+    assert!(harness.orchestrator.has_config_conflict());
+    assert_eq!(
+        folder_names(&CloudsConfig::load_from_file().unwrap()),
+        ["documents", "videos"]
+    );
+
+    harness
+        .orchestrator
+        .resolve_config_conflict(ConflictResolution::Merge)
+        .unwrap();
+    assert!(!harness.orchestrator.has_config_conflict());
+    let on_disk = CloudsConfig::load_from_file().unwrap();
+    assert_eq!(folder_names(&on_disk), ["videos"]);
+    assert_eq!(on_disk.clouds.len(), 1);
+    assert_eq!(
+        folder_names(&harness.orchestrator.clouds_config),
+        ["videos"]
+    );
+    assert!(!harness.orchestrator.clouds_config.is_stale());
+}
+
+#[test]
+fn merging_keeps_both_sides_changes() {
+    let folder = |name: &str, path: &str| CloudFolder::new(name.to_string(), PathBuf::from(path));
+    let mut base = CloudsConfig::default();
+    base.cloud_folders = vec![
+        folder("documents", "/srv/documents"),
+        folder("music", "/srv/music"),
+        folder("photos", "/srv/photos"),
+    ];
+    base.port_retries = 10;
+
+    let mut ours = base.clone();
+    ours.cloud_folders[0].folder_path = PathBuf::from("/mnt/documents");
+    ours.cloud_folders.retain(|folder| folder.name != "photos");
+    ours.cloud_folders.push(folder("books", "/srv/books"));
+
+    let mut theirs = base.clone();
+    theirs.cloud_folders.retain(|folder| folder.name != "music");
+    // Changed there while we removed it: their change is kept
+    theirs.cloud_folders[1].folder_path = PathBuf::from("/mnt/photos");
+    theirs.port_retries = 3;
+
+    let merged = CloudsConfig::merge(&base, &ours, &theirs);
+    let folders: Vec<(&str, PathBuf)> = merged
+        .cloud_folders
+        .iter()
+        .map(|folder| (folder.name.as_str(), folder.folder_path.clone()))
+        .collect();
+    assert_eq!(
+        folders,
+        [
+            ("documents", PathBuf::from("/mnt/documents")),
+            ("photos", PathBuf::from("/mnt/photos")),
+            ("books", PathBuf::from("/srv/books")),
+        ]
+    );
+    assert_eq!(merged.port_retries, 3);
+}
+
+#[tokio::test]
+async fn a_lock_left_behind_does_not_block_saving() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents"]);
+    let config = harness.orchestrator.clouds_config.clone();
+
+    // A program that was holding the lock went away: the file stays, the lock doesn't
+    fs::write(lock_path(), "12345").unwrap();
+    let held = fs::OpenOptions::new()
+        .write(true)
+        .open(lock_path())
+        .unwrap();
+    held.lock().unwrap();
+    drop(held);
+
+    let music = TempDir::new("music");
+    let saved = save_in_background(
+        &config,
+        CloudFolder::new("music".to_string(), music.path().to_path_buf()),
+    );
+    assert_eq!(saved.recv_timeout(Duration::from_secs(5)), Ok(true));
+
+    // While another program does hold it, saving waits for it
+    let held = fs::OpenOptions::new()
+        .write(true)
+        .open(lock_path())
+        .unwrap();
+    held.lock().unwrap();
+    let videos = TempDir::new("videos");
+    let config = CloudsConfig::load_from_file().unwrap();
+    let saved = save_in_background(
+        &config,
+        CloudFolder::new("videos".to_string(), videos.path().to_path_buf()),
+    );
+    assert!(saved.recv_timeout(Duration::from_millis(300)).is_err());
+    held.unlock().unwrap();
+    assert_eq!(saved.recv_timeout(Duration::from_secs(5)), Ok(true));
+    assert_eq!(
+        folder_names(&CloudsConfig::load_from_file().unwrap()),
+        ["documents", "music", "videos"]
+    );
+}
+
+#[tokio::test]
+async fn a_failed_write_leaves_the_previous_file() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents"]);
+    let config_path = config_paths::get_clouds_config_path();
+    let before = fs::read_to_string(&config_path).unwrap();
+
+    // The temp file the new version is written to can't be created
+    let temp_path = config_path.with_extension(format!("toml.tmp-{}", std::process::id()));
+    fs::create_dir(&temp_path).unwrap();
+    let music = TempDir::new("music");
+    let result = harness.orchestrator.add_cloud_folder(CloudFolder::new(
+        "music".to_string(),
+        music.path().to_path_buf(),
+    ));
+    assert!(result.is_err());
+    assert_eq!(fs::read_to_string(&config_path).unwrap(), before);
+    assert_eq!(
+        folder_names(&CloudsConfig::load_from_file().unwrap()),
+        ["documents"]
+    );
+
+    // Once writing works again the change is saved
+    fs::remove_dir(&temp_path).unwrap();
+    harness.orchestrator.clouds_config.save_to_file().unwrap();
+    assert_eq!(
+        folder_names(&CloudsConfig::load_from_file().unwrap()),
+        ["documents", "music"]
+    );
+}
@@ -0,0 +1,354 @@
+//! Test harness: a cloud server on a free local port serving a temporary cloud folder,
+//! and a minimal HTTP/1.1 client that sends requests exactly as written, custom
+//! methods and headers included, and `ftp_commands` for FTP. `orchestrator` runs whole
+//! clouds the way the TUI does.
+#![allow(dead_code)]
+
+pub mod orchestrator;
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use cloudhost_server::auth::AuthState;
+use cloudhost_server::cloud::{Cloud, CloudFolder, CloudServer};
+use cloudhost_server::debug_stream::DebugStream;
+use cloudhost_server::password::PasswordHashParams;
+
+/// Name of the cloud folder every test server shares
+pub const CLOUD_FOLDER: &str = "files";
+
+/// A directory under the system temp dir, removed on drop
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(prefix: &str) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "cloudhost-{}-{}-{}",
+            prefix,
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&path).expect("create temp dir");
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+pub struct TestServer {
+    server: CloudServer,
+    pub port: u16,
+    pub token: String,
+    pub folder: TempDir,
+    /// The cloud's log, as `/api/logs/stream` sends it
+    pub debug_stream: Arc<DebugStream>,
+}
+
+impl TestServer {
+    /// Start a cloud with one cloud folder, `CLOUD_FOLDER`, backed by a fresh temp dir
+    pub async fn start() -> Self {
+        Self::start_with(|_| {}).await
+    }
+
+    /// Like `start`, with the cloud's settings changed by `configure` first
+    pub async fn start_with(configure: impl FnOnce(&mut Cloud)) -> Self {
+        let folder = TempDir::new("test");
+        let port = free_port();
+        let mut cloud = Cloud::new(
+            "test".to_string(),
+            vec![CloudFolder::new(
+                CLOUD_FOLDER.to_string(),
+                folder.path().to_path_buf(),
+            )],
+        );
+        configure(&mut cloud);
+        let auth_state = Arc::new(AuthState::new(
+            "test-secret".to_string(),
+            None,
+            None,
+            PasswordHashParams::default(),
+        ));
+        let token = auth_state.generate_token().expect("generate token");
+
+        let debug_stream = Arc::new(DebugStream::new(100));
+        let mut server = CloudServer::new(cloud, port);
+        server
+            .start_server(auth_state, debug_stream.clone())
+            .await
+            .expect("start server");
+
+        // The listener is bound by the server task
+        for _ in 0..100 {
+            if TcpStream::connect(("127.0.0.1", port)).await.is_ok() {
+                return Self {
+                    server,
+                    port,
+                    token,
+                    folder,
+                    debug_stream,
+                };
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("server did not start listening on port {}", port);
+    }
+
+    /// Path of `relative` inside the cloud folder on disk
+    pub fn file(&self, relative: &str) -> PathBuf {
+        self.folder.path().join(relative)
+    }
+
+    /// A request with the bearer token set
+    pub fn request(&self, method: &str, path: &str) -> RequestBuilder {
+        request(self.port, method, path).bearer(&self.token)
+    }
+
+    /// A request with the token as the Basic-auth app password, as WebDAV clients send it
+    pub fn dav_request(&self, method: &str, path: &str) -> RequestBuilder {
+        request(self.port, method, path).basic("test", &self.token)
+    }
+
+    pub async fn stop(mut self) {
+        let _ = self.server.stop_server().await;
+    }
+}
+
+pub fn free_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .expect("find a free port")
+}
+
+/// Send FTP commands over one control connection to `port`, returns the first line of
+/// each reply. Stops early when the server closes the connection.
+pub async fn ftp_commands(port: u16, commands: &[&str]) -> Vec<String> {
+    use tokio::io::AsyncBufReadExt;
+    let stream = TcpStream::connect(("127.0.0.1", port))
+        .await
+        .expect("connect to FTP");
+    let (read, mut write) = stream.into_split();
+    let mut lines = tokio::io::BufReader::new(read).lines();
+    lines.next_line().await.expect("greeting");
+    let mut replies = Vec::new();
+    for command in commands {
+        if write
+            .write_all(format!("{}\r\n", command).as_bytes())
+            .await
+            .is_err()
+        {
+            break;
+        }
+        match lines.next_line().await {
+            Ok(Some(line)) => replies.push(line),
+            _ => break,
+        }
+    }
+    replies
+}
+
+/// A request without credentials to the server on `port`
+pub fn request(port: u16, method: &str, path: &str) -> RequestBuilder {
+    RequestBuilder {
+        port,
+        method: method.to_string(),
+        path: path.to_string(),
+        headers: Vec::new(),
+        body: Vec::new(),
+        chunked: false,
+    }
+}
+
+pub struct RequestBuilder {
+    port: u16,
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    chunked: bool,
+}
+
+impl RequestBuilder {
+    pub fn bearer(self, token: &str) -> Self {
+        self.header("Authorization", &format!("Bearer {}", token))
+    }
+
+    /// Basic auth, as WebDAV and Nextcloud clients send their app password
+    pub fn basic(self, user: &str, password: &str) -> Self {
+        use base64::Engine;
+        let credentials =
+            base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", user, password));
+        self.anonymous()
+            .header("Authorization", &format!("Basic {}", credentials))
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Send the request without credentials
+    pub fn anonymous(mut self) -> Self {
+        self.headers.retain(|(name, _)| name != "Authorization");
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Send the body with `Transfer-Encoding: chunked` instead of a Content-Length
+    pub fn chunked(mut self) -> Self {
+        self.chunked = true;
+        self
+    }
+
+    /// A `multipart/form-data` body with one file, as the upload API takes it
+    pub fn file(self, filename: &str, contents: &[u8]) -> Self {
+        const BOUNDARY: &str = "cloudhost-test-boundary";
+        let mut body = format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n\
+             Content-Type: application/octet-stream\r\n\r\n",
+            BOUNDARY, filename
+        )
+        .into_bytes();
+        body.extend_from_slice(contents);
+        body.extend_from_slice(format!("\r\n--{}--\r\n", BOUNDARY).as_bytes());
+        self.header(
+            "Content-Type",
+            &format!("multipart/form-data; boundary={}", BOUNDARY),
+        )
+        .body(body)
+    }
+
+    pub async fn send(self) -> TestResponse {
+        let mut request = format!(
+            "{} {} HTTP/1.1\r\nHost: 127.0.0.1:{}\r\nConnection: close\r\n",
+            self.method, self.path, self.port,
+        );
+        let body = if self.chunked {
+            request.push_str("Transfer-Encoding: chunked\r\n");
+            let mut body = Vec::new();
+            for chunk in self.body.chunks(64 * 1024) {
+                body.extend_from_slice(format!("{:x}\r\n", chunk.len()).as_bytes());
+                body.extend_from_slice(chunk);
+                body.extend_from_slice(b"\r\n");
+            }
+            body.extend_from_slice(b"0\r\n\r\n");
+            body
+        } else {
+            request.push_str(&format!("Content-Length: {}\r\n", self.body.len()));
+            self.body
+        };
+        for (name, value) in &self.headers {
+            request.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        request.push_str("\r\n");
+
+        let mut stream = TcpStream::connect(("127.0.0.1", self.port))
+            .await
+            .expect("connect");
+        stream.write_all(request.as_bytes()).await.expect("send");
+        stream.write_all(&body).await.expect("send body");
+        let mut raw = Vec::new();
+        stream.read_to_end(&mut raw).await.expect("read response");
+        TestResponse::parse(&raw)
+    }
+}
+
+#[derive(Debug)]
+pub struct TestResponse {
+    pub status: u16,
+    headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl TestResponse {
+    fn parse(raw: &[u8]) -> Self {
+        let split = raw
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .expect("response has a header section");
+        let head = String::from_utf8_lossy(&raw[..split]);
+        let mut lines = head.lines();
+        let status = lines
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|code| code.parse().ok())
+            .expect("status line");
+        let headers: Vec<(String, String)> = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+            .collect();
+
+        let mut body = raw[split + 4..].to_vec();
+        let chunked = headers
+            .iter()
+            .any(|(name, value)| name == "transfer-encoding" && value.contains("chunked"));
+        if chunked {
+            body = decode_chunked(&body);
+        }
+        Self {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_ascii_lowercase();
+        self.headers
+            .iter()
+            .find(|(header, _)| *header == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Every value of a header that can repeat, like `Set-Cookie`
+    pub fn headers(&self, name: &str) -> Vec<&str> {
+        let name = name.to_ascii_lowercase();
+        self.headers
+            .iter()
+            .filter(|(header, _)| *header == name)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body)
+            .unwrap_or_else(|e| panic!("body is not JSON ({}): {}", e, self.text()))
+    }
+}
+
+fn decode_chunked(mut data: &[u8]) -> Vec<u8> {
+    let mut body = Vec::new();
+    while let Some(line_end) = data.windows(2).position(|window| window == b"\r\n") {
+        let size_line = String::from_utf8_lossy(&data[..line_end]);
+        let size = usize::from_str_radix(size_line.split(';').next().unwrap_or("").trim(), 16)
+            .unwrap_or(0);
+        data = &data[line_end + 2..];
+        if size == 0 || data.len() < size {
+            break;
+        }
+        body.extend_from_slice(&data[..size]);
+        data = &data[(size + 2).min(data.len())..];
+    }
+    body
+}
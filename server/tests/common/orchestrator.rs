@@ -0,0 +1,113 @@
+//! An `Orchestrator` whose config dir is a temp dir, with clouds over temp cloud folders
+//! that start on free ports. The config dir is set through `CLOUDHOST_CONFIG_DIR`, which
+//! the whole process shares, so tests using it take turns.
+
+use std::path::Path;
+use tokio::sync::{Mutex, MutexGuard};
+
+use cloudhost_server::cloud::{Cloud, CloudFolder};
+use cloudhost_server::orchestrator::Orchestrator;
+use cloudhost_server::password::PasswordHashParams;
+
+use super::{free_port, request, TempDir};
+
+/// Password of every cloud added by `add_cloud`
+pub const PASSWORD: &str = "orchestrated";
+
+static TURN: Mutex<()> = Mutex::const_new(());
+
+pub struct TestOrchestrator {
+    pub orchestrator: Orchestrator,
+    pub config_dir: TempDir,
+    /// Cloud folders on disk, by name
+    folders: Vec<(String, TempDir)>,
+    _turn: MutexGuard<'static, ()>,
+}
+
+impl TestOrchestrator {
+    /// An orchestrator with an empty config in a fresh config dir
+    pub async fn new() -> Self {
+        let turn = TURN.lock().await;
+        let config_dir = TempDir::new("config");
+        std::env::set_var("CLOUDHOST_CONFIG_DIR", config_dir.path());
+        // Deleted files go to a trash in the config dir rather than the user's
+        std::env::set_var("XDG_DATA_HOME", config_dir.path());
+
+        let mut orchestrator = Orchestrator::new();
+        // The cheapest Argon2 parameters; logins don't need to be slow here
+        orchestrator.clouds_config.password_hashing = PasswordHashParams {
+            memory_kib: 8,
+            iterations: 1,
+            parallelism: 1,
+        };
+        Self {
+            orchestrator,
+            config_dir,
+            folders: Vec::new(),
+            _turn: turn,
+        }
+    }
+
+    /// A cloud folder backed by a fresh temp dir, added to the config
+    pub fn add_folder(&mut self, name: &str) -> CloudFolder {
+        let dir = TempDir::new(name);
+        let folder = CloudFolder::new(name.to_string(), dir.path().to_path_buf());
+        self.orchestrator
+            .add_cloud_folder(folder.clone())
+            .expect("add cloud folder");
+        self.folders.push((name.to_string(), dir));
+        folder
+    }
+
+    /// A cloud with a new folder for each of `folders`, its password set to `PASSWORD`
+    pub fn add_cloud(&mut self, name: &str, folders: &[&str]) -> Cloud {
+        let folders = folders
+            .iter()
+            .map(|folder| self.add_folder(folder))
+            .collect();
+        let cloud = Cloud::new(name.to_string(), folders);
+        self.orchestrator
+            .add_cloud(cloud.clone())
+            .expect("add cloud");
+        self.orchestrator
+            .set_cloud_password(name, PASSWORD)
+            .expect("set password");
+        cloud
+    }
+
+    /// Path on disk of the cloud folder `folder`
+    pub fn folder_path(&self, folder: &str) -> &Path {
+        self.folders
+            .iter()
+            .find(|(name, _)| name == folder)
+            .map(|(_, dir)| dir.path())
+            .unwrap_or_else(|| panic!("no cloud folder '{}'", folder))
+    }
+
+    /// Start `cloud` on a free port, which is returned once it's listening
+    pub async fn start(&mut self, cloud: &str) -> u16 {
+        self.orchestrator.next_port = free_port();
+        self.orchestrator
+            .start_cloud(cloud)
+            .await
+            .unwrap_or_else(|e| panic!("start cloud '{}': {}", cloud, e))
+    }
+
+    /// A session token for the cloud on `port`
+    pub async fn login(&self, port: u16) -> String {
+        let response = request(port, "POST", "/api/login")
+            .header("Content-Type", "application/json")
+            .body(serde_json::json!({ "password": PASSWORD }).to_string())
+            .send()
+            .await;
+        assert_eq!(response.status, 200, "login: {}", response.text());
+        response.json()["token"]
+            .as_str()
+            .expect("token in the login response")
+            .to_string()
+    }
+
+    pub async fn stop(mut self) {
+        let _ = self.orchestrator.stop_all().await;
+    }
+}
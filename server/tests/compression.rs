@@ -0,0 +1,118 @@
+//! Response compression: text is sent gzip- or zstd-compressed as it streams, whatever
+//! its size, while media, ranges and tiny bodies are sent as they are.
+
+mod common;
+
+use std::io::Read;
+
+use common::{TestServer, CLOUD_FOLDER};
+
+fn static_path(name: &str) -> String {
+    format!("/api/{}/static/{}", CLOUD_FOLDER, name)
+}
+
+/// Some 10 MB of text, more than any buffer a compressor would hold it in
+fn large_text() -> String {
+    (0..200_000)
+        .map(|line| format!("line {} of a large log file\n", line))
+        .collect()
+}
+
+#[tokio::test]
+async fn large_text_downloads_are_compressed_as_they_stream() {
+    let server = TestServer::start_with(|cloud| cloud.nextcloud_compat = true).await;
+    let text = large_text();
+    std::fs::write(server.file("big.log"), &text).unwrap();
+
+    let response = server
+        .request("GET", &static_path("big.log"))
+        .header("Accept-Encoding", "gzip")
+        .send()
+        .await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("content-encoding"), Some("gzip"));
+    assert!(response.header("accept-ranges").is_none());
+    assert!(response
+        .header("etag")
+        .is_none_or(|tag| tag.starts_with("W/")));
+    assert!(response.body.len() < text.len() / 4);
+    let mut unpacked = String::new();
+    flate2::read::GzDecoder::new(response.body.as_slice())
+        .read_to_string(&mut unpacked)
+        .unwrap();
+    assert_eq!(unpacked, text);
+
+    let response = server
+        .request("GET", &static_path("big.log"))
+        .header("Accept-Encoding", "zstd")
+        .send()
+        .await;
+    assert_eq!(response.header("content-encoding"), Some("zstd"));
+    assert_eq!(
+        zstd::decode_all(response.body.as_slice()).unwrap(),
+        text.as_bytes()
+    );
+
+    // Not asked for
+    let response = server.request("GET", &static_path("big.log")).send().await;
+    assert!(response.header("content-encoding").is_none());
+    assert_eq!(response.body.len(), text.len());
+
+    // WebDAV downloads stream the file from disk
+    let response = server
+        .dav_request(
+            "GET",
+            &format!("/remote.php/webdav/{}/big.log", CLOUD_FOLDER),
+        )
+        .header("Accept-Encoding", "gzip")
+        .send()
+        .await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("content-encoding"), Some("gzip"));
+    let mut unpacked = String::new();
+    flate2::read::GzDecoder::new(response.body.as_slice())
+        .read_to_string(&mut unpacked)
+        .unwrap();
+    assert_eq!(unpacked, text);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn compressed_media_ranges_and_tiny_bodies_are_sent_as_they_are() {
+    let server = TestServer::start_with(|cloud| cloud.nextcloud_compat = true).await;
+    std::fs::write(server.file("photo.jpg"), vec![0u8; 64 * 1024]).unwrap();
+    std::fs::write(server.file("big.log"), large_text()).unwrap();
+    std::fs::write(server.file("tiny.txt"), "hello").unwrap();
+
+    let response = server
+        .request("GET", &static_path("photo.jpg"))
+        .header("Accept-Encoding", "gzip, zstd")
+        .send()
+        .await;
+    assert_eq!(response.status, 200);
+    assert!(response.header("content-encoding").is_none());
+
+    let response = server
+        .dav_request(
+            "GET",
+            &format!("/remote.php/webdav/{}/big.log", CLOUD_FOLDER),
+        )
+        .header("Accept-Encoding", "gzip")
+        .header("Range", "bytes=0-99")
+        .send()
+        .await;
+    assert_eq!(response.status, 206);
+    assert!(response.header("content-encoding").is_none());
+    assert_eq!(response.body.len(), 100);
+
+    let response = server
+        .request("GET", &static_path("tiny.txt"))
+        .header("Accept-Encoding", "gzip")
+        .send()
+        .await;
+    assert!(response.header("content-encoding").is_none());
+    assert_eq!(response.text(), "hello");
+
+    server.stop().await;
+}
@@ -0,0 +1,58 @@
+//! `--config-dir`: a bootstrap file in the default config directory points at another one,
+//! and the configs and data can be moved there.
+
+mod common;
+
+use cloudhost_server::config_paths;
+use common::orchestrator::TestOrchestrator;
+use common::TempDir;
+
+#[tokio::test]
+async fn chosen_config_dir_is_remembered_and_can_take_the_files() {
+    // Holds the turn on the environment; the default directory is under its XDG_DATA_HOME
+    let harness = TestOrchestrator::new().await;
+    std::env::remove_var(config_paths::CONFIG_DIR_VAR);
+    std::env::set_var("CLOUDHOST_DEV", "0");
+    let default_dir = harness.config_dir.path().join("CloudHost");
+    assert_eq!(config_paths::get_config_dir(), default_dir);
+
+    std::fs::create_dir_all(default_dir.join("logs")).unwrap();
+    std::fs::write(default_dir.join("logs").join("home.log"), "{}\n").unwrap();
+    std::fs::write(default_dir.join("clouds-config.toml"), "clouds = []\n").unwrap();
+    std::fs::write(default_dir.join("notes.txt"), "not ours").unwrap();
+
+    let synced = TempDir::new("synced");
+    // The new directory's own config is kept
+    std::fs::write(synced.path().join("tui-config.toml"), "").unwrap();
+    std::fs::write(default_dir.join("tui-config.toml"), "").unwrap();
+    assert_eq!(
+        config_paths::movable_config_entries(&default_dir, synced.path()),
+        ["clouds-config.toml", "logs"]
+    );
+    let moved = config_paths::move_config_entries(&default_dir, synced.path()).unwrap();
+    assert_eq!(moved, ["clouds-config.toml", "logs"]);
+    assert!(synced.path().join("logs").join("home.log").is_file());
+    assert!(!default_dir.join("clouds-config.toml").exists());
+    assert!(default_dir.join("tui-config.toml").exists());
+    assert!(default_dir.join("notes.txt").exists());
+
+    config_paths::set_config_dir(synced.path()).unwrap();
+    assert!(config_paths::get_bootstrap_path().starts_with(&default_dir));
+    assert_eq!(config_paths::get_config_dir(), synced.path());
+    assert_eq!(
+        config_paths::get_clouds_config_path(),
+        synced.path().join("clouds-config.toml")
+    );
+    // The environment still wins
+    std::env::set_var(config_paths::CONFIG_DIR_VAR, harness.config_dir.path());
+    assert_eq!(config_paths::get_config_dir(), harness.config_dir.path());
+    std::env::remove_var(config_paths::CONFIG_DIR_VAR);
+
+    // Choosing the default directory again forgets the choice
+    config_paths::set_config_dir(&default_dir).unwrap();
+    assert!(!config_paths::get_bootstrap_path().exists());
+    assert_eq!(config_paths::get_config_dir(), default_dir);
+
+    std::env::remove_var("CLOUDHOST_DEV");
+    std::env::set_var(config_paths::CONFIG_DIR_VAR, harness.config_dir.path());
+}
@@ -0,0 +1,117 @@
+//! `schema_version` of the config files: older files are upgraded step by step, newer
+//! ones are refused.
+
+mod common;
+
+use cloudhost_server::clouds_config::CLOUDS_SCHEMA_VERSION;
+use cloudhost_server::config_migration::{backup_before_migration, migrate, Migration};
+use cloudhost_server::CloudsConfig;
+use common::orchestrator::TestOrchestrator;
+use common::TempDir;
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        to: 2,
+        description: "port is now ports",
+        apply: |table| {
+            if let Some(port) = table.remove("port") {
+                table.insert("ports".to_string(), toml::Value::Array(vec![port]));
+            }
+            Ok(())
+        },
+    },
+    Migration {
+        to: 3,
+        description: "ports must not be empty",
+        apply: |table| match table.get("ports").and_then(|ports| ports.as_array()) {
+            Some(ports) if ports.is_empty() => Err("no ports".to_string()),
+            _ => Ok(()),
+        },
+    },
+];
+
+#[test]
+fn older_files_go_through_every_later_step() {
+    let migrated = migrate("test.toml", "port = 3000\n", 3, MIGRATIONS)
+        .unwrap()
+        .expect("upgraded");
+    assert_eq!((migrated.from, migrated.to), (1, 3));
+    assert_eq!(
+        migrated.steps,
+        ["port is now ports", "ports must not be empty"]
+    );
+    let table: toml::Table = migrated.content.parse().unwrap();
+    assert_eq!(table["schema_version"].as_integer(), Some(3));
+    assert_eq!(table["ports"].as_array().unwrap().len(), 1);
+
+    // Only the steps after the file's version
+    let migrated = migrate(
+        "test.toml",
+        "schema_version = 2\nports = []\nport = 1\n",
+        2,
+        MIGRATIONS,
+    )
+    .unwrap();
+    assert_eq!(migrated, None);
+    let error = migrate(
+        "test.toml",
+        "schema_version = 2\nports = []\n",
+        3,
+        MIGRATIONS,
+    )
+    .unwrap_err();
+    assert!(
+        error.contains("upgrading to schema_version 3 failed: no ports"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn newer_and_broken_versions_are_refused() {
+    let error = migrate("test.toml", "schema_version = 4\n", 3, MIGRATIONS).unwrap_err();
+    assert!(error.contains("reads up to 3"), "{}", error);
+    let error = migrate("test.toml", "schema_version = \"two\"\n", 3, MIGRATIONS).unwrap_err();
+    assert!(error.contains("must be a positive number"), "{}", error);
+
+    let error = CloudsConfig::parse(&format!(
+        "schema_version = {}\ncloud_folders = []\nclouds = []\n",
+        CLOUDS_SCHEMA_VERSION + 1
+    ))
+    .unwrap_err()
+    .to_string();
+    assert!(
+        error.contains("clouds-config.toml is schema_version"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn backups_keep_the_oldest_original() {
+    let dir = TempDir::new("migration-backup");
+    let path = dir.path().join("test.toml");
+    std::fs::write(&path, "port = 3000\n").unwrap();
+    let backup = backup_before_migration(&path, 1).unwrap();
+    assert_eq!(backup, dir.path().join("test.toml.v1"));
+
+    std::fs::write(&path, "port = 4000\n").unwrap();
+    backup_before_migration(&path, 1).unwrap();
+    assert_eq!(std::fs::read_to_string(backup).unwrap(), "port = 3000\n");
+}
+
+#[tokio::test]
+async fn saved_clouds_configs_carry_the_version() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents"]);
+    let saved =
+        std::fs::read_to_string(cloudhost_server::config_paths::get_clouds_config_path()).unwrap();
+    assert!(
+        saved.starts_with(&format!("schema_version = {}\n", CLOUDS_SCHEMA_VERSION)),
+        "{}",
+        saved
+    );
+    // Files from before versioning are version 1
+    let config = CloudsConfig::parse("cloud_folders = []\nclouds = []\n").unwrap();
+    assert_eq!(config.schema_version, CLOUDS_SCHEMA_VERSION);
+}
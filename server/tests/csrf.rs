@@ -0,0 +1,99 @@
+//! Double-submit CSRF protection: a state-changing request authenticated by the login
+//! cookie needs the CSRF cookie of a page and the same token in `X-CSRF-Token`.
+
+mod common;
+
+use common::orchestrator::{TestOrchestrator, PASSWORD};
+use common::request;
+
+/// The `name=value` part of the `Set-Cookie` header setting `name`
+fn set_cookie(response: &common::TestResponse, name: &str) -> String {
+    response
+        .headers("set-cookie")
+        .into_iter()
+        .filter_map(|cookie| cookie.split(';').next())
+        .find(|cookie| cookie.starts_with(&format!("{}=", name)))
+        .unwrap_or_else(|| panic!("no {} cookie", name))
+        .to_string()
+}
+
+#[tokio::test]
+async fn cookie_requests_need_the_csrf_token() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents"]);
+    let port = harness.start("home").await;
+    let notes = harness.folder_path("documents").join("notes.txt");
+    std::fs::write(&notes, "hello").unwrap();
+
+    let response = request(port, "POST", "/api/login")
+        .header("Content-Type", "application/json")
+        .body(serde_json::json!({ "password": PASSWORD }).to_string())
+        .send()
+        .await;
+    let login = set_cookie(&response, &format!("auth_token_{}", port));
+
+    // A page hands out the CSRF token, as a cookie and for its scripts
+    let page = request(port, "GET", "/web/documents/files")
+        .header("Cookie", &login)
+        .send()
+        .await;
+    assert_eq!(page.status, 200);
+    let csrf = set_cookie(&page, "csrf_token");
+    let token = csrf.strip_prefix("csrf_token=").unwrap();
+    assert!(page
+        .text()
+        .contains(&format!(r#"<meta name="csrf-token" content="{}">"#, token)));
+
+    let delete = || request(port, "DELETE", "/api/delete/documents/notes.txt");
+    let forged = [
+        delete().header("Cookie", &login),
+        delete().header("Cookie", &format!("{}; {}", login, csrf)),
+        delete()
+            .header("Cookie", &login)
+            .header("X-CSRF-Token", token),
+        delete()
+            .header("Cookie", &format!("{}; {}", login, csrf))
+            .header("X-CSRF-Token", "A".repeat(token.len()).as_str()),
+    ];
+    for forged in forged {
+        let response = forged.send().await;
+        assert_eq!(response.status, 403, "{}", response.text());
+        assert_eq!(response.json()["code"], "CSRF_TOKEN_INVALID");
+        assert!(notes.exists());
+    }
+
+    let response = delete()
+        .header("Cookie", &format!("{}; {}", login, csrf))
+        .header("X-CSRF-Token", token)
+        .send()
+        .await;
+    assert_eq!(response.status, 200, "{}", response.text());
+    assert!(!notes.exists());
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn bearer_and_read_requests_need_no_csrf_token() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents"]);
+    let port = harness.start("home").await;
+    let token = harness.login(port).await;
+    let notes = harness.folder_path("documents").join("notes.txt");
+    std::fs::write(&notes, "hello").unwrap();
+
+    // A login cookie alone reads
+    let response = request(port, "GET", "/api/documents/files")
+        .header("Cookie", &format!("auth_token_{}={}", port, token))
+        .send()
+        .await;
+    assert_eq!(response.status, 200, "{}", response.text());
+
+    // A browser never sends the bearer token on its own
+    let response = request(port, "DELETE", "/api/delete/documents/notes.txt")
+        .bearer(&token)
+        .send()
+        .await;
+    assert_eq!(response.status, 200, "{}", response.text());
+    assert!(!notes.exists());
+    harness.stop().await;
+}
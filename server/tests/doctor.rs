@@ -0,0 +1,204 @@
+//! The doctor's local checks: the clouds config, the cloud folders, ports taken by other
+//! programs and certificate expiry, plus the clock comparison and why a cloud can't be
+//! reached from the internet.
+
+mod common;
+
+use std::net::TcpListener;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use chrono::{TimeZone, Utc};
+use cloudhost_server::doctor::{
+    certificate_expiry, check_clock, expiry_check, unreachable_check, worst, Check, Doctor, Status,
+};
+use common::TempDir;
+
+/// Self-signed, valid until 2126-09-22
+const CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBfzCCASWgAwIBAgIUAKCvA16pg4ewNrjO4DljiurZ+Y4wCgYIKoZIzj0EAwIw
+FDESMBAGA1UEAwwJbG9jYWxob3N0MCAXDTI2MTAxNjE4MTQwNloYDzIxMjYwOTIy
+MTgxNDA2WjAUMRIwEAYDVQQDDAlsb2NhbGhvc3QwWTATBgcqhkjOPQIBBggqhkjO
+PQMBBwNCAATN3YtjgniuIZ95enCp4vrR6RSr3X2g4viCcd9poSsExydFP5cVTGeN
+h+Dz8t2iBxbTltoFwWKaAbvZDq5pBSrWo1MwUTAdBgNVHQ4EFgQUYtwYE9mvVfFS
+9+xG2ZR+ukdxS14wHwYDVR0jBBgwFoAUYtwYE9mvVfFS9+xG2ZR+ukdxS14wDwYD
+VR0TAQH/BAUwAwEB/zAKBggqhkjOPQQDAgNIADBFAiEAnZIbiimVhzuHWPEbLo8T
+Po+P/Ydkf2PoSisSMB0Wz34CIE4D7GilSZcEK+DKfmY70doWo4aKBewnnM9Jj7Wt
+Ru9r
+-----END CERTIFICATE-----
+";
+
+/// A clouds config with one cloud sharing `folder`, written into `dir`
+fn write_config(dir: &Path, folder: &Path, extra: &str) -> std::path::PathBuf {
+    let folder = folder.display();
+    let config = format!(
+        r#"{extra}
+[[cloud_folders]]
+name = "photos"
+folder_path = "{folder}"
+
+[[clouds]]
+name = "family"
+jwt_secret = "secret"
+cloud_folders = [{{ name = "photos", folder_path = "{folder}" }}]
+"#
+    );
+    let path = dir.join("clouds-config.toml");
+    std::fs::write(&path, config).unwrap();
+    path
+}
+
+fn in_area<'a>(checks: &'a [Check], area: &str) -> Vec<&'a Check> {
+    checks.iter().filter(|check| check.area == area).collect()
+}
+
+/// A port nothing listens on, and the one after it
+fn free_port_pair() -> u16 {
+    loop {
+        let port = common::free_port();
+        if port < u16::MAX && TcpListener::bind(("0.0.0.0", port + 1)).is_ok() {
+            return port;
+        }
+    }
+}
+
+#[test]
+fn missing_folder_and_broken_config_are_errors() {
+    let dir = TempDir::new("doctor");
+    let photos = TempDir::new("doctor-photos");
+    let path = write_config(dir.path(), photos.path(), "");
+    let checks = Doctor::new(path.clone())
+        .with_base_port(free_port_pair())
+        .local_checks();
+    assert_eq!(in_area(&checks, "config")[0].status, Status::Ok);
+    assert_eq!(in_area(&checks, "folders")[0].status, Status::Ok);
+
+    let gone = dir.path().join("gone");
+    let path = write_config(dir.path(), &gone, "");
+    let checks = Doctor::new(path.clone()).local_checks();
+    let folder = in_area(&checks, "folders")[0];
+    assert_eq!(folder.status, Status::Error);
+    assert!(
+        folder.message.contains("doesn't exist"),
+        "{}",
+        folder.message
+    );
+    assert!(folder.hint.is_some());
+    assert_eq!(worst(&checks), Status::Error);
+
+    // Nothing else can be checked without a config
+    std::fs::write(&path, "[[cloud_folders]]\nname = ").unwrap();
+    let checks = Doctor::new(path).local_checks();
+    assert_eq!(checks.len(), 1);
+    assert_eq!(checks[0].status, Status::Error);
+}
+
+#[test]
+fn ports_taken_by_other_programs() {
+    let dir = TempDir::new("doctor");
+    let photos = TempDir::new("doctor-photos");
+    let port = free_port_pair();
+    let _other_program = TcpListener::bind(("0.0.0.0", port)).unwrap();
+
+    // The next port is free to skip to
+    let path = write_config(dir.path(), photos.path(), "port_retries = 1");
+    let checks = Doctor::new(path).with_base_port(port).local_checks();
+    let ports = in_area(&checks, "ports");
+    assert_eq!(ports[0].status, Status::Warning);
+    assert!(ports[0].message.contains(&port.to_string()));
+
+    // No skipping allowed
+    let path = write_config(dir.path(), photos.path(), "port_retries = 0");
+    let checks = Doctor::new(path.clone())
+        .with_base_port(port)
+        .local_checks();
+    assert_eq!(in_area(&checks, "ports")[0].status, Status::Error);
+
+    // The clouds of this process don't count as taken
+    let checks = Doctor::new(path)
+        .with_base_port(port)
+        .with_own_ports([port])
+        .local_checks();
+    assert_eq!(in_area(&checks, "ports")[0].status, Status::Ok);
+}
+
+#[test]
+fn certificate_expiry_is_read_and_judged() {
+    let dir = TempDir::new("doctor");
+    let cert = dir.path().join("cert.pem");
+    std::fs::write(&cert, CERTIFICATE).unwrap();
+    let expiry = certificate_expiry(&cert).unwrap();
+    assert_eq!(expiry.format("%Y-%m-%d").to_string(), "2126-09-22");
+
+    let now = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+    let days = |days| now + chrono::Duration::days(days);
+    assert_eq!(expiry_check("cert", days(100), now).status, Status::Ok);
+    assert_eq!(expiry_check("cert", days(5), now).status, Status::Warning);
+    assert_eq!(expiry_check("cert", days(-1), now).status, Status::Error);
+
+    let not_pem = dir.path().join("key.pem");
+    std::fs::write(&not_pem, "not a certificate").unwrap();
+    assert!(certificate_expiry(&not_pem).is_err());
+}
+
+#[test]
+fn clock_skew() {
+    let now = SystemTime::now();
+    assert_eq!(check_clock(now, now).status, Status::Ok);
+    let behind = check_clock(now + Duration::from_secs(90), now);
+    assert_eq!(behind.status, Status::Warning);
+    assert!(behind.message.contains("90s behind"), "{}", behind.message);
+    assert_eq!(
+        check_clock(now - Duration::from_secs(3600), now).status,
+        Status::Error
+    );
+}
+
+#[test]
+fn unreachable_cloud_is_explained() {
+    let public = "203.0.113.7:3000".parse().unwrap();
+
+    let firewall = unreachable_check("family", public, Some(public.ip()), true);
+    assert_eq!(firewall.status, Status::Warning);
+    assert!(
+        firewall.message.contains("firewall"),
+        "{}",
+        firewall.message
+    );
+
+    let router = unreachable_check("family", public, "192.168.1.20".parse().ok(), true);
+    assert!(
+        router.message.contains("behind a router"),
+        "{}",
+        router.message
+    );
+    let hint = router.hint.unwrap();
+    assert!(hint.contains("192.168.1.20:3000"), "{}", hint);
+    assert!(!hint.contains("reachability_url"), "{}", hint);
+
+    let shared = unreachable_check("family", public, "100.72.0.5".parse().ok(), true);
+    assert!(
+        shared.message.contains("carrier-grade NAT"),
+        "{}",
+        shared.message
+    );
+
+    // Connecting to the own public IP proves less than asking from outside
+    let from_here = unreachable_check("family", public, "192.168.1.20".parse().ok(), false);
+    assert!(from_here.hint.unwrap().contains("reachability_url"));
+}
+
+#[test]
+fn reachability_url_needs_the_port() {
+    let dir = TempDir::new("doctor");
+    let photos = TempDir::new("doctor-photos");
+    let path = write_config(
+        dir.path(),
+        photos.path(),
+        r#"reachability_url = "https://check.example.com/""#,
+    );
+    let checks = Doctor::new(path).local_checks();
+    let config = in_area(&checks, "config")[0];
+    assert_eq!(config.status, Status::Error);
+    assert!(config.message.contains("{port}"), "{}", config.message);
+}
@@ -0,0 +1,85 @@
+//! `PATCH /api/file`: appends and byte-range writes go through the same disk space
+//! checks as whole uploads.
+
+mod common;
+
+use common::{TestServer, CLOUD_FOLDER};
+
+fn patch_url(query: &str) -> String {
+    format!("/api/file/{}/log.txt?{}", CLOUD_FOLDER, query)
+}
+
+#[tokio::test]
+async fn files_are_appended_to_and_overwritten_in_place() {
+    let server = TestServer::start().await;
+    std::fs::write(server.file("log.txt"), "one").unwrap();
+
+    let response = server
+        .request("PATCH", &patch_url("append=true"))
+        .body(" two")
+        .send()
+        .await;
+    assert_eq!(response.status, 200, "{}", response.text());
+    assert_eq!(response.json()["size"], 7);
+    let response = server
+        .request("PATCH", &patch_url("offset=4&truncate=true"))
+        .body("2")
+        .send()
+        .await;
+    assert_eq!(response.status, 200, "{}", response.text());
+    assert_eq!(
+        std::fs::read_to_string(server.file("log.txt")).unwrap(),
+        "one 2"
+    );
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn patches_stop_at_the_upload_limit() {
+    let server = TestServer::start_with(|cloud| cloud.disk_space.max_upload_mb = 1).await;
+    std::fs::write(server.file("log.txt"), vec![b'x'; 1024 * 1024 - 10]).unwrap();
+
+    // The limit is on the patched file, not on the body
+    let response = server
+        .request("PATCH", &patch_url("append=true"))
+        .body(vec![b'y'; 20])
+        .send()
+        .await;
+    assert_eq!(response.status, 413, "{}", response.text());
+    assert_eq!(response.json()["code"], "PAYLOAD_TOO_LARGE");
+    assert_eq!(
+        std::fs::metadata(server.file("log.txt")).unwrap().len(),
+        1024 * 1024 - 10
+    );
+    let response = server
+        .request("PATCH", &patch_url("offset=0"))
+        .body(vec![b'y'; 20])
+        .send()
+        .await;
+    assert_eq!(response.status, 200, "{}", response.text());
+    // Nothing of the refused patch is left behind
+    assert_eq!(std::fs::read_dir(server.file("")).unwrap().count(), 1);
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn patches_are_refused_when_the_disk_is_almost_full() {
+    let server = TestServer::start_with(|cloud| {
+        cloud.disk_space.min_free_mb = u64::MAX / (2 * 1024 * 1024);
+        cloud.disk_space.warn_free_mb = cloud.disk_space.min_free_mb;
+    })
+    .await;
+    std::fs::write(server.file("log.txt"), "one").unwrap();
+
+    let response = server
+        .request("PATCH", &patch_url("append=true"))
+        .body(" two")
+        .send()
+        .await;
+    assert_eq!(response.status, 507, "{}", response.text());
+    assert_eq!(
+        std::fs::read_to_string(server.file("log.txt")).unwrap(),
+        "one"
+    );
+    server.stop().await;
+}
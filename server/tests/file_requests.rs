@@ -0,0 +1,147 @@
+//! File requests: anyone with a live `/drop/<id>` link uploads into its directory without
+//! logging in, until the link is full, expires or is deleted.
+
+mod common;
+
+use cloudhost_server::file_requests::FileRequest;
+use common::{request, TestServer, CLOUD_FOLDER};
+
+#[tokio::test]
+async fn live_links_take_uploads_until_full() {
+    let server = TestServer::start().await;
+    std::fs::create_dir(server.file("inbox")).unwrap();
+    let response = server
+        .request("POST", "/api/file-requests")
+        .header("Content-Type", "application/json")
+        .body(
+            serde_json::json!({
+                "name": "Wedding photos",
+                "path": format!("{}/inbox", CLOUD_FOLDER),
+                "max_files": 2,
+                "expires_in_hours": 1,
+            })
+            .to_string(),
+        )
+        .send()
+        .await;
+    assert_eq!(response.status, 201, "{}", response.text());
+    let created = response.json();
+    let url = created["url"].as_str().unwrap().to_string();
+    assert_eq!(url, format!("/drop/{}", created["id"].as_str().unwrap()));
+
+    let page = request(server.port, "GET", &url).send().await;
+    assert_eq!(page.status, 200);
+    assert!(page.text().contains("Wedding photos"));
+    assert!(page.text().contains("2 more files"));
+
+    let response = request(server.port, "POST", &url)
+        .file("first.jpg", b"first photo")
+        .send()
+        .await;
+    assert_eq!(response.status, 200, "{}", response.text());
+    assert_eq!(response.json()["stored"], serde_json::json!(["first.jpg"]));
+    assert_eq!(
+        std::fs::read(server.file("inbox/first.jpg")).unwrap(),
+        b"first photo"
+    );
+    let response = request(server.port, "POST", &url)
+        .file("second.jpg", b"second photo")
+        .send()
+        .await;
+    assert_eq!(response.status, 200, "{}", response.text());
+
+    // Full
+    let response = request(server.port, "POST", &url)
+        .file("third.jpg", b"third photo")
+        .send()
+        .await;
+    assert_eq!(response.status, 410, "{}", response.text());
+    assert_eq!(response.json()["code"], "GONE");
+    assert!(!server.file("inbox/third.jpg").exists());
+    let listing = server.request("GET", "/api/file-requests").send().await;
+    let listed = &listing.json()["file_requests"][0];
+    assert_eq!(listed["uploads"], 2);
+    assert_eq!(listed["open"], false);
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn deleted_links_refuse_uploads() {
+    let server = TestServer::start().await;
+    let response = server
+        .request("POST", "/api/file-requests")
+        .header("Content-Type", "application/json")
+        .body(serde_json::json!({ "name": "Scans", "path": CLOUD_FOLDER }).to_string())
+        .send()
+        .await;
+    let url = response.json()["url"].as_str().unwrap().to_string();
+    let id = response.json()["id"].as_str().unwrap().to_string();
+    let response = request(server.port, "POST", &url)
+        .file("scan.pdf", b"scan")
+        .send()
+        .await;
+    assert_eq!(response.status, 200, "{}", response.text());
+
+    let response = server
+        .request("DELETE", &format!("/api/file-requests/{}", id))
+        .send()
+        .await;
+    assert_eq!(response.status, 200, "{}", response.text());
+    assert_eq!(request(server.port, "GET", &url).send().await.status, 404);
+    let response = request(server.port, "POST", &url)
+        .file("another.pdf", b"scan")
+        .send()
+        .await;
+    assert_eq!(response.status, 404, "{}", response.text());
+    assert_eq!(response.json()["code"], "NOT_FOUND");
+    assert!(server.file("scan.pdf").exists());
+    assert!(!server.file("another.pdf").exists());
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn expired_links_refuse_uploads() {
+    let mut expired = FileRequest::new("Old photos", CLOUD_FOLDER, "");
+    expired.expires_at = Some(chrono::Utc::now() - chrono::Duration::minutes(1));
+    let url = format!("/drop/{}", expired.id);
+    let server = TestServer::start_with(|cloud| cloud.file_requests = vec![expired]).await;
+
+    let page = request(server.port, "GET", &url).send().await;
+    assert_eq!(page.status, 410);
+    assert!(page.text().contains("This link has expired"));
+    let response = request(server.port, "POST", &url)
+        .file("photo.jpg", b"photo")
+        .send()
+        .await;
+    assert_eq!(response.status, 410, "{}", response.text());
+    assert_eq!(response.json()["error"], "This link has expired");
+    assert!(!server.file("photo.jpg").exists());
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn uploads_larger_than_the_cloud_limit_are_refused() {
+    let open = FileRequest::new("Scans", CLOUD_FOLDER, "");
+    let url = format!("/drop/{}", open.id);
+    let server = TestServer::start_with(|cloud| {
+        cloud.file_requests = vec![open];
+        cloud.disk_space.max_upload_mb = 1;
+    })
+    .await;
+
+    let response = request(server.port, "POST", &url)
+        .file("large.bin", &vec![0u8; 1024 * 1024 + 1])
+        .send()
+        .await;
+    assert_eq!(response.status, 413, "{}", response.text());
+    assert_eq!(response.json()["code"], "PAYLOAD_TOO_LARGE");
+    assert!(!server.file("large.bin").exists());
+    // Nor is the partial temp file left behind
+    let leftovers: Vec<_> = std::fs::read_dir(server.file(""))
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(".upload-"))
+        .collect();
+    assert!(leftovers.is_empty(), "{:?}", leftovers);
+    server.stop().await;
+}
@@ -0,0 +1,183 @@
+//! Cloud folders whose path goes away while a cloud serves them, as when a drive is
+//! unplugged: requests to them answer 503 (FTP: 450) until they are back, and webhooks
+//! hear of both.
+
+mod common;
+
+use cloudhost_server::folder_health::check_path;
+use cloudhost_server::ftp::FtpConfig;
+use cloudhost_server::webhooks::{WebhookConfig, WebhookEvent};
+use common::orchestrator::{TestOrchestrator, PASSWORD};
+use common::{free_port, ftp_commands, request, TempDir};
+
+#[test]
+fn paths_that_cannot_be_served_are_described() {
+    let dir = TempDir::new("health");
+    assert!(check_path(dir.path(), false).is_ok());
+
+    let missing = check_path(&dir.path().join("unplugged"), false).unwrap_err();
+    assert!(missing.contains("doesn't exist"), "{}", missing);
+    std::fs::write(dir.path().join("file.txt"), "").unwrap();
+    let file = check_path(&dir.path().join("file.txt"), false).unwrap_err();
+    assert!(file.contains("is not a directory"), "{}", file);
+    // A temp dir isn't a mount point, as if its drive had been unmounted
+    let unmounted = check_path(dir.path(), true).unwrap_err();
+    assert!(
+        unmounted.contains("no longer a mounted drive"),
+        "{}",
+        unmounted
+    );
+}
+
+#[tokio::test]
+async fn unavailable_folders_answer_503_until_they_are_back() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents", "music"]);
+    harness.orchestrator.clouds_config.clouds[0].webhooks = vec![WebhookConfig {
+        url: format!("http://127.0.0.1:{}/hook", free_port()),
+        secret: "a secret of sixteen".to_string(),
+        events: vec![
+            WebhookEvent::FolderUnavailable,
+            WebhookEvent::FolderAvailable,
+        ],
+    }];
+    let port = harness.start("home").await;
+    let token = harness.login(port).await;
+    assert!(harness.orchestrator.check_folder_health().is_empty());
+
+    let documents = harness.folder_path("documents").to_path_buf();
+    let unplugged = documents.with_extension("unplugged");
+    std::fs::rename(&documents, &unplugged).unwrap();
+    let changes = harness.orchestrator.check_folder_health();
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].folder, "documents");
+    assert!(changes[0].problem.is_some());
+    assert_eq!(
+        harness.orchestrator.unavailable_folders("home"),
+        ["documents"]
+    );
+    // Only changes are reported
+    assert!(harness.orchestrator.check_folder_health().is_empty());
+
+    let response = request(port, "GET", "/api/documents/files")
+        .bearer(&token)
+        .send()
+        .await;
+    assert_eq!(response.status, 503);
+    assert_eq!(response.json()["code"], "UNAVAILABLE");
+    let message = response.json()["error"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    assert!(
+        message.contains("Cloud folder 'documents' is unavailable"),
+        "{}",
+        response.text()
+    );
+    // The other folder is still served
+    let response = request(port, "GET", "/api/music/files")
+        .bearer(&token)
+        .send()
+        .await;
+    assert_eq!(response.status, 200);
+
+    std::fs::rename(&unplugged, &documents).unwrap();
+    let changes = harness.orchestrator.check_folder_health();
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].problem, None);
+    let response = request(port, "GET", "/api/documents/files")
+        .bearer(&token)
+        .send()
+        .await;
+    assert_eq!(response.status, 200);
+
+    let events: Vec<WebhookEvent> = harness
+        .orchestrator
+        .webhook_deliveries("home")
+        .unwrap()
+        .iter()
+        .map(|delivery| delivery.event)
+        .collect();
+    assert_eq!(
+        events,
+        [
+            WebhookEvent::FolderAvailable,
+            WebhookEvent::FolderUnavailable
+        ]
+    );
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn webdav_and_ftp_refuse_unavailable_folders() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents", "music"]);
+    let ftp_port = free_port();
+    let cloud = &mut harness.orchestrator.clouds_config.clouds[0];
+    cloud.nextcloud_compat = true;
+    cloud.ftp = Some(FtpConfig {
+        port: ftp_port,
+        passive_ports: None,
+        public_address: None,
+        tls_cert: None,
+        tls_key: None,
+        require_tls: false,
+    });
+    let port = harness.start("home").await;
+    let token = harness.login(port).await;
+    let propfind = |folder: &str| {
+        request(port, "PROPFIND", &format!("/remote.php/webdav/{}/", folder))
+            .basic("home", &token)
+            .header("Depth", "0")
+            .send()
+    };
+    let ftp_cwd = |folder: &str| {
+        let cwd = format!("CWD /{}", folder);
+        async move {
+            let replies = ftp_commands(
+                ftp_port,
+                &["USER home", &format!("PASS {}", PASSWORD), &cwd],
+            )
+            .await;
+            assert!(replies[1].starts_with("230"), "{:?}", replies);
+            replies[2].clone()
+        }
+    };
+
+    let documents = harness.folder_path("documents").to_path_buf();
+    let unplugged = documents.with_extension("unplugged");
+    std::fs::rename(&documents, &unplugged).unwrap();
+    harness.orchestrator.check_folder_health();
+
+    let response = propfind("documents").await;
+    assert_eq!(response.status, 503);
+    assert!(
+        response
+            .text()
+            .contains("Cloud folder 'documents' is unavailable"),
+        "{}",
+        response.text()
+    );
+    let response = request(port, "PUT", "/remote.php/webdav/documents/new.txt")
+        .basic("home", &token)
+        .body("x")
+        .send()
+        .await;
+    assert_eq!(response.status, 503);
+    assert!(!unplugged.join("new.txt").exists());
+    let reply = ftp_cwd("documents").await;
+    assert!(
+        reply.starts_with("450 Cloud folder 'documents' is unavailable"),
+        "{}",
+        reply
+    );
+    // The other folder is still served
+    assert_eq!(propfind("music").await.status, 207);
+    assert!(ftp_cwd("music").await.starts_with("250"));
+
+    std::fs::rename(&unplugged, &documents).unwrap();
+    harness.orchestrator.check_folder_health();
+    assert_eq!(propfind("documents").await.status, 207);
+    assert!(ftp_cwd("documents").await.starts_with("250"));
+    harness.stop().await;
+}
@@ -0,0 +1,431 @@
+//! `[clouds.ftp]`: the FTP listener of a cloud, driven over real control and data
+//! connections. Logins take the cloud password, data connections only come from or go
+//! to the client, uploads follow the cloud's conflict policy and can be resumed, and
+//! nothing outside the cloud folders can be reached.
+
+mod common;
+
+use cloudhost_server::api_routes::upload::OnConflict;
+use cloudhost_server::ftp::FtpConfig;
+use common::orchestrator::{TestOrchestrator, PASSWORD};
+use common::{free_port, ftp_commands, TempDir};
+use std::net::SocketAddr;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpSocket, TcpStream};
+
+/// A logged in control connection
+struct FtpClient {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    write: OwnedWriteHalf,
+}
+
+impl FtpClient {
+    async fn login(port: u16) -> Self {
+        let stream = TcpStream::connect(("127.0.0.1", port))
+            .await
+            .expect("connect to FTP");
+        let (read, write) = stream.into_split();
+        let mut client = Self {
+            lines: BufReader::new(read).lines(),
+            write,
+        };
+        assert!(client.reply().await.starts_with("220"));
+        client.command("USER scanner").await;
+        assert_eq!(
+            client.command(&format!("PASS {}", PASSWORD)).await,
+            "230 Logged in"
+        );
+        client
+    }
+
+    /// The first line of the next reply; the rest of a multi-line one is skipped
+    async fn reply(&mut self) -> String {
+        let first = self.lines.next_line().await.unwrap().expect("reply");
+        if first.as_bytes().get(3) == Some(&b'-') {
+            let end = format!("{} ", &first[..3]);
+            while let Some(line) = self.lines.next_line().await.unwrap() {
+                if line.starts_with(&end) {
+                    break;
+                }
+            }
+        }
+        first
+    }
+
+    async fn command(&mut self, command: &str) -> String {
+        self.write
+            .write_all(format!("{}\r\n", command).as_bytes())
+            .await
+            .unwrap();
+        self.reply().await
+    }
+
+    /// PASV, returning the announced data address
+    async fn passive(&mut self) -> SocketAddr {
+        let answer = self.command("PASV").await;
+        assert!(answer.starts_with("227"), "{}", answer);
+        let numbers: Vec<u16> = answer[answer.find('(').unwrap() + 1..answer.find(')').unwrap()]
+            .split(',')
+            .map(|number| number.parse().unwrap())
+            .collect();
+        SocketAddr::from((
+            [
+                numbers[0] as u8,
+                numbers[1] as u8,
+                numbers[2] as u8,
+                numbers[3] as u8,
+            ],
+            numbers[4] << 8 | numbers[5],
+        ))
+    }
+
+    /// STOR or APPE `contents`; returns the final reply
+    async fn upload(&mut self, command: &str, contents: &[u8]) -> String {
+        let address = self.passive().await;
+        let mut data = TcpStream::connect(address).await.unwrap();
+        let answer = self.command(command).await;
+        if !answer.starts_with("150") {
+            return answer;
+        }
+        data.write_all(contents).await.unwrap();
+        data.shutdown().await.unwrap();
+        drop(data);
+        self.reply().await
+    }
+
+    /// RETR `path`: the contents and the final reply
+    async fn download(&mut self, command: &str) -> (Vec<u8>, String) {
+        let address = self.passive().await;
+        let mut data = TcpStream::connect(address).await.unwrap();
+        let answer = self.command(command).await;
+        if !answer.starts_with("150") {
+            return (Vec::new(), answer);
+        }
+        let mut contents = Vec::new();
+        data.read_to_end(&mut contents).await.unwrap();
+        (contents, self.reply().await)
+    }
+}
+
+fn ftp_config(port: u16) -> FtpConfig {
+    FtpConfig {
+        port,
+        passive_ports: None,
+        public_address: None,
+        tls_cert: None,
+        tls_key: None,
+        require_tls: false,
+    }
+}
+
+/// A cloud "home" with the folder "documents" and FTP on the returned port
+async fn start_with_ftp(harness: &mut TestOrchestrator, upload_conflict: OnConflict) -> u16 {
+    harness.add_cloud("home", &["documents"]);
+    let ftp_port = free_port();
+    let cloud = &mut harness.orchestrator.clouds_config.clouds[0];
+    cloud.ftp = Some(ftp_config(ftp_port));
+    cloud.upload_conflict = upload_conflict;
+    cloud.guest_access = true;
+    harness.start("home").await;
+    ftp_port
+}
+
+#[tokio::test]
+async fn logins_take_the_cloud_password() {
+    let mut harness = TestOrchestrator::new().await;
+    let port = start_with_ftp(&mut harness, OnConflict::Rename).await;
+
+    let replies = ftp_commands(
+        port,
+        &[
+            "PWD",
+            "PASS secret",
+            "USER scanner",
+            "PASS wrong",
+            "PWD",
+            "USER scanner",
+            &format!("PASS {}", PASSWORD),
+            "PWD",
+        ],
+    )
+    .await;
+    assert_eq!(
+        replies,
+        [
+            "530 Not logged in",
+            "503 Send USER first",
+            "331 Password required",
+            "530 Login incorrect",
+            "530 Not logged in",
+            "331 Password required",
+            "230 Logged in",
+            "257 \"/\" is the current directory",
+        ]
+    );
+
+    // Guest access opens the web pages, not FTP
+    let replies = ftp_commands(port, &["USER anonymous", "PASS guest@example.com", "LIST"]).await;
+    assert_eq!(replies[1], "530 Login incorrect");
+    assert_eq!(replies[2], "530 Not logged in");
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn files_are_uploaded_listed_and_downloaded() {
+    let mut harness = TestOrchestrator::new().await;
+    let port = start_with_ftp(&mut harness, OnConflict::Rename).await;
+    let mut client = FtpClient::login(port).await;
+
+    assert_eq!(
+        client.command("CWD documents").await,
+        "250 Directory is now /documents"
+    );
+    assert_eq!(
+        client.upload("STOR scan.pdf", b"scanned").await,
+        "226 Stored as /documents/scan.pdf"
+    );
+    assert_eq!(
+        std::fs::read(harness.folder_path("documents").join("scan.pdf")).unwrap(),
+        b"scanned"
+    );
+    let (listing, answer) = client.download("NLST").await;
+    assert_eq!(answer, "226 Listing sent");
+    assert_eq!(listing, b"scan.pdf\r\n");
+    let (contents, answer) = client.download("RETR scan.pdf").await;
+    assert_eq!(answer, "226 Transfer complete");
+    assert_eq!(contents, b"scanned");
+    assert_eq!(client.command("SIZE scan.pdf").await, "213 7");
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn taken_names_follow_the_upload_conflict_policy() {
+    let mut harness = TestOrchestrator::new().await;
+    let port = start_with_ftp(&mut harness, OnConflict::Rename).await;
+    let documents = harness.folder_path("documents").to_path_buf();
+    std::fs::write(documents.join("scan.pdf"), "first").unwrap();
+
+    let mut client = FtpClient::login(port).await;
+    assert_eq!(
+        client.upload("STOR documents/scan.pdf", b"second").await,
+        "226 Stored as /documents/scan(1).pdf"
+    );
+    assert_eq!(
+        std::fs::read_to_string(documents.join("scan.pdf")).unwrap(),
+        "first"
+    );
+    assert_eq!(
+        std::fs::read_to_string(documents.join("scan(1).pdf")).unwrap(),
+        "second"
+    );
+    harness.stop().await;
+
+    let mut harness = TestOrchestrator::new().await;
+    let port = start_with_ftp(&mut harness, OnConflict::Reject).await;
+    let documents = harness.folder_path("documents").to_path_buf();
+    std::fs::write(documents.join("scan.pdf"), "first").unwrap();
+
+    let mut client = FtpClient::login(port).await;
+    assert_eq!(
+        client.upload("STOR documents/scan.pdf", b"second").await,
+        "553 File exists"
+    );
+    assert_eq!(
+        std::fs::read_to_string(documents.join("scan.pdf")).unwrap(),
+        "first"
+    );
+    // Appending isn't replacing, the policy doesn't stop it
+    assert_eq!(
+        client.upload("APPE documents/scan.pdf", b" and more").await,
+        "226 Stored as /documents/scan.pdf"
+    );
+    assert_eq!(
+        std::fs::read_to_string(documents.join("scan.pdf")).unwrap(),
+        "first and more"
+    );
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn interrupted_transfers_are_resumed() {
+    let mut harness = TestOrchestrator::new().await;
+    let port = start_with_ftp(&mut harness, OnConflict::Reject).await;
+    let documents = harness.folder_path("documents").to_path_buf();
+    std::fs::write(documents.join("video.mp4"), "0123456789").unwrap();
+    let mut client = FtpClient::login(port).await;
+    client.command("CWD documents").await;
+
+    // Downloads go on from the offset
+    assert_eq!(client.command("REST 4").await, "350 Restarting at 4");
+    let (contents, answer) = client.download("RETR video.mp4").await;
+    assert_eq!(answer, "226 Transfer complete");
+    assert_eq!(contents, b"456789");
+    // The offset only applies to the next transfer
+    let (contents, _) = client.download("RETR video.mp4").await;
+    assert_eq!(contents, b"0123456789");
+    assert_eq!(client.command("REST 11").await, "350 Restarting at 11");
+    let (_, answer) = client.download("RETR video.mp4").await;
+    assert_eq!(answer, "554 Restart offset is past the end of the file");
+    assert_eq!(
+        client.command("REST four").await,
+        "501 Expected REST <byte offset>"
+    );
+
+    // Uploads replace what follows the offset
+    client.command("REST 6").await;
+    assert_eq!(
+        client.upload("STOR video.mp4", b"abcdef").await,
+        "226 Stored as /documents/video.mp4"
+    );
+    assert_eq!(
+        std::fs::read_to_string(documents.join("video.mp4")).unwrap(),
+        "012345abcdef"
+    );
+    client.command("REST 20").await;
+    assert_eq!(
+        client.upload("STOR video.mp4", b"late").await,
+        "554 Restart offset is past the end of the file"
+    );
+    client.command("REST 4").await;
+    assert_eq!(
+        client.upload("STOR missing.mp4", b"late").await,
+        "554 Restart offset is past the end of the file"
+    );
+
+    // APPE adds to the end, or starts a new file
+    assert_eq!(
+        client.upload("APPE video.mp4", b"!").await,
+        "226 Stored as /documents/video.mp4"
+    );
+    assert_eq!(
+        std::fs::read_to_string(documents.join("video.mp4")).unwrap(),
+        "012345abcdef!"
+    );
+    client.upload("APPE new.txt", b"new").await;
+    assert_eq!(
+        std::fs::read_to_string(documents.join("new.txt")).unwrap(),
+        "new"
+    );
+    harness.stop().await;
+}
+
+/// Passive data connections must come from the client's own address, and active ones
+/// only go back to it (no FTP bounce)
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn data_connections_only_come_from_the_client() {
+    let mut harness = TestOrchestrator::new().await;
+    let port = start_with_ftp(&mut harness, OnConflict::Rename).await;
+    std::fs::write(harness.folder_path("documents").join("scan.pdf"), "secret").unwrap();
+    let mut client = FtpClient::login(port).await;
+
+    // Linux routes all of 127.0.0.0/8 to the loopback
+    let address = client.passive().await;
+    let socket = TcpSocket::new_v4().unwrap();
+    socket.bind("127.0.0.2:0".parse().unwrap()).unwrap();
+    let mut data = socket.connect(address).await.unwrap();
+    assert!(client
+        .command("RETR documents/scan.pdf")
+        .await
+        .starts_with("150"));
+    assert_eq!(
+        client.reply().await,
+        "425 Data connection came from another address"
+    );
+    let mut contents = Vec::new();
+    let _ = data.read_to_end(&mut contents).await;
+    assert!(contents.is_empty());
+
+    assert_eq!(
+        client.command("PORT 10,0,0,1,200,10").await,
+        "504 Data connections only go to the client, port 1024 and up"
+    );
+    assert_eq!(
+        client.command("PORT 127,0,0,1,0,22").await,
+        "504 Data connections only go to the client, port 1024 and up"
+    );
+    assert_eq!(
+        client.command("EPRT |1|10.0.0.1|50000|").await,
+        "504 Data connections only go to the client, port 1024 and up"
+    );
+    // The client's own address is fine
+    assert_eq!(
+        client.command("PORT 127,0,0,1,200,10").await,
+        "200 Active mode set"
+    );
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn paths_cannot_leave_the_cloud_folders() {
+    let mut harness = TestOrchestrator::new().await;
+    let port = start_with_ftp(&mut harness, OnConflict::Rename).await;
+    let mut client = FtpClient::login(port).await;
+
+    // `..` stops at the root, where only the cloud folders are
+    assert_eq!(
+        client.command("CWD documents/../../..").await,
+        "250 Directory is now /"
+    );
+    let (_, answer) = client.download("RETR ../../etc/passwd").await;
+    assert!(answer.starts_with("550"), "{}", answer);
+    assert!(client
+        .command("CWD /documents/../../tmp")
+        .await
+        .starts_with("550"));
+    assert_eq!(
+        client
+            .upload("STOR documents/../../escaped.txt", b"escaped")
+            .await,
+        "550 No cloud folder 'escaped.txt'"
+    );
+    let documents = harness.folder_path("documents");
+    assert!(!documents.parent().unwrap().join("escaped.txt").exists());
+    harness.stop().await;
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn symlinks_out_of_the_cloud_folders_are_not_followed() {
+    let mut harness = TestOrchestrator::new().await;
+    let port = start_with_ftp(&mut harness, OnConflict::Rename).await;
+    let outside = TempDir::new("outside");
+    std::fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+    let documents = harness.folder_path("documents").to_path_buf();
+    std::os::unix::fs::symlink(outside.path(), documents.join("linked")).unwrap();
+    std::os::unix::fs::symlink(
+        outside.path().join("secret.txt"),
+        documents.join("secret.txt"),
+    )
+    .unwrap();
+    let mut client = FtpClient::login(port).await;
+
+    for command in [
+        "RETR documents/secret.txt",
+        "RETR documents/linked/secret.txt",
+        "LIST documents/linked",
+    ] {
+        let (contents, answer) = client.download(command).await;
+        assert!(answer.starts_with("550"), "{}: {}", command, answer);
+        assert!(contents.is_empty());
+    }
+    for command in [
+        "CWD documents/linked",
+        "SIZE documents/linked/secret.txt",
+        "DELE documents/linked/secret.txt",
+        "MKD documents/linked/new",
+    ] {
+        let answer = client.command(command).await;
+        assert!(answer.starts_with("550"), "{}: {}", command, answer);
+    }
+    let answer = client
+        .upload("STOR documents/linked/planted.txt", b"planted")
+        .await;
+    assert!(answer.starts_with("550"), "{}", answer);
+
+    assert!(outside.path().join("secret.txt").exists());
+    assert!(!outside.path().join("planted.txt").exists());
+    assert!(!outside.path().join("new").exists());
+    harness.stop().await;
+}
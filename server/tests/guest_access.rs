@@ -0,0 +1,69 @@
+//! `guest_access`: visitors without a login can browse the cloud folders, and nothing else,
+//! even when a folder shares its name with one of the fixed `/api/<name>` routes.
+
+mod common;
+
+use common::orchestrator::TestOrchestrator;
+use common::request;
+
+#[tokio::test]
+async fn guests_only_read_the_cloud_folders() {
+    let mut harness = TestOrchestrator::new().await;
+    let names = [
+        "documents",
+        "tokens",
+        "file-requests",
+        "clients",
+        "stats",
+        "usage",
+        "metrics",
+    ];
+    harness.add_cloud("home", &names);
+    harness.orchestrator.clouds_config.clouds[0].guest_access = true;
+    let port = harness.start("home").await;
+    let token = harness.login(port).await;
+    std::fs::write(harness.folder_path("documents").join("notes.txt"), "hello").unwrap();
+
+    // Something worth hiding
+    let response = request(port, "POST", "/api/tokens")
+        .bearer(&token)
+        .header("Content-Type", "application/json")
+        .body(r#"{"name": "backup"}"#)
+        .send()
+        .await;
+    assert_eq!(response.status, 201, "{}", response.text());
+    let response = request(port, "POST", "/api/file-requests")
+        .bearer(&token)
+        .header("Content-Type", "application/json")
+        .body(r#"{"name": "drop", "path": "documents"}"#)
+        .send()
+        .await;
+    assert_eq!(response.status, 201, "{}", response.text());
+
+    for path in [
+        "/api",
+        "/api/documents",
+        "/api/documents/files",
+        "/api/documents/static/notes.txt",
+        "/web/documents/files",
+    ] {
+        let response = request(port, "GET", path).send().await;
+        assert_eq!(response.status, 200, "{}: {}", path, response.text());
+    }
+    for name in &names[1..] {
+        let path = format!("/api/{}", name);
+        let response = request(port, "GET", &path).send().await;
+        assert_eq!(response.status, 401, "{}: {}", path, response.text());
+        assert!(!response.text().contains("backup"), "{}", response.text());
+        assert!(!response.text().contains("/drop/"), "{}", response.text());
+        // The owner still gets them
+        let response = request(port, "GET", &path).bearer(&token).send().await;
+        assert_eq!(response.status, 200, "{}: {}", path, response.text());
+    }
+    // Guests never write
+    let response = request(port, "DELETE", "/api/delete/documents/notes.txt")
+        .send()
+        .await;
+    assert_eq!(response.status, 401);
+    harness.stop().await;
+}
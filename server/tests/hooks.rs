@@ -0,0 +1,115 @@
+//! Folder hooks: `pre_upload` gets the received file, whichever way it came, so it can
+//! check what's in it before the upload is stored.
+#![cfg(unix)]
+
+mod common;
+
+use cloudhost_server::file_requests::FileRequest;
+use cloudhost_server::hooks::FolderHooks;
+use common::{request, TestServer, CLOUD_FOLDER};
+
+/// Refuses uploads that say "virus", and any it can't read
+const SCANNER: &str = r#"test -s "$CLOUDHOST_FILE" && ! grep -q virus "$CLOUDHOST_FILE""#;
+
+async fn start_scanned() -> (TestServer, String) {
+    let drop_link = FileRequest::new("Inbox", CLOUD_FOLDER, "");
+    let url = format!("/drop/{}", drop_link.id);
+    let server = TestServer::start_with(|cloud| {
+        cloud.nextcloud_compat = true;
+        cloud.file_requests = vec![drop_link];
+        cloud.cloud_folders[0].hooks = Some(FolderHooks {
+            pre_upload: Some(SCANNER.to_string()),
+            post_upload: None,
+            post_delete: None,
+            timeout_seconds: 10,
+        });
+    })
+    .await;
+    (server, url)
+}
+
+#[tokio::test]
+async fn api_uploads_are_scanned() {
+    let (server, _) = start_scanned().await;
+    let upload = |name: &str, contents: &[u8]| {
+        server
+            .request("POST", &format!("/api/upload/{}", CLOUD_FOLDER))
+            .file(name, contents)
+            .send()
+    };
+
+    let response = upload("clean.txt", b"clean").await;
+    assert_eq!(response.status, 200, "{}", response.text());
+    let response = upload("infected.txt", b"a virus").await;
+    assert_eq!(response.status, 403, "{}", response.text());
+    assert!(server.file("clean.txt").exists());
+    assert!(!server.file("infected.txt").exists());
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn drop_link_uploads_are_scanned() {
+    let (server, url) = start_scanned().await;
+
+    let response = request(server.port, "POST", &url)
+        .file("clean.txt", b"clean")
+        .send()
+        .await;
+    assert_eq!(response.status, 200, "{}", response.text());
+    let response = request(server.port, "POST", &url)
+        .file("infected.txt", b"a virus")
+        .send()
+        .await;
+    assert_eq!(response.status, 403, "{}", response.text());
+    assert!(server.file("clean.txt").exists());
+    assert!(!server.file("infected.txt").exists());
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn webdav_uploads_are_scanned() {
+    let (server, _) = start_scanned().await;
+    let put = |name: &str, contents: &'static [u8]| {
+        server
+            .dav_request(
+                "PUT",
+                &format!("/remote.php/webdav/{}/{}", CLOUD_FOLDER, name),
+            )
+            .body(contents)
+            .send()
+    };
+
+    let response = put("clean.txt", b"clean").await;
+    assert_eq!(response.status, 201, "{}", response.text());
+    let response = put("infected.txt", b"a virus").await;
+    assert_eq!(response.status, 403, "{}", response.text());
+    assert!(server.file("clean.txt").exists());
+    assert!(!server.file("infected.txt").exists());
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn patched_files_are_scanned() {
+    let (server, _) = start_scanned().await;
+    std::fs::write(server.file("notes.txt"), "clean").unwrap();
+    let append = |contents: &'static [u8]| {
+        server
+            .request(
+                "PATCH",
+                &format!("/api/file/{}/notes.txt?append=true", CLOUD_FOLDER),
+            )
+            .body(contents)
+            .send()
+    };
+
+    let response = append(b" and tidy").await;
+    assert_eq!(response.status, 200, "{}", response.text());
+    // The hook sees the whole patched file, and the file stays as it was if it refuses
+    let response = append(b", with a virus").await;
+    assert_eq!(response.status, 403, "{}", response.text());
+    assert_eq!(
+        std::fs::read_to_string(server.file("notes.txt")).unwrap(),
+        "clean and tidy"
+    );
+    server.stop().await;
+}
@@ -0,0 +1,68 @@
+//! Pages built from file names: a name can hold markup, quotes and URL syntax, and must
+//! show up as text, never as part of the page.
+
+mod common;
+
+use common::{TestServer, CLOUD_FOLDER};
+
+const SCRIPT_NAME: &str = "<img src=x onerror=alert(1)>.txt";
+const QUOTED_NAME: &str = r#"a"b'c&d ?#.txt"#;
+
+#[tokio::test]
+async fn file_names_are_escaped_in_listings() {
+    let server = TestServer::start().await;
+    std::fs::write(server.file(SCRIPT_NAME), "").unwrap();
+    std::fs::write(server.file(QUOTED_NAME), "").unwrap();
+    std::fs::create_dir(server.file("<b>bold")).unwrap();
+
+    let response = server
+        .request("GET", &format!("/web/{}/files", CLOUD_FOLDER))
+        .send()
+        .await;
+    assert_eq!(response.status, 200);
+    let page = response.text();
+    assert!(!page.contains("<img src=x"), "{}", page);
+    assert!(!page.contains("<b>bold"), "{}", page);
+    assert!(page.contains("&lt;img src=x onerror=alert(1)&gt;.txt"));
+    assert!(page.contains("a&quot;b&#39;c&amp;d ?#.txt"));
+    // Links carry the names percent-encoded
+    assert!(page.contains(&format!(
+        r#"href="/api/{}/static/%3Cimg%20src%3Dx%20onerror%3Dalert%281%29%3E.txt""#,
+        CLOUD_FOLDER
+    )));
+    assert!(page.contains("%22b%27c%26d%20%3F%23.txt"));
+    assert!(page.contains(&format!(
+        r#"href="/web/{}/files/%3Cb%3Ebold""#,
+        CLOUD_FOLDER
+    )));
+
+    // The directory's own page, with the name in its title and breadcrumb
+    let response = server
+        .request("GET", &format!("/web/{}/files/%3Cb%3Ebold", CLOUD_FOLDER))
+        .send()
+        .await;
+    assert_eq!(response.status, 200);
+    let page = response.text();
+    assert!(!page.contains("<b>bold"), "{}", page);
+    assert!(page.contains("📁 &lt;b&gt;bold"));
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn links_from_listings_lead_to_the_file() {
+    let server = TestServer::start().await;
+    std::fs::write(server.file(QUOTED_NAME), "contents").unwrap();
+
+    let response = server
+        .request(
+            "GET",
+            &format!("/api/{}/static/a%22b%27c%26d%20%3F%23.txt", CLOUD_FOLDER),
+        )
+        .send()
+        .await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.text(), "contents");
+
+    server.stop().await;
+}
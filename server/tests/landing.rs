@@ -0,0 +1,72 @@
+//! The landing page lists clouds that let guests in to anyone, and the others only to
+//! visitors logged in to them, with links in the scheme the page was reached with.
+
+mod common;
+
+use common::orchestrator::{TestOrchestrator, PASSWORD};
+use common::{free_port, request};
+
+#[tokio::test]
+async fn visitors_only_see_guest_clouds_and_their_own() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("public", &["shared"]);
+    harness.add_cloud("private", &["documents"]);
+    harness.orchestrator.clouds_config.clouds[0].guest_access = true;
+    let landing_port = free_port();
+    harness.orchestrator.clouds_config.landing_port = Some(landing_port);
+    let public_port = harness.start("public").await;
+    let private_port = harness.start("private").await;
+    harness.orchestrator.start_landing().await.unwrap();
+    harness.orchestrator.refresh_landing();
+
+    let page = request(landing_port, "GET", "/").send().await;
+    assert_eq!(page.status, 200);
+    let page = page.text();
+    assert!(page.contains("public"), "{}", page);
+    assert!(!page.contains("private"), "{}", page);
+    assert!(
+        page.contains(&format!(r#"href="http://127.0.0.1:{}/login""#, public_port)),
+        "{}",
+        page
+    );
+
+    // Through a TLS proxy
+    let page = request(landing_port, "GET", "/")
+        .header("X-Forwarded-Proto", "https")
+        .send()
+        .await
+        .text();
+    assert!(
+        page.contains(&format!(
+            r#"href="https://127.0.0.1:{}/login""#,
+            public_port
+        )),
+        "{}",
+        page
+    );
+    assert!(!page.contains("http://"), "{}", page);
+
+    // Logged in to the private cloud, whose cookie the landing page sees on the same host
+    let response = request(private_port, "POST", "/api/login")
+        .header("Content-Type", "application/json")
+        .body(serde_json::json!({ "password": PASSWORD }).to_string())
+        .send()
+        .await;
+    let cookie = response
+        .header("set-cookie")
+        .and_then(|cookie| cookie.split(';').next())
+        .expect("login cookie")
+        .to_string();
+    let page = request(landing_port, "GET", "/")
+        .header("Cookie", &cookie)
+        .send()
+        .await
+        .text();
+    assert!(page.contains("private"), "{}", page);
+    assert!(
+        page.contains(&format!(r#"href="http://127.0.0.1:{}/""#, private_port)),
+        "{}",
+        page
+    );
+    harness.stop().await;
+}
@@ -0,0 +1,126 @@
+//! Load checks for the route handlers: many concurrent requests against one cloud, and a
+//! file read that blocks while other requests go on. The test runtime has a single
+//! thread, which server and client share, so a handler doing file system access on it
+//! would stall every other request; with a read that never returns, the test hangs.
+
+mod common;
+
+use common::{TestServer, CLOUD_FOLDER};
+use futures_util::future::join_all;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[tokio::test]
+async fn concurrent_listings_and_downloads() {
+    let server = TestServer::start().await;
+    std::fs::create_dir(server.file("many")).unwrap();
+    for i in 0..2000 {
+        std::fs::write(server.file(&format!("many/file-{:04}.txt", i)), "content").unwrap();
+    }
+    std::fs::write(server.file("big.bin"), vec![7u8; 4 * 1024 * 1024]).unwrap();
+
+    let listing = format!("/api/{}/files/many", CLOUD_FOLDER);
+    let download = format!("/api/{}/static/big.bin", CLOUD_FOLDER);
+    let requests = (0..64).map(|i| {
+        let path = if i % 2 == 0 { &listing } else { &download };
+        server.request("GET", path).send()
+    });
+    let responses = tokio::time::timeout(TIMEOUT, join_all(requests))
+        .await
+        .expect("requests finish");
+
+    for (i, response) in responses.iter().enumerate() {
+        assert_eq!(response.status, 200);
+        if i % 2 == 0 {
+            assert_eq!(response.json()["items"].as_array().unwrap().len(), 2000);
+        } else {
+            assert_eq!(response.body.len(), 4 * 1024 * 1024);
+        }
+    }
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn uploads_larger_than_the_default_body_limit() {
+    let server = TestServer::start().await;
+    // Axum refuses bodies over 2 MB unless a route says otherwise
+    let contents = vec![7u8; 8 * 1024 * 1024];
+    let response = tokio::time::timeout(
+        TIMEOUT,
+        server
+            .request("POST", &format!("/api/upload/{}", CLOUD_FOLDER))
+            .file("big.bin", &contents)
+            .send(),
+    )
+    .await
+    .expect("upload finishes");
+
+    assert_eq!(response.status, 200, "{}", response.text());
+    assert_eq!(std::fs::read(server.file("big.bin")).unwrap(), contents);
+
+    // Streamed to a temporary file, then moved next to the first one
+    let response = server
+        .request("POST", &format!("/api/upload/{}", CLOUD_FOLDER))
+        .file("big.bin", &contents)
+        .send()
+        .await;
+    assert_eq!(response.json()["filename"], "big(1).bin");
+    assert_eq!(response.json()["conflict"], "renamed");
+    let mut names: Vec<_> = std::fs::read_dir(server.file(""))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["big(1).bin", "big.bin"]);
+
+    server.stop().await;
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn blocked_read_does_not_stall_other_requests() {
+    let server = TestServer::start().await;
+    std::fs::write(server.file("note.txt"), "hello").unwrap();
+    // Opening a FIFO for reading blocks until someone opens it for writing
+    let fifo = server.file("pipe");
+    let created = std::process::Command::new("mkfifo")
+        .arg(&fifo)
+        .status()
+        .is_ok_and(|status| status.success());
+    if !created {
+        eprintln!("mkfifo is not available, skipping");
+        return;
+    }
+
+    let blocked_path = format!("/api/{}/static/pipe", CLOUD_FOLDER);
+    let blocked = server.request("GET", &blocked_path).send();
+    let others = async {
+        // Let the blocked request reach its handler first
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let listing = server
+            .request("GET", &format!("/api/{}/files", CLOUD_FOLDER))
+            .send()
+            .await;
+        let file = server
+            .request("GET", &format!("/api/{}/static/note.txt", CLOUD_FOLDER))
+            .send()
+            .await;
+        // Unblock the FIFO read
+        std::fs::write(&fifo, "from the pipe").unwrap();
+        (listing, file)
+    };
+    let (blocked, (listing, file)) =
+        tokio::time::timeout(TIMEOUT, async { tokio::join!(blocked, others) })
+            .await
+            .expect("other requests are served while a read blocks");
+
+    assert_eq!(listing.status, 200);
+    assert_eq!(file.status, 200);
+    assert_eq!(file.text(), "hello");
+    assert_eq!(blocked.status, 200);
+    assert_eq!(blocked.text(), "from the pipe");
+
+    server.stop().await;
+}
@@ -0,0 +1,98 @@
+//! `[clouds.logging]`: a cloud's minimum log level, and levels for single sources.
+
+mod common;
+
+use std::time::Duration;
+
+use cloudhost_server::debug_stream::{LogLevel, LogLevelConfig};
+use cloudhost_server::CloudsConfig;
+use common::orchestrator::TestOrchestrator;
+use common::request;
+
+#[tokio::test]
+async fn sources_log_at_their_own_level() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents"]);
+    let mut cloud = harness.orchestrator.get_cloud("home").unwrap();
+    cloud.logging = LogLevelConfig {
+        level: Some(LogLevel::Error),
+        sources: [("http".to_string(), LogLevel::Info)].into(),
+    };
+    harness.orchestrator.update_cloud("home", cloud).unwrap();
+    let port = harness.start("home").await;
+    let mut logs = harness.orchestrator.subscribe_cloud_logs("home").unwrap();
+
+    // A wrong password is a warning from the auth code, which `level` drops
+    let response = request(port, "POST", "/api/login")
+        .header("Content-Type", "application/json")
+        .body(r#"{"password":"wrong password"}"#)
+        .send()
+        .await;
+    assert_eq!(response.status, 401);
+
+    let message = tokio::time::timeout(Duration::from_secs(5), logs.recv())
+        .await
+        .expect("a message is logged")
+        .unwrap();
+    assert_eq!(message.source, "http");
+    assert!(
+        message.message.contains("request finished"),
+        "{}",
+        message.message
+    );
+    // Nothing below error from anywhere else
+    while let Ok(message) = logs.try_recv() {
+        assert!(
+            message.source == "http" || message.level == LogLevel::Error,
+            "{:?}",
+            message
+        );
+    }
+    harness.stop().await;
+}
+
+#[test]
+fn levels_are_read_from_the_config() {
+    let config = CloudsConfig::parse(
+        r#"
+cloud_folders = []
+
+[[clouds]]
+name = "home"
+jwt_secret = "secret"
+
+[[clouds.cloud_folders]]
+name = "documents"
+folder_path = "/srv/documents"
+
+[clouds.logging]
+level = "warn"
+sources = { upload = "debug", Watcher = "error" }
+"#,
+    )
+    .unwrap();
+    let logging = &config.clouds[0].logging;
+    assert_eq!(logging.min_level("auth"), LogLevel::Warning);
+    assert_eq!(logging.min_level("upload"), LogLevel::Debug);
+    // Source names match whatever their case
+    assert_eq!(logging.min_level("watcher"), LogLevel::Error);
+    assert!(logging.allows(&LogLevel::Error, "watcher"));
+
+    let empty_source = CloudsConfig::parse(
+        r#"
+cloud_folders = []
+
+[[clouds]]
+name = "home"
+jwt_secret = "secret"
+
+[[clouds.cloud_folders]]
+name = "documents"
+folder_path = "/srv/documents"
+
+[clouds.logging]
+sources = { "" = "error" }
+"#,
+    );
+    assert!(empty_source.is_err());
+}
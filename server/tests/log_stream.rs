@@ -0,0 +1,55 @@
+//! `/api/logs/stream` sends the types of `cloudhost-shared`, so a client reads its frames
+//! into the same `LogFrame` and `DebugMessage` the server logged.
+
+mod common;
+
+use cloudhost_shared::{LogFrame, LogLevel};
+use futures_util::StreamExt;
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
+
+use common::TestServer;
+
+#[tokio::test]
+async fn frames_are_the_shared_types() {
+    let server = TestServer::start().await;
+    server
+        .debug_stream
+        .push(LogLevel::Info, "test", "started".to_string());
+    server
+        .debug_stream
+        .push(LogLevel::Warning, "test", "disk almost full".to_string());
+    server
+        .debug_stream
+        .push(LogLevel::Error, "test", "disk full".to_string());
+
+    let url = format!(
+        "ws://127.0.0.1:{}/api/logs/stream?level=warn&history=10",
+        server.port
+    );
+    let mut request = url.into_client_request().unwrap();
+    request.headers_mut().insert(
+        "Authorization",
+        format!("Bearer {}", server.token).parse().unwrap(),
+    );
+    let (mut socket, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+
+    let mut received = Vec::new();
+    while received.len() < 2 {
+        let Some(Ok(Message::Text(text))) = socket.next().await else {
+            panic!("the stream ended early");
+        };
+        match serde_json::from_str::<LogFrame>(&text).unwrap() {
+            LogFrame::Log(message) => received.push((message.level, message.message)),
+            LogFrame::Lagged { skipped } => panic!("lagged by {} messages", skipped),
+        }
+    }
+    assert_eq!(
+        received,
+        [
+            (LogLevel::Warning, "disk almost full".to_string()),
+            (LogLevel::Error, "disk full".to_string()),
+        ]
+    );
+
+    server.stop().await;
+}
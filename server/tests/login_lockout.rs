@@ -0,0 +1,115 @@
+//! One lockout for every way of entering the cloud password: failures on the login page,
+//! the Nextcloud login flow or FTP lock the address out of all of them.
+
+mod common;
+
+use cloudhost_server::ftp::FtpConfig;
+use cloudhost_server::login_limiter::MAX_FAILURES;
+use common::orchestrator::{TestOrchestrator, PASSWORD};
+use common::{free_port, ftp_commands, request};
+
+/// A cloud with the Nextcloud layer and FTP, returns the HTTP and FTP ports
+async fn start(harness: &mut TestOrchestrator) -> (u16, u16) {
+    harness.add_cloud("home", &["documents"]);
+    let ftp_port = free_port();
+    let cloud = &mut harness.orchestrator.clouds_config.clouds[0];
+    cloud.nextcloud_compat = true;
+    cloud.ftp = Some(FtpConfig {
+        port: ftp_port,
+        passive_ports: None,
+        public_address: None,
+        tls_cert: None,
+        tls_key: None,
+        require_tls: false,
+    });
+    (harness.start("home").await, ftp_port)
+}
+
+/// Log in over FTP with each password in turn on one connection, returns the replies
+async fn ftp_login(port: u16, passwords: &[&str]) -> Vec<String> {
+    let commands: Vec<String> = passwords
+        .iter()
+        .flat_map(|password| ["USER anyone".to_string(), format!("PASS {}", password)])
+        .collect();
+    let commands: Vec<&str> = commands.iter().map(String::as_str).collect();
+    // The replies to PASS
+    ftp_commands(port, &commands)
+        .await
+        .into_iter()
+        .skip(1)
+        .step_by(2)
+        .collect()
+}
+
+async fn web_login(port: u16, password: &str) -> u16 {
+    request(port, "POST", "/api/login")
+        .header("Content-Type", "application/json")
+        .body(serde_json::json!({ "password": password }).to_string())
+        .send()
+        .await
+        .status
+}
+
+/// The login page of a fresh Nextcloud login flow
+async fn login_flow_path(port: u16) -> String {
+    let response = request(port, "POST", "/index.php/login/v2").send().await;
+    let login = response.json()["login"].as_str().unwrap().to_string();
+    login
+        .strip_prefix(&format!("http://127.0.0.1:{}", port))
+        .unwrap()
+        .to_string()
+}
+
+async fn submit_flow(port: u16, path: &str, password: &str) -> u16 {
+    request(port, "POST", path)
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .body(format!("password={}", password))
+        .send()
+        .await
+        .status
+}
+
+#[tokio::test]
+async fn login_flow_failures_lock_out_the_login_page_and_ftp() {
+    let mut harness = TestOrchestrator::new().await;
+    let (port, ftp_port) = start(&mut harness).await;
+    let flow = login_flow_path(port).await;
+
+    for _ in 1..MAX_FAILURES {
+        assert_eq!(submit_flow(port, &flow, "wrong").await, 401);
+    }
+    assert_eq!(submit_flow(port, &flow, "wrong").await, 429);
+    // Even the right password waits for the lockout, everywhere
+    assert_eq!(submit_flow(port, &flow, PASSWORD).await, 429);
+    assert_eq!(web_login(port, PASSWORD).await, 429);
+    let replies = ftp_login(ftp_port, &[PASSWORD]).await;
+    assert!(
+        replies[0].starts_with("530 Too many failed logins"),
+        "{:?}",
+        replies
+    );
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn ftp_failures_lock_out_the_login_page() {
+    let mut harness = TestOrchestrator::new().await;
+    let (port, ftp_port) = start(&mut harness).await;
+
+    // FTP drops the connection after three tries, so over two connections
+    let mut replies = ftp_login(ftp_port, &["wrong", "wrong", "wrong"]).await;
+    replies.extend(ftp_login(ftp_port, &["wrong", "wrong"]).await);
+    assert!(replies[..4]
+        .iter()
+        .all(|reply| reply == "530 Login incorrect"));
+    assert!(
+        replies[4].starts_with("530 Too many failed logins"),
+        "{:?}",
+        replies
+    );
+
+    assert_eq!(web_login(port, PASSWORD).await, 429);
+    let flow = login_flow_path(port).await;
+    assert_eq!(submit_flow(port, &flow, PASSWORD).await, 429);
+    harness.stop().await;
+}
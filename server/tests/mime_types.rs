@@ -0,0 +1,66 @@
+//! Content types in listings and downloads: by extension, by the first bytes for files
+//! without one, and plain text for source code.
+
+mod common;
+
+use common::{TestServer, CLOUD_FOLDER};
+
+const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+/// Files, their contents, and the type they're served as
+const FILES: &[(&str, &[u8], &str)] = &[
+    ("photo.jpg", b"\xff\xd8\xff\xe0", "image/jpeg"),
+    ("IMG_0001", PNG, "image/png"),
+    ("scan", b"%PDF-1.7\n", "application/pdf"),
+    ("README", b"Read me first\n", "text/plain"),
+    ("blob", b"\x00\x01\x02\xff\xfe", "application/octet-stream"),
+    ("main.rs", b"fn main() {}\n", "text/plain"),
+    ("notes.md", b"# Notes\n", "text/markdown"),
+];
+
+#[tokio::test]
+async fn types_in_listings_and_downloads() {
+    let server = TestServer::start().await;
+    std::fs::create_dir(server.file("album")).unwrap();
+    for (name, contents, _) in FILES {
+        std::fs::write(server.file(name), contents).unwrap();
+    }
+
+    let response = server
+        .request("GET", &format!("/api/{}/files", CLOUD_FOLDER))
+        .send()
+        .await;
+    assert_eq!(response.status, 200);
+    let listing = response.json();
+    let items = listing["items"].as_array().unwrap();
+    let type_of = |name: &str| {
+        items
+            .iter()
+            .find(|item| item["name"] == name)
+            .unwrap_or_else(|| panic!("{} is listed", name))["mime_type"]
+            .clone()
+    };
+    assert!(type_of("album").is_null());
+    for (name, _, mime_type) in FILES {
+        assert_eq!(type_of(name), *mime_type, "{}", name);
+
+        let response = server
+            .request("GET", &format!("/api/{}/static/{}", CLOUD_FOLDER, name))
+            .send()
+            .await;
+        assert_eq!(
+            response.header("content-type"),
+            Some(*mime_type),
+            "{}",
+            name
+        );
+    }
+
+    let response = server
+        .request("GET", &format!("/api/{}/files/IMG_0001", CLOUD_FOLDER))
+        .send()
+        .await;
+    assert_eq!(response.json()["mime_type"], "image/png");
+
+    server.stop().await;
+}
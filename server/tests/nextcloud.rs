@@ -0,0 +1,205 @@
+//! The Nextcloud compatibility layer as the official apps see it: the server status,
+//! the capabilities, and Login Flow v2 ending in an app password that opens WebDAV.
+
+mod common;
+
+use common::orchestrator::{TestOrchestrator, PASSWORD};
+use common::request;
+
+async fn start_nextcloud(harness: &mut TestOrchestrator) -> u16 {
+    harness.add_cloud("home", &["photos"]);
+    harness.orchestrator.clouds_config.clouds[0].nextcloud_compat = true;
+    harness.start("home").await
+}
+
+#[tokio::test]
+async fn status_and_capabilities_answer_without_login() {
+    let mut harness = TestOrchestrator::new().await;
+    let port = start_nextcloud(&mut harness).await;
+
+    let response = request(port, "GET", "/status.php").send().await;
+    assert_eq!(response.status, 200);
+    let status = response.json();
+    assert_eq!(status["installed"], true);
+    assert_eq!(status["maintenance"], false);
+    assert_eq!(status["productname"], "CloudHost (home)");
+    assert!(status["version"].as_str().unwrap().starts_with("28."));
+
+    // Same envelope, different success codes
+    for (version, statuscode) in [("v1.php", 100), ("v2.php", 200)] {
+        let path = format!("/ocs/{}/cloud/capabilities", version);
+        let response = request(port, "GET", &path).send().await;
+        assert_eq!(response.status, 200, "{}", path);
+        let ocs = &response.json()["ocs"];
+        assert_eq!(ocs["meta"]["status"], "ok");
+        assert_eq!(ocs["meta"]["statuscode"], statuscode);
+        let capabilities = &ocs["data"]["capabilities"];
+        assert_eq!(capabilities["core"]["webdav-root"], "remote.php/webdav");
+        assert_eq!(capabilities["dav"]["chunking"], "1.0");
+    }
+    let response = request(port, "GET", "/ocs/v3.php/cloud/capabilities")
+        .send()
+        .await;
+    assert_eq!(response.status, 404);
+
+    // Everything else needs an app password
+    let response = request(port, "GET", "/ocs/v2.php/cloud/user").send().await;
+    assert_eq!(response.status, 401);
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn login_flow_hands_out_an_app_password() {
+    let mut harness = TestOrchestrator::new().await;
+    let port = start_nextcloud(&mut harness).await;
+
+    let response = request(port, "POST", "/index.php/login/v2").send().await;
+    assert_eq!(response.status, 200);
+    let flow = response.json();
+    let login = flow["login"].as_str().unwrap();
+    let poll_token = flow["poll"]["token"].as_str().unwrap();
+    assert_eq!(
+        flow["poll"]["endpoint"],
+        format!("http://127.0.0.1:{}/index.php/login/v2/poll", port)
+    );
+    let login_path = login
+        .strip_prefix(&format!("http://127.0.0.1:{}", port))
+        .unwrap();
+
+    let poll = || {
+        request(port, "POST", "/index.php/login/v2/poll")
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(format!("token={}", poll_token))
+            .send()
+    };
+    assert_eq!(poll().await.status, 404, "nothing granted yet");
+
+    let response = request(port, "GET", login_path).send().await;
+    assert_eq!(response.status, 200);
+    assert!(response.text().contains("Grant access"));
+
+    let submit = |password: &str| {
+        request(port, "POST", login_path)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(format!("password={}", password))
+            .send()
+    };
+    let response = submit("wrong").await;
+    assert_eq!(response.status, 401);
+    assert!(response.text().contains("Wrong password"));
+    assert_eq!(poll().await.status, 404);
+
+    let response = submit(PASSWORD).await;
+    assert_eq!(response.status, 200, "{}", response.text());
+    assert!(response.text().contains("Access granted"));
+    // The link only works once
+    assert_eq!(
+        request(port, "GET", login_path).send().await.status,
+        404,
+        "a granted flow is no longer pending"
+    );
+
+    let response = poll().await;
+    assert_eq!(response.status, 200);
+    let granted = response.json();
+    assert_eq!(granted["loginName"], "home");
+    assert_eq!(granted["server"], format!("http://127.0.0.1:{}", port));
+    let app_password = granted["appPassword"].as_str().unwrap().to_string();
+    // Collected once
+    assert_eq!(poll().await.status, 404);
+
+    let response = request(port, "GET", "/ocs/v1.php/cloud/user")
+        .basic("home", &app_password)
+        .send()
+        .await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.json()["ocs"]["data"]["id"], "home");
+    let response = request(port, "PROPFIND", "/remote.php/webdav/")
+        .basic("home", &app_password)
+        .header("Depth", "1")
+        .send()
+        .await;
+    assert_eq!(response.status, 207);
+    assert!(response.text().contains("/remote.php/webdav/photos/"));
+    let response = request(port, "PROPFIND", "/remote.php/webdav/")
+        .basic("home", "not-an-app-password")
+        .send()
+        .await;
+    assert_eq!(response.status, 401);
+    harness.stop().await;
+}
+
+/// Go through the login flow as the app `client`, returns the app password
+async fn app_password(port: u16, client: &str) -> String {
+    let response = request(port, "POST", "/index.php/login/v2")
+        .header("User-Agent", client)
+        .send()
+        .await;
+    let flow = response.json();
+    let login_path = flow["login"]
+        .as_str()
+        .unwrap()
+        .strip_prefix(&format!("http://127.0.0.1:{}", port))
+        .unwrap()
+        .to_string();
+    let response = request(port, "POST", &login_path)
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .body(format!("password={}", PASSWORD))
+        .send()
+        .await;
+    assert_eq!(response.status, 200, "{}", response.text());
+    let response = request(port, "POST", "/index.php/login/v2/poll")
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .body(format!("token={}", flow["poll"]["token"].as_str().unwrap()))
+        .send()
+        .await;
+    response.json()["appPassword"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn app_passwords_only_open_the_nextcloud_routes_until_revoked() {
+    let mut harness = TestOrchestrator::new().await;
+    let port = start_nextcloud(&mut harness).await;
+    let login = harness.login(port).await;
+    let app_password = app_password(port, "Nextcloud-android/3.30.1").await;
+    let propfind = || {
+        request(port, "PROPFIND", "/remote.php/webdav/")
+            .basic("home", &app_password)
+            .header("Depth", "1")
+            .send()
+    };
+    assert_eq!(propfind().await.status, 207);
+
+    let response = request(port, "GET", "/api/photos/files")
+        .bearer(&app_password)
+        .send()
+        .await;
+    assert_eq!(response.status, 403, "{}", response.text());
+    assert_eq!(response.json()["code"], "TOKEN_OUT_OF_SCOPE");
+
+    // Listed with the other API tokens, named after the app
+    let response = request(port, "GET", "/api/tokens")
+        .bearer(&login)
+        .send()
+        .await;
+    let tokens = response.json();
+    let token = tokens["tokens"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|token| token["scope"] == "nextcloud")
+        .expect("the app password is listed")
+        .clone();
+    assert_eq!(token["name"], "Nextcloud: Nextcloud-android/3.30.1");
+    let response = request(
+        port,
+        "DELETE",
+        &format!("/api/tokens/{}", token["id"].as_str().unwrap()),
+    )
+    .bearer(&login)
+    .send()
+    .await;
+    assert_eq!(response.status, 200, "{}", response.text());
+    assert_eq!(propfind().await.status, 401);
+    harness.stop().await;
+}
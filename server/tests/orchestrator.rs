@@ -0,0 +1,192 @@
+//! End-to-end tests through the `Orchestrator`: clouds are configured, started and
+//! stopped as the TUI does it, and used over HTTP like a browser or the API would.
+
+mod common;
+
+use cloudhost_server::cloud::Cloud;
+use cloudhost_server::error::ServerError;
+use common::orchestrator::{TestOrchestrator, PASSWORD};
+use common::request;
+use tokio::net::TcpStream;
+
+#[tokio::test]
+async fn start_and_stop() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents"]);
+
+    let port = harness.start("home").await;
+    assert!(harness.orchestrator.is_cloud_running("home"));
+    assert_eq!(harness.orchestrator.get_cloud_port("home"), Some(port));
+    assert_eq!(request(port, "GET", "/login").send().await.status, 200);
+    assert!(matches!(
+        harness.orchestrator.start_cloud("home").await,
+        Err(ServerError::ServerAlreadyRunning)
+    ));
+
+    harness.orchestrator.stop_cloud("home").await.unwrap();
+    assert!(!harness.orchestrator.is_cloud_running("home"));
+    assert!(TcpStream::connect(("127.0.0.1", port)).await.is_err());
+    assert!(matches!(
+        harness.orchestrator.stop_cloud("home").await,
+        Err(ServerError::ServerNotRunning)
+    ));
+
+    // Starts again after a stop
+    let port = harness.start("home").await;
+    assert_eq!(request(port, "GET", "/login").send().await.status, 200);
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn cloud_without_password_does_not_start() {
+    let mut harness = TestOrchestrator::new().await;
+    let folder = harness.add_folder("music");
+    harness
+        .orchestrator
+        .add_cloud(Cloud::new("unlocked".to_string(), vec![folder]))
+        .unwrap();
+
+    let result = harness.orchestrator.start_cloud("unlocked").await;
+    assert!(matches!(result, Err(ServerError::Validation(_))));
+    assert!(!harness.orchestrator.is_cloud_running("unlocked"));
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn config_is_saved_in_the_config_dir() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents"]);
+
+    let config =
+        std::fs::read_to_string(harness.config_dir.path().join("clouds-config.toml")).unwrap();
+    assert!(config.contains("name = \"home\""));
+    assert!(config.contains("name = \"documents\""));
+    // The password is stored hashed
+    assert!(!config.contains(PASSWORD));
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn login_is_required() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents"]);
+    let port = harness.start("home").await;
+
+    assert_eq!(request(port, "GET", "/api").send().await.status, 401);
+    let wrong = request(port, "POST", "/api/login")
+        .header("Content-Type", "application/json")
+        .body(r#"{"password":"wrong password"}"#)
+        .send()
+        .await;
+    assert_eq!(wrong.status, 401);
+    assert_eq!(
+        request(port, "GET", "/api")
+            .bearer("not-a-token")
+            .send()
+            .await
+            .status,
+        401
+    );
+
+    let token = harness.login(port).await;
+    let response = request(port, "GET", "/api").bearer(&token).send().await;
+    assert_eq!(response.status, 200);
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn listing() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents", "photos"]);
+    let documents = harness.folder_path("documents");
+    std::fs::write(documents.join("notes.txt"), "hello").unwrap();
+    std::fs::create_dir(documents.join("drafts")).unwrap();
+    let port = harness.start("home").await;
+    let token = harness.login(port).await;
+
+    let response = request(port, "GET", "/api/documents/files")
+        .bearer(&token)
+        .send()
+        .await;
+    assert_eq!(response.status, 200);
+    let names: Vec<String> = response.json()["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["name"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(names, ["drafts", "notes.txt"]);
+
+    let response = request(port, "GET", "/api/photos/files")
+        .bearer(&token)
+        .send()
+        .await;
+    assert_eq!(response.status, 200);
+    assert!(response.json()["items"].as_array().unwrap().is_empty());
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn upload_and_delete() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents"]);
+    let port = harness.start("home").await;
+    let token = harness.login(port).await;
+
+    let upload = request(port, "POST", "/api/upload/documents")
+        .bearer(&token)
+        .file("report.txt", b"quarterly numbers")
+        .send()
+        .await;
+    assert_eq!(upload.status, 200, "{}", upload.text());
+    let stored = harness.folder_path("documents").join("report.txt");
+    assert_eq!(std::fs::read(&stored).unwrap(), b"quarterly numbers");
+
+    let download = request(port, "GET", "/api/documents/static/report.txt")
+        .bearer(&token)
+        .send()
+        .await;
+    assert_eq!(download.text(), "quarterly numbers");
+
+    let delete = request(port, "DELETE", "/api/delete/documents/report.txt")
+        .bearer(&token)
+        .send()
+        .await;
+    assert_eq!(delete.status, 200, "{}", delete.text());
+    assert!(!stored.exists());
+    let listing = request(port, "GET", "/api/documents/files")
+        .bearer(&token)
+        .send()
+        .await;
+    assert!(listing.json()["items"].as_array().unwrap().is_empty());
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn folder_changes_apply_while_running() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents"]);
+    let port = harness.start("home").await;
+    let token = harness.login(port).await;
+    let response = request(port, "GET", "/api/music/files")
+        .bearer(&token)
+        .send()
+        .await;
+    assert_ne!(response.status, 200);
+
+    let folder = harness.add_folder("music");
+    std::fs::write(harness.folder_path("music").join("song.mp3"), "la la").unwrap();
+    let mut cloud = harness.orchestrator.get_cloud("home").unwrap();
+    cloud.cloud_folders.push(folder);
+    harness.orchestrator.update_cloud("home", cloud).unwrap();
+
+    // Same server, same session, new folder
+    assert_eq!(harness.orchestrator.get_cloud_port("home"), Some(port));
+    let response = request(port, "GET", "/api/music/files")
+        .bearer(&token)
+        .send()
+        .await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.json()["items"][0]["name"], "song.mp3");
+    harness.stop().await;
+}
@@ -0,0 +1,123 @@
+//! Environment variables over the clouds config: where the config and data live, and the
+//! base port, bind address and log level of the clouds.
+
+mod common;
+
+use cloudhost_server::config_paths::{self, Overrides};
+use cloudhost_server::debug_stream::LogLevel;
+use cloudhost_server::defaults::ServerDefaults;
+use cloudhost_server::orchestrator::Orchestrator;
+use cloudhost_server::{log_store, CloudsConfig};
+use common::orchestrator::TestOrchestrator;
+use common::{free_port, request, TempDir};
+
+#[test]
+fn overrides_parse_and_report_bad_values() {
+    let vars = |set: &'static [(&'static str, &'static str)]| {
+        move |name: &str| {
+            set.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        }
+    };
+
+    assert_eq!(Overrides::parse(vars(&[])).unwrap(), Overrides::default());
+    let overrides = Overrides::parse(vars(&[
+        ("CLOUDHOST_BASE_PORT", "8080"),
+        ("CLOUDHOST_BIND_ADDRESS", "127.0.0.1"),
+        ("CLOUDHOST_LOG_LEVEL", "warn"),
+    ]))
+    .unwrap();
+    let defaults = ServerDefaults::default().with_overrides(&overrides);
+    assert_eq!(defaults.base_port, 8080);
+    assert_eq!(defaults.bind_address.to_string(), "127.0.0.1");
+    assert_eq!(defaults.log_level, Some(LogLevel::Warning));
+    assert_eq!(
+        overrides.describe(),
+        [
+            "CLOUDHOST_BASE_PORT=8080",
+            "CLOUDHOST_BIND_ADDRESS=127.0.0.1",
+            "CLOUDHOST_LOG_LEVEL=warn"
+        ]
+    );
+
+    for (var, value, expected) in [
+        ("CLOUDHOST_BASE_PORT", "0", "is not a port"),
+        (
+            "CLOUDHOST_BIND_ADDRESS",
+            "localhost",
+            "is not an IP address",
+        ),
+        ("CLOUDHOST_LOG_LEVEL", "loud", "Unknown log level"),
+    ] {
+        let error =
+            Overrides::parse(|name: &str| (name == var).then(|| value.to_string())).unwrap_err();
+        assert!(error.starts_with(var), "{}", error);
+        assert!(error.contains(expected), "{}", error);
+    }
+}
+
+#[tokio::test]
+async fn overridden_defaults_are_used_but_not_saved() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents"]);
+    let port = free_port();
+    std::env::set_var(config_paths::BASE_PORT_VAR, port.to_string());
+    std::env::set_var(config_paths::BIND_ADDRESS_VAR, "127.0.0.1");
+
+    harness.orchestrator = Orchestrator::new();
+    assert_eq!(harness.orchestrator.defaults().base_port, port);
+    assert_eq!(
+        harness.orchestrator.start_cloud("home").await.unwrap(),
+        port
+    );
+    let response = request(port, "GET", "/login").send().await;
+    assert_eq!(response.status, 200);
+
+    // Editing the defaults saves the config's own values, not the overrides
+    let defaults = ServerDefaults {
+        session_lifetime_hours: Some(48),
+        ..harness.orchestrator.clouds_config.defaults.clone()
+    };
+    harness.orchestrator.set_defaults(defaults).unwrap();
+    let saved = CloudsConfig::load_from_file().unwrap().defaults;
+    assert_eq!(saved.base_port, ServerDefaults::default().base_port);
+    assert_eq!(saved.bind_address, ServerDefaults::default().bind_address);
+    assert_eq!(saved.session_lifetime_hours, Some(48));
+
+    std::env::remove_var(config_paths::BASE_PORT_VAR);
+    std::env::remove_var(config_paths::BIND_ADDRESS_VAR);
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn config_file_and_data_dir_can_live_elsewhere() {
+    let mut harness = TestOrchestrator::new().await;
+    let elsewhere = TempDir::new("elsewhere");
+    let config_path = elsewhere.path().join("configs").join("home.toml");
+    let data_dir = elsewhere.path().join("data");
+    std::env::set_var(config_paths::CLOUDS_CONFIG_VAR, &config_path);
+    std::env::set_var(config_paths::DATA_DIR_VAR, &data_dir);
+
+    harness.orchestrator = Orchestrator::new();
+    harness.add_cloud("home", &["documents"]);
+    assert!(config_path.is_file());
+    assert!(!harness
+        .config_dir
+        .path()
+        .join("clouds-config.toml")
+        .exists());
+    assert_eq!(
+        CloudsConfig::load_from_file().unwrap().clouds[0].name,
+        "home"
+    );
+    // The last good config is kept next to it
+    assert_eq!(
+        CloudsConfig::get_backup_path(),
+        elsewhere.path().join("configs").join("home.toml.bak")
+    );
+    assert!(log_store::get_cloud_log_path("home").starts_with(&data_dir));
+
+    std::env::remove_var(config_paths::CLOUDS_CONFIG_VAR);
+    std::env::remove_var(config_paths::DATA_DIR_VAR);
+}
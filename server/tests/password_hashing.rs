@@ -0,0 +1,98 @@
+//! Passwords from configs written before hashing are plaintext: they still log in, and
+//! the first login replaces them with an Argon2id hash that keeps working.
+
+mod common;
+
+use cloudhost_server::password::{self, PasswordHashParams};
+use cloudhost_server::CloudsConfig;
+use common::orchestrator::{TestOrchestrator, PASSWORD};
+use common::request;
+
+async fn login_status(port: u16, password: &str) -> u16 {
+    request(port, "POST", "/api/login")
+        .header("Content-Type", "application/json")
+        .body(serde_json::json!({ "password": password }).to_string())
+        .send()
+        .await
+        .status
+}
+
+#[tokio::test]
+async fn legacy_passwords_are_rehashed_on_login() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents"]);
+    // As an old config stored it
+    harness.orchestrator.clouds_config.clouds[0].password = Some(PASSWORD.to_string());
+    let port = harness.start("home").await;
+
+    assert_eq!(login_status(port, "wrong").await, 401);
+    assert!(harness
+        .orchestrator
+        .persist_password_upgrades()
+        .unwrap()
+        .is_empty());
+    assert_eq!(login_status(port, PASSWORD).await, 200);
+    assert_eq!(
+        harness.orchestrator.persist_password_upgrades().unwrap(),
+        ["home"]
+    );
+
+    let stored = CloudsConfig::load_from_file().unwrap().clouds[0]
+        .password
+        .clone()
+        .unwrap();
+    assert!(stored.starts_with("$argon2id$"), "{}", stored);
+    assert!(!stored.contains(PASSWORD));
+    assert!(!password::needs_rehash(
+        &stored,
+        &harness.orchestrator.clouds_config.password_hashing
+    ));
+
+    // The hash logs in, in the running cloud and after a restart
+    assert_eq!(login_status(port, PASSWORD).await, 200);
+    assert!(harness
+        .orchestrator
+        .persist_password_upgrades()
+        .unwrap()
+        .is_empty());
+    harness.orchestrator.stop_cloud("home").await.unwrap();
+    let port = harness.start("home").await;
+    assert_eq!(login_status(port, PASSWORD).await, 200);
+    assert_eq!(login_status(port, "wrong").await, 401);
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn hashes_with_old_parameters_are_rehashed_on_login() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents"]);
+    let old = PasswordHashParams {
+        memory_kib: 16,
+        iterations: 2,
+        parallelism: 1,
+    };
+    let old_hash = password::hash_password(PASSWORD, &old).unwrap();
+    harness.orchestrator.clouds_config.clouds[0].password = Some(old_hash.clone());
+    let port = harness.start("home").await;
+
+    assert_eq!(login_status(port, PASSWORD).await, 200);
+    assert_eq!(
+        harness.orchestrator.persist_password_upgrades().unwrap(),
+        ["home"]
+    );
+    let stored = CloudsConfig::load_from_file().unwrap().clouds[0]
+        .password
+        .clone()
+        .unwrap();
+    assert_ne!(stored, old_hash);
+    assert!(password::needs_rehash(
+        &old_hash,
+        &harness.orchestrator.clouds_config.password_hashing
+    ));
+    assert!(!password::needs_rehash(
+        &stored,
+        &harness.orchestrator.clouds_config.password_hashing
+    ));
+    assert_eq!(login_status(port, PASSWORD).await, 200);
+    harness.stop().await;
+}
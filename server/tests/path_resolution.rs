@@ -0,0 +1,42 @@
+//! The web pages, the JSON API and downloads resolve paths the same way, so a path that's
+//! missing, malformed or in an unknown cloud folder gets the same status from each.
+
+mod common;
+
+use common::{TestServer, CLOUD_FOLDER};
+
+#[tokio::test]
+async fn routes_agree_on_paths() {
+    let server = TestServer::start().await;
+    std::fs::create_dir(server.file("docs")).unwrap();
+    std::fs::write(server.file("docs/notes.txt"), "notes").unwrap();
+
+    let cases = [
+        (CLOUD_FOLDER, "docs/notes.txt", 200),
+        (CLOUD_FOLDER, "docs/missing.txt", 404),
+        (CLOUD_FOLDER, "..%2F..%2Fetc", 400),
+        ("nowhere", "docs/notes.txt", 404),
+    ];
+    for (cloud_folder, path, status) in cases {
+        for route in [
+            format!("/web/{}/files/{}", cloud_folder, path),
+            format!("/api/{}/files/{}", cloud_folder, path),
+            format!("/api/{}/static/{}", cloud_folder, path),
+        ] {
+            let response = server.request("GET", &route).send().await;
+            assert_eq!(response.status, status, "GET {}", route);
+        }
+    }
+
+    // A directory has a listing but nothing to download
+    for (route, status) in [
+        (format!("/web/{}/files/docs", CLOUD_FOLDER), 200),
+        (format!("/api/{}/files/docs", CLOUD_FOLDER), 200),
+        (format!("/api/{}/static/docs", CLOUD_FOLDER), 404),
+    ] {
+        let response = server.request("GET", &route).send().await;
+        assert_eq!(response.status, status, "GET {}", route);
+    }
+
+    server.stop().await;
+}
@@ -0,0 +1,300 @@
+//! Property tests for path containment. Generated paths — `..` in its many spellings,
+//! encoded separators, backslashes, NULs, unicode look-alikes, drive prefixes, absolute
+//! paths and symlinks that lead out — are sent to the routes that take a path. Nothing
+//! outside the cloud folder may be read, listed, written or deleted through them.
+
+mod common;
+
+use cloudhost_server::utils::sanitize_path;
+use common::{TempDir, TestServer, CLOUD_FOLDER};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use proptest::prelude::*;
+use proptest::test_runner::{Config, TestCaseError, TestRunner};
+use std::path::{Component, Path};
+use tokio::runtime::Runtime;
+
+/// Contents of the file outside the cloud folder
+const SECRET: &str = "the secret outside the cloud folder";
+/// Its name, which no generated path contains, so it only shows up in a listing that leaked
+const SECRET_FILE: &str = "outside-secret.txt";
+/// Names of the files uploads try to plant
+const PLANTED: &str = "planted";
+const CASES: u32 = 128;
+
+/// Path parts as a client means them, before they're encoded into the URL
+const PARTS: &[&str] = &[
+    "..",
+    ".",
+    "",
+    "...",
+    "../..",
+    "..\\..",
+    "\\",
+    "a\\..\\..",
+    "..\0",
+    "/",
+    "//",
+    "%2e%2e",
+    "%252e%252e",
+    "\u{2215}..",
+    "\u{ff0e}\u{ff0e}",
+    "..\u{2215}..",
+    "ü",
+    "文件",
+    "C:",
+    "C:\\Windows",
+    "~",
+    "/etc",
+    "inside.txt",
+    "dir",
+    "nested.txt",
+    "escape",
+    "escape.txt",
+];
+
+/// How a part is put into the URL
+#[derive(Debug, Clone, Copy)]
+enum Encoding {
+    /// As it is, even where that makes the request invalid
+    Raw,
+    /// Percent-encoded except for unreserved characters, so `/` becomes `%2F`
+    Encoded,
+    /// Every byte percent-encoded, dots included
+    EncodedAll,
+}
+
+const UNRESERVED: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'.')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'~');
+
+fn encode(part: &str, encoding: Encoding) -> String {
+    match encoding {
+        Encoding::Raw => part.to_string(),
+        Encoding::Encoded => utf8_percent_encode(part, UNRESERVED).to_string(),
+        Encoding::EncodedAll => part.bytes().map(|byte| format!("%{:02X}", byte)).collect(),
+    }
+}
+
+/// Paths of one to five parts, from `PARTS` and `extra`, each encoded its own way
+fn url_path(extra: Vec<String>) -> impl Strategy<Value = String> {
+    let parts: Vec<String> = PARTS
+        .iter()
+        .map(|part| part.to_string())
+        .chain(extra)
+        .collect();
+    let encoding = prop_oneof![
+        Just(Encoding::Raw),
+        Just(Encoding::Encoded),
+        Just(Encoding::EncodedAll)
+    ];
+    prop::collection::vec((prop::sample::select(parts), encoding), 1..6).prop_map(|parts| {
+        parts
+            .iter()
+            .map(|(part, encoding)| encode(part, *encoding))
+            .collect::<Vec<_>>()
+            .join("/")
+    })
+}
+
+/// The same parts as a handler gets them, decoded
+fn decoded_path() -> impl Strategy<Value = String> {
+    prop::collection::vec(prop::sample::select(PARTS), 1..6).prop_map(|parts| parts.join("/"))
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(1024))]
+
+    #[test]
+    fn sanitized_paths_stay_inside(relative in prop_oneof![any::<String>(), decoded_path()]) {
+        let root = Path::new("/srv/cloud");
+        if let Ok(path) = sanitize_path(root, &relative) {
+            let rest = path.strip_prefix(root).expect("the path starts with the root");
+            prop_assert!(
+                rest.components().all(|part| matches!(part, Component::Normal(_))),
+                "{:?} became {:?}",
+                relative,
+                path
+            );
+        }
+    }
+}
+
+/// A server whose cloud folder has a few files and, on Unix, symlinks to a directory
+/// outside of it that holds `SECRET_FILE`
+struct Fixture {
+    runtime: Runtime,
+    server: TestServer,
+    outside: TempDir,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()
+            .unwrap();
+        let server = runtime.block_on(TestServer::start());
+        let outside = TempDir::new("outside");
+        std::fs::write(outside.path().join(SECRET_FILE), SECRET).unwrap();
+        std::fs::write(server.file("inside.txt"), "inside").unwrap();
+        std::fs::create_dir(server.file("dir")).unwrap();
+        std::fs::write(server.file("dir/nested.txt"), "nested").unwrap();
+        let fixture = Self {
+            runtime,
+            server,
+            outside,
+        };
+        fixture.restore_links();
+        fixture
+    }
+
+    /// Put the symlinks back, in case a delete removed them
+    fn restore_links(&self) {
+        #[cfg(unix)]
+        {
+            let _ = std::os::unix::fs::symlink(self.outside.path(), self.server.file("escape"));
+            let _ = std::os::unix::fs::symlink(
+                self.outside.path().join(SECRET_FILE),
+                self.server.file("escape.txt"),
+            );
+        }
+    }
+
+    /// The outside directory's absolute path, as one more part to generate
+    fn outside_part(&self) -> Vec<String> {
+        vec![self.outside.path().to_string_lossy().to_string()]
+    }
+
+    /// Run `check` on `CASES` generated URL paths
+    fn run(&self, check: impl Fn(&str) -> Result<(), TestCaseError>) {
+        let mut runner = TestRunner::new(Config::with_cases(CASES));
+        runner
+            .run(&url_path(self.outside_part()), |path| {
+                self.restore_links();
+                check(&path)
+            })
+            .unwrap();
+    }
+
+    /// Whether the secret is where it was, unchanged, and alone
+    fn outside_untouched(&self) -> bool {
+        let entries: Vec<String> = std::fs::read_dir(self.outside.path())
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        entries == [SECRET_FILE]
+            && std::fs::read_to_string(self.outside.path().join(SECRET_FILE)).unwrap() == SECRET
+    }
+
+    fn stop(self) {
+        self.runtime.block_on(self.server.stop());
+    }
+}
+
+fn leaks(body: &[u8]) -> bool {
+    let body = String::from_utf8_lossy(body);
+    body.contains(SECRET) || body.contains(SECRET_FILE)
+}
+
+#[test]
+fn reads_and_listings_stay_inside() {
+    let fixture = Fixture::new();
+    let server = &fixture.server;
+    // The routes work, so a refusal below is about the path
+    let inside = fixture.runtime.block_on(
+        server
+            .request("GET", &format!("/api/{}/static/inside.txt", CLOUD_FOLDER))
+            .send(),
+    );
+    assert_eq!(inside.text(), "inside");
+
+    fixture.run(|path| {
+        for route in [
+            format!("/api/{}/files/{}", CLOUD_FOLDER, path),
+            format!("/api/{}/static/{}", CLOUD_FOLDER, path),
+            format!("/api/stream/{}/{}", CLOUD_FOLDER, path),
+            format!("/web/{}/files/{}", CLOUD_FOLDER, path),
+        ] {
+            let response = fixture
+                .runtime
+                .block_on(server.request("GET", &route).send());
+            prop_assert!(
+                !leaks(&response.body),
+                "GET {} answered {}: {}",
+                route,
+                response.status,
+                response.text()
+            );
+        }
+        Ok(())
+    });
+    fixture.stop();
+}
+
+#[test]
+fn uploads_stay_inside() {
+    let fixture = Fixture::new();
+    let server = &fixture.server;
+    let planted_outside = || {
+        [fixture.outside.path(), &std::env::temp_dir()]
+            .iter()
+            .flat_map(|dir| std::fs::read_dir(dir).unwrap().flatten())
+            .any(|entry| entry.file_name().to_string_lossy().starts_with(PLANTED))
+    };
+
+    fixture.run(|path| {
+        // Generated parts go into the target directory and the file name alike
+        let filename = format!("{}-{}", PLANTED, path);
+        let route = format!("/api/upload/{}/{}?create_dirs=true", CLOUD_FOLDER, path);
+        let response = fixture.runtime.block_on(
+            server
+                .request("POST", &route)
+                .file(&filename, PLANTED.as_bytes())
+                .send(),
+        );
+        prop_assert!(
+            fixture.outside_untouched() && !planted_outside(),
+            "POST {} with '{}' answered {}: {}",
+            route,
+            filename,
+            response.status,
+            response.text()
+        );
+        Ok(())
+    });
+    fixture.stop();
+}
+
+#[test]
+fn deletes_stay_inside() {
+    // Deleted files go to a trash in here rather than the user's
+    let trash = TempDir::new("trash");
+    std::env::set_var("XDG_DATA_HOME", trash.path());
+    let fixture = Fixture::new();
+    let server = &fixture.server;
+
+    fixture.run(|path| {
+        // Aimed at the secret, and at whatever the path itself names
+        for route in [
+            format!("/api/delete/{}/{}/{}", CLOUD_FOLDER, path, SECRET_FILE),
+            format!("/api/delete/{}/{}", CLOUD_FOLDER, path),
+        ] {
+            let response = fixture
+                .runtime
+                .block_on(server.request("DELETE", &route).send());
+            prop_assert!(
+                fixture.outside_untouched(),
+                "DELETE {} answered {}: {}",
+                route,
+                response.status,
+                response.text()
+            );
+        }
+        Ok(())
+    });
+    fixture.stop();
+}
@@ -0,0 +1,93 @@
+//! Every secret of a cloud is kept out of the clouds config file once the secrets are
+//! encrypted at rest, and comes back when they are unlocked. Exports without secrets
+//! have none of them.
+
+mod common;
+
+use cloudhost_server::secrets::{SecretsKey, SecretsProtection};
+use cloudhost_server::CloudsConfig;
+use common::orchestrator::TestOrchestrator;
+
+/// A cloud with one of each kind of secret, all containing "s3cr3t"
+const FULL_CONFIG: &str = r#"
+[[cloud_folders]]
+name = "documents"
+folder_path = "/srv/documents"
+
+[[clouds]]
+name = "home"
+password = "cloud-password-s3cr3t"
+jwt_secret = "session-s3cr3t"
+
+[[clouds.cloud_folders]]
+name = "documents"
+folder_path = "/srv/documents"
+
+[clouds.email]
+smtp_host = "smtp.example.com"
+username = "me@example.com"
+password = "smtp-s3cr3t"
+from = "me@example.com"
+to = ["me@example.com"]
+
+[[clouds.webhooks]]
+url = "https://example.com/hooks/cloudhost"
+secret = "webhook-s3cr3t-0123456789"
+
+[clouds.office_preview]
+converter = "onlyoffice"
+url = "http://localhost:8080"
+public_url = "http://192.168.1.2:8000"
+jwt_secret = "onlyoffice-s3cr3t"
+"#;
+
+#[tokio::test]
+async fn all_secrets_are_sealed_and_unlocked() {
+    let mut harness = TestOrchestrator::new().await;
+    let config = CloudsConfig::parse(FULL_CONFIG).unwrap();
+    harness.orchestrator.clouds_config = config.clone();
+    harness
+        .orchestrator
+        .protect_secrets(SecretsProtection::Passphrase, Some("master passphrase"))
+        .unwrap();
+
+    let on_disk =
+        std::fs::read_to_string(harness.config_dir.path().join("clouds-config.toml")).unwrap();
+    assert!(!on_disk.contains("s3cr3t"), "{}", on_disk);
+    assert!(on_disk.contains("smtp.example.com"), "{}", on_disk);
+
+    // Loads while locked, though the secrets are missing
+    let mut loaded = CloudsConfig::load_from_file().unwrap();
+    assert!(loaded.secrets_locked());
+    let key = SecretsKey::from_passphrase(
+        "master passphrase",
+        loaded.encrypted_secrets.as_ref().unwrap(),
+    )
+    .unwrap();
+    loaded.unlock_secrets(key).unwrap();
+    assert_eq!(loaded.clouds, config.clouds);
+}
+
+#[tokio::test]
+async fn exports_without_secrets_have_none_of_them() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.orchestrator.clouds_config = CloudsConfig::parse(FULL_CONFIG).unwrap();
+    let path = harness.config_dir.path().join("export.toml");
+    harness.orchestrator.export_config(&path, None).unwrap();
+
+    let exported = std::fs::read_to_string(&path).unwrap();
+    assert!(!exported.contains("s3cr3t"), "{}", exported);
+    assert!(exported.contains("smtp.example.com"), "{}", exported);
+
+    // Imported elsewhere, what can't work without its secrets is left out
+    harness.orchestrator.clouds_config = CloudsConfig::default();
+    let summary = harness.orchestrator.import_config(&path, None).unwrap();
+    assert_eq!(summary.needs_password, ["home"]);
+    assert_eq!(
+        summary.dropped_without_secrets,
+        ["home email", "home webhooks"]
+    );
+    let loaded = CloudsConfig::load_from_file().unwrap();
+    assert_eq!(loaded.clouds[0].name, "home");
+    assert!(loaded.clouds[0].email.is_none());
+}
@@ -0,0 +1,255 @@
+//! `[defaults]`: the base port and bind address of the clouds, and the log level, login
+//! lifetime and upload limits for clouds that don't set their own.
+
+mod common;
+
+use cloudhost_server::cloud::Cloud;
+use cloudhost_server::debug_stream::LogLevel;
+use cloudhost_server::defaults::ServerDefaults;
+use cloudhost_server::ftp::FtpConfig;
+use cloudhost_server::CloudsConfig;
+use common::orchestrator::{TestOrchestrator, PASSWORD};
+use common::{free_port, request};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+#[test]
+fn defaults_fill_in_what_clouds_leave_built_in() {
+    let defaults = ServerDefaults {
+        log_level: Some(LogLevel::Warning),
+        session_lifetime_hours: Some(72),
+        min_free_mb: Some(4096),
+        max_upload_mb: Some(100),
+        ..ServerDefaults::default()
+    };
+
+    let cloud = defaults.apply(&Cloud::new("home".to_string(), Vec::new()));
+    assert_eq!(cloud.logging.level, Some(LogLevel::Warning));
+    assert_eq!(cloud.sessions.lifetime_hours, 72);
+    assert_eq!(cloud.disk_space.min_free_mb, 4096);
+    // Still warned about before uploads are refused
+    assert_eq!(cloud.disk_space.warn_free_mb, 4096);
+    assert_eq!(cloud.disk_space.max_upload_mb, 100);
+
+    let mut own = Cloud::new("work".to_string(), Vec::new());
+    own.logging.level = Some(LogLevel::Error);
+    own.sessions.lifetime_hours = 8;
+    own.disk_space.min_free_mb = 100;
+    own.disk_space.max_upload_mb = 0;
+    assert_eq!(defaults.apply(&own), own);
+}
+
+#[test]
+fn invalid_defaults_are_reported() {
+    let error = CloudsConfig::parse(
+        r#"
+cloud_folders = []
+clouds = []
+
+[defaults]
+base_port = 0
+session_lifetime_hours = 0
+"#,
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(error.contains("defaults.base_port"), "{}", error);
+    assert!(
+        error.contains("defaults.session_lifetime_hours"),
+        "{}",
+        error
+    );
+}
+
+#[tokio::test]
+async fn clouds_start_from_the_base_port_on_the_bind_address() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents"]);
+    let port = free_port();
+    let defaults = ServerDefaults {
+        base_port: port,
+        bind_address: "127.0.0.1".parse().unwrap(),
+        ..ServerDefaults::default()
+    };
+    harness.orchestrator.set_defaults(defaults.clone()).unwrap();
+    assert_eq!(CloudsConfig::load_from_file().unwrap().defaults, defaults);
+
+    // Nothing runs, so the next cloud gets the new base port
+    assert_eq!(
+        harness.orchestrator.start_cloud("home").await.unwrap(),
+        port
+    );
+    assert_eq!(
+        harness.orchestrator.get_cloud_server_url("home").unwrap(),
+        format!("http://localhost:{}", port)
+    );
+    let response = request(port, "GET", "/login").send().await;
+    assert_eq!(response.status, 200);
+
+    // Refused values leave the defaults as they were
+    let mut broken = defaults.clone();
+    broken.session_lifetime_hours = Some(0);
+    assert!(harness.orchestrator.set_defaults(broken).is_err());
+    assert_eq!(harness.orchestrator.clouds_config.defaults, defaults);
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn running_clouds_take_the_upload_limits_right_away() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents"]);
+    let port = harness.start("home").await;
+    let token = harness.login(port).await;
+
+    let defaults = ServerDefaults {
+        min_free_mb: Some(1),
+        max_upload_mb: Some(1),
+        session_lifetime_hours: Some(2),
+        ..ServerDefaults::default()
+    };
+    harness.orchestrator.set_defaults(defaults).unwrap();
+    let running = &harness.orchestrator.running_clouds["home"].cloud;
+    assert_eq!(running.disk_space.min_free_mb, 1);
+    assert_eq!(running.disk_space.max_upload_mb, 1);
+    // Set up when the server starts
+    assert_eq!(running.sessions.lifetime_hours, 24);
+
+    let response = request(port, "POST", "/api/upload/documents")
+        .bearer(&token)
+        .file("big.bin", &vec![7u8; 2 * 1024 * 1024])
+        .send()
+        .await;
+    assert_eq!(response.status, 413, "{}", response.text());
+    assert_eq!(response.json()["code"], "PAYLOAD_TOO_LARGE");
+    // Nothing is kept of the refused upload
+    let documents = harness.folder_path("documents");
+    assert_eq!(std::fs::read_dir(documents).unwrap().count(), 0);
+    let response = request(port, "POST", "/api/upload/documents")
+        .bearer(&token)
+        .file("small.bin", &[7u8; 1024])
+        .send()
+        .await;
+    assert_eq!(response.status, 200, "{}", response.text());
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn webdav_uploads_stop_at_the_upload_limit_without_a_length() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents"]);
+    let cloud = &mut harness.orchestrator.clouds_config.clouds[0];
+    cloud.nextcloud_compat = true;
+    cloud.disk_space.max_upload_mb = 1;
+    let port = harness.start("home").await;
+    let token = harness.login(port).await;
+
+    let response = request(port, "PUT", "/remote.php/webdav/documents/big.bin")
+        .basic("home", &token)
+        .body(vec![7u8; 2 * 1024 * 1024])
+        .chunked()
+        .send()
+        .await;
+    assert_eq!(response.status, 413, "{}", response.text());
+    // Nothing is kept of the refused upload
+    let documents = harness.folder_path("documents");
+    assert_eq!(std::fs::read_dir(documents).unwrap().count(), 0);
+    let response = request(port, "PUT", "/remote.php/webdav/documents/small.bin")
+        .basic("home", &token)
+        .body(vec![7u8; 1024])
+        .chunked()
+        .send()
+        .await;
+    assert_eq!(response.status, 201, "{}", response.text());
+    harness.stop().await;
+}
+
+type FtpControl = (
+    tokio::net::tcp::OwnedWriteHalf,
+    tokio::io::Lines<BufReader<tokio::net::tcp::OwnedReadHalf>>,
+);
+
+/// Send one FTP command, returns the first line of its reply
+async fn ftp_command((write, lines): &mut FtpControl, command: &str) -> String {
+    write
+        .write_all(format!("{}\r\n", command).as_bytes())
+        .await
+        .unwrap();
+    lines.next_line().await.unwrap().unwrap()
+}
+
+/// Log in to FTP on `port` and send `contents` with a STOR or APPE of `path` over a
+/// passive data connection; returns the final reply
+async fn ftp_upload(port: u16, command: &str, path: &str, contents: &[u8]) -> String {
+    let (read, write) = TcpStream::connect(("127.0.0.1", port))
+        .await
+        .unwrap()
+        .into_split();
+    let mut control = (write, BufReader::new(read).lines());
+    control.1.next_line().await.unwrap();
+    ftp_command(&mut control, "USER home").await;
+    let reply = ftp_command(&mut control, &format!("PASS {}", PASSWORD)).await;
+    assert!(reply.starts_with("230"), "{}", reply);
+    ftp_command(&mut control, "TYPE I").await;
+    let passive = ftp_command(&mut control, "PASV").await;
+    let numbers: Vec<u16> = passive[passive.find('(').unwrap() + 1..passive.find(')').unwrap()]
+        .split(',')
+        .map(|n| n.parse().unwrap())
+        .collect();
+    let mut data = TcpStream::connect(("127.0.0.1", numbers[4] * 256 + numbers[5]))
+        .await
+        .unwrap();
+    let reply = ftp_command(&mut control, &format!("{} {}", command, path)).await;
+    assert!(reply.starts_with("150"), "{}", reply);
+    // The server stops reading once the upload is too large
+    let _ = data.write_all(contents).await;
+    let _ = data.shutdown().await;
+    drop(data);
+    control.1.next_line().await.unwrap().unwrap()
+}
+
+#[tokio::test]
+async fn ftp_uploads_stop_at_the_upload_limit() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["documents"]);
+    let ftp_port = free_port();
+    let cloud = &mut harness.orchestrator.clouds_config.clouds[0];
+    cloud.disk_space.max_upload_mb = 1;
+    cloud.ftp = Some(FtpConfig {
+        port: ftp_port,
+        passive_ports: None,
+        public_address: None,
+        tls_cert: None,
+        tls_key: None,
+        require_tls: false,
+    });
+    harness.start("home").await;
+    let documents = harness.folder_path("documents").to_path_buf();
+    let large = vec![7u8; 2 * 1024 * 1024];
+
+    let reply = ftp_upload(ftp_port, "STOR", "/documents/big.bin", &large).await;
+    assert!(reply.starts_with("552"), "{}", reply);
+    assert_eq!(std::fs::read_dir(&documents).unwrap().count(), 0);
+
+    // An append over the limit leaves the file as it was
+    std::fs::write(documents.join("notes.txt"), b"notes").unwrap();
+    let reply = ftp_upload(ftp_port, "APPE", "/documents/notes.txt", &large).await;
+    assert!(reply.starts_with("552"), "{}", reply);
+    assert_eq!(
+        std::fs::read(documents.join("notes.txt")).unwrap(),
+        b"notes"
+    );
+    let reply = ftp_upload(ftp_port, "APPE", "/documents/notes.txt", b" and more").await;
+    assert!(reply.starts_with("226"), "{}", reply);
+    assert_eq!(
+        std::fs::read(documents.join("notes.txt")).unwrap(),
+        b"notes and more"
+    );
+
+    let reply = ftp_upload(ftp_port, "STOR", "/documents/small.bin", &[7u8; 1024]).await;
+    assert!(reply.starts_with("226"), "{}", reply);
+    assert_eq!(
+        std::fs::read(documents.join("small.bin")).unwrap().len(),
+        1024
+    );
+    harness.stop().await;
+}
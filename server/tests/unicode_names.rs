@@ -0,0 +1,111 @@
+//! File names with spaces, URL syntax, non-ASCII letters and emoji through upload,
+//! listing and download, and names that aren't UTF-8, which no route can address.
+
+mod common;
+
+use common::{TestServer, CLOUD_FOLDER};
+
+/// Names and how they look percent-encoded
+const NAMES: &[(&str, &str)] = &[
+    ("two words.txt", "two%20words.txt"),
+    ("#1 100%.txt", "%231%20100%25.txt"),
+    ("Übersicht.txt", "%C3%9Cbersicht.txt"),
+    ("🌩️ storm.txt", "%F0%9F%8C%A9%EF%B8%8F%20storm.txt"),
+];
+
+#[tokio::test]
+async fn names_survive_upload_listing_and_download() {
+    let server = TestServer::start().await;
+
+    for (name, encoded) in NAMES {
+        let response = server
+            .request("POST", &format!("/api/upload/{}", CLOUD_FOLDER))
+            .file(name, name.as_bytes())
+            .send()
+            .await;
+        assert_eq!(response.status, 200, "{}: {}", name, response.text());
+        assert_eq!(
+            response.json()["stored_path"],
+            format!("{}/{}", CLOUD_FOLDER, name)
+        );
+
+        let response = server
+            .request("GET", &format!("/api/{}/files/{}", CLOUD_FOLDER, encoded))
+            .send()
+            .await;
+        assert_eq!(response.status, 200, "{}", name);
+        assert_eq!(
+            response.json()["download_url"],
+            format!("/api/{}/static/{}", CLOUD_FOLDER, encoded)
+        );
+
+        let response = server
+            .request("GET", &format!("/api/{}/static/{}", CLOUD_FOLDER, encoded))
+            .send()
+            .await;
+        assert_eq!(response.status, 200, "{}", name);
+        assert_eq!(response.text(), *name);
+        let disposition = response.header("content-disposition").unwrap();
+        assert!(
+            disposition.ends_with(&format!("filename*=UTF-8''{}", encoded)),
+            "{}",
+            disposition
+        );
+        assert!(disposition.is_ascii());
+    }
+
+    let response = server
+        .request("GET", &format!("/api/{}/files", CLOUD_FOLDER))
+        .send()
+        .await;
+    let mut listed: Vec<String> = response.json()["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["name"].as_str().unwrap().to_string())
+        .collect();
+    listed.sort();
+    let mut names: Vec<&str> = NAMES.iter().map(|(name, _)| *name).collect();
+    names.sort();
+    assert_eq!(listed, names);
+
+    server.stop().await;
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn names_that_are_not_utf8_are_left_out_of_listings() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let server = TestServer::start().await;
+    let latin1 = OsStr::from_bytes(b"caf\xe9.txt");
+    // Some file systems only take UTF-8 names
+    if std::fs::write(server.file("").join(latin1), "").is_err() {
+        return;
+    }
+    std::fs::write(server.file("plain.txt"), "").unwrap();
+
+    let response = server
+        .request("GET", &format!("/api/{}/files", CLOUD_FOLDER))
+        .send()
+        .await;
+    assert_eq!(response.status, 200);
+    let items = response.json()["items"].clone();
+    let names: Vec<&str> = items
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["plain.txt"]);
+
+    let response = server
+        .request("GET", &format!("/web/{}/files", CLOUD_FOLDER))
+        .send()
+        .await;
+    assert_eq!(response.status, 200);
+    assert!(!response.text().contains('\u{fffd}'));
+
+    server.stop().await;
+}
@@ -0,0 +1,74 @@
+//! Uploads are received under a hidden `.upload-<uuid>.tmp` name next to their target.
+//! Listings leave those out, and ones left behind by a crash go when the cloud starts.
+
+mod common;
+
+use std::time::{Duration, SystemTime};
+
+use common::{TestServer, CLOUD_FOLDER};
+
+const RECEIVING: &str = ".upload-0123456789abcdef0123456789abcdef.tmp";
+const ABANDONED: &str = ".upload-fedcba9876543210fedcba9876543210.tmp";
+
+#[tokio::test]
+async fn listings_leave_out_uploads_being_received() {
+    let server = TestServer::start_with(|cloud| {
+        let folder = &cloud.cloud_folders[0].folder_path;
+        std::fs::write(folder.join("report.pdf"), b"report").unwrap();
+        std::fs::write(folder.join(RECEIVING), b"half a file").unwrap();
+        cloud.nextcloud_compat = true;
+    })
+    .await;
+
+    let response = server
+        .request("GET", &format!("/api/{}/files", CLOUD_FOLDER))
+        .send()
+        .await;
+    assert_eq!(response.status, 200, "{}", response.text());
+    assert!(
+        response.text().contains("report.pdf"),
+        "{}",
+        response.text()
+    );
+    assert!(!response.text().contains(".upload-"), "{}", response.text());
+
+    let response = server
+        .dav_request("PROPFIND", &format!("/remote.php/webdav/{}/", CLOUD_FOLDER))
+        .header("Depth", "1")
+        .send()
+        .await;
+    assert_eq!(response.status, 207, "{}", response.text());
+    assert!(
+        response.text().contains("report.pdf"),
+        "{}",
+        response.text()
+    );
+    assert!(!response.text().contains(".upload-"), "{}", response.text());
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn abandoned_uploads_are_removed_on_start() {
+    let server = TestServer::start_with(|cloud| {
+        let folder = &cloud.cloud_folders[0].folder_path;
+        std::fs::create_dir(folder.join("inbox")).unwrap();
+        let abandoned = std::fs::File::create(folder.join("inbox").join(ABANDONED)).unwrap();
+        abandoned
+            .set_modified(SystemTime::now() - Duration::from_secs(2 * 60 * 60))
+            .unwrap();
+        // Could be an upload to another cloud serving the same folder
+        std::fs::write(folder.join(RECEIVING), b"half a file").unwrap();
+    })
+    .await;
+
+    let abandoned = server.file(&format!("inbox/{}", ABANDONED));
+    for _ in 0..100 {
+        if !abandoned.exists() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(!abandoned.exists());
+    assert!(server.file(RECEIVING).exists());
+    server.stop().await;
+}
@@ -0,0 +1,411 @@
+//! Litmus-style WebDAV compliance checks for the operations rclone and OS clients
+//! (Finder, Windows Explorer, davfs2) rely on, run against a live cloud server with a
+//! temporary cloud folder.
+//!
+//! WebDAV is served by the Nextcloud compatibility layer, which mounts each cloud folder
+//! at `DAV_ROOT/{cloud_folder_name}` and takes the token as a Basic-auth app password.
+
+mod common;
+
+use common::{TestServer, CLOUD_FOLDER};
+
+const DAV_ROOT: &str = "/remote.php/webdav";
+
+fn dav(path: &str) -> String {
+    format!("{}/{}/{}", DAV_ROOT, CLOUD_FOLDER, path)
+}
+
+/// A test server with the Nextcloud compatibility layer, and so WebDAV, turned on
+async fn start_dav() -> TestServer {
+    TestServer::start_with(|cloud| cloud.nextcloud_compat = true).await
+}
+
+const PROPFIND_ALLPROP: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:propfind xmlns:D="DAV:"><D:allprop/></D:propfind>"#;
+
+/// Bodies of the `<d:response>` elements of a multistatus answer, with the `d:` prefix
+/// for `DAV:` that Nextcloud uses
+fn responses(xml: &str) -> Vec<&str> {
+    xml.split("<d:response>")
+        .skip(1)
+        .map(|part| part.split("</d:response>").next().unwrap_or(part))
+        .collect()
+}
+
+#[tokio::test]
+async fn harness_reaches_the_api() {
+    let server = TestServer::start().await;
+
+    let response = server.request("GET", "/api").send().await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.json()["status"], "running");
+    assert!(response.header("x-request-id").is_some());
+
+    let response = server.request("GET", "/api").anonymous().send().await;
+    assert_eq!(response.status, 401);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn options_advertises_class_1_and_2() {
+    let server = start_dav().await;
+
+    let response = server.dav_request("OPTIONS", &dav("")).send().await;
+    assert_eq!(response.status, 200);
+    let dav_header = response.header("dav").expect("DAV header");
+    assert!(dav_header.split(',').any(|class| class.trim() == "1"));
+    assert!(dav_header.split(',').any(|class| class.trim() == "2"));
+    let allow = response.header("allow").expect("Allow header");
+    for method in ["PROPFIND", "PUT", "MKCOL", "MOVE", "COPY", "DELETE"] {
+        assert!(allow.contains(method), "Allow is missing {}", method);
+    }
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn propfind_depth_0_describes_only_the_collection() {
+    let server = start_dav().await;
+    std::fs::write(server.file("a.txt"), "a").unwrap();
+
+    let response = server
+        .dav_request("PROPFIND", &dav(""))
+        .header("Depth", "0")
+        .header("Content-Type", "application/xml")
+        .body(PROPFIND_ALLPROP)
+        .send()
+        .await;
+    assert_eq!(response.status, 207);
+    let body = response.text();
+    assert!(body.contains("multistatus"));
+    assert!(body.contains("DAV:"));
+    assert_eq!(responses(&body).len(), 1);
+    assert!(body.contains("<d:collection/>"));
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn propfind_depth_1_lists_children_with_their_properties() {
+    let server = start_dav().await;
+    std::fs::write(server.file("a.txt"), "hello").unwrap();
+    std::fs::create_dir(server.file("sub")).unwrap();
+
+    let response = server
+        .dav_request("PROPFIND", &dav(""))
+        .header("Depth", "1")
+        .send()
+        .await;
+    assert_eq!(response.status, 207);
+    let body = response.text();
+    let responses = responses(&body);
+    assert_eq!(responses.len(), 3, "the collection and its two children");
+
+    let file = responses
+        .iter()
+        .find(|response| response.contains("a.txt"))
+        .expect("a.txt listed");
+    assert!(file.contains("<d:getcontentlength>5</d:getcontentlength>"));
+    assert!(file.contains("<d:getlastmodified>"));
+    assert!(file.contains("<d:resourcetype/>"));
+
+    let dir = responses
+        .iter()
+        .find(|response| response.contains("sub/"))
+        .expect("sub/ listed with a trailing slash");
+    assert!(dir.contains("<d:collection/>"));
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn propfind_infinity_is_refused() {
+    let server = start_dav().await;
+
+    let response = server
+        .dav_request("PROPFIND", &dav(""))
+        .header("Depth", "infinity")
+        .send()
+        .await;
+    assert_eq!(response.status, 403);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn propfind_on_a_missing_path_is_404() {
+    let server = start_dav().await;
+
+    let response = server
+        .dav_request("PROPFIND", &dav("missing.txt"))
+        .header("Depth", "0")
+        .send()
+        .await;
+    assert_eq!(response.status, 404);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn put_then_get_round_trips_and_overwrite_is_204() {
+    let server = start_dav().await;
+
+    let response = server
+        .dav_request("PUT", &dav("notes.txt"))
+        .body("first")
+        .send()
+        .await;
+    assert_eq!(response.status, 201);
+    assert_eq!(
+        std::fs::read_to_string(server.file("notes.txt")).unwrap(),
+        "first"
+    );
+
+    let response = server
+        .dav_request("PUT", &dav("notes.txt"))
+        .body("second")
+        .send()
+        .await;
+    assert_eq!(response.status, 204);
+
+    let response = server.dav_request("GET", &dav("notes.txt")).send().await;
+    assert_eq!(response.status, 200);
+    assert_eq!(response.text(), "second");
+    assert!(response.header("etag").is_some());
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn put_into_a_missing_collection_is_409() {
+    let server = start_dav().await;
+
+    let response = server
+        .dav_request("PUT", &dav("nope/notes.txt"))
+        .body("x")
+        .send()
+        .await;
+    assert_eq!(response.status, 409);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn put_keeps_the_x_oc_mtime() {
+    let server = start_dav().await;
+
+    let response = server
+        .dav_request("PUT", &dav("old.txt"))
+        .header("X-OC-Mtime", "1000000000")
+        .body("x")
+        .send()
+        .await;
+    assert!(response.status == 201 || response.status == 204);
+    assert_eq!(response.header("x-oc-mtime"), Some("accepted"));
+
+    let response = server
+        .dav_request("PROPFIND", &dav("old.txt"))
+        .header("Depth", "0")
+        .send()
+        .await;
+    assert!(response
+        .text()
+        .contains("<d:getlastmodified>Sun, 09 Sep 2001 01:46:40 GMT</d:getlastmodified>"));
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn mkcol_creates_once_and_needs_a_parent() {
+    let server = start_dav().await;
+
+    let response = server.dav_request("MKCOL", &dav("new")).send().await;
+    assert_eq!(response.status, 201);
+    assert!(server.file("new").is_dir());
+
+    let response = server.dav_request("MKCOL", &dav("new")).send().await;
+    assert_eq!(response.status, 405);
+
+    let response = server.dav_request("MKCOL", &dav("a/b/c")).send().await;
+    assert_eq!(response.status, 409);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn delete_removes_files_and_collections() {
+    let server = start_dav().await;
+    std::fs::write(server.file("gone.txt"), "x").unwrap();
+    std::fs::create_dir_all(server.file("dir/inner")).unwrap();
+
+    let response = server.dav_request("DELETE", &dav("gone.txt")).send().await;
+    assert_eq!(response.status, 204);
+    assert!(!server.file("gone.txt").exists());
+
+    let response = server.dav_request("DELETE", &dav("dir")).send().await;
+    assert_eq!(response.status, 204);
+    assert!(!server.file("dir").exists());
+
+    let response = server.dav_request("DELETE", &dav("gone.txt")).send().await;
+    assert_eq!(response.status, 404);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn move_honors_destination_and_overwrite() {
+    let server = start_dav().await;
+    std::fs::write(server.file("a.txt"), "a").unwrap();
+    std::fs::write(server.file("b.txt"), "b").unwrap();
+    let destination = |name: &str| format!("http://127.0.0.1:{}{}", server.port, dav(name));
+
+    let response = server
+        .dav_request("MOVE", &dav("a.txt"))
+        .header("Destination", &destination("b.txt"))
+        .header("Overwrite", "F")
+        .send()
+        .await;
+    assert_eq!(response.status, 412);
+    assert_eq!(std::fs::read_to_string(server.file("b.txt")).unwrap(), "b");
+
+    let response = server
+        .dav_request("MOVE", &dav("a.txt"))
+        .header("Destination", &destination("c.txt"))
+        .send()
+        .await;
+    assert_eq!(response.status, 201);
+    assert!(!server.file("a.txt").exists());
+    assert_eq!(std::fs::read_to_string(server.file("c.txt")).unwrap(), "a");
+
+    let response = server
+        .dav_request("MOVE", &dav("c.txt"))
+        .header("Destination", &destination("b.txt"))
+        .header("Overwrite", "T")
+        .send()
+        .await;
+    assert_eq!(response.status, 204);
+    assert_eq!(std::fs::read_to_string(server.file("b.txt")).unwrap(), "a");
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn copy_duplicates_collections_recursively() {
+    let server = start_dav().await;
+    std::fs::create_dir_all(server.file("src/inner")).unwrap();
+    std::fs::write(server.file("src/inner/x.txt"), "x").unwrap();
+
+    let response = server
+        .dav_request("COPY", &dav("src"))
+        .header(
+            "Destination",
+            &format!("http://127.0.0.1:{}{}", server.port, dav("dst")),
+        )
+        .header("Depth", "infinity")
+        .send()
+        .await;
+    assert_eq!(response.status, 201);
+    assert_eq!(
+        std::fs::read_to_string(server.file("dst/inner/x.txt")).unwrap(),
+        "x"
+    );
+    assert!(server.file("src/inner/x.txt").exists());
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn destination_outside_the_server_is_refused() {
+    let server = start_dav().await;
+    std::fs::write(server.file("a.txt"), "a").unwrap();
+
+    let response = server
+        .dav_request("MOVE", &dav("a.txt"))
+        .header("Destination", "http://elsewhere.example/dav/files/a.txt")
+        .send()
+        .await;
+    assert_eq!(response.status, 502);
+    assert!(server.file("a.txt").exists());
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn traversal_never_leaves_the_cloud_folder() {
+    let server = start_dav().await;
+
+    for path in [
+        "../escape.txt",
+        "%2e%2e/escape.txt",
+        "sub/..%2f..%2fescape.txt",
+    ] {
+        let response = server
+            .dav_request("PUT", &format!("{}/{}/{}", DAV_ROOT, CLOUD_FOLDER, path))
+            .body("x")
+            .send()
+            .await;
+        assert!(
+            !(200..300).contains(&response.status),
+            "{} was accepted",
+            path
+        );
+    }
+    assert!(!server
+        .folder
+        .path()
+        .parent()
+        .unwrap()
+        .join("escape.txt")
+        .exists());
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn lock_and_unlock_round_trip_for_os_clients() {
+    let server = start_dav().await;
+    std::fs::write(server.file("doc.txt"), "x").unwrap();
+
+    let response = server
+        .dav_request("LOCK", &dav("doc.txt"))
+        .header("Timeout", "Second-60")
+        .body(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<D:lockinfo xmlns:D="DAV:"><D:lockscope><D:exclusive/></D:lockscope><D:locktype><D:write/></D:locktype></D:lockinfo>"#,
+        )
+        .send()
+        .await;
+    assert_eq!(response.status, 200);
+    let token = response
+        .header("lock-token")
+        .expect("Lock-Token header")
+        .to_string();
+
+    let response = server
+        .dav_request("UNLOCK", &dav("doc.txt"))
+        .header("Lock-Token", &token)
+        .send()
+        .await;
+    assert_eq!(response.status, 204);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn dav_requires_authentication() {
+    let server = start_dav().await;
+
+    let response = server
+        .dav_request("PROPFIND", &dav(""))
+        .anonymous()
+        .header("Depth", "0")
+        .send()
+        .await;
+    assert_eq!(response.status, 401);
+    assert!(response
+        .header("www-authenticate")
+        .is_some_and(|value| value.starts_with("Basic")));
+
+    server.stop().await;
+}
@@ -0,0 +1,210 @@
+//! Webhooks against a local receiver: every delivery is signed with the hook's secret,
+//! and one the receiver fails is sent again.
+
+mod common;
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::Router;
+use cloudhost_server::webhooks::{DeliveryStatus, WebhookConfig, WebhookEvent};
+use common::orchestrator::TestOrchestrator;
+use common::request;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const SECRET: &str = "a secret of sixteen";
+
+/// A request the receiver got
+#[derive(Debug, Clone)]
+struct Received {
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl Received {
+    fn header(&self, name: &str) -> &str {
+        self.headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+    }
+
+    fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap()
+    }
+}
+
+#[derive(Clone, Default)]
+struct Receiver {
+    received: Arc<Mutex<Vec<Received>>>,
+    /// Requests to answer with a 500 before accepting them
+    failures: Arc<Mutex<usize>>,
+}
+
+impl Receiver {
+    /// Listen on a free port; returns the hook URL
+    async fn start(&self) -> String {
+        async fn receive(
+            State(receiver): State<Receiver>,
+            headers: HeaderMap,
+            body: Bytes,
+        ) -> StatusCode {
+            receiver
+                .received
+                .lock()
+                .unwrap()
+                .push(Received { headers, body });
+            let mut failures = receiver.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return StatusCode::INTERNAL_SERVER_ERROR;
+            }
+            StatusCode::NO_CONTENT
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let app = Router::new()
+            .route("/hook", post(receive))
+            .with_state(self.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        format!("http://127.0.0.1:{}/hook", port)
+    }
+
+    /// Wait until `count` requests came in
+    async fn wait_for(&self, count: usize, within: Duration) -> Vec<Received> {
+        let deadline = tokio::time::Instant::now() + within;
+        loop {
+            let received = self.received.lock().unwrap().clone();
+            if received.len() >= count || tokio::time::Instant::now() >= deadline {
+                return received;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+}
+
+/// A cloud `home` sending `file.uploaded` to `url`; returns its port and a login token
+async fn start_with_hook(harness: &mut TestOrchestrator, url: String) -> (u16, String) {
+    harness.add_cloud("home", &["documents"]);
+    harness.orchestrator.clouds_config.clouds[0].webhooks = vec![WebhookConfig {
+        url,
+        secret: SECRET.to_string(),
+        events: vec![WebhookEvent::FileUploaded],
+    }];
+    let port = harness.start("home").await;
+    let token = harness.login(port).await;
+    (port, token)
+}
+
+async fn upload(port: u16, token: &str, name: &str, contents: &[u8]) {
+    let response = request(port, "POST", "/api/upload/documents")
+        .bearer(token)
+        .file(name, contents)
+        .send()
+        .await;
+    assert_eq!(response.status, 200, "{}", response.text());
+}
+
+fn signature(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(body);
+    let hex: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("sha256={}", hex)
+}
+
+#[tokio::test]
+async fn deliveries_are_signed_with_the_hook_secret() {
+    let receiver = Receiver::default();
+    let url = receiver.start().await;
+    let mut harness = TestOrchestrator::new().await;
+    let (port, token) = start_with_hook(&mut harness, url).await;
+
+    upload(port, &token, "notes.txt", b"hello").await;
+    let received = receiver.wait_for(1, Duration::from_secs(5)).await;
+    assert_eq!(received.len(), 1);
+    let delivery = &received[0];
+    assert_eq!(delivery.header("content-type"), "application/json");
+    assert_eq!(delivery.header("x-cloudhost-event"), "file.uploaded");
+    assert_eq!(
+        delivery.header("x-cloudhost-signature"),
+        signature(SECRET, &delivery.body)
+    );
+    // A receiver with another secret can tell the body wasn't meant for it
+    assert_ne!(
+        delivery.header("x-cloudhost-signature"),
+        signature("another secret!!", &delivery.body)
+    );
+    let body = delivery.json();
+    assert_eq!(body["id"], delivery.header("x-cloudhost-delivery"));
+    assert_eq!(body["event"], "file.uploaded");
+    assert_eq!(body["data"]["path"], "documents/notes.txt");
+    assert_eq!(body["data"]["size"], 5);
+    assert_eq!(body["data"]["source"], "api");
+
+    // Events the hook didn't ask for aren't sent
+    let response = request(port, "DELETE", "/api/delete/documents/notes.txt")
+        .bearer(&token)
+        .send()
+        .await;
+    assert!(response.status < 300, "{}", response.text());
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(receiver.received.lock().unwrap().len(), 1);
+    harness.stop().await;
+}
+
+#[tokio::test]
+async fn failed_deliveries_are_retried() {
+    let receiver = Receiver::default();
+    *receiver.failures.lock().unwrap() = 1;
+    let url = receiver.start().await;
+    let mut harness = TestOrchestrator::new().await;
+    let (port, token) = start_with_hook(&mut harness, url).await;
+
+    upload(port, &token, "notes.txt", b"hello").await;
+    let received = receiver.wait_for(1, Duration::from_secs(5)).await;
+    assert_eq!(received.len(), 1);
+    // The first retry waits 5 seconds
+    let received = receiver.wait_for(2, Duration::from_secs(15)).await;
+    assert_eq!(received.len(), 2, "the failed delivery wasn't retried");
+    let (first, retry) = (&received[0], &received[1]);
+    assert_eq!(
+        retry.header("x-cloudhost-delivery"),
+        first.header("x-cloudhost-delivery")
+    );
+    assert_eq!(retry.body, first.body);
+    assert_eq!(
+        retry.header("x-cloudhost-signature"),
+        signature(SECRET, &retry.body)
+    );
+
+    // The receiver sees the retry just before the server hears back
+    let mut deliveries = Vec::new();
+    for _ in 0..100 {
+        deliveries = harness.orchestrator.webhook_deliveries("home").unwrap();
+        if deliveries
+            .iter()
+            .all(|delivery| delivery.status != DeliveryStatus::Pending)
+        {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(deliveries.len(), 1);
+    assert_eq!(deliveries[0].attempts, 2);
+    assert_eq!(deliveries[0].status, DeliveryStatus::Delivered(204));
+    assert_eq!(
+        deliveries[0].last_error.as_deref(),
+        Some("HTTP 500 Internal Server Error")
+    );
+    harness.stop().await;
+}
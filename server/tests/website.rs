@@ -0,0 +1,65 @@
+//! Public website folders: nothing outside the published folder is served, however a
+//! symlink points, and a folder whose drive went away answers 503.
+
+mod common;
+
+use cloudhost_server::cloud::WebsiteConfig;
+use common::orchestrator::TestOrchestrator;
+use common::{request, TempDir, TestServer, CLOUD_FOLDER};
+
+fn published() -> Option<WebsiteConfig> {
+    Some(WebsiteConfig {
+        directory_listing: false,
+    })
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn an_index_symlinked_out_of_the_folder_is_not_served() {
+    let outside = TempDir::new("outside");
+    std::fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+    let server = TestServer::start_with(|cloud| cloud.cloud_folders[0].website = published()).await;
+    std::fs::create_dir(server.file("blog")).unwrap();
+    std::os::unix::fs::symlink(
+        outside.path().join("secret.txt"),
+        server.file("blog/index.html"),
+    )
+    .unwrap();
+    std::fs::create_dir(server.file("about")).unwrap();
+    std::fs::write(server.file("about/index.html"), "about us").unwrap();
+
+    let response = request(server.port, "GET", &format!("/site/{}/blog/", CLOUD_FOLDER))
+        .send()
+        .await;
+    assert_eq!(response.status, 404);
+    assert!(!response.text().contains("secret"));
+    let response = request(
+        server.port,
+        "GET",
+        &format!("/site/{}/about/", CLOUD_FOLDER),
+    )
+    .send()
+    .await;
+    assert_eq!(response.text(), "about us");
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn an_unavailable_website_folder_answers_503() {
+    let mut harness = TestOrchestrator::new().await;
+    harness.add_cloud("home", &["site"]);
+    harness.orchestrator.clouds_config.clouds[0].cloud_folders[0].website = published();
+    let port = harness.start("home").await;
+    let site = harness.folder_path("site").to_path_buf();
+    std::fs::write(site.join("index.html"), "welcome").unwrap();
+    let response = request(port, "GET", "/site/site/").send().await;
+    assert_eq!(response.text(), "welcome");
+
+    let unplugged = site.with_extension("unplugged");
+    std::fs::rename(&site, &unplugged).unwrap();
+    harness.orchestrator.check_folder_health();
+    let response = request(port, "GET", "/site/site/").send().await;
+    assert_eq!(response.status, 503, "{}", response.text());
+    std::fs::rename(&unplugged, &site).unwrap();
+    harness.stop().await;
+}
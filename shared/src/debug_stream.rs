@@ -0,0 +1,79 @@
+//! Log messages as the server sends them: to the TUI through `DebugStream`, to log files
+//! one JSON object per line, and to `/api/logs/stream` as `LogFrame`s.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebugMessage {
+    pub timestamp: DateTime<Utc>,
+    pub level: LogLevel,
+    pub source: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Info,
+    #[serde(alias = "warn")]
+    Warning,
+    Error,
+    Debug,
+}
+
+impl LogLevel {
+    /// Debug < Info < Warning < Error
+    pub fn severity(&self) -> u8 {
+        match self {
+            LogLevel::Debug => 0,
+            LogLevel::Info => 1,
+            LogLevel::Warning => 2,
+            LogLevel::Error => 3,
+        }
+    }
+
+    /// Whether a message of this level passes a `min` level filter
+    pub fn at_least(&self, min: &LogLevel) -> bool {
+        self.severity() >= min.severity()
+    }
+}
+
+impl std::str::FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "debug" => Ok(LogLevel::Debug),
+            "info" => Ok(LogLevel::Info),
+            "warn" | "warning" => Ok(LogLevel::Warning),
+            "error" => Ok(LogLevel::Error),
+            _ => Err(format!(
+                "Unknown log level '{}' (expected debug, info, warn or error)",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogLevel::Info => write!(f, "INFO"),
+            LogLevel::Warning => write!(f, "WARN"),
+            LogLevel::Error => write!(f, "ERROR"),
+            LogLevel::Debug => write!(f, "DEBUG"),
+        }
+    }
+}
+
+/// One WebSocket text frame of `/api/logs/stream`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum LogFrame {
+    Log(DebugMessage),
+    /// The client fell behind and `skipped` messages were dropped
+    Lagged {
+        skipped: u64,
+    },
+}
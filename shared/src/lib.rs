@@ -0,0 +1,6 @@
+//! Types the CloudHost server, the TUI and clients exchange, kept here so each side
+//! works with the same definitions instead of its own copy.
+
+pub mod debug_stream;
+
+pub use debug_stream::{DebugMessage, LogFrame, LogLevel};
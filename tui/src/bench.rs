@@ -0,0 +1,133 @@
+//! `cloudhost-tui bench`: listing latency and download throughput of a running cloud,
+//! measured through the API as a client sees them, network included. For numbers of the
+//! server alone, there are the criterion benchmarks of the server crate.
+use clap::Args;
+use cloudhost_client::{CloudHostClient, RetryPolicy};
+use color_eyre::{eyre::eyre, Result};
+use futures_util::StreamExt;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+#[derive(Args, Debug)]
+pub struct BenchArgs {
+    /// URL of the cloud, e.g. http://localhost:3000
+    url: String,
+
+    /// Cloud password; asked for if neither this nor CLOUDHOST_PASSWORD is set
+    #[arg(long)]
+    password: Option<String>,
+
+    /// Cloud folder to list and download from (default: the cloud's first)
+    #[arg(long)]
+    folder: Option<String>,
+
+    /// Directory inside the cloud folder to list
+    #[arg(long, default_value = "")]
+    path: String,
+
+    /// File to download (default: the largest file of the listed directory)
+    #[arg(long)]
+    file: Option<String>,
+
+    /// Times each request is made
+    #[arg(long, default_value_t = 10)]
+    runs: usize,
+}
+
+/// Fastest, median and slowest of `times`
+fn summary(times: &mut [Duration]) -> (Duration, Duration, Duration) {
+    times.sort();
+    (times[0], times[times.len() / 2], times[times.len() - 1])
+}
+
+fn read_password() -> Result<String> {
+    if let Ok(password) = std::env::var("CLOUDHOST_PASSWORD") {
+        return Ok(password);
+    }
+    print!("Password: ");
+    std::io::stdout().flush()?;
+    let mut password = String::new();
+    std::io::stdin().read_line(&mut password)?;
+    Ok(password.trim_end_matches(['\r', '\n']).to_string())
+}
+
+pub async fn run(args: BenchArgs) -> Result<()> {
+    let runs = args.runs.max(1);
+    // A retried request would count its retries' time
+    let client = CloudHostClient::with_retry_policy(&args.url, RetryPolicy::none())?;
+    let password = match args.password {
+        Some(password) => password,
+        None => read_password()?,
+    };
+    client.login(&password).await?;
+    let folder = match args.folder {
+        Some(folder) => folder,
+        None => client
+            .cloud_folders()
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| eyre!("The cloud has no cloud folders"))?,
+    };
+
+    let directory = match args.path.trim_matches('/') {
+        "" => folder.clone(),
+        path => format!("{}/{}", folder, path),
+    };
+
+    let mut times = Vec::with_capacity(runs);
+    let mut entries = Vec::new();
+    for _ in 0..runs {
+        let started = Instant::now();
+        entries = client.list_dir(&folder, &args.path).await?;
+        times.push(started.elapsed());
+    }
+    let (fastest, median, slowest) = summary(&mut times);
+    println!(
+        "Listing {} ({} entries): median {:.2?}, fastest {:.2?}, slowest {:.2?}",
+        directory,
+        entries.len(),
+        median,
+        fastest,
+        slowest
+    );
+
+    let file = match args.file {
+        Some(file) => file,
+        None => match entries
+            .iter()
+            .filter(|entry| !entry.is_dir())
+            .max_by_key(|entry| entry.size)
+        {
+            Some(entry) => entry.path.clone(),
+            None => {
+                println!("No file to download in {}", directory);
+                return Ok(());
+            }
+        },
+    };
+    let mut times = Vec::with_capacity(runs);
+    let mut bytes = 0u64;
+    for _ in 0..runs {
+        let started = Instant::now();
+        let mut stream = Box::pin(client.download_stream(&folder, &file).await?);
+        bytes = 0;
+        while let Some(chunk) = stream.next().await {
+            bytes += chunk?.len() as u64;
+        }
+        times.push(started.elapsed());
+    }
+    let (fastest, median, slowest) = summary(&mut times);
+    let mib_per_second =
+        |time: Duration| bytes as f64 / (1024.0 * 1024.0) / time.as_secs_f64().max(f64::EPSILON);
+    println!(
+        "Download {}/{} ({} bytes): median {:.1} MiB/s, fastest {:.1} MiB/s, slowest {:.1} MiB/s",
+        folder,
+        file,
+        bytes,
+        mib_per_second(median),
+        mib_per_second(fastest),
+        mib_per_second(slowest)
+    );
+    Ok(())
+}
@@ -0,0 +1,367 @@
+//! `cloudhost-tui cloud|folder|password|start`: the clouds config and the clouds, without
+//! the TUI, for scripted setups. They work on the same config files as the TUI, and `start`
+//! serves clouds in the foreground until Ctrl+C. `--config-dir` moves those files.
+use clap::{Args, Subcommand};
+use cloudhost_server::{
+    config_paths, folder_health, Cloud, CloudFolder, Orchestrator, SecretsProtection,
+};
+use color_eyre::{eyre::eyre, Result};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const MIN_PASSWORD_LEN: usize = 8;
+
+#[derive(Subcommand, Debug)]
+pub enum CloudCommand {
+    /// Add a cloud sharing existing cloud folders
+    Add {
+        name: String,
+        /// Cloud folder to share, repeated for each
+        #[arg(long = "folder", value_name = "FOLDER", required = true)]
+        folders: Vec<String>,
+        /// Tag to group the cloud by, repeated for each
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+    },
+    /// List the clouds with their folders and tags
+    List,
+    /// Remove a cloud; its folders and their files stay
+    Remove { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum FolderCommand {
+    /// Add a directory as a cloud folder
+    Add { name: String, path: PathBuf },
+    /// List the cloud folders with their paths
+    List,
+    /// Remove a cloud folder from the config; its files stay
+    Remove { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PasswordCommand {
+    /// Set the password of a cloud, from CLOUDHOST_PASSWORD, a prompt or the first line
+    /// of standard input
+    Set { cloud: String },
+}
+
+#[derive(Args, Debug)]
+pub struct StartArgs {
+    /// Clouds to serve until Ctrl+C
+    #[arg(required = true)]
+    clouds: Vec<String>,
+}
+
+/// The orchestrator of the clouds config, refusing a config that didn't load so a broken
+/// file isn't overwritten
+fn open_orchestrator() -> Result<Orchestrator> {
+    let mut orchestrator = Orchestrator::new();
+    if let Some(e) = orchestrator.take_config_error() {
+        return Err(eyre!("{}", e));
+    }
+    Ok(orchestrator)
+}
+
+/// Unlock encrypted cloud secrets with the keyring or CLOUDHOST_SECRETS_PASSPHRASE
+fn unlock_secrets(orchestrator: &mut Orchestrator) -> Result<()> {
+    if !orchestrator.secrets_locked() {
+        return Ok(());
+    }
+    match orchestrator.secrets_protection() {
+        Some(SecretsProtection::Keyring) => orchestrator.unlock_secrets_with_keyring()?,
+        _ => {
+            let passphrase =
+                std::env::var(crate::models::SECRETS_PASSPHRASE_ENV).map_err(|_| {
+                    eyre!(
+                        "Cloud secrets are encrypted with a passphrase, set {} to unlock them",
+                        crate::models::SECRETS_PASSPHRASE_ENV
+                    )
+                })?;
+            orchestrator.unlock_secrets_with_passphrase(&passphrase)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn run_cloud(orchestrator: &mut Orchestrator, command: CloudCommand) -> Result<String> {
+    match command {
+        CloudCommand::Add {
+            name,
+            folders,
+            tags,
+        } => {
+            let all_folders = orchestrator.get_cloud_folders();
+            let folders = folders
+                .iter()
+                .map(|folder_name| {
+                    all_folders
+                        .iter()
+                        .find(|folder| &folder.name == folder_name)
+                        .cloned()
+                        .ok_or_else(|| eyre!("No cloud folder named '{}'", folder_name))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            orchestrator.add_cloud(Cloud::new(name.clone(), folders))?;
+            if !tags.is_empty() {
+                orchestrator.set_cloud_tags(&name, tags)?;
+            }
+            Ok(format!(
+                "Added cloud '{}', set its password with: cloudhost-tui password set {}",
+                name, name
+            ))
+        }
+        CloudCommand::List => {
+            let lines: Vec<String> = orchestrator
+                .get_clouds()
+                .iter()
+                .map(|cloud| {
+                    let folders: Vec<&str> = cloud
+                        .cloud_folders
+                        .iter()
+                        .map(|folder| folder.name.as_str())
+                        .collect();
+                    let mut line = format!("{}\t{}", cloud.name, folders.join(","));
+                    if !cloud.tags.is_empty() {
+                        line.push_str(&format!("\t#{}", cloud.tags.join(" #")));
+                    }
+                    if !cloud.has_password() {
+                        line.push_str("\t(no password)");
+                    }
+                    line
+                })
+                .collect();
+            Ok(lines.join("\n"))
+        }
+        CloudCommand::Remove { name } => {
+            orchestrator.remove_cloud(&name)?;
+            Ok(format!("Removed cloud '{}'", name))
+        }
+    }
+}
+
+pub fn run_folder(orchestrator: &mut Orchestrator, command: FolderCommand) -> Result<String> {
+    match command {
+        FolderCommand::Add { name, path } => {
+            let path = absolute_dir(&path)?;
+            orchestrator.add_cloud_folder(CloudFolder::new(name.clone(), path.clone()))?;
+            Ok(format!("Added folder '{}' at {}", name, path.display()))
+        }
+        FolderCommand::List => {
+            let lines: Vec<String> = orchestrator
+                .get_cloud_folders()
+                .iter()
+                .map(|folder| format!("{}\t{}", folder.name, folder.folder_path.display()))
+                .collect();
+            Ok(lines.join("\n"))
+        }
+        FolderCommand::Remove { name } => {
+            orchestrator.remove_cloud_folder(&name)?;
+            Ok(format!("Removed folder '{}'", name))
+        }
+    }
+}
+
+pub fn run_password(
+    orchestrator: &mut Orchestrator,
+    command: PasswordCommand,
+    password: &str,
+) -> Result<String> {
+    match command {
+        PasswordCommand::Set { cloud } => {
+            if password.len() < MIN_PASSWORD_LEN {
+                return Err(eyre!(
+                    "Password must be at least {} characters",
+                    MIN_PASSWORD_LEN
+                ));
+            }
+            orchestrator.set_cloud_password(&cloud, password)?;
+            Ok(format!("Set the password of cloud '{}'", cloud))
+        }
+    }
+}
+
+/// `path` as an absolute path, if it is a directory
+fn absolute_dir(path: &Path) -> Result<PathBuf> {
+    if !path.is_dir() {
+        return Err(eyre!("'{}' is not a directory", path.display()));
+    }
+    Ok(std::fs::canonicalize(path)?)
+}
+
+/// `--config-dir`: keep the configs in `dir` from now on, offering to move those of the
+/// current config directory there first. Applies to this run too.
+pub fn change_config_dir(dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let dir = std::fs::canonicalize(dir)?;
+    // Profiles live in the base directory and move along with it
+    let current = config_paths::get_base_config_dir();
+    let same = std::fs::canonicalize(&current).is_ok_and(|current| current == dir);
+
+    let movable = config_paths::movable_config_entries(&current, &dir);
+    if !same && !movable.is_empty() {
+        println!("{} holds {}", current.display(), movable.join(", "));
+        if confirm(&format!("Move them to {}? [y/N] ", dir.display()))? {
+            let moved = config_paths::move_config_entries(&current, &dir)?;
+            println!("Moved {} to {}", moved.join(", "), dir.display());
+        } else {
+            println!("Left them in {}", current.display());
+        }
+    }
+
+    config_paths::set_config_dir(&dir)?;
+    std::env::set_var(config_paths::CONFIG_DIR_VAR, &dir);
+    if config_paths::is_dev_mode() {
+        println!("Configs are kept in {} when not in dev mode", dir.display());
+    } else {
+        println!("Configs are kept in {} from now on", dir.display());
+    }
+    Ok(())
+}
+
+/// Whether the answer to `prompt` on standard input is yes
+fn confirm(prompt: &str) -> Result<bool> {
+    print!("{}", prompt);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// The new password: CLOUDHOST_PASSWORD, typed twice at a terminal, or piped in
+fn read_new_password() -> Result<String> {
+    if let Ok(password) = std::env::var("CLOUDHOST_PASSWORD") {
+        return Ok(password);
+    }
+    let read_line = |prompt: &str| -> Result<String> {
+        let stdin = std::io::stdin();
+        if stdin.is_terminal() {
+            print!("{}", prompt);
+            std::io::stdout().flush()?;
+        }
+        let mut line = String::new();
+        stdin.read_line(&mut line)?;
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    };
+    let password = read_line("Password: ")?;
+    if std::io::stdin().is_terminal() && read_line("Confirm password: ")? != password {
+        return Err(eyre!("Passwords do not match"));
+    }
+    Ok(password)
+}
+
+pub async fn cloud(command: CloudCommand) -> Result<()> {
+    let mut orchestrator = open_orchestrator()?;
+    if !matches!(command, CloudCommand::List) {
+        unlock_secrets(&mut orchestrator)?;
+    }
+    print_output(run_cloud(&mut orchestrator, command)?);
+    Ok(())
+}
+
+pub async fn folder(command: FolderCommand) -> Result<()> {
+    let mut orchestrator = open_orchestrator()?;
+    if !matches!(command, FolderCommand::List) {
+        unlock_secrets(&mut orchestrator)?;
+    }
+    print_output(run_folder(&mut orchestrator, command)?);
+    Ok(())
+}
+
+pub async fn password(command: PasswordCommand) -> Result<()> {
+    let mut orchestrator = open_orchestrator()?;
+    unlock_secrets(&mut orchestrator)?;
+    let password = read_new_password()?;
+    print_output(run_password(&mut orchestrator, command, &password)?);
+    Ok(())
+}
+
+fn print_output(output: String) {
+    if !output.is_empty() {
+        println!("{}", output);
+    }
+}
+
+/// Serve `args.clouds` and the landing page, printing their logs, until Ctrl+C
+pub async fn start(args: StartArgs) -> Result<()> {
+    let mut orchestrator = open_orchestrator()?;
+    unlock_secrets(&mut orchestrator)?;
+
+    for name in &args.clouds {
+        if let Err(e) = orchestrator.start_cloud(name).await {
+            let _ = orchestrator.stop_all().await;
+            return Err(eyre!("Cloud '{}' didn't start: {}", name, e));
+        }
+        let url = orchestrator.get_cloud_server_url(name).unwrap_or_default();
+        println!("Cloud '{}' is running at {}", name, url);
+        if let Some(mut logs) = orchestrator.subscribe_cloud_logs(name) {
+            let name = name.clone();
+            tokio::spawn(async move {
+                loop {
+                    match logs.recv().await {
+                        Ok(message) => println!(
+                            "{} [{}] {} {}: {}",
+                            message.timestamp.format("%H:%M:%S"),
+                            name,
+                            message.level,
+                            message.source,
+                            message.message
+                        ),
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            });
+        }
+    }
+    match orchestrator.start_landing().await {
+        Ok(Some(port)) => println!("Landing page is running on port {}", port),
+        Ok(None) => {}
+        Err(e) => eprintln!("Landing page didn't start: {}", e),
+    }
+    println!("Press Ctrl+C to stop");
+
+    // What the TUI does on its ticks: save what the servers changed, run scheduled tasks
+    // and check the folders are still there
+    let mut tick = tokio::time::interval(Duration::from_secs(1));
+    let mut folder_health = tokio::time::interval(folder_health::CHECK_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            _ = folder_health.tick() => {
+                for change in orchestrator.check_folder_health() {
+                    match change.problem {
+                        Some(problem) => eprintln!(
+                            "Cloud folder '{}' is unavailable: {}",
+                            change.folder, problem
+                        ),
+                        None => println!("Cloud folder '{}' is available again", change.folder),
+                    }
+                }
+            }
+            _ = tick.tick() => {
+                let saved = [
+                    orchestrator.persist_password_upgrades(),
+                    orchestrator.persist_recovered_passwords(),
+                    orchestrator.persist_api_token_changes(),
+                    orchestrator.persist_revoked_sessions(),
+                    orchestrator.persist_file_request_changes(),
+                ];
+                for result in saved {
+                    if let Err(e) = result {
+                        eprintln!("Failed to save the clouds config: {}", e);
+                    }
+                }
+                orchestrator.refresh_landing();
+                for task in orchestrator.run_due_tasks() {
+                    println!("Started scheduled task {}", task);
+                }
+            }
+        }
+    }
+
+    println!("Stopping...");
+    orchestrator.stop_all().await?;
+    Ok(())
+}
@@ -0,0 +1,131 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap},
+};
+
+use crate::components::text_input::input_line;
+use crate::utils::password::{ChangePasswordState, ChangePasswordStep};
+
+/// Renders the change password modal: current password, new password, confirmation,
+/// and a warning that every session of the cloud gets logged out
+pub fn render_change_password_modal(
+    cloud_name: &str,
+    state: &ChangePasswordState,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let modal_width = 60.min(area.width.saturating_sub(4));
+    let modal_height = 16.min(area.height.saturating_sub(4));
+
+    let modal_area = Rect {
+        x: (area.width.saturating_sub(modal_width)) / 2,
+        y: (area.height.saturating_sub(modal_height)) / 2,
+        width: modal_width,
+        height: modal_height,
+    };
+
+    Clear.render(modal_area, buf);
+
+    let modal_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2), // Border and spacing
+            Constraint::Length(2), // Cloud name
+            Constraint::Length(1), // Current password
+            Constraint::Length(1), // New password
+            Constraint::Length(2), // Confirmation
+            Constraint::Length(3), // Warning
+            Constraint::Length(2), // Error/Help
+            Constraint::Min(0),
+        ])
+        .split(modal_area);
+
+    Paragraph::new("")
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("🔑 Change Cloud Password")
+                .title_alignment(Alignment::Center)
+                .border_style(
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+        )
+        .render(modal_area, buf);
+
+    Paragraph::new(format!("Cloud: {}", cloud_name))
+        .style(Style::default().fg(Color::Cyan))
+        .alignment(Alignment::Center)
+        .render(modal_chunks[1], buf);
+
+    let fields = [
+        (
+            "Current:",
+            &state.current_password,
+            ChangePasswordStep::Current,
+        ),
+        ("New:", &state.new_password, ChangePasswordStep::New),
+        (
+            "Confirm:",
+            &state.confirm_password,
+            ChangePasswordStep::Confirm,
+        ),
+    ];
+    for ((label, value, step), chunk) in fields.into_iter().zip(&modal_chunks[2..5]) {
+        let style = if state.step == step {
+            Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        Paragraph::new(input_line(
+            &format!("{:<9}", label),
+            value,
+            state.step == step,
+            Some('*'),
+            chunk.width,
+        ))
+        .style(style)
+        .alignment(Alignment::Center)
+        .render(*chunk, buf);
+    }
+
+    Paragraph::new(
+        "⚠ Every browser session, API client and Nextcloud app logged in to this cloud will be logged out. API tokens keep working.",
+    )
+    .style(Style::default().fg(Color::Yellow))
+    .alignment(Alignment::Center)
+    .wrap(Wrap { trim: true })
+    .render(
+        Rect {
+            x: modal_chunks[5].x + 2,
+            width: modal_chunks[5].width.saturating_sub(4),
+            ..modal_chunks[5]
+        },
+        buf,
+    );
+
+    let (message, style) = match &state.error {
+        Some(error) => (
+            format!("❌ {}", error),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ),
+        None => (
+            match state.step {
+                ChangePasswordStep::Current => "Enter the current password and press Enter",
+                ChangePasswordStep::New => "Enter the new password (min 8 characters)",
+                ChangePasswordStep::Confirm => "Confirm the new password and press Enter",
+            }
+            .to_string(),
+            Style::default().fg(Color::Gray),
+        ),
+    };
+    Paragraph::new(message)
+        .style(style)
+        .alignment(Alignment::Center)
+        .render(modal_chunks[6], buf);
+}
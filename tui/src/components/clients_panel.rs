@@ -0,0 +1,88 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+
+use crate::utils::jobs::format_bytes;
+use cloudhost_server::clients::ClientInfo;
+
+fn client_line(client: &ClientInfo, selected: bool) -> Line<'static> {
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut ip_style = Style::default().fg(Color::Cyan);
+    if selected {
+        ip_style = ip_style.add_modifier(Modifier::BOLD | Modifier::REVERSED);
+    }
+    Line::from(vec![
+        Span::styled(format!("{:<16}", client.ip), ip_style),
+        Span::raw(format!(
+            " {:<10}",
+            client.user.as_deref().unwrap_or("anonymous")
+        )),
+        Span::styled(format!(" {:<6}", client.method), dim),
+        Span::raw(format!(" {:<28}", client.path)),
+        Span::raw(format!(" {:>9}", format_bytes(client.bytes))),
+        Span::styled(
+            format!(
+                " {:>11}",
+                format!("{}/s", format_bytes(client.bytes_per_sec))
+            ),
+            Style::default().fg(Color::Green),
+        ),
+        Span::styled(format!(" {}s", client.duration_secs), dim),
+    ])
+}
+
+/// Renders the clients a cloud is serving, with what they transfer and how fast
+pub fn render_clients_panel(
+    cloud: &str,
+    clients: &[ClientInfo],
+    selected: usize,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let modal_width = 100.min(area.width);
+    let modal_height = (clients.len() as u16 + 2).max(6).min(area.height);
+    let modal_area = Rect::new(
+        area.x + (area.width.saturating_sub(modal_width)) / 2,
+        area.y + (area.height.saturating_sub(modal_height)) / 2,
+        modal_width,
+        modal_height,
+    );
+
+    Clear.render(modal_area, buf);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("🔌 Clients of '{}'", cloud))
+        .title_alignment(Alignment::Center)
+        .title_bottom(Line::from(" j/k to select, x to kill, Esc to close ").centered())
+        .border_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let lines: Vec<Line> = if clients.is_empty() {
+        vec![Line::styled(
+            "No clients connected",
+            Style::default().fg(Color::DarkGray),
+        )]
+    } else {
+        clients
+            .iter()
+            .enumerate()
+            .map(|(index, client)| client_line(client, index == selected))
+            .collect()
+    };
+    // Keep the selected client in view
+    let inner_height = modal_height.saturating_sub(2);
+    let scroll = (selected as u16 + 1).saturating_sub(inner_height);
+
+    Paragraph::new(lines)
+        .block(block)
+        .scroll((scroll, 0))
+        .render(modal_area, buf);
+}
@@ -0,0 +1,61 @@
+use ratatui::{
+    style::{Modifier, Style},
+    text::Span,
+};
+
+use cloudhost_server::cloud::CloudStatus;
+
+use crate::theme::Theme;
+
+/// The glyph for a cloud's state, followed by its name, in the lists of clouds
+pub fn cloud_status_glyph(status: &CloudStatus) -> &'static str {
+    match status {
+        CloudStatus::Running => "🟢",
+        CloudStatus::Starting => "🟡",
+        CloudStatus::Stopped => "🔴",
+        CloudStatus::Failed(_) => "❌",
+    }
+}
+
+/// A list entry for a cloud: its state, its name, and the port it's on or a badge when it
+/// failed to start, then a badge when some of its folders are unavailable
+pub fn cloud_list_spans(
+    theme: &Theme,
+    name: &str,
+    status: &CloudStatus,
+    port: Option<u16>,
+    degraded: bool,
+) -> Vec<Span<'static>> {
+    let mut spans = vec![
+        Span::raw(format!("{} ", cloud_status_glyph(status))),
+        Span::raw(name.to_string()),
+    ];
+    match status {
+        CloudStatus::Running | CloudStatus::Starting => {
+            if let Some(port) = port {
+                spans.push(Span::styled(
+                    format!(" :{}", port),
+                    Style::default().fg(if *status == CloudStatus::Running {
+                        theme.success
+                    } else {
+                        theme.warning
+                    }),
+                ));
+            }
+        }
+        CloudStatus::Failed(_) => spans.push(Span::styled(
+            " failed",
+            Style::default()
+                .fg(theme.error)
+                .add_modifier(Modifier::BOLD),
+        )),
+        CloudStatus::Stopped => {}
+    }
+    if degraded {
+        spans.push(Span::styled(
+            " degraded",
+            Style::default().fg(theme.warning),
+        ));
+    }
+    spans
+}
@@ -0,0 +1,70 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Widget},
+};
+
+use crate::utils::command_line::CommandLineState;
+
+/// Max completion candidates shown above the command line
+const MAX_VISIBLE_COMPLETIONS: usize = 8;
+
+/// Renders the `:` command line in the footer area, with completions above it
+pub fn render_command_line(state: &CommandLineState, footer_area: Rect, buf: &mut Buffer) {
+    Clear.render(footer_area, buf);
+
+    let line = Line::from(vec![
+        Span::styled(":", Style::default().fg(Color::Yellow)),
+        Span::raw(state.input.as_str()),
+        Span::styled("█", Style::default().fg(Color::Gray)),
+    ]);
+    Paragraph::new(line).render(footer_area, buf);
+
+    if state.completions.is_empty() {
+        return;
+    }
+
+    // Scroll the window so the selected candidate stays visible
+    let selected = state.selected_completion().unwrap_or(0);
+    let skip = selected.saturating_sub(MAX_VISIBLE_COMPLETIONS - 1);
+    let visible: Vec<ListItem> = state
+        .completions
+        .iter()
+        .enumerate()
+        .skip(skip)
+        .take(MAX_VISIBLE_COMPLETIONS)
+        .map(|(i, candidate)| {
+            let style = if Some(i) == state.selected_completion() {
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            ListItem::new(candidate.as_str()).style(style)
+        })
+        .collect();
+
+    let width = state
+        .completions
+        .iter()
+        .map(|c| c.chars().count() as u16)
+        .max()
+        .unwrap_or(0)
+        .saturating_add(4)
+        .min(footer_area.width);
+    let height = (visible.len() as u16 + 2).min(footer_area.y);
+    let popup_area = Rect::new(footer_area.x, footer_area.y - height, width, height);
+
+    Clear.render(popup_area, buf);
+    List::new(visible)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        )
+        .render(popup_area, buf);
+}
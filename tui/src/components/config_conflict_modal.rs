@@ -0,0 +1,58 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap},
+};
+
+/// Asks how to settle unsaved changes when the clouds config changed on disk
+pub fn render_config_conflict_modal(area: Rect, buf: &mut Buffer) {
+    let modal_width = 64.min(area.width.saturating_sub(4));
+    let modal_height = 12.min(area.height.saturating_sub(2));
+    let modal_area = Rect::new(
+        area.x + (area.width.saturating_sub(modal_width)) / 2,
+        area.y + (area.height.saturating_sub(modal_height)) / 2,
+        modal_width,
+        modal_height,
+    );
+
+    Clear.render(modal_area, buf);
+
+    let key = Style::default()
+        .fg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+    let lines = vec![
+        Line::from("clouds-config.toml was changed by another program"),
+        Line::from("(e.g. a text editor or a headless CloudHost) since it was loaded."),
+        Line::from("Your last change has not been saved yet."),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("m", key),
+            Span::raw("  Merge: apply your changes on top of the file"),
+        ]),
+        Line::from(vec![
+            Span::styled("o", key),
+            Span::raw("  Overwrite the file with your version"),
+        ]),
+        Line::from(vec![
+            Span::styled("r", key),
+            Span::raw("  Reload the file and drop your changes"),
+        ]),
+    ];
+
+    Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("⚠️  Clouds Config Changed on Disk")
+                .title_alignment(Alignment::Center)
+                .border_style(
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+        )
+        .wrap(Wrap { trim: false })
+        .render(modal_area, buf);
+}
@@ -0,0 +1,79 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+
+use crate::theme::Theme;
+use crate::utils::confirmation::Confirmation;
+
+/// Renders a yes/no question before a destructive action, with what it affects below it
+pub fn render_confirm_modal(
+    theme: &Theme,
+    confirmation: &Confirmation,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    // Create a centered modal, taller for each line of details
+    let details_height = confirmation.details.len() as u16;
+    let modal_width = 60.min(area.width);
+    let modal_height = (7 + details_height).min(area.height);
+    let x = (area.width.saturating_sub(modal_width)) / 2;
+    let y = (area.height.saturating_sub(modal_height)) / 2;
+
+    let modal_area = Rect::new(area.x + x, area.y + y, modal_width, modal_height);
+
+    // Clear the modal area
+    Clear.render(modal_area, buf);
+
+    let modal_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),              // Border
+            Constraint::Length(2),              // Question
+            Constraint::Length(details_height), // Details
+            Constraint::Length(2),              // Instructions
+            Constraint::Min(0),                 // Spacer
+        ])
+        .split(modal_area);
+
+    let title_block = Block::default()
+        .borders(Borders::ALL)
+        .title(confirmation.title())
+        .title_alignment(Alignment::Center)
+        .border_style(
+            Style::default()
+                .fg(theme.focused_border)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    Paragraph::new("")
+        .block(title_block)
+        .render(modal_area, buf);
+
+    Paragraph::new(confirmation.question())
+        .style(
+            Style::default()
+                .fg(theme.error)
+                .add_modifier(Modifier::BOLD),
+        )
+        .alignment(Alignment::Center)
+        .render(modal_chunks[1], buf);
+
+    let details: Vec<Line> = confirmation
+        .details
+        .iter()
+        .map(|line| Line::from(line.as_str()))
+        .collect();
+    Paragraph::new(details)
+        .style(Style::default().fg(theme.text))
+        .alignment(Alignment::Center)
+        .render(modal_chunks[2], buf);
+
+    Paragraph::new(confirmation.hint())
+        .style(Style::default().fg(theme.info))
+        .alignment(Alignment::Center)
+        .render(modal_chunks[3], buf);
+}
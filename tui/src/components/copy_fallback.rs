@@ -0,0 +1,55 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap},
+};
+
+use crate::theme::Theme;
+use crate::utils::clipboard::CopyFallback;
+
+/// Shows text that couldn't be put on the clipboard, to be selected with the mouse instead
+pub fn render_copy_fallback(theme: &Theme, fallback: &CopyFallback, area: Rect, buf: &mut Buffer) {
+    let text_lines = fallback.text.lines().count() as u16;
+    let modal_width = 80.min(area.width);
+    let modal_height = (text_lines + 6).min(area.height);
+    let modal_area = Rect::new(
+        area.x + (area.width.saturating_sub(modal_width)) / 2,
+        area.y + (area.height.saturating_sub(modal_height)) / 2,
+        modal_width,
+        modal_height,
+    );
+
+    Clear.render(modal_area, buf);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("📋 Copy the {}", fallback.what))
+        .title_alignment(Alignment::Center)
+        .title_bottom(Line::from(" Esc to close ").centered())
+        .border_style(
+            Style::default()
+                .fg(theme.focused_border)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let mut lines = vec![
+        Line::styled(
+            format!("Not copied ({}), select it yourself:", fallback.reason),
+            Style::default().fg(theme.hint),
+        ),
+        Line::raw(""),
+    ];
+    lines.extend(fallback.text.lines().map(|line| {
+        Line::styled(
+            line.to_string(),
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+        )
+    }));
+
+    Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .render(modal_area, buf);
+}
@@ -0,0 +1,91 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+
+use crate::components::text_input::input_line;
+use crate::theme::Theme;
+use crate::utils::server_defaults::DefaultEditor;
+
+/// Prompt for a new value of a server default, with what it takes and when it applies
+pub fn render_default_editor_modal(
+    theme: &Theme,
+    editor: &DefaultEditor,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let modal_width = 64.min(area.width);
+    let modal_height = 10.min(area.height);
+    let modal_area = Rect::new(
+        area.x + (area.width.saturating_sub(modal_width)) / 2,
+        area.y + (area.height.saturating_sub(modal_height)) / 2,
+        modal_width,
+        modal_height,
+    );
+
+    Clear.render(modal_area, buf);
+    Block::default()
+        .borders(Borders::ALL)
+        .title(format!("🛠️  {}", editor.field.label()))
+        .title_alignment(Alignment::Center)
+        .border_style(
+            Style::default()
+                .fg(theme.focused_border)
+                .add_modifier(Modifier::BOLD),
+        )
+        .render(modal_area, buf);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(2), // What to type
+            Constraint::Length(2), // Input
+            Constraint::Length(2), // When it applies
+            Constraint::Length(2), // Error/Help
+        ])
+        .split(modal_area);
+
+    Paragraph::new(editor.field.help())
+        .style(Style::default().fg(theme.info))
+        .alignment(Alignment::Center)
+        .render(chunks[0], buf);
+
+    Paragraph::new(input_line("", &editor.input, true, None, chunks[1].width))
+        .style(
+            Style::default()
+                .fg(theme.success)
+                .add_modifier(Modifier::BOLD),
+        )
+        .alignment(Alignment::Center)
+        .render(chunks[1], buf);
+
+    let applies = if editor.field.applies_right_away() {
+        "Running clouds take it right away"
+    } else {
+        "Clouds take it the next time they start"
+    };
+    Paragraph::new(applies)
+        .style(Style::default().fg(theme.dim))
+        .alignment(Alignment::Center)
+        .render(chunks[2], buf);
+
+    let (text, style) = match &editor.error {
+        Some(error) => (
+            format!("❌ {}", error),
+            Style::default()
+                .fg(theme.error)
+                .add_modifier(Modifier::BOLD),
+        ),
+        None => (
+            "Enter to save, Esc to cancel".to_string(),
+            Style::default().fg(theme.hint),
+        ),
+    };
+    Paragraph::new(text)
+        .style(style)
+        .alignment(Alignment::Center)
+        .render(chunks[3], buf);
+}
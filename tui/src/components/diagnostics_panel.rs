@@ -0,0 +1,84 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap},
+};
+
+use crate::theme::Theme;
+use crate::utils::diagnostics::Diagnostics;
+use cloudhost_server::doctor::Status;
+
+/// Renders the doctor's checks, each with what to do about it when something is wrong
+pub fn render_diagnostics_panel(
+    theme: &Theme,
+    diagnostics: &Diagnostics,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let modal_width = 100.min(area.width);
+    let modal_height = area.height.saturating_sub(4).max(10).min(area.height);
+    let modal_area = Rect::new(
+        area.x + (area.width.saturating_sub(modal_width)) / 2,
+        area.y + (area.height.saturating_sub(modal_height)) / 2,
+        modal_width,
+        modal_height,
+    );
+
+    Clear.render(modal_area, buf);
+
+    let hint = if diagnostics.checking_online {
+        " j/k to scroll, Esc to close "
+    } else {
+        " j/k to scroll, r to run again, Esc to close "
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("🩺 Diagnostics")
+        .title_alignment(Alignment::Center)
+        .title_bottom(Line::from(hint).centered())
+        .border_style(
+            Style::default()
+                .fg(theme.focused_border)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let mut lines = Vec::new();
+    for check in &diagnostics.checks {
+        let color = match check.status {
+            Status::Ok => theme.success,
+            Status::Info => theme.info,
+            Status::Warning => theme.warning,
+            Status::Error => theme.error,
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{} {:<9}", check.status.symbol(), check.area),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!(" {}", check.message),
+                Style::default().fg(theme.text),
+            ),
+        ]));
+        if let Some(hint) = &check.hint {
+            lines.push(Line::styled(
+                format!("    → {}", hint),
+                Style::default().fg(theme.hint),
+            ));
+        }
+    }
+    if diagnostics.checking_online {
+        lines.push(Line::styled(
+            "↻ Checking from the internet...",
+            Style::default().fg(theme.dim),
+        ));
+    }
+
+    Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .scroll((diagnostics.scroll, 0))
+        .render(modal_area, buf);
+}
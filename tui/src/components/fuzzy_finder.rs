@@ -0,0 +1,100 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{
+        Block, Borders, Clear, List, ListItem, ListState, Paragraph, StatefulWidget, Widget,
+    },
+};
+
+use crate::components::text_input::input_line;
+use crate::utils::fuzzy_finder::FuzzyFinderState;
+
+/// Renders the fuzzy finder overlay centered in `area`
+pub fn render_fuzzy_finder(state: &FuzzyFinderState, area: Rect, buf: &mut Buffer) {
+    let modal_width = (area.width * 6 / 10).max(40).min(area.width);
+    let modal_height = (area.height * 6 / 10).max(10).min(area.height);
+    let modal_area = Rect::new(
+        area.x + (area.width.saturating_sub(modal_width)) / 2,
+        area.y + (area.height.saturating_sub(modal_height)) / 2,
+        modal_width,
+        modal_height,
+    );
+
+    Clear.render(modal_area, buf);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("🔍 Find clouds, folders and actions")
+        .title_alignment(Alignment::Center)
+        .border_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+    let inner = block.inner(modal_area);
+    block.render(modal_area, buf);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Query
+            Constraint::Length(1), // Separator / count
+            Constraint::Min(0),    // Results
+        ])
+        .split(inner);
+
+    let mut query = input_line(
+        "",
+        &state.query,
+        true,
+        None,
+        chunks[0].width.saturating_sub(2),
+    );
+    query
+        .spans
+        .insert(0, Span::styled("> ", Style::default().fg(Color::Yellow)));
+    Paragraph::new(query).render(chunks[0], buf);
+
+    Paragraph::new(format!("{}/{}", state.matches.len(), state.items.len()))
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Right)
+        .render(chunks[1], buf);
+
+    let items: Vec<ListItem> = state
+        .matches
+        .iter()
+        .filter_map(|m| state.items.get(m.item_index).map(|item| (m, item)))
+        .map(|(m, item)| {
+            let mut spans = vec![Span::raw(format!("{} ", item.kind.icon()))];
+            for (i, c) in item.label.chars().enumerate() {
+                let style = if m.positions.contains(&i) {
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                spans.push(Span::styled(c.to_string(), style));
+            }
+            if !item.detail.is_empty() {
+                spans.push(Span::styled(
+                    format!("  {}", item.detail),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .highlight_style(Style::default().bg(Color::DarkGray))
+        .highlight_symbol(">> ");
+    let mut list_state = ListState::default().with_selected(if state.matches.is_empty() {
+        None
+    } else {
+        Some(state.selected)
+    });
+    StatefulWidget::render(list, chunks[2], buf, &mut list_state);
+}
@@ -0,0 +1,106 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap},
+};
+
+use crate::config::Config;
+
+/// Human readable name for the leader key
+fn leader_display(leader: &str) -> String {
+    match leader {
+        " " => "Space".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Build the help lines for `tab`: tab-specific actions first, then global ones
+pub fn help_lines(config: &Config, tab: &str, tab_title: &str) -> Vec<Line<'static>> {
+    let heading = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+    let key_style = Style::default().fg(Color::Cyan);
+
+    let mut lines = vec![Line::from(vec![
+        Span::raw("Leader key: "),
+        Span::styled(leader_display(&config.leader), key_style),
+    ])];
+
+    let sections = [
+        (tab, format!("{} tab", tab_title)),
+        ("any", "Global".to_string()),
+    ];
+    for (section_tab, title) in sections {
+        let mut actions: Vec<(&String, &crate::config::Action)> = config
+            .actions
+            .iter()
+            .filter(|(_, action)| action.tab == section_tab)
+            .collect();
+        if actions.is_empty() {
+            continue;
+        }
+        actions.sort_by(|a, b| a.0.cmp(b.0));
+
+        let key_width = actions
+            .iter()
+            .map(|(_, action)| action.keys.join(", ").chars().count())
+            .max()
+            .unwrap_or(0);
+
+        lines.push(Line::raw(""));
+        lines.push(Line::styled(title, heading));
+        for (name, action) in actions {
+            let keys = if action.keys.is_empty() {
+                "(unbound)".to_string()
+            } else {
+                action.keys.join(", ")
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {:<width$}", keys, width = key_width), key_style),
+                Span::raw(format!("  {}", name)),
+            ]));
+        }
+    }
+
+    lines
+}
+
+/// Renders the keybinding help modal for the current tab
+pub fn render_help_overlay(
+    config: &Config,
+    tab: &str,
+    tab_title: &str,
+    scroll: u16,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let modal_width = 70.min(area.width);
+    let modal_height = (area.height * 8 / 10).max(10).min(area.height);
+    let modal_area = Rect::new(
+        area.x + (area.width.saturating_sub(modal_width)) / 2,
+        area.y + (area.height.saturating_sub(modal_height)) / 2,
+        modal_width,
+        modal_height,
+    );
+
+    Clear.render(modal_area, buf);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("❓ Keybindings - {}", tab_title))
+        .title_alignment(Alignment::Center)
+        .title_bottom(Line::from(" j/k to scroll, Esc or ? to close ").centered())
+        .border_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    Paragraph::new(help_lines(config, tab, tab_title))
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0))
+        .render(modal_area, buf);
+}
@@ -0,0 +1,85 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+
+use crate::utils::jobs::{format_bytes, Job, JobStatus};
+
+const BAR_WIDTH: usize = 20;
+
+fn progress_text(job: &Job) -> String {
+    match (job.fraction(), job.total) {
+        (Some(fraction), Some(total)) => {
+            let filled = (fraction * BAR_WIDTH as f64).round() as usize;
+            format!(
+                "[{}{}] {:>3}% {}/{}",
+                "█".repeat(filled),
+                "░".repeat(BAR_WIDTH - filled),
+                (fraction * 100.0).round() as u64,
+                format_bytes(job.done),
+                format_bytes(total)
+            )
+        }
+        _ if job.done > 0 => format_bytes(job.done),
+        _ => String::new(),
+    }
+}
+
+fn job_lines(job: &Job) -> Vec<Line<'static>> {
+    let (status_style, detail) = match &job.status {
+        JobStatus::Running => (Style::default().fg(Color::Yellow), progress_text(job)),
+        JobStatus::Succeeded(message) => (Style::default().fg(Color::Green), message.clone()),
+        JobStatus::Failed(e) => (Style::default().fg(Color::Red), e.clone()),
+    };
+    let mut lines = vec![Line::from(vec![
+        Span::styled(format!("{} ", job.icon()), status_style),
+        Span::styled(
+            job.label.clone(),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+    ])];
+    if !detail.is_empty() {
+        lines.push(Line::styled(format!("   {}", detail), status_style));
+    }
+    lines
+}
+
+/// Floating panel in the bottom right corner listing `jobs`.
+/// `full` shows the whole history with a title hint for closing it.
+pub fn render_jobs_panel(jobs: &[&Job], full: bool, area: Rect, buf: &mut Buffer) {
+    let lines: Vec<Line> = if jobs.is_empty() {
+        vec![Line::styled(
+            "No jobs yet",
+            Style::default().fg(Color::DarkGray),
+        )]
+    } else {
+        jobs.iter().rev().flat_map(|job| job_lines(job)).collect()
+    };
+
+    let panel_width = 60.min(area.width);
+    let panel_height = (lines.len() as u16 + 2).min(area.height.saturating_sub(2));
+    let panel_area = Rect::new(
+        area.x + area.width.saturating_sub(panel_width),
+        // Keep the footer visible
+        area.y + area.height.saturating_sub(panel_height + 1),
+        panel_width,
+        panel_height,
+    );
+
+    Clear.render(panel_area, buf);
+
+    let running = jobs.iter().filter(|job| job.is_running()).count();
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("⚙️ Jobs ({} running)", running))
+        .title_alignment(Alignment::Center)
+        .border_style(Style::default().fg(Color::Cyan));
+    if full {
+        block = block.title_bottom(Line::from(" Esc to close ").centered());
+    }
+
+    Paragraph::new(lines).block(block).render(panel_area, buf);
+}
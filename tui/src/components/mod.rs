@@ -1 +1,21 @@
+pub mod change_password_modal;
+pub mod clients_panel;
+pub mod cloud_status;
+pub mod command_line;
+pub mod config_conflict_modal;
+pub mod confirm_modal;
+pub mod copy_fallback;
+pub mod default_editor_modal;
+pub mod diagnostics_panel;
+pub mod fuzzy_finder;
+pub mod help_overlay;
+pub mod jobs_panel;
+pub mod notifications;
+pub mod panel_switcher;
+pub mod passphrase_modal;
 pub mod password_modal;
+pub mod profile_picker_modal;
+pub mod recovery_code;
+pub mod tasks_panel;
+pub mod text_input;
+pub mod webhook_history;
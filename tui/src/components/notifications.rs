@@ -0,0 +1,122 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap},
+};
+
+use crate::utils::notifications::{Notification, Notifications, Severity};
+
+fn severity_color(severity: Severity) -> Color {
+    match severity {
+        Severity::Info => Color::Cyan,
+        Severity::Success => Color::Green,
+        Severity::Warning => Color::Yellow,
+        Severity::Error => Color::Red,
+    }
+}
+
+/// Error banners, one line each, newest on top
+pub fn render_banners(banners: &[&Notification], area: Rect, buf: &mut Buffer) {
+    for (i, banner) in banners.iter().rev().take(area.height as usize).enumerate() {
+        let row = Rect::new(area.x, area.y + i as u16, area.width, 1);
+        Clear.render(row, buf);
+        Paragraph::new(Line::from(vec![
+            Span::raw(format!(" {} ", banner.severity.icon())),
+            Span::styled(
+                banner.message.clone(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("  (Esc to dismiss)"),
+        ]))
+        .style(Style::default().fg(Color::White).bg(Color::Red))
+        .render(row, buf);
+    }
+}
+
+/// Stack of toasts in the top right corner of `area`, newest on top
+pub fn render_toasts(toasts: &[&Notification], area: Rect, buf: &mut Buffer) {
+    let toast_width = 50.min(area.width);
+    let mut y = area.y;
+    for toast in toasts {
+        if y + 3 > area.y + area.height {
+            break;
+        }
+        let toast_area = Rect::new(
+            area.x + area.width.saturating_sub(toast_width),
+            y,
+            toast_width,
+            3,
+        );
+        Clear.render(toast_area, buf);
+
+        let color = severity_color(toast.severity);
+        Paragraph::new(format!("{} {}", toast.severity.icon(), toast.message))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(color)),
+            )
+            .style(Style::default().fg(color))
+            .render(toast_area, buf);
+        y += 3;
+    }
+}
+
+/// Modal listing every notification, newest first
+pub fn render_notification_history(notifications: &Notifications, area: Rect, buf: &mut Buffer) {
+    let modal_width = 80.min(area.width);
+    let modal_height = (area.height * 8 / 10).max(10).min(area.height);
+    let modal_area = Rect::new(
+        area.x + (area.width.saturating_sub(modal_width)) / 2,
+        area.y + (area.height.saturating_sub(modal_height)) / 2,
+        modal_width,
+        modal_height,
+    );
+
+    Clear.render(modal_area, buf);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("🔔 Notifications")
+        .title_alignment(Alignment::Center)
+        .title_bottom(Line::from(" j/k to scroll, Esc to close ").centered())
+        .border_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let lines: Vec<Line> = if notifications.history.is_empty() {
+        vec![Line::styled(
+            "No notifications yet",
+            Style::default().fg(Color::DarkGray),
+        )]
+    } else {
+        notifications
+            .history
+            .iter()
+            .rev()
+            .map(|n| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{} ", n.timestamp.format("%H:%M:%S")),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::raw(format!("{} ", n.severity.icon())),
+                    Span::styled(
+                        n.message.clone(),
+                        Style::default().fg(severity_color(n.severity)),
+                    ),
+                ])
+            })
+            .collect()
+    };
+
+    Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .scroll((notifications.history_scroll, 0))
+        .render(modal_area, buf);
+}
@@ -0,0 +1,39 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Paragraph, Widget},
+};
+
+use crate::theme::Theme;
+
+/// Renders the names of a tab's panels on one line, the `focused` one highlighted, for
+/// layouts that only have room for that one
+pub fn render_panel_switcher(
+    theme: &Theme,
+    names: &[&str],
+    focused: usize,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let mut spans = Vec::new();
+    for (i, name) in names.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::styled(" │ ", Style::default().fg(theme.dim)));
+        }
+        let style = if i == focused {
+            Style::default()
+                .fg(theme.focused_border)
+                .add_modifier(Modifier::BOLD | Modifier::REVERSED)
+        } else {
+            Style::default().fg(theme.hint)
+        };
+        spans.push(Span::styled(format!(" {} ", name), style));
+    }
+    spans.push(Span::styled(
+        "  Tab to switch",
+        Style::default().fg(theme.dim),
+    ));
+    Paragraph::new(Line::from(spans)).render(area, buf);
+}
@@ -0,0 +1,104 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+
+use crate::components::text_input::input_line;
+use crate::utils::passphrase_prompt::{PassphrasePrompt, PassphrasePurpose};
+
+/// Masked passphrase input for encrypted config exports/imports and cloud secrets
+pub fn render_passphrase_modal(prompt: &PassphrasePrompt, area: Rect, buf: &mut Buffer) {
+    let modal_width = 64.min(area.width.saturating_sub(4));
+    let modal_height = 11.min(area.height.saturating_sub(4));
+    let modal_area = Rect::new(
+        area.x + (area.width.saturating_sub(modal_width)) / 2,
+        area.y + (area.height.saturating_sub(modal_height)) / 2,
+        modal_width,
+        modal_height,
+    );
+
+    Clear.render(modal_area, buf);
+
+    let (title, subject) = match &prompt.purpose {
+        PassphrasePurpose::Export(path) => {
+            ("🔐 Encrypt Export", format!("File: {}", path.display()))
+        }
+        PassphrasePurpose::Import(path) => {
+            ("🔐 Decrypt Import", format!("File: {}", path.display()))
+        }
+        PassphrasePurpose::UnlockSecrets => (
+            "🔐 Unlock Cloud Secrets",
+            "Cloud passwords are encrypted with a master passphrase".to_string(),
+        ),
+        PassphrasePurpose::ProtectSecrets => (
+            "🔐 Encrypt Cloud Secrets",
+            "Cloud passwords will be encrypted at rest".to_string(),
+        ),
+    };
+    Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_alignment(Alignment::Center)
+        .border_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+        .render(modal_area, buf);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(2), // Subject
+            Constraint::Length(2), // Instructions
+            Constraint::Length(2), // Input
+            Constraint::Length(2), // Error/Help
+        ])
+        .split(modal_area);
+
+    Paragraph::new(subject)
+        .style(Style::default().fg(Color::Cyan))
+        .alignment(Alignment::Center)
+        .render(chunks[0], buf);
+
+    let instructions = match (&prompt.purpose, prompt.is_confirming()) {
+        (PassphrasePurpose::Import(_), _) => "Enter the passphrase of this export:",
+        (PassphrasePurpose::UnlockSecrets, _) => "Enter the master passphrase:",
+        (_, false) => "Choose a passphrase (min 8 characters):",
+        (_, true) => "Confirm the passphrase:",
+    };
+    Paragraph::new(instructions)
+        .style(Style::default().fg(Color::Cyan))
+        .alignment(Alignment::Center)
+        .render(chunks[1], buf);
+
+    Paragraph::new(input_line(
+        "Passphrase: ",
+        &prompt.input,
+        true,
+        Some('*'),
+        chunks[2].width,
+    ))
+    .style(
+        Style::default()
+            .fg(Color::Green)
+            .add_modifier(Modifier::BOLD),
+    )
+    .alignment(Alignment::Center)
+    .render(chunks[2], buf);
+
+    if let Some(ref error) = prompt.error {
+        Paragraph::new(format!("❌ {}", error))
+            .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+            .alignment(Alignment::Center)
+            .render(chunks[3], buf);
+    } else {
+        Paragraph::new("Enter to continue, Esc to cancel")
+            .style(Style::default().fg(Color::Gray))
+            .alignment(Alignment::Center)
+            .render(chunks[3], buf);
+    }
+}
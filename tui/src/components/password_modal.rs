@@ -5,6 +5,7 @@ use ratatui::{
     widgets::{Block, Borders, Clear, Paragraph, Widget},
 };
 
+use crate::components::text_input::input_line;
 use crate::utils::password::{PasswordCreationState, PasswordMode};
 
 /// Renders a password creation modal
@@ -78,7 +79,13 @@ pub fn render_password_modal(
         .render(modal_chunks[2], buf);
 
     // Password field
-    let password_field = format!("Password: {}", password_state.get_masked_password());
+    let password_field = input_line(
+        "Password: ",
+        &password_state.password_input,
+        password_state.get_password_mode() == &PasswordMode::Creating,
+        Some('*'),
+        modal_chunks[3].width,
+    );
     let password_style = if password_state.get_password_mode() == &PasswordMode::Creating {
         Style::default()
             .fg(Color::Green)
@@ -94,7 +101,13 @@ pub fn render_password_modal(
 
     // Confirm field (only show when confirming)
     if password_state.get_password_mode() == &PasswordMode::Confirming {
-        let confirm_field = format!("Confirm: {}", password_state.get_masked_confirm());
+        let confirm_field = input_line(
+            "Confirm: ",
+            &password_state.password_confirm,
+            true,
+            Some('*'),
+            modal_chunks[4].width,
+        );
         Paragraph::new(confirm_field)
             .style(
                 Style::default()
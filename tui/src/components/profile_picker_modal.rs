@@ -0,0 +1,89 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+
+use crate::components::text_input::input_line;
+use crate::theme::Theme;
+use crate::utils::profile_picker::ProfilePicker;
+use cloudhost_server::config_paths;
+
+/// Renders the profiles to switch to, with an input for the name of a new one
+pub fn render_profile_picker(
+    theme: &Theme,
+    picker: &ProfilePicker,
+    running_clouds: usize,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let modal_width = 56.min(area.width.saturating_sub(4));
+    let modal_height = (picker.profiles.len() as u16 + 8).min(area.height.saturating_sub(2));
+    let modal_area = Rect::new(
+        area.x + (area.width.saturating_sub(modal_width)) / 2,
+        area.y + (area.height.saturating_sub(modal_height)) / 2,
+        modal_width,
+        modal_height,
+    );
+
+    Clear.render(modal_area, buf);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("👤 Switch Profile")
+        .title_alignment(Alignment::Center)
+        .title_bottom(Line::from(" ↑/↓ to select, Enter to switch, Esc to cancel ").centered())
+        .border_style(
+            Style::default()
+                .fg(theme.focused_border)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let current = config_paths::current_profile()
+        .unwrap_or_else(|| config_paths::DEFAULT_PROFILE.to_string());
+    let mut lines = Vec::new();
+    for (i, profile) in picker.profiles.iter().enumerate() {
+        let marker = if *profile == current {
+            " (current)"
+        } else {
+            ""
+        };
+        let style = if i == picker.selected && picker.input.is_empty() {
+            Style::default()
+                .fg(theme.selection)
+                .add_modifier(Modifier::BOLD | Modifier::REVERSED)
+        } else {
+            Style::default().fg(theme.text)
+        };
+        lines.push(Line::styled(format!(" {}{} ", profile, marker), style));
+    }
+    lines.push(Line::raw(""));
+    lines.push(input_line(
+        "New profile: ",
+        &picker.input,
+        true,
+        None,
+        modal_width.saturating_sub(2),
+    ));
+    lines.push(Line::raw(""));
+    if let Some(error) = &picker.error {
+        lines.push(Line::styled(
+            format!("❌ {}", error),
+            Style::default().fg(theme.error),
+        ));
+    } else if running_clouds > 0 {
+        lines.push(Line::styled(
+            format!("Switching stops the {} running cloud(s)", running_clouds),
+            Style::default().fg(theme.warning),
+        ));
+    } else {
+        lines.push(Line::styled(
+            "Each profile has its own clouds and settings",
+            Style::default().fg(theme.hint),
+        ));
+    }
+
+    Paragraph::new(lines).block(block).render(modal_area, buf);
+}
@@ -0,0 +1,74 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap},
+};
+
+use cloudhost_server::recovery::CODE_LIFETIME_MINUTES;
+
+/// Shows a cloud's one-time recovery code, which only ever appears here
+pub fn render_recovery_code(
+    cloud: &str,
+    code: &str,
+    url: Option<&str>,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let modal_width = 60.min(area.width);
+    let modal_height = 11.min(area.height);
+    let modal_area = Rect::new(
+        area.x + (area.width.saturating_sub(modal_width)) / 2,
+        area.y + (area.height.saturating_sub(modal_height)) / 2,
+        modal_width,
+        modal_height,
+    );
+
+    Clear.render(modal_area, buf);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("🔑 Recovery code for '{}'", cloud))
+        .title_alignment(Alignment::Center)
+        .title_bottom(Line::from(" Esc to close ").centered())
+        .border_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let page = match url {
+        Some(url) => format!("{}/login/recover", url),
+        None => "/login/recover".to_string(),
+    };
+    let lines = vec![
+        Line::raw(""),
+        Line::styled(
+            code.to_string(),
+            Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+        )
+        .centered(),
+        Line::raw(""),
+        Line::raw(format!(
+            "Enter it with a new password at {} (also \"Forgot password?\" on the login page).",
+            page
+        ))
+        .centered(),
+        Line::styled(
+            format!(
+                "It works once, for {} minutes. Issuing a new code replaces it.",
+                CODE_LIFETIME_MINUTES
+            ),
+            Style::default().fg(Color::DarkGray),
+        )
+        .centered(),
+    ];
+
+    Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: true })
+        .render(modal_area, buf);
+}
@@ -0,0 +1,95 @@
+use chrono::{DateTime, Local};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap},
+};
+
+use cloudhost_server::scheduler::TaskStatus;
+
+fn format_time(time: Option<DateTime<Local>>) -> String {
+    match time {
+        Some(time) if time.date_naive() == Local::now().date_naive() => {
+            time.format("%H:%M").to_string()
+        }
+        Some(time) => time.format("%d %b %H:%M").to_string(),
+        None => "-".to_string(),
+    }
+}
+
+fn task_lines(task: &TaskStatus, selected: bool) -> Vec<Line<'static>> {
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut name_style = Style::default().fg(Color::Cyan);
+    if selected {
+        name_style = name_style.add_modifier(Modifier::BOLD | Modifier::REVERSED);
+    }
+    let result = if task.running {
+        Span::styled("↻ running", Style::default().fg(Color::Yellow))
+    } else {
+        match &task.last_result {
+            Some(Ok(summary)) => {
+                Span::styled(format!("✓ {}", summary), Style::default().fg(Color::Green))
+            }
+            Some(Err(e)) => Span::styled(format!("✗ {}", e), Style::default().fg(Color::Red)),
+            None => Span::styled("never ran", dim),
+        }
+    };
+    vec![
+        Line::from(vec![
+            Span::styled(format!("{:<15}", task.task.as_str()), name_style),
+            Span::styled(format!(" {:<14}", task.schedule), dim),
+            Span::raw(format!(" next {:<13}", format_time(task.next_run))),
+            Span::raw(format!(" last {}", format_time(task.last_run))),
+        ]),
+        Line::from(vec![Span::raw("   "), result]),
+    ]
+}
+
+/// Renders the scheduled tasks with their next and last run
+pub fn render_tasks_panel(tasks: &[TaskStatus], selected: usize, area: Rect, buf: &mut Buffer) {
+    let modal_width = 90.min(area.width);
+    let modal_height = (tasks.len() as u16 * 2 + 2).max(6).min(area.height);
+    let modal_area = Rect::new(
+        area.x + (area.width.saturating_sub(modal_width)) / 2,
+        area.y + (area.height.saturating_sub(modal_height)) / 2,
+        modal_width,
+        modal_height,
+    );
+
+    Clear.render(modal_area, buf);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("⏱ Scheduled tasks")
+        .title_alignment(Alignment::Center)
+        .title_bottom(Line::from(" j/k to select, r to run now, Esc to close ").centered())
+        .border_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let lines: Vec<Line> = if tasks.is_empty() {
+        vec![Line::styled(
+            "No tasks, add [[tasks]] to the clouds config",
+            Style::default().fg(Color::DarkGray),
+        )]
+    } else {
+        tasks
+            .iter()
+            .enumerate()
+            .flat_map(|(index, task)| task_lines(task, index == selected))
+            .collect()
+    };
+    // Keep the selected task in view
+    let inner_height = modal_height.saturating_sub(2);
+    let scroll = (selected as u16 * 2 + 2).saturating_sub(inner_height);
+
+    Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0))
+        .render(modal_area, buf);
+}
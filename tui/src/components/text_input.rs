@@ -0,0 +1,63 @@
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+};
+
+use crate::utils::text_input::TextInput;
+
+/// `label` followed by the text of `input`, shown as `mask` characters for passwords. The
+/// focused input shows its cursor and selection, and scrolls to keep the cursor within
+/// `width` columns.
+pub fn input_line(
+    label: &str,
+    input: &TextInput,
+    focused: bool,
+    mask: Option<char>,
+    width: u16,
+) -> Line<'static> {
+    let chars: Vec<char> = match mask {
+        Some(mask) => vec![mask; input.char_count()],
+        None => input.chars().collect(),
+    };
+    let mut spans = vec![Span::raw(label.to_string())];
+    if !focused {
+        spans.push(Span::raw(chars.into_iter().collect::<String>()));
+        return Line::from(spans);
+    }
+
+    // Room for the text and the cursor after it; earlier characters scroll out on the left
+    let room = (width as usize)
+        .saturating_sub(label.chars().count())
+        .max(1);
+    let start = (input.cursor() + 1).saturating_sub(room);
+    let selection = input.selection().unwrap_or_default();
+    let cursor_style = Style::default().add_modifier(Modifier::REVERSED);
+    let selected_style = Style::default().add_modifier(Modifier::REVERSED | Modifier::UNDERLINED);
+
+    let style_at = |i: usize| {
+        if i == input.cursor() {
+            cursor_style
+        } else if selection.contains(&i) {
+            selected_style
+        } else {
+            Style::default()
+        }
+    };
+    // Runs of characters in the same style, one span each
+    let mut run = String::new();
+    let mut run_style = Style::default();
+    for (i, c) in chars.iter().enumerate().skip(start) {
+        let style = style_at(i);
+        if style != run_style {
+            spans.push(Span::styled(std::mem::take(&mut run), run_style));
+            run_style = style;
+        }
+        run.push(*c);
+    }
+    spans.push(Span::styled(run, run_style));
+    if input.cursor() == chars.len() {
+        spans.push(Span::styled(" ", cursor_style));
+    }
+    spans.retain(|span| !span.content.is_empty());
+    Line::from(spans)
+}
@@ -0,0 +1,96 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap},
+};
+
+use cloudhost_server::webhooks::{DeliveryStatus, WebhookDelivery};
+
+fn status_span(delivery: &WebhookDelivery) -> Span<'static> {
+    match delivery.status {
+        DeliveryStatus::Delivered(code) => {
+            Span::styled(format!("✓ {}", code), Style::default().fg(Color::Green))
+        }
+        DeliveryStatus::Pending if delivery.attempts > 0 => Span::styled(
+            format!("↻ retry {}", delivery.attempts),
+            Style::default().fg(Color::Yellow),
+        ),
+        DeliveryStatus::Pending => Span::styled("… sending", Style::default().fg(Color::Yellow)),
+        DeliveryStatus::Failed => Span::styled("✗ failed", Style::default().fg(Color::Red)),
+    }
+}
+
+/// Renders the last webhook deliveries of a cloud, newest first
+pub fn render_webhook_history(
+    cloud: &str,
+    deliveries: &[WebhookDelivery],
+    scroll: u16,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let modal_width = 100.min(area.width);
+    let modal_height = (area.height * 8 / 10).max(10).min(area.height);
+    let modal_area = Rect::new(
+        area.x + (area.width.saturating_sub(modal_width)) / 2,
+        area.y + (area.height.saturating_sub(modal_height)) / 2,
+        modal_width,
+        modal_height,
+    );
+
+    Clear.render(modal_area, buf);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("🪝 Webhooks - {}", cloud))
+        .title_alignment(Alignment::Center)
+        .title_bottom(Line::from(" j/k to scroll, Esc to close ").centered())
+        .border_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let dim = Style::default().fg(Color::DarkGray);
+    let lines: Vec<Line> = if deliveries.is_empty() {
+        vec![Line::styled("No deliveries since the cloud started", dim)]
+    } else {
+        deliveries
+            .iter()
+            .flat_map(|delivery| {
+                let mut lines = vec![Line::from(vec![
+                    Span::styled(
+                        format!(
+                            "{} ",
+                            delivery
+                                .created_at
+                                .with_timezone(&chrono::Local)
+                                .format("%H:%M:%S")
+                        ),
+                        dim,
+                    ),
+                    Span::styled(
+                        format!("{:<14}", delivery.event.as_str()),
+                        Style::default().fg(Color::Cyan),
+                    ),
+                    status_span(delivery),
+                    Span::raw(format!("  {}", delivery.url)),
+                ])];
+                if let Some(error) = &delivery.last_error {
+                    lines.push(Line::styled(
+                        format!("         {}", error),
+                        Style::default().fg(Color::Red),
+                    ));
+                }
+                lines
+            })
+            .collect()
+    };
+
+    Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0))
+        .render(modal_area, buf);
+}
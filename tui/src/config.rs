@@ -1,20 +1,63 @@
 use crate::error::{TuiError, TuiResult};
+use crate::layout::LayoutConfig;
+use crate::theme::ThemeConfig;
+use cloudhost_server::config_migration::{self, Migration};
 use cloudhost_server::config_paths;
+use ratatui::crossterm::event::{KeyCode, KeyModifiers};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Action {
     pub keys: Vec<String>,
     pub tab: String,
 }
 
+/// Version of the TUI config format this CloudHost writes
+pub const TUI_SCHEMA_VERSION: u32 = 2;
+
+/// Steps from older versions of the format, see `config_migration`
+const MIGRATIONS: &[Migration] = &[Migration {
+    to: 2,
+    description: "confirm_deletes is now confirmations",
+    apply: |table| {
+        if let Some(confirm) = table.remove("confirm_deletes") {
+            if !confirm.is_bool() {
+                return Err("confirm_deletes must be true or false".to_string());
+            }
+            table.entry("confirmations").or_insert(confirm);
+        }
+        Ok(())
+    },
+}];
+
+fn default_schema_version() -> u32 {
+    TUI_SCHEMA_VERSION
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Format version of the file, see `config_migration`
+    #[serde(default = "default_schema_version")]
+    pub schema_version: u32,
     pub leader: String,
+    /// Ask for y/n confirmation before deleting folders, clouds and files, and before
+    /// stopping a cloud that is serving clients
+    #[serde(default = "default_confirmations")]
+    pub confirmations: bool,
+    /// Built-in theme and color overrides
+    #[serde(default)]
+    pub theme: ThemeConfig,
+    /// Terminal sizes at which the tabs stack their panels or show one at a time
+    #[serde(default)]
+    pub layout: LayoutConfig,
     pub actions: HashMap<String, Action>,
 }
 
+fn default_confirmations() -> bool {
+    true
+}
+
 impl Default for Config {
     fn default() -> Self {
         let mut actions = HashMap::new();
@@ -46,6 +89,41 @@ impl Default for Config {
                 tab: "clouds".to_string(),
             },
         );
+        actions.insert(
+            "Show Clients".to_string(),
+            Action {
+                keys: vec!["c".to_string()],
+                tab: "clouds".to_string(),
+            },
+        );
+        actions.insert(
+            "Filter Clouds By Tag".to_string(),
+            Action {
+                keys: vec!["t".to_string()],
+                tab: "clouds".to_string(),
+            },
+        );
+
+        // Cloud Logs panel
+        for (name, key) in [
+            ("Toggle Error Logs", "1"),
+            ("Toggle Warning Logs", "2"),
+            ("Toggle Info Logs", "3"),
+            ("Toggle Debug Logs", "4"),
+            ("Search Logs", "/"),
+            ("Next Log Match", "n"),
+            ("Previous Log Match", "N"),
+            ("Pause/Follow Logs", "f"),
+        ] {
+            actions.insert(
+                name.to_string(),
+                Action {
+                    keys: vec![key.to_string()],
+                    tab: "clouds".to_string(),
+                },
+            );
+        }
+
         actions.insert(
             "Create New".to_string(),
             Action {
@@ -74,6 +152,27 @@ impl Default for Config {
                 tab: "folders".to_string(),
             },
         );
+        actions.insert(
+            "Toggle Folder Details".to_string(),
+            Action {
+                keys: vec!["v".to_string()],
+                tab: "folders".to_string(),
+            },
+        );
+        actions.insert(
+            "Sort Folders".to_string(),
+            Action {
+                keys: vec!["o".to_string()],
+                tab: "folders".to_string(),
+            },
+        );
+        actions.insert(
+            "Reverse Folder Sort".to_string(),
+            Action {
+                keys: vec!["O".to_string()],
+                tab: "folders".to_string(),
+            },
+        );
         actions.insert(
             "Edit".to_string(),
             Action {
@@ -88,6 +187,20 @@ impl Default for Config {
                 tab: "clouds".to_string(),
             },
         );
+        actions.insert(
+            "Change Password".to_string(),
+            Action {
+                keys: vec!["P".to_string()],
+                tab: "clouds".to_string(),
+            },
+        );
+        actions.insert(
+            "Issue Recovery Code".to_string(),
+            Action {
+                keys: vec!["R".to_string()],
+                tab: "clouds".to_string(),
+            },
+        );
         actions.insert(
             "Set Password".to_string(),
             Action {
@@ -124,6 +237,42 @@ impl Default for Config {
             },
         );
 
+        actions.insert(
+            "Export Clouds Config".to_string(),
+            Action {
+                keys: vec!["<leader>e".to_string()],
+                tab: "settings".to_string(),
+            },
+        );
+        actions.insert(
+            "Import Clouds Config".to_string(),
+            Action {
+                keys: vec!["<leader>i".to_string()],
+                tab: "settings".to_string(),
+            },
+        );
+        actions.insert(
+            "Send Test Email".to_string(),
+            Action {
+                keys: vec!["<leader>m".to_string()],
+                tab: "settings".to_string(),
+            },
+        );
+        actions.insert(
+            "Run Diagnostics".to_string(),
+            Action {
+                keys: vec!["<leader>D".to_string()],
+                tab: "settings".to_string(),
+            },
+        );
+        actions.insert(
+            "Next Theme".to_string(),
+            Action {
+                keys: vec!["<leader>T".to_string()],
+                tab: "settings".to_string(),
+            },
+        );
+
         // Vim-style navigation keys
         actions.insert(
             "Navigate Up".to_string(),
@@ -154,6 +303,22 @@ impl Default for Config {
             },
         );
 
+        // Panel widths of the Clouds and Folders tabs
+        actions.insert(
+            "Grow Panel".to_string(),
+            Action {
+                keys: vec!["<Ctrl>l".to_string()],
+                tab: "any".to_string(),
+            },
+        );
+        actions.insert(
+            "Shrink Panel".to_string(),
+            Action {
+                keys: vec!["<Ctrl>h".to_string()],
+                tab: "any".to_string(),
+            },
+        );
+
         // Focus management
         actions.insert(
             "Cycle Focus Forward".to_string(),
@@ -190,10 +355,24 @@ impl Default for Config {
         actions.insert(
             "Refresh/Reload".to_string(),
             Action {
-                keys: vec!["r".to_string(), "<C-r>".to_string()],
+                keys: vec!["r".to_string(), "<Ctrl>r".to_string()],
                 tab: "any".to_string(),
             },
         );
+        actions.insert(
+            "Add Keybinding".to_string(),
+            Action {
+                keys: vec!["a".to_string()],
+                tab: "settings".to_string(),
+            },
+        );
+        actions.insert(
+            "Reset Keybinding".to_string(),
+            Action {
+                keys: vec!["R".to_string()],
+                tab: "settings".to_string(),
+            },
+        );
         actions.insert(
             "Execute Action".to_string(),
             Action {
@@ -202,25 +381,203 @@ impl Default for Config {
             },
         );
 
+        // Command line
+        actions.insert(
+            "Command Mode".to_string(),
+            Action {
+                keys: vec![":".to_string()],
+                tab: "any".to_string(),
+            },
+        );
+
+        // Help overlay
+        actions.insert(
+            "Show Help".to_string(),
+            Action {
+                keys: vec!["?".to_string()],
+                tab: "any".to_string(),
+            },
+        );
+
+        // Fuzzy finder
+        actions.insert(
+            "Fuzzy Finder".to_string(),
+            Action {
+                keys: vec!["<Ctrl>p".to_string()],
+                tab: "any".to_string(),
+            },
+        );
+
+        // File browsing (Client tab and Files panel in the Folders tab)
+        actions.insert(
+            "Open Entry".to_string(),
+            Action {
+                keys: vec!["<Enter>".to_string(), "l".to_string()],
+                tab: "any".to_string(),
+            },
+        );
+        actions.insert(
+            "Parent Directory".to_string(),
+            Action {
+                keys: vec!["h".to_string(), "<Backspace>".to_string()],
+                tab: "any".to_string(),
+            },
+        );
+
+        // Client tab (remote browser)
+        actions.insert(
+            "Connect".to_string(),
+            Action {
+                keys: vec!["c".to_string()],
+                tab: "client".to_string(),
+            },
+        );
+        actions.insert(
+            "Disconnect".to_string(),
+            Action {
+                keys: vec!["X".to_string()],
+                tab: "client".to_string(),
+            },
+        );
+        actions.insert(
+            "Download".to_string(),
+            Action {
+                keys: vec!["d".to_string()],
+                tab: "client".to_string(),
+            },
+        );
+        actions.insert(
+            "Upload".to_string(),
+            Action {
+                keys: vec!["u".to_string()],
+                tab: "client".to_string(),
+            },
+        );
+        actions.insert(
+            "Show Jobs".to_string(),
+            Action {
+                keys: vec!["<leader>j".to_string()],
+                tab: "any".to_string(),
+            },
+        );
+        actions.insert(
+            "Show Tasks".to_string(),
+            Action {
+                keys: vec!["<leader>t".to_string()],
+                tab: "any".to_string(),
+            },
+        );
+        actions.insert(
+            "Show Notifications".to_string(),
+            Action {
+                keys: vec!["<leader>n".to_string()],
+                tab: "any".to_string(),
+            },
+        );
+        actions.insert(
+            "Switch Profile".to_string(),
+            Action {
+                keys: vec!["<leader>P".to_string()],
+                tab: "any".to_string(),
+            },
+        );
+        actions.insert(
+            "Dismiss Notifications".to_string(),
+            Action {
+                keys: vec!["<Esc>".to_string()],
+                tab: "any".to_string(),
+            },
+        );
+
+        // Outside the terminal
+        actions.insert(
+            "Test Reachability".to_string(),
+            Action {
+                keys: vec!["<leader>I".to_string()],
+                tab: "clouds".to_string(),
+            },
+        );
+        actions.insert(
+            "Open in Browser".to_string(),
+            Action {
+                keys: vec!["gx".to_string()],
+                tab: "any".to_string(),
+            },
+        );
+        actions.insert(
+            "Open in File Manager".to_string(),
+            Action {
+                keys: vec!["go".to_string()],
+                tab: "folders".to_string(),
+            },
+        );
+
+        // Clipboard ("yank")
+        actions.insert(
+            "Copy Cloud URL".to_string(),
+            Action {
+                keys: vec!["yu".to_string()],
+                tab: "any".to_string(),
+            },
+        );
+        actions.insert(
+            "Copy Path".to_string(),
+            Action {
+                keys: vec!["yp".to_string()],
+                tab: "folders".to_string(),
+            },
+        );
+        actions.insert(
+            "Copy Share Links".to_string(),
+            Action {
+                keys: vec!["ys".to_string()],
+                tab: "any".to_string(),
+            },
+        );
+        actions.insert(
+            "Undo".to_string(),
+            Action {
+                keys: vec!["u".to_string()],
+                tab: "folders".to_string(),
+            },
+        );
+        actions.insert(
+            "Redo".to_string(),
+            Action {
+                keys: vec!["<Ctrl>r".to_string()],
+                tab: "folders".to_string(),
+            },
+        );
+
         Self {
+            schema_version: TUI_SCHEMA_VERSION,
             leader: " ".to_string(),
+            confirmations: true,
+            theme: ThemeConfig::default(),
+            layout: LayoutConfig::default(),
             actions,
         }
     }
 }
 
 impl Config {
+    /// Load the TUI config, upgrading a file of an older version on disk first and keeping
+    /// the original next to it
     pub fn load() -> TuiResult<Self> {
         let config_path = config_paths::get_tui_config_path();
 
         match std::fs::read_to_string(&config_path) {
-            Ok(config_str) => match toml::from_str::<Config>(&config_str) {
-                Ok(config) => Ok(config),
-                Err(e) => Err(TuiError::configuration(format!(
-                    "Failed to parse TUI config: {}",
-                    e
-                ))),
-            },
+            Ok(mut config_str) => {
+                if let Some(migrated) = Self::migrate(&config_str)? {
+                    config_migration::backup_before_migration(&config_path, migrated.from)
+                        .and_then(|_| std::fs::write(&config_path, &migrated.content))
+                        .map_err(|e| {
+                            TuiError::configuration(format!("Failed to upgrade TUI config: {}", e))
+                        })?;
+                    config_str = migrated.content;
+                }
+                Self::parse(&config_str)
+            }
             Err(_) => Err(TuiError::configuration(format!(
                 "Could not find TUI config at: {:?}",
                 config_path
@@ -228,6 +585,27 @@ impl Config {
         }
     }
 
+    /// The contents of a TUI config of an older version upgraded to the current one
+    fn migrate(config_str: &str) -> TuiResult<Option<config_migration::Migrated>> {
+        config_migration::migrate(
+            "tui-config.toml",
+            config_str,
+            TUI_SCHEMA_VERSION,
+            MIGRATIONS,
+        )
+        .map_err(TuiError::configuration)
+    }
+
+    /// Parse the contents of a TUI config file, of any version up to the current one
+    pub fn parse(config_str: &str) -> TuiResult<Self> {
+        let migrated = Self::migrate(config_str)?;
+        let config_str = migrated
+            .as_ref()
+            .map_or(config_str, |migrated| migrated.content.as_str());
+        toml::from_str::<Config>(config_str)
+            .map_err(|e| TuiError::configuration(format!("Failed to parse TUI config: {}", e)))
+    }
+
     pub fn load_or_default() -> Self {
         match Self::load() {
             Ok(mut config) => {
@@ -274,6 +652,72 @@ impl Config {
         None
     }
 
+    /// Find the action bound to `key` on `current_tab`.
+    /// Tab-specific bindings win over "any" bindings, so the same key can mean different things per tab.
+    pub fn get_action_for_key_in_tab(&self, key: &str, current_tab: &str) -> Option<String> {
+        let mut fallback = None;
+        for (action_name, action) in &self.actions {
+            if !action.keys.iter().any(|k| k == key) {
+                continue;
+            }
+            if action.tab == current_tab {
+                return Some(action_name.clone());
+            }
+            if action.tab == "any" {
+                fallback = Some(action_name.clone());
+            }
+        }
+        fallback
+    }
+
+    /// Other actions bound to `key` on a tab that overlaps with `action_name`'s tab
+    pub fn find_conflicts(&self, key: &str, action_name: &str) -> Vec<String> {
+        let Some(tab) = self.actions.get(action_name).map(|a| a.tab.as_str()) else {
+            return Vec::new();
+        };
+        let mut conflicts: Vec<String> = self
+            .actions
+            .iter()
+            .filter(|(name, action)| {
+                name.as_str() != action_name
+                    && action.keys.iter().any(|k| k == key)
+                    && (action.tab == tab || action.tab == "any" || tab == "any")
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        conflicts.sort();
+        conflicts
+    }
+
+    /// Bind `key` to an action, either replacing its keys or adding to them
+    pub fn bind_key(&mut self, action_name: &str, key: &str, append: bool) {
+        if let Some(action) = self.actions.get_mut(action_name) {
+            if !append {
+                action.keys.clear();
+            }
+            if !action.keys.iter().any(|k| k == key) {
+                action.keys.push(key.to_string());
+            }
+        }
+    }
+
+    pub fn unbind_key(&mut self, action_name: &str, key: &str) {
+        if let Some(action) = self.actions.get_mut(action_name) {
+            action.keys.retain(|k| k != key);
+        }
+    }
+
+    /// Restore the default keys of a single action
+    pub fn reset_action(&mut self, action_name: &str) -> bool {
+        match Self::default().actions.remove(action_name) {
+            Some(default_action) => {
+                self.actions.insert(action_name.to_string(), default_action);
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn get_action(&self, action_name: &str) -> Option<&Action> {
         self.actions.get(action_name)
     }
@@ -311,26 +755,129 @@ impl Config {
         Ok(())
     }
 
-    /// Check if the config needs migration (missing important keys)
+    /// Human readable list of differences, e.g. "rebound 'Quit' to q, <Ctrl>c"
+    pub fn diff(&self, new: &Config) -> Vec<String> {
+        let mut changes = Vec::new();
+        if self.leader != new.leader {
+            changes.push(format!("leader '{}' -> '{}'", self.leader, new.leader));
+        }
+        if self.confirmations != new.confirmations {
+            changes.push(format!("confirmations = {}", new.confirmations));
+        }
+        if self.theme.name != new.theme.name {
+            changes.push(format!(
+                "theme '{}' -> '{}'",
+                self.theme.name, new.theme.name
+            ));
+        }
+        if self.theme.colors != new.theme.colors {
+            changes.push("theme colors changed".to_string());
+        }
+        if self.layout != new.layout {
+            changes.push("layout sizes changed".to_string());
+        }
+
+        let mut names: Vec<&String> = self.actions.keys().chain(new.actions.keys()).collect();
+        names.sort();
+        names.dedup();
+        for name in names {
+            match (self.actions.get(name), new.actions.get(name)) {
+                (Some(old), Some(action)) if old.keys != action.keys => {
+                    changes.push(format!("rebound '{}' to {}", name, action.keys.join(", ")))
+                }
+                (Some(old), Some(action)) if old.tab != action.tab => {
+                    changes.push(format!("moved '{}' to tab {}", name, action.tab))
+                }
+                (None, Some(_)) => changes.push(format!("added action '{}'", name)),
+                (Some(_), None) => changes.push(format!("removed action '{}'", name)),
+                _ => {}
+            }
+        }
+        changes
+    }
+
+    /// Check if the config needs migration (missing actions that exist in the defaults)
     fn needs_migration(&self) -> bool {
-        // Check if Execute Action for settings tab is missing
-        !self.actions.contains_key("Execute Action")
-            || !self.actions.get("Execute Action").is_some_and(|action| {
-                action.keys.contains(&"<Enter>".to_string()) && action.tab == "settings"
-            })
+        Self::default()
+            .actions
+            .keys()
+            .any(|name| !self.actions.contains_key(name))
     }
 
-    /// Migrate config to latest version by adding missing actions
+    /// Migrate config to latest version by adding missing actions with their default keys
     fn migrate_to_latest(&mut self) {
-        // Add Execute Action for settings tab if missing
-        if !self.actions.contains_key("Execute Action") {
-            self.actions.insert(
-                "Execute Action".to_string(),
-                Action {
-                    keys: vec!["<Enter>".to_string()],
-                    tab: "settings".to_string(),
-                },
-            );
+        for (name, action) in Self::default().actions {
+            self.actions.entry(name).or_insert(action);
+        }
+    }
+}
+
+/// Convert a key press into the string used for it in the config, e.g. `j`, `<Enter>`, `<Ctrl>p`
+pub fn key_to_string(key: KeyCode, modifiers: KeyModifiers) -> Option<String> {
+    let key_str = match key {
+        KeyCode::Char(c) => {
+            // Check for Ctrl combinations
+            if modifiers.contains(KeyModifiers::CONTROL) {
+                format!("<Ctrl>{}", c.to_ascii_lowercase())
+            } else if c.is_ascii_control() {
+                let ctrl_char = (c as u8 + 96) as char; // Convert control char to letter
+                format!("<Ctrl>{}", ctrl_char)
+            } else {
+                c.to_string()
+            }
+        }
+        KeyCode::Up => "<Up>".to_string(),
+        KeyCode::Down => "<Down>".to_string(),
+        KeyCode::Left => "<Left>".to_string(),
+        KeyCode::Right => "<Right>".to_string(),
+        KeyCode::Enter => "<Enter>".to_string(),
+        KeyCode::Esc => "<Esc>".to_string(),
+        KeyCode::Backspace => "<Backspace>".to_string(),
+        KeyCode::Delete => "<Delete>".to_string(),
+        KeyCode::Home => "<Home>".to_string(),
+        KeyCode::End => "<End>".to_string(),
+        KeyCode::Tab => {
+            // Check for Shift+Tab
+            if modifiers.contains(KeyModifiers::SHIFT) {
+                "<S-Tab>".to_string()
+            } else {
+                "<Tab>".to_string()
+            }
+        }
+        KeyCode::BackTab => "<S-Tab>".to_string(),
+        _ => return None,
+    };
+    Some(key_str)
+}
+
+/// The key press a config string stands for, the reverse of `key_to_string`. `<Space>`
+/// also stands for a space, as a bare " " can't be written between other keys.
+pub fn string_to_key(key: &str) -> Option<(KeyCode, KeyModifiers)> {
+    let plain = |code| Some((code, KeyModifiers::NONE));
+    match key {
+        "<Up>" => plain(KeyCode::Up),
+        "<Down>" => plain(KeyCode::Down),
+        "<Left>" => plain(KeyCode::Left),
+        "<Right>" => plain(KeyCode::Right),
+        "<Enter>" => plain(KeyCode::Enter),
+        "<Esc>" => plain(KeyCode::Esc),
+        "<Backspace>" => plain(KeyCode::Backspace),
+        "<Delete>" => plain(KeyCode::Delete),
+        "<Home>" => plain(KeyCode::Home),
+        "<End>" => plain(KeyCode::End),
+        "<Tab>" => plain(KeyCode::Tab),
+        "<S-Tab>" => Some((KeyCode::BackTab, KeyModifiers::SHIFT)),
+        "<Space>" => plain(KeyCode::Char(' ')),
+        _ => {
+            let (chars, modifiers) = match key.strip_prefix("<Ctrl>") {
+                Some(rest) => (rest, KeyModifiers::CONTROL),
+                None => (key, KeyModifiers::NONE),
+            };
+            let mut chars = chars.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some((KeyCode::Char(c), modifiers)),
+                _ => None,
+            }
         }
     }
 }
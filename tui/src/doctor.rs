@@ -0,0 +1,72 @@
+//! `cloudhost-tui doctor`: what keeps clouds from working or from being reached, with what
+//! to do about it. The same checks run from the Diagnostics entry of the Settings tab,
+//! which also knows the clouds the TUI is running.
+use clap::Args;
+use cloudhost_server::doctor::{worst, Check, Doctor, Status};
+use color_eyre::Result;
+
+#[derive(Args, Debug)]
+pub struct DoctorArgs {
+    /// Skip the checks that need the internet (public IP, reachability, clock)
+    #[arg(long)]
+    offline: bool,
+}
+
+/// Whether the TUI config parses; a missing one is written with the defaults on start
+pub fn tui_config_check() -> Check {
+    let path = cloudhost_server::config_paths::get_tui_config_path();
+    if !path.exists() {
+        return Check::new(
+            "config",
+            Status::Info,
+            format!("No TUI config yet at {}", path.display()),
+        );
+    }
+    // Parsed without upgrading the file, which the TUI does when it starts
+    let parsed = std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|content| crate::config::Config::parse(&content).map_err(|e| e.to_string()));
+    match parsed {
+        Ok(_) => Check::new("config", Status::Ok, format!("{} is valid", path.display())),
+        Err(e) => Check::new("config", Status::Error, e).hint(
+            "Fix the file, or reset it from the Settings tab; until then the defaults are used",
+        ),
+    }
+}
+
+/// The local checks of this process
+pub fn local_checks(doctor: &Doctor) -> Vec<Check> {
+    let mut checks = vec![tui_config_check()];
+    checks.extend(doctor.local_checks());
+    checks
+}
+
+pub async fn run(args: DoctorArgs) -> Result<()> {
+    let doctor = Doctor::new(cloudhost_server::config_paths::get_clouds_config_path());
+    let mut checks = local_checks(&doctor);
+    for check in &checks {
+        println!("{}", check);
+    }
+    if !args.offline {
+        println!("Checking from the internet...");
+        let online = doctor.online_checks(&[]).await;
+        for check in &online {
+            println!("{}", check);
+        }
+        checks.extend(online);
+    }
+
+    let problems = checks
+        .iter()
+        .filter(|check| check.status >= Status::Warning)
+        .count();
+    if problems == 0 {
+        println!("No problems found");
+    } else {
+        println!("{} problem(s) found", problems);
+    }
+    if worst(&checks) == Status::Error {
+        std::process::exit(1);
+    }
+    Ok(())
+}
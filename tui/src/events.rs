@@ -0,0 +1,112 @@
+/// Events delivered to the run loop from background tasks.
+/// Terminal input and the tick are handled by the loop itself; everything else goes through here.
+use crate::utils::config_watcher::ConfigFile;
+use crate::utils::folder_preview::FolderPreview;
+use crate::utils::jobs::JobId;
+use cloudhost_server::stats::{CloudStats, StatsSnapshot};
+use cloudhost_shared::debug_stream::DebugMessage;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// How often a running cloud's statistics are sent
+const STATS_INTERVAL: Duration = Duration::from_secs(1);
+
+pub type EventSender = mpsc::UnboundedSender<AppEvent>;
+pub type EventReceiver = mpsc::UnboundedReceiver<AppEvent>;
+
+#[derive(Debug)]
+pub enum AppEvent {
+    /// A new log line from a running cloud server
+    CloudLog {
+        cloud: String,
+        message: DebugMessage,
+    },
+    /// A cloud server's log stream opened (server running) or closed (server gone)
+    ServerStateChanged { cloud: String, running: bool },
+    /// Fresh statistics of a running cloud
+    CloudStats { cloud: String, stats: StatsSnapshot },
+    /// A background job made progress
+    JobProgress {
+        id: JobId,
+        done: u64,
+        total: Option<u64>,
+    },
+    /// A config file was changed, possibly by another program
+    ConfigFileChanged(ConfigFile),
+    /// Result of inspecting the path typed in a folder modal
+    FolderPreview(FolderPreview),
+    /// Size of a cloud folder, for the detailed folders list
+    FolderScanned(FolderPreview),
+    /// Results of the doctor's checks that need the internet
+    OnlineChecks(Vec<cloudhost_server::doctor::Check>),
+    /// Whether a cloud could be reached from the internet
+    Reachability {
+        cloud: String,
+        check: cloudhost_server::doctor::Check,
+    },
+    /// A background job finished; the Ok value is a message for the user
+    JobFinished {
+        id: JobId,
+        result: Result<String, String>,
+    },
+}
+
+pub fn channel() -> (EventSender, EventReceiver) {
+    mpsc::unbounded_channel()
+}
+
+/// Forward a cloud's debug messages, and its statistics every second, into the event
+/// channel until its server shuts down
+pub fn forward_cloud_logs(
+    cloud: String,
+    mut receiver: tokio::sync::broadcast::Receiver<DebugMessage>,
+    stats: Option<Arc<CloudStats>>,
+    events: EventSender,
+) {
+    tokio::spawn(async move {
+        use tokio::sync::broadcast::error::RecvError;
+
+        let _ = events.send(AppEvent::ServerStateChanged {
+            cloud: cloud.clone(),
+            running: true,
+        });
+        let mut stats_interval = tokio::time::interval(STATS_INTERVAL);
+        loop {
+            let received = tokio::select! {
+                received = receiver.recv() => received,
+                _ = stats_interval.tick(), if stats.is_some() => {
+                    if let Some(stats) = &stats {
+                        let event = AppEvent::CloudStats {
+                            cloud: cloud.clone(),
+                            stats: stats.snapshot(),
+                        };
+                        if events.send(event).is_err() {
+                            return;
+                        }
+                    }
+                    continue;
+                }
+            };
+            match received {
+                Ok(message) => {
+                    let event = AppEvent::CloudLog {
+                        cloud: cloud.clone(),
+                        message,
+                    };
+                    if events.send(event).is_err() {
+                        // The TUI is shutting down
+                        return;
+                    }
+                }
+                // Missed some messages under load; the history still has them
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+        let _ = events.send(AppEvent::ServerStateChanged {
+            cloud,
+            running: false,
+        });
+    });
+}
@@ -0,0 +1,193 @@
+//! How the tabs arrange their panels for the size of the terminal. Wide terminals get the
+//! panels side by side, narrower ones get them on top of each other, and the smallest
+//! only the focused panel under a line naming the others. The `[layout]` section of the
+//! TUI config sets where one layout gives way to the next, and how wide the columns of the
+//! Clouds and Folders tabs are, in percent:
+//!
+//! ```toml
+//! [layout]
+//! stack_below_width = 100
+//! single_below_width = 60
+//! single_below_height = 24
+//! clouds_columns = [15, 35, 50]
+//! folders_columns = [33, 33, 34]
+//! ```
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use serde::{Deserialize, Serialize};
+
+/// Narrowest a column can be resized to, in percent
+pub const MIN_COLUMN_PERCENT: u16 = 10;
+/// How far one resize moves a column's edge, in percent
+pub const RESIZE_STEP: u16 = 5;
+
+const DEFAULT_CLOUDS_COLUMNS: [u16; 3] = [15, 35, 50];
+const DEFAULT_FOLDERS_COLUMNS: [u16; 3] = [33, 33, 34];
+
+/// `[layout]` in the TUI config, in columns and rows of the tab's area
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutConfig {
+    /// Narrower than this, panels are stacked instead of side by side
+    pub stack_below_width: u16,
+    /// Narrower than this, only the focused panel is shown
+    pub single_below_width: u16,
+    /// Stacked panels shorter than this are shown one at a time as well
+    pub single_below_height: u16,
+    /// Widths of the clouds, info and logs columns of the Clouds tab
+    pub clouds_columns: [u16; 3],
+    /// Widths of the folders and files, clouds and info columns of the Folders tab
+    pub folders_columns: [u16; 3],
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            stack_below_width: 100,
+            single_below_width: 60,
+            single_below_height: 24,
+            clouds_columns: DEFAULT_CLOUDS_COLUMNS,
+            folders_columns: DEFAULT_FOLDERS_COLUMNS,
+        }
+    }
+}
+
+impl LayoutConfig {
+    pub fn mode(&self, area: Rect) -> LayoutMode {
+        if area.width < self.single_below_width {
+            LayoutMode::Single
+        } else if area.width >= self.stack_below_width {
+            LayoutMode::Columns
+        } else if area.height < self.single_below_height {
+            LayoutMode::Single
+        } else {
+            LayoutMode::Stacked
+        }
+    }
+
+    /// Problems with the sizes, one message each
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.single_below_width > self.stack_below_width {
+            problems.push(format!(
+                "layout.single_below_width ({}) is more than layout.stack_below_width ({}), so panels are never stacked",
+                self.single_below_width, self.stack_below_width
+            ));
+        }
+        for (name, columns) in [
+            ("clouds_columns", self.clouds_columns),
+            ("folders_columns", self.folders_columns),
+        ] {
+            if !valid_columns(columns) {
+                problems.push(format!(
+                    "layout.{}: {:?} should add up to 100 with none under {}, using the default",
+                    name, columns, MIN_COLUMN_PERCENT
+                ));
+            }
+        }
+        problems
+    }
+
+    pub fn clouds_columns(&self) -> [Constraint; 3] {
+        column_constraints(self.clouds_columns, DEFAULT_CLOUDS_COLUMNS)
+    }
+
+    pub fn folders_columns(&self) -> [Constraint; 3] {
+        column_constraints(self.folders_columns, DEFAULT_FOLDERS_COLUMNS)
+    }
+}
+
+fn valid_columns(columns: [u16; 3]) -> bool {
+    columns.iter().sum::<u16>() == 100 && columns.iter().all(|&c| c >= MIN_COLUMN_PERCENT)
+}
+
+/// `columns` as percentages, or `default` when they don't make sense
+fn column_constraints(columns: [u16; 3], default: [u16; 3]) -> [Constraint; 3] {
+    let columns = if valid_columns(columns) {
+        columns
+    } else {
+        default
+    };
+    columns.map(Constraint::Percentage)
+}
+
+/// Make `column` wider, or narrower when `grow` is false, by `RESIZE_STEP`. The width comes
+/// from or goes to the column on its right, or on its left for the last one. False when a
+/// column would get narrower than `MIN_COLUMN_PERCENT`.
+pub fn resize_column(columns: &mut [u16; 3], column: usize, grow: bool) -> bool {
+    if !valid_columns(*columns) || column >= columns.len() {
+        return false;
+    }
+    let neighbour = if column + 1 < columns.len() {
+        column + 1
+    } else {
+        column - 1
+    };
+    let (wider, narrower) = if grow {
+        (column, neighbour)
+    } else {
+        (neighbour, column)
+    };
+    if columns[narrower] < MIN_COLUMN_PERCENT + RESIZE_STEP {
+        return false;
+    }
+    columns[narrower] -= RESIZE_STEP;
+    columns[wider] += RESIZE_STEP;
+    true
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutMode {
+    /// Side by side
+    Columns,
+    /// On top of each other
+    Stacked,
+    /// The focused panel alone
+    Single,
+}
+
+/// Where a tab's panels go
+pub struct PanelAreas<const N: usize> {
+    /// One area per panel; panels that aren't shown get an empty one
+    pub panels: [Rect; N],
+    /// The line for the panel switcher, in the single panel layout
+    pub switcher: Option<Rect>,
+}
+
+/// Split `area` for `N` panels: by `columns` side by side, by `rows` stacked, or all of it
+/// but the switcher line for the `focused` panel
+pub fn panel_areas<const N: usize>(
+    mode: LayoutMode,
+    area: Rect,
+    columns: [Constraint; N],
+    rows: [Constraint; N],
+    focused: usize,
+) -> PanelAreas<N> {
+    match mode {
+        LayoutMode::Columns => PanelAreas {
+            panels: Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(columns)
+                .areas(area),
+            switcher: None,
+        },
+        LayoutMode::Stacked => PanelAreas {
+            panels: Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(rows)
+                .areas(area),
+            switcher: None,
+        },
+        LayoutMode::Single => {
+            let [switcher, panel] =
+                Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
+            let mut panels = [Rect::default(); N];
+            if let Some(focused) = panels.get_mut(focused) {
+                *focused = panel;
+            }
+            PanelAreas {
+                panels,
+                switcher: Some(switcher),
+            }
+        }
+    }
+}
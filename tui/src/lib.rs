@@ -0,0 +1,93 @@
+//! The CloudHost TUI. The binary parses its arguments and runs `App` in the terminal;
+//! everything else lives here, so tests can drive and render an `App` without one.
+use color_eyre::Result;
+use futures_util::StreamExt;
+use ratatui::{
+    crossterm::{
+        event::{DisableBracketedPaste, EnableBracketedPaste, Event, EventStream, KeyEventKind},
+        execute,
+    },
+    DefaultTerminal,
+};
+use std::time::Duration;
+use utils::script::Script;
+
+pub mod bench;
+pub mod cli;
+pub mod components;
+pub mod config;
+pub mod doctor;
+pub mod error;
+pub mod events;
+pub mod layout;
+pub mod models;
+pub mod tabs;
+pub mod theme;
+pub mod utils;
+pub use models::App;
+
+/// How often the UI refreshes when nothing else happens
+const TICK_RATE: Duration = Duration::from_millis(250);
+
+impl App {
+    /// Run in `terminal` until quit, typing the keys of `script` first
+    pub async fn run(
+        mut self,
+        mut terminal: DefaultTerminal,
+        script: Option<Script>,
+    ) -> Result<()> {
+        let (events_tx, mut events_rx) = events::channel();
+        self.start_config_watcher(events_tx.clone());
+        self.events = Some(events_tx);
+
+        // Pasted text arrives as one event instead of a key per character
+        execute!(std::io::stdout(), EnableBracketedPaste)?;
+        let mut input = EventStream::new();
+        let mut tick = tokio::time::interval(TICK_RATE);
+        tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        self.update_cloud_logs().await;
+        self.start_landing().await;
+        if let Some(script) = script {
+            self.run_script(&script).await;
+        }
+
+        while self.state == models::AppState::Running {
+            terminal.draw(|frame| frame.render_widget(&mut self, frame.area()))?;
+
+            tokio::select! {
+                maybe_event = input.next() => match maybe_event {
+                    Some(Ok(event)) => self.handle_terminal_event(event).await,
+                    Some(Err(e)) => return Err(e.into()),
+                    None => break,
+                },
+                Some(event) = events_rx.recv() => self.handle_app_event(event).await,
+                _ = tick.tick() => self.on_tick().await,
+            }
+
+            // Hand the terminal over to the editor, then take it back.
+            // The input stream is dropped first so it doesn't swallow the editor's keys.
+            if let Some(path) = self.pending_editor.take() {
+                drop(input);
+                execute!(std::io::stdout(), DisableBracketedPaste)?;
+                ratatui::restore();
+                self.open_in_editor(&path);
+                terminal = ratatui::init();
+                execute!(std::io::stdout(), EnableBracketedPaste)?;
+                input = EventStream::new();
+            }
+        }
+        execute!(std::io::stdout(), DisableBracketedPaste)?;
+        Ok(())
+    }
+
+    async fn handle_terminal_event(&mut self, event: Event) {
+        match event {
+            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                self.handle_dynamic_key(key.code, key.modifiers).await;
+            }
+            Event::Paste(text) => self.handle_paste(&text),
+            _ => {}
+        }
+    }
+}
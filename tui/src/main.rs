@@ -1,18 +1,14 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use cloudhost_server::config_paths;
+use cloudhost_server::LogLevel;
+use cloudhost_tui::bench::BenchArgs;
+use cloudhost_tui::cli::{CloudCommand, FolderCommand, PasswordCommand, StartArgs};
+use cloudhost_tui::doctor::DoctorArgs;
+use cloudhost_tui::utils::script::Script;
+use cloudhost_tui::App;
 use color_eyre::Result;
-use ratatui::{
-    crossterm::event::{self, Event, KeyEventKind},
-    DefaultTerminal,
-};
-
-mod components;
-mod config;
-mod error;
-mod models;
-mod tabs;
-mod utils;
-use error::TuiResult;
-use models::App;
+use std::net::IpAddr;
+use std::path::PathBuf;
 
 /// CloudHost TUI - Personal Cloud Storage Server
 #[derive(Parser, Debug)]
@@ -29,10 +25,73 @@ struct Args {
     /// Enable debug logging
     #[arg(short = 'v', long)]
     debug: bool,
+
+    /// Type the keys listed in FILE once the TUI has started, e.g. to automate a setup
+    #[arg(long, value_name = "FILE")]
+    script: Option<std::path::PathBuf>,
+
+    /// Keep the configs in DIR from now on, offering to move the current ones there
+    #[arg(long, value_name = "DIR", global = true)]
+    config_dir: Option<PathBuf>,
+
+    /// Use the configs and data of profile NAME, created on first save
+    /// [env: CLOUDHOST_PROFILE]
+    #[arg(long, value_name = "NAME", global = true)]
+    profile: Option<String>,
+
+    /// Use FILE as the clouds config [env: CLOUDHOST_CONFIG]
+    #[arg(long, value_name = "FILE", global = true)]
+    config: Option<PathBuf>,
+
+    /// Keep logs, caches and backups in DIR [env: CLOUDHOST_DATA_DIR]
+    #[arg(long, value_name = "DIR", global = true)]
+    data_dir: Option<PathBuf>,
+
+    /// Port of the first cloud, over the clouds config [env: CLOUDHOST_BASE_PORT]
+    #[arg(long, value_name = "PORT", global = true, value_parser = clap::value_parser!(u16).range(1..))]
+    base_port: Option<u16>,
+
+    /// Address clouds listen on, over the clouds config [env: CLOUDHOST_BIND_ADDRESS]
+    #[arg(long, value_name = "ADDRESS", global = true)]
+    bind_address: Option<IpAddr>,
+
+    /// Log level of clouds without their own, over the clouds config
+    /// [env: CLOUDHOST_LOG_LEVEL]
+    #[arg(long, value_name = "LEVEL", global = true)]
+    log_level: Option<LogLevel>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Measure listing latency and download throughput of a running cloud
+    Bench(BenchArgs),
+    /// Check the configs, folders, ports, firewall, certificates, reachability and clock
+    Doctor(DoctorArgs),
+    /// Add, list and remove clouds
+    Cloud {
+        #[command(subcommand)]
+        command: CloudCommand,
+    },
+    /// Add, list and remove cloud folders
+    Folder {
+        #[command(subcommand)]
+        command: FolderCommand,
+    },
+    /// Set cloud passwords
+    Password {
+        #[command(subcommand)]
+        command: PasswordCommand,
+    },
+    /// Serve clouds without the TUI until Ctrl+C
+    Start(StartArgs),
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+/// Sets the environment up from the flags before the runtime starts: the variables
+/// mustn't change while other threads might read them
+fn main() -> Result<()> {
     color_eyre::install()?;
 
     // Parse command line arguments
@@ -53,45 +112,78 @@ async fn main() -> Result<()> {
         std::env::set_var("DEBUG", "1");
     }
 
-    // Don't initialize global tracing to avoid breaking TUI
-    // tracing_subscriber::fmt::init();
-
-    // Initialize debug stream for server-to-TUI communication
+    if let Some(dir) = &args.config_dir {
+        cloudhost_tui::cli::change_config_dir(dir)?;
+    }
 
-    let terminal = ratatui::init();
-    let app = App::new();
+    // Profiles live in the config directory chosen above
+    if let Some(profile) = &args.profile {
+        config_paths::set_profile(profile).map_err(|e| color_eyre::eyre::eyre!(e))?;
+    } else if let Ok(profile) = std::env::var(config_paths::PROFILE_VAR) {
+        config_paths::validate_profile_name(&profile)
+            .map_err(|e| color_eyre::eyre::eyre!("{}: {}", config_paths::PROFILE_VAR, e))?;
+    }
 
-    // Load TUI config
-    let _config = crate::config::Config::load();
+    // Flags win over the environment by replacing it; everything reads the variables
+    let overrides = [
+        (
+            config_paths::CLOUDS_CONFIG_VAR,
+            args.config.map(|path| path.display().to_string()),
+        ),
+        (
+            config_paths::DATA_DIR_VAR,
+            args.data_dir.map(|dir| dir.display().to_string()),
+        ),
+        (
+            config_paths::BASE_PORT_VAR,
+            args.base_port.map(|port| port.to_string()),
+        ),
+        (
+            config_paths::BIND_ADDRESS_VAR,
+            args.bind_address.map(|address| address.to_string()),
+        ),
+        (
+            config_paths::LOG_LEVEL_VAR,
+            args.log_level.map(|level| level.to_string()),
+        ),
+    ];
+    for (var, value) in overrides {
+        if let Some(value) = value {
+            std::env::set_var(var, value);
+        }
+    }
+    config_paths::Overrides::from_env().map_err(|e| color_eyre::eyre::eyre!(e))?;
 
-    let app_result = app.run(terminal).await;
-    ratatui::restore();
-    app_result
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(run(args.command, args.script))
 }
 
-impl App {
-    async fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
-        // Start debug stream subscription
-        while self.state == models::AppState::Running {
-            terminal.draw(|frame| frame.render_widget(&mut self, frame.area()))?;
+async fn run(command: Option<Command>, script: Option<PathBuf>) -> Result<()> {
+    match command {
+        Some(Command::Bench(args)) => return cloudhost_tui::bench::run(args).await,
+        Some(Command::Doctor(args)) => return cloudhost_tui::doctor::run(args).await,
+        Some(Command::Cloud { command }) => return cloudhost_tui::cli::cloud(command).await,
+        Some(Command::Folder { command }) => return cloudhost_tui::cli::folder(command).await,
+        Some(Command::Password { command }) => return cloudhost_tui::cli::password(command).await,
+        Some(Command::Start(args)) => return cloudhost_tui::cli::start(args).await,
+        None => {}
+    }
 
-            // Check for timeouts before handling new events
-            self.check_timeouts().await;
+    // No fmt subscriber: it would write over the TUI. Starting a cloud installs one that
+    // forwards server events to the cloud's debug stream instead.
 
-            // Update server logs periodically
-            self.update_cloud_logs().await;
+    let script = script
+        .as_deref()
+        .map(Script::load)
+        .transpose()
+        .map_err(|e| color_eyre::eyre::eyre!(e))?;
 
-            self.handle_events().await?;
-        }
-        Ok(())
-    }
+    let terminal = ratatui::init();
+    let app = App::new();
 
-    async fn handle_events(&mut self) -> TuiResult<()> {
-        if let Event::Key(key) = event::read()? {
-            if key.kind == KeyEventKind::Press {
-                self.handle_dynamic_key(key.code, key.modifiers).await;
-            }
-        }
-        Ok(())
-    }
+    let app_result = app.run(terminal, script).await;
+    ratatui::restore();
+    app_result
 }
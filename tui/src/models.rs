@@ -1,6 +1,6 @@
 use ratatui::{
     buffer::Buffer,
-    crossterm::event::KeyCode,
+    crossterm::event::{KeyCode, KeyModifiers},
     layout::Rect,
     prelude::Stylize,
     text::Line,
@@ -8,8 +8,23 @@ use ratatui::{
 };
 use strum::IntoEnumIterator;
 
-use crate::tabs::{clouds, focus::TabFocus, folders, settings, SelectedTab};
-use cloudhost_server::debug_stream::DebugMessage;
+use crate::layout::resize_column;
+use crate::tabs::clouds::models::{log_line, CloudFocusedPanel};
+use crate::tabs::folders::models::FolderSortColumn;
+use crate::tabs::{client, clouds, focus::TabFocus, folders, settings, SelectedTab};
+use crate::theme::Theme;
+use crate::utils::jobs::JobKind;
+use crate::utils::notifications::Severity;
+use crate::utils::text_input::TextInput;
+use cloudhost_server::config_paths::Overrides;
+use cloudhost_server::secrets::SecretsProtection;
+
+/// Log lines of the selected cloud kept in the Clouds tab (older ones stay on disk)
+const MAX_CLOUD_LOGS: usize = 1000;
+
+/// Master passphrase for encrypted cloud secrets, for unattended starts
+pub(crate) const SECRETS_PASSPHRASE_ENV: &str = "CLOUDHOST_SECRETS_PASSPHRASE";
+use cloudhost_shared::debug_stream::{DebugMessage, LogLevel};
 
 // Timeout for key sequences (like Vim's timeoutlen)
 const KEY_SEQUENCE_TIMEOUT_MS: u64 = 1000; // 1 second
@@ -25,7 +40,62 @@ pub struct App {
     pub debug_info: Vec<String>,
     pub cloud_logs: Vec<DebugMessage>,
     pub debug_receiver:
-        Option<std::sync::Arc<std::sync::Mutex<Vec<cloudhost_server::debug_stream::DebugMessage>>>>,
+        Option<std::sync::Arc<std::sync::Mutex<Vec<cloudhost_shared::debug_stream::DebugMessage>>>>,
+    pub command_line: crate::utils::command_line::CommandLineState,
+    pub fuzzy_finder: crate::utils::fuzzy_finder::FuzzyFinderState,
+    pub show_help: bool,
+    pub help_scroll: u16,
+    // Cloud whose webhook deliveries are shown
+    pub webhook_history: Option<String>,
+    pub webhook_history_scroll: u16,
+    // Cloud and the recovery code issued for it, shown until closed
+    pub recovery_code: Option<(String, String)>,
+    // System clipboard for the Copy actions, and text that couldn't go on it, shown instead
+    pub clipboard: crate::utils::clipboard::Clipboard,
+    pub copy_fallback: Option<crate::utils::clipboard::CopyFallback>,
+    // Opens cloud URLs in the browser and folders in the file manager
+    pub launcher: crate::utils::launcher::Launcher,
+    // Results of the doctor, shown until closed
+    pub diagnostics: Option<crate::utils::diagnostics::Diagnostics>,
+    // Profiles to switch to, shown until one is picked or closed
+    pub profile_picker: Option<crate::utils::profile_picker::ProfilePicker>,
+    // Cloud whose connected clients are shown, and the selected client
+    pub clients_panel: Option<String>,
+    pub clients_selected: usize,
+    // File waiting to be opened in an external editor (needs the terminal released)
+    pub pending_editor: Option<std::path::PathBuf>,
+    // Removed clouds and folders that can be restored with Undo
+    pub undo_stack: crate::utils::undo::UndoStack,
+    // Channel background tasks use to reach the run loop (set when the loop starts)
+    pub events: Option<crate::events::EventSender>,
+    pub jobs: crate::utils::jobs::JobList,
+    pub notifications: crate::utils::notifications::Notifications,
+    // Jobs panel with the full history (otherwise only running/recent jobs float in the corner)
+    pub show_jobs: bool,
+    // Scheduled tasks panel and its selected task
+    pub show_tasks: bool,
+    pub tasks_selected: usize,
+    // When disk space was last checked, and the folders already warned about
+    disk_space_checked: Option<std::time::Instant>,
+    disk_space_warned:
+        std::collections::HashMap<(String, String), cloudhost_server::disk_space::SpaceLevel>,
+    // When the cloud folders were last checked to still be there
+    folder_health_checked: Option<std::time::Instant>,
+    // Keeps the config file watcher alive
+    config_watcher: Option<notify::RecommendedWatcher>,
+    // Config files changed on disk and when to reload them (debounced)
+    pending_config_reloads:
+        std::collections::HashMap<crate::utils::config_watcher::ConfigFile, std::time::Instant>,
+    // The clouds config changed on disk while we had unsaved changes
+    pub show_config_conflict: bool,
+    // Yes/no question before a destructive action
+    pub confirmation: Option<crate::utils::confirmation::Confirmation>,
+    // Passphrase entry for encrypted config exports/imports and cloud secrets
+    pub passphrase_prompt: Option<crate::utils::passphrase_prompt::PassphrasePrompt>,
+    // Clouds whose log stream is being forwarded into the event channel
+    log_subscriptions: std::collections::HashSet<String>,
+    // Cloud whose logs are currently shown in the Clouds tab
+    logs_cloud: Option<String>,
 
     // Shared orchestrator instance - owns all cloud/folder/server management
     pub orchestrator: cloudhost_server::Orchestrator,
@@ -33,6 +103,7 @@ pub struct App {
     // Tab states
     pub clouds_state: clouds::models::CloudsState,
     pub folders_state: folders::models::FoldersState,
+    pub client_state: client::models::ClientState,
     pub settings_state: settings::models::SettingsState,
 }
 
@@ -65,11 +136,18 @@ impl App {
             orchestrator,
             clouds_state: clouds::models::CloudsState::new(),
             folders_state: folders::models::FoldersState::default(),
+            client_state: client::models::ClientState::new(),
             settings_state: settings::models::SettingsState::new(),
             debug_receiver: None,
             ..Default::default()
         };
 
+        if let Some(e) = app.orchestrator.take_config_error() {
+            app.notify(Severity::Error, e.to_string());
+        }
+        app.warn_about_tui_config();
+        app.unlock_secrets_on_startup();
+
         // Load folders and clouds from orchestrator into the folders state
         app.load_folders_from_orchestrator();
 
@@ -80,7 +158,9 @@ impl App {
         self.folders_state.cloud_folders = self.orchestrator.get_cloud_folders();
         self.folders_state.clouds = self.orchestrator.get_clouds();
         // Also update clouds state
-        self.clouds_state.clouds = self.orchestrator.get_clouds();
+        self.clouds_state.set_clouds(self.orchestrator.get_clouds());
+        self.folders_state.sort_folders();
+        self.folders_state.sync_files_panel();
     }
 
     fn start_creating_folder(&mut self) {
@@ -90,6 +170,7 @@ impl App {
             self.folders_state.new_folder_path.clear();
             self.folders_state.folder_input_field = folders::models::FolderInputField::Name;
             self.folders_state.folder_creation_error = None;
+            self.folders_state.reset_path_input();
         }
     }
 
@@ -109,6 +190,13 @@ impl App {
                     }
                     self.folders_state.start_creating_cloud();
                 }
+                folders::models::FocusedPanel::Files => {
+                    if self.folders_state.files.folder_name.is_some() {
+                        self.folders_state.files.start_creating_directory();
+                    } else {
+                        self.add_debug("Select a cloud folder to create a directory in.");
+                    }
+                }
                 folders::models::FocusedPanel::Info => {
                     // Info panel doesn't support creation
                     self.add_debug(
@@ -124,15 +212,16 @@ impl App {
             && !self.folders_state.cloud_folders.is_empty()
             && self.folders_state.selected_folder_index < self.folders_state.cloud_folders.len()
         {
-            let folder_name = self.folders_state.cloud_folders
-                [self.folders_state.selected_folder_index]
-                .name
-                .clone();
+            let folder =
+                self.folders_state.cloud_folders[self.folders_state.selected_folder_index].clone();
+            let folder_name = folder.name.clone();
 
             if let Err(e) = self.orchestrator.remove_cloud_folder(&folder_name) {
-                self.add_debug(&format!("Failed to remove folder: {}", e));
+                self.notify(Severity::Error, format!("Failed to remove folder: {}", e));
                 return;
             }
+            self.undo_stack
+                .record(crate::utils::undo::RemovedItem::Folder(folder));
 
             // Reload from orchestrator
             self.load_folders_from_orchestrator();
@@ -145,7 +234,249 @@ impl App {
                     self.folders_state.cloud_folders.len() - 1;
             }
 
-            self.add_debug(&format!("Deleted folder '{}'", folder_name));
+            self.notify(
+                Severity::Success,
+                format!("Deleted folder '{}' (u to undo)", folder_name),
+            );
+        }
+    }
+
+    fn delete_selected_file(&mut self) {
+        if self.selected_tab == SelectedTab::Folders {
+            match self.folders_state.files.delete_selected() {
+                Ok(name) => self.notify(Severity::Success, format!("Deleted '{}'", name)),
+                Err(e) => {
+                    self.notify(Severity::Error, e.clone());
+                    self.folders_state.files.error = Some(e);
+                }
+            }
+        }
+    }
+
+    /// Enter on the Folders tab: jump into the files panel, enter a directory or edit a file
+    fn open_file_entry(&mut self) {
+        match self.folders_state.focused_panel {
+            folders::models::FocusedPanel::Folders => {
+                self.folders_state.sync_files_panel();
+                self.folders_state.focused_panel = folders::models::FocusedPanel::Files;
+            }
+            folders::models::FocusedPanel::Files => {
+                if let Some(path) = self.folders_state.files.open_selected() {
+                    self.add_debug(&format!("Opening '{}' in editor", path.display()));
+                    self.pending_editor = Some(path);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Open a file in $VISUAL/$EDITOR, or the system default application if neither is set.
+    /// The terminal must be restored before calling this and re-initialized afterwards.
+    /// Copy `text` to the clipboard, or show it to be copied by hand when there is none
+    pub fn copy_to_clipboard(&mut self, what: String, text: String) {
+        match self.clipboard.copy(&text) {
+            Ok(()) => self.notify(Severity::Success, format!("Copied the {}", what)),
+            Err(reason) => {
+                self.add_debug(&format!("Clipboard unavailable: {}", reason));
+                self.copy_fallback =
+                    Some(crate::utils::clipboard::CopyFallback { what, text, reason });
+            }
+        }
+    }
+
+    /// The cloud selected in the current tab: the Folders tab has its own list of clouds
+    fn selected_cloud_name(&self) -> Option<String> {
+        let cloud = if self.selected_tab == SelectedTab::Folders {
+            self.folders_state
+                .clouds
+                .get(self.folders_state.selected_cloud_index)
+        } else {
+            self.clouds_state
+                .clouds
+                .get(self.clouds_state.selected_cloud_index)
+        };
+        cloud.map(|cloud| cloud.name.clone())
+    }
+
+    /// URL of the selected cloud, which has to be running to have one
+    fn copy_cloud_url(&mut self) {
+        let Some(cloud) = self.selected_cloud_name() else {
+            self.add_debug("No cloud selected");
+            return;
+        };
+        match self.orchestrator.get_cloud_server_url(&cloud) {
+            Some(url) => self.copy_to_clipboard(format!("URL of '{}'", cloud), url),
+            None => self.notify(
+                Severity::Warning,
+                format!("Cloud '{}' is not running, start it to get its URL", cloud),
+            ),
+        }
+    }
+
+    /// Path on disk of the selected folder, or of the selected file in the files panel
+    fn copy_path(&mut self) {
+        if self.selected_tab != SelectedTab::Folders {
+            return;
+        }
+        let selected = if self.folders_state.focused_panel == folders::models::FocusedPanel::Files {
+            self.folders_state
+                .files
+                .selected_entry()
+                .map(|entry| (entry.name.clone(), entry.path.clone()))
+        } else {
+            self.folders_state
+                .cloud_folders
+                .get(self.folders_state.selected_folder_index)
+                .map(|folder| (folder.name.clone(), folder.folder_path.clone()))
+        };
+        if let Some((name, path)) = selected {
+            self.copy_to_clipboard(
+                format!("path of '{}'", name),
+                path.to_string_lossy().to_string(),
+            );
+        }
+    }
+
+    /// Links of the selected cloud's file requests that still take files, one per line
+    fn copy_share_links(&mut self) {
+        let Some(cloud) = self.selected_cloud_name() else {
+            self.add_debug("No cloud selected");
+            return;
+        };
+        let Some(url) = self.orchestrator.get_cloud_server_url(&cloud) else {
+            self.notify(
+                Severity::Warning,
+                format!("Cloud '{}' is not running, start it to share links", cloud),
+            );
+            return;
+        };
+        let links: Vec<String> = self
+            .orchestrator
+            .get_cloud(&cloud)
+            .map(|c| c.file_requests)
+            .unwrap_or_default()
+            .iter()
+            .filter(|request| request.check_open().is_ok())
+            .map(|request| format!("{}/drop/{}", url, request.id))
+            .collect();
+        if links.is_empty() {
+            self.notify(
+                Severity::Info,
+                format!("Cloud '{}' has no open file request links", cloud),
+            );
+            return;
+        }
+        let what = match links.len() {
+            1 => format!("file request link of '{}'", cloud),
+            n => format!("{} file request links of '{}'", n, cloud),
+        };
+        self.copy_to_clipboard(what, links.join("\n"));
+    }
+
+    /// Open `target` in the browser or file manager; when that can't be done, copy it
+    pub fn open_externally(&mut self, what: String, target: String) {
+        match self.launcher.open(&target) {
+            Ok(()) => self.notify(Severity::Info, format!("Opened the {}", what)),
+            Err(reason) => {
+                self.notify(
+                    Severity::Warning,
+                    format!("Couldn't open the {} ({})", what, reason),
+                );
+                self.copy_to_clipboard(what, target);
+            }
+        }
+    }
+
+    /// The selected cloud in the default browser, which has to be running
+    fn open_cloud_in_browser(&mut self) {
+        let Some(cloud) = self.selected_cloud_name() else {
+            self.add_debug("No cloud selected");
+            return;
+        };
+        match self.orchestrator.get_cloud_server_url(&cloud) {
+            Some(url) => self.open_externally(format!("URL of '{}'", cloud), url),
+            None => self.notify(
+                Severity::Warning,
+                format!("Cloud '{}' is not running, start it to open it", cloud),
+            ),
+        }
+    }
+
+    /// The selected folder in the file manager, or the directory shown in the files panel
+    fn open_folder_in_file_manager(&mut self) {
+        if self.selected_tab != SelectedTab::Folders {
+            return;
+        }
+        let selected = if self.folders_state.focused_panel == folders::models::FocusedPanel::Files {
+            let dir = self.folders_state.files.current_dir.clone();
+            (!dir.as_os_str().is_empty()).then(|| {
+                let name = dir
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| dir.to_string_lossy().to_string());
+                (name, dir)
+            })
+        } else {
+            self.folders_state
+                .cloud_folders
+                .get(self.folders_state.selected_folder_index)
+                .map(|folder| (folder.name.clone(), folder.folder_path.clone()))
+        };
+        if let Some((name, path)) = selected {
+            self.open_externally(
+                format!("path of '{}'", name),
+                path.to_string_lossy().to_string(),
+            );
+        }
+    }
+
+    pub fn open_in_editor(&mut self, path: &std::path::Path) {
+        let editor = std::env::var("VISUAL")
+            .or_else(|_| std::env::var("EDITOR"))
+            .ok()
+            .filter(|editor| !editor.trim().is_empty());
+
+        let result = match editor {
+            Some(editor) => {
+                // Allow editors configured with arguments, e.g. "code --wait"
+                let mut parts = editor.split_whitespace();
+                let program = parts.next().unwrap_or_default();
+                std::process::Command::new(program)
+                    .args(parts)
+                    .arg(path)
+                    .status()
+                    .map(|_| ())
+            }
+            None => open::that(path),
+        };
+
+        if let Err(e) = result {
+            self.folders_state.files.error = Some(format!("Failed to open editor: {}", e));
+        }
+        self.folders_state.files.reload();
+    }
+
+    fn handle_directory_creation_input(&mut self, key: KeyCode, modifiers: KeyModifiers) -> bool {
+        match key {
+            KeyCode::Esc => {
+                self.folders_state.files.clear_directory_creation();
+                true
+            }
+            KeyCode::Enter => {
+                match self.folders_state.files.create_directory() {
+                    Ok(path) => self.notify(
+                        Severity::Success,
+                        format!("Created directory {}", path.display()),
+                    ),
+                    Err(e) => self.folders_state.files.directory_creation_error = Some(e),
+                }
+                true
+            }
+            _ => self
+                .folders_state
+                .files
+                .new_directory_name
+                .handle_key(key, modifiers),
         }
     }
 
@@ -154,14 +485,15 @@ impl App {
             && !self.folders_state.clouds.is_empty()
             && self.folders_state.selected_cloud_index < self.folders_state.clouds.len()
         {
-            let cloud_name = self.folders_state.clouds[self.folders_state.selected_cloud_index]
-                .name
-                .clone();
+            let cloud = self.folders_state.clouds[self.folders_state.selected_cloud_index].clone();
+            let cloud_name = cloud.name.clone();
 
             if let Err(e) = self.orchestrator.remove_cloud(&cloud_name) {
-                self.add_debug(&format!("Failed to remove cloud: {}", e));
+                self.notify(Severity::Error, format!("Failed to remove cloud: {}", e));
                 return;
             }
+            self.undo_stack
+                .record(crate::utils::undo::RemovedItem::Cloud(Box::new(cloud)));
 
             // Reload from orchestrator
             self.load_folders_from_orchestrator();
@@ -173,7 +505,197 @@ impl App {
                 self.folders_state.selected_cloud_index = self.folders_state.clouds.len() - 1;
             }
 
-            self.add_debug(&format!("Deleted cloud '{}'", cloud_name));
+            self.notify(
+                Severity::Success,
+                format!("Deleted cloud '{}' (u to undo)", cloud_name),
+            );
+        }
+    }
+
+    /// Delete the selected folder, cloud or file, asking first unless confirmations are off
+    async fn request_delete(&mut self, cloud: bool) {
+        use crate::utils::confirmation::{ConfirmAction, Confirmation};
+
+        if self.selected_tab != SelectedTab::Folders {
+            return;
+        }
+        let confirmation = if cloud {
+            self.folders_state
+                .clouds
+                .get(self.folders_state.selected_cloud_index)
+                .map(|c| {
+                    let mut details = vec![format!(
+                        "Shares {} folder(s); the files stay on disk",
+                        c.cloud_folders.len()
+                    )];
+                    if let Some(port) = self.orchestrator.get_cloud_port(&c.name) {
+                        details.push(format!("Still running on port {}", port));
+                    }
+                    Confirmation::new(ConfirmAction::DeleteCloud(c.name.clone()))
+                        .with_details(details)
+                })
+        } else if self.folders_state.focused_panel == folders::models::FocusedPanel::Files {
+            self.folders_state
+                .files
+                .selected_entry()
+                .map(|e| Confirmation::new(ConfirmAction::DeleteFile(e.name.clone())))
+        } else {
+            self.folders_state
+                .cloud_folders
+                .get(self.folders_state.selected_folder_index)
+                .map(|f| {
+                    let clouds = self.folders_state.clouds_with_folder(&f.name);
+                    let details = if clouds.is_empty() {
+                        vec!["No cloud shares it; the files stay on disk".to_string()]
+                    } else {
+                        vec![format!("Removed from {}", clouds.join(", "))]
+                    };
+                    Confirmation::new(ConfirmAction::DeleteFolder(f.name.clone()))
+                        .with_details(details)
+                })
+        };
+        let Some(confirmation) = confirmation else {
+            return;
+        };
+
+        if self.config.confirmations {
+            self.confirmation = Some(confirmation);
+        } else {
+            self.perform_confirmed(confirmation.action).await;
+        }
+    }
+
+    /// Stop the selected cloud, asking first while it's serving clients unless
+    /// confirmations are off
+    async fn request_stop(&mut self, name: String) {
+        use crate::utils::confirmation::{ConfirmAction, Confirmation};
+
+        let clients = self.orchestrator.cloud_clients(&name);
+        if self.config.confirmations && !clients.is_empty() {
+            let mut details = vec![format!("{} client(s) still connected:", clients.len())];
+            details.extend(
+                clients
+                    .iter()
+                    .take(3)
+                    .map(|client| format!("{} {} {}", client.ip, client.method, client.path)),
+            );
+            self.confirmation =
+                Some(Confirmation::new(ConfirmAction::StopCloud(name)).with_details(details));
+            return;
+        }
+        self.perform_confirmed(ConfirmAction::StopCloud(name)).await;
+    }
+
+    async fn perform_confirmed(&mut self, action: crate::utils::confirmation::ConfirmAction) {
+        use crate::utils::confirmation::ConfirmAction;
+
+        match action {
+            ConfirmAction::DeleteFolder(_) => self.delete_selected_folder(),
+            ConfirmAction::DeleteCloud(_) => self.delete_selected_cloud(),
+            ConfirmAction::DeleteFile(_) => self.delete_selected_file(),
+            ConfirmAction::StopCloud(name) => {
+                if self.select_cloud(&name) {
+                    self.clouds_state.stop_server(&mut self.orchestrator).await;
+                    self.report_cloud_command(&name, "Stopped");
+                }
+            }
+        }
+    }
+
+    async fn handle_confirmation_input(&mut self, key: KeyCode) {
+        match crate::utils::confirmation::answer(key) {
+            Some(true) => {
+                if let Some(confirmation) = self.confirmation.take() {
+                    self.perform_confirmed(confirmation.action).await;
+                }
+            }
+            Some(false) => {
+                self.confirmation = None;
+                self.add_debug("Confirmation declined");
+            }
+            None => {}
+        }
+    }
+
+    // ========== Undo ==========
+
+    /// Re-add the most recently removed folder or cloud to the clouds config
+    fn undo_delete(&mut self) {
+        let Some(item) = self.undo_stack.pop_undo() else {
+            self.notify(Severity::Warning, "Nothing to undo");
+            return;
+        };
+
+        let result = match &item {
+            crate::utils::undo::RemovedItem::Folder(folder) => {
+                self.orchestrator.add_cloud_folder(folder.clone())
+            }
+            crate::utils::undo::RemovedItem::Cloud(cloud) => {
+                self.orchestrator.add_cloud(cloud.as_ref().clone())
+            }
+        };
+
+        match result {
+            Ok(()) => {
+                self.load_folders_from_orchestrator();
+                let message = format!(
+                    "Restored {} '{}' ({} more to undo)",
+                    item.kind(),
+                    item.name(),
+                    self.undo_stack.undo_len()
+                );
+                self.notify(Severity::Success, message);
+                self.undo_stack.push_redo(item);
+            }
+            Err(e) => {
+                // The name may have been reused since; the entry is dropped rather than blocking older ones
+                self.notify(
+                    Severity::Error,
+                    format!("Cannot restore {} '{}': {}", item.kind(), item.name(), e),
+                );
+            }
+        }
+    }
+
+    /// Remove again the last folder or cloud brought back by Undo
+    fn redo_delete(&mut self) {
+        let Some(item) = self.undo_stack.pop_redo() else {
+            self.notify(Severity::Warning, "Nothing to redo");
+            return;
+        };
+
+        // Remove the current version, it may have been edited since it was restored
+        let result = match &item {
+            crate::utils::undo::RemovedItem::Folder(folder) => {
+                let current = self
+                    .orchestrator
+                    .get_cloud_folders()
+                    .into_iter()
+                    .find(|f| f.name == folder.name);
+                self.orchestrator
+                    .remove_cloud_folder(&folder.name)
+                    .map(|_| current.map(crate::utils::undo::RemovedItem::Folder))
+            }
+            crate::utils::undo::RemovedItem::Cloud(cloud) => {
+                let current = self.orchestrator.get_cloud(&cloud.name);
+                self.orchestrator.remove_cloud(&cloud.name).map(|_| {
+                    current.map(|cloud| crate::utils::undo::RemovedItem::Cloud(Box::new(cloud)))
+                })
+            }
+        };
+
+        match result {
+            Ok(current) => {
+                self.load_folders_from_orchestrator();
+                self.notify(
+                    Severity::Success,
+                    format!("Removed {} '{}' again", item.kind(), item.name()),
+                );
+                self.undo_stack.push_undo(current.unwrap_or(item));
+            }
+            Err(e) => {
+                self.notify(Severity::Error, format!("Cannot redo: {}", e));
+            }
         }
     }
 
@@ -229,6 +751,14 @@ impl App {
                         self.add_debug("No clouds to edit");
                     }
                 }
+                folders::models::FocusedPanel::Files => {
+                    match self.folders_state.files.selected_entry() {
+                        Some(entry) if !entry.is_dir => {
+                            self.pending_editor = Some(entry.path.clone());
+                        }
+                        _ => self.add_debug("Select a file to edit"),
+                    }
+                }
                 folders::models::FocusedPanel::Info => {
                     self.add_debug("Cannot edit from info panel");
                 }
@@ -260,12 +790,7 @@ impl App {
                         .cloud_folders
                         .iter()
                         .find(|f| &f.name == name)
-                        .map(|f| {
-                            cloudhost_server::CloudFolder::new(
-                                f.name.clone(),
-                                f.folder_path.clone(),
-                            )
-                        })
+                        .cloned()
                 })
                 .collect();
 
@@ -285,11 +810,14 @@ impl App {
             // Reload from orchestrator to update the cloud list
             self.load_folders_from_orchestrator();
 
-            self.add_debug(&format!(
-                "Created cloud '{}' with {} folders",
-                cloud_name,
-                self.folders_state.get_selected_folders_count()
-            ));
+            let folder_count = self.folders_state.get_selected_folders_count();
+            self.notify(
+                Severity::Success,
+                format!(
+                    "Created cloud '{}' with {} folders",
+                    cloud_name, folder_count
+                ),
+            );
 
             // Clear folder selections after creating cloud
             self.folders_state.clear_folder_selections();
@@ -333,10 +861,10 @@ impl App {
                 return;
             }
 
-            self.add_debug(&format!(
-                "Password set successfully for cloud '{}'",
-                cloud_name
-            ));
+            self.notify(
+                Severity::Success,
+                format!("Password set for cloud '{}'", cloud_name),
+            );
 
             // Clear password creation state
             self.folders_state
@@ -350,7 +878,7 @@ impl App {
         }
     }
 
-    fn handle_folder_creation_input(&mut self, key: KeyCode) -> bool {
+    fn handle_folder_creation_input(&mut self, key: KeyCode, modifiers: KeyModifiers) -> bool {
         match key {
             KeyCode::Esc => {
                 self.folders_state.creating_folder = false;
@@ -373,7 +901,14 @@ impl App {
                 }
                 true
             }
-            KeyCode::Tab => {
+            KeyCode::Tab | KeyCode::BackTab => {
+                let forward = key == KeyCode::Tab;
+                // On the path field Tab completes; it only switches fields when nothing matches
+                if self.folders_state.folder_input_field == folders::models::FolderInputField::Path
+                    && self.folders_state.complete_path_input(forward)
+                {
+                    return true;
+                }
                 self.folders_state.folder_input_field = match self.folders_state.folder_input_field
                 {
                     folders::models::FolderInputField::Name => {
@@ -385,45 +920,34 @@ impl App {
                 };
                 true
             }
-            KeyCode::Backspace => {
-                match self.folders_state.folder_input_field {
-                    folders::models::FolderInputField::Name => {
-                        self.folders_state.new_folder_name.pop();
-                    }
-                    folders::models::FolderInputField::Path => {
-                        self.folders_state.new_folder_path.pop();
-                    }
-                }
+            KeyCode::Down
+                if self.folders_state.folder_input_field
+                    == folders::models::FolderInputField::Path =>
+            {
+                self.folders_state.open_dir_picker();
                 true
             }
-            KeyCode::Char(c) => {
-                match self.folders_state.folder_input_field {
-                    folders::models::FolderInputField::Name => {
-                        self.folders_state.new_folder_name.push(c);
-                    }
-                    folders::models::FolderInputField::Path => {
-                        self.folders_state.new_folder_path.push(c);
-                    }
+            _ => match self.folders_state.folder_input_field {
+                folders::models::FolderInputField::Name => self
+                    .folders_state
+                    .new_folder_name
+                    .handle_key(key, modifiers),
+                folders::models::FolderInputField::Path => {
+                    self.folders_state.path_completion.reset();
+                    self.folders_state
+                        .new_folder_path
+                        .handle_key(key, modifiers)
                 }
-                true
-            }
-            _ => false,
+            },
         }
     }
 
-    fn handle_cloud_creation_input(&mut self, key: KeyCode) -> bool {
+    fn handle_cloud_creation_input(&mut self, key: KeyCode, modifiers: KeyModifiers) -> bool {
         // If we're in password creation mode, handle password input
         if self.folders_state.password_creation.creating_password {
-            let char_key = match key {
-                KeyCode::Char(c) => c,
-                KeyCode::Enter => '\n',
-                KeyCode::Esc => '\x1b',
-                KeyCode::Backspace => '\x08',
-                _ => return false,
-            };
             self.folders_state
                 .password_creation
-                .handle_password_input(char_key);
+                .handle_password_input(key, modifiers);
 
             // Check if password creation is complete
             if self
@@ -448,30 +972,14 @@ impl App {
                 }
                 true
             }
-            KeyCode::Backspace => {
-                self.folders_state.new_cloud_name.pop();
-                true
-            }
-            KeyCode::Char(c) => {
-                self.folders_state.new_cloud_name.push(c);
-                true
-            }
-            _ => false,
+            _ => self.folders_state.new_cloud_name.handle_key(key, modifiers),
         }
     }
 
-    fn handle_password_creation_input(&mut self, key: KeyCode) -> bool {
-        let char_key = match key {
-            KeyCode::Char(c) => c,
-            KeyCode::Enter => '\n',
-            KeyCode::Esc => '\x1b',
-            KeyCode::Backspace => '\x08',
-            _ => return false,
-        };
-
+    fn handle_password_creation_input(&mut self, key: KeyCode, modifiers: KeyModifiers) -> bool {
         self.folders_state
             .password_creation
-            .handle_password_input(char_key);
+            .handle_password_input(key, modifiers);
 
         // Check if password creation is complete
         if self
@@ -485,35 +993,123 @@ impl App {
         true
     }
 
+    async fn handle_client_input(&mut self, key: KeyCode, modifiers: KeyModifiers) -> bool {
+        use client::models::{ClientInputMode, ConnectField};
+
+        match key {
+            KeyCode::Esc => {
+                self.client_state.cancel_input();
+                self.client_state.error = None;
+            }
+            KeyCode::Enter => match self.client_state.input_mode {
+                ClientInputMode::Connect(ConnectField::Url) => {
+                    if !self.client_state.url_input.trim().is_empty() {
+                        self.client_state.input_mode =
+                            ClientInputMode::Connect(ConnectField::Password);
+                    }
+                }
+                ClientInputMode::Connect(ConnectField::Password) => {
+                    self.client_state.connect().await;
+                    if self.client_state.is_connected() {
+                        self.add_debug(&format!(
+                            "Connected to remote CloudHost at {}",
+                            self.client_state.url_input
+                        ));
+                    }
+                }
+                ClientInputMode::DownloadPath => {
+                    if let Some(events) = self.events.clone() {
+                        self.client_state.download_selected(&mut self.jobs, &events);
+                    }
+                }
+                ClientInputMode::UploadPath => {
+                    if let Some(events) = self.events.clone() {
+                        self.client_state.upload_local_file(&mut self.jobs, &events);
+                    }
+                }
+                ClientInputMode::None => return false,
+            },
+            KeyCode::Tab | KeyCode::BackTab => {
+                if let ClientInputMode::Connect(field) = self.client_state.input_mode {
+                    let next = match field {
+                        ConnectField::Url => ConnectField::Password,
+                        ConnectField::Password => ConnectField::Url,
+                    };
+                    self.client_state.input_mode = ClientInputMode::Connect(next);
+                }
+            }
+            _ => {
+                return self
+                    .client_state
+                    .active_input_mut()
+                    .is_some_and(|input| input.handle_key(key, modifiers))
+            }
+        }
+        true
+    }
+
+    fn handle_dir_picker_input(&mut self, key: KeyCode) {
+        let Some(picker) = self.folders_state.dir_picker.as_mut() else {
+            return;
+        };
+        match key {
+            KeyCode::Esc => self.folders_state.dir_picker = None,
+            KeyCode::Up | KeyCode::Char('k') => picker.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => picker.select_next(),
+            KeyCode::Right | KeyCode::Char('l') => picker.enter_selected(),
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::Backspace => picker.go_up(),
+            KeyCode::Char('H') => picker.toggle_hidden(),
+            KeyCode::Enter => {
+                let path = picker.selected_path();
+                self.folders_state.pick_directory(path);
+            }
+            KeyCode::Char('.') => {
+                let path = picker.current_dir.clone();
+                self.folders_state.pick_directory(path);
+            }
+            _ => {}
+        }
+    }
+
     fn complete_folder_creation(&mut self) {
         let folder_name = self.folders_state.new_folder_name.trim().to_string();
-        let folder_path = std::path::PathBuf::from(self.folders_state.new_folder_path.trim());
+        let folder_path =
+            crate::utils::path_input::expand_home(self.folders_state.new_folder_path.trim());
 
-        if folder_name.is_empty() {
-            self.folders_state.folder_creation_error =
-                Some("Folder name cannot be empty".to_string());
+        if let Err(e) = self.add_cloud_folder(&folder_name, &folder_path) {
+            self.folders_state.folder_creation_error = Some(e);
             return;
         }
 
+        self.folders_state.creating_folder = false;
+        self.folders_state.folder_creation_error = None;
+    }
+
+    /// Validate and add a cloud folder, used by the creation modal and `:addfolder`
+    fn add_cloud_folder(
+        &mut self,
+        folder_name: &str,
+        folder_path: &std::path::Path,
+    ) -> Result<(), String> {
+        if folder_name.is_empty() {
+            return Err("Folder name cannot be empty".to_string());
+        }
+
         // Validate folder path exists
         if !folder_path.exists() {
-            self.folders_state.folder_creation_error =
-                Some(format!("Folder '{}' does not exist", folder_path.display()));
-            return;
+            return Err(format!("Folder '{}' does not exist", folder_path.display()));
         }
 
         if !folder_path.is_dir() {
-            self.folders_state.folder_creation_error =
-                Some(format!("'{}' is not a directory", folder_path.display()));
-            return;
+            return Err(format!("'{}' is not a directory", folder_path.display()));
         }
 
-        let folder = cloudhost_server::CloudFolder::new(folder_name.clone(), folder_path.clone());
+        let folder =
+            cloudhost_server::CloudFolder::new(folder_name.to_string(), folder_path.to_path_buf());
 
-        if let Err(e) = self.orchestrator.add_cloud_folder(folder) {
-            self.folders_state.folder_creation_error = Some(e.to_string());
-            return;
-        }
+        self.orchestrator
+            .add_cloud_folder(folder)
+            .map_err(|e| e.to_string())?;
 
         // Reload from orchestrator
         self.load_folders_from_orchestrator();
@@ -523,15 +1119,35 @@ impl App {
             folder_name,
             folder_path.display()
         ));
-        self.folders_state.creating_folder = false;
-        self.folders_state.folder_creation_error = None;
+        Ok(())
     }
 
-    fn handle_folder_edit_input(&mut self, key: KeyCode) -> bool {
-        match key {
-            KeyCode::Esc => {
-                self.folders_state.clear_folder_edit();
-                true
+    /// Add a cloud of the existing cloud folders `folder_names`, used by `:addcloud`
+    fn add_cloud(&mut self, name: &str, folder_names: &[String]) -> Result<(), String> {
+        let all_folders = self.orchestrator.get_cloud_folders();
+        let folders = folder_names
+            .iter()
+            .map(|folder_name| {
+                all_folders
+                    .iter()
+                    .find(|folder| &folder.name == folder_name)
+                    .cloned()
+                    .ok_or_else(|| format!("No cloud folder named '{}'", folder_name))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.orchestrator
+            .add_cloud(cloudhost_server::Cloud::new(name.to_string(), folders))
+            .map_err(|e| e.to_string())?;
+        self.load_folders_from_orchestrator();
+        self.add_debug(&format!("Created cloud '{}'", name));
+        Ok(())
+    }
+
+    fn handle_folder_edit_input(&mut self, key: KeyCode, modifiers: KeyModifiers) -> bool {
+        match key {
+            KeyCode::Esc => {
+                self.folders_state.clear_folder_edit();
+                true
             }
             KeyCode::Enter => {
                 match self.folders_state.edit_folder_input_field {
@@ -549,7 +1165,14 @@ impl App {
                 }
                 true
             }
-            KeyCode::Tab => {
+            KeyCode::Tab | KeyCode::BackTab => {
+                let forward = key == KeyCode::Tab;
+                if self.folders_state.edit_folder_input_field
+                    == folders::models::FolderInputField::Path
+                    && self.folders_state.complete_path_input(forward)
+                {
+                    return true;
+                }
                 self.folders_state.edit_folder_input_field =
                     match self.folders_state.edit_folder_input_field {
                         folders::models::FolderInputField::Name => {
@@ -561,33 +1184,29 @@ impl App {
                     };
                 true
             }
-            KeyCode::Backspace => {
-                match self.folders_state.edit_folder_input_field {
-                    folders::models::FolderInputField::Name => {
-                        self.folders_state.edit_folder_name.pop();
-                    }
-                    folders::models::FolderInputField::Path => {
-                        self.folders_state.edit_folder_path.pop();
-                    }
-                }
+            KeyCode::Down
+                if self.folders_state.edit_folder_input_field
+                    == folders::models::FolderInputField::Path =>
+            {
+                self.folders_state.open_dir_picker();
                 true
             }
-            KeyCode::Char(c) => {
-                match self.folders_state.edit_folder_input_field {
-                    folders::models::FolderInputField::Name => {
-                        self.folders_state.edit_folder_name.push(c);
-                    }
-                    folders::models::FolderInputField::Path => {
-                        self.folders_state.edit_folder_path.push(c);
-                    }
+            _ => match self.folders_state.edit_folder_input_field {
+                folders::models::FolderInputField::Name => self
+                    .folders_state
+                    .edit_folder_name
+                    .handle_key(key, modifiers),
+                folders::models::FolderInputField::Path => {
+                    self.folders_state.path_completion.reset();
+                    self.folders_state
+                        .edit_folder_path
+                        .handle_key(key, modifiers)
                 }
-                true
-            }
-            _ => false,
+            },
         }
     }
 
-    fn handle_cloud_edit_input(&mut self, key: KeyCode) -> bool {
+    fn handle_cloud_edit_input(&mut self, key: KeyCode, modifiers: KeyModifiers) -> bool {
         match key {
             KeyCode::Esc => {
                 self.folders_state.clear_cloud_edit();
@@ -611,15 +1230,7 @@ impl App {
                 };
                 true
             }
-            KeyCode::Backspace => {
-                if self.folders_state.cloud_edit_focus
-                    == crate::tabs::folders::models::CloudEditFocus::Name
-                {
-                    self.folders_state.edit_cloud_name.pop();
-                }
-                true
-            }
-            KeyCode::Char(c) => {
+            KeyCode::Char(c) if modifiers.is_empty() || modifiers == KeyModifiers::SHIFT => {
                 match self.folders_state.cloud_edit_focus {
                     crate::tabs::folders::models::CloudEditFocus::Name => {
                         if c.to_string() == self.config.leader {
@@ -630,7 +1241,7 @@ impl App {
                                     .toggle_cloud_folder_selection(current_index);
                             }
                         } else {
-                            self.folders_state.edit_cloud_name.push(c);
+                            self.folders_state.edit_cloud_name.insert_char(c);
                         }
                         true
                     }
@@ -653,16 +1264,20 @@ impl App {
                     }
                 }
             }
-            KeyCode::Up | KeyCode::Down => {
+            KeyCode::Up | KeyCode::Down
                 if self.folders_state.cloud_edit_focus
-                    == crate::tabs::folders::models::CloudEditFocus::Folders
-                {
-                    // Allow navigation in folder list within cloud edit modal
-                    self.folders_state.handle_folders_navigation(key);
-                    true
-                } else {
-                    false
-                }
+                    == crate::tabs::folders::models::CloudEditFocus::Folders =>
+            {
+                // Allow navigation in folder list within cloud edit modal
+                self.folders_state.handle_folders_navigation(key);
+                true
+            }
+            _ if self.folders_state.cloud_edit_focus
+                == crate::tabs::folders::models::CloudEditFocus::Name =>
+            {
+                self.folders_state
+                    .edit_cloud_name
+                    .handle_key(key, modifiers)
             }
             _ => false,
         }
@@ -670,7 +1285,8 @@ impl App {
 
     fn complete_folder_edit(&mut self) {
         let new_name = self.folders_state.edit_folder_name.trim().to_string();
-        let new_path = std::path::PathBuf::from(self.folders_state.edit_folder_path.trim());
+        let new_path =
+            crate::utils::path_input::expand_home(self.folders_state.edit_folder_path.trim());
         let old_name = self.folders_state.edit_folder_original_name.clone();
 
         if new_name.is_empty() {
@@ -691,7 +1307,18 @@ impl App {
             return;
         }
 
-        let new_folder = cloudhost_server::CloudFolder::new(new_name.clone(), new_path.clone());
+        // Keep the folder's other settings, like camera roll mode
+        let mut new_folder = self
+            .folders_state
+            .cloud_folders
+            .iter()
+            .find(|f| f.name == old_name)
+            .cloned()
+            .unwrap_or_else(|| {
+                cloudhost_server::CloudFolder::new(new_name.clone(), new_path.clone())
+            });
+        new_folder.name = new_name.clone();
+        new_folder.folder_path = new_path.clone();
 
         if let Err(e) = self.orchestrator.update_cloud_folder(&old_name, new_folder) {
             self.folders_state.folder_edit_error = Some(e.to_string());
@@ -730,9 +1357,7 @@ impl App {
                     .cloud_folders
                     .iter()
                     .find(|f| &f.name == name)
-                    .map(|f| {
-                        cloudhost_server::CloudFolder::new(f.name.clone(), f.folder_path.clone())
-                    })
+                    .cloned()
             })
             .collect();
 
@@ -745,13 +1370,11 @@ impl App {
         let old_cloud = self.orchestrator.get_cloud(&old_name);
 
         let new_cloud = if let Some(old_cloud_data) = old_cloud {
-            // Preserve password and JWT secret
+            // Preserve password, JWT secret and the other settings
             cloudhost_server::Cloud {
                 name: new_name.clone(),
                 cloud_folders: folders,
-                password: old_cloud_data.password,
-                password_changed_at: old_cloud_data.password_changed_at,
-                jwt_secret: old_cloud_data.jwt_secret,
+                ..old_cloud_data
             }
         } else {
             cloudhost_server::Cloud::new(new_name.clone(), folders)
@@ -816,6 +1439,13 @@ impl App {
         }
     }
 
+    /// Show a notification to the user (and log it to the debug panel)
+    pub fn notify(&mut self, severity: Severity, message: impl Into<String>) {
+        let message = message.into();
+        self.add_debug(&message);
+        self.notifications.push(severity, message);
+    }
+
     pub fn add_debug(&mut self, message: &str) {
         if self.debug_mode {
             self.debug_info.push(format!(
@@ -830,40 +1460,548 @@ impl App {
         }
     }
 
-    pub async fn update_cloud_logs(&mut self) {
-        // Clear existing logs
-        self.cloud_logs.clear();
-        self.clouds_state.cloud_logs.clear();
+    // ========== Background events ==========
+
+    pub async fn handle_app_event(&mut self, event: crate::events::AppEvent) {
+        use crate::events::AppEvent;
+
+        match event {
+            AppEvent::CloudLog { cloud, message } => {
+                // Server errors (e.g. the port is taken) must not go unnoticed
+                if message.level == cloudhost_shared::debug_stream::LogLevel::Error {
+                    self.notify(
+                        Severity::Error,
+                        format!("Cloud '{}': {}", cloud, message.message),
+                    );
+                }
+                if self.logs_cloud.as_deref() == Some(cloud.as_str()) {
+                    self.push_cloud_log(message);
+                }
+            }
+            AppEvent::CloudStats { cloud, stats } => {
+                self.clouds_state
+                    .stats
+                    .entry(cloud)
+                    .or_default()
+                    .push(stats);
+            }
+            AppEvent::ServerStateChanged { cloud, running } => {
+                if !running {
+                    self.log_subscriptions.remove(&cloud);
+                    self.clouds_state.stats.remove(&cloud);
+                } else if self.logs_cloud.as_deref() == Some(cloud.as_str()) {
+                    // Pick up whatever was logged before the subscription started
+                    self.update_cloud_logs().await;
+                }
+                self.add_debug(&format!(
+                    "Cloud '{}' {}",
+                    cloud,
+                    if running { "is running" } else { "has stopped" }
+                ));
+            }
+            AppEvent::ConfigFileChanged(file) => {
+                let at = std::time::Instant::now() + crate::utils::config_watcher::RELOAD_DELAY;
+                self.pending_config_reloads.insert(file, at);
+            }
+            AppEvent::FolderPreview(preview) => {
+                self.folders_state.path_preview = Some(preview);
+            }
+            AppEvent::FolderScanned(preview) => {
+                self.folders_state
+                    .folder_sizes
+                    .insert(preview.path.clone(), preview);
+                if matches!(
+                    self.folders_state.folder_sort,
+                    Some(FolderSortColumn::Size | FolderSortColumn::Items)
+                ) {
+                    self.folders_state.sort_folders();
+                }
+            }
+            AppEvent::OnlineChecks(checks) => {
+                // Dropped when the panel was closed in the meantime
+                if let Some(diagnostics) = self.diagnostics.as_mut() {
+                    diagnostics.checks.extend(checks);
+                    diagnostics.checking_online = false;
+                }
+            }
+            AppEvent::Reachability { cloud, check } => {
+                self.clouds_state.reachability.insert(cloud, Some(check));
+            }
+            AppEvent::JobProgress { id, done, total } => {
+                self.jobs.progress(id, done, total);
+            }
+            AppEvent::JobFinished { id, result } => {
+                let Some(job) = self.jobs.finish(id, &result) else {
+                    return;
+                };
+                match &result {
+                    Ok(message) => self.notify(Severity::Success, message.clone()),
+                    Err(e) => self.notify(Severity::Error, format!("{} failed: {}", job.label, e)),
+                }
+                match job.kind {
+                    JobKind::Download | JobKind::Upload => {
+                        self.client_state.finish_job(job.kind, result).await;
+                    }
+                    JobKind::Email => {}
+                }
+            }
+        }
+    }
+
+    /// Periodic work that does not depend on input: key sequence timeouts and log streams
+    pub async fn on_tick(&mut self) {
+        self.check_timeouts().await;
+        self.sync_log_subscriptions();
+        self.request_folder_preview();
+        self.request_folder_sizes();
+        if self.orchestrator.has_config_conflict() {
+            self.show_config_conflict = true;
+        }
+        self.apply_pending_config_reloads().await;
+        match self.orchestrator.persist_password_upgrades() {
+            Ok(clouds) if !clouds.is_empty() => {
+                log::info!("Upgraded password hashes of: {}", clouds.join(", "))
+            }
+            Ok(_) => {}
+            Err(e) => self.notify(
+                Severity::Error,
+                format!("Failed to save upgraded password hashes: {}", e),
+            ),
+        }
+        match self.orchestrator.persist_recovered_passwords() {
+            Ok(clouds) => {
+                for cloud in clouds {
+                    self.notify(
+                        Severity::Warning,
+                        format!(
+                            "The password of cloud '{}' was reset with a recovery code",
+                            cloud
+                        ),
+                    );
+                }
+            }
+            Err(e) => self.notify(
+                Severity::Error,
+                format!("Failed to save a recovered password: {}", e),
+            ),
+        }
+        match self.orchestrator.persist_api_token_changes() {
+            Ok(clouds) if !clouds.is_empty() => {
+                self.load_folders_from_orchestrator();
+                log::info!("Saved API token changes of: {}", clouds.join(", "))
+            }
+            Ok(_) => {}
+            Err(e) => self.notify(
+                Severity::Error,
+                format!("Failed to save API token changes: {}", e),
+            ),
+        }
+        match self.orchestrator.persist_revoked_sessions() {
+            Ok(clouds) if !clouds.is_empty() => {
+                log::debug!("Saved logouts of: {}", clouds.join(", "))
+            }
+            Ok(_) => {}
+            Err(e) => self.notify(
+                Severity::Error,
+                format!("Failed to save logged out sessions: {}", e),
+            ),
+        }
+        match self.orchestrator.persist_file_request_changes() {
+            Ok(clouds) if !clouds.is_empty() => {
+                log::debug!("Saved file request changes of: {}", clouds.join(", "))
+            }
+            Ok(_) => {}
+            Err(e) => self.notify(
+                Severity::Error,
+                format!("Failed to save file request changes: {}", e),
+            ),
+        }
+        self.orchestrator.refresh_landing();
+        for task in self.orchestrator.run_due_tasks() {
+            log::info!("Started scheduled task {}", task);
+        }
+        self.check_disk_space();
+        self.check_folder_health();
+        self.check_stopped_clouds();
+
+        let selected_cloud = self
+            .clouds_state
+            .clouds
+            .get(self.clouds_state.selected_cloud_index)
+            .map(|cloud| cloud.name.clone());
+        if selected_cloud != self.logs_cloud {
+            self.update_cloud_logs().await;
+        }
+    }
+
+    /// Notice clouds whose server ended without being stopped
+    fn check_stopped_clouds(&mut self) {
+        let stopped: Vec<String> = self
+            .clouds_state
+            .running_clouds
+            .keys()
+            .filter(|name| !self.orchestrator.is_cloud_running(name))
+            .cloned()
+            .collect();
+        for name in stopped {
+            self.clouds_state.running_clouds.remove(&name);
+            let reason = match self.orchestrator.cloud_status(&name) {
+                cloudhost_server::cloud::CloudStatus::Failed(reason) => reason,
+                _ => "it stopped".to_string(),
+            };
+            self.notify(
+                Severity::Error,
+                format!("Cloud '{}' is no longer running: {}", name, reason),
+            );
+        }
+    }
+
+    /// Tell when a cloud folder goes away, e.g. with its drive, and when it is back
+    fn check_folder_health(&mut self) {
+        if self.folder_health_checked.is_some_and(|checked| {
+            checked.elapsed() < cloudhost_server::folder_health::CHECK_INTERVAL
+        }) {
+            return;
+        }
+        self.folder_health_checked = Some(std::time::Instant::now());
+
+        for change in self.orchestrator.check_folder_health() {
+            match change.problem {
+                Some(problem) => self.notify(
+                    Severity::Warning,
+                    format!(
+                        "Cloud folder '{}' is unavailable: {}",
+                        change.folder, problem
+                    ),
+                ),
+                None => self.notify(
+                    Severity::Success,
+                    format!("Cloud folder '{}' is available again", change.folder),
+                ),
+            }
+        }
+    }
+
+    /// Warn once when a cloud folder's disk gets low, and again when uploads stop
+    fn check_disk_space(&mut self) {
+        use cloudhost_server::disk_space::SpaceLevel;
 
-        // Get the selected cloud name and fetch its logs
-        if !self.clouds_state.clouds.is_empty()
-            && self.clouds_state.selected_cloud_index < self.clouds_state.clouds.len()
+        const INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+        if self
+            .disk_space_checked
+            .is_some_and(|checked| checked.elapsed() < INTERVAL)
         {
-            let cloud_name = &self.clouds_state.clouds[self.clouds_state.selected_cloud_index].name;
-
-            // Get debug logs for the specific cloud
-            let messages = self.orchestrator.get_cloud_debug_logs(cloud_name).await;
-
-            for message in messages {
-                self.cloud_logs.push(message.clone());
-                // Also add formatted string to clouds_state for display
-                let log_entry = format!(
-                    "[{}] [{}] {}: {}",
-                    message.timestamp.format("%H:%M:%S"),
-                    message.level,
-                    message.source,
-                    message.message
-                );
-                self.clouds_state.cloud_logs.push(log_entry);
+            return;
+        }
+        self.disk_space_checked = Some(std::time::Instant::now());
+
+        let low = self.orchestrator.low_disk_space();
+        let mut warned = std::collections::HashMap::new();
+        for (cloud, space) in low {
+            let key = (cloud, space.folder.clone());
+            if self.disk_space_warned.get(&key) < Some(&space.level) {
+                let free = crate::utils::jobs::format_bytes(space.free_bytes);
+                match space.level {
+                    SpaceLevel::Critical => self.notify(
+                        Severity::Error,
+                        format!(
+                            "The disk of '{}' ({}) is full: {} free, uploads are refused",
+                            key.1, key.0, free
+                        ),
+                    ),
+                    _ => self.notify(
+                        Severity::Warning,
+                        format!("The disk of '{}' ({}) is low: {} free", key.1, key.0, free),
+                    ),
+                }
+            }
+            warned.insert(key, space.level);
+        }
+        self.disk_space_warned = warned;
+    }
+
+    pub fn start_config_watcher(&mut self, events: crate::events::EventSender) {
+        match crate::utils::config_watcher::watch_config_files(events) {
+            Ok(watcher) => self.config_watcher = Some(watcher),
+            Err(e) => self.notify(
+                Severity::Warning,
+                format!("Config files won't reload automatically: {}", e),
+            ),
+        }
+    }
+
+    /// Reload config files whose changes have settled, reporting what changed
+    /// Start, move or stop the landing page to match `landing_port`
+    pub async fn start_landing(&mut self) {
+        match self.orchestrator.start_landing().await {
+            Ok(Some(port)) => log::info!("Landing page on port {}", port),
+            Ok(None) => {}
+            Err(e) => self.notify(
+                Severity::Error,
+                format!("Failed to start the landing page: {}", e),
+            ),
+        }
+    }
+
+    async fn apply_pending_config_reloads(&mut self) {
+        use crate::utils::config_watcher::ConfigFile;
+
+        let now = std::time::Instant::now();
+        let due: Vec<ConfigFile> = self
+            .pending_config_reloads
+            .iter()
+            .filter(|(_, at)| **at <= now)
+            .map(|(file, _)| *file)
+            .collect();
+
+        for file in due {
+            self.pending_config_reloads.remove(&file);
+            match file {
+                ConfigFile::Tui => match crate::config::Config::load() {
+                    Ok(config) => {
+                        let changes = self.config.diff(&config);
+                        if !changes.is_empty() {
+                            self.config = config;
+                            self.notify(
+                                Severity::Info,
+                                format!("TUI config reloaded: {}", summarize_changes(&changes)),
+                            );
+                            self.warn_about_tui_config();
+                        }
+                    }
+                    Err(e) => self.notify(
+                        Severity::Error,
+                        format!("Failed to reload TUI config: {}", e),
+                    ),
+                },
+                ConfigFile::Clouds => match self.orchestrator.reload_config_if_changed().await {
+                    Ok(changes) => {
+                        self.load_folders_from_orchestrator();
+                        self.start_landing().await;
+                        if !changes.is_empty() {
+                            self.notify(
+                                Severity::Info,
+                                format!("Clouds config reloaded: {}", summarize_changes(&changes)),
+                            );
+                        }
+                    }
+                    Err(e) => self.notify(
+                        Severity::Error,
+                        format!("Failed to reload clouds config: {}", e),
+                    ),
+                },
+            }
+        }
+    }
+
+    /// Inspect the path typed in a folder modal in the background, once per distinct input
+    fn request_folder_preview(&mut self) {
+        let Some(events) = self.events.clone() else {
+            return;
+        };
+        let Some(input) = self
+            .folders_state
+            .active_path_input()
+            .map(|input| input.trim().to_string())
+        else {
+            return;
+        };
+        if input.is_empty() || self.folders_state.preview_requested.as_deref() == Some(&input) {
+            return;
+        }
+        self.folders_state.preview_requested = Some(input.clone());
+
+        tokio::spawn(async move {
+            let path = crate::utils::path_input::expand_home(&input);
+            let preview = tokio::task::spawn_blocking(move || {
+                crate::utils::folder_preview::preview_folder(&input, &path)
+            })
+            .await;
+            if let Ok(preview) = preview {
+                let _ = events.send(crate::events::AppEvent::FolderPreview(preview));
+            }
+        });
+    }
+
+    /// Scan the cloud folders not scanned yet in the background while their sizes are shown
+    fn request_folder_sizes(&mut self) {
+        if !self.folders_state.folder_details {
+            return;
+        }
+        let Some(events) = self.events.clone() else {
+            return;
+        };
+        for folder in &self.folders_state.cloud_folders {
+            let path = folder.folder_path.clone();
+            if !self.folders_state.sizes_requested.insert(path.clone()) {
+                continue;
+            }
+            let events = events.clone();
+            tokio::spawn(async move {
+                let preview = tokio::task::spawn_blocking(move || {
+                    crate::utils::folder_preview::preview_folder(&path.to_string_lossy(), &path)
+                })
+                .await;
+                if let Ok(preview) = preview {
+                    let _ = events.send(crate::events::AppEvent::FolderScanned(preview));
+                }
+            });
+        }
+    }
+
+    /// Start forwarding logs of clouds that started since the last tick
+    fn sync_log_subscriptions(&mut self) {
+        let Some(events) = self.events.clone() else {
+            return;
+        };
+        for cloud in self.orchestrator.get_running_clouds().into_keys() {
+            if self.log_subscriptions.contains(&cloud) {
+                continue;
+            }
+            if let Some(receiver) = self.orchestrator.subscribe_cloud_logs(&cloud) {
+                self.log_subscriptions.insert(cloud.clone());
+                let stats = self.orchestrator.cloud_stats(&cloud);
+                crate::events::forward_cloud_logs(cloud, receiver, stats, events.clone());
+            }
+        }
+    }
+
+    fn push_cloud_log(&mut self, message: DebugMessage) {
+        let log_entry = format!(
+            "[{}] [{}] {}: {}",
+            message.timestamp.format("%H:%M:%S"),
+            message.level,
+            message.source,
+            message.message
+        );
+        self.clouds_state.cloud_logs.push(log_entry);
+        self.cloud_logs.push(message);
+
+        if self.cloud_logs.len() > MAX_CLOUD_LOGS {
+            self.cloud_logs.remove(0);
+        }
+        if self.clouds_state.cloud_logs.len() > MAX_CLOUD_LOGS {
+            self.clouds_state.cloud_logs.remove(0);
+        }
+    }
+
+    // ========== Cloud Logs panel ==========
+
+    /// j/k/g/G in the logs panel; scrolling back pauses the live tail, G resumes it
+    fn navigate_logs(&mut self, key: KeyCode) -> bool {
+        let count = self.clouds_state.log_view.visible(&self.cloud_logs).len();
+        let Some(last) = count.checked_sub(1) else {
+            return true;
+        };
+        let state = &mut self.clouds_state.cloud_logs_list_state;
+        let selected = state.selected().unwrap_or(last).min(last);
+        let (index, follow) = match key {
+            KeyCode::Up | KeyCode::Char('k') => (selected.saturating_sub(1), false),
+            KeyCode::Down | KeyCode::Char('j') => (
+                selected.saturating_add(1).min(last),
+                self.clouds_state.log_view.follow,
+            ),
+            KeyCode::Char('g') => (0, false),
+            KeyCode::Char('G') => (last, true),
+            _ => return false,
+        };
+        state.select(Some(index));
+        self.clouds_state.log_view.follow = follow;
+        true
+    }
+
+    fn handle_log_search_input(&mut self, key: KeyCode, modifiers: KeyModifiers) {
+        let log_view = &mut self.clouds_state.log_view;
+        let Some(input) = log_view.search_input.as_mut() else {
+            return;
+        };
+        match key {
+            KeyCode::Esc => log_view.search_input = None,
+            KeyCode::Enter => {
+                let query = log_view
+                    .search_input
+                    .take()
+                    .map(|input| input.to_string())
+                    .unwrap_or_default();
+                if query.is_empty() {
+                    log_view.search = None;
+                    return;
+                }
+                log_view.search = Some(query);
+                // Start from the newest match
+                self.clouds_state.cloud_logs_list_state.select(None);
+                self.jump_to_log_match(false);
+            }
+            _ => {
+                input.handle_key(key, modifiers);
             }
         }
+    }
+
+    /// Select the next (newer) or previous (older) message matching the search, wrapping around
+    fn jump_to_log_match(&mut self, forward: bool) {
+        let log_view = &self.clouds_state.log_view;
+        let Some(query) = log_view.search.clone() else {
+            self.notify(Severity::Info, "No log search, press / to search");
+            return;
+        };
+        let matching: Vec<bool> = log_view
+            .visible(&self.cloud_logs)
+            .into_iter()
+            .map(|message| !log_view.matches(&log_line(message)).is_empty())
+            .collect();
+        let count = matching.len();
+        // Nothing selected counts as just past the newest message
+        let current = self
+            .clouds_state
+            .cloud_logs_list_state
+            .selected()
+            .filter(|&selected| selected < count)
+            .unwrap_or(count);
+        let found = (1..=count)
+            .map(|step| {
+                if forward {
+                    (current + step) % (count + 1)
+                } else {
+                    (current + count + 1 - step) % (count + 1)
+                }
+            })
+            .find(|&index| index < count && matching[index]);
 
-        // Keep only last 100 logs
-        if self.cloud_logs.len() > 100 {
-            self.cloud_logs.truncate(100);
+        match found {
+            Some(index) => {
+                self.clouds_state.cloud_logs_list_state.select(Some(index));
+                self.clouds_state.log_view.follow = false;
+            }
+            None => self.notify(Severity::Info, format!("No log matches for '{}'", query)),
         }
-        if self.clouds_state.cloud_logs.len() > 100 {
-            self.clouds_state.cloud_logs.truncate(100);
+    }
+
+    /// Reload the log history of the selected cloud
+    pub async fn update_cloud_logs(&mut self) {
+        self.cloud_logs.clear();
+        self.clouds_state.cloud_logs.clear();
+
+        self.logs_cloud = self
+            .clouds_state
+            .clouds
+            .get(self.clouds_state.selected_cloud_index)
+            .map(|cloud| cloud.name.clone());
+
+        if let Some(cloud_name) = self.logs_cloud.clone() {
+            // Persisted logs, so they survive restarts and stopped clouds
+            let filter = cloudhost_server::log_store::LogFilter::last(MAX_CLOUD_LOGS);
+            match self.orchestrator.get_cloud_debug_logs(&cloud_name, &filter) {
+                Ok(messages) => {
+                    for message in messages {
+                        self.push_cloud_log(message);
+                    }
+                }
+                Err(e) => self.notify(
+                    Severity::Error,
+                    format!("Failed to read logs of '{}': {}", cloud_name, e),
+                ),
+            }
         }
     }
 
@@ -872,6 +2010,7 @@ impl App {
         match self.selected_tab {
             SelectedTab::Clouds => self.clouds_state.cycle_focus_forward(),
             SelectedTab::Folders => self.folders_state.cycle_focus_forward(),
+            SelectedTab::Client => self.client_state.cycle_focus_forward(),
             SelectedTab::Settings => self.settings_state.cycle_focus_forward(),
         }
     }
@@ -880,6 +2019,7 @@ impl App {
         match self.selected_tab {
             SelectedTab::Clouds => self.clouds_state.cycle_focus_backward(),
             SelectedTab::Folders => self.folders_state.cycle_focus_backward(),
+            SelectedTab::Client => self.client_state.cycle_focus_backward(),
             SelectedTab::Settings => self.settings_state.cycle_focus_backward(),
         }
     }
@@ -888,6 +2028,7 @@ impl App {
         match self.selected_tab {
             SelectedTab::Clouds => self.clouds_state.get_focused_element(),
             SelectedTab::Folders => self.folders_state.get_focused_element(),
+            SelectedTab::Client => self.client_state.get_focused_element(),
             SelectedTab::Settings => self.settings_state.get_focused_element(),
         }
     }
@@ -922,10 +2063,10 @@ impl App {
             self.clouds_state.password_creation.password_success = true;
             // Clear any server start errors since password is now set
             self.clouds_state.cloud_start_error = None;
-            self.add_debug(&format!(
-                "Password set successfully for cloud '{}'",
-                cloud_name
-            ));
+            self.notify(
+                Severity::Success,
+                format!("Password set for cloud '{}'", cloud_name),
+            );
 
             // If server is running, restart it to pick up the new AuthState
             let was_running = self.clouds_state.running_clouds.contains_key(&cloud_name);
@@ -950,29 +2091,124 @@ impl App {
         }
     }
 
+    fn complete_password_change(&mut self) {
+        let Some(cloud_name) = self
+            .clouds_state
+            .clouds
+            .get(self.clouds_state.selected_cloud_index)
+            .map(|cloud| cloud.name.clone())
+        else {
+            self.clouds_state.password_change.clear();
+            return;
+        };
+        let change = &self.clouds_state.password_change;
+        match self.orchestrator.change_cloud_password(
+            &cloud_name,
+            &change.current_password,
+            &change.new_password,
+        ) {
+            Ok(()) => {
+                self.clouds_state.password_change.clear();
+                self.notify(
+                    Severity::Success,
+                    format!(
+                        "Password of cloud '{}' changed, all its sessions were logged out",
+                        cloud_name
+                    ),
+                );
+            }
+            Err(cloudhost_server::ServerError::Authentication(message)) => {
+                self.clouds_state.password_change.reject_current(message);
+            }
+            Err(e) => {
+                self.clouds_state.password_change.error = Some(e.to_string());
+            }
+        }
+    }
+
     // Tab-specific navigation methods
     pub fn handle_tab_navigation(&mut self, key: ratatui::crossterm::event::KeyCode) -> bool {
         match self.selected_tab {
+            SelectedTab::Clouds
+                if self.clouds_state.focused_panel == CloudFocusedPanel::CloudLogs
+                    && key != KeyCode::Tab =>
+            {
+                self.navigate_logs(key)
+            }
             SelectedTab::Clouds => self.clouds_state.handle_navigation(key),
-            SelectedTab::Folders => self.folders_state.handle_navigation(key),
+            SelectedTab::Folders => {
+                let handled = self.folders_state.handle_navigation(key);
+                self.folders_state.sync_files_panel();
+                handled
+            }
+            SelectedTab::Client => self.client_state.handle_navigation(key),
             SelectedTab::Settings => self.settings_state.handle_navigation(key),
         }
     }
 
-    pub fn reload_tui_config(&mut self) {
-        self.config = crate::config::Config::load().unwrap();
+    /// Returns false (after notifying the user) when the config could not be loaded
+    pub fn reload_tui_config(&mut self) -> bool {
+        match crate::config::Config::load() {
+            Ok(config) => {
+                self.config = config;
+                self.warn_about_tui_config();
+                true
+            }
+            Err(e) => {
+                self.notify(
+                    Severity::Error,
+                    format!("Failed to reload TUI config: {}", e),
+                );
+                false
+            }
+        }
     }
-    pub async fn reload_clouds_config(&mut self) {
+    pub async fn reload_clouds_config(&mut self) -> bool {
         if let Err(e) = self.orchestrator.reload_config().await {
-            self.add_debug(&format!("Failed to reload clouds config: {}", e));
+            self.notify(
+                Severity::Error,
+                format!("Failed to reload clouds config: {}", e),
+            );
+            false
         } else {
             // Reload data from orchestrator to reflect changes
             self.load_folders_from_orchestrator();
+            true
         }
     }
-    pub async fn reload_all_configs(&mut self) {
-        self.reload_tui_config();
-        self.reload_clouds_config().await;
+    pub async fn reload_all_configs(&mut self) -> bool {
+        let tui_ok = self.reload_tui_config();
+        let clouds_ok = self.reload_clouds_config().await;
+        tui_ok && clouds_ok
+    }
+
+    /// Text pasted into the terminal goes into the input being typed in, if any, all at once
+    /// rather than as keys, so its characters never trigger bindings
+    pub fn handle_paste(&mut self, text: &str) {
+        if self.fuzzy_finder.active {
+            self.fuzzy_finder.paste(text);
+        } else if let Some(prompt) = self.passphrase_prompt.as_mut() {
+            prompt.input.insert_str(text);
+        } else if let Some(picker) = self.profile_picker.as_mut() {
+            picker.input.insert_str(text);
+        } else if let Some(editor) = self.settings_state.default_editor.as_mut() {
+            editor.input.insert_str(text);
+        } else if let Some(input) = self.clouds_state.log_view.search_input.as_mut() {
+            input.insert_str(text);
+        } else if self.clouds_state.password_creation.creating_password {
+            self.clouds_state.password_creation.paste(text);
+        } else if self.clouds_state.password_change.active {
+            self.clouds_state.password_change.paste(text);
+        } else if self.confirmation.is_some() || self.folders_state.dir_picker.is_some() {
+            // Nothing to type into, whatever is open underneath
+        } else if self.folders_state.password_creation.creating_password {
+            self.folders_state.password_creation.paste(text);
+        } else if let Some(input) = self.folders_state.focused_input() {
+            input.insert_str(text);
+            self.folders_state.path_completion.reset();
+        } else if let Some(input) = self.client_state.active_input_mut() {
+            input.insert_str(text);
+        }
     }
 
     pub async fn handle_dynamic_key(
@@ -981,41 +2217,12 @@ impl App {
         modifiers: ratatui::crossterm::event::KeyModifiers,
     ) {
         // Convert key to string for config lookup
-        let key_str = match key {
-            KeyCode::Char(c) => {
-                // Check for Ctrl combinations
-                if c.is_ascii_control() {
-                    let ctrl_char = (c as u8 + 96) as char; // Convert control char to letter
-                    format!("<Ctrl>{}", ctrl_char)
-                } else {
-                    c.to_string()
-                }
-            }
-            KeyCode::Up => "<Up>".to_string(),
-            KeyCode::Down => "<Down>".to_string(),
-            KeyCode::Left => "<Left>".to_string(),
-            KeyCode::Right => "<Right>".to_string(),
-            KeyCode::Enter => "<Enter>".to_string(),
-            KeyCode::Esc => "<Esc>".to_string(),
-            KeyCode::Backspace => "<Backspace>".to_string(),
-            KeyCode::Tab => {
-                // Check for Shift+Tab
-                if modifiers.contains(ratatui::crossterm::event::KeyModifiers::SHIFT) {
-                    "<S-Tab>".to_string()
-                } else {
-                    "<Tab>".to_string()
-                }
-            }
-            KeyCode::BackTab => "<S-Tab>".to_string(),
-            _ => return,
+        let Some(key_str) = crate::config::key_to_string(key, modifiers) else {
+            return;
         };
 
         // Get current tab name
-        let current_tab = match self.selected_tab {
-            SelectedTab::Clouds => "clouds",
-            SelectedTab::Folders => "folders",
-            SelectedTab::Settings => "settings",
-        };
+        let current_tab = self.selected_tab.config_name();
 
         self.add_debug(&format!("Key: {} -> tab: {}", key_str, current_tab));
         self.add_debug(&format!("Input state: {:?}", self.input_state));
@@ -1023,138 +2230,1319 @@ impl App {
         // Handle special cases first (cloud management)
         // Clouds are managed in the folders tab, not here
 
-        // Handle password creation modal (now on clouds tab)
-        if self.clouds_state.password_creation.creating_password {
-            let char_key = match key {
-                KeyCode::Char(c) => c,
-                KeyCode::Enter => '\n',
-                KeyCode::Esc => '\x1b',
-                KeyCode::Backspace => '\x08',
-                _ => return,
-            };
-            self.clouds_state.handle_password_input(char_key);
-            // If password creation is complete, handle it
-            if self
-                .clouds_state
-                .password_creation
-                .is_password_creation_complete()
-            {
-                self.complete_password_creation().await;
-            }
-            return;
+        // Any key dismisses the result of the previous command
+        self.command_line.message = None;
+
+        // Handle the help overlay
+        if self.show_help {
+            match key {
+                KeyCode::Esc | KeyCode::Char('?') | KeyCode::Char('q') => self.show_help = false,
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.help_scroll = self.help_scroll.saturating_add(1)
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.help_scroll = self.help_scroll.saturating_sub(1)
+                }
+                KeyCode::Char('g') => self.help_scroll = 0,
+                _ => {}
+            }
+            return;
+        }
+
+        // Handle the webhook deliveries
+        if self.webhook_history.is_some() {
+            match key {
+                KeyCode::Esc | KeyCode::Char('q') => self.webhook_history = None,
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.webhook_history_scroll = self.webhook_history_scroll.saturating_add(1)
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.webhook_history_scroll = self.webhook_history_scroll.saturating_sub(1)
+                }
+                KeyCode::Char('g') => self.webhook_history_scroll = 0,
+                _ => {}
+            }
+            return;
+        }
+
+        // Handle the recovery code
+        if self.recovery_code.is_some() {
+            if matches!(key, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q')) {
+                self.recovery_code = None;
+            }
+            return;
+        }
+
+        // Handle the profile switcher
+        if let Some(picker) = self.profile_picker.as_mut() {
+            match key {
+                KeyCode::Esc => self.profile_picker = None,
+                KeyCode::Down => picker.select_next(),
+                KeyCode::Up => picker.select_previous(),
+                KeyCode::Enter => {
+                    if let Some(name) = picker.submit() {
+                        self.profile_picker = None;
+                        self.switch_profile(&name).await;
+                    }
+                }
+                _ => {
+                    picker.error = None;
+                    picker.input.handle_key(key, modifiers);
+                }
+            }
+            return;
+        }
+
+        // Handle the Diagnostics panel
+        if let Some(diagnostics) = self.diagnostics.as_mut() {
+            match key {
+                KeyCode::Esc | KeyCode::Char('q') => self.diagnostics = None,
+                KeyCode::Down | KeyCode::Char('j') => {
+                    diagnostics.scroll = diagnostics.scroll.saturating_add(1)
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    diagnostics.scroll = diagnostics.scroll.saturating_sub(1)
+                }
+                KeyCode::Char('r') if !diagnostics.checking_online => self.run_diagnostics(),
+                _ => {}
+            }
+            return;
+        }
+
+        // Handle the text that couldn't be copied
+        if self.copy_fallback.is_some() {
+            if matches!(key, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q')) {
+                self.copy_fallback = None;
+            }
+            return;
+        }
+
+        // Handle the connected clients
+        if let Some(cloud) = self.clients_panel.clone() {
+            let clients = self.orchestrator.cloud_clients(&cloud);
+            match key {
+                KeyCode::Esc | KeyCode::Char('q') => self.clients_panel = None,
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.clients_selected =
+                        (self.clients_selected + 1).min(clients.len().saturating_sub(1))
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.clients_selected = self.clients_selected.saturating_sub(1)
+                }
+                KeyCode::Char('x') | KeyCode::Delete => {
+                    if let Some(client) = clients.get(self.clients_selected) {
+                        match self.orchestrator.kill_client(&cloud, client.id) {
+                            Ok(()) => self.notify(
+                                Severity::Info,
+                                format!("Killed the connection of {}", client.ip),
+                            ),
+                            Err(e) => self.notify(Severity::Error, e.to_string()),
+                        }
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        // Handle the scheduled tasks
+        if self.show_tasks {
+            let count = self.orchestrator.task_statuses().len();
+            match key {
+                KeyCode::Esc | KeyCode::Char('q') => self.show_tasks = false,
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.tasks_selected = (self.tasks_selected + 1).min(count.saturating_sub(1))
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.tasks_selected = self.tasks_selected.saturating_sub(1)
+                }
+                KeyCode::Char('r') | KeyCode::Enter => {
+                    if let Err(e) = self.orchestrator.run_task_now(self.tasks_selected) {
+                        self.notify(Severity::Error, format!("Couldn't run the task: {}", e));
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        // Handle the notification history
+        if self.notifications.show_history {
+            match key {
+                KeyCode::Esc | KeyCode::Char('q') => self.notifications.toggle_history(),
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.notifications.history_scroll =
+                        self.notifications.history_scroll.saturating_add(1)
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.notifications.history_scroll =
+                        self.notifications.history_scroll.saturating_sub(1)
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        // Esc closes the jobs panel
+        if self.show_jobs && key == KeyCode::Esc {
+            self.show_jobs = false;
+            return;
+        }
+
+        // Handle the fuzzy finder overlay
+        if self.fuzzy_finder.active {
+            self.handle_fuzzy_finder_input(key, modifiers).await;
+            return;
+        }
+
+        // Handle the `:` command line
+        if self.command_line.active {
+            self.handle_command_line_input(key, modifiers).await;
+            return;
+        }
+
+        // Handle the clouds config conflict prompt
+        if self.show_config_conflict {
+            self.handle_config_conflict_input(key);
+            return;
+        }
+
+        // Handle the passphrase prompt of an encrypted export/import
+        if self.passphrase_prompt.is_some() {
+            self.handle_passphrase_input(key, modifiers);
+            return;
+        }
+
+        // Handle the prompt of a server default in the Settings tab
+        if self.settings_state.default_editor.is_some() {
+            self.handle_default_editor_input(key, modifiers);
+            return;
+        }
+
+        // Handle the `/` search prompt of the Cloud Logs panel
+        if self.clouds_state.log_view.search_input.is_some() {
+            self.handle_log_search_input(key, modifiers);
+            return;
+        }
+
+        // Handle password creation modal (now on clouds tab)
+        if self.clouds_state.password_creation.creating_password {
+            self.clouds_state.handle_password_input(key, modifiers);
+            // If password creation is complete, handle it
+            if self
+                .clouds_state
+                .password_creation
+                .is_password_creation_complete()
+            {
+                self.complete_password_creation().await;
+            }
+            return;
+        }
+
+        // Handle the change password modal
+        if self.clouds_state.password_change.active {
+            if self
+                .clouds_state
+                .password_change
+                .handle_input(key, modifiers)
+            {
+                self.complete_password_change();
+            }
+            return;
+        }
+
+        // Handle the confirmation before a destructive action
+        if self.confirmation.is_some() {
+            self.handle_confirmation_input(key).await;
+            return;
+        }
+
+        // Handle the directory picker opened from a folder modal
+        if self.folders_state.dir_picker.is_some() {
+            self.handle_dir_picker_input(key);
+            return;
+        }
+
+        // Handle folder creation modal
+        if self.folders_state.creating_folder && self.handle_folder_creation_input(key, modifiers) {
+            return;
+        }
+
+        // Handle cloud creation modal
+        if self.folders_state.creating_cloud && self.handle_cloud_creation_input(key, modifiers) {
+            return;
+        }
+
+        // Handle password creation modal (for existing clouds)
+        if self.folders_state.password_creation.creating_password
+            && !self.folders_state.creating_cloud
+            && self.handle_password_creation_input(key, modifiers)
+        {
+            return;
+        }
+
+        // Handle folder edit modal
+        if self.folders_state.editing_folder && self.handle_folder_edit_input(key, modifiers) {
+            return;
+        }
+
+        // Handle cloud edit modal
+        if self.folders_state.editing_cloud && self.handle_cloud_edit_input(key, modifiers) {
+            return;
+        }
+
+        // Handle directory creation modal in the files panel
+        if self.folders_state.files.creating_directory
+            && self.handle_directory_creation_input(key, modifiers)
+        {
+            return;
+        }
+
+        // Handle client tab inputs (connect, download and upload modals)
+        if self.client_state.input_mode != client::models::ClientInputMode::None
+            && self.handle_client_input(key, modifiers).await
+        {
+            return;
+        }
+
+        // Handle keybinding capture in the settings tab
+        if self.settings_state.capture.is_some() {
+            self.handle_keybinding_capture(key, &key_str).await;
+            return;
+        }
+
+        // Handle leader key sequences first
+        if key_str == self.config.leader {
+            self.input_state =
+                InputState::KeySequence("<leader>".to_string(), std::time::Instant::now());
+            self.add_debug(&format!("Leader key ('{}') pressed", key_str));
+            return;
+        }
+
+        // Handle multi-key sequences (like gt, gT, <leader>d, ft, etc.)
+        if let InputState::KeySequence(ref seq, start_time) = self.input_state {
+            // Check if the sequence has timed out
+            if start_time.elapsed().as_millis() > KEY_SEQUENCE_TIMEOUT_MS as u128 {
+                // Timeout reached, execute the single key if it exists
+                if let Some(action) = self.config.get_action_for_key_in_tab(seq, current_tab) {
+                    self.execute_action(&action).await;
+                }
+                self.input_state = InputState::Normal;
+                return;
+            }
+
+            if seq == "<leader>" {
+                let leader_key = format!("<leader>{}", key_str);
+                if let Some(action) = self
+                    .config
+                    .get_action_for_key_in_tab(&leader_key, current_tab)
+                {
+                    self.execute_action(&action).await;
+                }
+                self.input_state = InputState::Normal;
+                return;
+            } else {
+                // Try to complete the sequence with the current key
+                let complete_key = format!("{}{}", seq, key_str);
+                if let Some(action) = self
+                    .config
+                    .get_action_for_key_in_tab(&complete_key, current_tab)
+                {
+                    self.execute_action(&action).await;
+                }
+                self.input_state = InputState::Normal;
+                return;
+            }
+        }
+
+        // Check if this key could start a multi-key sequence
+        // Look for any keybinding that starts with this key
+        // Skip special keys that are complete by themselves (like <Up>, <Down>, <Enter>)
+        // but allow multi-key special keys (like <Ctrl>c, <Alt>f, etc.)
+        let potential_sequences: Vec<String> = self
+            .config
+            .actions
+            .values()
+            .flat_map(|action| &action.keys)
+            .filter(|k| {
+                k.starts_with(&key_str)
+                    && k.len() > key_str.len()
+                    && // Skip if it's a single special key (starts with <, ends with >, and doesn't contain another <)
+                    !(k.starts_with('<') && k.ends_with('>') && !k[1..k.len() - 1].contains('<'))
+            })
+            .cloned()
+            .collect();
+
+        if !potential_sequences.is_empty() {
+            self.input_state = InputState::KeySequence(key_str.clone(), std::time::Instant::now());
+            self.add_debug(&format!(
+                "Started '{}' sequence, potential: {:?}",
+                key_str, potential_sequences
+            ));
+            return;
+        }
+
+        // Check if key is valid for current tab and process it
+        if let Some(action) = self.config.get_action_for_key_in_tab(&key_str, current_tab) {
+            self.execute_action(&action).await;
+        } else if self.config.get_action_for_key(&key_str).is_some() {
+            self.add_debug(&format!(
+                "Key '{}' not valid for tab '{}'",
+                key_str, current_tab
+            ));
+        } else {
+            self.add_debug(&format!("No keybinding found for key '{}'", key_str));
+        }
+    }
+
+    // ========== Keybinding editor ==========
+
+    async fn handle_keybinding_capture(&mut self, key: KeyCode, key_str: &str) {
+        let leader = self.config.leader.clone();
+        let Some(capture) = self.settings_state.capture.as_mut() else {
+            return;
+        };
+
+        // Waiting for the user to confirm stealing the key from other actions
+        if !capture.conflicts.is_empty() {
+            match key {
+                KeyCode::Enter => self.apply_keybinding_capture(),
+                KeyCode::Esc => self.settings_state.cancel_capture(),
+                _ => {}
+            }
+            return;
+        }
+
+        match key {
+            KeyCode::Esc => self.settings_state.cancel_capture(),
+            KeyCode::Enter if !capture.keys.is_empty() => {
+                let chord = capture.key();
+                let conflicts = self.config.find_conflicts(&chord, &capture.action);
+                if conflicts.is_empty() {
+                    self.apply_keybinding_capture();
+                } else {
+                    self.settings_state.keybinding_error = Some(format!(
+                        "'{}' is already used by {}. Enter to reassign, Esc to cancel",
+                        chord,
+                        conflicts.join(", ")
+                    ));
+                    if let Some(capture) = self.settings_state.capture.as_mut() {
+                        capture.conflicts = conflicts;
+                    }
+                }
+            }
+            KeyCode::Backspace if !capture.keys.is_empty() => {
+                capture.keys.pop();
+            }
+            _ => {
+                let key_str = if key_str == leader {
+                    "<leader>".to_string()
+                } else {
+                    key_str.to_string()
+                };
+                capture.keys.push(key_str);
+            }
+        }
+    }
+
+    fn apply_keybinding_capture(&mut self) {
+        let Some(capture) = self.settings_state.capture.take() else {
+            return;
+        };
+        let chord = capture.key();
+        for other in &capture.conflicts {
+            self.config.unbind_key(other, &chord);
+        }
+        self.config
+            .bind_key(&capture.action, &chord, capture.append);
+        self.settings_state.keybinding_error = None;
+
+        let message = if capture.conflicts.is_empty() {
+            format!("Bound '{}' to '{}'", chord, capture.action)
+        } else {
+            format!(
+                "Bound '{}' to '{}' (removed from {})",
+                chord,
+                capture.action,
+                capture.conflicts.join(", ")
+            )
+        };
+        self.save_keybindings(message);
+    }
+
+    // ========== Theme ==========
+
+    /// Colors to draw with, from `[theme]` in the TUI config
+    pub fn theme(&self) -> Theme {
+        self.config.theme.resolve()
+    }
+
+    /// Switch to the next built-in theme and keep it in the TUI config
+    pub fn next_theme(&mut self) {
+        self.config.theme.name = self.config.theme.name.next();
+        let name = self.config.theme.name;
+        match self.config.save_to_file() {
+            Ok(()) => self.notify(Severity::Info, format!("Theme: {}", name)),
+            Err(e) => self.notify(
+                Severity::Error,
+                format!("Switched to theme {}, but it wasn't saved: {}", name, e),
+            ),
+        }
+    }
+
+    /// Move the edge of the focused column of the Clouds or Folders tab, and keep the new
+    /// widths in the TUI config
+    fn resize_panel(&mut self, grow: bool) {
+        let layout = &mut self.config.layout;
+        let (columns, column) = match self.selected_tab {
+            SelectedTab::Clouds => (
+                &mut layout.clouds_columns,
+                match self.clouds_state.focused_panel {
+                    CloudFocusedPanel::Clouds => 0,
+                    CloudFocusedPanel::CloudInfo => 1,
+                    CloudFocusedPanel::CloudLogs => 2,
+                },
+            ),
+            SelectedTab::Folders => (
+                &mut layout.folders_columns,
+                match self.folders_state.focused_panel {
+                    folders::models::FocusedPanel::Folders
+                    | folders::models::FocusedPanel::Files => 0,
+                    folders::models::FocusedPanel::Clouds => 1,
+                    folders::models::FocusedPanel::Info => 2,
+                },
+            ),
+            _ => return,
+        };
+        // At the narrowest a column can be; nothing to do
+        if !resize_column(columns, column, grow) {
+            return;
+        }
+        if let Err(e) = self.config.save_to_file() {
+            self.notify(
+                Severity::Error,
+                format!("Resized the panel, but it wasn't saved: {}", e),
+            );
+        }
+    }
+
+    /// Tell the user about theme colors that are ignored and layout sizes that conflict
+    fn warn_about_tui_config(&mut self) {
+        let mut problems = self.config.theme.validate();
+        problems.extend(self.config.layout.validate());
+        if !problems.is_empty() {
+            self.notify(Severity::Warning, problems.join("; "));
+        }
+    }
+
+    /// Persist the in-memory keybindings and reload them from disk
+    fn save_keybindings(&mut self, message: String) {
+        match self.config.save_to_file() {
+            Ok(()) => {
+                if let Ok(config) = crate::config::Config::load() {
+                    self.config = config;
+                }
+                self.add_debug(&message);
+                self.settings_state.keybinding_message = Some(message);
+            }
+            Err(e) => {
+                self.settings_state.keybinding_error =
+                    Some(format!("Failed to save keybindings: {}", e));
+            }
+        }
+    }
+
+    // ========== Fuzzy finder ==========
+
+    fn open_fuzzy_finder(&mut self) {
+        use crate::utils::fuzzy_finder::{FinderItem, FinderItemKind};
+
+        let mut items = Vec::new();
+        for cloud in &self.clouds_state.all_clouds {
+            let detail = match self.clouds_state.get_cloud_port(&cloud.name) {
+                Some(port) => format!("running on port {}", port),
+                None => format!("{} folder(s)", cloud.cloud_folders.len()),
+            };
+            items.push(FinderItem {
+                kind: FinderItemKind::Cloud,
+                label: cloud.name.clone(),
+                detail,
+            });
+        }
+        for folder in &self.folders_state.cloud_folders {
+            items.push(FinderItem {
+                kind: FinderItemKind::CloudFolder,
+                label: folder.name.clone(),
+                detail: folder.folder_path.display().to_string(),
+            });
+        }
+        let mut actions: Vec<(&String, &crate::config::Action)> =
+            self.config.actions.iter().collect();
+        actions.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, action) in actions {
+            items.push(FinderItem {
+                kind: FinderItemKind::Action,
+                label: name.clone(),
+                detail: format!("{} ({})", action.keys.join(", "), action.tab),
+            });
+        }
+
+        self.fuzzy_finder.open(items);
+    }
+
+    async fn handle_fuzzy_finder_input(
+        &mut self,
+        key: KeyCode,
+        modifiers: ratatui::crossterm::event::KeyModifiers,
+    ) {
+        let ctrl = modifiers.contains(ratatui::crossterm::event::KeyModifiers::CONTROL);
+        match key {
+            KeyCode::Esc => self.fuzzy_finder.close(),
+            KeyCode::Enter => {
+                let item = self.fuzzy_finder.selected_item().cloned();
+                self.fuzzy_finder.close();
+                if let Some(item) = item {
+                    self.jump_to_finder_item(item).await;
+                }
+            }
+            KeyCode::Up | KeyCode::BackTab => self.fuzzy_finder.select_previous(),
+            KeyCode::Down | KeyCode::Tab => self.fuzzy_finder.select_next(),
+            KeyCode::Char('p' | 'k') if ctrl => self.fuzzy_finder.select_previous(),
+            KeyCode::Char('n' | 'j') if ctrl => self.fuzzy_finder.select_next(),
+            _ => {
+                self.fuzzy_finder.edit_query(key, modifiers);
+            }
+        }
+    }
+
+    async fn jump_to_finder_item(&mut self, item: crate::utils::fuzzy_finder::FinderItem) {
+        use crate::utils::fuzzy_finder::FinderItemKind;
+
+        match item.kind {
+            FinderItemKind::Cloud => {
+                if self.select_cloud(&item.label) {
+                    self.selected_tab = SelectedTab::Clouds;
+                    self.clouds_state.focused_panel = clouds::models::CloudFocusedPanel::Clouds;
+                }
+            }
+            FinderItemKind::CloudFolder => {
+                if let Some(index) = self
+                    .folders_state
+                    .cloud_folders
+                    .iter()
+                    .position(|f| f.name == item.label)
+                {
+                    self.selected_tab = SelectedTab::Folders;
+                    self.folders_state.focused_panel = folders::models::FocusedPanel::Folders;
+                    self.folders_state.selected_folder_index = index;
+                    self.folders_state.folders_list_state.select(Some(index));
+                    self.folders_state.sync_files_panel();
+                }
+            }
+            FinderItemKind::Action => self.execute_action(&item.label).await,
+        }
+        self.add_debug(&format!("Fuzzy finder jumped to '{}'", item.label));
+    }
+
+    // ========== Command line ==========
+
+    async fn handle_command_line_input(
+        &mut self,
+        key: KeyCode,
+        modifiers: ratatui::crossterm::event::KeyModifiers,
+    ) {
+        match key {
+            KeyCode::Esc => self.command_line.close(),
+            KeyCode::Enter => {
+                let line = self.command_line.submit();
+                if !line.is_empty() {
+                    self.run_command_line(&line).await;
+                }
+            }
+            // Like vim, backspace on an empty line leaves command mode
+            KeyCode::Backspace if !self.command_line.pop_char() => self.command_line.close(),
+            KeyCode::Tab | KeyCode::BackTab => {
+                let cloud_names: Vec<String> = self
+                    .clouds_state
+                    .all_clouds
+                    .iter()
+                    .map(|c| c.name.clone())
+                    .collect();
+                let tags = self.clouds_state.tags();
+                let action_names: Vec<String> = self.config.actions.keys().cloned().collect();
+                let forward = key == KeyCode::Tab
+                    && !modifiers.contains(ratatui::crossterm::event::KeyModifiers::SHIFT);
+                self.command_line
+                    .complete(&cloud_names, &tags, &action_names, forward);
+            }
+            KeyCode::Up => self.command_line.history_previous(),
+            KeyCode::Down => self.command_line.history_next(),
+            KeyCode::Char(c) => self.command_line.push_char(c),
+            _ => {}
+        }
+    }
+
+    async fn run_command_line(&mut self, line: &str) {
+        let action_names: Vec<String> = self.config.actions.keys().cloned().collect();
+        match crate::utils::command_line::parse_command(line, &action_names) {
+            Ok(command) => self.run_command(command).await,
+            Err(e) => self.command_line.set_message(e, true),
+        }
+    }
+
+    async fn run_command(&mut self, command: crate::utils::command_line::Command) {
+        use crate::utils::command_line::{Command, CommandTarget};
+        use crate::utils::passphrase_prompt::{PassphrasePrompt, PassphrasePurpose};
+
+        self.add_debug(&format!("Command: {:?}", command));
+
+        match command {
+            Command::Start(CommandTarget::Cloud(name)) => {
+                if !self.select_cloud(&name) {
+                    return;
+                }
+                if self.clouds_state.is_cloud_running(&name) {
+                    self.command_line
+                        .set_message(format!("Cloud '{}' is already running", name), true);
+                } else {
+                    self.execute_action("Start/Stop Cloud").await;
+                    self.report_cloud_command(&name, "Started");
+                }
+            }
+            Command::Start(target @ (CommandTarget::All | CommandTarget::Tag(_))) => {
+                let names: Vec<String> = match &target {
+                    CommandTarget::Tag(tag) => {
+                        let tagged = self.clouds_state.clouds_tagged(tag);
+                        if tagged.is_empty() {
+                            self.command_line
+                                .set_message(format!("No clouds are tagged '{}'", tag), true);
+                            return;
+                        }
+                        tagged
+                    }
+                    _ => self
+                        .clouds_state
+                        .all_clouds
+                        .iter()
+                        .map(|c| c.name.clone())
+                        .collect(),
+                };
+                let names: Vec<String> = names
+                    .into_iter()
+                    .filter(|name| !self.clouds_state.is_cloud_running(name))
+                    .collect();
+                let mut failed = Vec::new();
+                for name in &names {
+                    self.select_cloud(name);
+                    self.execute_action("Start/Stop Cloud").await;
+                    if !self.clouds_state.is_cloud_running(name) {
+                        failed.push(name.clone());
+                    }
+                }
+                if failed.is_empty() {
+                    self.command_line
+                        .set_message(format!("Started {} cloud(s)", names.len()), false);
+                } else {
+                    self.command_line
+                        .set_message(format!("Failed to start: {}", failed.join(", ")), true);
+                }
+            }
+            Command::Stop(CommandTarget::Cloud(name)) => {
+                if !self.select_cloud(&name) {
+                    return;
+                }
+                if self.clouds_state.is_cloud_running(&name) {
+                    self.request_stop(name).await;
+                } else {
+                    self.command_line
+                        .set_message(format!("Cloud '{}' is not running", name), true);
+                }
+            }
+            Command::Stop(CommandTarget::All) => {
+                self.clouds_state
+                    .stop_all_servers(&mut self.orchestrator)
+                    .await;
+                self.command_line.set_message("Stopped all clouds", false);
+            }
+            Command::Stop(CommandTarget::Tag(tag)) => {
+                let names: Vec<String> = self
+                    .clouds_state
+                    .clouds_tagged(&tag)
+                    .into_iter()
+                    .filter(|name| self.clouds_state.is_cloud_running(name))
+                    .collect();
+                // Stopping by tag is deliberate enough not to ask for each cloud
+                for name in &names {
+                    self.perform_confirmed(crate::utils::confirmation::ConfirmAction::StopCloud(
+                        name.clone(),
+                    ))
+                    .await;
+                }
+                self.command_line.set_message(
+                    format!("Stopped {} cloud(s) tagged '{}'", names.len(), tag),
+                    false,
+                );
+            }
+            Command::Tag { cloud, tags } => match self.orchestrator.set_cloud_tags(&cloud, tags) {
+                Ok(()) => {
+                    self.load_folders_from_orchestrator();
+                    self.command_line
+                        .set_message(format!("Updated the tags of '{}'", cloud), false);
+                }
+                Err(e) => self.command_line.set_message(e.to_string(), true),
+            },
+            Command::Password(name) => {
+                if self.select_cloud(&name) {
+                    self.selected_tab = SelectedTab::Clouds;
+                    self.execute_action("Create Password").await;
+                }
+            }
+            Command::AddFolder { name, path } => match self.add_cloud_folder(&name, &path) {
+                Ok(()) => self
+                    .command_line
+                    .set_message(format!("Added folder '{}'", name), false),
+                Err(e) => self.command_line.set_message(e, true),
+            },
+            Command::AddCloud { name, folders } => match self.add_cloud(&name, &folders) {
+                Ok(()) => self.command_line.set_message(
+                    format!(
+                        "Added cloud '{}', set its password with :password {}",
+                        name, name
+                    ),
+                    false,
+                ),
+                Err(e) => self.command_line.set_message(e, true),
+            },
+            Command::Export { path, encrypt } => {
+                if encrypt {
+                    self.passphrase_prompt =
+                        Some(PassphrasePrompt::new(PassphrasePurpose::Export(path)));
+                } else {
+                    self.export_clouds_config(&path, None);
+                }
+            }
+            Command::Import(path) => {
+                match cloudhost_server::Orchestrator::is_export_encrypted(&path) {
+                    Ok(true) => {
+                        self.passphrase_prompt =
+                            Some(PassphrasePrompt::new(PassphrasePurpose::Import(path)));
+                    }
+                    Ok(false) => self.import_clouds_config(&path, None),
+                    Err(e) => self.notify(
+                        Severity::Error,
+                        format!("Failed to read {}: {}", path.display(), e),
+                    ),
+                }
+            }
+            Command::Secrets(command) => self.run_secrets_command(command),
+            Command::Token { cloud, command } => self.run_token_command(&cloud, command),
+            Command::Webhooks(cloud) => match self.orchestrator.webhook_deliveries(&cloud) {
+                Ok(_) => {
+                    self.webhook_history = Some(cloud);
+                    self.webhook_history_scroll = 0;
+                }
+                Err(e) => self.command_line.set_message(e.to_string(), true),
+            },
+            Command::Profile(Some(name)) => self.switch_profile(&name).await,
+            Command::Profile(None) => {
+                self.profile_picker = Some(crate::utils::profile_picker::ProfilePicker::new())
+            }
+            Command::Action(action) => self.execute_action(&action).await,
+        }
+    }
+
+    /// Stop the clouds and start over with the configs of profile `name`, creating it
+    /// when it doesn't exist yet
+    pub async fn switch_profile(&mut self, name: &str) {
+        use cloudhost_server::config_paths;
+
+        if let Err(e) = config_paths::validate_profile_name(name) {
+            self.notify(Severity::Error, e);
+            return;
+        }
+        let current = config_paths::current_profile()
+            .unwrap_or_else(|| config_paths::DEFAULT_PROFILE.to_string());
+        if current == name {
+            self.notify(Severity::Info, format!("Already using profile '{}'", name));
+            return;
+        }
+
+        self.clouds_state
+            .stop_all_servers(&mut self.orchestrator)
+            .await;
+        if let Err(e) = config_paths::set_profile(name) {
+            self.notify(Severity::Error, e);
+            return;
+        }
+        let events = self.events.take();
+        let history = std::mem::take(&mut self.command_line.history);
+        *self = App::new();
+        self.command_line.history = history;
+        if let Some(events) = events {
+            self.start_config_watcher(events.clone());
+            self.events = Some(events);
+            self.update_cloud_logs().await;
+            self.start_landing().await;
+        }
+        self.notify(Severity::Success, format!("Switched to profile '{}'", name));
+    }
+
+    /// Open the Diagnostics panel with the doctor's local checks, and run the online ones
+    /// in the background
+    fn run_diagnostics(&mut self) {
+        use cloudhost_server::doctor::Doctor;
+
+        let doctor = Doctor::new(cloudhost_server::config_paths::get_clouds_config_path())
+            .with_own_ports(self.orchestrator.own_ports());
+        let checks = crate::doctor::local_checks(&doctor);
+        let checking_online = match self.events.clone() {
+            Some(events) => {
+                let mut running: Vec<(String, u16)> =
+                    self.orchestrator.get_running_clouds().into_iter().collect();
+                running.sort();
+                tokio::spawn(async move {
+                    let checks = doctor.online_checks(&running).await;
+                    let _ = events.send(crate::events::AppEvent::OnlineChecks(checks));
+                });
+                true
+            }
+            None => false,
+        };
+        self.diagnostics = Some(crate::utils::diagnostics::Diagnostics {
+            checks,
+            checking_online,
+            scroll: 0,
+        });
+    }
+
+    /// Test in the background whether the selected running cloud can be reached from the
+    /// internet; the result is shown in the Cloud Info panel
+    fn test_reachability(&mut self) {
+        let Some(cloud) = self.selected_cloud_name() else {
+            self.add_debug("No cloud selected");
+            return;
+        };
+        let Some(port) = self.orchestrator.get_running_clouds().get(&cloud).copied() else {
+            self.notify(
+                Severity::Warning,
+                format!("Cloud '{}' is not running, start it to test it", cloud),
+            );
+            return;
+        };
+        let Some(events) = self.events.clone() else {
+            return;
+        };
+        let echo_url = self.orchestrator.clouds_config.reachability_url.clone();
+        self.clouds_state.reachability.insert(cloud.clone(), None);
+        tokio::spawn(async move {
+            let check =
+                cloudhost_server::doctor::reachability_test(&cloud, port, echo_url.as_deref())
+                    .await;
+            let _ = events.send(crate::events::AppEvent::Reachability { cloud, check });
+        });
+    }
+
+    /// Send a test email for every cloud with email notifications, each as a job
+    fn send_test_emails(&mut self) {
+        let Some(events) = self.events.clone() else {
+            return;
+        };
+        let configs = self.orchestrator.email_configs();
+        if configs.is_empty() {
+            self.notify(
+                Severity::Info,
+                "No cloud has email notifications, add [clouds.email] to the clouds config",
+            );
+            return;
+        }
+        for (cloud, config) in configs {
+            let label = format!("Test email of '{}'", cloud);
+            self.jobs
+                .spawn(JobKind::Email, label, &events, |_| async move {
+                    cloudhost_server::email::send_test(&config, &cloud)
+                        .await
+                        .map(|()| {
+                            format!(
+                                "Sent a test email of '{}' to {}",
+                                cloud,
+                                config.to.join(", ")
+                            )
+                        })
+                        .map_err(|e| e.to_string())
+                });
+        }
+    }
+
+    fn run_token_command(
+        &mut self,
+        cloud: &str,
+        command: crate::utils::command_line::TokenCommand,
+    ) {
+        use crate::utils::command_line::TokenCommand;
+
+        match command {
+            TokenCommand::List => match self.orchestrator.list_api_tokens(cloud) {
+                Ok(tokens) if tokens.is_empty() => self
+                    .command_line
+                    .set_message(format!("Cloud '{}' has no API tokens", cloud), false),
+                Ok(tokens) => {
+                    let tokens: Vec<String> = tokens
+                        .iter()
+                        .map(|token| format!("{} {} ({})", token.id, token.name, token.scope))
+                        .collect();
+                    self.command_line.set_message(tokens.join(", "), false);
+                }
+                Err(e) => self.command_line.set_message(e.to_string(), true),
+            },
+            TokenCommand::Create { name, scope } => {
+                match self.orchestrator.create_api_token(cloud, &name, scope) {
+                    Ok((token, value)) => {
+                        self.load_folders_from_orchestrator();
+                        // Shown until the next key press, and never again
+                        self.command_line.set_message(
+                            format!("Token '{}' ({}): {}", token.name, token.scope, value),
+                            false,
+                        );
+                    }
+                    Err(e) => self.command_line.set_message(e.to_string(), true),
+                }
+            }
+            TokenCommand::Revoke(id) => match self.orchestrator.revoke_api_token(cloud, &id) {
+                Ok(()) => {
+                    self.load_folders_from_orchestrator();
+                    self.notify(
+                        Severity::Success,
+                        format!("Revoked API token {} of '{}'", id, cloud),
+                    );
+                }
+                Err(e) => self.command_line.set_message(e.to_string(), true),
+            },
+        }
+    }
+
+    fn handle_config_conflict_input(&mut self, key: ratatui::crossterm::event::KeyCode) {
+        use cloudhost_server::ConflictResolution;
+        use ratatui::crossterm::event::KeyCode;
+
+        let resolution = match key {
+            KeyCode::Char('m') => ConflictResolution::Merge,
+            KeyCode::Char('o') => ConflictResolution::KeepMine,
+            KeyCode::Char('r') => ConflictResolution::KeepTheirs,
+            _ => return,
+        };
+        self.show_config_conflict = false;
+        match self.orchestrator.resolve_config_conflict(resolution) {
+            Ok(()) => {
+                self.load_folders_from_orchestrator();
+                let message = match resolution {
+                    ConflictResolution::Merge => "Merged your changes into the clouds config",
+                    ConflictResolution::KeepMine => "Overwrote the clouds config with your version",
+                    ConflictResolution::KeepTheirs => "Reloaded the clouds config from disk",
+                };
+                self.notify(Severity::Success, message);
+            }
+            Err(e) => self.notify(
+                Severity::Error,
+                format!("Failed to resolve config conflict: {}", e),
+            ),
+        }
+    }
+
+    fn handle_passphrase_input(&mut self, key: KeyCode, modifiers: KeyModifiers) {
+        use crate::utils::passphrase_prompt::PassphrasePurpose;
+
+        let Some(prompt) = self.passphrase_prompt.as_mut() else {
+            return;
+        };
+        match key {
+            KeyCode::Esc => {
+                if prompt.purpose == PassphrasePurpose::UnlockSecrets {
+                    self.notify(
+                        Severity::Warning,
+                        "Cloud secrets stay locked: clouds can't start and changes can't be saved until you run :secrets unlock",
+                    );
+                }
+                self.passphrase_prompt = None;
+            }
+            KeyCode::Enter => {
+                let Some(passphrase) = prompt.submit() else {
+                    return;
+                };
+                let purpose = prompt.purpose.clone();
+                match purpose {
+                    PassphrasePurpose::UnlockSecrets => {
+                        // Keep the prompt open to retry a wrong passphrase
+                        match self
+                            .orchestrator
+                            .unlock_secrets_with_passphrase(&passphrase)
+                        {
+                            Ok(()) => {
+                                self.passphrase_prompt = None;
+                                self.load_folders_from_orchestrator();
+                                self.notify(Severity::Success, "Cloud secrets unlocked");
+                            }
+                            Err(e) => prompt.error = Some(e.to_string()),
+                        }
+                    }
+                    PassphrasePurpose::ProtectSecrets => {
+                        self.passphrase_prompt = None;
+                        self.protect_secrets(SecretsProtection::Passphrase, Some(&passphrase));
+                    }
+                    PassphrasePurpose::Export(path) => {
+                        self.passphrase_prompt = None;
+                        self.export_clouds_config(&path, Some(&passphrase));
+                    }
+                    PassphrasePurpose::Import(path) => {
+                        self.passphrase_prompt = None;
+                        self.import_clouds_config(&path, Some(&passphrase));
+                    }
+                }
+            }
+            _ => {
+                prompt.input.handle_key(key, modifiers);
+            }
         }
+    }
 
-        // Handle folder creation modal
-        if self.folders_state.creating_folder && self.handle_folder_creation_input(key) {
+    fn handle_default_editor_input(&mut self, key: KeyCode, modifiers: KeyModifiers) {
+        let Some(editor) = self.settings_state.default_editor.as_mut() else {
             return;
+        };
+        match key {
+            KeyCode::Esc => self.settings_state.default_editor = None,
+            KeyCode::Enter => {
+                let field = editor.field;
+                let mut defaults = self.orchestrator.clouds_config.defaults.clone();
+                if let Err(e) = field.parse(editor.input.value(), &mut defaults) {
+                    editor.error = Some(e);
+                    return;
+                }
+                // Keep the prompt open to correct a value the server refuses
+                if let Err(e) = self.orchestrator.set_defaults(defaults) {
+                    editor.error = Some(e.to_string());
+                    return;
+                }
+                self.settings_state.default_editor = None;
+                let value = field.display(&self.orchestrator.clouds_config.defaults);
+                let overrides = Overrides::from_env().unwrap_or_default();
+                if let Some(var) = field.overridden_by(&overrides) {
+                    self.notify(
+                        Severity::Warning,
+                        format!(
+                            "{} set to {} in the config, but {} is used while it is set",
+                            field.label(),
+                            value,
+                            var
+                        ),
+                    );
+                    return;
+                }
+                let applies = if field.applies_right_away() {
+                    "running clouds took it"
+                } else {
+                    "clouds take it the next time they start"
+                };
+                self.notify(
+                    Severity::Success,
+                    format!("{} set to {}, {}", field.label(), value, applies),
+                );
+            }
+            _ => {
+                editor.input.handle_key(key, modifiers);
+            }
         }
+    }
 
-        // Handle cloud creation modal
-        if self.folders_state.creating_cloud && self.handle_cloud_creation_input(key) {
-            return;
-        }
+    // ========== Cloud Secrets ==========
 
-        // Handle password creation modal (for existing clouds)
-        if self.folders_state.password_creation.creating_password
-            && !self.folders_state.creating_cloud
-            && self.handle_password_creation_input(key)
-        {
+    /// Unlock encrypted cloud secrets at startup: from the OS keyring, the
+    /// CLOUDHOST_SECRETS_PASSPHRASE environment variable, or by asking for the passphrase
+    fn unlock_secrets_on_startup(&mut self) {
+        use crate::utils::passphrase_prompt::{PassphrasePrompt, PassphrasePurpose};
+
+        if !self.orchestrator.secrets_locked() {
             return;
         }
+        match self.orchestrator.secrets_protection() {
+            Some(SecretsProtection::Keyring) => {
+                if let Err(e) = self.orchestrator.unlock_secrets_with_keyring() {
+                    self.notify(
+                        Severity::Error,
+                        format!("Failed to unlock cloud secrets: {}", e),
+                    );
+                }
+            }
+            _ => {
+                if let Ok(passphrase) = std::env::var(SECRETS_PASSPHRASE_ENV) {
+                    if self
+                        .orchestrator
+                        .unlock_secrets_with_passphrase(&passphrase)
+                        .is_ok()
+                    {
+                        return;
+                    }
+                }
+                self.passphrase_prompt =
+                    Some(PassphrasePrompt::new(PassphrasePurpose::UnlockSecrets));
+            }
+        }
+    }
 
-        // Handle folder edit modal
-        if self.folders_state.editing_folder && self.handle_folder_edit_input(key) {
-            return;
+    fn protect_secrets(&mut self, protection: SecretsProtection, passphrase: Option<&str>) {
+        match self.orchestrator.protect_secrets(protection, passphrase) {
+            Ok(()) => self.notify(
+                Severity::Success,
+                format!("Cloud secrets are now encrypted with the {}", protection),
+            ),
+            Err(e) => self.notify(
+                Severity::Error,
+                format!("Failed to encrypt cloud secrets: {}", e),
+            ),
         }
+    }
 
-        // Handle cloud edit modal
-        if self.folders_state.editing_cloud && self.handle_cloud_edit_input(key) {
-            return;
+    fn run_secrets_command(&mut self, command: crate::utils::command_line::SecretsCommand) {
+        use crate::utils::command_line::SecretsCommand;
+        use crate::utils::passphrase_prompt::{PassphrasePrompt, PassphrasePurpose};
+
+        match command {
+            SecretsCommand::Passphrase => {
+                self.passphrase_prompt =
+                    Some(PassphrasePrompt::new(PassphrasePurpose::ProtectSecrets));
+            }
+            SecretsCommand::Keyring => self.protect_secrets(SecretsProtection::Keyring, None),
+            SecretsCommand::Off => match self.orchestrator.unprotect_secrets() {
+                Ok(()) => self.notify(
+                    Severity::Warning,
+                    "Cloud secrets are stored in plain text again",
+                ),
+                Err(e) => self.notify(
+                    Severity::Error,
+                    format!("Failed to decrypt cloud secrets: {}", e),
+                ),
+            },
+            SecretsCommand::Unlock => {
+                if self.orchestrator.secrets_locked() {
+                    self.unlock_secrets_on_startup();
+                } else {
+                    self.notify(Severity::Info, "Cloud secrets are not locked");
+                }
+            }
         }
+    }
 
-        // Handle leader key sequences first
-        if key_str == self.config.leader {
-            self.input_state =
-                InputState::KeySequence("<leader>".to_string(), std::time::Instant::now());
-            self.add_debug(&format!("Leader key ('{}') pressed", key_str));
-            return;
+    fn export_clouds_config(&mut self, path: &std::path::Path, passphrase: Option<&str>) {
+        match self.orchestrator.export_config(path, passphrase) {
+            Ok(()) => {
+                let what = if passphrase.is_some() {
+                    "encrypted, with secrets"
+                } else {
+                    "without secrets"
+                };
+                self.notify(
+                    Severity::Success,
+                    format!("Exported clouds config ({}) to {}", what, path.display()),
+                );
+            }
+            Err(e) => self.notify(Severity::Error, format!("Export failed: {}", e)),
         }
+    }
 
-        // Handle multi-key sequences (like gt, gT, <leader>d, ft, etc.)
-        if let InputState::KeySequence(ref seq, start_time) = self.input_state {
-            // Check if the sequence has timed out
-            if start_time.elapsed().as_millis() > KEY_SEQUENCE_TIMEOUT_MS as u128 {
-                // Timeout reached, execute the single key if it exists
-                if let Some(action) = self.config.get_action_for_key(seq) {
-                    if self.config.is_key_valid_for_tab(seq, current_tab) {
-                        self.execute_action(&action).await;
-                    }
-                }
-                self.input_state = InputState::Normal;
-                return;
+    fn import_clouds_config(&mut self, path: &std::path::Path, passphrase: Option<&str>) {
+        match self.orchestrator.import_config(path, passphrase) {
+            Ok(summary) => {
+                self.load_folders_from_orchestrator();
+                let severity = if summary.skipped.is_empty() && summary.missing_paths.is_empty() {
+                    Severity::Success
+                } else {
+                    Severity::Warning
+                };
+                self.notify(severity, summary.describe());
             }
+            Err(e) => self.notify(Severity::Error, format!("Import failed: {}", e)),
+        }
+    }
 
-            if seq == "<leader>" {
-                let leader_key = format!("<leader>{}", key_str);
-                if let Some(action) = self.config.get_action_for_key(&leader_key) {
-                    if self.config.is_key_valid_for_tab(&leader_key, current_tab) {
-                        self.execute_action(&action).await;
-                    }
-                }
-                self.input_state = InputState::Normal;
-                return;
-            } else {
-                // Try to complete the sequence with the current key
-                let complete_key = format!("{}{}", seq, key_str);
-                if let Some(action) = self.config.get_action_for_key(&complete_key) {
-                    if self.config.is_key_valid_for_tab(&complete_key, current_tab) {
-                        self.execute_action(&action).await;
-                    }
-                }
-                self.input_state = InputState::Normal;
-                return;
+    /// Select a cloud by name in the Clouds tab; reports an error if it doesn't exist
+    fn select_cloud(&mut self, name: &str) -> bool {
+        // A cloud hidden by the tag filter is shown again
+        if !self.clouds_state.clouds.iter().any(|c| c.name == name)
+            && self.clouds_state.all_clouds.iter().any(|c| c.name == name)
+        {
+            self.clouds_state.clear_tag_filter();
+        }
+        match self.clouds_state.clouds.iter().position(|c| c.name == name) {
+            Some(index) => {
+                self.clouds_state.selected_cloud_index = index;
+                self.clouds_state.clouds_list_state.select(Some(index));
+                true
+            }
+            None => {
+                self.command_line
+                    .set_message(format!("No cloud named '{}'", name), true);
+                false
             }
         }
+    }
 
-        // Check if this key could start a multi-key sequence
-        // Look for any keybinding that starts with this key
-        // Skip special keys that are complete by themselves (like <Up>, <Down>, <Enter>)
-        // but allow multi-key special keys (like <Ctrl>c, <Alt>f, etc.)
-        let potential_sequences: Vec<String> = self
-            .config
-            .actions
-            .values()
-            .flat_map(|action| &action.keys)
-            .filter(|k| {
-                k.starts_with(&key_str)
-                    && k.len() > 1
-                    && // Skip if it's a single special key (starts with <, ends with >, and doesn't contain another <)
-                    !(k.starts_with('<') && k.ends_with('>') && !k[1..k.len() - 1].contains('<'))
-            })
-            .cloned()
-            .collect();
+    /// Notify about the outcome of starting or stopping the selected cloud
+    fn report_cloud_command(&mut self, name: &str, verb: &str) {
+        match self.clouds_state.cloud_start_error.clone() {
+            Some(error) => self.notify(Severity::Error, error.trim_start_matches("❌ ")),
+            None => self.notify(Severity::Success, format!("{} cloud '{}'", verb, name)),
+        }
+    }
 
-        if !potential_sequences.is_empty() {
-            self.input_state = InputState::KeySequence(key_str.clone(), std::time::Instant::now());
-            self.add_debug(&format!(
-                "Started '{}' sequence, potential: {:?}",
-                key_str, potential_sequences
-            ));
-            return;
+    // ========== Scripting ==========
+
+    /// Run the action named `action` as its key would, or with `args`, the command of the
+    /// command line named `action`, e.g. `apply_action("start", &["family"])`. What the UI
+    /// would show as an error, in the footer or as a banner, is returned as the error.
+    pub async fn apply_action(&mut self, action: &str, args: &[&str]) -> Result<(), String> {
+        use crate::utils::command_line::{parse_command, Command};
+
+        let line = std::iter::once(action)
+            .chain(args.iter().copied())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let action_names: Vec<String> = self.config.actions.keys().cloned().collect();
+        let command = parse_command(&line, &action_names)?;
+
+        let started = std::time::Instant::now();
+        self.command_line.message = None;
+        match command {
+            Command::Action(action) => self.execute_action(&action).await,
+            command => self.run_command(command).await,
         }
 
-        // Check if key is valid for current tab and process it
-        if let Some(action) = self.config.get_action_for_key(&key_str) {
-            if self.config.is_key_valid_for_tab(&key_str, current_tab) {
-                self.execute_action(&action).await;
-            } else {
-                self.add_debug(&format!(
-                    "Key '{}' not valid for tab '{}'",
-                    key_str, current_tab
-                ));
+        if self.command_line.message_is_error {
+            if let Some(message) = self.command_line.message.clone() {
+                return Err(message);
+            }
+        }
+        match self.notifications.history.iter().find(|notification| {
+            notification.severity == Severity::Error && notification.created_at >= started
+        }) {
+            Some(notification) => Err(notification.message.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// Feed the keys of `script` to the app as if they were typed, until one of them quits
+    pub async fn run_script(&mut self, script: &crate::utils::script::Script) {
+        use crate::utils::script::ScriptStep;
+
+        for step in &script.steps {
+            if self.state != AppState::Running {
+                break;
+            }
+            let key = match step {
+                ScriptStep::Key(code, modifiers) => Some((*code, *modifiers)),
+                ScriptStep::Leader => crate::config::string_to_key(&self.config.leader),
+            };
+            if let Some((code, modifiers)) = key {
+                self.handle_dynamic_key(code, modifiers).await;
             }
-        } else {
-            self.add_debug(&format!("No keybinding found for key '{}'", key_str));
         }
     }
 
@@ -1165,25 +3553,51 @@ impl App {
             "Previous Tab" => self.previous_tab(),
             "Toggle Debug" => self.toggle_debug(),
             "Start/Stop Cloud" => {
-                if !self.clouds_state.clouds.is_empty()
-                    && self.clouds_state.selected_cloud_index < self.clouds_state.clouds.len()
-                    && self.clouds_state.is_cloud_running(
-                        &self.clouds_state.clouds[self.clouds_state.selected_cloud_index].name,
-                    )
-                {
-                    self.clouds_state.stop_server(&mut self.orchestrator).await;
-                } else {
-                    self.clouds_state.start_server(&mut self.orchestrator).await;
+                let selected = self
+                    .clouds_state
+                    .clouds
+                    .get(self.clouds_state.selected_cloud_index)
+                    .map(|cloud| cloud.name.clone());
+                match selected {
+                    Some(name) if self.clouds_state.is_cloud_running(&name) => {
+                        self.request_stop(name).await;
+                    }
+                    Some(name) => {
+                        self.clouds_state.start_server(&mut self.orchestrator).await;
+                        self.report_cloud_command(&name, "Started");
+                    }
+                    None => self.clouds_state.start_server(&mut self.orchestrator).await,
                 }
             }
             "Create New" => {
                 self.start_creating_folder_or_cloud();
             }
             "Delete Folder" => {
-                self.delete_selected_folder();
+                self.request_delete(false).await;
             }
             "Delete Cloud" => {
-                self.delete_selected_cloud();
+                self.request_delete(true).await;
+            }
+            "Undo" => {
+                self.undo_delete();
+            }
+            "Show Jobs" => {
+                self.show_jobs = !self.show_jobs;
+            }
+            "Show Tasks" => {
+                self.show_tasks = !self.show_tasks;
+                self.tasks_selected = 0;
+            }
+            "Show Notifications" => {
+                self.notifications.toggle_history();
+            }
+            "Dismiss Notifications" => {
+                if !self.notifications.dismiss_banners() {
+                    self.add_debug("No notifications to dismiss");
+                }
+            }
+            "Redo" => {
+                self.redo_delete();
             }
             "Set Password" => {
                 self.start_setting_cloud_password();
@@ -1191,6 +3605,24 @@ impl App {
             "Select All Folders" => {
                 self.select_all_folders();
             }
+            "Toggle Folder Details" => {
+                self.folders_state.folder_details = !self.folders_state.folder_details;
+            }
+            "Sort Folders" => {
+                self.folders_state.folder_sort =
+                    FolderSortColumn::next(self.folders_state.folder_sort);
+                match self.folders_state.folder_sort {
+                    Some(_) => self.folders_state.sort_folders(),
+                    // Back to the order of the config
+                    None => self
+                        .folders_state
+                        .reorder_folders(self.orchestrator.get_cloud_folders()),
+                }
+            }
+            "Reverse Folder Sort" => {
+                self.folders_state.folder_sort_reversed = !self.folders_state.folder_sort_reversed;
+                self.folders_state.sort_folders();
+            }
             "Edit" => {
                 self.start_editing();
             }
@@ -1213,6 +3645,85 @@ impl App {
                     self.add_debug("Password creation moved to Clouds tab. Switch to Clouds tab and press 'p'.");
                 }
             }
+            "Show Clients" => {
+                let cloud = self
+                    .clouds_state
+                    .clouds
+                    .get(self.clouds_state.selected_cloud_index)
+                    .map(|cloud| cloud.name.clone());
+                match cloud {
+                    Some(cloud) if self.clouds_state.is_cloud_running(&cloud) => {
+                        self.clients_panel = Some(cloud);
+                        self.clients_selected = 0;
+                    }
+                    Some(cloud) => self.add_debug(&format!("Cloud '{}' is not running", cloud)),
+                    None => self.add_debug("No cloud selected"),
+                }
+            }
+            "Copy Cloud URL" => self.copy_cloud_url(),
+            "Copy Path" => self.copy_path(),
+            "Copy Share Links" => self.copy_share_links(),
+            "Open in Browser" => self.open_cloud_in_browser(),
+            "Open in File Manager" => self.open_folder_in_file_manager(),
+            "Issue Recovery Code" => {
+                let Some(cloud) = self
+                    .clouds_state
+                    .clouds
+                    .get(self.clouds_state.selected_cloud_index)
+                    .map(|cloud| cloud.name.clone())
+                else {
+                    self.add_debug("No cloud selected");
+                    return;
+                };
+                match self.orchestrator.issue_recovery_code(&cloud) {
+                    Ok(code) => self.recovery_code = Some((cloud, code)),
+                    Err(_) => self.add_debug(&format!(
+                        "Cloud '{}' is not running, start it to use a recovery code",
+                        cloud
+                    )),
+                }
+            }
+            "Change Password" => {
+                match self
+                    .clouds_state
+                    .clouds
+                    .get(self.clouds_state.selected_cloud_index)
+                {
+                    Some(cloud) if self.orchestrator.cloud_has_password(&cloud.name) => {
+                        self.clouds_state.password_change.start();
+                    }
+                    Some(cloud) => {
+                        let message = format!(
+                            "Cloud '{}' has no password yet, press 'p' to set one",
+                            cloud.name
+                        );
+                        self.add_debug(&message);
+                    }
+                    None => self.add_debug("No cloud selected"),
+                }
+            }
+            "Filter Clouds By Tag" => {
+                if self.clouds_state.tags().is_empty() {
+                    self.add_debug("No cloud has tags, add them with :tag <cloud> <tag>");
+                    return;
+                }
+                self.clouds_state.cycle_tag_filter();
+                match &self.clouds_state.tag_filter {
+                    Some(tag) => self.add_debug(&format!("Showing clouds tagged '{}'", tag)),
+                    None => self.add_debug("Showing all clouds"),
+                }
+            }
+            "Toggle Error Logs" => self.clouds_state.log_view.toggle_level(LogLevel::Error),
+            "Toggle Warning Logs" => self.clouds_state.log_view.toggle_level(LogLevel::Warning),
+            "Toggle Info Logs" => self.clouds_state.log_view.toggle_level(LogLevel::Info),
+            "Toggle Debug Logs" => self.clouds_state.log_view.toggle_level(LogLevel::Debug),
+            "Search Logs" => self.clouds_state.log_view.search_input = Some(TextInput::default()),
+            "Next Log Match" => self.jump_to_log_match(true),
+            "Previous Log Match" => self.jump_to_log_match(false),
+            "Pause/Follow Logs" => {
+                let log_view = &mut self.clouds_state.log_view;
+                log_view.follow = !log_view.follow;
+            }
             "Cycle Focus Forward" => self.cycle_focus_forward(),
             "Cycle Focus Backward" => self.cycle_focus_backward(),
             "Navigate Up" => {
@@ -1242,29 +3753,131 @@ impl App {
                 }
             }
             "Refresh/Reload" => {
-                // Reload data from orchestrator
+                if self.selected_tab == SelectedTab::Client {
+                    self.client_state.refresh().await;
+                    self.add_debug("Refreshed remote listing");
+                    return;
+                }
+                // Reload data from orchestrator, and scan the folder sizes again
+                self.folders_state.folder_sizes.clear();
+                self.folders_state.sizes_requested.clear();
                 self.load_folders_from_orchestrator();
                 self.add_debug("Refreshed data from orchestrator");
             }
             "Reload TUI Config" => {
-                self.reload_tui_config();
-                self.add_debug("TUI config reloaded successfully");
+                if self.reload_tui_config() {
+                    self.notify(Severity::Info, "TUI config reloaded");
+                }
             }
             "Reload Clouds Config" => {
-                self.reload_clouds_config().await;
-                self.add_debug("Clouds config reloaded successfully");
+                if self.reload_clouds_config().await {
+                    self.notify(Severity::Info, "Clouds config reloaded");
+                }
             }
             "Reload All Configs" => {
-                self.reload_all_configs().await;
-                self.add_debug("All configs reloaded successfully");
+                if self.reload_all_configs().await {
+                    self.notify(Severity::Info, "All configs reloaded");
+                }
+            }
+            "Connect" => {
+                if self.selected_tab == SelectedTab::Client {
+                    self.client_state.start_connecting();
+                }
+            }
+            "Disconnect" => {
+                if self.selected_tab == SelectedTab::Client {
+                    self.client_state.disconnect().await;
+                    self.add_debug("Disconnected from remote CloudHost");
+                }
+            }
+            "Open Entry" => match self.selected_tab {
+                SelectedTab::Client => self.client_state.open_selected().await,
+                SelectedTab::Folders => self.open_file_entry(),
+                _ => {}
+            },
+            "Parent Directory" => match self.selected_tab {
+                SelectedTab::Client => self.client_state.go_up().await,
+                SelectedTab::Folders
+                    if self.folders_state.focused_panel == folders::models::FocusedPanel::Files =>
+                {
+                    self.folders_state.files.go_up();
+                }
+                _ => {}
+            },
+            "Download" => {
+                if self.selected_tab == SelectedTab::Client {
+                    self.client_state.start_download();
+                }
+            }
+            "Upload" => {
+                if self.selected_tab == SelectedTab::Client {
+                    self.client_state.start_upload();
+                }
+            }
+            "Command Mode" => self.command_line.open(),
+            "Export Clouds Config" => self
+                .command_line
+                .open_with("export ~/cloudhost-clouds-export.toml"),
+            "Import Clouds Config" => self.command_line.open_with("import "),
+            "Send Test Email" => self.send_test_emails(),
+            "Run Diagnostics" => self.run_diagnostics(),
+            "Switch Profile" => {
+                self.profile_picker = Some(crate::utils::profile_picker::ProfilePicker::new())
+            }
+            "Test Reachability" => self.test_reachability(),
+            "Next Theme" => self.next_theme(),
+            "Grow Panel" => self.resize_panel(true),
+            "Shrink Panel" => self.resize_panel(false),
+            "Fuzzy Finder" => self.open_fuzzy_finder(),
+            "Show Help" => {
+                self.show_help = true;
+                self.help_scroll = 0;
             }
             "Execute Action" => {
                 // Handle Enter key in settings tab
                 if self.selected_tab == SelectedTab::Settings {
-                    self.settings_state.handle_enter();
+                    if self.settings_state.focused_panel
+                        == settings::models::SettingsFocusedPanel::Keybindings
+                    {
+                        self.settings_state.start_capture(&self.config, false);
+                    } else if let Some(field) = self.settings_state.selected_default_field() {
+                        self.settings_state.default_editor =
+                            Some(crate::utils::server_defaults::DefaultEditor::new(
+                                field,
+                                &self.orchestrator.clouds_config.defaults,
+                            ));
+                    } else if let Some(action) = self.settings_state.handle_enter() {
+                        Box::pin(self.execute_action(action)).await;
+                    }
                     self.add_debug("Executed settings action");
                 }
             }
+            "Add Keybinding" => {
+                if self.selected_tab == SelectedTab::Settings
+                    && self.settings_state.focused_panel
+                        == settings::models::SettingsFocusedPanel::Keybindings
+                {
+                    self.settings_state.start_capture(&self.config, true);
+                }
+            }
+            "Reset Keybinding" => {
+                if self.selected_tab == SelectedTab::Settings
+                    && self.settings_state.focused_panel
+                        == settings::models::SettingsFocusedPanel::Keybindings
+                {
+                    if let Some(action) = self.settings_state.selected_action(&self.config) {
+                        if self.config.reset_action(&action) {
+                            self.save_keybindings(format!(
+                                "Restored default keys for '{}'",
+                                action
+                            ));
+                        } else {
+                            self.settings_state.keybinding_error =
+                                Some(format!("'{}' has no default keys", action));
+                        }
+                    }
+                }
+            }
             _ => {
                 self.add_debug(&format!("Unknown action: {}", action));
             }
@@ -1276,15 +3889,9 @@ impl App {
         if let InputState::KeySequence(ref seq, start_time) = self.input_state {
             if start_time.elapsed().as_millis() > KEY_SEQUENCE_TIMEOUT_MS as u128 {
                 // Timeout reached, execute the single key if it exists
-                if let Some(action) = self.config.get_action_for_key(seq) {
-                    let current_tab = match self.selected_tab {
-                        SelectedTab::Clouds => "clouds",
-                        SelectedTab::Folders => "folders",
-                        SelectedTab::Settings => "settings",
-                    };
-                    if self.config.is_key_valid_for_tab(seq, current_tab) {
-                        self.execute_action(&action).await;
-                    }
+                let current_tab = self.selected_tab.config_name();
+                if let Some(action) = self.config.get_action_for_key_in_tab(seq, current_tab) {
+                    self.execute_action(&action).await;
                 }
                 self.input_state = InputState::Normal;
             }
@@ -1292,10 +3899,27 @@ impl App {
     }
 }
 
+/// First few changes of a config diff, for a one line notification
+fn summarize_changes(changes: &[String]) -> String {
+    const SHOWN: usize = 4;
+    let mut summary = changes
+        .iter()
+        .take(SHOWN)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if changes.len() > SHOWN {
+        summary.push_str(&format!(" and {} more", changes.len() - SHOWN));
+    }
+    summary
+}
+
 impl Widget for &mut App {
     fn render(self, area: Rect, buf: &mut Buffer) {
         use ratatui::layout::{Constraint, Layout};
 
+        // Room for a profile name after the title
+        let title_width = (title().chars().count() as u16 + 1).max(20);
         if self.debug_mode {
             // Debug mode: show debug panel
             let vertical = Layout::vertical([
@@ -1306,12 +3930,13 @@ impl Widget for &mut App {
             ]);
             let [header_area, inner_area, footer_area, debug_area] = vertical.areas(area);
 
-            let horizontal = Layout::horizontal([Constraint::Min(0), Constraint::Length(20)]);
+            let horizontal =
+                Layout::horizontal([Constraint::Min(0), Constraint::Length(title_width)]);
             let [tabs_area, title_area] = horizontal.areas(header_area);
 
             render_title(title_area, buf);
             self.render_tabs(tabs_area, buf);
-            self.selected_tab.render_tab(self, inner_area, buf);
+            self.render_tab_with_notifications(inner_area, buf);
             self.render_footer(footer_area, buf);
             self.render_debug_panel(debug_area, buf);
         } else {
@@ -1323,23 +3948,184 @@ impl Widget for &mut App {
             ]);
             let [header_area, inner_area, footer_area] = vertical.areas(area);
 
-            let horizontal = Layout::horizontal([Constraint::Min(0), Constraint::Length(20)]);
+            let horizontal =
+                Layout::horizontal([Constraint::Min(0), Constraint::Length(title_width)]);
             let [tabs_area, title_area] = horizontal.areas(header_area);
 
             render_title(title_area, buf);
             self.render_tabs(tabs_area, buf);
-            self.selected_tab.render_tab(self, inner_area, buf);
+            self.render_tab_with_notifications(inner_area, buf);
             self.render_footer(footer_area, buf);
         }
+
+        if self.show_help {
+            crate::components::help_overlay::render_help_overlay(
+                &self.config,
+                self.selected_tab.config_name(),
+                &self.selected_tab.to_string(),
+                self.help_scroll,
+                area,
+                buf,
+            );
+        }
+
+        if self.show_jobs {
+            let jobs: Vec<_> = self.jobs.jobs.iter().collect();
+            crate::components::jobs_panel::render_jobs_panel(&jobs, true, area, buf);
+        } else {
+            let recent = self.jobs.recent();
+            if !recent.is_empty() {
+                crate::components::jobs_panel::render_jobs_panel(&recent, false, area, buf);
+            }
+        }
+
+        if let Some(cloud) = &self.webhook_history {
+            // Read every frame, so retries show up as they happen
+            let deliveries = self
+                .orchestrator
+                .webhook_deliveries(cloud)
+                .unwrap_or_default();
+            crate::components::webhook_history::render_webhook_history(
+                cloud,
+                &deliveries,
+                self.webhook_history_scroll,
+                area,
+                buf,
+            );
+        }
+
+        if let Some((cloud, code)) = &self.recovery_code {
+            let url = self.orchestrator.get_cloud_server_url(cloud);
+            crate::components::recovery_code::render_recovery_code(
+                cloud,
+                code,
+                url.as_deref(),
+                area,
+                buf,
+            );
+        }
+
+        if let Some(diagnostics) = &self.diagnostics {
+            crate::components::diagnostics_panel::render_diagnostics_panel(
+                &self.theme(),
+                diagnostics,
+                area,
+                buf,
+            );
+        }
+
+        if let Some(picker) = &self.profile_picker {
+            crate::components::profile_picker_modal::render_profile_picker(
+                &self.theme(),
+                picker,
+                self.orchestrator.get_running_clouds().len(),
+                area,
+                buf,
+            );
+        }
+
+        if let Some(fallback) = &self.copy_fallback {
+            crate::components::copy_fallback::render_copy_fallback(
+                &self.theme(),
+                fallback,
+                area,
+                buf,
+            );
+        }
+
+        if let Some(cloud) = &self.clients_panel {
+            // Read every frame, so transfers show up as they start and end
+            let clients = self.orchestrator.cloud_clients(cloud);
+            self.clients_selected = self.clients_selected.min(clients.len().saturating_sub(1));
+            crate::components::clients_panel::render_clients_panel(
+                cloud,
+                &clients,
+                self.clients_selected,
+                area,
+                buf,
+            );
+        }
+
+        if self.show_tasks {
+            // Read every frame, so runs show up as they finish
+            let tasks = self.orchestrator.task_statuses();
+            crate::components::tasks_panel::render_tasks_panel(
+                &tasks,
+                self.tasks_selected,
+                area,
+                buf,
+            );
+        }
+
+        if self.notifications.show_history {
+            crate::components::notifications::render_notification_history(
+                &self.notifications,
+                area,
+                buf,
+            );
+        }
+
+        if self.show_config_conflict {
+            crate::components::config_conflict_modal::render_config_conflict_modal(area, buf);
+        }
+
+        if let Some(ref prompt) = self.passphrase_prompt {
+            crate::components::passphrase_modal::render_passphrase_modal(prompt, area, buf);
+        }
+
+        if let Some(ref editor) = self.settings_state.default_editor {
+            crate::components::default_editor_modal::render_default_editor_modal(
+                &self.theme(),
+                editor,
+                area,
+                buf,
+            );
+        }
+
+        if let Some(ref confirmation) = self.confirmation {
+            crate::components::confirm_modal::render_confirm_modal(
+                &self.theme(),
+                confirmation,
+                area,
+                buf,
+            );
+        }
+
+        if self.fuzzy_finder.active {
+            crate::components::fuzzy_finder::render_fuzzy_finder(&self.fuzzy_finder, area, buf);
+        }
     }
 }
 
 impl App {
+    /// Error banners above the tab content, toasts floating in its top right corner
+    fn render_tab_with_notifications(&mut self, area: Rect, buf: &mut Buffer) {
+        use ratatui::layout::{Constraint, Layout};
+
+        // Never let banners take more than a third of the screen
+        let banner_rows = (self.notifications.banners().len() as u16).min(area.height / 3);
+        let [banner_area, tab_area] =
+            Layout::vertical([Constraint::Length(banner_rows), Constraint::Min(0)]).areas(area);
+
+        self.selected_tab.render_tab(self, tab_area, buf);
+        crate::components::notifications::render_banners(
+            &self.notifications.banners(),
+            banner_area,
+            buf,
+        );
+        crate::components::notifications::render_toasts(
+            &self.notifications.toasts(),
+            tab_area,
+            buf,
+        );
+    }
+
     pub fn render_tabs(&self, area: Rect, buf: &mut Buffer) {
-        let titles = SelectedTab::iter().map(SelectedTab::title);
+        let theme = self.theme();
+        let titles = SelectedTab::iter().map(|tab| tab.title(&theme));
         let highlight_style = (
             ratatui::style::Color::default(),
-            self.selected_tab.palette().c700,
+            theme.tab_accent(self.selected_tab),
         );
         let selected_tab_index = self.selected_tab as usize;
         Tabs::new(titles)
@@ -1351,9 +4137,11 @@ impl App {
     }
 
     pub fn render_debug_panel(&self, area: Rect, buf: &mut Buffer) {
-        use ratatui::style::{Color, Style};
+        use ratatui::style::Style;
         use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph};
 
+        let theme = self.theme();
+
         // Create a scrollable area for server logs
         let cloud_logs_area = Rect {
             x: area.x,
@@ -1366,14 +4154,14 @@ impl App {
             x: area.x,
             y: area.y + area.height.saturating_sub(3),
             width: area.width,
-            height: 3,
+            height: area.height.min(3),
         };
 
         // Render header with log count
         let header_text = format!("TUI Debug ({} messages)", self.debug_info.len());
         let header = Paragraph::new(header_text)
             .block(Block::default().borders(Borders::ALL).title("Debug Panel"))
-            .style(Style::default().fg(Color::Cyan));
+            .style(Style::default().fg(theme.info));
         header.render(header_area, buf);
 
         // Only show TUI debug messages in debug panel (server logs are in server tab)
@@ -1382,7 +4170,8 @@ impl App {
         // Add TUI debug messages
         for info in &self.debug_info {
             all_items.push(
-                ListItem::new(format!("[TUI] {}", info)).style(Style::default().fg(Color::Magenta)),
+                ListItem::new(format!("[TUI] {}", info))
+                    .style(Style::default().fg(theme.log_debug)),
             );
         }
 
@@ -1393,21 +4182,46 @@ impl App {
     }
 }
 
-pub fn render_title(area: Rect, buf: &mut Buffer) {
-    let title = if cloudhost_server::config_paths::is_dev_mode() {
-        "CloudHost (dev)"
+/// The mode, and the profile unless it is the default one
+fn title() -> String {
+    let mut title = if cloudhost_server::config_paths::is_dev_mode() {
+        "CloudHost (dev)".to_string()
     } else {
-        "CloudHost"
+        "CloudHost".to_string()
     };
-    title.bold().render(area, buf);
+    if let Some(profile) = cloudhost_server::config_paths::current_profile() {
+        title.push_str(&format!(" · {}", profile));
+    }
+    title
+}
+
+pub fn render_title(area: Rect, buf: &mut Buffer) {
+    title().bold().render(area, buf);
 }
 
 impl App {
     pub fn render_footer(&self, area: Rect, buf: &mut Buffer) {
+        if self.command_line.active {
+            crate::components::command_line::render_command_line(&self.command_line, area, buf);
+            return;
+        }
+        if let Some(ref message) = self.command_line.message {
+            let theme = self.theme();
+            let color = if self.command_line.message_is_error {
+                theme.error
+            } else {
+                theme.success
+            };
+            Line::raw(message.as_str())
+                .style(ratatui::style::Style::default().fg(color))
+                .render(area, buf);
+            return;
+        }
         let footer_text = match self.selected_tab {
-            SelectedTab::Clouds => "j/k or ↑/↓ to navigate | s to start/stop server | gt/gT to switch tabs | q to quit",
-            SelectedTab::Folders => "j/k or ↑/↓ to navigate | Tab to switch panels | gt/gT to switch tabs | q to quit",
-            SelectedTab::Settings => "j/k or ↑/↓ to navigate | Enter to execute action | gt/gT to switch tabs | q to quit",
+            SelectedTab::Clouds => "j/k or ↑/↓ to navigate | s to start/stop server | / search logs | f pause logs | gt/gT to switch tabs | ? for help | q to quit",
+            SelectedTab::Folders => "j/k or ↑/↓ to navigate | Tab to switch panels | u to undo delete | gt/gT to switch tabs | ? for help | q to quit",
+            SelectedTab::Client => "j/k to navigate | Enter/h to open/go up | c to connect | d/u to download/upload | ? for help | q to quit",
+            SelectedTab::Settings => "j/k or ↑/↓ to navigate | Tab to switch panels | Enter to execute or rebind | gt/gT to switch tabs | ? for help | q to quit",
         };
         Line::raw(footer_text).centered().render(area, buf);
     }
@@ -0,0 +1,393 @@
+use crate::events::EventSender;
+use crate::tabs::focus::TabFocus;
+use crate::utils::jobs::{format_bytes, JobKind, JobList};
+use crate::utils::path_input::expand_home;
+use crate::utils::text_input::TextInput;
+use cloudhost_client::{CloudHostClient, Entry};
+use ratatui::crossterm::event::KeyCode;
+use ratatui::widgets::{ListState, ScrollbarState};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConnectField {
+    #[default]
+    Url,
+    Password,
+}
+
+/// Which text input (if any) is currently open on the Client tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClientInputMode {
+    #[default]
+    None,
+    Connect(ConnectField),
+    DownloadPath,
+    UploadPath,
+}
+
+/// One row of the remote browser: either a cloud folder (at the root) or an entry inside one
+#[derive(Debug, Clone)]
+pub enum RemoteItem {
+    CloudFolder(String),
+    Entry(Entry),
+}
+
+impl RemoteItem {
+    pub fn name(&self) -> &str {
+        match self {
+            RemoteItem::CloudFolder(name) => name,
+            RemoteItem::Entry(entry) => &entry.name,
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        match self {
+            RemoteItem::CloudFolder(_) => true,
+            RemoteItem::Entry(entry) => entry.is_dir(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ClientState {
+    pub client: Option<CloudHostClient>,
+    pub remote_cloud_name: Option<String>,
+    pub input_mode: ClientInputMode,
+    pub url_input: TextInput,
+    pub password_input: TextInput,
+    pub local_path_input: TextInput,
+    /// Cloud folder being browsed; `None` means the list of cloud folders is shown
+    pub cloud_folder: Option<String>,
+    /// Directory inside the cloud folder, relative to its root
+    pub path: String,
+    pub items: Vec<RemoteItem>,
+    pub selected_index: usize,
+    pub status_message: Option<String>,
+    pub error: Option<String>,
+    pub list_state: ListState,
+    pub scroll_state: ScrollbarState,
+}
+
+impl ClientState {
+    pub fn new() -> Self {
+        Self {
+            url_input: TextInput::new("http://localhost:3000"),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.client.is_some()
+    }
+
+    pub fn selected_item(&self) -> Option<&RemoteItem> {
+        self.items.get(self.selected_index)
+    }
+
+    /// Human readable location, e.g. `photos/2024`
+    pub fn location(&self) -> String {
+        match &self.cloud_folder {
+            None => "/".to_string(),
+            Some(folder) if self.path.is_empty() => format!("/{}", folder),
+            Some(folder) => format!("/{}/{}", folder, self.path),
+        }
+    }
+
+    pub fn start_connecting(&mut self) {
+        self.input_mode = ClientInputMode::Connect(ConnectField::Url);
+        self.password_input.clear();
+        self.error = None;
+    }
+
+    pub fn start_download(&mut self) {
+        match self.selected_item() {
+            Some(RemoteItem::Entry(entry)) if !entry.is_dir() => {
+                let target = dirs::download_dir()
+                    .unwrap_or_else(|| PathBuf::from("."))
+                    .join(&entry.name);
+                self.local_path_input
+                    .set(target.to_string_lossy().to_string());
+                self.input_mode = ClientInputMode::DownloadPath;
+                self.error = None;
+            }
+            _ => self.error = Some("Select a file to download".to_string()),
+        }
+    }
+
+    pub fn start_upload(&mut self) {
+        if self.cloud_folder.is_none() {
+            self.error = Some("Open a cloud folder before uploading".to_string());
+            return;
+        }
+        self.local_path_input.clear();
+        self.input_mode = ClientInputMode::UploadPath;
+        self.error = None;
+    }
+
+    pub fn cancel_input(&mut self) {
+        self.input_mode = ClientInputMode::None;
+        self.password_input.clear();
+    }
+
+    /// The text buffer behind the currently open input
+    pub fn active_input_mut(&mut self) -> Option<&mut TextInput> {
+        match self.input_mode {
+            ClientInputMode::None => None,
+            ClientInputMode::Connect(ConnectField::Url) => Some(&mut self.url_input),
+            ClientInputMode::Connect(ConnectField::Password) => Some(&mut self.password_input),
+            ClientInputMode::DownloadPath | ClientInputMode::UploadPath => {
+                Some(&mut self.local_path_input)
+            }
+        }
+    }
+
+    fn set_items(&mut self, items: Vec<RemoteItem>) {
+        self.items = items;
+        self.selected_index = 0;
+        self.list_state
+            .select(if self.items.is_empty() { None } else { Some(0) });
+    }
+
+    // ========== Remote operations ==========
+
+    /// Log in to the URL/password from the connect modal and list the cloud folders
+    pub async fn connect(&mut self) {
+        let client = match CloudHostClient::new(&self.url_input) {
+            Ok(client) => client,
+            Err(e) => {
+                self.error = Some(e.to_string());
+                return;
+            }
+        };
+
+        if let Err(e) = client.login(&self.password_input).await {
+            self.error = Some(e.to_string());
+            return;
+        }
+        self.password_input.clear();
+        self.input_mode = ClientInputMode::None;
+
+        match client.status().await {
+            Ok(status) => {
+                self.remote_cloud_name = Some(status.cloud.name.clone());
+                self.status_message = Some(format!(
+                    "✅ Connected to '{}' at {}",
+                    status.cloud.name,
+                    client.base_url()
+                ));
+                self.error = None;
+                self.client = Some(client);
+                self.cloud_folder = None;
+                self.path.clear();
+                self.set_items(
+                    status
+                        .cloud
+                        .cloud_folders
+                        .into_iter()
+                        .map(|folder| RemoteItem::CloudFolder(folder.name))
+                        .collect(),
+                );
+            }
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+
+    pub async fn disconnect(&mut self) {
+        if let Some(client) = self.client.take() {
+            client.logout().await;
+        }
+        self.remote_cloud_name = None;
+        self.cloud_folder = None;
+        self.path.clear();
+        self.set_items(Vec::new());
+        self.status_message = Some("Disconnected".to_string());
+    }
+
+    /// Re-fetch the listing of the current location
+    pub async fn refresh(&mut self) {
+        let Some(client) = self.client.clone() else {
+            return;
+        };
+
+        let result = match &self.cloud_folder {
+            None => client.cloud_folders().await.map(|folders| {
+                folders
+                    .into_iter()
+                    .map(RemoteItem::CloudFolder)
+                    .collect::<Vec<_>>()
+            }),
+            Some(folder) => client
+                .list_dir(folder, &self.path)
+                .await
+                .map(|entries| entries.into_iter().map(RemoteItem::Entry).collect()),
+        };
+
+        match result {
+            Ok(items) => {
+                self.set_items(items);
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+
+    /// Enter the selected cloud folder or directory
+    pub async fn open_selected(&mut self) {
+        match self.selected_item().cloned() {
+            Some(RemoteItem::CloudFolder(name)) => {
+                self.cloud_folder = Some(name);
+                self.path.clear();
+                self.refresh().await;
+            }
+            Some(RemoteItem::Entry(entry)) if entry.is_dir() => {
+                self.path = entry.path;
+                self.refresh().await;
+            }
+            Some(RemoteItem::Entry(_)) => self.start_download(),
+            None => {}
+        }
+    }
+
+    /// Go up one directory (or back to the cloud folder list)
+    pub async fn go_up(&mut self) {
+        if self.cloud_folder.is_none() {
+            return;
+        }
+        if self.path.is_empty() {
+            self.cloud_folder = None;
+        } else {
+            self.path = match self.path.rfind('/') {
+                Some(pos) => self.path[..pos].to_string(),
+                None => String::new(),
+            };
+        }
+        self.refresh().await;
+    }
+
+    /// Download the selected file to the path typed in the download modal
+    /// Download the selected file as a background job
+    pub fn download_selected(&mut self, jobs: &mut JobList, events: &EventSender) {
+        let (Some(client), Some(folder), Some(RemoteItem::Entry(entry))) = (
+            self.client.clone(),
+            self.cloud_folder.clone(),
+            self.selected_item().cloned(),
+        ) else {
+            return;
+        };
+
+        let destination = expand_home(self.local_path_input.trim());
+        self.status_message = Some(format!("⏳ Downloading '{}'...", entry.name));
+        self.error = None;
+        self.input_mode = ClientInputMode::None;
+
+        let label = format!("Download {}", entry.name);
+        jobs.spawn(JobKind::Download, label, events, |reporter| async move {
+            client
+                .download_to_file_with_progress(
+                    &folder,
+                    &entry.path,
+                    &destination,
+                    |done, total| reporter.progress(done, total),
+                )
+                .await
+                .map(|bytes| {
+                    format!(
+                        "⬇️  Downloaded '{}' ({}) to {}",
+                        entry.name,
+                        format_bytes(bytes),
+                        destination.display()
+                    )
+                })
+                .map_err(|e| e.to_string())
+        });
+    }
+
+    /// Upload a local file into the current directory as a background job
+    pub fn upload_local_file(&mut self, jobs: &mut JobList, events: &EventSender) {
+        let (Some(client), Some(folder)) = (self.client.clone(), self.cloud_folder.clone()) else {
+            return;
+        };
+
+        let source = expand_home(self.local_path_input.trim());
+        if !source.is_file() {
+            self.error = Some(format!("'{}' is not a file", source.display()));
+            return;
+        }
+
+        self.status_message = Some(format!("⏳ Uploading {}...", source.display()));
+        self.error = None;
+        self.input_mode = ClientInputMode::None;
+
+        let label = format!(
+            "Upload {}",
+            source
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        );
+        let path = self.path.clone();
+        jobs.spawn(JobKind::Upload, label, events, |reporter| async move {
+            client
+                .upload_file_with_progress(
+                    &folder,
+                    &path,
+                    &source,
+                    &cloudhost_client::UploadOptions::default(),
+                    move |sent, total| reporter.progress(sent, Some(total)),
+                )
+                .await
+                .map(|response| format!("⬆️  {}", response.message))
+                .map_err(|e| e.to_string())
+        });
+    }
+
+    /// Show the result of a background download or upload
+    pub async fn finish_job(&mut self, kind: JobKind, result: Result<String, String>) {
+        match result {
+            Ok(message) => {
+                self.status_message = Some(message);
+                self.error = None;
+                if kind == JobKind::Upload {
+                    self.refresh().await;
+                }
+            }
+            Err(e) => {
+                self.status_message = None;
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+impl TabFocus for ClientState {
+    fn get_focused_element(&self) -> String {
+        "RemoteBrowser".to_string()
+    }
+
+    fn cycle_focus_forward(&mut self) {
+        // Single panel, nothing to cycle
+    }
+
+    fn cycle_focus_backward(&mut self) {
+        // Single panel, nothing to cycle
+    }
+
+    fn handle_navigation(&mut self, key: KeyCode) -> bool {
+        if self.items.is_empty() {
+            return false;
+        }
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected_index = self.selected_index.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected_index = (self.selected_index + 1).min(self.items.len() - 1);
+            }
+            KeyCode::Char('g') => self.selected_index = 0,
+            KeyCode::Char('G') => self.selected_index = self.items.len() - 1,
+            _ => return false,
+        }
+        self.list_state.select(Some(self.selected_index));
+        true
+    }
+}
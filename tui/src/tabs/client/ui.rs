@@ -0,0 +1,371 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    widgets::{
+        Block, Borders, Clear, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation,
+        StatefulWidget, Widget, Wrap,
+    },
+};
+
+use crate::components::text_input::input_line;
+use crate::models::App;
+use crate::tabs::client::models::{ClientInputMode, ConnectField, RemoteItem};
+use crate::theme::Theme;
+use crate::utils::text_input::TextInput;
+
+pub fn render_client_tab(app: &mut App, area: Rect, buf: &mut Buffer) {
+    let theme = app.theme();
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // Connection status
+            Constraint::Min(0),    // Browser
+            Constraint::Length(3), // Status / error line
+        ])
+        .split(area);
+
+    render_connection_bar(app, rows[0], buf);
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(rows[1]);
+
+    render_remote_list(app, columns[0], buf);
+    render_details_panel(app, columns[1], buf);
+    render_status_line(app, rows[2], buf);
+
+    match app.client_state.input_mode {
+        ClientInputMode::None => {}
+        ClientInputMode::Connect(field) => render_connect_modal(app, field, area, buf),
+        ClientInputMode::DownloadPath => render_path_modal(
+            &theme,
+            "⬇️  Download File",
+            "Save to:",
+            &app.client_state.local_path_input,
+            app.client_state.error.as_deref(),
+            area,
+            buf,
+        ),
+        ClientInputMode::UploadPath => render_path_modal(
+            &theme,
+            "⬆️  Upload File",
+            "Local file:",
+            &app.client_state.local_path_input,
+            app.client_state.error.as_deref(),
+            area,
+            buf,
+        ),
+    }
+}
+
+fn render_connection_bar(app: &App, area: Rect, buf: &mut Buffer) {
+    let theme = app.theme();
+    let state = &app.client_state;
+    let (text, color) = match (&state.client, &state.remote_cloud_name) {
+        (Some(client), Some(cloud)) => (
+            format!(
+                "🟢 Connected to '{}' at {}  |  {}",
+                cloud,
+                client.base_url(),
+                state.location()
+            ),
+            theme.success,
+        ),
+        _ => {
+            let connect_keys = app.config.get_keys_for_action("Connect").join(", ");
+            (
+                format!("🔴 Not connected - press {} to connect", connect_keys),
+                theme.error,
+            )
+        }
+    };
+
+    Paragraph::new(text)
+        .style(Style::default().fg(color))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Remote CloudHost"),
+        )
+        .render(area, buf);
+}
+
+fn render_remote_list(app: &mut App, area: Rect, buf: &mut Buffer) {
+    let theme = app.theme();
+    let state = &mut app.client_state;
+    let title = if state.cloud_folder.is_some() {
+        format!("Files - {}", state.location())
+    } else {
+        "Cloud Folders".to_string()
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(
+            Style::default()
+                .fg(theme.focused_border)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let items: Vec<ListItem> = state
+        .items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let icon = match item {
+                RemoteItem::CloudFolder(_) => "☁️ ",
+                RemoteItem::Entry(entry) if entry.is_dir() => "📁",
+                RemoteItem::Entry(_) => "📄",
+            };
+            let style = if i == state.selected_index {
+                Style::default().fg(theme.selection)
+            } else if item.is_dir() {
+                Style::default().fg(theme.marked)
+            } else {
+                Style::default()
+            };
+            ListItem::new(format!("{} {}", icon, item.name())).style(style)
+        })
+        .collect();
+
+    let item_count = items.len();
+    let list = List::new(items).block(block);
+    StatefulWidget::render(list, area, buf, &mut state.list_state);
+
+    let mut scroll_state = state.scroll_state.content_length(item_count);
+    if let Some(selected) = state.list_state.selected() {
+        scroll_state = scroll_state.position(selected);
+    }
+
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(Some("↑"))
+        .end_symbol(Some("↓"));
+    scrollbar.render(area, buf, &mut scroll_state);
+    state.scroll_state = scroll_state;
+}
+
+fn render_details_panel(app: &App, area: Rect, buf: &mut Buffer) {
+    let state = &app.client_state;
+    let keys = |action: &str| app.config.get_keys_for_action(action).join(", ");
+
+    let mut lines = match state.selected_item() {
+        Some(RemoteItem::CloudFolder(name)) => vec![
+            format!("☁️  Cloud folder: {}", name),
+            String::new(),
+            format!("Open: {}", keys("Open Entry")),
+        ],
+        Some(RemoteItem::Entry(entry)) if entry.is_dir() => vec![
+            format!("📁 Directory: {}", entry.name),
+            format!("Path: {}", entry.path),
+            String::new(),
+            format!("Open: {}", keys("Open Entry")),
+        ],
+        Some(RemoteItem::Entry(entry)) => vec![
+            format!("📄 File: {}", entry.name),
+            format!("Path: {}", entry.path),
+            format!("Size: {} bytes", entry.size),
+            String::new(),
+            format!("Download: {}", keys("Download")),
+        ],
+        None if state.is_connected() => vec!["(empty)".to_string()],
+        None => vec!["Connect to a CloudHost server to browse its files.".to_string()],
+    };
+
+    if state.is_connected() {
+        lines.push(String::new());
+        lines.push(format!("Parent directory: {}", keys("Parent Directory")));
+        lines.push(format!("Upload here: {}", keys("Upload")));
+        lines.push(format!("Disconnect: {}", keys("Disconnect")));
+    }
+
+    Paragraph::new(lines.join("\n"))
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title("Details"))
+        .render(area, buf);
+}
+
+fn render_status_line(app: &App, area: Rect, buf: &mut Buffer) {
+    let theme = app.theme();
+    let state = &app.client_state;
+    let block = Block::default().borders(Borders::ALL).title("Status");
+
+    // Errors from modals are shown inside the modal itself
+    if let (Some(error), ClientInputMode::None) = (&state.error, state.input_mode) {
+        Paragraph::new(format!("❌ {}", error))
+            .style(
+                Style::default()
+                    .fg(theme.error)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .block(block)
+            .render(area, buf);
+    } else {
+        Paragraph::new(state.status_message.clone().unwrap_or_default())
+            .style(Style::default().fg(theme.success))
+            .block(block)
+            .render(area, buf);
+    }
+}
+
+fn centered_modal(area: Rect, width: u16, height: u16) -> Rect {
+    let x = (area.width.saturating_sub(width)) / 2;
+    let y = (area.height.saturating_sub(height)) / 2;
+    Rect::new(
+        area.x + x,
+        area.y + y,
+        width.min(area.width),
+        height.min(area.height),
+    )
+}
+
+fn field_style(theme: &Theme, active: bool) -> Style {
+    if active {
+        Style::default()
+            .fg(theme.success)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(theme.text)
+    }
+}
+
+fn render_modal_frame(theme: &Theme, title: &str, modal_area: Rect, buf: &mut Buffer) {
+    Clear.render(modal_area, buf);
+
+    let title_block = Block::default()
+        .borders(Borders::ALL)
+        .title(title.to_string())
+        .title_alignment(Alignment::Center)
+        .border_style(
+            Style::default()
+                .fg(theme.focused_border)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    Paragraph::new("")
+        .block(title_block)
+        .render(modal_area, buf);
+}
+
+fn render_error_or_help(
+    theme: &Theme,
+    error: Option<&str>,
+    help: &str,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    if let Some(error) = error {
+        Paragraph::new(format!("❌ {}", error))
+            .style(
+                Style::default()
+                    .fg(theme.error)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .render(area, buf);
+    } else {
+        Paragraph::new(help.to_string())
+            .style(Style::default().fg(theme.hint))
+            .alignment(Alignment::Center)
+            .render(area, buf);
+    }
+}
+
+fn render_connect_modal(app: &App, field: ConnectField, area: Rect, buf: &mut Buffer) {
+    let theme = app.theme();
+    let modal_area = centered_modal(area, 64, 14);
+    render_modal_frame(&theme, "🌐 Connect to CloudHost", modal_area, buf);
+
+    let modal_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(2), // Instructions
+            Constraint::Length(2), // URL field
+            Constraint::Length(2), // Password field
+            Constraint::Min(2),    // Error/Help
+        ])
+        .split(modal_area);
+
+    Paragraph::new("Tab to switch fields, Enter to submit, Esc to cancel")
+        .style(Style::default().fg(theme.info))
+        .alignment(Alignment::Center)
+        .render(modal_chunks[0], buf);
+
+    Paragraph::new(input_line(
+        "URL: ",
+        &app.client_state.url_input,
+        field == ConnectField::Url,
+        None,
+        modal_chunks[1].width,
+    ))
+    .style(field_style(&theme, field == ConnectField::Url))
+    .render(modal_chunks[1], buf);
+
+    Paragraph::new(input_line(
+        "Password: ",
+        &app.client_state.password_input,
+        field == ConnectField::Password,
+        Some('*'),
+        modal_chunks[2].width,
+    ))
+    .style(field_style(&theme, field == ConnectField::Password))
+    .render(modal_chunks[2], buf);
+
+    render_error_or_help(
+        &theme,
+        app.client_state.error.as_deref(),
+        "Enter the server URL and the cloud password",
+        modal_chunks[3],
+        buf,
+    );
+}
+
+fn render_path_modal(
+    theme: &Theme,
+    title: &str,
+    label: &str,
+    value: &TextInput,
+    error: Option<&str>,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let modal_area = centered_modal(area, 72, 10);
+    render_modal_frame(theme, title, modal_area, buf);
+
+    let modal_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(2), // Instructions
+            Constraint::Length(2), // Path field
+            Constraint::Min(2),    // Error/Help
+        ])
+        .split(modal_area);
+
+    Paragraph::new("Enter to confirm, Esc to cancel")
+        .style(Style::default().fg(theme.info))
+        .alignment(Alignment::Center)
+        .render(modal_chunks[0], buf);
+
+    Paragraph::new(input_line(
+        &format!("{} ", label),
+        value,
+        true,
+        None,
+        modal_chunks[1].width,
+    ))
+    .style(field_style(theme, true))
+    .render(modal_chunks[1], buf);
+
+    render_error_or_help(
+        theme,
+        error,
+        "~ expands to your home directory",
+        modal_chunks[2],
+        buf,
+    );
+}
@@ -6,44 +6,192 @@ pub enum CloudFocusedPanel {
     CloudLogs,
 }
 use crate::tabs::focus::TabFocus;
-use crate::utils::password::PasswordCreationState;
+use crate::utils::password::{ChangePasswordState, PasswordCreationState};
+use crate::utils::text_input::TextInput;
+use cloudhost_server::doctor::Check;
+use cloudhost_server::stats::StatsSnapshot;
 use cloudhost_server::{Cloud, Orchestrator};
-use ratatui::crossterm::event::KeyCode;
+use cloudhost_shared::debug_stream::{DebugMessage, LogLevel};
+use ratatui::crossterm::event::{KeyCode, KeyModifiers};
 use ratatui::widgets::{ListState, ScrollbarState};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+
+/// Level filter, search and follow state of the Cloud Logs panel
+pub struct LogView {
+    pub hidden_levels: Vec<LogLevel>,
+    /// Query being typed after `/`
+    pub search_input: Option<TextInput>,
+    /// Confirmed query, highlighted and jumped between with n/N
+    pub search: Option<String>,
+    /// Keep the newest message selected as logs arrive; off while scrolling back
+    pub follow: bool,
+}
+
+impl Default for LogView {
+    fn default() -> Self {
+        Self {
+            hidden_levels: Vec::new(),
+            search_input: None,
+            search: None,
+            follow: true,
+        }
+    }
+}
+
+impl LogView {
+    pub fn toggle_level(&mut self, level: LogLevel) {
+        if let Some(index) = self.hidden_levels.iter().position(|l| *l == level) {
+            self.hidden_levels.remove(index);
+        } else {
+            self.hidden_levels.push(level);
+        }
+    }
+
+    pub fn shows_level(&self, level: &LogLevel) -> bool {
+        !self.hidden_levels.contains(level)
+    }
+
+    /// Messages passing the level filter
+    pub fn visible<'a>(&self, logs: &'a [DebugMessage]) -> Vec<&'a DebugMessage> {
+        logs.iter()
+            .filter(|message| self.shows_level(&message.level))
+            .collect()
+    }
+
+    /// Byte ranges of the search matches in a displayed log line
+    pub fn matches(&self, line: &str) -> Vec<Range<usize>> {
+        match &self.search {
+            Some(query) => find_matches(line, query),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// How a log message is shown in the panel (and what search looks at)
+pub fn log_line(message: &DebugMessage) -> String {
+    format!(
+        "[{}] [{}] {}: {}",
+        message.timestamp.format("%H:%M:%S%.3f"),
+        message.level,
+        message.source,
+        message.message
+    )
+}
+
+/// Non-overlapping, ASCII case-insensitive occurrences of `query`
+pub fn find_matches(text: &str, query: &str) -> Vec<Range<usize>> {
+    let (text, query) = (text.as_bytes(), query.as_bytes());
+    let mut matches = Vec::new();
+    if query.is_empty() {
+        return matches;
+    }
+    let mut start = 0;
+    while start + query.len() <= text.len() {
+        if text[start..start + query.len()].eq_ignore_ascii_case(query) {
+            matches.push(start..start + query.len());
+            start += query.len();
+        } else {
+            start += 1;
+        }
+    }
+    matches
+}
+
+/// How many statistics updates (one a second) the activity graphs cover
+const STATS_HISTORY: usize = 60;
+
+/// The recent statistics of a running cloud, oldest first
+#[derive(Debug, Default)]
+pub struct StatsHistory {
+    snapshots: VecDeque<StatsSnapshot>,
+}
+
+impl StatsHistory {
+    pub fn push(&mut self, stats: StatsSnapshot) {
+        if self.snapshots.len() == STATS_HISTORY + 1 {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(stats);
+    }
+
+    pub fn latest(&self) -> Option<&StatsSnapshot> {
+        self.snapshots.back()
+    }
+
+    /// Per-update increase of a counter, oldest first
+    fn rates(&self, counter: impl Fn(&StatsSnapshot) -> u64) -> Vec<u64> {
+        self.snapshots
+            .iter()
+            .zip(self.snapshots.iter().skip(1))
+            .map(|(before, after)| counter(after).saturating_sub(counter(before)))
+            .collect()
+    }
+
+    /// Requests per second
+    pub fn request_rates(&self) -> Vec<u64> {
+        self.rates(|stats| stats.requests)
+    }
+
+    /// Bytes received per second
+    pub fn received_rates(&self) -> Vec<u64> {
+        self.rates(|stats| stats.bytes_received)
+    }
+
+    /// Bytes sent per second
+    pub fn sent_rates(&self) -> Vec<u64> {
+        self.rates(|stats| stats.bytes_sent)
+    }
+}
 
 #[derive(Default)]
 pub struct CloudsState {
+    /// The clouds shown in the list: those with `tag_filter`, grouped by their first tag
     pub clouds: Vec<Cloud>,
+    /// Every cloud of the config
+    pub all_clouds: Vec<Cloud>,
+    pub tag_filter: Option<String>,
     pub selected_cloud_index: usize,
     pub cloud_start_error: Option<String>,
     pub cloud_logs: Vec<String>,
     pub log_scroll_offset: usize,
     pub focused_panel: CloudFocusedPanel,
     pub running_clouds: HashMap<String, u16>,
+    /// Recent statistics of the running clouds
+    pub stats: HashMap<String, StatsHistory>,
+    /// Results of the reachability test by cloud, None while it runs
+    pub reachability: HashMap<String, Option<Check>>,
     pub cloud_logs_list_state: ListState,
     pub cloud_logs_scroll_state: ScrollbarState,
+    pub log_view: LogView,
     pub clouds_list_state: ListState,
     pub clouds_scroll_state: ScrollbarState,
     // Shared password creation state
     pub password_creation: PasswordCreationState,
+    pub password_change: ChangePasswordState,
 }
 
 impl CloudsState {
     pub fn new() -> Self {
         Self {
             clouds: Vec::new(),
+            all_clouds: Vec::new(),
+            tag_filter: None,
             selected_cloud_index: 0,
             cloud_start_error: None,
             cloud_logs: Vec::new(),
             log_scroll_offset: 0,
             focused_panel: CloudFocusedPanel::Clouds,
             running_clouds: HashMap::new(),
+            stats: HashMap::new(),
+            reachability: HashMap::new(),
             cloud_logs_list_state: ListState::default(),
             cloud_logs_scroll_state: ScrollbarState::default(),
+            log_view: LogView::default(),
             clouds_list_state: ListState::default(),
             clouds_scroll_state: ScrollbarState::default(),
             password_creation: PasswordCreationState::new(),
+            password_change: ChangePasswordState::default(),
         }
     }
 }
@@ -56,14 +204,24 @@ impl CloudsState {
         }
 
         let cloud_name = &self.clouds[self.selected_cloud_index].name;
+        let first_port = orchestrator.next_port;
 
         match orchestrator.start_cloud(cloud_name).await {
             Ok(port) => {
                 self.running_clouds.insert(cloud_name.clone(), port);
-                self.cloud_logs.push(format!(
-                    "✅ Started cloud '{}' on port {}",
-                    cloud_name, port
-                ));
+                self.cloud_logs.push(if port == first_port {
+                    format!("✅ Started cloud '{}' on port {}", cloud_name, port)
+                } else {
+                    let taken = if port - 1 == first_port {
+                        format!("port {} was", first_port)
+                    } else {
+                        format!("ports {}-{} were", first_port, port - 1)
+                    };
+                    format!(
+                        "✅ Started cloud '{}' on port {} ({} in use)",
+                        cloud_name, port, taken
+                    )
+                });
                 self.cloud_start_error = None;
             }
             Err(e) => {
@@ -155,14 +313,96 @@ impl CloudsState {
         self.password_creation.clear_password_creation();
     }
 
-    pub fn handle_password_input(&mut self, key: char) {
-        self.password_creation.handle_password_input(key);
+    pub fn handle_password_input(&mut self, key: KeyCode, modifiers: KeyModifiers) {
+        self.password_creation.handle_password_input(key, modifiers);
     }
 
     pub fn is_cloud_running(&self, cloud_name: &str) -> bool {
         self.running_clouds.contains_key(cloud_name)
     }
 
+    pub fn set_clouds(&mut self, clouds: Vec<Cloud>) {
+        self.all_clouds = clouds;
+        if self
+            .tag_filter
+            .as_ref()
+            .is_some_and(|tag| !self.tags().contains(tag))
+        {
+            self.tag_filter = None;
+        }
+        self.apply_tag_filter();
+    }
+
+    /// All tags of the clouds, sorted
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .all_clouds
+            .iter()
+            .flat_map(|cloud| cloud.tags.iter().cloned())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    /// Names of the clouds with `tag`
+    pub fn clouds_tagged(&self, tag: &str) -> Vec<String> {
+        self.all_clouds
+            .iter()
+            .filter(|cloud| cloud.tags.iter().any(|t| t == tag))
+            .map(|cloud| cloud.name.clone())
+            .collect()
+    }
+
+    /// Show only the clouds with the next tag, after the last one show all again
+    pub fn cycle_tag_filter(&mut self) {
+        let tags = self.tags();
+        self.tag_filter = match &self.tag_filter {
+            None => tags.first().cloned(),
+            Some(current) => tags
+                .iter()
+                .position(|tag| tag == current)
+                .and_then(|index| tags.get(index + 1))
+                .cloned(),
+        };
+        self.apply_tag_filter();
+    }
+
+    pub fn clear_tag_filter(&mut self) {
+        self.tag_filter = None;
+        self.apply_tag_filter();
+    }
+
+    /// Rebuild the shown list, keeping the selected cloud selected if it's still there
+    fn apply_tag_filter(&mut self) {
+        let selected = self
+            .clouds
+            .get(self.selected_cloud_index)
+            .map(|cloud| cloud.name.clone());
+        let mut clouds: Vec<Cloud> = self
+            .all_clouds
+            .iter()
+            .filter(|cloud| match &self.tag_filter {
+                Some(tag) => cloud.tags.contains(tag),
+                None => true,
+            })
+            .cloned()
+            .collect();
+        // Untagged clouds go last
+        clouds.sort_by(|a, b| match (a.tags.first(), b.tags.first()) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+        self.clouds = clouds;
+        self.selected_cloud_index = selected
+            .and_then(|name| self.clouds.iter().position(|cloud| cloud.name == name))
+            .unwrap_or(0);
+        self.clouds_list_state
+            .select((!self.clouds.is_empty()).then_some(self.selected_cloud_index));
+    }
+
     pub fn get_cloud_port(&self, cloud_name: &str) -> Option<u16> {
         self.running_clouds.get(cloud_name).copied()
     }
@@ -1,31 +1,201 @@
 use ratatui::{
     buffer::Buffer,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
     widgets::{
-        Block, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation, StatefulWidget, Widget,
+        Block, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation, Sparkline,
+        StatefulWidget, Widget,
     },
 };
 
+use crate::components::change_password_modal::render_change_password_modal;
+use crate::components::cloud_status::cloud_list_spans;
+use crate::components::panel_switcher::render_panel_switcher;
 use crate::components::password_modal::render_password_modal;
+use crate::layout::panel_areas;
 use crate::models::App;
-use crate::tabs::clouds::models::CloudFocusedPanel;
+use crate::tabs::clouds::models::{log_line, CloudFocusedPanel, LogView, StatsHistory};
+use crate::theme::Theme;
+use crate::utils::jobs::format_bytes;
+use cloudhost_server::cloud::CloudStatus;
+use cloudhost_server::stats::StatsSnapshot;
+use cloudhost_shared::debug_stream::LogLevel;
+
+fn format_uptime(secs: u64) -> String {
+    let (days, hours, minutes) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60);
+    if days > 0 {
+        format!("{}d {}h {}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m {}s", minutes, secs % 60)
+    }
+}
 
-pub fn render_servers_tab(app: &mut App, area: Rect, buf: &mut Buffer) {
-    // Ensure we have enough space for borders
-    if area.height < 8 || area.width < 20 {
+/// Requests, transfers, connections and uptime of the selected cloud while it runs
+fn render_stats(stats: Option<&StatsSnapshot>, theme: &Theme, area: Rect, buf: &mut Buffer) {
+    let label = Style::default().fg(theme.dim);
+    let lines = match stats {
+        Some(stats) => [
+            ("Uptime", format_uptime(stats.uptime_secs)),
+            ("Requests", stats.requests.to_string()),
+            ("Connections", stats.active_connections.to_string()),
+            ("Received", format_bytes(stats.bytes_received)),
+            ("Sent", format_bytes(stats.bytes_sent)),
+        ]
+        .into_iter()
+        .map(|(name, value)| {
+            Line::from(vec![
+                Span::styled(format!("{:<12}", name), label),
+                Span::raw(value),
+            ])
+        })
+        .collect(),
+        None => vec![Line::styled("Cloud not running", label)],
+    };
+    Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(ratatui::widgets::Borders::ALL)
+                .title("Stats"),
+        )
+        .render(area, buf);
+}
+
+/// Request rate and throughput of the last minute as sparklines
+fn render_activity(history: Option<&StatsHistory>, theme: &Theme, area: Rect, buf: &mut Buffer) {
+    let block = Block::default()
+        .borders(ratatui::widgets::Borders::ALL)
+        .title("Activity (last minute)");
+    let inner = block.inner(area);
+    block.render(area, buf);
+
+    let label = Style::default().fg(theme.dim);
+    let Some(history) = history else {
+        Paragraph::new(Line::styled("Cloud not running", label)).render(inner, buf);
         return;
+    };
+
+    let graphs = [
+        ("Requests", history.request_rates(), theme.warning, false),
+        ("Sent", history.sent_rates(), theme.info, true),
+        ("Received", history.received_rates(), theme.success, true),
+    ];
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2); 3])
+        .split(inner);
+    for ((name, rates, color, bytes), row) in graphs.into_iter().zip(rows.iter()) {
+        let current = rates.last().copied().unwrap_or(0);
+        let current = if bytes {
+            format!("{}/s", format_bytes(current))
+        } else {
+            format!("{}/s", current)
+        };
+        Paragraph::new(Line::from(vec![
+            Span::styled(format!("{:<12}", name), label),
+            Span::raw(current),
+        ]))
+        .render(Rect { height: 1, ..*row }, buf);
+
+        // The newest values that fit, so the graph scrolls to the left
+        let width = row.width as usize;
+        let shown = &rates[rates.len().saturating_sub(width)..];
+        Sparkline::default()
+            .data(shown)
+            .style(Style::default().fg(color))
+            .render(
+                Rect {
+                    y: row.y + 1,
+                    height: row.height.saturating_sub(1),
+                    ..*row
+                },
+                buf,
+            );
+    }
+}
+
+fn level_color(theme: &Theme, level: &LogLevel) -> Color {
+    match level {
+        LogLevel::Error => theme.log_error,
+        LogLevel::Warning => theme.log_warning,
+        LogLevel::Info => theme.log_info,
+        LogLevel::Debug => theme.log_debug,
     }
+}
 
-    // Create 3-column layout: clouds (15%), server info (35%), server logs (50%)
-    let three_column_chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Length((area.width * 10 / 100).max(20)), // Left: clouds (15% or min 15 chars)
-            Constraint::Length(40), // Middle: server info (35% or min 30 chars)
-            Constraint::Min(30),    // Right: server logs (50% - remaining space)
-        ])
-        .split(area);
+/// A log line with its search matches highlighted
+fn highlighted_line(
+    line: String,
+    log_view: &LogView,
+    color: Color,
+    theme: &Theme,
+) -> Line<'static> {
+    let base = Style::default().fg(color);
+    let matches = log_view.matches(&line);
+    if matches.is_empty() {
+        return Line::styled(line, base);
+    }
+    let highlight = Style::default()
+        .fg(Color::Black)
+        .bg(theme.selection)
+        .add_modifier(Modifier::BOLD);
+    let mut spans = Vec::new();
+    let mut last = 0;
+    for range in matches {
+        spans.push(Span::styled(line[last..range.start].to_string(), base));
+        spans.push(Span::styled(line[range.clone()].to_string(), highlight));
+        last = range.end;
+    }
+    spans.push(Span::styled(line[last..].to_string(), base));
+    Line::from(spans)
+}
+
+/// Panel title with the level toggles, follow state and search
+fn logs_title(log_view: &LogView, focused: bool, match_count: usize) -> String {
+    let levels: Vec<&str> = [
+        (LogLevel::Error, "E"),
+        (LogLevel::Warning, "W"),
+        (LogLevel::Info, "I"),
+        (LogLevel::Debug, "D"),
+    ]
+    .iter()
+    .map(|(level, label)| {
+        if log_view.shows_level(level) {
+            *label
+        } else {
+            "·"
+        }
+    })
+    .collect();
+    let mut title = format!("Cloud Logs [{}]", levels.join(""));
+    if !log_view.follow {
+        title.push_str(" ⏸ paused");
+    }
+    if let Some(input) = &log_view.search_input {
+        // `_` marks the cursor
+        let (before, after) = input.split_at(
+            input
+                .char_indices()
+                .nth(input.cursor())
+                .map_or(input.len(), |(i, _)| i),
+        );
+        title.push_str(&format!(" /{}_{}", before, after));
+    } else if let Some(query) = &log_view.search {
+        title.push_str(&format!(" /{} ({} matches)", query, match_count));
+    }
+    if focused {
+        title.push_str(" (FOCUSED)");
+    }
+    title
+}
+
+fn render_clouds_list(app: &mut App, theme: &Theme, area: Rect, buf: &mut Buffer) {
+    // Nothing fits, and a scrollbar can't be drawn in no width
+    if area.is_empty() {
+        return;
+    }
 
     // Create clouds list items
     let cloud_items: Vec<ListItem> = app
@@ -35,20 +205,36 @@ pub fn render_servers_tab(app: &mut App, area: Rect, buf: &mut Buffer) {
         .enumerate()
         .map(|(i, cloud)| {
             let style = if i == app.clouds_state.selected_cloud_index {
-                Style::default().fg(Color::Yellow)
+                Style::default().fg(theme.selection)
             } else {
                 Style::default()
             };
-            ListItem::new(cloud.name.as_str()).style(style)
+            let mut spans = cloud_list_spans(
+                theme,
+                &cloud.name,
+                &app.orchestrator.cloud_status(&cloud.name),
+                app.orchestrator.get_cloud_port(&cloud.name),
+                !app.orchestrator.unavailable_folders(&cloud.name).is_empty(),
+            );
+            for tag in &cloud.tags {
+                spans.push(Span::styled(
+                    format!(" #{}", tag),
+                    Style::default().fg(theme.dim),
+                ));
+            }
+            ListItem::new(Line::from(spans)).style(style)
         })
         .collect();
 
     // Add focus indicator to title
-    let cloud_title = if app.clouds_state.focused_panel == CloudFocusedPanel::Clouds {
-        "Clouds (FOCUSED)"
+    let mut cloud_title = if app.clouds_state.focused_panel == CloudFocusedPanel::Clouds {
+        "Clouds (FOCUSED)".to_string()
     } else {
-        "Clouds"
+        "Clouds".to_string()
     };
+    if let Some(tag) = &app.clouds_state.tag_filter {
+        cloud_title.push_str(&format!(" #{}", tag));
+    }
 
     // Ensure we have a selection if none exists
     if app.clouds_state.clouds_list_state.selected().is_none() && !cloud_items.is_empty() {
@@ -64,20 +250,20 @@ pub fn render_servers_tab(app: &mut App, area: Rect, buf: &mut Buffer) {
                 .border_style(
                     if app.clouds_state.focused_panel == CloudFocusedPanel::Clouds {
                         Style::default()
-                            .fg(Color::Cyan)
+                            .fg(theme.focused_border)
                             .add_modifier(ratatui::style::Modifier::BOLD)
                     } else {
                         Style::default()
                     },
                 ),
         )
-        .highlight_style(Style::default().fg(Color::Yellow))
+        .highlight_style(Style::default().fg(theme.selection))
         .highlight_symbol(">> ");
 
     // Render the list with persistent state
     StatefulWidget::render(
         cloud_list,
-        three_column_chunks[0],
+        area,
         buf,
         &mut app.clouds_state.clouds_list_state,
     );
@@ -92,10 +278,16 @@ pub fn render_servers_tab(app: &mut App, area: Rect, buf: &mut Buffer) {
     let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
         .begin_symbol(Some("↑"))
         .end_symbol(Some("↓"));
-    scrollbar.render(three_column_chunks[0], buf, &mut scroll_state);
+    scrollbar.render(area, buf, &mut scroll_state);
 
     // Update the persistent scroll state
     app.clouds_state.clouds_scroll_state = scroll_state;
+}
+
+fn render_cloud_info(app: &App, theme: &Theme, area: Rect, buf: &mut Buffer) {
+    if area.is_empty() {
+        return;
+    }
 
     // Right side: Server controls and info
     let cloud_info = if let Some(cloud) = app
@@ -112,14 +304,16 @@ pub fn render_servers_tab(app: &mut App, area: Rect, buf: &mut Buffer) {
         } else {
             false
         };
-        let cloud_status = if is_running {
-            if let Some(port) = app.clouds_state.get_cloud_port(&cloud.name) {
-                format!("🟢 Running (port {})", port)
-            } else {
-                "🟢 Running".to_string()
-            }
-        } else {
-            "🔴 Not Running".to_string()
+        let status = app.orchestrator.cloud_status(&cloud.name);
+        let failed = matches!(status, CloudStatus::Failed(_));
+        let cloud_status = match status {
+            CloudStatus::Running => match app.clouds_state.get_cloud_port(&cloud.name) {
+                Some(port) => format!("🟢 Running (port {})", port),
+                None => "🟢 Running".to_string(),
+            },
+            CloudStatus::Starting => "🟡 Starting".to_string(),
+            CloudStatus::Failed(reason) => format!("❌ Failed: {}", reason),
+            CloudStatus::Stopped => "🔴 Not Running".to_string(),
         };
 
         let cloud_url = if is_running {
@@ -137,10 +331,36 @@ pub fn render_servers_tab(app: &mut App, area: Rect, buf: &mut Buffer) {
             cloud_url,
             cloud_status,
         );
+        let unavailable = app.orchestrator.unavailable_folders(&cloud.name);
+        if !unavailable.is_empty() {
+            info.push_str(&format!(
+                "\n⚠️  Degraded: {} unavailable",
+                unavailable.join(", ")
+            ));
+        }
+        if cloud.guest_access {
+            info.push_str("\nGuest access: anyone can browse and download");
+        }
+        match app.clouds_state.reachability.get(&cloud.name) {
+            Some(None) => info.push_str("\nInternet: ↻ Testing reachability..."),
+            Some(Some(check)) => {
+                info.push_str(&format!(
+                    "\nInternet: {} {}",
+                    check.status.symbol(),
+                    check.message
+                ));
+                if let Some(hint) = &check.hint {
+                    info.push_str(&format!("\n  → {}", hint));
+                }
+            }
+            None => {}
+        }
 
-        // Add server start error if present
+        // Add server start error if present, unless the status already says it
         if let Some(ref error) = app.clouds_state.cloud_start_error {
-            info.push_str(&format!("\n\n{}", error));
+            if !failed {
+                info.push_str(&format!("\n\n{}", error));
+            }
         }
 
         info
@@ -171,53 +391,87 @@ pub fn render_servers_tab(app: &mut App, area: Rect, buf: &mut Buffer) {
                 .border_style(
                     if app.clouds_state.focused_panel == CloudFocusedPanel::CloudInfo {
                         Style::default()
-                            .fg(Color::Cyan)
+                            .fg(theme.focused_border)
                             .add_modifier(ratatui::style::Modifier::BOLD)
                     } else {
                         Style::default()
                     },
                 ),
         )
-        .alignment(ratatui::layout::Alignment::Left);
-
-    cloud_block.render(three_column_chunks[1], buf);
+        .alignment(ratatui::layout::Alignment::Left)
+        .wrap(ratatui::widgets::Wrap { trim: false });
 
-    // Cloud logs section with scrolling
-    let logs = &app.cloud_logs; // Use the main app's cloud logs
+    let selected_stats = app
+        .clouds_state
+        .clouds
+        .get(app.clouds_state.selected_cloud_index)
+        .and_then(|cloud| app.clouds_state.stats.get(&cloud.name));
+
+    // Info, stats and activity take 23 rows on top of each other; with fewer, the stats go
+    // next to the info and the activity is left out
+    if area.height >= 23 {
+        let info_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(8),
+                Constraint::Length(7),
+                Constraint::Length(8),
+            ])
+            .split(area);
+        cloud_block.render(info_chunks[0], buf);
+        render_stats(
+            selected_stats.and_then(|history| history.latest()),
+            theme,
+            info_chunks[1],
+            buf,
+        );
+        render_activity(selected_stats, theme, info_chunks[2], buf);
+    } else {
+        let info_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(area);
+        cloud_block.render(info_chunks[0], buf);
+        render_stats(
+            selected_stats.and_then(|history| history.latest()),
+            theme,
+            info_chunks[1],
+            buf,
+        );
+    }
+}
 
+fn render_cloud_logs(app: &mut App, theme: &Theme, area: Rect, buf: &mut Buffer) {
     // Ensure the logs area has enough space
-    if three_column_chunks[2].height < 3 {
+    if area.height < 3 || area.width == 0 {
         return;
     }
 
-    let visible_logs: Vec<ListItem> = logs
-        .iter()
-        .map(|log| {
-            let level_color = match log.level {
-                cloudhost_server::debug_stream::LogLevel::Error => Color::Red,
-                cloudhost_server::debug_stream::LogLevel::Warning => Color::Yellow,
-                cloudhost_server::debug_stream::LogLevel::Info => Color::Green,
-                cloudhost_server::debug_stream::LogLevel::Debug => Color::Blue,
-            };
-
-            let formatted_msg = format!(
-                "[{}] [{}] {}: {}",
-                log.timestamp.format("%H:%M:%S%.3f"),
-                log.level,
-                log.source,
-                log.message
-            );
+    // Cloud logs section with scrolling
+    let logs = &app.cloud_logs; // Use the main app's cloud logs
 
-            ListItem::new(formatted_msg).style(Style::default().fg(level_color))
+    let log_view = &app.clouds_state.log_view;
+    let mut match_count = 0;
+    let visible_logs: Vec<ListItem> = log_view
+        .visible(logs)
+        .into_iter()
+        .map(|log| {
+            let line = log_line(log);
+            match_count += log_view.matches(&line).len();
+            ListItem::new(highlighted_line(
+                line,
+                log_view,
+                level_color(theme, &log.level),
+                theme,
+            ))
         })
         .collect();
 
-    // Add focus indicator to title
-    let logs_title = if app.clouds_state.focused_panel == CloudFocusedPanel::CloudLogs {
-        "Cloud Logs (FOCUSED)"
-    } else {
-        "Cloud Logs"
-    };
+    let logs_title = logs_title(
+        log_view,
+        app.clouds_state.focused_panel == CloudFocusedPanel::CloudLogs,
+        match_count,
+    );
 
     let cloud_logs_list = List::new(visible_logs.clone())
         .block(
@@ -228,34 +482,35 @@ pub fn render_servers_tab(app: &mut App, area: Rect, buf: &mut Buffer) {
                 .border_style(
                     if app.clouds_state.focused_panel == CloudFocusedPanel::CloudLogs {
                         Style::default()
-                            .fg(Color::Cyan)
+                            .fg(theme.focused_border)
                             .add_modifier(ratatui::style::Modifier::BOLD)
                     } else {
                         Style::default()
                     },
                 ),
         )
-        .style(Style::default().fg(Color::Green))
+        .style(Style::default().fg(theme.log_info))
         .highlight_style(
             Style::default()
-                .fg(Color::Yellow)
+                .fg(theme.selection)
                 .add_modifier(ratatui::style::Modifier::BOLD),
         )
         .highlight_symbol(">> ");
 
-    // Use the persistent ListState from ServerState
-    if !visible_logs.is_empty() && app.clouds_state.cloud_logs_list_state.selected().is_none() {
-        // Select the last item (newest log) by default if nothing is selected
-        let selected_index = visible_logs.len().saturating_sub(1);
-        app.clouds_state
-            .cloud_logs_list_state
-            .select(Some(selected_index));
-    }
+    // Following selects the newest log; otherwise keep the selection within the filtered list
+    let last_index = visible_logs.len().checked_sub(1);
+    let selected = app.clouds_state.cloud_logs_list_state.selected();
+    let selected = if app.clouds_state.log_view.follow || selected.is_none() {
+        last_index
+    } else {
+        selected.zip(last_index).map(|(s, last)| s.min(last))
+    };
+    app.clouds_state.cloud_logs_list_state.select(selected);
 
     // Render the list with state
     StatefulWidget::render(
         cloud_logs_list,
-        three_column_chunks[2],
+        area,
         buf,
         &mut app.clouds_state.cloud_logs_list_state,
     );
@@ -270,10 +525,45 @@ pub fn render_servers_tab(app: &mut App, area: Rect, buf: &mut Buffer) {
     let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
         .begin_symbol(Some("↑"))
         .end_symbol(Some("↓"));
-    scrollbar.render(three_column_chunks[2], buf, &mut scroll_state);
+    scrollbar.render(area, buf, &mut scroll_state);
 
     // Update the persistent scroll state
     app.clouds_state.cloud_logs_scroll_state = scroll_state;
+}
+
+pub fn render_servers_tab(app: &mut App, area: Rect, buf: &mut Buffer) {
+    let theme = app.theme();
+
+    // Clouds, server info and server logs side by side, stacked, or one at a time
+    let focused = match app.clouds_state.focused_panel {
+        CloudFocusedPanel::Clouds => 0,
+        CloudFocusedPanel::CloudInfo => 1,
+        CloudFocusedPanel::CloudLogs => 2,
+    };
+    let layout = panel_areas(
+        app.config.layout.mode(area),
+        area,
+        app.config.layout.clouds_columns(),
+        [
+            Constraint::Percentage(25),
+            Constraint::Percentage(35),
+            Constraint::Percentage(40),
+        ],
+        focused,
+    );
+    if let Some(switcher) = layout.switcher {
+        render_panel_switcher(
+            &theme,
+            &["Clouds", "Cloud Info", "Cloud Logs"],
+            focused,
+            switcher,
+            buf,
+        );
+    }
+    let [clouds_area, info_area, logs_area] = layout.panels;
+    render_clouds_list(app, &theme, clouds_area, buf);
+    render_cloud_info(app, &theme, info_area, buf);
+    render_cloud_logs(app, &theme, logs_area, buf);
 
     // Render password creation modal if active
     if app.clouds_state.password_creation.creating_password {
@@ -292,4 +582,14 @@ pub fn render_servers_tab(app: &mut App, area: Rect, buf: &mut Buffer) {
             buf,
         );
     }
+
+    if app.clouds_state.password_change.active {
+        if let Some(cloud) = app
+            .clouds_state
+            .clouds
+            .get(app.clouds_state.selected_cloud_index)
+        {
+            render_change_password_modal(&cloud.name, &app.clouds_state.password_change, area, buf);
+        }
+    }
 }
@@ -1,7 +1,12 @@
 use crate::tabs::focus::TabFocus;
+use crate::utils::folder_preview::FolderPreview;
 use crate::utils::password::PasswordCreationState;
+use crate::utils::path_input::{DirPicker, PathCompletion};
+use crate::utils::text_input::TextInput;
 use ratatui::crossterm::event::KeyCode;
 use ratatui::widgets::{ListState, ScrollbarState};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 // Re-export server types
 pub use cloudhost_server::{Cloud, CloudFolder};
@@ -9,6 +14,7 @@ pub use cloudhost_server::{Cloud, CloudFolder};
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FocusedPanel {
     Folders,
+    Files,
     Clouds,
     Info,
 }
@@ -24,6 +30,30 @@ pub enum CloudEditFocus {
     Name,
     Folders,
 }
+/// Columns of the detailed folders list the folders can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FolderSortColumn {
+    Name,
+    Path,
+    Size,
+    Items,
+    Clouds,
+}
+
+impl FolderSortColumn {
+    /// The column sorted by after this one; `None` after the last, for the config order
+    pub fn next(column: Option<Self>) -> Option<Self> {
+        match column {
+            None => Some(Self::Name),
+            Some(Self::Name) => Some(Self::Path),
+            Some(Self::Path) => Some(Self::Size),
+            Some(Self::Size) => Some(Self::Items),
+            Some(Self::Items) => Some(Self::Clouds),
+            Some(Self::Clouds) => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum PasswordDisplayState {
     #[default]
@@ -37,25 +67,25 @@ pub struct FoldersState {
     pub clouds: Vec<Cloud>,
     pub selected_cloud_index: usize,
     pub creating_folder: bool,
-    pub new_folder_name: String,
-    pub new_folder_path: String,
+    pub new_folder_name: TextInput,
+    pub new_folder_path: TextInput,
     pub folder_input_field: FolderInputField,
     pub folder_creation_error: Option<String>,
     pub creating_cloud: bool,
-    pub new_cloud_name: String,
+    pub new_cloud_name: TextInput,
     pub cloud_creation_error: Option<String>,
     // Shared password creation state
     pub password_creation: PasswordCreationState,
     // Edit state
     pub editing_folder: bool,
     pub edit_folder_original_name: String,
-    pub edit_folder_name: String,
-    pub edit_folder_path: String,
+    pub edit_folder_name: TextInput,
+    pub edit_folder_path: TextInput,
     pub edit_folder_input_field: FolderInputField,
     pub folder_edit_error: Option<String>,
     pub editing_cloud: bool,
     pub edit_cloud_original_name: String,
-    pub edit_cloud_name: String,
+    pub edit_cloud_name: TextInput,
     pub edit_cloud_selected_folders: std::collections::HashSet<usize>, // Folders selected for this group
     pub cloud_edit_focus: CloudEditFocus,
     pub cloud_edit_error: Option<String>,
@@ -65,6 +95,22 @@ pub struct FoldersState {
     pub folders_scroll_state: ScrollbarState,
     pub clouds_list_state: ListState,
     pub clouds_scroll_state: ScrollbarState,
+    // Files panel for browsing the selected cloud folder
+    pub files: FilesPanelState,
+    // Tab completion and directory picker for the path field of the folder modals
+    pub path_completion: PathCompletion,
+    pub dir_picker: Option<DirPicker>,
+    // Preview of the typed path and the input it was last requested for
+    pub path_preview: Option<FolderPreview>,
+    pub preview_requested: Option<String>,
+    // Table of the folders with their path, size and clouds instead of just their names
+    pub folder_details: bool,
+    /// `None` keeps the order of the clouds config
+    pub folder_sort: Option<FolderSortColumn>,
+    pub folder_sort_reversed: bool,
+    /// Sizes of the cloud folders by path, scanned while the details are shown
+    pub folder_sizes: HashMap<PathBuf, FolderPreview>,
+    pub sizes_requested: HashSet<PathBuf>,
 }
 
 impl Default for FoldersState {
@@ -76,23 +122,23 @@ impl Default for FoldersState {
             clouds: Vec::new(),
             selected_cloud_index: 0,
             creating_folder: false,
-            new_folder_name: String::new(),
-            new_folder_path: String::new(),
+            new_folder_name: TextInput::default(),
+            new_folder_path: TextInput::default(),
             folder_input_field: FolderInputField::Name,
             folder_creation_error: None,
             creating_cloud: false,
-            new_cloud_name: String::new(),
+            new_cloud_name: TextInput::default(),
             cloud_creation_error: None,
             password_creation: PasswordCreationState::new(),
             editing_folder: false,
             edit_folder_original_name: String::new(),
-            edit_folder_name: String::new(),
-            edit_folder_path: String::new(),
+            edit_folder_name: TextInput::default(),
+            edit_folder_path: TextInput::default(),
             edit_folder_input_field: FolderInputField::Name,
             folder_edit_error: None,
             editing_cloud: false,
             edit_cloud_original_name: String::new(),
-            edit_cloud_name: String::new(),
+            edit_cloud_name: TextInput::default(),
             edit_cloud_selected_folders: std::collections::HashSet::new(),
             cloud_edit_focus: CloudEditFocus::Name,
             cloud_edit_error: None,
@@ -102,6 +148,16 @@ impl Default for FoldersState {
             folders_scroll_state: ScrollbarState::default(),
             clouds_list_state: ListState::default(),
             clouds_scroll_state: ScrollbarState::default(),
+            files: FilesPanelState::default(),
+            path_completion: PathCompletion::default(),
+            dir_picker: None,
+            path_preview: None,
+            preview_requested: None,
+            folder_details: false,
+            folder_sort: None,
+            folder_sort_reversed: false,
+            folder_sizes: HashMap::new(),
+            sizes_requested: HashSet::new(),
         }
     }
 }
@@ -110,6 +166,7 @@ impl TabFocus for FoldersState {
     fn get_focused_element(&self) -> String {
         match self.focused_panel {
             FocusedPanel::Folders => "FoldersList".to_string(),
+            FocusedPanel::Files => "FilesList".to_string(),
             FocusedPanel::Clouds => "CloudsList".to_string(),
             FocusedPanel::Info => "InfoPanel".to_string(),
         }
@@ -117,7 +174,8 @@ impl TabFocus for FoldersState {
 
     fn cycle_focus_forward(&mut self) {
         self.focused_panel = match self.focused_panel {
-            FocusedPanel::Folders => FocusedPanel::Clouds,
+            FocusedPanel::Folders => FocusedPanel::Files,
+            FocusedPanel::Files => FocusedPanel::Clouds,
             FocusedPanel::Clouds => FocusedPanel::Folders,
             FocusedPanel::Info => FocusedPanel::Folders, // Info panel is not focusable
         };
@@ -126,7 +184,8 @@ impl TabFocus for FoldersState {
     fn cycle_focus_backward(&mut self) {
         self.focused_panel = match self.focused_panel {
             FocusedPanel::Folders => FocusedPanel::Clouds,
-            FocusedPanel::Clouds => FocusedPanel::Folders,
+            FocusedPanel::Files => FocusedPanel::Folders,
+            FocusedPanel::Clouds => FocusedPanel::Files,
             FocusedPanel::Info => FocusedPanel::Folders, // Info panel is not focusable
         };
     }
@@ -134,6 +193,7 @@ impl TabFocus for FoldersState {
     fn handle_navigation(&mut self, key: KeyCode) -> bool {
         match self.focused_panel {
             FocusedPanel::Folders => self.handle_folders_navigation(key),
+            FocusedPanel::Files => self.files.handle_navigation(key),
             FocusedPanel::Clouds => self.handle_clouds_navigation(key),
             FocusedPanel::Info => false, // Info panel is not navigable
         }
@@ -168,6 +228,77 @@ impl FoldersState {
         }
     }
 
+    /// Names of the clouds serving the folder called `name`
+    pub fn clouds_with_folder(&self, name: &str) -> Vec<&str> {
+        self.clouds
+            .iter()
+            .filter(|cloud| cloud.cloud_folders.iter().any(|f| f.name == name))
+            .map(|cloud| cloud.name.as_str())
+            .collect()
+    }
+
+    /// The last scan of the folder at `path`, if there was one
+    pub fn folder_size(&self, path: &Path) -> Option<&FolderPreview> {
+        self.folder_sizes.get(path)
+    }
+
+    /// Put the folders in the order of `folder_sort`, keeping the selected and marked
+    /// folders. Folders not scanned yet count as the smallest.
+    pub fn sort_folders(&mut self) {
+        let Some(column) = self.folder_sort else {
+            return;
+        };
+        let mut folders = self.cloud_folders.clone();
+        match column {
+            FolderSortColumn::Name => folders.sort_by_key(|f| f.name.to_lowercase()),
+            FolderSortColumn::Path => folders.sort_by(|a, b| a.folder_path.cmp(&b.folder_path)),
+            FolderSortColumn::Size => folders.sort_by_key(|f| {
+                std::cmp::Reverse(self.folder_size(&f.folder_path).map(|s| s.total_bytes))
+            }),
+            FolderSortColumn::Items => folders.sort_by_key(|f| {
+                std::cmp::Reverse(
+                    self.folder_size(&f.folder_path)
+                        .map(|s| s.file_count + s.dir_count),
+                )
+            }),
+            FolderSortColumn::Clouds => {
+                folders.sort_by_key(|f| std::cmp::Reverse(self.clouds_with_folder(&f.name).len()))
+            }
+        }
+        // Names and paths go A to Z, sizes and counts largest first, unless reversed
+        if self.folder_sort_reversed {
+            folders.reverse();
+        }
+        self.reorder_folders(folders);
+    }
+
+    /// Show `folders`, the same folders in another order, keeping which are selected and
+    /// marked
+    pub fn reorder_folders(&mut self, folders: Vec<CloudFolder>) {
+        let selected = self
+            .cloud_folders
+            .get(self.selected_folder_index)
+            .map(|folder| folder.name.clone());
+        let marked: HashSet<String> = self.get_selected_folder_names().into_iter().collect();
+        self.cloud_folders = folders;
+
+        self.selected_folders = self
+            .cloud_folders
+            .iter()
+            .enumerate()
+            .filter(|(_, folder)| marked.contains(&folder.name))
+            .map(|(index, _)| index)
+            .collect();
+        if let Some(index) = selected.and_then(|name| {
+            self.cloud_folders
+                .iter()
+                .position(|folder| folder.name == name)
+        }) {
+            self.selected_folder_index = index;
+            self.folders_list_state.select(Some(index));
+        }
+    }
+
     pub fn start_creating_cloud(&mut self) {
         self.creating_cloud = true;
         self.new_cloud_name.clear();
@@ -181,14 +312,13 @@ impl FoldersState {
         self.password_creation.clear_password_creation();
     }
 
+    /// In the order of the folders list, not the order they were selected in
     pub fn get_selected_folder_names(&self) -> Vec<String> {
-        self.selected_folders
+        self.cloud_folders
             .iter()
-            .filter_map(|&index| {
-                self.cloud_folders
-                    .get(index)
-                    .map(|folder| folder.name.clone())
-            })
+            .enumerate()
+            .filter(|(index, _)| self.selected_folders.contains(index))
+            .map(|(_, folder)| folder.name.clone())
             .collect()
     }
 
@@ -196,10 +326,12 @@ impl FoldersState {
         if let Some(folder) = self.cloud_folders.get(self.selected_folder_index) {
             self.editing_folder = true;
             self.edit_folder_original_name = folder.name.clone();
-            self.edit_folder_name = folder.name.clone();
-            self.edit_folder_path = folder.folder_path.to_string_lossy().to_string();
+            self.edit_folder_name.set(folder.name.clone());
+            self.edit_folder_path
+                .set(folder.folder_path.to_string_lossy().to_string());
             self.edit_folder_input_field = FolderInputField::Name;
             self.folder_edit_error = None;
+            self.reset_path_input();
         }
     }
 
@@ -215,7 +347,7 @@ impl FoldersState {
         if let Some(cloud) = self.clouds.get(self.selected_cloud_index) {
             self.editing_cloud = true;
             self.edit_cloud_original_name = cloud.name.clone();
-            self.edit_cloud_name = cloud.name.clone();
+            self.edit_cloud_name.set(cloud.name.clone());
             self.cloud_edit_focus = CloudEditFocus::Name;
             self.cloud_edit_error = None;
 
@@ -270,13 +402,105 @@ impl FoldersState {
     }
 
     /// Get password display text based on current state
+    /// Hashed passwords can't be shown, only legacy plaintext ones
     pub fn get_password_display(&self, password: &str) -> String {
+        if cloudhost_server::password::is_hash(password) {
+            return "******** (hashed)".to_string();
+        }
         match self.password_display_state {
             PasswordDisplayState::Hidden => "*".repeat(password.len()),
             PasswordDisplayState::Visible => password.to_string(),
         }
     }
 
+    /// The name or path field of the open modal that has focus, where pasted text goes.
+    /// Passwords are pasted through `password_creation` instead.
+    pub fn focused_input(&mut self) -> Option<&mut TextInput> {
+        if self.creating_folder {
+            Some(match self.folder_input_field {
+                FolderInputField::Name => &mut self.new_folder_name,
+                FolderInputField::Path => &mut self.new_folder_path,
+            })
+        } else if self.creating_cloud {
+            Some(&mut self.new_cloud_name)
+        } else if self.editing_folder {
+            Some(match self.edit_folder_input_field {
+                FolderInputField::Name => &mut self.edit_folder_name,
+                FolderInputField::Path => &mut self.edit_folder_path,
+            })
+        } else if self.editing_cloud && self.cloud_edit_focus == CloudEditFocus::Name {
+            Some(&mut self.edit_cloud_name)
+        } else if self.files.creating_directory {
+            Some(&mut self.files.new_directory_name)
+        } else {
+            None
+        }
+    }
+
+    // ========== Path input ==========
+
+    /// The path being typed in the folder creation or edit modal
+    pub fn active_path_input(&mut self) -> Option<&mut TextInput> {
+        if self.creating_folder {
+            Some(&mut self.new_folder_path)
+        } else if self.editing_folder {
+            Some(&mut self.edit_folder_path)
+        } else {
+            None
+        }
+    }
+
+    /// Forget completions and preview left over from a previous modal
+    pub fn reset_path_input(&mut self) {
+        self.path_completion.reset();
+        self.path_preview = None;
+        self.preview_requested = None;
+    }
+
+    /// The preview for the path currently typed, if it has arrived
+    pub fn current_preview(&self) -> Option<&FolderPreview> {
+        let input = if self.creating_folder {
+            &self.new_folder_path
+        } else {
+            &self.edit_folder_path
+        };
+        self.path_preview
+            .as_ref()
+            .filter(|preview| preview.input == input.trim())
+    }
+
+    /// Tab-complete the path field; returns false when nothing matched
+    pub fn complete_path_input(&mut self, forward: bool) -> bool {
+        let mut completion = std::mem::take(&mut self.path_completion);
+        let completed = self
+            .active_path_input()
+            .is_some_and(|input| completion.complete(input, forward));
+        self.path_completion = completion;
+        completed
+    }
+
+    pub fn open_dir_picker(&mut self) {
+        let start = self.active_path_input().map(|input| input.to_string());
+        if let Some(start) = start {
+            self.dir_picker = Some(DirPicker::open(&start));
+        }
+    }
+
+    /// Put the picked directory into the path field and close the picker
+    pub fn pick_directory(&mut self, path: PathBuf) {
+        self.dir_picker = None;
+        self.path_completion.reset();
+        if let Some(input) = self.active_path_input() {
+            input.set(path.display().to_string());
+        }
+    }
+
+    /// Point the files panel at the currently selected cloud folder
+    pub fn sync_files_panel(&mut self) {
+        let folder = self.cloud_folders.get(self.selected_folder_index);
+        self.files.show_folder(folder);
+    }
+
     pub fn handle_folders_navigation(&mut self, key: KeyCode) -> bool {
         match key {
             KeyCode::Up | KeyCode::Char('k') => {
@@ -361,3 +585,216 @@ impl FoldersState {
         }
     }
 }
+
+/// A file or directory inside a cloud folder, as shown in the files panel
+#[derive(Debug, Clone)]
+pub struct LocalEntry {
+    pub name: String,
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub size: u64,
+}
+
+#[derive(Default)]
+pub struct FilesPanelState {
+    /// Name of the cloud folder being browsed
+    pub folder_name: Option<String>,
+    pub root: PathBuf,
+    pub current_dir: PathBuf,
+    pub entries: Vec<LocalEntry>,
+    pub selected_index: usize,
+    pub creating_directory: bool,
+    pub new_directory_name: TextInput,
+    pub directory_creation_error: Option<String>,
+    pub error: Option<String>,
+    pub list_state: ListState,
+    pub scroll_state: ScrollbarState,
+}
+
+impl FilesPanelState {
+    /// Switch to another cloud folder; keeps the current directory if it is the same one
+    pub fn show_folder(&mut self, folder: Option<&CloudFolder>) {
+        match folder {
+            Some(folder) => {
+                if self.folder_name.as_deref() == Some(folder.name.as_str())
+                    && self.root == folder.folder_path
+                {
+                    return;
+                }
+                self.folder_name = Some(folder.name.clone());
+                self.root = folder.folder_path.clone();
+                self.current_dir = folder.folder_path.clone();
+                self.reload();
+            }
+            None => *self = Self::default(),
+        }
+    }
+
+    /// Re-read the current directory from disk, directories first
+    pub fn reload(&mut self) {
+        self.entries.clear();
+        self.error = None;
+
+        match std::fs::read_dir(&self.current_dir) {
+            Ok(read_dir) => {
+                for entry in read_dir.flatten() {
+                    let metadata = entry.metadata().ok();
+                    self.entries.push(LocalEntry {
+                        name: entry.file_name().to_string_lossy().to_string(),
+                        path: entry.path(),
+                        is_dir: metadata.as_ref().is_some_and(|m| m.is_dir()),
+                        size: metadata.map(|m| m.len()).unwrap_or(0),
+                    });
+                }
+                self.entries
+                    .sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then(a.name.cmp(&b.name)));
+            }
+            Err(e) => {
+                self.error = Some(format!(
+                    "Cannot read '{}': {}",
+                    self.current_dir.display(),
+                    e
+                ));
+            }
+        }
+
+        if self.entries.is_empty() {
+            self.selected_index = 0;
+            self.list_state.select(None);
+        } else {
+            self.selected_index = self.selected_index.min(self.entries.len() - 1);
+            self.list_state.select(Some(self.selected_index));
+        }
+    }
+
+    pub fn selected_entry(&self) -> Option<&LocalEntry> {
+        self.entries.get(self.selected_index)
+    }
+
+    /// Current directory relative to the cloud folder root, e.g. `/photos/2024`
+    pub fn relative_location(&self) -> String {
+        let relative = self
+            .current_dir
+            .strip_prefix(&self.root)
+            .unwrap_or(&self.current_dir);
+        format!("/{}", relative.to_string_lossy().replace('\\', "/"))
+    }
+
+    fn change_dir(&mut self, dir: PathBuf) {
+        self.current_dir = dir;
+        self.selected_index = 0;
+        self.reload();
+    }
+
+    /// Enter the selected directory. Returns the path of the selected file if it is not a directory.
+    pub fn open_selected(&mut self) -> Option<PathBuf> {
+        let entry = self.selected_entry()?.clone();
+        if entry.is_dir {
+            self.change_dir(entry.path);
+            None
+        } else {
+            Some(entry.path)
+        }
+    }
+
+    /// Go up one directory, never leaving the cloud folder root
+    pub fn go_up(&mut self) {
+        if self.current_dir == self.root {
+            return;
+        }
+        if let Some(parent) = self.current_dir.parent() {
+            let previous = self.current_dir.clone();
+            self.change_dir(parent.to_path_buf());
+            // Keep the directory we came from selected
+            if let Some(index) = self.entries.iter().position(|e| e.path == previous) {
+                self.selected_index = index;
+                self.list_state.select(Some(index));
+            }
+        }
+    }
+
+    /// Delete the selected entry (to the trash on desktop)
+    pub fn delete_selected(&mut self) -> Result<String, String> {
+        let entry = self
+            .selected_entry()
+            .cloned()
+            .ok_or_else(|| "No file selected".to_string())?;
+
+        delete_path(&entry.path, entry.is_dir)
+            .map_err(|e| format!("Failed to delete '{}': {}", entry.name, e))?;
+
+        self.reload();
+        Ok(entry.name)
+    }
+
+    pub fn start_creating_directory(&mut self) {
+        self.creating_directory = true;
+        self.new_directory_name.clear();
+        self.directory_creation_error = None;
+    }
+
+    pub fn clear_directory_creation(&mut self) {
+        self.creating_directory = false;
+        self.new_directory_name.clear();
+        self.directory_creation_error = None;
+    }
+
+    /// Create the directory typed in the modal inside the current directory
+    pub fn create_directory(&mut self) -> Result<PathBuf, String> {
+        let name = self.new_directory_name.trim();
+        if name.is_empty() {
+            return Err("Directory name cannot be empty".to_string());
+        }
+        if name.contains(['/', '\\']) || name == "." || name == ".." {
+            return Err("Directory name cannot contain path separators".to_string());
+        }
+
+        let path = self.current_dir.join(name);
+        if path.exists() {
+            return Err(format!("'{}' already exists", name));
+        }
+        std::fs::create_dir(&path).map_err(|e| format!("Failed to create directory: {}", e))?;
+
+        self.clear_directory_creation();
+        self.reload();
+        if let Some(index) = self.entries.iter().position(|e| e.path == path) {
+            self.selected_index = index;
+            self.list_state.select(Some(index));
+        }
+        Ok(path)
+    }
+
+    pub fn handle_navigation(&mut self, key: KeyCode) -> bool {
+        if self.entries.is_empty() {
+            return true;
+        }
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected_index = self.selected_index.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected_index = (self.selected_index + 1).min(self.entries.len() - 1);
+            }
+            KeyCode::Char('g') => self.selected_index = 0,
+            KeyCode::Char('G') => self.selected_index = self.entries.len() - 1,
+            _ => return false,
+        }
+        self.list_state.select(Some(self.selected_index));
+        true
+    }
+}
+
+#[cfg(feature = "desktop")]
+fn delete_path(path: &std::path::Path, _is_dir: bool) -> Result<(), String> {
+    trash::delete(path).map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "desktop"))]
+fn delete_path(path: &std::path::Path, is_dir: bool) -> Result<(), String> {
+    let result = if is_dir {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    };
+    result.map_err(|e| e.to_string())
+}
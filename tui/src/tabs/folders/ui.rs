@@ -2,35 +2,76 @@ use ratatui::{
     buffer::Buffer,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
+    text::{Line, Span},
     widgets::{
-        Block, Borders, Clear, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation,
-        StatefulWidget, Widget,
+        Block, Borders, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Scrollbar,
+        ScrollbarOrientation, StatefulWidget, Table, TableState, Widget, Wrap,
     },
 };
 
+use crate::components::cloud_status::cloud_list_spans;
+use crate::components::panel_switcher::render_panel_switcher;
 use crate::components::password_modal::render_password_modal;
+use crate::components::text_input::input_line;
+use crate::layout::{panel_areas, LayoutMode};
 use crate::models::App;
-use crate::tabs::folders::models::FocusedPanel;
+use crate::tabs::folders::models::{FocusedPanel, FolderInputField, FolderSortColumn};
+use crate::theme::Theme;
+use crate::utils::jobs::format_bytes;
+use crate::utils::path_input::DirPicker;
 
 pub fn render_folders_tab(app: &App, area: Rect, buf: &mut Buffer) {
-    // Create three equal columns: folders, clouds, info
-    let chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage(33), // Folders
-            Constraint::Percentage(33), // Clouds
-            Constraint::Percentage(34), // Info
-        ])
-        .split(area);
-
-    // Render folders list
-    render_folders_list(app, chunks[0], buf);
-
-    // Render clouds list
-    render_clouds_list(app, chunks[1], buf);
+    let theme = app.theme();
+    let focused = match app.folders_state.focused_panel {
+        FocusedPanel::Folders => 0,
+        FocusedPanel::Files => 1,
+        FocusedPanel::Clouds => 2,
+        FocusedPanel::Info => 3,
+    };
+    let mode = app.config.layout.mode(area);
+    let [folders_area, files_area, clouds_area, info_area] = if mode == LayoutMode::Columns {
+        // Columns for folders, clouds and info, as wide as the layout config says
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(app.config.layout.folders_columns())
+            .split(area);
+
+        // Folders list on top, files of the selected folder below
+        let folder_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+            .split(chunks[0]);
+        [folder_chunks[0], folder_chunks[1], chunks[1], chunks[2]]
+    } else {
+        let layout = panel_areas(
+            mode,
+            area,
+            [Constraint::Fill(1); 4],
+            [
+                Constraint::Percentage(25),
+                Constraint::Percentage(30),
+                Constraint::Percentage(20),
+                Constraint::Percentage(25),
+            ],
+            focused,
+        );
+        if let Some(switcher) = layout.switcher {
+            // The info panel follows the selection, it can't be focused
+            render_panel_switcher(
+                &theme,
+                &["Cloud Folders", "Files", "Clouds"],
+                focused,
+                switcher,
+                buf,
+            );
+        }
+        layout.panels
+    };
 
-    // Render info panel
-    render_info_panel(app, chunks[2], buf);
+    render_folders_list(app, folders_area, buf);
+    render_files_panel(app, files_area, buf);
+    render_clouds_list(app, clouds_area, buf);
+    render_info_panel(app, info_area, buf);
 
     // Render creation modals
     if app.folders_state.creating_folder {
@@ -40,7 +81,7 @@ pub fn render_folders_tab(app: &App, area: Rect, buf: &mut Buffer) {
         let (title, cloud_name) = if !app.folders_state.new_cloud_name.is_empty() {
             (
                 "🔐 Set Cloud Password",
-                app.folders_state.new_cloud_name.as_str(),
+                app.folders_state.new_cloud_name.value(),
             )
         } else if app.folders_state.selected_cloud_index < app.folders_state.clouds.len() {
             (
@@ -70,61 +111,259 @@ pub fn render_folders_tab(app: &App, area: Rect, buf: &mut Buffer) {
     } else if app.folders_state.editing_cloud {
         render_cloud_edit_modal(app, area, buf);
     }
+
+    if app.folders_state.files.creating_directory {
+        render_directory_creation_modal(app, area, buf);
+    }
+
+    if let Some(ref picker) = app.folders_state.dir_picker {
+        render_dir_picker(&theme, picker, area, buf);
+    }
 }
 
 fn render_folders_list(app: &App, area: Rect, buf: &mut Buffer) {
+    let theme = app.theme();
+    // Nothing fits, and a scrollbar can't be drawn in no width
+    if area.is_empty() {
+        return;
+    }
     let selected_count = app.folders_state.get_selected_folders_count();
-    let title = if app.folders_state.focused_panel == FocusedPanel::Folders {
+    let mut title = if app.folders_state.focused_panel == FocusedPanel::Folders {
         format!("Cloud Folders (FOCUSED) - Selected: {}", selected_count)
     } else {
         format!("Cloud Folders - Selected: {}", selected_count)
     };
+    if let Some(column) = app.folders_state.folder_sort {
+        title.push_str(&format!(
+            " - by {}{}",
+            sort_column_name(column),
+            sort_arrow(app.folders_state.folder_sort_reversed)
+        ));
+    }
 
     let block = Block::default()
         .borders(Borders::ALL)
         .title(title)
         .border_style(
             Style::default()
-                .fg(Color::Green)
+                .fg(theme.border(app.folders_state.focused_panel == FocusedPanel::Folders))
                 .add_modifier(ratatui::style::Modifier::BOLD),
         );
 
-    let folders_items: Vec<ListItem> = app
-        .folders_state
-        .cloud_folders
+    let row_style = |i: usize| {
+        if i == app.folders_state.selected_folder_index {
+            Style::default().fg(theme.selection)
+        } else if app.folders_state.is_folder_selected(i) {
+            Style::default().fg(theme.marked)
+        } else {
+            Style::default()
+        }
+    };
+    let radio_indicator = |i: usize| {
+        if app.folders_state.is_folder_selected(i) {
+            "●"
+        } else {
+            "○"
+        }
+    };
+
+    if app.folders_state.folder_details {
+        render_folders_table(app, &theme, block, row_style, radio_indicator, area, buf);
+    } else {
+        let folders_items: Vec<ListItem> = app
+            .folders_state
+            .cloud_folders
+            .iter()
+            .enumerate()
+            .map(|(i, folder)| {
+                let mut spans = vec![Span::raw(format!("{} {}", radio_indicator(i), folder.name))];
+                if app.orchestrator.folder_problem(&folder.name).is_some() {
+                    spans.push(Span::styled(
+                        " unavailable",
+                        Style::default().fg(theme.warning),
+                    ));
+                }
+                ListItem::new(Line::from(spans)).style(row_style(i))
+            })
+            .collect();
+
+        let folders_list = List::new(folders_items).block(block);
+
+        // Use StatefulWidget for proper scrolling
+        StatefulWidget::render(
+            folders_list,
+            area,
+            buf,
+            &mut app.folders_state.folders_list_state.clone(),
+        );
+    }
+
+    // Render scrollbar
+    let mut scroll_state = app.folders_state.folders_scroll_state;
+    scroll_state = scroll_state.content_length(app.folders_state.cloud_folders.len());
+    if let Some(selected) = app.folders_state.folders_list_state.selected() {
+        scroll_state = scroll_state.position(selected);
+    }
+
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(Some("↑"))
+        .end_symbol(Some("↓"));
+
+    scrollbar.render(area, buf, &mut scroll_state);
+}
+
+fn sort_column_name(column: FolderSortColumn) -> &'static str {
+    match column {
+        FolderSortColumn::Name => "name",
+        FolderSortColumn::Path => "path",
+        FolderSortColumn::Size => "size",
+        FolderSortColumn::Items => "items",
+        FolderSortColumn::Clouds => "clouds",
+    }
+}
+
+fn sort_arrow(reversed: bool) -> &'static str {
+    if reversed {
+        " ▲"
+    } else {
+        " ▼"
+    }
+}
+
+/// The folders with their path, size, item count and the clouds serving them, one row
+/// each; sizes show up as the background scans finish
+fn render_folders_table(
+    app: &App,
+    theme: &Theme,
+    block: Block,
+    row_style: impl Fn(usize) -> Style,
+    radio_indicator: impl Fn(usize) -> &'static str,
+    area: Rect,
+    buf: &mut Buffer,
+) {
+    let state = &app.folders_state;
+    let columns = [
+        ("Name", FolderSortColumn::Name),
+        ("Path", FolderSortColumn::Path),
+        ("Size", FolderSortColumn::Size),
+        ("Items", FolderSortColumn::Items),
+        ("Clouds", FolderSortColumn::Clouds),
+    ];
+    let header = Row::new(columns.map(|(name, column)| {
+        if state.folder_sort == Some(column) {
+            Cell::from(format!(
+                "{}{}",
+                name,
+                sort_arrow(state.folder_sort_reversed)
+            ))
+        } else {
+            Cell::from(name)
+        }
+    }))
+    .style(Style::default().fg(theme.dim).add_modifier(Modifier::BOLD));
+
+    let rows = state.cloud_folders.iter().enumerate().map(|(i, folder)| {
+        // Counts of a scan that stopped early are lower bounds
+        let (size, items) = match state.folder_size(&folder.folder_path) {
+            Some(scan) if !scan.is_dir => ("missing".to_string(), String::new()),
+            Some(scan) => {
+                let approx = if scan.truncated { ">" } else { "" };
+                (
+                    format!("{}{}", approx, format_bytes(scan.total_bytes)),
+                    format!("{}{}", approx, scan.file_count + scan.dir_count),
+                )
+            }
+            None => ("…".to_string(), "…".to_string()),
+        };
+        Row::new([
+            format!("{} {}", radio_indicator(i), folder.name),
+            folder.folder_path.display().to_string(),
+            size,
+            items,
+            state.clouds_with_folder(&folder.name).join(", "),
+        ])
+        .style(row_style(i))
+    });
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Fill(2),
+            Constraint::Fill(3),
+            Constraint::Length(10),
+            Constraint::Length(7),
+            Constraint::Fill(2),
+        ],
+    )
+    .header(header)
+    .block(block);
+    let mut table_state = TableState::default()
+        .with_offset(state.folders_list_state.offset())
+        .with_selected(state.folders_list_state.selected());
+    StatefulWidget::render(table, area, buf, &mut table_state);
+}
+
+fn render_files_panel(app: &App, area: Rect, buf: &mut Buffer) {
+    // Nothing fits, and a scrollbar can't be drawn in no width
+    if area.is_empty() {
+        return;
+    }
+    let theme = app.theme();
+    let files = &app.folders_state.files;
+    let location = match &files.folder_name {
+        Some(name) => format!("{}{}", name, files.relative_location()),
+        None => String::new(),
+    };
+    let title = if app.folders_state.focused_panel == FocusedPanel::Files {
+        format!("Files (FOCUSED) - {}", location)
+    } else {
+        format!("Files - {}", location)
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(
+            Style::default()
+                .fg(theme.border(app.folders_state.focused_panel == FocusedPanel::Files))
+                .add_modifier(Modifier::BOLD),
+        );
+
+    if let Some(ref error) = files.error {
+        Paragraph::new(format!("❌ {}", error))
+            .style(Style::default().fg(theme.error))
+            .wrap(ratatui::widgets::Wrap { trim: true })
+            .block(block)
+            .render(area, buf);
+        return;
+    }
+
+    let file_items: Vec<ListItem> = files
+        .entries
         .iter()
         .enumerate()
-        .map(|(i, folder)| {
-            let is_selected = app.folders_state.is_folder_selected(i);
-            let radio_indicator = if is_selected { "●" } else { "○" };
-
-            let style = if i == app.folders_state.selected_folder_index {
-                Style::default().fg(Color::Yellow)
-            } else if is_selected {
-                Style::default().fg(Color::Cyan)
+        .map(|(i, entry)| {
+            let icon = if entry.is_dir { "📁" } else { "📄" };
+            let style = if i == files.selected_index
+                && app.folders_state.focused_panel == FocusedPanel::Files
+            {
+                Style::default().fg(theme.selection)
+            } else if entry.is_dir {
+                Style::default().fg(theme.marked)
             } else {
                 Style::default()
             };
-
-            let content = format!("{} {}", radio_indicator, folder.name);
-            ListItem::new(content).style(style)
+            ListItem::new(format!("{} {}", icon, entry.name)).style(style)
         })
         .collect();
 
-    let folders_list = List::new(folders_items).block(block);
+    let files_list = List::new(file_items).block(block);
 
-    // Use StatefulWidget for proper scrolling
-    StatefulWidget::render(
-        folders_list,
-        area,
-        buf,
-        &mut app.folders_state.folders_list_state.clone(),
-    );
+    StatefulWidget::render(files_list, area, buf, &mut files.list_state.clone());
 
-    // Render scrollbar
-    let mut scroll_state = app.folders_state.folders_scroll_state;
-    scroll_state = scroll_state.content_length(app.folders_state.cloud_folders.len());
-    if let Some(selected) = app.folders_state.folders_list_state.selected() {
+    let mut scroll_state = files.scroll_state;
+    scroll_state = scroll_state.content_length(files.entries.len());
+    if let Some(selected) = files.list_state.selected() {
         scroll_state = scroll_state.position(selected);
     }
 
@@ -136,6 +375,11 @@ fn render_folders_list(app: &App, area: Rect, buf: &mut Buffer) {
 }
 
 fn render_clouds_list(app: &App, area: Rect, buf: &mut Buffer) {
+    let theme = app.theme();
+    // Nothing fits, and a scrollbar can't be drawn in no width
+    if area.is_empty() {
+        return;
+    }
     let title = if app.folders_state.focused_panel == FocusedPanel::Clouds {
         "Clouds (FOCUSED)"
     } else {
@@ -147,7 +391,7 @@ fn render_clouds_list(app: &App, area: Rect, buf: &mut Buffer) {
         .title(title)
         .border_style(
             Style::default()
-                .fg(Color::Blue)
+                .fg(theme.border(app.folders_state.focused_panel == FocusedPanel::Clouds))
                 .add_modifier(ratatui::style::Modifier::BOLD),
         );
 
@@ -158,11 +402,18 @@ fn render_clouds_list(app: &App, area: Rect, buf: &mut Buffer) {
         .enumerate()
         .map(|(i, cloud)| {
             let style = if i == app.folders_state.selected_cloud_index {
-                Style::default().fg(Color::Yellow)
+                Style::default().fg(theme.selection)
             } else {
                 Style::default()
             };
-            ListItem::new(cloud.name.as_str()).style(style)
+            ListItem::new(Line::from(cloud_list_spans(
+                &theme,
+                &cloud.name,
+                &app.orchestrator.cloud_status(&cloud.name),
+                app.orchestrator.get_cloud_port(&cloud.name),
+                !app.orchestrator.unavailable_folders(&cloud.name).is_empty(),
+            )))
+            .style(style)
         })
         .collect();
 
@@ -191,6 +442,10 @@ fn render_clouds_list(app: &App, area: Rect, buf: &mut Buffer) {
 }
 
 fn render_info_panel(app: &App, area: Rect, buf: &mut Buffer) {
+    if area.is_empty() {
+        return;
+    }
+    let theme = app.theme();
     let title = if app.folders_state.focused_panel == FocusedPanel::Info {
         "Info (FOCUSED)"
     } else {
@@ -202,7 +457,7 @@ fn render_info_panel(app: &App, area: Rect, buf: &mut Buffer) {
         .title(title)
         .border_style(
             Style::default()
-                .fg(Color::Magenta)
+                .fg(theme.border(app.folders_state.focused_panel == FocusedPanel::Info))
                 .add_modifier(ratatui::style::Modifier::BOLD),
         );
 
@@ -235,11 +490,18 @@ fn render_info_panel(app: &App, area: Rect, buf: &mut Buffer) {
                     .get_keys_for_action("Select All Folders")
                     .join(", ");
 
+                let health = app
+                    .orchestrator
+                    .folder_problem(&folder.name)
+                    .map(|problem| format!("\n⚠️  Unavailable: {}", problem))
+                    .unwrap_or_default();
+
                 format!(
-                    "Cloud Folder: {}\nPath: {}\nStatus: {}\n\nPress {} to create cloud folder.\nPress {} to delete this cloud folder.\nPress {} to toggle selection.\nPress {} to select all cloud folders.",
+                    "Cloud Folder: {}\nPath: {}\nStatus: {}{}\n\nPress {} to create cloud folder.\nPress {} to delete this cloud folder.\nPress {} to toggle selection.\nPress {} to select all cloud folders.",
                     folder.name,
                     folder.folder_path.display(),
                     selection_status,
+                    health,
                     create_keys,
                     delete_keys,
                     toggle_keys,
@@ -305,6 +567,37 @@ fn render_info_panel(app: &App, area: Rect, buf: &mut Buffer) {
                 "No cloud selected.".to_string()
             }
         }
+        FocusedPanel::Files => {
+            let files = &app.folders_state.files;
+            let open_keys = app.config.get_keys_for_action("Open Entry").join(", ");
+            let up_keys = app
+                .config
+                .get_keys_for_action("Parent Directory")
+                .join(", ");
+            let create_keys = app.config.get_keys_for_action("Create New").join(", ");
+            let delete_keys = app.config.get_keys_for_action("Delete Folder").join(", ");
+            let help = format!(
+                "Press {} to open a directory or edit a file.\nPress {} to go up.\nPress {} to create a directory.\nPress {} to move the selected item to the trash.",
+                open_keys, up_keys, create_keys, delete_keys
+            );
+
+            match files.selected_entry() {
+                Some(entry) if entry.is_dir => format!(
+                    "Directory: {}\nPath: {}\n\n{}",
+                    entry.name,
+                    entry.path.display(),
+                    help
+                ),
+                Some(entry) => format!(
+                    "File: {}\nPath: {}\nSize: {} bytes\n\n{}",
+                    entry.name,
+                    entry.path.display(),
+                    entry.size,
+                    help
+                ),
+                None => format!("This directory is empty.\n\n{}", help),
+            }
+        }
         FocusedPanel::Info => {
             "ℹ️  Info Panel\n\nThis panel shows information about the selected item.".to_string()
         }
@@ -318,9 +611,10 @@ fn render_info_panel(app: &App, area: Rect, buf: &mut Buffer) {
 }
 
 fn render_folder_creation_modal(app: &App, area: Rect, buf: &mut Buffer) {
+    let theme = app.theme();
     // Create a centered modal
-    let modal_width = 60;
-    let modal_height = 16;
+    let modal_width = 60.min(area.width);
+    let modal_height = 18.min(area.height);
     let x = (area.width.saturating_sub(modal_width)) / 2;
     let y = (area.height.saturating_sub(modal_height)) / 2;
 
@@ -337,6 +631,7 @@ fn render_folder_creation_modal(app: &App, area: Rect, buf: &mut Buffer) {
             Constraint::Length(2), // Instructions
             Constraint::Length(3), // Name field
             Constraint::Length(3), // Path field
+            Constraint::Length(2), // Path preview
             Constraint::Length(2), // Error/Help
             Constraint::Min(0),    // Spacer
         ])
@@ -349,7 +644,7 @@ fn render_folder_creation_modal(app: &App, area: Rect, buf: &mut Buffer) {
         .title_alignment(Alignment::Center)
         .border_style(
             Style::default()
-                .fg(Color::Yellow)
+                .fg(theme.focused_border)
                 .add_modifier(Modifier::BOLD),
         );
 
@@ -359,7 +654,7 @@ fn render_folder_creation_modal(app: &App, area: Rect, buf: &mut Buffer) {
 
     // Instructions
     Paragraph::new("Tab to switch fields, Enter to submit, Esc to cancel")
-        .style(Style::default().fg(Color::Cyan))
+        .style(Style::default().fg(theme.info))
         .alignment(Alignment::Center)
         .render(modal_chunks[1], buf);
 
@@ -369,13 +664,19 @@ fn render_folder_creation_modal(app: &App, area: Rect, buf: &mut Buffer) {
         crate::tabs::folders::models::FolderInputField::Name
     ) {
         Style::default()
-            .fg(Color::Green)
+            .fg(theme.success)
             .add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(Color::White)
+        Style::default().fg(theme.text)
     };
 
-    let name_text = format!("Name: {}", app.folders_state.new_folder_name);
+    let name_text = input_line(
+        "Name: ",
+        &app.folders_state.new_folder_name,
+        app.folders_state.folder_input_field == FolderInputField::Name,
+        None,
+        modal_chunks[2].width,
+    );
     Paragraph::new(name_text)
         .style(name_style)
         .alignment(Alignment::Left)
@@ -387,36 +688,51 @@ fn render_folder_creation_modal(app: &App, area: Rect, buf: &mut Buffer) {
         crate::tabs::folders::models::FolderInputField::Path
     ) {
         Style::default()
-            .fg(Color::Green)
+            .fg(theme.success)
             .add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(Color::White)
+        Style::default().fg(theme.text)
     };
 
-    let path_text = format!("Path: {}", app.folders_state.new_folder_path);
+    let path_text = input_line(
+        "Path: ",
+        &app.folders_state.new_folder_path,
+        app.folders_state.folder_input_field == FolderInputField::Path,
+        None,
+        modal_chunks[3].width,
+    );
     Paragraph::new(path_text)
         .style(path_style)
         .alignment(Alignment::Left)
         .render(modal_chunks[3], buf);
 
+    render_folder_preview(app, modal_chunks[4], buf);
+
     // Error message or help text
     if let Some(ref error) = app.folders_state.folder_creation_error {
         Paragraph::new(format!("❌ {}", error))
-            .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+            .style(
+                Style::default()
+                    .fg(theme.error)
+                    .add_modifier(Modifier::BOLD),
+            )
             .alignment(Alignment::Center)
-            .render(modal_chunks[4], buf);
+            .render(modal_chunks[5], buf);
     } else {
         Paragraph::new("Fill in both fields and press Enter")
-            .style(Style::default().fg(Color::Gray))
+            .style(Style::default().fg(theme.hint))
             .alignment(Alignment::Center)
-            .render(modal_chunks[4], buf);
+            .render(modal_chunks[5], buf);
     }
+
+    render_path_completions(app, modal_chunks[6], buf);
 }
 
 fn render_cloud_creation_modal(app: &App, area: Rect, buf: &mut Buffer) {
+    let theme = app.theme();
     // Create a centered modal
-    let modal_width = 70;
-    let modal_height = 20;
+    let modal_width = 70.min(area.width);
+    let modal_height = 20.min(area.height);
     let x = (area.width.saturating_sub(modal_width)) / 2;
     let y = (area.height.saturating_sub(modal_height)) / 2;
 
@@ -444,7 +760,7 @@ fn render_cloud_creation_modal(app: &App, area: Rect, buf: &mut Buffer) {
         .title_alignment(Alignment::Center)
         .border_style(
             Style::default()
-                .fg(Color::Yellow)
+                .fg(theme.focused_border)
                 .add_modifier(Modifier::BOLD),
         );
 
@@ -454,16 +770,22 @@ fn render_cloud_creation_modal(app: &App, area: Rect, buf: &mut Buffer) {
 
     // Instructions
     Paragraph::new("Enter cloud name, then press Enter. Esc to cancel")
-        .style(Style::default().fg(Color::Cyan))
+        .style(Style::default().fg(theme.info))
         .alignment(Alignment::Center)
         .render(modal_chunks[1], buf);
 
     // Name field
-    let name_text = format!("Name: {}", app.folders_state.new_cloud_name);
+    let name_text = input_line(
+        "Name: ",
+        &app.folders_state.new_cloud_name,
+        true,
+        None,
+        modal_chunks[2].width,
+    );
     Paragraph::new(name_text)
         .style(
             Style::default()
-                .fg(Color::Green)
+                .fg(theme.success)
                 .add_modifier(Modifier::BOLD),
         )
         .alignment(Alignment::Left)
@@ -476,14 +798,14 @@ fn render_cloud_creation_modal(app: &App, area: Rect, buf: &mut Buffer) {
     let folders_block = Block::default()
         .borders(Borders::ALL)
         .title(format!("Selected Cloud Folders ({})", selected_count))
-        .border_style(Style::default().fg(Color::Cyan));
+        .border_style(Style::default().fg(theme.border));
 
     if selected_folder_names.is_empty() {
         Paragraph::new(
             "⚠️  NO CLOUD FOLDERS SELECTED!\n\nTo create a cloud:\n1. Press Esc to close this modal\n2. Select cloud folders using <leader> key\n3. Press 'n' again to create cloud\n\nCurrently selected: 0 folders",
         )
         .block(folders_block)
-        .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+        .style(Style::default().fg(theme.error).add_modifier(Modifier::BOLD))
         .alignment(Alignment::Center)
         .render(modal_chunks[3], buf);
     } else {
@@ -494,7 +816,7 @@ fn render_cloud_creation_modal(app: &App, area: Rect, buf: &mut Buffer) {
 
         let folders_list = List::new(folder_items)
             .block(folders_block)
-            .style(Style::default().fg(Color::White));
+            .style(Style::default().fg(theme.text));
 
         Widget::render(folders_list, modal_chunks[3], buf);
     }
@@ -502,7 +824,11 @@ fn render_cloud_creation_modal(app: &App, area: Rect, buf: &mut Buffer) {
     // Error message or help text
     if let Some(ref error) = app.folders_state.cloud_creation_error {
         Paragraph::new(format!("❌ {}", error))
-            .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+            .style(
+                Style::default()
+                    .fg(theme.error)
+                    .add_modifier(Modifier::BOLD),
+            )
             .alignment(Alignment::Center)
             .render(modal_chunks[4], buf);
     } else if selected_count > 0 {
@@ -510,16 +836,17 @@ fn render_cloud_creation_modal(app: &App, area: Rect, buf: &mut Buffer) {
             "Creating cloud with {} cloud folder(s).\nAfter creation, you'll set a password.\nPress Enter to confirm.",
             selected_count
         ))
-        .style(Style::default().fg(Color::Gray))
+        .style(Style::default().fg(theme.hint))
         .alignment(Alignment::Center)
         .render(modal_chunks[4], buf);
     }
 }
 
 fn render_folder_edit_modal(app: &App, area: Rect, buf: &mut Buffer) {
+    let theme = app.theme();
     // Create a centered modal
-    let modal_width = 60;
-    let modal_height = 16;
+    let modal_width = 60.min(area.width);
+    let modal_height = 18.min(area.height);
     let x = (area.width.saturating_sub(modal_width)) / 2;
     let y = (area.height.saturating_sub(modal_height)) / 2;
 
@@ -536,6 +863,7 @@ fn render_folder_edit_modal(app: &App, area: Rect, buf: &mut Buffer) {
             Constraint::Length(2), // Instructions
             Constraint::Length(3), // Name field
             Constraint::Length(3), // Path field
+            Constraint::Length(2), // Path preview
             Constraint::Length(2), // Error/Help
             Constraint::Min(0),    // Spacer
         ])
@@ -548,7 +876,7 @@ fn render_folder_edit_modal(app: &App, area: Rect, buf: &mut Buffer) {
         .title_alignment(Alignment::Center)
         .border_style(
             Style::default()
-                .fg(Color::Yellow)
+                .fg(theme.focused_border)
                 .add_modifier(Modifier::BOLD),
         );
 
@@ -558,7 +886,7 @@ fn render_folder_edit_modal(app: &App, area: Rect, buf: &mut Buffer) {
 
     // Instructions
     Paragraph::new("Tab to switch fields, Enter to save, Esc to cancel")
-        .style(Style::default().fg(Color::Cyan))
+        .style(Style::default().fg(theme.info))
         .alignment(Alignment::Center)
         .render(modal_chunks[1], buf);
 
@@ -568,13 +896,19 @@ fn render_folder_edit_modal(app: &App, area: Rect, buf: &mut Buffer) {
         crate::tabs::folders::models::FolderInputField::Name
     ) {
         Style::default()
-            .fg(Color::Green)
+            .fg(theme.success)
             .add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(Color::White)
+        Style::default().fg(theme.text)
     };
 
-    let name_text = format!("Name: {}", app.folders_state.edit_folder_name);
+    let name_text = input_line(
+        "Name: ",
+        &app.folders_state.edit_folder_name,
+        app.folders_state.edit_folder_input_field == FolderInputField::Name,
+        None,
+        modal_chunks[2].width,
+    );
     Paragraph::new(name_text)
         .style(name_style)
         .alignment(Alignment::Left)
@@ -586,36 +920,51 @@ fn render_folder_edit_modal(app: &App, area: Rect, buf: &mut Buffer) {
         crate::tabs::folders::models::FolderInputField::Path
     ) {
         Style::default()
-            .fg(Color::Green)
+            .fg(theme.success)
             .add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(Color::White)
+        Style::default().fg(theme.text)
     };
 
-    let path_text = format!("Path: {}", app.folders_state.edit_folder_path);
+    let path_text = input_line(
+        "Path: ",
+        &app.folders_state.edit_folder_path,
+        app.folders_state.edit_folder_input_field == FolderInputField::Path,
+        None,
+        modal_chunks[3].width,
+    );
     Paragraph::new(path_text)
         .style(path_style)
         .alignment(Alignment::Left)
         .render(modal_chunks[3], buf);
 
+    render_folder_preview(app, modal_chunks[4], buf);
+
     // Error message or help text
     if let Some(ref error) = app.folders_state.folder_edit_error {
         Paragraph::new(format!("❌ {}", error))
-            .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+            .style(
+                Style::default()
+                    .fg(theme.error)
+                    .add_modifier(Modifier::BOLD),
+            )
             .alignment(Alignment::Center)
-            .render(modal_chunks[4], buf);
+            .render(modal_chunks[5], buf);
     } else {
         Paragraph::new("Edit both fields and press Enter to save")
-            .style(Style::default().fg(Color::Gray))
+            .style(Style::default().fg(theme.hint))
             .alignment(Alignment::Center)
-            .render(modal_chunks[4], buf);
+            .render(modal_chunks[5], buf);
     }
+
+    render_path_completions(app, modal_chunks[6], buf);
 }
 
 fn render_cloud_edit_modal(app: &App, area: Rect, buf: &mut Buffer) {
+    let theme = app.theme();
     // Create a centered modal
-    let modal_width = 70;
-    let modal_height = 20;
+    let modal_width = 70.min(area.width);
+    let modal_height = 20.min(area.height);
     let x = (area.width.saturating_sub(modal_width)) / 2;
     let y = (area.height.saturating_sub(modal_height)) / 2;
 
@@ -643,7 +992,7 @@ fn render_cloud_edit_modal(app: &App, area: Rect, buf: &mut Buffer) {
         .title_alignment(Alignment::Center)
         .border_style(
             Style::default()
-                .fg(Color::Yellow)
+                .fg(theme.focused_border)
                 .add_modifier(Modifier::BOLD),
         );
 
@@ -667,21 +1016,27 @@ fn render_cloud_edit_modal(app: &App, area: Rect, buf: &mut Buffer) {
         }
     };
     Paragraph::new(instructions)
-        .style(Style::default().fg(Color::Cyan))
+        .style(Style::default().fg(theme.info))
         .alignment(Alignment::Center)
         .render(modal_chunks[1], buf);
 
     // Name field
-    let name_text = format!("Name: {}", app.folders_state.edit_cloud_name);
+    let name_text = input_line(
+        "Name: ",
+        &app.folders_state.edit_cloud_name,
+        app.folders_state.cloud_edit_focus == crate::tabs::folders::models::CloudEditFocus::Name,
+        None,
+        modal_chunks[2].width,
+    );
     let name_style = if app.folders_state.cloud_edit_focus
         == crate::tabs::folders::models::CloudEditFocus::Name
     {
         Style::default()
-            .fg(Color::Yellow)
+            .fg(theme.focused_border)
             .add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
     } else {
         Style::default()
-            .fg(Color::Green)
+            .fg(theme.success)
             .add_modifier(Modifier::BOLD)
     };
     Paragraph::new(name_text)
@@ -700,10 +1055,10 @@ fn render_cloud_edit_modal(app: &App, area: Rect, buf: &mut Buffer) {
                 == crate::tabs::folders::models::CloudEditFocus::Folders
             {
                 Style::default()
-                    .fg(Color::Yellow)
+                    .fg(theme.focused_border)
                     .add_modifier(Modifier::BOLD)
             } else {
-                Style::default().fg(Color::Cyan)
+                Style::default().fg(theme.border)
             },
         );
 
@@ -717,9 +1072,9 @@ fn render_cloud_edit_modal(app: &App, area: Rect, buf: &mut Buffer) {
             let radio_indicator = if is_selected { "●" } else { "○" };
 
             let style = if index == app.folders_state.selected_folder_index {
-                Style::default().fg(Color::Yellow)
+                Style::default().fg(theme.selection)
             } else if is_selected {
-                Style::default().fg(Color::Cyan)
+                Style::default().fg(theme.marked)
             } else {
                 Style::default()
             };
@@ -736,7 +1091,11 @@ fn render_cloud_edit_modal(app: &App, area: Rect, buf: &mut Buffer) {
     // Error message or help text
     if let Some(ref error) = app.folders_state.cloud_edit_error {
         Paragraph::new(format!("❌ {}", error))
-            .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+            .style(
+                Style::default()
+                    .fg(theme.error)
+                    .add_modifier(Modifier::BOLD),
+            )
             .alignment(Alignment::Center)
             .render(modal_chunks[4], buf);
     } else if selected_count > 0 {
@@ -744,8 +1103,256 @@ fn render_cloud_edit_modal(app: &App, area: Rect, buf: &mut Buffer) {
             "Editing cloud with {} folder(s). Press Enter to save.",
             selected_count
         ))
-        .style(Style::default().fg(Color::Gray))
+        .style(Style::default().fg(theme.hint))
         .alignment(Alignment::Center)
         .render(modal_chunks[4], buf);
     }
 }
+
+fn render_directory_creation_modal(app: &App, area: Rect, buf: &mut Buffer) {
+    let theme = app.theme();
+    // Create a centered modal
+    let modal_width = 60.min(area.width);
+    let modal_height = 10.min(area.height);
+    let x = (area.width.saturating_sub(modal_width)) / 2;
+    let y = (area.height.saturating_sub(modal_height)) / 2;
+
+    let modal_area = Rect::new(area.x + x, area.y + y, modal_width, modal_height);
+
+    // Clear the modal area
+    Clear.render(modal_area, buf);
+
+    let modal_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Border
+            Constraint::Length(2), // Instructions
+            Constraint::Length(2), // Name field
+            Constraint::Length(2), // Error/Help
+            Constraint::Min(0),    // Spacer
+        ])
+        .split(modal_area);
+
+    let title_block = Block::default()
+        .borders(Borders::ALL)
+        .title("📁 New Directory")
+        .title_alignment(Alignment::Center)
+        .border_style(
+            Style::default()
+                .fg(theme.focused_border)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    Paragraph::new("")
+        .block(title_block)
+        .render(modal_area, buf);
+
+    Paragraph::new("Enter to create, Esc to cancel")
+        .style(Style::default().fg(theme.info))
+        .alignment(Alignment::Center)
+        .render(modal_chunks[1], buf);
+
+    let files = &app.folders_state.files;
+    Paragraph::new(input_line(
+        " Name: ",
+        &files.new_directory_name,
+        true,
+        None,
+        modal_chunks[2].width,
+    ))
+    .style(
+        Style::default()
+            .fg(theme.success)
+            .add_modifier(Modifier::BOLD),
+    )
+    .render(modal_chunks[2], buf);
+
+    if let Some(ref error) = files.directory_creation_error {
+        Paragraph::new(format!("❌ {}", error))
+            .style(
+                Style::default()
+                    .fg(theme.error)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .alignment(Alignment::Center)
+            .render(modal_chunks[3], buf);
+    } else {
+        Paragraph::new(format!("Created in {}", files.relative_location()))
+            .style(Style::default().fg(theme.hint))
+            .alignment(Alignment::Center)
+            .render(modal_chunks[3], buf);
+    }
+}
+
+/// Completion candidates under the path field, or a hint on how to get them
+/// What we know about the typed path: existence, permissions and rough size
+fn render_folder_preview(app: &App, area: Rect, buf: &mut Buffer) {
+    let theme = app.theme();
+    let area = Rect::new(
+        area.x + 2,
+        area.y,
+        area.width.saturating_sub(4),
+        area.height,
+    );
+    let input = if app.folders_state.creating_folder {
+        &app.folders_state.new_folder_path
+    } else {
+        &app.folders_state.edit_folder_path
+    };
+    if input.trim().is_empty() {
+        return;
+    }
+
+    let ok = Style::default().fg(theme.success);
+    let warn = Style::default().fg(theme.selection);
+    let bad = Style::default().fg(theme.error);
+    let lines = match app.folders_state.current_preview() {
+        None => vec![Line::styled(
+            "⏳ Checking path...",
+            Style::default().fg(theme.dim),
+        )],
+        Some(preview) if !preview.exists => {
+            vec![Line::styled("❌ Path does not exist", bad)]
+        }
+        Some(preview) if !preview.is_dir => {
+            vec![Line::styled("❌ Path is not a directory", bad)]
+        }
+        Some(preview) if !preview.readable => {
+            vec![Line::styled("❌ Directory is not readable", bad)]
+        }
+        Some(preview) => {
+            let access = if preview.writable {
+                Span::styled("✅ Readable, writable", ok)
+            } else {
+                Span::styled("⚠️  Read-only: uploads will fail", warn)
+            };
+            let approx = if preview.truncated { "more than " } else { "" };
+            vec![
+                Line::from(access),
+                Line::styled(
+                    format!(
+                        "📊 {}{} files, {} folders, {}",
+                        approx,
+                        preview.file_count,
+                        preview.dir_count,
+                        format_bytes(preview.total_bytes)
+                    ),
+                    Style::default().fg(theme.hint),
+                ),
+            ]
+        }
+    };
+    Paragraph::new(lines).render(area, buf);
+}
+
+fn render_path_completions(app: &App, area: Rect, buf: &mut Buffer) {
+    let theme = app.theme();
+    // Stay inside the modal border
+    let area = Rect::new(
+        area.x + 2,
+        area.y,
+        area.width.saturating_sub(4),
+        area.height.saturating_sub(1),
+    );
+    let completion = &app.folders_state.path_completion;
+
+    if completion.candidates.is_empty() {
+        Paragraph::new("Path: Tab to complete, ↓ to browse, ~ for home")
+            .style(Style::default().fg(theme.dim))
+            .alignment(Alignment::Center)
+            .render(area, buf);
+        return;
+    }
+
+    let mut spans = Vec::new();
+    for (i, candidate) in completion.candidates.iter().enumerate() {
+        // Only the last path component, the rest is already in the field
+        let name = candidate
+            .trim_end_matches('/')
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or(candidate);
+        let style = if completion.index == Some(i) {
+            Style::default().fg(Color::Black).bg(theme.marked)
+        } else {
+            Style::default().fg(theme.marked)
+        };
+        spans.push(Span::styled(format!("{}/", name), style));
+        spans.push(Span::raw("  "));
+    }
+    Paragraph::new(Line::from(spans))
+        .wrap(Wrap { trim: true })
+        .render(area, buf);
+}
+
+fn render_dir_picker(theme: &Theme, picker: &DirPicker, area: Rect, buf: &mut Buffer) {
+    let modal_width = 70.min(area.width);
+    let modal_height = 20.min(area.height);
+    let x = (area.width.saturating_sub(modal_width)) / 2;
+    let y = (area.height.saturating_sub(modal_height)) / 2;
+
+    let modal_area = Rect::new(area.x + x, area.y + y, modal_width, modal_height);
+
+    // Clear the modal area
+    Clear.render(modal_area, buf);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("📂 Pick a Directory")
+        .title_alignment(Alignment::Center)
+        .title_bottom(
+            Line::from(" Enter pick, . pick current, l/h in/out, H hidden, Esc cancel ").centered(),
+        )
+        .border_style(
+            Style::default()
+                .fg(theme.focused_border)
+                .add_modifier(Modifier::BOLD),
+        );
+    let inner = block.inner(modal_area);
+    block.render(modal_area, buf);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Current directory
+            Constraint::Min(0),    // Sub-directories
+        ])
+        .split(inner);
+
+    Paragraph::new(format!("📁 {}", picker.current_dir.display()))
+        .style(
+            Style::default()
+                .fg(theme.success)
+                .add_modifier(Modifier::BOLD),
+        )
+        .render(chunks[0], buf);
+
+    if let Some(ref error) = picker.error {
+        Paragraph::new(format!("❌ {}", error))
+            .style(
+                Style::default()
+                    .fg(theme.error)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .wrap(Wrap { trim: true })
+            .render(chunks[1], buf);
+        return;
+    }
+    if picker.entries.is_empty() {
+        Paragraph::new("  (no sub-directories)")
+            .style(Style::default().fg(theme.dim))
+            .render(chunks[1], buf);
+        return;
+    }
+
+    let items: Vec<ListItem> = picker
+        .entries
+        .iter()
+        .map(|name| ListItem::new(format!("  {}/", name)))
+        .collect();
+    let list = List::new(items)
+        .highlight_style(Style::default().bg(theme.dim).fg(theme.text))
+        .highlight_symbol(">> ");
+    let mut list_state = ListState::default().with_selected(Some(picker.selected));
+    StatefulWidget::render(list, chunks[1], buf, &mut list_state);
+}
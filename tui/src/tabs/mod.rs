@@ -1,3 +1,4 @@
+pub mod client;
 pub mod clouds;
 pub mod focus;
 pub mod folders;
@@ -1,27 +1,78 @@
+use crate::config::Config;
 use crate::tabs::focus::TabFocus;
+use crate::tabs::SelectedTab;
+use crate::utils::server_defaults::{DefaultEditor, DefaultField};
 use ratatui::crossterm::event::KeyCode;
 use ratatui::widgets::{ListState, ScrollbarState};
+use strum::IntoEnumIterator;
+
+/// Index of the first server default in the general list, see `DefaultField::ALL`
+pub const DEFAULTS_START: usize = 38;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SettingsFocusedPanel {
+    #[default]
+    General,
+    Keybindings,
+}
+
+/// A rebind in progress: keys pressed so far for the selected action
+#[derive(Debug, Clone, Default)]
+pub struct KeybindingCapture {
+    pub action: String,
+    pub keys: Vec<String>,
+    /// Add the new key instead of replacing the existing ones
+    pub append: bool,
+    /// Actions that already use the captured key on an overlapping tab
+    pub conflicts: Vec<String>,
+}
+
+impl KeybindingCapture {
+    /// The captured chord as stored in the config, e.g. `gt` or `<leader>p`
+    pub fn key(&self) -> String {
+        self.keys.concat()
+    }
+}
 
 #[derive(Default)]
 pub struct SettingsState {
     pub list_state: ListState,
     pub scroll_state: ScrollbarState,
+    pub focused_panel: SettingsFocusedPanel,
+    pub keybindings_list_state: ListState,
+    pub keybindings_scroll_state: ScrollbarState,
+    pub selected_keybinding: usize,
+    pub keybinding_count: usize,
+    pub capture: Option<KeybindingCapture>,
+    pub keybinding_message: Option<String>,
+    pub keybinding_error: Option<String>,
+    /// The server default being edited, if any
+    pub default_editor: Option<DefaultEditor>,
 }
 
 impl TabFocus for SettingsState {
     fn get_focused_element(&self) -> String {
-        "SettingsList".to_string()
+        match self.focused_panel {
+            SettingsFocusedPanel::General => "SettingsList".to_string(),
+            SettingsFocusedPanel::Keybindings => "KeybindingsList".to_string(),
+        }
     }
 
     fn cycle_focus_forward(&mut self) {
-        // Settings tab is just one scrollable column, no focus cycling needed
+        self.focused_panel = match self.focused_panel {
+            SettingsFocusedPanel::General => SettingsFocusedPanel::Keybindings,
+            SettingsFocusedPanel::Keybindings => SettingsFocusedPanel::General,
+        };
     }
 
     fn cycle_focus_backward(&mut self) {
-        // Settings tab is just one scrollable column, no focus cycling needed
+        self.cycle_focus_forward();
     }
 
     fn handle_navigation(&mut self, key: KeyCode) -> bool {
+        if self.focused_panel == SettingsFocusedPanel::Keybindings {
+            return self.handle_keybindings_navigation(key);
+        }
         match key {
             KeyCode::Up | KeyCode::Char('k') => {
                 if let Some(selected) = self.list_state.selected() {
@@ -53,13 +104,20 @@ impl TabFocus for SettingsState {
 
 impl SettingsState {
     pub fn new() -> Self {
-        Self {
-            list_state: ListState::default(),
-            scroll_state: ScrollbarState::default(),
-        }
+        Self::default()
+    }
+
+    /// The server default selected in the general list
+    pub fn selected_default_field(&self) -> Option<DefaultField> {
+        let selected = self.list_state.selected()?;
+        DefaultField::ALL
+            .get(selected.checked_sub(DEFAULTS_START)?)
+            .copied()
     }
 
-    pub fn handle_enter(&self) {
+    /// Run the selected general setting.
+    /// Returns the name of an action the App should execute (for the reload entries).
+    pub fn handle_enter(&self) -> Option<&'static str> {
         if let Some(selected) = self.list_state.selected() {
             match selected {
                 0 => {
@@ -76,7 +134,13 @@ impl SettingsState {
                         log::error!("Failed to open clouds config file: {}", e);
                     }
                 }
-                6 => {
+                6 => return Some("Reload TUI Config"),
+                9 => return Some("Reload Clouds Config"),
+                12 => return Some("Reload All Configs"),
+                15 => return Some("Export Clouds Config"),
+                18 => return Some("Import Clouds Config"),
+                21 => return Some("Send Test Email"),
+                24 => {
                     // Reset TUI config to default
                     match crate::config::Config::reset_to_default() {
                         Ok(_) => {
@@ -88,8 +152,73 @@ impl SettingsState {
                         }
                     }
                 }
+                31 => return Some("Next Theme"),
+                34 => return Some("Run Diagnostics"),
                 _ => {}
             }
         }
+        None
+    }
+
+    // ========== Keybinding editor ==========
+
+    fn handle_keybindings_navigation(&mut self, key: KeyCode) -> bool {
+        if self.keybinding_count == 0 {
+            return false;
+        }
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected_keybinding = self.selected_keybinding.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected_keybinding =
+                    (self.selected_keybinding + 1).min(self.keybinding_count - 1);
+            }
+            KeyCode::Char('g') => self.selected_keybinding = 0,
+            KeyCode::Char('G') => self.selected_keybinding = self.keybinding_count - 1,
+            _ => return false,
+        }
+        self.keybindings_list_state
+            .select(Some(self.selected_keybinding));
+        true
+    }
+
+    /// Action names in display order: grouped by tab (global first), then by name
+    pub fn sorted_actions(config: &Config) -> Vec<String> {
+        let tab_rank = |tab: &str| {
+            if tab == "any" {
+                return 0;
+            }
+            SelectedTab::iter()
+                .position(|t| t.config_name() == tab)
+                .map(|i| i + 1)
+                .unwrap_or(usize::MAX)
+        };
+        let mut names: Vec<&String> = config.actions.keys().collect();
+        names.sort_by_key(|name| (tab_rank(&config.actions[*name].tab), name.to_string()));
+        names.into_iter().cloned().collect()
+    }
+
+    pub fn selected_action(&self, config: &Config) -> Option<String> {
+        Self::sorted_actions(config)
+            .get(self.selected_keybinding)
+            .cloned()
+    }
+
+    pub fn start_capture(&mut self, config: &Config, append: bool) {
+        if let Some(action) = self.selected_action(config) {
+            self.capture = Some(KeybindingCapture {
+                action,
+                append,
+                ..Default::default()
+            });
+            self.keybinding_message = None;
+            self.keybinding_error = None;
+        }
+    }
+
+    pub fn cancel_capture(&mut self) {
+        self.capture = None;
+        self.keybinding_error = None;
     }
 }
@@ -1,18 +1,42 @@
 use ratatui::{
     buffer::Buffer,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
-    widgets::{Block, Borders, List, ListItem, Scrollbar, ScrollbarOrientation, StatefulWidget},
+    style::{Modifier, Style},
+    widgets::{
+        Block, Borders, Clear, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation,
+        StatefulWidget, Widget, Wrap,
+    },
 };
 
+use crate::components::panel_switcher::render_panel_switcher;
+use crate::layout::panel_areas;
 use crate::models::App;
+use crate::tabs::settings::models::{SettingsFocusedPanel, SettingsState};
+use crate::theme::{Theme, ThemeName};
+use crate::utils::server_defaults::DefaultField;
+use cloudhost_server::config_paths::Overrides;
+use strum::IntoEnumIterator;
 
 pub fn render_settings_tab(app: &mut App, area: Rect, buf: &mut Buffer) {
-    // Create a single column layout
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Min(0)])
-        .split(area);
+    let theme = app.theme();
+    // Config files on the left, keybinding editor on the right, or above each other
+    let focused = match app.settings_state.focused_panel {
+        SettingsFocusedPanel::General => 0,
+        SettingsFocusedPanel::Keybindings => 1,
+    };
+    let layout = panel_areas(
+        app.config.layout.mode(area),
+        area,
+        [Constraint::Percentage(45), Constraint::Percentage(55)],
+        [Constraint::Percentage(45), Constraint::Percentage(55)],
+        focused,
+    );
+    if let Some(switcher) = layout.switcher {
+        render_panel_switcher(&theme, &["Settings", "Keybindings"], focused, switcher, buf);
+    }
+    let chunks = layout.panels;
+
+    render_keybindings_panel(app, chunks[1], buf);
 
     // Create config file information items
     let tui_config_path = cloudhost_server::config_paths::get_tui_config_path();
@@ -31,6 +55,17 @@ pub fn render_settings_tab(app: &mut App, area: Rect, buf: &mut Buffer) {
         .config
         .get_keys_for_action("Reload All Configs")
         .join(", ");
+    let export_keys = app
+        .config
+        .get_keys_for_action("Export Clouds Config")
+        .join(", ");
+    let import_keys = app
+        .config
+        .get_keys_for_action("Import Clouds Config")
+        .join(", ");
+    let test_email_keys = app.config.get_keys_for_action("Send Test Email").join(", ");
+    let next_theme_keys = app.config.get_keys_for_action("Next Theme").join(", ");
+    let diagnostics_keys = app.config.get_keys_for_action("Run Diagnostics").join(", ");
 
     let mut items = vec![
         ListItem::new("📄 TUI Config File"),
@@ -48,6 +83,15 @@ pub fn render_settings_tab(app: &mut App, area: Rect, buf: &mut Buffer) {
         ListItem::new(format!("🔄 Reload All Configs ({})", reload_all_keys)),
         ListItem::new("   Reload both TUI and clouds configs"),
         ListItem::new(""),
+        ListItem::new(format!("📤 Export Clouds Config ({})", export_keys)),
+        ListItem::new("   Portable file for another host (:export [--encrypt] <path>)"),
+        ListItem::new(""),
+        ListItem::new(format!("📥 Import Clouds Config ({})", import_keys)),
+        ListItem::new("   Add folders and clouds from an export (:import <path>)"),
+        ListItem::new(""),
+        ListItem::new(format!("📧 Send Test Email ({})", test_email_keys)),
+        ListItem::new("   From every cloud with [clouds.email] configured"),
+        ListItem::new(""),
     ];
 
     items.push(ListItem::new("🔄 Reset TUI Config to Default"));
@@ -55,17 +99,82 @@ pub fn render_settings_tab(app: &mut App, area: Rect, buf: &mut Buffer) {
         "   ⚠️  This will delete your current keybinds and restore defaults",
     ));
     items.push(ListItem::new("   ℹ️  Restart the app to see the changes"));
+    items.push(ListItem::new(""));
+
+    let secrets_status = match app.orchestrator.secrets_protection() {
+        None => "plain text".to_string(),
+        Some(protection) if app.orchestrator.secrets_locked() => {
+            format!("encrypted with the {} (locked)", protection)
+        }
+        Some(protection) => format!("encrypted with the {}", protection),
+    };
+    items.push(ListItem::new(format!(
+        "🔐 Cloud Secrets: {}",
+        secrets_status
+    )));
+    items.push(ListItem::new(
+        "   Change with :secrets passphrase|keyring|off, unlock with :secrets unlock",
+    ));
+    items.push(ListItem::new(""));
+
+    let theme_names: Vec<String> = ThemeName::iter().map(|name| name.to_string()).collect();
+    items.push(ListItem::new(format!(
+        "🎨 Theme: {} ({})",
+        app.config.theme.name, next_theme_keys
+    )));
+    items.push(ListItem::new(format!(
+        "   Enter for the next one: {}",
+        theme_names.join(", ")
+    )));
+    items.push(ListItem::new(""));
+
+    items.push(ListItem::new(format!(
+        "🩺 Diagnostics ({})",
+        diagnostics_keys
+    )));
+    items.push(ListItem::new(
+        "   Configs, folders, ports, firewall, certificates, reachability and clock",
+    ));
+    items.push(ListItem::new(""));
+
+    items.push(ListItem::new("🛠️  Server Defaults (Enter to edit)"));
+    // What the clouds get, environment overrides included
+    let defaults = app.orchestrator.defaults();
+    let overrides = Overrides::from_env().unwrap_or_default();
+    for field in DefaultField::ALL {
+        let applies = if field.overridden_by(&overrides).is_some() {
+            "overridden"
+        } else if field.applies_right_away() {
+            "right away"
+        } else {
+            "on next start"
+        };
+        items.push(ListItem::new(format!(
+            "   {:<24}{:<20}{}",
+            format!("{}:", field.label()),
+            field.display(&defaults),
+            applies
+        )));
+    }
 
     // Create the list
     let list = List::new(items.clone())
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("⚙️  Settings - Config Files")
+                .title(
+                    if app.settings_state.focused_panel == SettingsFocusedPanel::General {
+                        "⚙️  Settings - Config Files (FOCUSED)"
+                    } else {
+                        "⚙️  Settings - Config Files"
+                    },
+                )
                 .title_alignment(Alignment::Center)
-                .border_style(Style::default().fg(Color::Cyan)),
+                .border_style(Style::default().fg(
+                    theme.border(app.settings_state.focused_panel == SettingsFocusedPanel::General),
+                )),
         )
-        .highlight_style(Style::default().fg(Color::Yellow))
+        .highlight_style(Style::default().fg(theme.selection))
         .highlight_symbol(">> ");
 
     // Render the list with state
@@ -81,8 +190,149 @@ pub fn render_settings_tab(app: &mut App, area: Rect, buf: &mut Buffer) {
     let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
         .begin_symbol(Some("↑"))
         .end_symbol(Some("↓"));
-    scrollbar.render(chunks[0], buf, &mut scroll_state);
+    if !chunks[0].is_empty() {
+        scrollbar.render(chunks[0], buf, &mut scroll_state);
+    }
 
     // Update the persistent scroll state
     app.settings_state.scroll_state = scroll_state;
 }
+
+fn render_keybindings_panel(app: &mut App, area: Rect, buf: &mut Buffer) {
+    let theme = app.theme();
+    let focused = app.settings_state.focused_panel == SettingsFocusedPanel::Keybindings;
+    let actions = SettingsState::sorted_actions(&app.config);
+    app.settings_state.keybinding_count = actions.len();
+    if app.settings_state.selected_keybinding >= actions.len() {
+        app.settings_state.selected_keybinding = actions.len().saturating_sub(1);
+    }
+    if focused {
+        app.settings_state
+            .keybindings_list_state
+            .select(Some(app.settings_state.selected_keybinding));
+    } else {
+        app.settings_state.keybindings_list_state.select(None);
+    }
+
+    // Nothing fits, and a scrollbar can't be drawn in no width
+    if area.is_empty() {
+        return;
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(4)])
+        .split(area);
+
+    let name_width = actions.iter().map(|a| a.len()).max().unwrap_or(0);
+    let items: Vec<ListItem> = actions
+        .iter()
+        .map(|name| {
+            let action = &app.config.actions[name];
+            let keys = if action.keys.is_empty() {
+                "(unbound)".to_string()
+            } else {
+                action.keys.join(", ")
+            };
+            ListItem::new(format!(
+                "{:<width$}  {:<10}  {}",
+                name,
+                action.tab,
+                keys,
+                width = name_width
+            ))
+        })
+        .collect();
+
+    let title = if focused {
+        "⌨️  Keybindings (FOCUSED)"
+    } else {
+        "⌨️  Keybindings"
+    };
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .title_alignment(Alignment::Center)
+                .border_style(Style::default().fg(theme.border(focused))),
+        )
+        .highlight_style(Style::default().fg(theme.selection))
+        .highlight_symbol(">> ");
+
+    StatefulWidget::render(
+        list,
+        chunks[0],
+        buf,
+        &mut app.settings_state.keybindings_list_state,
+    );
+
+    let mut scroll_state = app
+        .settings_state
+        .keybindings_scroll_state
+        .content_length(actions.len())
+        .position(app.settings_state.selected_keybinding);
+    Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(Some("↑"))
+        .end_symbol(Some("↓"))
+        .render(chunks[0], buf, &mut scroll_state);
+    app.settings_state.keybindings_scroll_state = scroll_state;
+
+    render_keybinding_status(app, &theme, chunks[1], buf);
+}
+
+fn render_keybinding_status(app: &App, theme: &Theme, area: Rect, buf: &mut Buffer) {
+    let state = &app.settings_state;
+    let keys = |action: &str| app.config.get_keys_for_action(action).join(", ");
+
+    let (text, style) = if let Some(ref capture) = state.capture {
+        let pressed = if capture.keys.is_empty() {
+            "…".to_string()
+        } else {
+            capture.key()
+        };
+        let text = match state.keybinding_error {
+            Some(ref error) => format!("❌ {}", error),
+            None => format!(
+                "{} '{}': {}\nEnter to confirm, Backspace to undo a key, Esc to cancel",
+                if capture.append {
+                    "Add key to"
+                } else {
+                    "Rebind"
+                },
+                capture.action,
+                pressed
+            ),
+        };
+        (
+            text,
+            Style::default()
+                .fg(theme.warning)
+                .add_modifier(Modifier::BOLD),
+        )
+    } else if let Some(ref error) = state.keybinding_error {
+        (format!("❌ {}", error), Style::default().fg(theme.error))
+    } else if let Some(ref message) = state.keybinding_message {
+        (
+            format!("✅ {}", message),
+            Style::default().fg(theme.success),
+        )
+    } else {
+        (
+            format!(
+                "{} to rebind, {} to add a key, {} to restore defaults",
+                keys("Execute Action"),
+                keys("Add Keybinding"),
+                keys("Reset Keybinding")
+            ),
+            Style::default().fg(theme.hint),
+        )
+    };
+
+    Clear.render(area, buf);
+    Paragraph::new(text)
+        .style(style)
+        .wrap(Wrap { trim: true })
+        .block(Block::default().borders(Borders::ALL))
+        .render(area, buf);
+}
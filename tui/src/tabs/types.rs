@@ -1,12 +1,13 @@
 use ratatui::{
     layout::Rect,
     prelude::Stylize,
-    style::palette::tailwind,
     symbols,
     widgets::{Block, Padding, Widget},
 };
 use strum::{Display, EnumIter, FromRepr, IntoEnumIterator};
 
+use crate::theme::Theme;
+
 #[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Display, EnumIter, FromRepr)]
 pub enum SelectedTab {
     #[default]
@@ -14,6 +15,8 @@ pub enum SelectedTab {
     Clouds,
     #[strum(to_string = "Folders")]
     Folders,
+    #[strum(to_string = "Client")]
+    Client,
     #[strum(to_string = "Settings")]
     Settings,
 }
@@ -40,27 +43,28 @@ impl SelectedTab {
     }
 
     /// A block surrounding the tab's content
-    pub fn block(self) -> Block<'static> {
+    pub fn block(self, theme: &Theme) -> Block<'static> {
         Block::bordered()
             .border_set(symbols::border::PROPORTIONAL_TALL)
             .padding(Padding::horizontal(1))
-            .border_style(self.palette().c700)
+            .border_style(theme.tab_accent(self))
     }
 
-    pub const fn palette(self) -> tailwind::Palette {
+    /// Name used for this tab in the `tab` field of keybindings
+    pub const fn config_name(self) -> &'static str {
         match self {
-            Self::Clouds => tailwind::BLUE,
-            Self::Folders => tailwind::GREEN,
-            Self::Settings => tailwind::INDIGO,
+            Self::Clouds => "clouds",
+            Self::Folders => "folders",
+            Self::Client => "client",
+            Self::Settings => "settings",
         }
     }
 
     /// Return tab's name as a styled `Line`
-    pub fn title(self) -> ratatui::text::Line<'static> {
-        use ratatui::style::palette::tailwind;
+    pub fn title(self, theme: &Theme) -> ratatui::text::Line<'static> {
         format!("  {self}  ")
-            .fg(tailwind::SLATE.c200)
-            .bg(self.palette().c900)
+            .fg(theme.tab_text)
+            .bg(theme.tab_background(self))
             .into()
     }
 }
@@ -75,16 +79,22 @@ impl SelectedTab {
         match self {
             Self::Clouds => crate::tabs::clouds::ui::render_servers_tab(app, area, buf),
             Self::Folders => crate::tabs::folders::ui::render_folders_tab(app, area, buf),
+            Self::Client => crate::tabs::client::ui::render_client_tab(app, area, buf),
             Self::Settings => crate::tabs::settings::ui::render_settings_tab(app, area, buf),
         }
     }
 }
 
 impl SelectedTab {
-    pub fn render_server_placeholder(self, area: Rect, buf: &mut ratatui::buffer::Buffer) {
+    pub fn render_server_placeholder(
+        self,
+        theme: &Theme,
+        area: Rect,
+        buf: &mut ratatui::buffer::Buffer,
+    ) {
         use ratatui::widgets::Paragraph;
         Paragraph::new("Server functionality - managed by App")
-            .block(self.block())
+            .block(self.block(theme))
             .render(area, buf);
     }
 }
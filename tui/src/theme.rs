@@ -0,0 +1,281 @@
+//! Colors of the TUI. A theme names each color by what it's for, so the renderers ask
+//! for `theme.focused_border` instead of `Color::Yellow`. The `[theme]` section of the
+//! TUI config picks one of the built-in themes and may override single colors:
+//!
+//! ```toml
+//! [theme]
+//! name = "light"
+//! colors = { selection = "magenta", log_debug = "#8888ff" }
+//! ```
+use ratatui::style::palette::tailwind;
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use strum::{Display, EnumIter, IntoEnumIterator};
+
+use crate::tabs::SelectedTab;
+
+/// The built-in themes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Display, EnumIter)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum ThemeName {
+    #[default]
+    Default,
+    /// For terminals with a light background
+    Light,
+    HighContrast,
+    /// Greys only
+    Monochrome,
+}
+
+impl ThemeName {
+    /// The theme after this one, wrapping around
+    pub fn next(self) -> Self {
+        let names: Vec<Self> = Self::iter().collect();
+        let index = names.iter().position(|name| *name == self).unwrap_or(0);
+        names[(index + 1) % names.len()]
+    }
+}
+
+/// `[theme]` in the TUI config
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThemeConfig {
+    #[serde(default)]
+    pub name: ThemeName,
+    /// Colors replacing the theme's, by the names in `Theme::COLOR_NAMES`: `red`,
+    /// `lightblue`, `#rrggbb` or a 256-color index
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub colors: BTreeMap<String, String>,
+}
+
+impl ThemeConfig {
+    /// The named theme with the overrides applied; ones that aren't valid are skipped
+    pub fn resolve(&self) -> Theme {
+        let mut theme = Theme::builtin(self.name);
+        for (name, value) in &self.colors {
+            if let Ok(color) = Color::from_str(value) {
+                theme.set(name, color);
+            }
+        }
+        theme
+    }
+
+    /// Problems with the overrides, one message each
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (name, value) in &self.colors {
+            if !Theme::COLOR_NAMES.contains(&name.as_str()) {
+                problems.push(format!(
+                    "theme.colors.{}: unknown color (expected one of {})",
+                    name,
+                    Theme::COLOR_NAMES.join(", ")
+                ));
+            } else if Color::from_str(value).is_err() {
+                problems.push(format!(
+                    "theme.colors.{}: '{}' is not a color name, #rrggbb or 0-255",
+                    name, value
+                ));
+            }
+        }
+        problems
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Panel borders
+    pub border: Color,
+    /// The border of the focused panel, and of modals
+    pub focused_border: Color,
+    /// The selected row of a list
+    pub selection: Color,
+    /// Marked rows, such as folders picked for a cloud, and directories
+    pub marked: Color,
+    /// Text of inactive form fields and lists in modals
+    pub text: Color,
+    /// Help text under forms
+    pub hint: Color,
+    /// Labels, tags and placeholders
+    pub dim: Color,
+    /// The field being typed in, and things that worked
+    pub success: Color,
+    pub warning: Color,
+    pub error: Color,
+    /// Instructions at the top of modals
+    pub info: Color,
+    pub log_error: Color,
+    pub log_warning: Color,
+    pub log_info: Color,
+    pub log_debug: Color,
+    /// Text of the tab titles
+    pub tab_text: Color,
+    /// Background of each tab's title, in tab order
+    pub tab_backgrounds: [Color; 4],
+    /// Each tab's highlight when selected, in tab order
+    pub tab_accents: [Color; 4],
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::builtin(ThemeName::Default)
+    }
+}
+
+impl Theme {
+    /// Colors `[theme.colors]` can set
+    pub const COLOR_NAMES: &'static [&'static str] = &[
+        "border",
+        "focused_border",
+        "selection",
+        "marked",
+        "text",
+        "hint",
+        "dim",
+        "success",
+        "warning",
+        "error",
+        "info",
+        "log_error",
+        "log_warning",
+        "log_info",
+        "log_debug",
+        "tab_text",
+    ];
+
+    pub fn builtin(name: ThemeName) -> Self {
+        let tailwind_tabs = || {
+            [
+                tailwind::BLUE,
+                tailwind::GREEN,
+                tailwind::ORANGE,
+                tailwind::INDIGO,
+            ]
+        };
+        match name {
+            ThemeName::Default => Self {
+                border: Color::Cyan,
+                focused_border: Color::Yellow,
+                selection: Color::Yellow,
+                marked: Color::Cyan,
+                text: Color::White,
+                hint: Color::Gray,
+                dim: Color::DarkGray,
+                success: Color::Green,
+                warning: Color::Yellow,
+                error: Color::Red,
+                info: Color::Cyan,
+                log_error: Color::Red,
+                log_warning: Color::Yellow,
+                log_info: Color::Green,
+                log_debug: Color::Blue,
+                tab_text: tailwind::SLATE.c200,
+                tab_backgrounds: tailwind_tabs().map(|palette| palette.c900),
+                tab_accents: tailwind_tabs().map(|palette| palette.c700),
+            },
+            ThemeName::Light => Self {
+                border: Color::Blue,
+                focused_border: Color::Magenta,
+                selection: Color::Magenta,
+                marked: Color::Blue,
+                text: Color::Black,
+                hint: Color::DarkGray,
+                dim: Color::Gray,
+                success: Color::Green,
+                warning: tailwind::AMBER.c700,
+                error: Color::Red,
+                info: Color::Blue,
+                log_error: Color::Red,
+                log_warning: tailwind::AMBER.c700,
+                log_info: Color::Green,
+                log_debug: Color::Blue,
+                tab_text: tailwind::SLATE.c900,
+                tab_backgrounds: tailwind_tabs().map(|palette| palette.c200),
+                tab_accents: tailwind_tabs().map(|palette| palette.c400),
+            },
+            ThemeName::HighContrast => Self {
+                border: Color::White,
+                focused_border: Color::LightYellow,
+                selection: Color::LightYellow,
+                marked: Color::LightCyan,
+                text: Color::White,
+                hint: Color::White,
+                dim: Color::Gray,
+                success: Color::LightGreen,
+                warning: Color::LightYellow,
+                error: Color::LightRed,
+                info: Color::LightCyan,
+                log_error: Color::LightRed,
+                log_warning: Color::LightYellow,
+                log_info: Color::LightGreen,
+                log_debug: Color::LightCyan,
+                tab_text: Color::White,
+                tab_backgrounds: [Color::Black; 4],
+                tab_accents: [Color::Blue; 4],
+            },
+            ThemeName::Monochrome => Self {
+                border: Color::Gray,
+                focused_border: Color::White,
+                selection: Color::White,
+                marked: Color::Gray,
+                text: Color::Gray,
+                hint: Color::Gray,
+                dim: Color::DarkGray,
+                success: Color::White,
+                warning: Color::White,
+                error: Color::White,
+                info: Color::Gray,
+                log_error: Color::White,
+                log_warning: Color::White,
+                log_info: Color::Gray,
+                log_debug: Color::DarkGray,
+                tab_text: Color::White,
+                tab_backgrounds: [Color::Black; 4],
+                tab_accents: [Color::DarkGray; 4],
+            },
+        }
+    }
+
+    /// Set the color called `name` in `COLOR_NAMES`; false for other names
+    pub fn set(&mut self, name: &str, color: Color) -> bool {
+        let field = match name {
+            "border" => &mut self.border,
+            "focused_border" => &mut self.focused_border,
+            "selection" => &mut self.selection,
+            "marked" => &mut self.marked,
+            "text" => &mut self.text,
+            "hint" => &mut self.hint,
+            "dim" => &mut self.dim,
+            "success" => &mut self.success,
+            "warning" => &mut self.warning,
+            "error" => &mut self.error,
+            "info" => &mut self.info,
+            "log_error" => &mut self.log_error,
+            "log_warning" => &mut self.log_warning,
+            "log_info" => &mut self.log_info,
+            "log_debug" => &mut self.log_debug,
+            "tab_text" => &mut self.tab_text,
+            _ => return false,
+        };
+        *field = color;
+        true
+    }
+
+    /// Border color of a panel
+    pub fn border(&self, focused: bool) -> Color {
+        if focused {
+            self.focused_border
+        } else {
+            self.border
+        }
+    }
+
+    pub fn tab_background(&self, tab: SelectedTab) -> Color {
+        self.tab_backgrounds[tab as usize]
+    }
+
+    pub fn tab_accent(&self, tab: SelectedTab) -> Color {
+        self.tab_accents[tab as usize]
+    }
+}
@@ -0,0 +1,66 @@
+//! Copying cloud URLs, folder paths and file request links to the system clipboard.
+//! Over SSH, or without a clipboard at all, the text is shown to be selected by hand.
+
+/// Text that couldn't be copied, shown in a modal until closed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CopyFallback {
+    /// What the text is, e.g. "URL of 'family'"
+    pub what: String,
+    pub text: String,
+    /// Why it wasn't copied
+    pub reason: String,
+}
+
+/// The system clipboard, opened on the first copy. It stays open because on X11 the
+/// copied text only lasts as long as the program that copied it holds on to it.
+#[derive(Default)]
+pub struct Clipboard {
+    #[cfg(feature = "desktop")]
+    system: Option<arboard::Clipboard>,
+    /// Never use the system clipboard, as over SSH
+    disabled: bool,
+}
+
+impl Clipboard {
+    /// A clipboard that always falls back to showing the text
+    pub fn disabled() -> Self {
+        Self {
+            disabled: true,
+            ..Self::default()
+        }
+    }
+
+    /// Put `text` on the clipboard, or say why it can't be
+    pub fn copy(&mut self, text: &str) -> Result<(), String> {
+        if self.disabled {
+            return Err("clipboard turned off".to_string());
+        }
+        // The clipboard would be the server's, not the one of the machine in front of you
+        if ssh_session() {
+            return Err("no clipboard over SSH".to_string());
+        }
+        self.copy_to_system(text)
+    }
+
+    #[cfg(feature = "desktop")]
+    fn copy_to_system(&mut self, text: &str) -> Result<(), String> {
+        let system = match &mut self.system {
+            Some(system) => system,
+            None => self
+                .system
+                .insert(arboard::Clipboard::new().map_err(|e| e.to_string())?),
+        };
+        system.set_text(text).map_err(|e| e.to_string())
+    }
+
+    #[cfg(not(feature = "desktop"))]
+    fn copy_to_system(&mut self, _text: &str) -> Result<(), String> {
+        Err("no clipboard in this build".to_string())
+    }
+}
+
+/// Whether the TUI runs over SSH, where the clipboard, browser and file manager at hand
+/// are the server's
+pub fn ssh_session() -> bool {
+    std::env::var_os("SSH_CONNECTION").is_some() || std::env::var_os("SSH_TTY").is_some()
+}
@@ -0,0 +1,431 @@
+/// Vim-style `:` command line: input, history, completion and parsing.
+/// Executing the parsed commands is left to the App.
+use crate::utils::path_input::{complete_path, expand_home};
+use cloudhost_server::api_tokens::ApiTokenScope;
+use std::path::PathBuf;
+
+/// Commands with arguments; everything else is looked up as an action name
+pub const COMMANDS: [&str; 13] = [
+    "start",
+    "stop",
+    "tag",
+    "password",
+    "token",
+    "addfolder",
+    "addcloud",
+    "export",
+    "import",
+    "secrets",
+    "webhooks",
+    "profile",
+    "quit",
+];
+
+/// Arguments of `:secrets`
+const SECRETS_ARGS: [&str; 4] = ["passphrase", "keyring", "off", "unlock"];
+
+/// Subcommands of `:token`
+const TOKEN_ARGS: [&str; 3] = ["list", "create", "revoke"];
+
+const MAX_HISTORY: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandTarget {
+    All,
+    Cloud(String),
+    /// `tag:<tag>`, every cloud with the tag
+    Tag(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Start(CommandTarget),
+    Stop(CommandTarget),
+    Password(String),
+    /// Replace a cloud's tags, none to remove them
+    Tag {
+        cloud: String,
+        tags: Vec<String>,
+    },
+    AddFolder {
+        name: String,
+        path: PathBuf,
+    },
+    /// A cloud of existing cloud folders
+    AddCloud {
+        name: String,
+        folders: Vec<String>,
+    },
+    /// Write the clouds config to a file, encrypted with a passphrase or without secrets
+    Export {
+        path: PathBuf,
+        encrypt: bool,
+    },
+    Import(PathBuf),
+    Secrets(SecretsCommand),
+    /// Manage a cloud's API tokens
+    Token {
+        cloud: String,
+        command: TokenCommand,
+    },
+    /// Show a cloud's webhook deliveries
+    Webhooks(String),
+    /// Switch to a profile, or pick one when none is given
+    Profile(Option<String>),
+    /// Any action from the TUI config, e.g. `:reload all configs`
+    Action(String),
+}
+
+/// How cloud secrets should be stored, or unlock them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretsCommand {
+    Passphrase,
+    Keyring,
+    Off,
+    Unlock,
+}
+
+/// `:token <cloud> list|create|revoke ...`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenCommand {
+    List,
+    Create { name: String, scope: ApiTokenScope },
+    Revoke(String),
+}
+
+#[derive(Default)]
+pub struct CommandLineState {
+    pub active: bool,
+    pub input: String,
+    pub history: Vec<String>,
+    history_index: Option<usize>,
+    /// Candidates for the word being completed and the one currently inserted
+    pub completions: Vec<String>,
+    completion_index: Option<usize>,
+    /// Result of the last command, shown in the footer until the next key press
+    pub message: Option<String>,
+    pub message_is_error: bool,
+}
+
+impl CommandLineState {
+    pub fn open(&mut self) {
+        self.active = true;
+        self.input.clear();
+        self.history_index = None;
+        self.reset_completion();
+        self.message = None;
+    }
+
+    /// Open with `input` already typed, e.g. a command waiting for its argument
+    pub fn open_with(&mut self, input: &str) {
+        self.open();
+        self.input = input.to_string();
+    }
+
+    pub fn close(&mut self) {
+        self.active = false;
+        self.input.clear();
+        self.history_index = None;
+        self.reset_completion();
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        self.input.push(c);
+        self.reset_completion();
+    }
+
+    /// Remove the last character; returns false when the line was already empty
+    pub fn pop_char(&mut self) -> bool {
+        self.reset_completion();
+        self.input.pop().is_some()
+    }
+
+    pub fn set_message(&mut self, message: impl Into<String>, is_error: bool) {
+        self.message = Some(message.into());
+        self.message_is_error = is_error;
+    }
+
+    /// Take the current line, recording it in the history
+    pub fn submit(&mut self) -> String {
+        let line = self.input.trim().to_string();
+        if !line.is_empty() && self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+            if self.history.len() > MAX_HISTORY {
+                self.history.remove(0);
+            }
+        }
+        self.close();
+        line
+    }
+
+    pub fn history_previous(&mut self) {
+        if self.history.is_empty() {
+            return;
+        }
+        let index = match self.history_index {
+            Some(0) => 0,
+            Some(i) => i - 1,
+            None => self.history.len() - 1,
+        };
+        self.history_index = Some(index);
+        self.input = self.history[index].clone();
+        self.reset_completion();
+    }
+
+    pub fn history_next(&mut self) {
+        match self.history_index {
+            Some(i) if i + 1 < self.history.len() => {
+                self.history_index = Some(i + 1);
+                self.input = self.history[i + 1].clone();
+            }
+            Some(_) => {
+                self.history_index = None;
+                self.input.clear();
+            }
+            None => {}
+        }
+        self.reset_completion();
+    }
+
+    fn reset_completion(&mut self) {
+        self.completions.clear();
+        self.completion_index = None;
+    }
+
+    /// Complete the word under the cursor, cycling through candidates on repeated calls
+    pub fn complete(
+        &mut self,
+        cloud_names: &[String],
+        tags: &[String],
+        action_names: &[String],
+        forward: bool,
+    ) {
+        if self.completions.is_empty() {
+            self.completions = self.candidates(cloud_names, tags, action_names);
+            self.completion_index = None;
+            if self.completions.is_empty() {
+                return;
+            }
+        }
+
+        let count = self.completions.len();
+        let index = match (self.completion_index, forward) {
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(i), true) => (i + 1) % count,
+            (Some(i), false) => (i + count - 1) % count,
+        };
+        self.completion_index = Some(index);
+
+        let replacement = self.completions[index].clone();
+        let word_start = self.current_word_start();
+        self.input.truncate(word_start);
+        self.input.push_str(&replacement);
+    }
+
+    pub fn selected_completion(&self) -> Option<usize> {
+        self.completion_index
+    }
+
+    fn current_word_start(&self) -> usize {
+        // Action names contain spaces, so complete the whole line for them
+        if !self.input.contains(' ') || !COMMANDS.contains(&self.first_word()) {
+            return 0;
+        }
+        self.input.rfind(' ').map(|i| i + 1).unwrap_or(0)
+    }
+
+    fn first_word(&self) -> &str {
+        self.input.split_whitespace().next().unwrap_or("")
+    }
+
+    fn candidates(
+        &self,
+        cloud_names: &[String],
+        tags: &[String],
+        action_names: &[String],
+    ) -> Vec<String> {
+        let word_start = self.current_word_start();
+        let word = &self.input[word_start..];
+
+        if word_start == 0 {
+            // Completing the command itself (or a multi-word action name)
+            let word = word.to_lowercase();
+            let mut candidates: Vec<String> = COMMANDS
+                .iter()
+                .map(|c| c.to_string())
+                .chain(action_names.iter().map(|a| a.to_lowercase()))
+                .filter(|c| c.starts_with(&word))
+                .collect();
+            candidates.sort();
+            candidates.dedup();
+            return candidates;
+        }
+
+        let arg_index = self.input[..word_start].split_whitespace().count() - 1;
+        match (self.first_word(), arg_index) {
+            ("start" | "stop", 0) => std::iter::once("all".to_string())
+                .chain(cloud_names.iter().cloned())
+                .chain(tags.iter().map(|tag| format!("tag:{}", tag)))
+                .filter(|c| c.starts_with(word))
+                .collect(),
+            ("tag", 0) | ("password" | "token" | "webhooks", 0) => cloud_names
+                .iter()
+                .filter(|c| c.starts_with(word))
+                .cloned()
+                .collect(),
+            ("addfolder", 1) | ("import", 0) => complete_path(word),
+            ("export", 0) => std::iter::once("--encrypt".to_string())
+                .filter(|flag| flag.starts_with(word) && !word.is_empty())
+                .chain(complete_path(word))
+                .collect(),
+            ("export", 1) => complete_path(word),
+            ("tag", _) => tags
+                .iter()
+                .filter(|tag| tag.starts_with(word))
+                .cloned()
+                .collect(),
+            ("token", 1) => TOKEN_ARGS
+                .iter()
+                .filter(|arg| arg.starts_with(word))
+                .map(|arg| arg.to_string())
+                .collect(),
+            ("token", 2) if self.input.split_whitespace().nth(2) == Some("create") => {
+                ApiTokenScope::ALL
+                    .iter()
+                    .map(|scope| scope.as_str())
+                    .filter(|scope| scope.starts_with(word))
+                    .map(|scope| scope.to_string())
+                    .collect()
+            }
+            ("profile", 0) => cloudhost_server::config_paths::list_profiles()
+                .into_iter()
+                .filter(|profile| profile.starts_with(word))
+                .collect(),
+            ("secrets", 0) => SECRETS_ARGS
+                .iter()
+                .filter(|arg| arg.starts_with(word))
+                .map(|arg| arg.to_string())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Parse a command line (without the leading `:`)
+pub fn parse_command(line: &str, action_names: &[String]) -> Result<Command, String> {
+    let mut parts = line.split_whitespace();
+    let command = parts.next().ok_or_else(|| "Empty command".to_string())?;
+    let args: Vec<&str> = parts.collect();
+
+    let target = |args: &[&str]| match args {
+        ["all"] => Ok(CommandTarget::All),
+        [name] => Ok(match name.strip_prefix("tag:") {
+            Some(tag) if !tag.is_empty() => CommandTarget::Tag(tag.to_string()),
+            _ => CommandTarget::Cloud(name.to_string()),
+        }),
+        _ => Err(format!("Usage: :{} <cloud>|tag:<tag>|all", command)),
+    };
+
+    match command {
+        "start" => target(&args).map(Command::Start),
+        "stop" => target(&args).map(Command::Stop),
+        "password" => match args.as_slice() {
+            [name] => Ok(Command::Password(name.to_string())),
+            _ => Err("Usage: :password <cloud>".to_string()),
+        },
+        "tag" => match args.as_slice() {
+            [cloud, tags @ ..] => Ok(Command::Tag {
+                cloud: cloud.to_string(),
+                tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            }),
+            _ => Err("Usage: :tag <cloud> [tag...]".to_string()),
+        },
+        "addfolder" => match args.as_slice() {
+            [name, path @ ..] if !path.is_empty() => Ok(Command::AddFolder {
+                name: name.to_string(),
+                // Paths may contain spaces
+                path: expand_home(&path.join(" ")),
+            }),
+            _ => Err("Usage: :addfolder <name> <path>".to_string()),
+        },
+        "addcloud" => match args.as_slice() {
+            [name, folders @ ..] if !folders.is_empty() => Ok(Command::AddCloud {
+                name: name.to_string(),
+                folders: folders.iter().map(|folder| folder.to_string()).collect(),
+            }),
+            _ => Err("Usage: :addcloud <name> <folder> [folder...]".to_string()),
+        },
+        "export" => match args.as_slice() {
+            ["--encrypt", path @ ..] if !path.is_empty() => Ok(Command::Export {
+                path: expand_home(&path.join(" ")),
+                encrypt: true,
+            }),
+            path if !path.is_empty() => Ok(Command::Export {
+                path: expand_home(&path.join(" ")),
+                encrypt: false,
+            }),
+            _ => Err("Usage: :export [--encrypt] <path>".to_string()),
+        },
+        "import" => match args.as_slice() {
+            path if !path.is_empty() => Ok(Command::Import(expand_home(&path.join(" ")))),
+            _ => Err("Usage: :import <path>".to_string()),
+        },
+        "secrets" => match args.as_slice() {
+            ["passphrase"] => Ok(Command::Secrets(SecretsCommand::Passphrase)),
+            ["keyring"] => Ok(Command::Secrets(SecretsCommand::Keyring)),
+            ["off"] => Ok(Command::Secrets(SecretsCommand::Off)),
+            ["unlock"] => Ok(Command::Secrets(SecretsCommand::Unlock)),
+            _ => Err(format!("Usage: :secrets {}", SECRETS_ARGS.join("|"))),
+        },
+        "token" => {
+            let usage = || {
+                "Usage: :token <cloud> list | create [full|read-only|upload-only|nextcloud] <name> | revoke <id>"
+                    .to_string()
+            };
+            match args.as_slice() {
+                [cloud, "list"] => Ok(Command::Token {
+                    cloud: cloud.to_string(),
+                    command: TokenCommand::List,
+                }),
+                [cloud, "create", rest @ ..] if !rest.is_empty() => {
+                    // The scope is optional, names may contain spaces
+                    let (scope, name) = match rest[0].parse::<ApiTokenScope>() {
+                        Ok(scope) => (scope, &rest[1..]),
+                        Err(_) => (ApiTokenScope::Full, rest),
+                    };
+                    if name.is_empty() {
+                        return Err(usage());
+                    }
+                    Ok(Command::Token {
+                        cloud: cloud.to_string(),
+                        command: TokenCommand::Create {
+                            name: name.join(" "),
+                            scope,
+                        },
+                    })
+                }
+                [cloud, "revoke", id] => Ok(Command::Token {
+                    cloud: cloud.to_string(),
+                    command: TokenCommand::Revoke(id.to_string()),
+                }),
+                _ => Err(usage()),
+            }
+        }
+        "webhooks" => match args.as_slice() {
+            [cloud] => Ok(Command::Webhooks(cloud.to_string())),
+            _ => Err("Usage: :webhooks <cloud>".to_string()),
+        },
+        "profile" => match args.as_slice() {
+            [] => Ok(Command::Profile(None)),
+            [name] => Ok(Command::Profile(Some(name.to_string()))),
+            _ => Err("Usage: :profile [name]".to_string()),
+        },
+        "q" | "quit" => Ok(Command::Action("Quit".to_string())),
+        _ => action_names
+            .iter()
+            .find(|action| action.eq_ignore_ascii_case(line.trim()))
+            .map(|action| Command::Action(action.clone()))
+            .ok_or_else(|| format!("Unknown command: {}", line)),
+    }
+}
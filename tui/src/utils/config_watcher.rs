@@ -0,0 +1,55 @@
+/// Watches the TUI and clouds config files so external edits are applied automatically.
+/// The config directory is watched rather than the files, because editors often save
+/// by writing a new file and renaming it over the old one.
+use crate::events::{AppEvent, EventSender};
+use cloudhost_server::config_paths;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::time::Duration;
+
+/// Wait for writes to settle before reloading; editors save in several steps
+pub const RELOAD_DELAY: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConfigFile {
+    Tui,
+    Clouds,
+}
+
+/// Start watching; events stop when the returned watcher is dropped
+pub fn watch_config_files(events: EventSender) -> notify::Result<RecommendedWatcher> {
+    let tui_config = config_paths::get_tui_config_path();
+    let clouds_config = config_paths::get_clouds_config_path();
+    let clouds_config_dir = clouds_config
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(|dir| dir.to_path_buf());
+
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+        let Ok(event) = result else {
+            return;
+        };
+        if event.kind.is_access() {
+            return;
+        }
+        for path in &event.paths {
+            let file = if *path == tui_config {
+                ConfigFile::Tui
+            } else if *path == clouds_config {
+                ConfigFile::Clouds
+            } else {
+                continue;
+            };
+            let _ = events.send(AppEvent::ConfigFileChanged(file));
+        }
+    })?;
+
+    config_paths::ensure_config_dir()?;
+    let config_dir = config_paths::get_config_dir();
+    watcher.watch(&config_dir, RecursiveMode::NonRecursive)?;
+    // A clouds config set with `--config` may live elsewhere
+    if let Some(clouds_dir) = clouds_config_dir.filter(|dir| *dir != config_dir) {
+        std::fs::create_dir_all(&clouds_dir)?;
+        watcher.watch(&clouds_dir, RecursiveMode::NonRecursive)?;
+    }
+    Ok(watcher)
+}
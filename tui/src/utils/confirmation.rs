@@ -0,0 +1,71 @@
+/// State of the yes/no dialog asked before destructive actions, unless
+/// `confirmations = false` in the TUI config
+use ratatui::crossterm::event::KeyCode;
+
+/// What is done once the dialog is answered with yes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfirmAction {
+    DeleteFolder(String),
+    DeleteCloud(String),
+    /// A file or directory in the files panel, to the trash
+    DeleteFile(String),
+    /// A cloud that is still serving clients
+    StopCloud(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Confirmation {
+    pub action: ConfirmAction,
+    /// Lines under the question on what the action affects
+    pub details: Vec<String>,
+}
+
+impl Confirmation {
+    pub fn new(action: ConfirmAction) -> Self {
+        Self {
+            action,
+            details: Vec::new(),
+        }
+    }
+
+    pub fn with_details(mut self, details: Vec<String>) -> Self {
+        self.details = details;
+        self
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self.action {
+            ConfirmAction::StopCloud(_) => "⏹ Confirm Stop",
+            _ => "🗑️ Confirm Delete",
+        }
+    }
+
+    pub fn question(&self) -> String {
+        match &self.action {
+            ConfirmAction::DeleteFolder(name) => format!("Remove cloud folder '{}'?", name),
+            ConfirmAction::DeleteCloud(name) => format!("Remove cloud '{}'?", name),
+            ConfirmAction::DeleteFile(name) => format!("Delete '{}' from disk?", name),
+            ConfirmAction::StopCloud(name) => format!("Stop cloud '{}'?", name),
+        }
+    }
+
+    pub fn hint(&self) -> &'static str {
+        match self.action {
+            ConfirmAction::DeleteFolder(_) | ConfirmAction::DeleteCloud(_) => {
+                "y/Enter to delete, n/Esc to cancel (u to undo later)"
+            }
+            ConfirmAction::DeleteFile(_) => "y/Enter to delete, n/Esc to cancel",
+            ConfirmAction::StopCloud(_) => "y/Enter to stop, n/Esc to cancel",
+        }
+    }
+}
+
+/// The answer a key gives: `Some(true)` for yes, `Some(false)` for no, `None` for keys
+/// that don't answer
+pub fn answer(key: KeyCode) -> Option<bool> {
+    match key {
+        KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => Some(true),
+        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc | KeyCode::Char('q') => Some(false),
+        _ => None,
+    }
+}
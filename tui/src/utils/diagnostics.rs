@@ -0,0 +1,11 @@
+/// State of the Diagnostics panel: the doctor's local checks, then the online ones when
+/// they come back from the background
+use cloudhost_server::doctor::Check;
+
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    pub checks: Vec<Check>,
+    /// The online checks are still running
+    pub checking_online: bool,
+    pub scroll: u16,
+}
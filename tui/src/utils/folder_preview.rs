@@ -0,0 +1,93 @@
+/// Quick look at a directory before it becomes a cloud folder:
+/// is it usable, and roughly how much is in it.
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Stop counting after this many entries so huge trees don't keep the disk busy
+const MAX_SCANNED_ENTRIES: u64 = 50_000;
+const MAX_SCAN_TIME: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Default)]
+pub struct FolderPreview {
+    /// The text that was typed, to match results with the current input
+    pub input: String,
+    pub path: PathBuf,
+    pub exists: bool,
+    pub is_dir: bool,
+    pub readable: bool,
+    pub writable: bool,
+    pub file_count: u64,
+    pub dir_count: u64,
+    pub total_bytes: u64,
+    /// The scan stopped early; counts are lower bounds
+    pub truncated: bool,
+}
+
+/// Inspect `path` (blocking, run it off the UI task)
+pub fn preview_folder(input: &str, path: &Path) -> FolderPreview {
+    let mut preview = FolderPreview {
+        input: input.to_string(),
+        path: path.to_path_buf(),
+        exists: path.exists(),
+        is_dir: path.is_dir(),
+        ..Default::default()
+    };
+    if !preview.is_dir {
+        return preview;
+    }
+
+    preview.readable = std::fs::read_dir(path).is_ok();
+    preview.writable = is_writable(path);
+    if preview.readable {
+        count_contents(path, &mut preview);
+    }
+    preview
+}
+
+/// Try to create (and remove) a file: permission bits alone don't tell whether we may write
+fn is_writable(dir: &Path) -> bool {
+    let probe = dir.join(format!(".cloudhost-write-check-{}", std::process::id()));
+    match std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+    {
+        Ok(_) => {
+            let _ = std::fs::remove_file(&probe);
+            true
+        }
+        Err(e) => e.kind() == std::io::ErrorKind::AlreadyExists,
+    }
+}
+
+fn count_contents(root: &Path, preview: &mut FolderPreview) {
+    let started = Instant::now();
+    let mut pending = vec![root.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if preview.file_count + preview.dir_count >= MAX_SCANNED_ENTRIES
+                || started.elapsed() > MAX_SCAN_TIME
+            {
+                preview.truncated = true;
+                return;
+            }
+            // Don't follow symlinks, they may point outside or loop
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                preview.dir_count += 1;
+                pending.push(entry.path());
+            } else {
+                preview.file_count += 1;
+                if let Ok(metadata) = entry.metadata() {
+                    preview.total_bytes += metadata.len();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,158 @@
+/// Ctrl-P style fuzzy finder over clouds, cloud folders and actions.
+/// Scoring and selection live here; the App decides what "jumping" to an item means.
+use crate::utils::text_input::TextInput;
+use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FinderItemKind {
+    Cloud,
+    CloudFolder,
+    Action,
+}
+
+impl FinderItemKind {
+    pub fn icon(self) -> &'static str {
+        match self {
+            FinderItemKind::Cloud => "☁️ ",
+            FinderItemKind::CloudFolder => "📁",
+            FinderItemKind::Action => "⚡",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FinderItem {
+    pub kind: FinderItemKind,
+    pub label: String,
+    /// Extra context shown dimmed next to the label (path, keys, port...)
+    pub detail: String,
+}
+
+/// A candidate that matched the query, with the char positions that matched
+#[derive(Debug, Clone)]
+pub struct FinderMatch {
+    pub item_index: usize,
+    pub score: i64,
+    pub positions: Vec<usize>,
+}
+
+#[derive(Default)]
+pub struct FuzzyFinderState {
+    pub active: bool,
+    pub query: TextInput,
+    pub items: Vec<FinderItem>,
+    pub matches: Vec<FinderMatch>,
+    pub selected: usize,
+}
+
+impl FuzzyFinderState {
+    pub fn open(&mut self, items: Vec<FinderItem>) {
+        self.active = true;
+        self.query.clear();
+        self.items = items;
+        self.update_matches();
+    }
+
+    pub fn close(&mut self) {
+        self.active = false;
+        self.query.clear();
+        self.items.clear();
+        self.matches.clear();
+        self.selected = 0;
+    }
+
+    /// Edit the query by `key`; false for keys that don't edit it
+    pub fn edit_query(&mut self, key: KeyCode, modifiers: KeyModifiers) -> bool {
+        let edited = self.query.handle_key(key, modifiers);
+        self.update_matches();
+        edited
+    }
+
+    pub fn paste(&mut self, text: &str) {
+        self.query.insert_str(text);
+        self.update_matches();
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.matches.is_empty() {
+            self.selected = (self.selected + 1).min(self.matches.len() - 1);
+        }
+    }
+
+    pub fn selected_item(&self) -> Option<&FinderItem> {
+        self.matches
+            .get(self.selected)
+            .and_then(|m| self.items.get(m.item_index))
+    }
+
+    fn update_matches(&mut self) {
+        self.matches = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(item_index, item)| {
+                fuzzy_match(&self.query, &item.label).map(|(score, positions)| FinderMatch {
+                    item_index,
+                    score,
+                    positions,
+                })
+            })
+            .collect();
+        // Stable sort keeps clouds, folders, actions in their original order on ties
+        self.matches.sort_by_key(|m| std::cmp::Reverse(m.score));
+        self.selected = 0;
+    }
+}
+
+/// Case-insensitive subsequence match.
+/// Returns a score (higher is better) and the matched char positions in `candidate`.
+/// Consecutive matches and matches at word starts score higher, gaps cost a little.
+pub fn fuzzy_match(pattern: &str, candidate: &str) -> Option<(i64, Vec<usize>)> {
+    if pattern.is_empty() {
+        return Some((0, Vec::new()));
+    }
+
+    let candidate_chars: Vec<char> = candidate.chars().collect();
+    let mut positions = Vec::with_capacity(pattern.len());
+    let mut score = 0i64;
+    let mut search_from = 0;
+    let mut previous: Option<usize> = None;
+
+    for pattern_char in pattern.chars().filter(|c| !c.is_whitespace()) {
+        let pattern_lower = pattern_char.to_lowercase().next()?;
+        let offset = candidate_chars[search_from..]
+            .iter()
+            .position(|c| c.to_lowercase().next() == Some(pattern_lower))?;
+        let position = search_from + offset;
+
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == position) {
+            score += 5;
+        } else if let Some(p) = previous {
+            score -= (position - p - 1).min(5) as i64;
+        }
+        let at_word_start = position == 0
+            || matches!(
+                candidate_chars[position - 1],
+                ' ' | '_' | '-' | '/' | '.' | '\\'
+            );
+        if at_word_start {
+            score += 8;
+        }
+        if candidate_chars[position] == pattern_char {
+            score += 1;
+        }
+
+        positions.push(position);
+        previous = Some(position);
+        search_from = position + 1;
+    }
+
+    // Prefer shorter candidates when everything else is equal
+    score -= (candidate_chars.len() / 8) as i64;
+    Some((score, positions))
+}
@@ -0,0 +1,192 @@
+/// Background jobs: long operations run in spawned tasks and report progress
+/// to the run loop through the event channel. The App owns the `JobList`.
+use crate::events::{AppEvent, EventSender};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub use cloudhost_server::utils::format_bytes;
+
+pub type JobId = u64;
+
+/// How many finished jobs are kept for the jobs panel
+const MAX_FINISHED: usize = 20;
+
+/// How long a finished job stays in the floating jobs panel
+pub const RECENT_JOB_DURATION: Duration = Duration::from_secs(5);
+
+/// Minimum time between two progress events of the same job
+const PROGRESS_REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// What a job does, so the App knows how to react when it finishes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobKind {
+    Download,
+    Upload,
+    /// Test email from the Settings tab
+    Email,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    Running,
+    Succeeded(String),
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub id: JobId,
+    pub kind: JobKind,
+    pub label: String,
+    pub done: u64,
+    pub total: Option<u64>,
+    pub status: JobStatus,
+    pub started_at: Instant,
+    pub finished_at: Option<Instant>,
+}
+
+impl Job {
+    pub fn is_running(&self) -> bool {
+        self.status == JobStatus::Running
+    }
+
+    /// Progress between 0 and 1, when the total is known
+    pub fn fraction(&self) -> Option<f64> {
+        self.total
+            .filter(|total| *total > 0)
+            .map(|total| (self.done as f64 / total as f64).min(1.0))
+    }
+
+    /// Spinner for running jobs, a check or cross for finished ones
+    pub fn icon(&self) -> &'static str {
+        match self.status {
+            JobStatus::Running => {
+                let frame = self.started_at.elapsed().as_millis() / 100;
+                SPINNER[frame as usize % SPINNER.len()]
+            }
+            JobStatus::Succeeded(_) => "✅",
+            JobStatus::Failed(_) => "❌",
+        }
+    }
+}
+
+/// Handle given to a job's task to report progress
+#[derive(Clone)]
+pub struct JobReporter {
+    id: JobId,
+    events: EventSender,
+    last_report: Arc<Mutex<Option<Instant>>>,
+}
+
+impl JobReporter {
+    /// Report progress; updates are throttled so fast transfers don't flood the UI
+    pub fn progress(&self, done: u64, total: Option<u64>) {
+        let complete = total.is_some_and(|total| done >= total);
+        if let Ok(mut last_report) = self.last_report.lock() {
+            if !complete
+                && last_report.is_some_and(|last| last.elapsed() < PROGRESS_REPORT_INTERVAL)
+            {
+                return;
+            }
+            *last_report = Some(Instant::now());
+        }
+        let _ = self.events.send(AppEvent::JobProgress {
+            id: self.id,
+            done,
+            total,
+        });
+    }
+}
+
+#[derive(Default)]
+pub struct JobList {
+    pub jobs: Vec<Job>,
+    next_id: JobId,
+}
+
+impl JobList {
+    /// Register a job and run `task` in the background.
+    /// The task's result (a message for the user) is delivered as `AppEvent::JobFinished`.
+    pub fn spawn<F, Fut>(
+        &mut self,
+        kind: JobKind,
+        label: impl Into<String>,
+        events: &EventSender,
+        task: F,
+    ) -> JobId
+    where
+        F: FnOnce(JobReporter) -> Fut,
+        Fut: Future<Output = Result<String, String>> + Send + 'static,
+    {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.jobs.push(Job {
+            id,
+            kind,
+            label: label.into(),
+            done: 0,
+            total: None,
+            status: JobStatus::Running,
+            started_at: Instant::now(),
+            finished_at: None,
+        });
+
+        let reporter = JobReporter {
+            id,
+            events: events.clone(),
+            last_report: Arc::default(),
+        };
+        let future = task(reporter);
+        let events = events.clone();
+        tokio::spawn(async move {
+            let result = future.await;
+            let _ = events.send(AppEvent::JobFinished { id, result });
+        });
+        id
+    }
+
+    pub fn progress(&mut self, id: JobId, done: u64, total: Option<u64>) {
+        if let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) {
+            job.done = done;
+            job.total = total;
+        }
+    }
+
+    /// Mark a job as finished, returning it so the caller can react to its kind
+    pub fn finish(&mut self, id: JobId, result: &Result<String, String>) -> Option<Job> {
+        let job = self.jobs.iter_mut().find(|job| job.id == id)?;
+        job.status = match result {
+            Ok(message) => JobStatus::Succeeded(message.clone()),
+            Err(e) => JobStatus::Failed(e.clone()),
+        };
+        job.finished_at = Some(Instant::now());
+        let finished = job.clone();
+
+        // Forget the oldest finished jobs
+        let finished_count = self.jobs.iter().filter(|job| !job.is_running()).count();
+        let mut excess = finished_count.saturating_sub(MAX_FINISHED);
+        self.jobs.retain(|job| {
+            if excess > 0 && !job.is_running() {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+
+        Some(finished)
+    }
+
+    /// Running jobs and those that finished a moment ago
+    pub fn recent(&self) -> Vec<&Job> {
+        self.jobs
+            .iter()
+            .filter(|job| {
+                job.finished_at
+                    .is_none_or(|finished| finished.elapsed() < RECENT_JOB_DURATION)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,29 @@
+//! Opening a cloud's URL in the default browser and folders in the file manager, with
+//! xdg-open, explorer or open depending on the system.
+
+use crate::utils::clipboard::ssh_session;
+
+#[derive(Debug, Default)]
+pub struct Launcher {
+    /// Never open anything, as in tests
+    disabled: bool,
+}
+
+impl Launcher {
+    /// A launcher that always fails, so the target gets copied instead
+    pub fn disabled() -> Self {
+        Self { disabled: true }
+    }
+
+    /// Open `target`, a URL or a path, without waiting for the program that shows it
+    pub fn open(&self, target: &str) -> Result<(), String> {
+        if self.disabled {
+            return Err("opening turned off".to_string());
+        }
+        // It would open on the server, out of sight
+        if ssh_session() {
+            return Err("nothing to open it with over SSH".to_string());
+        }
+        open::that_detached(target).map_err(|e| e.to_string())
+    }
+}
@@ -1 +1,18 @@
+pub mod clipboard;
+pub mod command_line;
+pub mod config_watcher;
+pub mod confirmation;
+pub mod diagnostics;
+pub mod folder_preview;
+pub mod fuzzy_finder;
+pub mod jobs;
+pub mod launcher;
+pub mod notifications;
+pub mod passphrase_prompt;
 pub mod password;
+pub mod path_input;
+pub mod profile_picker;
+pub mod script;
+pub mod server_defaults;
+pub mod text_input;
+pub mod undo;
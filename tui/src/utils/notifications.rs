@@ -0,0 +1,115 @@
+/// User-facing notifications: short-lived toasts for successes and info,
+/// banners that stay until dismissed for errors, and a history of both.
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How long a toast stays on screen
+pub const TOAST_DURATION: Duration = Duration::from_secs(4);
+
+/// Toasts shown at the same time; older ones are only in the history
+const MAX_TOASTS: usize = 3;
+
+const MAX_HISTORY: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn icon(self) -> &'static str {
+        match self {
+            Severity::Info => "ℹ️ ",
+            Severity::Success => "✅",
+            Severity::Warning => "⚠️ ",
+            Severity::Error => "❌",
+        }
+    }
+
+    /// Errors stay as banners until dismissed, everything else fades out
+    pub fn is_persistent(self) -> bool {
+        self == Severity::Error
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub severity: Severity,
+    pub message: String,
+    pub created_at: Instant,
+    pub timestamp: chrono::DateTime<chrono::Local>,
+    pub dismissed: bool,
+}
+
+impl Notification {
+    fn is_toast_visible(&self) -> bool {
+        !self.severity.is_persistent() && self.created_at.elapsed() < TOAST_DURATION
+    }
+}
+
+#[derive(Default)]
+pub struct Notifications {
+    pub history: VecDeque<Notification>,
+    pub show_history: bool,
+    pub history_scroll: u16,
+}
+
+impl Notifications {
+    pub fn push(&mut self, severity: Severity, message: impl Into<String>) {
+        // Banners and toasts are single lines
+        let message: String = message.into();
+        let message = message
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("  ");
+        self.history.push_back(Notification {
+            severity,
+            message,
+            created_at: Instant::now(),
+            timestamp: chrono::Local::now(),
+            dismissed: false,
+        });
+        if self.history.len() > MAX_HISTORY {
+            self.history.pop_front();
+        }
+    }
+
+    /// Newest toasts first
+    pub fn toasts(&self) -> Vec<&Notification> {
+        self.history
+            .iter()
+            .rev()
+            .filter(|n| n.is_toast_visible())
+            .take(MAX_TOASTS)
+            .collect()
+    }
+
+    /// Errors that haven't been dismissed, oldest first
+    pub fn banners(&self) -> Vec<&Notification> {
+        self.history
+            .iter()
+            .filter(|n| n.severity.is_persistent() && !n.dismissed)
+            .collect()
+    }
+
+    pub fn dismiss_banners(&mut self) -> bool {
+        let mut dismissed_any = false;
+        for notification in self.history.iter_mut() {
+            if notification.severity.is_persistent() && !notification.dismissed {
+                notification.dismissed = true;
+                dismissed_any = true;
+            }
+        }
+        dismissed_any
+    }
+
+    pub fn toggle_history(&mut self) {
+        self.show_history = !self.show_history;
+        self.history_scroll = 0;
+    }
+}
@@ -0,0 +1,90 @@
+/// State of the passphrase prompt: encrypted config exports/imports and the
+/// master passphrase protecting cloud secrets
+use crate::utils::text_input::TextInput;
+use std::path::PathBuf;
+
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PassphrasePurpose {
+    /// Encrypt a config export written to the path
+    Export(PathBuf),
+    /// Decrypt the config export at the path
+    Import(PathBuf),
+    /// Decrypt the cloud secrets of the clouds config
+    UnlockSecrets,
+    /// Choose a master passphrase to encrypt cloud secrets with
+    ProtectSecrets,
+}
+
+impl PassphrasePurpose {
+    /// New passphrases are typed twice, existing ones once
+    fn is_new_passphrase(&self) -> bool {
+        matches!(
+            self,
+            PassphrasePurpose::Export(_) | PassphrasePurpose::ProtectSecrets
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PassphrasePrompt {
+    pub purpose: PassphrasePurpose,
+    pub input: TextInput,
+    /// Set once a new passphrase was entered and is being confirmed
+    pub first_entry: Option<String>,
+    pub error: Option<String>,
+}
+
+impl PassphrasePrompt {
+    pub fn new(purpose: PassphrasePurpose) -> Self {
+        Self {
+            purpose,
+            input: TextInput::default(),
+            first_entry: None,
+            error: None,
+        }
+    }
+
+    pub fn is_new_passphrase(&self) -> bool {
+        self.purpose.is_new_passphrase()
+    }
+
+    pub fn is_confirming(&self) -> bool {
+        self.first_entry.is_some()
+    }
+
+    /// Handle Enter; returns the passphrase once it is complete
+    pub fn submit(&mut self) -> Option<String> {
+        self.error = None;
+        if !self.purpose.is_new_passphrase() {
+            if self.input.is_empty() {
+                self.error = Some("Enter the passphrase".to_string());
+                return None;
+            }
+            return Some(self.input.take());
+        }
+
+        match self.first_entry.take() {
+            None => {
+                if self.input.chars().count() < MIN_PASSPHRASE_LEN {
+                    self.error = Some(format!(
+                        "Passphrase must be at least {} characters",
+                        MIN_PASSPHRASE_LEN
+                    ));
+                    return None;
+                }
+                self.first_entry = Some(self.input.take());
+                None
+            }
+            Some(first) => {
+                if first == self.input.value() {
+                    return Some(first);
+                }
+                self.input.clear();
+                self.error = Some("Passphrases don't match, try again".to_string());
+                None
+            }
+        }
+    }
+}
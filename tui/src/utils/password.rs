@@ -1,6 +1,34 @@
 /// Password creation logic and state management
 /// This module contains all the business logic for password creation,
 /// separate from the UI components.
+use crate::utils::text_input::TextInput;
+use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+
+/// Longest password that can be typed
+const MAX_PASSWORD_LEN: usize = 50;
+
+/// Type `key` into a password `field`; passwords are printable ASCII
+fn edit_password(field: &mut TextInput, key: KeyCode, modifiers: KeyModifiers) -> bool {
+    if let KeyCode::Char(c) = key {
+        if !(c.is_ascii_graphic() || c == ' ') {
+            return false;
+        }
+    }
+    let handled = field.handle_key(key, modifiers);
+    field.truncate(MAX_PASSWORD_LEN);
+    handled
+}
+
+/// Paste `text` into a password `field`, keeping only what could be typed into it
+fn paste_password(field: &mut TextInput, text: &str) {
+    let text: String = text
+        .chars()
+        .filter(|c| c.is_ascii_graphic() || *c == ' ')
+        .collect();
+    field.insert_str(&text);
+    field.truncate(MAX_PASSWORD_LEN);
+}
+
 /// Password creation modes
 #[derive(Debug, Clone, PartialEq, Default)]
 pub enum PasswordMode {
@@ -19,8 +47,8 @@ pub enum PasswordDisplayState {
 #[derive(Default)]
 pub struct PasswordCreationState {
     pub creating_password: bool,
-    pub password_input: String,
-    pub password_confirm: String,
+    pub password_input: TextInput,
+    pub password_confirm: TextInput,
     pub password_mode: PasswordMode,
     pub password_error: Option<String>,
     pub password_success: bool,
@@ -31,8 +59,8 @@ impl PasswordCreationState {
     pub fn new() -> Self {
         Self {
             creating_password: false,
-            password_input: String::new(),
-            password_confirm: String::new(),
+            password_input: TextInput::default(),
+            password_confirm: TextInput::default(),
             password_mode: PasswordMode::default(),
             password_error: None,
             password_success: false,
@@ -60,63 +88,48 @@ impl PasswordCreationState {
         self.password_success = false;
     }
 
-    /// Handle password input from user
-    pub fn handle_password_input(&mut self, key: char) {
+    /// Handle a key typed into the password modal
+    pub fn handle_password_input(&mut self, key: KeyCode, modifiers: KeyModifiers) {
         match key {
-            '\n' | '\r' => {
-                match self.password_mode {
-                    PasswordMode::Creating => {
-                        if self.password_input.len() < 8 {
-                            self.password_error =
-                                Some("Password must be at least 8 characters".to_string());
-                            return;
-                        }
-                        self.password_mode = PasswordMode::Confirming;
-                        self.password_confirm.clear();
-                    }
-                    PasswordMode::Confirming => {
-                        if self.password_input == self.password_confirm {
-                            // Password confirmed, will be set by the caller
-                        } else {
-                            self.password_error = Some("Passwords do not match".to_string());
-                            self.password_confirm.clear();
-                        }
+            KeyCode::Enter => match self.password_mode {
+                PasswordMode::Creating => {
+                    if self.password_input.len() < 8 {
+                        self.password_error =
+                            Some("Password must be at least 8 characters".to_string());
+                        return;
                     }
+                    self.password_mode = PasswordMode::Confirming;
+                    self.password_confirm.clear();
                 }
-            }
-            '\x08' | '\x7f' => {
-                // Backspace
-                match self.password_mode {
-                    PasswordMode::Creating => {
-                        self.password_input.pop();
-                    }
-                    PasswordMode::Confirming => {
-                        self.password_confirm.pop();
+                PasswordMode::Confirming => {
+                    if self.password_input == self.password_confirm {
+                        // Password confirmed, will be set by the caller
+                    } else {
+                        self.password_error = Some("Passwords do not match".to_string());
+                        self.password_confirm.clear();
                     }
                 }
-                self.password_error = None;
-            }
-            '\x1b' => {
-                // Escape - cancel password creation
-                self.clear_password_creation();
-            }
-            c if c.is_ascii_graphic() || c == ' ' => {
-                // Add character
-                match self.password_mode {
-                    PasswordMode::Creating => {
-                        if self.password_input.len() < 50 {
-                            self.password_input.push(c);
-                        }
-                    }
-                    PasswordMode::Confirming => {
-                        if self.password_confirm.len() < 50 {
-                            self.password_confirm.push(c);
-                        }
-                    }
+            },
+            // Escape - cancel password creation
+            KeyCode::Esc => self.clear_password_creation(),
+            _ => {
+                if edit_password(self.field(), key, modifiers) {
+                    self.password_error = None;
                 }
-                self.password_error = None;
             }
-            _ => {}
+        }
+    }
+
+    /// Paste into the field being typed
+    pub fn paste(&mut self, text: &str) {
+        paste_password(self.field(), text);
+        self.password_error = None;
+    }
+
+    fn field(&mut self) -> &mut TextInput {
+        match self.password_mode {
+            PasswordMode::Creating => &mut self.password_input,
+            PasswordMode::Confirming => &mut self.password_confirm,
         }
     }
 
@@ -149,11 +162,103 @@ impl PasswordCreationState {
 
     /// Get the masked password for display
     pub fn get_masked_password(&self) -> String {
-        "*".repeat(self.password_input.len())
+        "*".repeat(self.password_input.char_count())
     }
 
     /// Get the masked confirmation password for display
     pub fn get_masked_confirm(&self) -> String {
-        "*".repeat(self.password_confirm.len())
+        "*".repeat(self.password_confirm.char_count())
+    }
+}
+
+/// Steps of a password change: the current password, then the new one twice
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChangePasswordStep {
+    #[default]
+    Current,
+    New,
+    Confirm,
+}
+
+/// State of the change password modal, separate from setting a first password
+#[derive(Default)]
+pub struct ChangePasswordState {
+    pub active: bool,
+    pub step: ChangePasswordStep,
+    pub current_password: TextInput,
+    pub new_password: TextInput,
+    pub confirm_password: TextInput,
+    pub error: Option<String>,
+}
+
+impl ChangePasswordState {
+    pub fn start(&mut self) {
+        *self = Self {
+            active: true,
+            ..Self::default()
+        };
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    fn field(&mut self) -> &mut TextInput {
+        match self.step {
+            ChangePasswordStep::Current => &mut self.current_password,
+            ChangePasswordStep::New => &mut self.new_password,
+            ChangePasswordStep::Confirm => &mut self.confirm_password,
+        }
+    }
+
+    /// Handle a key; true when all three fields are filled in and can be submitted
+    pub fn handle_input(&mut self, key: KeyCode, modifiers: KeyModifiers) -> bool {
+        match key {
+            KeyCode::Enter => match self.step {
+                ChangePasswordStep::Current => {
+                    if self.current_password.is_empty() {
+                        self.error = Some("Enter the current password".to_string());
+                    } else {
+                        self.step = ChangePasswordStep::New;
+                    }
+                }
+                ChangePasswordStep::New => {
+                    if self.new_password.len() < 8 {
+                        self.error = Some("Password must be at least 8 characters".to_string());
+                    } else if self.new_password == self.current_password {
+                        self.error =
+                            Some("The new password is the same as the current one".to_string());
+                    } else {
+                        self.step = ChangePasswordStep::Confirm;
+                    }
+                }
+                ChangePasswordStep::Confirm => {
+                    if self.new_password == self.confirm_password {
+                        return true;
+                    }
+                    self.error = Some("Passwords do not match".to_string());
+                    self.confirm_password.clear();
+                }
+            },
+            KeyCode::Esc => self.clear(),
+            _ => {
+                if edit_password(self.field(), key, modifiers) {
+                    self.error = None;
+                }
+            }
+        }
+        false
+    }
+
+    /// Paste into the field being typed
+    pub fn paste(&mut self, text: &str) {
+        paste_password(self.field(), text);
+        self.error = None;
+    }
+
+    /// Back to the first step after the current password was refused
+    pub fn reject_current(&mut self, error: String) {
+        self.start();
+        self.error = Some(error);
     }
 }
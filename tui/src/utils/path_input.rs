@@ -0,0 +1,194 @@
+/// Helpers for text inputs that take a local path: `~` expansion, Tab completion
+/// against the filesystem and a small interactive directory picker.
+use crate::utils::text_input::TextInput;
+use std::path::{Path, PathBuf};
+
+/// Expand a leading `~` to the user's home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    if path == "~" || path.starts_with("~/") || path.starts_with("~\\") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(path[1..].trim_start_matches(['/', '\\']));
+        }
+    }
+    PathBuf::from(path)
+}
+
+/// Directory candidates for a partially typed path.
+/// Candidates keep the user's spelling (including a leading `~`) and end with `/`.
+pub fn complete_path(partial: &str) -> Vec<String> {
+    let (dir, prefix) = match partial.rfind(['/', '\\']) {
+        Some(i) => (&partial[..=i], &partial[i + 1..]),
+        None if partial == "~" => return vec!["~/".to_string()],
+        None => ("", partial),
+    };
+    let search_dir = if dir.is_empty() {
+        PathBuf::from(".")
+    } else {
+        expand_home(dir)
+    };
+
+    let Ok(read_dir) = std::fs::read_dir(&search_dir) else {
+        return Vec::new();
+    };
+
+    let mut candidates: Vec<String> = read_dir
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| {
+            name.starts_with(prefix) && (!name.starts_with('.') || prefix.starts_with('.'))
+        })
+        .map(|name| format!("{}{}/", dir, name))
+        .collect();
+    candidates.sort();
+    candidates
+}
+
+/// Tab completion state for a single path input.
+/// Repeated Tabs cycle through the candidates computed for the text typed before the first Tab.
+#[derive(Debug, Default)]
+pub struct PathCompletion {
+    pub candidates: Vec<String>,
+    pub index: Option<usize>,
+}
+
+impl PathCompletion {
+    /// Complete `input` in place. Returns false when nothing matches.
+    pub fn complete(&mut self, input: &mut TextInput, forward: bool) -> bool {
+        if self.candidates.is_empty() {
+            self.candidates = complete_path(input);
+            self.index = None;
+            if self.candidates.is_empty() {
+                return false;
+            }
+            // A single match is accepted right away, so the next Tab descends into it
+            if self.candidates.len() == 1 {
+                input.set(self.candidates.remove(0));
+                return true;
+            }
+        }
+
+        let count = self.candidates.len();
+        let index = match (self.index, forward) {
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(i), true) => (i + 1) % count,
+            (Some(i), false) => (i + count - 1) % count,
+        };
+        self.index = Some(index);
+        input.set(self.candidates[index].clone());
+        true
+    }
+
+    /// Forget the candidates, e.g. after the user typed something
+    pub fn reset(&mut self) {
+        self.candidates.clear();
+        self.index = None;
+    }
+}
+
+/// Interactive directory browser used to pick a folder path
+#[derive(Debug, Clone)]
+pub struct DirPicker {
+    pub current_dir: PathBuf,
+    /// Sub-directory names of `current_dir`
+    pub entries: Vec<String>,
+    pub selected: usize,
+    pub show_hidden: bool,
+    pub error: Option<String>,
+}
+
+impl DirPicker {
+    /// Open at the directory typed so far, or its closest existing parent, or home
+    pub fn open(start: &str) -> Self {
+        let mut dir = expand_home(start.trim());
+        while !dir.is_dir() {
+            if !dir.pop() {
+                dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+                break;
+            }
+        }
+        let mut picker = Self {
+            current_dir: std::fs::canonicalize(&dir).unwrap_or(dir),
+            entries: Vec::new(),
+            selected: 0,
+            show_hidden: false,
+            error: None,
+        };
+        picker.reload();
+        picker
+    }
+
+    pub fn reload(&mut self) {
+        match read_subdirectories(&self.current_dir, self.show_hidden) {
+            Ok(entries) => {
+                self.entries = entries;
+                self.error = None;
+            }
+            Err(e) => {
+                self.entries.clear();
+                self.error = Some(format!("Cannot read {}: {}", self.current_dir.display(), e));
+            }
+        }
+        self.selected = self.selected.min(self.entries.len().saturating_sub(1));
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.entries.is_empty() {
+            self.selected = (self.selected + 1).min(self.entries.len() - 1);
+        }
+    }
+
+    /// Descend into the selected directory
+    pub fn enter_selected(&mut self) {
+        if let Some(name) = self.entries.get(self.selected) {
+            self.current_dir = self.current_dir.join(name);
+            self.selected = 0;
+            self.reload();
+        }
+    }
+
+    /// Go to the parent directory, keeping the directory we came from selected
+    pub fn go_up(&mut self) {
+        let Some(parent) = self.current_dir.parent().map(Path::to_path_buf) else {
+            return;
+        };
+        let previous = self
+            .current_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string());
+        self.current_dir = parent;
+        self.reload();
+        if let Some(index) = previous.and_then(|p| self.entries.iter().position(|e| *e == p)) {
+            self.selected = index;
+        }
+    }
+
+    pub fn toggle_hidden(&mut self) {
+        self.show_hidden = !self.show_hidden;
+        self.reload();
+    }
+
+    /// The directory that will be picked: the highlighted entry
+    pub fn selected_path(&self) -> PathBuf {
+        match self.entries.get(self.selected) {
+            Some(name) => self.current_dir.join(name),
+            None => self.current_dir.clone(),
+        }
+    }
+}
+
+fn read_subdirectories(dir: &Path, show_hidden: bool) -> std::io::Result<Vec<String>> {
+    let mut entries: Vec<String> = std::fs::read_dir(dir)?
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| show_hidden || !name.starts_with('.'))
+        .collect();
+    entries.sort_by_key(|name| name.to_lowercase());
+    Ok(entries)
+}
@@ -0,0 +1,63 @@
+/// State of the profile switcher: the existing profiles, and a name to type for a new one
+use crate::utils::text_input::TextInput;
+use cloudhost_server::config_paths;
+
+#[derive(Debug, Clone)]
+pub struct ProfilePicker {
+    pub profiles: Vec<String>,
+    pub selected: usize,
+    /// Name of a profile to switch to instead of the selected one, possibly a new one
+    pub input: TextInput,
+    pub error: Option<String>,
+}
+
+impl ProfilePicker {
+    /// The profiles in the config directory, with the current one selected
+    pub fn new() -> Self {
+        let profiles = config_paths::list_profiles();
+        let current = config_paths::current_profile()
+            .unwrap_or_else(|| config_paths::DEFAULT_PROFILE.to_string());
+        let selected = profiles
+            .iter()
+            .position(|profile| *profile == current)
+            .unwrap_or(0);
+        Self {
+            profiles,
+            selected,
+            input: TextInput::default(),
+            error: None,
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.profiles.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Handle Enter; returns the typed name if any, otherwise the selected profile
+    pub fn submit(&mut self) -> Option<String> {
+        self.error = None;
+        let typed = self.input.value().trim().to_string();
+        if typed.is_empty() {
+            return self.profiles.get(self.selected).cloned();
+        }
+        match config_paths::validate_profile_name(&typed) {
+            Ok(()) => Some(typed),
+            Err(e) => {
+                self.error = Some(e);
+                None
+            }
+        }
+    }
+}
+
+impl Default for ProfilePicker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,64 @@
+/// Scripted input: keys fed to the App as if they were typed, to automate flows such as
+/// creating a folder and a cloud for it, and to drive the App in tests. One step per line:
+///
+/// - keys in the keybinding notation, separated by spaces: `n`, `G`, `<Enter>`, `<S-Tab>`,
+///   `<Ctrl>p`, `<Space>`, `<leader>`
+/// - `type <text>`: the text after `type `, one key per character, spaces included
+/// - `:<command>`: a command line, typed and submitted
+///
+/// Blank lines and lines starting with `#` are skipped.
+use crate::config::string_to_key;
+use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptStep {
+    Key(KeyCode, KeyModifiers),
+    /// Whichever key is the leader in the config the script runs with
+    Leader,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Script {
+    pub steps: Vec<ScriptStep>,
+}
+
+impl Script {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let typed = |text: &str| {
+            text.chars()
+                .map(|c| ScriptStep::Key(KeyCode::Char(c), KeyModifiers::NONE))
+                .collect::<Vec<_>>()
+        };
+        let mut steps = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if let Some(text) = line.trim_start().strip_prefix("type ") {
+                steps.extend(typed(text));
+            } else if trimmed.starts_with(':') {
+                steps.extend(typed(trimmed));
+                steps.push(ScriptStep::Key(KeyCode::Enter, KeyModifiers::NONE));
+            } else {
+                for key in trimmed.split_whitespace() {
+                    let step = match key {
+                        "<leader>" => ScriptStep::Leader,
+                        _ => string_to_key(key)
+                            .map(|(code, modifiers)| ScriptStep::Key(code, modifiers))
+                            .ok_or_else(|| format!("line {}: unknown key '{}'", number + 1, key))?,
+                    };
+                    steps.push(step);
+                }
+            }
+        }
+        Ok(Self { steps })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
@@ -0,0 +1,199 @@
+/// The server defaults of the Settings tab, each edited as text in a small prompt
+use crate::utils::text_input::TextInput;
+use cloudhost_server::config_paths::{self, Overrides};
+use cloudhost_server::defaults::ServerDefaults;
+use cloudhost_server::LogLevel;
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefaultField {
+    BasePort,
+    BindAddress,
+    LogLevel,
+    SessionLifetime,
+    MinFreeSpace,
+    MaxUpload,
+}
+
+impl DefaultField {
+    /// In the order of the Settings tab
+    pub const ALL: [DefaultField; 6] = [
+        DefaultField::BasePort,
+        DefaultField::BindAddress,
+        DefaultField::LogLevel,
+        DefaultField::SessionLifetime,
+        DefaultField::MinFreeSpace,
+        DefaultField::MaxUpload,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DefaultField::BasePort => "Base port",
+            DefaultField::BindAddress => "Bind address",
+            DefaultField::LogLevel => "Log level",
+            DefaultField::SessionLifetime => "Login lifetime",
+            DefaultField::MinFreeSpace => "Free space for uploads",
+            DefaultField::MaxUpload => "Largest upload",
+        }
+    }
+
+    /// What to type, shown in the prompt
+    pub fn help(self) -> &'static str {
+        match self {
+            DefaultField::BasePort => "Port of the first cloud started, 1-65535",
+            DefaultField::BindAddress => "0.0.0.0 for every network, 127.0.0.1 for this machine",
+            DefaultField::LogLevel => "debug, info, warn or error; empty for debug",
+            DefaultField::SessionLifetime => "Hours a web login lasts; empty for 24",
+            DefaultField::MinFreeSpace => {
+                "MB uploads must leave free, 0 for no limit; empty for 512"
+            }
+            DefaultField::MaxUpload => "MB an upload may have, 0 for no limit; empty for 10240",
+        }
+    }
+
+    /// Running clouds take the change, rather than the next time they start
+    pub fn applies_right_away(self) -> bool {
+        matches!(self, DefaultField::MinFreeSpace | DefaultField::MaxUpload)
+    }
+
+    /// The environment variable set over this field of the config, if any
+    pub fn overridden_by(self, overrides: &Overrides) -> Option<&'static str> {
+        match self {
+            DefaultField::BasePort => overrides.base_port.map(|_| config_paths::BASE_PORT_VAR),
+            DefaultField::BindAddress => overrides
+                .bind_address
+                .map(|_| config_paths::BIND_ADDRESS_VAR),
+            DefaultField::LogLevel => overrides
+                .log_level
+                .as_ref()
+                .map(|_| config_paths::LOG_LEVEL_VAR),
+            DefaultField::SessionLifetime
+            | DefaultField::MinFreeSpace
+            | DefaultField::MaxUpload => None,
+        }
+    }
+
+    /// The value as shown in the Settings tab
+    pub fn display(self, defaults: &ServerDefaults) -> String {
+        match self {
+            DefaultField::BasePort => defaults.base_port.to_string(),
+            DefaultField::BindAddress => defaults.bind_address.to_string(),
+            DefaultField::LogLevel => match &defaults.log_level {
+                Some(level) => level.to_string().to_lowercase(),
+                None => "debug (built-in)".to_string(),
+            },
+            DefaultField::SessionLifetime => match defaults.session_lifetime_hours {
+                Some(hours) => format!("{} h", hours),
+                None => "24 h (built-in)".to_string(),
+            },
+            DefaultField::MinFreeSpace => match defaults.min_free_mb {
+                Some(mb) => format!("{} MB", mb),
+                None => "512 MB (built-in)".to_string(),
+            },
+            DefaultField::MaxUpload => match defaults.max_upload_mb {
+                Some(mb) => format!("{} MB", mb),
+                None => "10240 MB (built-in)".to_string(),
+            },
+        }
+    }
+
+    /// The value as typed in the prompt, empty for the built-in one
+    pub fn raw(self, defaults: &ServerDefaults) -> String {
+        match self {
+            DefaultField::BasePort => defaults.base_port.to_string(),
+            DefaultField::BindAddress => defaults.bind_address.to_string(),
+            DefaultField::LogLevel => defaults
+                .log_level
+                .as_ref()
+                .map(|level| level.to_string().to_lowercase())
+                .unwrap_or_default(),
+            DefaultField::SessionLifetime => defaults
+                .session_lifetime_hours
+                .map(|hours| hours.to_string())
+                .unwrap_or_default(),
+            DefaultField::MinFreeSpace => defaults
+                .min_free_mb
+                .map(|mb| mb.to_string())
+                .unwrap_or_default(),
+            DefaultField::MaxUpload => defaults
+                .max_upload_mb
+                .map(|mb| mb.to_string())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Set the field of `defaults` from what was typed
+    pub fn parse(self, text: &str, defaults: &mut ServerDefaults) -> Result<(), String> {
+        let text = text.trim();
+        match self {
+            DefaultField::BasePort => {
+                defaults.base_port = text
+                    .parse()
+                    .ok()
+                    .filter(|port| *port > 0)
+                    .ok_or_else(|| format!("'{}' is not a port from 1 to 65535", text))?;
+            }
+            DefaultField::BindAddress => {
+                defaults.bind_address = text
+                    .parse::<IpAddr>()
+                    .map_err(|_| format!("'{}' is not an IP address", text))?;
+            }
+            DefaultField::LogLevel => {
+                defaults.log_level = if text.is_empty() {
+                    None
+                } else {
+                    Some(text.parse::<LogLevel>()?)
+                };
+            }
+            DefaultField::SessionLifetime => {
+                defaults.session_lifetime_hours = if text.is_empty() {
+                    None
+                } else {
+                    Some(
+                        text.parse()
+                            .map_err(|_| format!("'{}' is not a number of hours", text))?,
+                    )
+                };
+            }
+            DefaultField::MinFreeSpace => {
+                defaults.min_free_mb = if text.is_empty() {
+                    None
+                } else {
+                    Some(
+                        text.parse()
+                            .map_err(|_| format!("'{}' is not a number of MB", text))?,
+                    )
+                };
+            }
+            DefaultField::MaxUpload => {
+                defaults.max_upload_mb = if text.is_empty() {
+                    None
+                } else {
+                    Some(
+                        text.parse()
+                            .map_err(|_| format!("'{}' is not a number of MB", text))?,
+                    )
+                };
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A server default being edited
+#[derive(Debug, Clone)]
+pub struct DefaultEditor {
+    pub field: DefaultField,
+    pub input: TextInput,
+    pub error: Option<String>,
+}
+
+impl DefaultEditor {
+    pub fn new(field: DefaultField, defaults: &ServerDefaults) -> Self {
+        Self {
+            field,
+            input: TextInput::new(field.raw(defaults)),
+            error: None,
+        }
+    }
+}
@@ -0,0 +1,236 @@
+/// A single line of text being typed: the cursor, a selection and the keys that edit them.
+/// Used by the modals' name, path and password fields and the search prompts.
+///
+/// Left/Right move by character, with Ctrl by word; Home/End (and Ctrl-A/Ctrl-E) go to
+/// either end. Shift with any of them extends the selection, which typing, Backspace and
+/// Delete replace. Backspace and Delete remove a character, with Ctrl (or Alt-Backspace
+/// and Ctrl-W) a word; Ctrl-U and Ctrl-K remove everything before and after the cursor.
+use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+use std::fmt;
+use std::ops::{Deref, Range};
+
+#[derive(Debug, Clone, Default)]
+pub struct TextInput {
+    value: String,
+    /// In characters, 0 to the length of the value
+    cursor: usize,
+    /// The other end of the selection, the cursor being one end
+    anchor: Option<usize>,
+}
+
+impl TextInput {
+    /// An input holding `value`, the cursor after it
+    pub fn new(value: impl Into<String>) -> Self {
+        let mut input = Self::default();
+        input.set(value);
+        input
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Replace the text, the cursor after it
+    pub fn set(&mut self, value: impl Into<String>) {
+        self.value = value.into();
+        self.cursor = self.char_count();
+        self.anchor = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.set(String::new());
+    }
+
+    /// The text, leaving the input empty
+    pub fn take(&mut self) -> String {
+        let value = std::mem::take(&mut self.value);
+        self.clear();
+        value
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Selected characters, as a range of character positions
+    pub fn selection(&self) -> Option<Range<usize>> {
+        let anchor = self.anchor?;
+        (anchor != self.cursor).then(|| anchor.min(self.cursor)..anchor.max(self.cursor))
+    }
+
+    pub fn char_count(&self) -> usize {
+        self.value.chars().count()
+    }
+
+    /// Type `c` at the cursor, over the selection if there is one
+    pub fn insert_char(&mut self, c: char) {
+        self.delete_selection();
+        let at = self.byte_index(self.cursor);
+        self.value.insert(at, c);
+        self.cursor += 1;
+    }
+
+    /// Paste `text` at the cursor. Line breaks and other control characters are dropped,
+    /// the input being a single line.
+    pub fn insert_str(&mut self, text: &str) {
+        self.delete_selection();
+        let text: String = text.chars().filter(|c| !c.is_control()).collect();
+        let at = self.byte_index(self.cursor);
+        self.value.insert_str(at, &text);
+        self.cursor += text.chars().count();
+    }
+
+    /// Drop characters past the first `max`
+    pub fn truncate(&mut self, max: usize) {
+        if self.char_count() > max {
+            self.value.truncate(self.byte_index(max));
+            self.cursor = self.cursor.min(max);
+            self.anchor = self.anchor.map(|anchor| anchor.min(max));
+        }
+    }
+
+    /// Edit or move by `key`. False for keys that aren't about editing text, such as Enter,
+    /// Esc or Tab, which are left to the modal.
+    pub fn handle_key(&mut self, key: KeyCode, modifiers: KeyModifiers) -> bool {
+        let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+        let alt = modifiers.contains(KeyModifiers::ALT);
+        let shift = modifiers.contains(KeyModifiers::SHIFT);
+        match key {
+            KeyCode::Left if ctrl => self.move_to(self.word_start(), shift),
+            KeyCode::Right if ctrl => self.move_to(self.word_end(), shift),
+            KeyCode::Left => self.move_to(self.cursor.saturating_sub(1), shift),
+            KeyCode::Right => self.move_to((self.cursor + 1).min(self.char_count()), shift),
+            KeyCode::Home => self.move_to(0, shift),
+            KeyCode::End => self.move_to(self.char_count(), shift),
+            KeyCode::Char('a') if ctrl => self.move_to(0, shift),
+            KeyCode::Char('e') if ctrl => self.move_to(self.char_count(), shift),
+            KeyCode::Backspace if ctrl || alt => self.delete_back(self.word_start()),
+            KeyCode::Char('w') if ctrl => self.delete_back(self.word_start()),
+            KeyCode::Char('h') if ctrl => self.delete_back(self.cursor.saturating_sub(1)),
+            KeyCode::Backspace => self.delete_back(self.cursor.saturating_sub(1)),
+            KeyCode::Delete if ctrl => self.delete_forward(self.word_end()),
+            KeyCode::Delete => self.delete_forward((self.cursor + 1).min(self.char_count())),
+            KeyCode::Char('u') if ctrl => self.delete_back(0),
+            KeyCode::Char('k') if ctrl => self.delete_forward(self.char_count()),
+            KeyCode::Char(_) if ctrl || alt => return false,
+            KeyCode::Char(c) => self.insert_char(c),
+            _ => return false,
+        }
+        true
+    }
+
+    fn move_to(&mut self, position: usize, select: bool) {
+        if select {
+            self.anchor.get_or_insert(self.cursor);
+        } else {
+            self.anchor = None;
+        }
+        self.cursor = position;
+    }
+
+    /// Remove the selection, or the characters from `from` up to the cursor
+    fn delete_back(&mut self, from: usize) {
+        if !self.delete_selection() {
+            self.remove(from..self.cursor);
+            self.cursor = from;
+        }
+    }
+
+    /// Remove the selection, or the characters from the cursor up to `to`
+    fn delete_forward(&mut self, to: usize) {
+        if !self.delete_selection() {
+            self.remove(self.cursor..to);
+        }
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        let selection = self.selection();
+        self.anchor = None;
+        match selection {
+            Some(range) => {
+                self.cursor = range.start;
+                self.remove(range);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn remove(&mut self, chars: Range<usize>) {
+        let bytes = self.byte_index(chars.start)..self.byte_index(chars.end);
+        self.value.replace_range(bytes, "");
+    }
+
+    fn byte_index(&self, chars: usize) -> usize {
+        self.value
+            .char_indices()
+            .nth(chars)
+            .map_or(self.value.len(), |(i, _)| i)
+    }
+
+    /// Start of the word before the cursor, past any separators right before it
+    fn word_start(&self) -> usize {
+        let chars: Vec<char> = self.value.chars().collect();
+        let mut i = self.cursor;
+        while i > 0 && !is_word_char(chars[i - 1]) {
+            i -= 1;
+        }
+        while i > 0 && is_word_char(chars[i - 1]) {
+            i -= 1;
+        }
+        i
+    }
+
+    /// End of the word after the cursor, past any separators right after it
+    fn word_end(&self) -> usize {
+        let chars: Vec<char> = self.value.chars().collect();
+        let mut i = self.cursor;
+        while i < chars.len() && !is_word_char(chars[i]) {
+            i += 1;
+        }
+        while i < chars.len() && is_word_char(chars[i]) {
+            i += 1;
+        }
+        i
+    }
+}
+
+/// Words end at spaces and punctuation, so word-wise moves stop at each part of a path
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl Deref for TextInput {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.value
+    }
+}
+
+impl fmt::Display for TextInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.value)
+    }
+}
+
+/// Inputs are equal when their text is, wherever the cursor is
+impl PartialEq for TextInput {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl Eq for TextInput {}
+
+impl From<&str> for TextInput {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<String> for TextInput {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
@@ -0,0 +1,67 @@
+/// Undo/redo history for clouds and cloud folders removed from the TUI.
+/// Only the removed items are kept here; re-adding them to the config is left to the App.
+use cloudhost_server::{Cloud, CloudFolder};
+
+/// How many deletions can be undone
+pub const MAX_UNDO: usize = 20;
+
+#[derive(Debug, Clone)]
+pub enum RemovedItem {
+    Folder(CloudFolder),
+    Cloud(Box<Cloud>),
+}
+
+impl RemovedItem {
+    pub fn name(&self) -> &str {
+        match self {
+            RemovedItem::Folder(folder) => &folder.name,
+            RemovedItem::Cloud(cloud) => &cloud.name,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RemovedItem::Folder(_) => "folder",
+            RemovedItem::Cloud(_) => "cloud",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct UndoStack {
+    undo: Vec<RemovedItem>,
+    redo: Vec<RemovedItem>,
+}
+
+impl UndoStack {
+    /// Record a new deletion. This forgets anything that could be redone.
+    pub fn record(&mut self, item: RemovedItem) {
+        self.redo.clear();
+        self.push_undo(item);
+    }
+
+    pub fn pop_undo(&mut self) -> Option<RemovedItem> {
+        self.undo.pop()
+    }
+
+    pub fn pop_redo(&mut self) -> Option<RemovedItem> {
+        self.redo.pop()
+    }
+
+    /// Put back an item after it was restored, so it can be removed again
+    pub fn push_redo(&mut self, item: RemovedItem) {
+        self.redo.push(item);
+    }
+
+    /// Put back an item after a redo (or a failed undo) without touching the redo history
+    pub fn push_undo(&mut self, item: RemovedItem) {
+        self.undo.push(item);
+        if self.undo.len() > MAX_UNDO {
+            self.undo.remove(0);
+        }
+    }
+
+    pub fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+}
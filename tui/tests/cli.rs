@@ -0,0 +1,136 @@
+//! `cloud`, `folder` and `password` subcommands on the clouds config, without the TUI.
+
+mod common;
+
+use cloudhost_server::CloudsConfig;
+use cloudhost_tui::cli::{
+    run_cloud, run_folder, run_password, CloudCommand, FolderCommand, PasswordCommand,
+};
+use common::{TempDir, TestApp};
+
+#[test]
+fn folders_and_clouds_are_added_listed_and_removed() {
+    let mut app = TestApp::new();
+    let orchestrator = &mut app.app.orchestrator;
+    let dir = TempDir::new("cli-folder");
+
+    let output = run_folder(
+        orchestrator,
+        FolderCommand::Add {
+            name: "photos".to_string(),
+            path: dir.path().to_path_buf(),
+        },
+    )
+    .unwrap();
+    assert!(output.starts_with("Added folder 'photos'"), "{}", output);
+    let listed = run_folder(orchestrator, FolderCommand::List).unwrap();
+    assert!(listed.starts_with("photos\t"), "{}", listed);
+
+    run_cloud(
+        orchestrator,
+        CloudCommand::Add {
+            name: "family".to_string(),
+            folders: vec!["photos".to_string()],
+            tags: vec!["home".to_string()],
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        run_cloud(orchestrator, CloudCommand::List).unwrap(),
+        "family\tphotos\t#home\t(no password)"
+    );
+    let saved = CloudsConfig::load_from_file().unwrap();
+    assert_eq!(saved.clouds[0].name, "family");
+    assert_eq!(saved.clouds[0].tags, ["home"]);
+
+    run_cloud(
+        orchestrator,
+        CloudCommand::Remove {
+            name: "family".to_string(),
+        },
+    )
+    .unwrap();
+    run_folder(
+        orchestrator,
+        FolderCommand::Remove {
+            name: "photos".to_string(),
+        },
+    )
+    .unwrap();
+    let saved = CloudsConfig::load_from_file().unwrap();
+    assert!(saved.clouds.is_empty());
+    assert!(saved.cloud_folders.is_empty());
+    // The directory itself stays
+    assert!(dir.path().is_dir());
+}
+
+#[test]
+fn mistakes_are_refused() {
+    let mut app = TestApp::with_clouds();
+    let orchestrator = &mut app.app.orchestrator;
+
+    let error = run_folder(
+        orchestrator,
+        FolderCommand::Add {
+            name: "missing".to_string(),
+            path: "/srv/cloudhost/missing".into(),
+        },
+    )
+    .unwrap_err();
+    assert!(
+        error.to_string().contains("is not a directory"),
+        "{}",
+        error
+    );
+
+    let error = run_cloud(
+        orchestrator,
+        CloudCommand::Add {
+            name: "friends".to_string(),
+            folders: vec!["videos".to_string()],
+            tags: Vec::new(),
+        },
+    )
+    .unwrap_err();
+    assert!(
+        error.to_string().contains("No cloud folder named 'videos'"),
+        "{}",
+        error
+    );
+    assert!(CloudsConfig::load_from_file()
+        .unwrap()
+        .clouds
+        .iter()
+        .all(|cloud| cloud.name != "friends"));
+}
+
+#[test]
+fn passwords_are_set() {
+    let mut app = TestApp::with_clouds();
+    let orchestrator = &mut app.app.orchestrator;
+    let set = |cloud: &str| PasswordCommand::Set {
+        cloud: cloud.to_string(),
+    };
+
+    let error = run_password(orchestrator, set("family"), "short").unwrap_err();
+    assert!(error.to_string().contains("at least 8"), "{}", error);
+    assert!(run_password(orchestrator, set("nowhere"), "long enough").is_err());
+
+    run_password(orchestrator, set("family"), "long enough").unwrap();
+    let listed = run_cloud(orchestrator, CloudCommand::List).unwrap();
+    assert!(
+        listed.contains("family\tphotos,documents\t#home\n"),
+        "{}",
+        listed
+    );
+    assert!(
+        listed.contains("work\tprojects\t(no password)"),
+        "{}",
+        listed
+    );
+    let saved = CloudsConfig::load_from_file().unwrap();
+    assert!(saved
+        .clouds
+        .iter()
+        .any(|c| c.name == "family" && c.has_password()));
+}
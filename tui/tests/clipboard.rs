@@ -0,0 +1,74 @@
+//! The Copy actions for cloud URLs, folder paths and file request links, and the text
+//! shown instead when there is no clipboard.
+
+mod common;
+
+use cloudhost_server::file_requests::FileRequest;
+use cloudhost_tui::tabs::SelectedTab;
+use cloudhost_tui::utils::clipboard::Clipboard;
+use cloudhost_tui::utils::script::Script;
+use common::{free_port, TempDir, TestApp};
+use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+
+#[tokio::test]
+async fn folder_path_is_shown_without_a_clipboard() {
+    let mut app = TestApp::with_clouds();
+    app.app.clipboard = Clipboard::disabled();
+    app.app.selected_tab = SelectedTab::Folders;
+
+    app.app.apply_action("Copy Path", &[]).await.unwrap();
+    let fallback = app.app.copy_fallback.clone().expect("shown instead");
+    assert_eq!(fallback.text, "/srv/cloudhost/photos");
+    let screen = app.render(120, 36);
+    assert!(screen.contains("Copy the path of 'photos'"), "{}", screen);
+    assert!(screen.contains("/srv/cloudhost/photos"), "{}", screen);
+
+    app.app
+        .handle_dynamic_key(KeyCode::Esc, KeyModifiers::NONE)
+        .await;
+    assert!(app.app.copy_fallback.is_none());
+}
+
+#[tokio::test]
+async fn cloud_url_and_share_links_need_a_running_cloud() {
+    let mut app = TestApp::new();
+    app.app.clipboard = Clipboard::disabled();
+    let photos = TempDir::new("photos");
+    let path = photos.path().to_string_lossy().to_string();
+    let tui = &mut app.app;
+    tui.apply_action("addfolder", &["photos", &path])
+        .await
+        .unwrap();
+    tui.apply_action("addcloud", &["family", "photos"])
+        .await
+        .unwrap();
+    tui.apply_action("password", &["family"]).await.unwrap();
+    let typed = Script::parse("type correct horse\n<Enter>\ntype correct horse\n<Enter>").unwrap();
+    tui.run_script(&typed).await;
+
+    let mut cloud = tui.orchestrator.get_cloud("family").unwrap();
+    let open = FileRequest::new("Wedding photos", "photos", "wedding");
+    let mut full = FileRequest::new("Old", "photos", "");
+    full.max_files = Some(1);
+    full.uploads = 1;
+    cloud.file_requests = vec![open.clone(), full];
+    tui.orchestrator.update_cloud("family", cloud).unwrap();
+    tui.selected_tab = SelectedTab::Clouds;
+
+    // Stopped clouds have no URL yet
+    tui.apply_action("Copy Cloud URL", &[]).await.unwrap();
+    assert!(tui.copy_fallback.is_none());
+
+    let port = free_port();
+    tui.orchestrator.next_port = port;
+    tui.apply_action("start", &["family"]).await.unwrap();
+    tui.apply_action("Copy Cloud URL", &[]).await.unwrap();
+    let url = tui.copy_fallback.take().expect("shown instead").text;
+    assert!(url.ends_with(&format!(":{}", port)), "{}", url);
+
+    // Only the links that still take files
+    tui.apply_action("Copy Share Links", &[]).await.unwrap();
+    let fallback = tui.copy_fallback.take().expect("shown instead");
+    assert_eq!(fallback.text, format!("{}/drop/{}", url, open.id));
+    assert_eq!(fallback.what, "file request link of 'family'");
+}
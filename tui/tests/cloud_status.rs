@@ -0,0 +1,95 @@
+//! The lists of clouds in the Clouds and Folders tabs show whether each cloud is running,
+//! the port it's on, and when it failed to start.
+
+mod common;
+
+use cloudhost_tui::tabs::SelectedTab;
+use cloudhost_tui::utils::script::Script;
+use common::{free_port, TempDir, TestApp};
+
+/// Wide enough for the port and badge in the narrow clouds column
+const WIDTH: u16 = 200;
+
+#[tokio::test]
+async fn lists_show_the_state_and_port_of_clouds() {
+    let mut app = TestApp::new();
+    let photos = TempDir::new("photos");
+    let path = photos.path().to_string_lossy().to_string();
+    let tui = &mut app.app;
+    tui.apply_action("addfolder", &["photos", &path])
+        .await
+        .unwrap();
+    tui.apply_action("addcloud", &["family", "photos"])
+        .await
+        .unwrap();
+
+    let screen = app.render(WIDTH, 36);
+    assert!(screen.contains("🔴 family"), "{}", screen);
+
+    // Starting without a password fails, and the list says so
+    let tui = &mut app.app;
+    assert!(tui.apply_action("start", &["family"]).await.is_err());
+    let screen = app.render(WIDTH, 36);
+    assert!(screen.contains("❌ family failed"), "{}", screen);
+
+    let tui = &mut app.app;
+    tui.apply_action("password", &["family"]).await.unwrap();
+    let typed = Script::parse("type correct horse\n<Enter>\ntype correct horse\n<Enter>").unwrap();
+    tui.run_script(&typed).await;
+    let port = free_port();
+    tui.orchestrator.next_port = port;
+    tui.apply_action("start", &["family"]).await.unwrap();
+
+    let running = format!("🟢 family :{}", port);
+    let screen = app.render(WIDTH, 36);
+    assert!(screen.contains(&running), "{}", screen);
+    app.app.selected_tab = SelectedTab::Folders;
+    app.app.folders_state.focused_panel =
+        cloudhost_tui::tabs::folders::models::FocusedPanel::Clouds;
+    let screen = app.render(WIDTH, 36);
+    assert!(screen.contains(&running), "{}", screen);
+
+    app.app.apply_action("stop", &["family"]).await.unwrap();
+    assert!(app.render(WIDTH, 36).contains("🔴 family"));
+}
+
+#[tokio::test]
+async fn clouds_with_an_unavailable_folder_are_degraded() {
+    let mut app = TestApp::new();
+    let photos = TempDir::new("photos");
+    let path = photos.path().to_path_buf();
+    let tui = &mut app.app;
+    tui.apply_action("addfolder", &["photos", &path.to_string_lossy()])
+        .await
+        .unwrap();
+    tui.apply_action("addcloud", &["family", "photos"])
+        .await
+        .unwrap();
+
+    // The drive is unplugged
+    let unplugged = path.with_extension("unplugged");
+    std::fs::rename(&path, &unplugged).unwrap();
+    app.app.on_tick().await;
+    let warned = app.app.notifications.history.iter().any(|notification| {
+        notification
+            .message
+            .contains("Cloud folder 'photos' is unavailable")
+    });
+    assert!(warned);
+    assert_eq!(
+        app.app.orchestrator.unavailable_folders("family"),
+        ["photos"]
+    );
+    let screen = app.render(WIDTH, 36);
+    assert!(screen.contains("🔴 family degraded"), "{}", screen);
+    assert!(
+        screen.contains("Degraded: photos unavailable"),
+        "{}",
+        screen
+    );
+
+    std::fs::rename(&unplugged, &path).unwrap();
+    let changes = app.app.orchestrator.check_folder_health();
+    assert_eq!(changes[0].problem, None);
+    assert!(!app.render(WIDTH, 36).contains("degraded"));
+}
@@ -0,0 +1,149 @@
+//! Test harness: an `App` over a config of its own in a temp config dir, with a few
+//! clouds and folders, rendered to a `TestBackend` of any size. The config dir is set
+//! through `CLOUDHOST_CONFIG_DIR`, which the whole process shares, so tests take turns.
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use cloudhost_server::{Cloud, CloudFolder, Orchestrator, PasswordHashParams};
+use cloudhost_tui::App;
+use ratatui::{backend::TestBackend, Terminal};
+
+/// Cloud folders of `TestApp::with_clouds`. Their paths don't exist, so nothing on the
+/// machine running the tests shows up in what's rendered.
+pub const FOLDERS: &[(&str, &str)] = &[
+    ("photos", "/srv/cloudhost/photos"),
+    ("documents", "/srv/cloudhost/documents"),
+    ("projects", "/srv/cloudhost/projects"),
+];
+
+/// Clouds of `TestApp::with_clouds`, with their folders and tags
+pub const CLOUDS: &[(&str, &[&str], &[&str])] = &[
+    ("family", &["photos", "documents"], &["home"]),
+    ("work", &["projects"], &[]),
+];
+
+static TURN: Mutex<()> = Mutex::new(());
+
+/// A local port nothing listens on
+pub fn free_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .expect("find a free port")
+}
+
+/// A directory under the system temp dir, removed on drop
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(prefix: &str) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "cloudhost-tui-{}-{}-{}",
+            prefix,
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&path).expect("create temp dir");
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+pub struct TestApp {
+    pub app: App,
+    pub config_dir: TempDir,
+    _turn: MutexGuard<'static, ()>,
+}
+
+impl TestApp {
+    /// An app with empty configs in a fresh config dir
+    pub fn new() -> Self {
+        Self::with_config(|_| {})
+    }
+
+    /// An app whose clouds config has `FOLDERS` and `CLOUDS`
+    pub fn with_clouds() -> Self {
+        let folder = |name: &str| {
+            let (name, path) = FOLDERS
+                .iter()
+                .find(|(folder, _)| *folder == name)
+                .expect("one of FOLDERS");
+            CloudFolder::new(name.to_string(), PathBuf::from(path))
+        };
+        Self::with_config(|orchestrator| {
+            for (name, _) in FOLDERS {
+                orchestrator
+                    .add_cloud_folder(folder(name))
+                    .expect("add cloud folder");
+            }
+            for (name, folders, tags) in CLOUDS {
+                let folders = folders.iter().map(|name| folder(name)).collect();
+                let mut cloud = Cloud::new(name.to_string(), folders);
+                cloud.tags = tags.iter().map(|tag| tag.to_string()).collect();
+                orchestrator.add_cloud(cloud).expect("add cloud");
+            }
+        })
+    }
+
+    /// An app over the clouds config `setup` writes into a fresh config dir
+    fn with_config(setup: impl FnOnce(&mut Orchestrator)) -> Self {
+        // A test that failed while holding the turn leaves nothing behind worth waiting for
+        let turn = TURN.lock().unwrap_or_else(|e| e.into_inner());
+        let config_dir = TempDir::new("config");
+        // Made the working directory and given as `.`, so the config paths the settings
+        // tab shows are the same on every machine
+        std::env::set_current_dir(config_dir.path()).expect("enter the config dir");
+        std::env::set_var("CLOUDHOST_CONFIG_DIR", ".");
+        // A test that switched profiles and failed may have left one set
+        std::env::remove_var("CLOUDHOST_PROFILE");
+        cloudhost_server::config_paths::set_profile(
+            cloudhost_server::config_paths::DEFAULT_PROFILE,
+        )
+        .expect("switch to the default profile");
+        // Deleted files go to a trash in the config dir rather than the user's
+        std::env::set_var("XDG_DATA_HOME", config_dir.path());
+        // The title says so, whichever way the tests are run
+        std::env::set_var("CLOUDHOST_DEV", "1");
+
+        setup(&mut Orchestrator::new());
+        let mut app = App::new();
+        // The cheapest Argon2 parameters; setting passwords doesn't need to be slow here
+        app.orchestrator.clouds_config.password_hashing = PasswordHashParams {
+            memory_kib: 8,
+            iterations: 1,
+            parallelism: 1,
+        };
+        Self {
+            app,
+            config_dir,
+            _turn: turn,
+        }
+    }
+
+    /// The app drawn on a `width` x `height` terminal
+    pub fn draw(&mut self, width: u16, height: u16) -> Terminal<TestBackend> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+        terminal
+            .draw(|frame| frame.render_widget(&mut self.app, frame.area()))
+            .expect("draw");
+        terminal
+    }
+
+    /// What `draw` shows, one quoted line per row
+    pub fn render(&mut self, width: u16, height: u16) -> String {
+        self.draw(width, height).backend().to_string()
+    }
+}
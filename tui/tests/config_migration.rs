@@ -0,0 +1,33 @@
+//! Upgrading a TUI config written by an older CloudHost.
+
+mod common;
+
+use cloudhost_server::config_paths;
+use cloudhost_tui::config::{Config, TUI_SCHEMA_VERSION};
+use common::TestApp;
+
+#[test]
+fn confirm_deletes_is_carried_over_with_a_backup() {
+    let _app = TestApp::new();
+    let path = config_paths::get_tui_config_path();
+    let original = "leader = \" \"\nconfirm_deletes = false\n\n[actions]\n";
+    std::fs::write(&path, original).unwrap();
+
+    let config = Config::load().unwrap();
+    assert_eq!(config.schema_version, TUI_SCHEMA_VERSION);
+    assert!(!config.confirmations);
+
+    let upgraded = std::fs::read_to_string(&path).unwrap();
+    assert!(upgraded.contains("schema_version = 2"), "{}", upgraded);
+    assert!(!upgraded.contains("confirm_deletes"), "{}", upgraded);
+    let mut backup = path.into_os_string();
+    backup.push(".v1");
+    assert_eq!(std::fs::read_to_string(backup).unwrap(), original);
+
+    // A setting already in the new form wins
+    let config = Config::parse(
+        "leader = \" \"\nconfirm_deletes = false\nconfirmations = true\n\n[actions]\n",
+    )
+    .unwrap();
+    assert!(config.confirmations);
+}
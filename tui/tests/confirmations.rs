@@ -0,0 +1,117 @@
+//! The yes/no dialog before destructive actions: deleting folders and clouds, and stopping
+//! a cloud that is still serving clients, and `confirmations = false` to skip it.
+
+mod common;
+
+use cloudhost_tui::tabs::SelectedTab;
+use cloudhost_tui::utils::confirmation::ConfirmAction;
+use cloudhost_tui::utils::script::Script;
+use common::{free_port, TempDir, TestApp};
+use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+
+async fn press(app: &mut TestApp, key: KeyCode) {
+    app.app.handle_dynamic_key(key, KeyModifiers::NONE).await;
+}
+
+fn folder_names(app: &TestApp) -> Vec<&str> {
+    app.app
+        .folders_state
+        .cloud_folders
+        .iter()
+        .map(|folder| folder.name.as_str())
+        .collect()
+}
+
+#[tokio::test]
+async fn deleting_a_folder_asks_first() {
+    let mut app = TestApp::with_clouds();
+    app.app.selected_tab = SelectedTab::Folders;
+
+    app.app.apply_action("Delete Folder", &[]).await.unwrap();
+    let confirmation = app.app.confirmation.clone().expect("asked");
+    assert_eq!(
+        confirmation.action,
+        ConfirmAction::DeleteFolder("photos".to_string())
+    );
+    let screen = app.render(120, 36);
+    assert!(
+        screen.contains("Remove cloud folder 'photos'?"),
+        "{}",
+        screen
+    );
+    assert!(screen.contains("Removed from family"), "{}", screen);
+
+    // No keeps the folder, yes removes it
+    press(&mut app, KeyCode::Char('n')).await;
+    assert!(app.app.confirmation.is_none());
+    assert_eq!(folder_names(&app), ["photos", "documents", "projects"]);
+
+    app.app.apply_action("Delete Folder", &[]).await.unwrap();
+    // Keys that don't answer leave the dialog open
+    press(&mut app, KeyCode::Char('j')).await;
+    assert!(app.app.confirmation.is_some());
+    press(&mut app, KeyCode::Char('y')).await;
+    assert!(app.app.confirmation.is_none());
+    assert_eq!(folder_names(&app), ["documents", "projects"]);
+}
+
+#[tokio::test]
+async fn confirmations_can_be_turned_off() {
+    let mut app = TestApp::with_clouds();
+    app.app.selected_tab = SelectedTab::Folders;
+    app.app.config.confirmations = false;
+
+    app.app.apply_action("Delete Cloud", &[]).await.unwrap();
+    assert!(app.app.confirmation.is_none());
+    assert_eq!(app.app.folders_state.clouds.len(), 1);
+}
+
+#[tokio::test]
+async fn stopping_a_cloud_with_clients_asks_first() {
+    let mut app = TestApp::new();
+    let photos = TempDir::new("photos");
+    let path = photos.path().to_string_lossy().to_string();
+    let tui = &mut app.app;
+    tui.apply_action("addfolder", &["photos", &path])
+        .await
+        .unwrap();
+    tui.apply_action("addcloud", &["family", "photos"])
+        .await
+        .unwrap();
+    tui.apply_action("password", &["family"]).await.unwrap();
+    let typed = Script::parse("type correct horse\n<Enter>\ntype correct horse\n<Enter>").unwrap();
+    tui.run_script(&typed).await;
+    tui.orchestrator.next_port = free_port();
+    tui.apply_action("start", &["family"]).await.unwrap();
+
+    // Nobody connected, so it stops right away
+    tui.apply_action("stop", &["family"]).await.unwrap();
+    assert!(tui.confirmation.is_none());
+    assert!(!tui.orchestrator.is_cloud_running("family"));
+
+    tui.apply_action("start", &["family"]).await.unwrap();
+    let stats = tui.orchestrator.cloud_stats("family").unwrap();
+    let request = axum::extract::Request::builder()
+        .uri("/photos/big.iso")
+        .body(axum::body::Body::empty())
+        .unwrap();
+    let _client = stats
+        .clients
+        .register("127.0.0.1".parse().unwrap(), &request);
+
+    tui.apply_action("stop", &["family"]).await.unwrap();
+    assert_eq!(
+        tui.confirmation.as_ref().map(|c| &c.action),
+        Some(&ConfirmAction::StopCloud("family".to_string()))
+    );
+    let screen = app.render(120, 36);
+    assert!(screen.contains("1 client(s) still connected"), "{}", screen);
+    assert!(screen.contains("/photos/big.iso"), "{}", screen);
+
+    press(&mut app, KeyCode::Esc).await;
+    assert!(app.app.orchestrator.is_cloud_running("family"));
+
+    app.app.apply_action("stop", &["family"]).await.unwrap();
+    press(&mut app, KeyCode::Enter).await;
+    assert!(!app.app.orchestrator.is_cloud_running("family"));
+}
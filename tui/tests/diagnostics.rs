@@ -0,0 +1,43 @@
+//! The Diagnostics panel of the Settings tab: the local checks right away, the ones that
+//! need the internet when they come back.
+
+mod common;
+
+use cloudhost_server::doctor::{Check, Status};
+use cloudhost_tui::events::AppEvent;
+use common::TestApp;
+use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+
+#[tokio::test]
+async fn panel_shows_checks_and_closes() {
+    let mut app = TestApp::with_clouds();
+    app.app.apply_action("Run Diagnostics", &[]).await.unwrap();
+
+    let diagnostics = app.app.diagnostics.as_ref().expect("panel open");
+    // Nothing to send the online results back through in tests
+    assert!(!diagnostics.checking_online);
+    assert!(diagnostics
+        .checks
+        .iter()
+        .any(|check| check.area == "folders" && check.status == Status::Error));
+    let screen = app.render(120, 40);
+    assert!(screen.contains("Diagnostics"), "{}", screen);
+    assert!(screen.contains("/srv/cloudhost/photos"), "{}", screen);
+
+    app.app.diagnostics.as_mut().unwrap().checking_online = true;
+    app.app
+        .handle_app_event(AppEvent::OnlineChecks(vec![Check::new(
+            "clock",
+            Status::Ok,
+            "The clock is right",
+        )]))
+        .await;
+    let diagnostics = app.app.diagnostics.as_ref().unwrap();
+    assert!(!diagnostics.checking_online);
+    assert_eq!(diagnostics.checks.last().unwrap().area, "clock");
+
+    app.app
+        .handle_dynamic_key(KeyCode::Esc, KeyModifiers::NONE)
+        .await;
+    assert!(app.app.diagnostics.is_none());
+}
@@ -0,0 +1,152 @@
+//! The detailed folders list: folder sizes scanned in the background, and sorting by
+//! its columns without losing which folders are selected.
+
+mod common;
+
+use std::path::PathBuf;
+
+use cloudhost_tui::events::{self, AppEvent};
+use cloudhost_tui::tabs::folders::models::FolderSortColumn;
+use cloudhost_tui::tabs::SelectedTab;
+use cloudhost_tui::utils::folder_preview::FolderPreview;
+use common::{TempDir, TestApp};
+
+fn folder_names(app: &TestApp) -> Vec<&str> {
+    app.app
+        .folders_state
+        .cloud_folders
+        .iter()
+        .map(|folder| folder.name.as_str())
+        .collect()
+}
+
+fn scanned(path: &str, total_bytes: u64) -> AppEvent {
+    AppEvent::FolderScanned(FolderPreview {
+        path: PathBuf::from(path),
+        exists: true,
+        is_dir: true,
+        file_count: 1,
+        total_bytes,
+        ..FolderPreview::default()
+    })
+}
+
+#[tokio::test]
+async fn folders_are_scanned_while_details_are_shown() {
+    let mut app = TestApp::with_clouds();
+    let (sender, mut receiver) = events::channel();
+    app.app.events = Some(sender);
+    app.app.selected_tab = SelectedTab::Folders;
+
+    // Nothing is scanned for the compact list
+    app.app.on_tick().await;
+    assert!(receiver.try_recv().is_err());
+
+    app.app
+        .apply_action("Toggle Folder Details", &[])
+        .await
+        .unwrap();
+    app.app.on_tick().await;
+    for _ in 0..3 {
+        let event = tokio::time::timeout(std::time::Duration::from_secs(5), receiver.recv())
+            .await
+            .expect("a folder is scanned")
+            .unwrap();
+        app.app.handle_app_event(event).await;
+    }
+    // The test folders don't exist
+    let photos = app
+        .app
+        .folders_state
+        .folder_size(&PathBuf::from("/srv/cloudhost/photos"))
+        .expect("photos scanned");
+    assert!(!photos.exists);
+    assert!(app.render(120, 36).contains("missing"));
+
+    // Each folder once, until a refresh
+    app.app.on_tick().await;
+    assert!(receiver.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn sizes_of_a_real_folder_are_counted() {
+    let mut app = TestApp::new();
+    let folder = TempDir::new("details");
+    std::fs::write(folder.path().join("a.txt"), "12345").unwrap();
+    std::fs::create_dir(folder.path().join("sub")).unwrap();
+    std::fs::write(folder.path().join("sub/b.txt"), "123").unwrap();
+    let (sender, mut receiver) = events::channel();
+    app.app.events = Some(sender);
+    app.app
+        .orchestrator
+        .add_cloud_folder(cloudhost_server::CloudFolder::new(
+            "real".to_string(),
+            folder.path().to_path_buf(),
+        ))
+        .unwrap();
+    app.app.apply_action("Refresh/Reload", &[]).await.unwrap();
+
+    app.app.folders_state.folder_details = true;
+    app.app.on_tick().await;
+    let event = receiver.recv().await.unwrap();
+    app.app.handle_app_event(event).await;
+    let scan = app.app.folders_state.folder_size(folder.path()).unwrap();
+    assert_eq!(
+        (scan.file_count, scan.dir_count, scan.total_bytes),
+        (2, 1, 8)
+    );
+}
+
+#[tokio::test]
+async fn sorting_keeps_the_selection() {
+    let mut app = TestApp::with_clouds();
+    app.app.selected_tab = SelectedTab::Folders;
+    // documents selected, photos and projects marked
+    app.app.folders_state.selected_folder_index = 1;
+    app.app.folders_state.selected_folders.extend([0, 2]);
+
+    app.app.apply_action("Sort Folders", &[]).await.unwrap();
+    assert_eq!(
+        app.app.folders_state.folder_sort,
+        Some(FolderSortColumn::Name)
+    );
+    assert_eq!(folder_names(&app), ["documents", "photos", "projects"]);
+    assert_eq!(app.app.folders_state.selected_folder_index, 0);
+    assert_eq!(
+        app.app.folders_state.get_selected_folder_names(),
+        ["photos", "projects"]
+    );
+
+    app.app
+        .apply_action("Reverse Folder Sort", &[])
+        .await
+        .unwrap();
+    assert_eq!(folder_names(&app), ["projects", "photos", "documents"]);
+
+    // By size, largest first, as the scans come in
+    app.app.folders_state.folder_sort = Some(FolderSortColumn::Size);
+    app.app.folders_state.folder_sort_reversed = false;
+    app.app
+        .handle_app_event(scanned("/srv/cloudhost/projects", 10))
+        .await;
+    app.app
+        .handle_app_event(scanned("/srv/cloudhost/photos", 500))
+        .await;
+    assert_eq!(folder_names(&app), ["photos", "projects", "documents"]);
+    assert_eq!(
+        app.app.folders_state.get_selected_folder_names(),
+        ["photos", "projects"]
+    );
+
+    // Past the last column, back to the order of the config
+    for _ in 0..3 {
+        app.app.apply_action("Sort Folders", &[]).await.unwrap();
+    }
+    assert_eq!(app.app.folders_state.folder_sort, None);
+    assert_eq!(folder_names(&app), ["photos", "documents", "projects"]);
+    assert_eq!(
+        app.app.folders_state.get_selected_folder_names(),
+        ["photos", "projects"]
+    );
+    assert_eq!(app.app.folders_state.selected_folder_index, 1);
+}
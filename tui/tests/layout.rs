@@ -0,0 +1,122 @@
+//! Layouts for the size of the terminal: panels side by side, stacked, or one at a time
+//! with a switcher, at the sizes `[layout]` in the TUI config sets, and columns resized
+//! from the keyboard.
+
+mod common;
+
+use cloudhost_tui::config::Config;
+use cloudhost_tui::layout::{resize_column, LayoutConfig, LayoutMode};
+use common::TestApp;
+use ratatui::layout::Rect;
+
+#[test]
+fn modes_follow_the_configured_sizes() {
+    let layout = LayoutConfig::default();
+    let mode = |width, height| layout.mode(Rect::new(0, 0, width, height));
+    assert_eq!(mode(120, 20), LayoutMode::Columns);
+    assert_eq!(mode(100, 30), LayoutMode::Columns);
+    assert_eq!(mode(99, 30), LayoutMode::Stacked);
+    assert_eq!(mode(99, 23), LayoutMode::Single);
+    assert_eq!(mode(59, 60), LayoutMode::Single);
+
+    let config: LayoutConfig = toml::from_str("stack_below_width = 140").unwrap();
+    assert_eq!(config.single_below_width, 60);
+    assert_eq!(config.mode(Rect::new(0, 0, 120, 34)), LayoutMode::Stacked);
+    assert!(config.validate().is_empty());
+
+    let backwards = LayoutConfig {
+        stack_below_width: 50,
+        single_below_width: 80,
+        ..LayoutConfig::default()
+    };
+    assert_eq!(backwards.validate().len(), 1);
+}
+
+#[tokio::test]
+async fn narrow_terminals_show_one_panel_at_a_time() {
+    let mut app = TestApp::with_clouds();
+    let screen = app.render(50, 30);
+    let lines: Vec<&str> = screen.lines().collect();
+    assert!(lines[1].contains("Clouds  │  Cloud Info  │  Cloud Logs"));
+    assert!(lines[2].contains("Clouds (FOCUSED)"));
+    assert!(!screen.contains("Cloud Logs [EWID]"));
+
+    app.app
+        .apply_action("Cycle Focus Backward", &[])
+        .await
+        .unwrap();
+    let screen = app.render(50, 30);
+    assert!(screen.lines().nth(2).unwrap().contains("Cloud Logs"));
+    assert!(!screen.contains("Clouds (FOCUSED)"));
+}
+
+#[test]
+fn panels_stack_below_the_configured_width() {
+    let mut app = TestApp::with_clouds();
+    app.app.config.layout.stack_below_width = 200;
+    let screen = app.render(120, 36);
+    // The three panels start on rows of their own, each the full width
+    let tops: Vec<&str> = screen
+        .lines()
+        .filter(|line| line.starts_with("\"┌"))
+        .collect();
+    assert_eq!(tops.len(), 3, "{}", screen);
+    assert!(tops[0].starts_with("\"┌Clouds (FOCUSED)"));
+    assert!(tops[1].starts_with("\"┌Cloud Info"));
+    assert!(tops[2].starts_with("\"┌Cloud Logs"));
+}
+
+#[tokio::test]
+async fn resized_columns_are_kept_in_the_config() {
+    let mut app = TestApp::with_clouds();
+    app.app.apply_action("Grow Panel", &[]).await.unwrap();
+    assert_eq!(app.app.config.layout.clouds_columns, [20, 30, 50]);
+    // The last column takes from the one on its left
+    app.app
+        .apply_action("Cycle Focus Backward", &[])
+        .await
+        .unwrap();
+    app.app.apply_action("Grow Panel", &[]).await.unwrap();
+    assert_eq!(app.app.config.layout.clouds_columns, [20, 25, 55]);
+    assert_eq!(Config::load().unwrap().layout.clouds_columns, [20, 25, 55]);
+
+    // No narrower than the minimum
+    for _ in 0..10 {
+        app.app.apply_action("Shrink Panel", &[]).await.unwrap();
+    }
+    assert_eq!(app.app.config.layout.clouds_columns, [20, 70, 10]);
+
+    // 20% of 120 columns for the clouds list, then the info
+    let top = app.render(120, 36).lines().nth(1).unwrap().to_string();
+    let info_column = top
+        .trim_matches('"')
+        .chars()
+        .enumerate()
+        .filter(|&(_, c)| c == '┌')
+        .nth(1)
+        .map(|(i, _)| i);
+    assert_eq!(info_column, Some(24), "{}", top);
+}
+
+#[test]
+fn resizing_stops_at_the_narrowest_column() {
+    let mut columns = [15, 35, 50];
+    assert!(resize_column(&mut columns, 1, false));
+    assert_eq!(columns, [15, 30, 55]);
+    assert!(resize_column(&mut columns, 0, false));
+    assert!(!resize_column(&mut columns, 0, false));
+    assert_eq!(columns, [10, 35, 55]);
+
+    // Widths that don't add up are left alone, and the default is drawn
+    let mut broken = [50, 50, 50];
+    assert!(!resize_column(&mut broken, 0, true));
+    let layout = LayoutConfig {
+        clouds_columns: broken,
+        ..LayoutConfig::default()
+    };
+    assert_eq!(layout.validate().len(), 1);
+    assert_eq!(
+        layout.clouds_columns(),
+        LayoutConfig::default().clouds_columns()
+    );
+}
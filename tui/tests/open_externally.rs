@@ -0,0 +1,47 @@
+//! Opening clouds in the browser and folders in the file manager, and copying them
+//! instead when nothing can open them.
+
+mod common;
+
+use cloudhost_tui::tabs::SelectedTab;
+use cloudhost_tui::utils::clipboard::Clipboard;
+use cloudhost_tui::utils::launcher::Launcher;
+use common::TestApp;
+
+#[tokio::test]
+async fn folder_is_copied_when_it_cant_be_opened() {
+    let mut app = TestApp::with_clouds();
+    app.app.launcher = Launcher::disabled();
+    app.app.clipboard = Clipboard::disabled();
+    app.app.selected_tab = SelectedTab::Folders;
+
+    app.app
+        .apply_action("Open in File Manager", &[])
+        .await
+        .unwrap();
+    let fallback = app.app.copy_fallback.clone().expect("shown instead");
+    assert_eq!(fallback.text, "/srv/cloudhost/photos");
+    let screen = app.render(120, 36);
+    assert!(
+        screen.contains("Couldn't open the path of 'photos'"),
+        "{}",
+        screen
+    );
+}
+
+#[tokio::test]
+async fn stopped_cloud_has_nothing_to_open() {
+    let mut app = TestApp::with_clouds();
+    app.app.launcher = Launcher::disabled();
+    app.app.clipboard = Clipboard::disabled();
+    app.app.selected_tab = SelectedTab::Clouds;
+
+    app.app.apply_action("Open in Browser", &[]).await.unwrap();
+    assert!(app.app.copy_fallback.is_none());
+    let screen = app.render(160, 36);
+    assert!(
+        screen.contains("Cloud 'family' is not running"),
+        "{}",
+        screen
+    );
+}
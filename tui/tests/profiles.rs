@@ -0,0 +1,80 @@
+//! Profiles: configs of their own under `profiles/<name>`, switched to with `:profile` or
+//! the profile switcher.
+
+mod common;
+
+use cloudhost_server::{config_paths, CloudFolder};
+use common::TestApp;
+use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+use std::path::Path;
+
+#[tokio::test]
+async fn profiles_keep_their_own_clouds() {
+    let mut app = TestApp::with_clouds();
+    assert_eq!(config_paths::list_profiles(), ["default"]);
+
+    app.app.apply_action("profile", &["office"]).await.unwrap();
+    assert_eq!(config_paths::current_profile().as_deref(), Some("office"));
+    assert!(app.app.orchestrator.get_clouds().is_empty());
+    app.app
+        .orchestrator
+        .add_cloud_folder(CloudFolder::new(
+            "reports".to_string(),
+            "/srv/cloudhost/reports".into(),
+        ))
+        .unwrap();
+    assert!(Path::new("profiles/office/clouds-config.toml").is_file());
+    let screen = app.render(120, 30);
+    assert!(screen.contains("CloudHost (dev) · office"), "{}", screen);
+
+    // The switcher lists both, the current one selected
+    app.app.apply_action("Switch Profile", &[]).await.unwrap();
+    let picker = app.app.profile_picker.as_ref().expect("switcher open");
+    assert_eq!(picker.profiles, ["default", "office"]);
+    assert_eq!(picker.selected, 1);
+    let screen = app.render(120, 30);
+    assert!(screen.contains("office (current)"), "{}", screen);
+
+    app.app
+        .handle_dynamic_key(KeyCode::Up, KeyModifiers::NONE)
+        .await;
+    app.app
+        .handle_dynamic_key(KeyCode::Enter, KeyModifiers::NONE)
+        .await;
+    assert!(app.app.profile_picker.is_none());
+    assert_eq!(config_paths::current_profile(), None);
+    let clouds: Vec<String> = app
+        .app
+        .orchestrator
+        .get_clouds()
+        .iter()
+        .map(|cloud| cloud.name.clone())
+        .collect();
+    assert_eq!(clouds, ["family", "work"]);
+}
+
+#[tokio::test]
+async fn bad_profile_names_are_refused() {
+    let mut app = TestApp::new();
+    app.app.apply_action("Switch Profile", &[]).await.unwrap();
+    for c in "../home".chars() {
+        app.app
+            .handle_dynamic_key(KeyCode::Char(c), KeyModifiers::NONE)
+            .await;
+    }
+    app.app
+        .handle_dynamic_key(KeyCode::Enter, KeyModifiers::NONE)
+        .await;
+    let picker = app.app.profile_picker.as_ref().expect("still open");
+    assert!(
+        picker
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("may only contain"),
+        "{:?}",
+        picker.error
+    );
+    assert_eq!(config_paths::current_profile(), None);
+    assert!(!Path::new("profiles").exists());
+}
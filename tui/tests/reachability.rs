@@ -0,0 +1,55 @@
+//! The reachability test of the Clouds tab, whose result shows in the Cloud Info panel.
+
+mod common;
+
+use cloudhost_server::doctor::{Check, Status};
+use cloudhost_tui::events::AppEvent;
+use cloudhost_tui::tabs::SelectedTab;
+use common::TestApp;
+
+#[tokio::test]
+async fn result_shows_in_cloud_info() {
+    let mut app = TestApp::with_clouds();
+    app.app.selected_tab = SelectedTab::Clouds;
+
+    app.app
+        .clouds_state
+        .reachability
+        .insert("family".into(), None);
+    let screen = app.render(160, 40);
+    assert!(screen.contains("Testing reachability"), "{}", screen);
+
+    let check = Check::new(
+        "internet",
+        Status::Warning,
+        "'family' isn't reachable at 203.0.113.7:3000",
+    )
+    .hint("Forward TCP port 3000 on the router");
+    app.app
+        .handle_app_event(AppEvent::Reachability {
+            cloud: "family".into(),
+            check,
+        })
+        .await;
+    let screen = app.render(160, 40);
+    assert!(screen.contains("isn't reachable"), "{}", screen);
+    assert!(screen.contains("→ Forward TCP"), "{}", screen);
+}
+
+#[tokio::test]
+async fn stopped_cloud_isnt_tested() {
+    let mut app = TestApp::with_clouds();
+    app.app.selected_tab = SelectedTab::Clouds;
+
+    app.app
+        .apply_action("Test Reachability", &[])
+        .await
+        .unwrap();
+    assert!(app.app.clouds_state.reachability.is_empty());
+    let screen = app.render(160, 40);
+    assert!(
+        screen.contains("Cloud 'family' is not running"),
+        "{}",
+        screen
+    );
+}
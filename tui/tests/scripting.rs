@@ -0,0 +1,114 @@
+//! End-to-end flows through the TUI without a terminal: a folder is created, a cloud of it
+//! with a password, and the cloud started, once by typing keys from a script and once
+//! through `App::apply_action`.
+
+mod common;
+
+use cloudhost_tui::utils::script::Script;
+use common::{free_port, TempDir, TestApp};
+
+const PASSWORD: &str = "correct horse";
+
+/// Whether something answers on `port`
+fn listening(port: u16) -> bool {
+    std::net::TcpStream::connect(("127.0.0.1", port)).is_ok()
+}
+
+#[tokio::test]
+async fn typed_script_creates_and_starts_a_cloud() {
+    let mut app = TestApp::new();
+    let photos = TempDir::new("photos");
+    let script = Script::parse(&format!(
+        "# A cloud folder, from the folders tab
+         g t
+         n
+         type photos
+         <Tab>
+         type {path}
+         <Enter>
+         # A cloud of it, from the clouds panel
+         :toggle selection
+         <S-Tab>
+         n
+         type family
+         <Enter>
+         type {password}
+         <Enter>
+         type {password}
+         <Enter>
+         # Back to the clouds tab to start it
+         g T
+         s",
+        path = photos.path().display(),
+        password = PASSWORD
+    ))
+    .expect("valid script");
+
+    let port = free_port();
+    app.app.orchestrator.next_port = port;
+    app.app.run_script(&script).await;
+
+    let cloud = app
+        .app
+        .orchestrator
+        .get_cloud("family")
+        .expect("cloud created");
+    let folders: Vec<&str> = cloud
+        .cloud_folders
+        .iter()
+        .map(|f| f.name.as_str())
+        .collect();
+    assert_eq!(folders, ["photos"]);
+    assert!(app.app.orchestrator.is_cloud_running("family"));
+    assert!(listening(port));
+
+    app.app.apply_action("stop", &["family"]).await.unwrap();
+    assert!(!app.app.orchestrator.is_cloud_running("family"));
+}
+
+#[tokio::test]
+async fn actions_create_and_start_a_cloud() {
+    let mut app = TestApp::new();
+    let photos = TempDir::new("photos");
+    let path = photos.path().to_string_lossy().to_string();
+    let tui = &mut app.app;
+
+    tui.apply_action("addfolder", &["photos", &path])
+        .await
+        .unwrap();
+    assert_eq!(
+        tui.apply_action("addcloud", &["family", "music"]).await,
+        Err("No cloud folder named 'music'".to_string())
+    );
+    tui.apply_action("addcloud", &["family", "photos"])
+        .await
+        .unwrap();
+    // Starting needs a password, which is typed into the modal `:password` opens
+    assert!(tui.apply_action("start", &["family"]).await.is_err());
+    tui.apply_action("password", &["family"]).await.unwrap();
+    let typed = Script::parse(&format!("type {0}\n<Enter>\ntype {0}\n<Enter>", PASSWORD)).unwrap();
+    tui.run_script(&typed).await;
+
+    let port = free_port();
+    tui.orchestrator.next_port = port;
+    tui.apply_action("start", &["family"]).await.unwrap();
+    assert!(tui.orchestrator.is_cloud_running("family"));
+    assert!(listening(port));
+
+    // Actions by the names keys are bound to
+    tui.apply_action("Next Tab", &[]).await.unwrap();
+    assert!(tui.apply_action("No Such Action", &[]).await.is_err());
+
+    tui.apply_action("stop", &["family"]).await.unwrap();
+    assert!(!tui.orchestrator.is_cloud_running("family"));
+}
+
+#[test]
+fn script_errors_name_the_line() {
+    assert_eq!(
+        Script::parse("g t\n\n<Enter> <Nope>").map(|_| ()),
+        Err("line 3: unknown key '<Nope>'".to_string())
+    );
+    let script = Script::parse("# nothing\n\n<leader> <Ctrl>x <Esc>").unwrap();
+    assert!(!script.steps.is_empty());
+}
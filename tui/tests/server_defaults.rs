@@ -0,0 +1,120 @@
+//! Editing the server defaults from the Settings tab.
+
+mod common;
+
+use cloudhost_tui::tabs::settings::models::DEFAULTS_START;
+use cloudhost_tui::tabs::SelectedTab;
+use common::{free_port, TestApp};
+use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+
+async fn type_text(app: &mut TestApp, text: &str) {
+    app.app
+        .handle_dynamic_key(KeyCode::Char('u'), KeyModifiers::CONTROL)
+        .await;
+    for c in text.chars() {
+        app.app
+            .handle_dynamic_key(KeyCode::Char(c), KeyModifiers::NONE)
+            .await;
+    }
+    app.app
+        .handle_dynamic_key(KeyCode::Enter, KeyModifiers::NONE)
+        .await;
+}
+
+#[tokio::test]
+async fn base_port_is_edited_and_saved() {
+    let mut app = TestApp::with_clouds();
+    app.app.selected_tab = SelectedTab::Settings;
+    app.app
+        .settings_state
+        .list_state
+        .select(Some(DEFAULTS_START));
+    let screen = app.render(160, 60);
+    assert!(screen.contains("Server Defaults"), "{}", screen);
+    assert!(screen.contains("Base port:"), "{}", screen);
+
+    app.app.apply_action("Execute Action", &[]).await.unwrap();
+    let editor = app
+        .app
+        .settings_state
+        .default_editor
+        .as_ref()
+        .expect("open");
+    assert_eq!(editor.input.value(), "3000");
+
+    // Refused values keep the prompt open
+    type_text(&mut app, "0").await;
+    let screen = app.render(160, 60);
+    assert!(screen.contains("is not a port"), "{}", screen);
+
+    let port = free_port();
+    type_text(&mut app, &port.to_string()).await;
+    assert!(app.app.settings_state.default_editor.is_none());
+    assert_eq!(app.app.orchestrator.clouds_config.defaults.base_port, port);
+    // Nothing runs, so the next cloud starts there
+    assert_eq!(app.app.orchestrator.next_port, port);
+    let saved = cloudhost_server::CloudsConfig::load_from_file().unwrap();
+    assert_eq!(saved.defaults.base_port, port);
+}
+
+#[tokio::test]
+async fn optional_defaults_go_back_to_built_in_when_emptied() {
+    let mut app = TestApp::with_clouds();
+    app.app.selected_tab = SelectedTab::Settings;
+    // Login lifetime
+    app.app
+        .settings_state
+        .list_state
+        .select(Some(DEFAULTS_START + 3));
+
+    app.app.apply_action("Execute Action", &[]).await.unwrap();
+    type_text(&mut app, "48").await;
+    assert_eq!(
+        app.app
+            .orchestrator
+            .clouds_config
+            .defaults
+            .session_lifetime_hours,
+        Some(48)
+    );
+    let screen = app.render(160, 60);
+    assert!(screen.contains("Login lifetime set to 48 h"), "{}", screen);
+
+    app.app.apply_action("Execute Action", &[]).await.unwrap();
+    type_text(&mut app, "").await;
+    assert_eq!(
+        app.app
+            .orchestrator
+            .clouds_config
+            .defaults
+            .session_lifetime_hours,
+        None
+    );
+}
+
+#[tokio::test]
+async fn overridden_defaults_say_where_they_come_from() {
+    let mut app = TestApp::with_clouds();
+    std::env::set_var("CLOUDHOST_BASE_PORT", "4100");
+    app.app.selected_tab = SelectedTab::Settings;
+    app.app
+        .settings_state
+        .list_state
+        .select(Some(DEFAULTS_START));
+    let screen = app.render(160, 60);
+    assert!(screen.contains("4100"), "{}", screen);
+    assert!(screen.contains("overridden"), "{}", screen);
+
+    // The config still takes the edit, for when the variable is unset
+    app.app.apply_action("Execute Action", &[]).await.unwrap();
+    type_text(&mut app, "4200").await;
+    assert_eq!(app.app.orchestrator.clouds_config.defaults.base_port, 4200);
+    assert_eq!(app.app.orchestrator.defaults().base_port, 4100);
+    let screen = app.render(160, 60);
+    assert!(
+        screen.contains("Base port set to 4200 in the config, but"),
+        "{}",
+        screen
+    );
+    std::env::remove_var("CLOUDHOST_BASE_PORT");
+}
@@ -0,0 +1,259 @@
+//! Snapshot tests of the UI: each tab and modal is rendered at a few terminal sizes and
+//! compared with what's in `tests/snapshots`. A missing snapshot is written rather than
+//! compared; after a change to the UI, `UPDATE_SNAPSHOTS=1 cargo test -p cloudhost-tui`
+//! writes them all again, to be reviewed in the diff. Smaller terminals than these are
+//! only drawn, to check the layout doesn't panic.
+
+mod common;
+
+use cloudhost_tui::tabs::client::models::ClientInputMode;
+use cloudhost_tui::tabs::folders::models::FocusedPanel;
+use cloudhost_tui::tabs::SelectedTab;
+use cloudhost_tui::utils::confirmation::{ConfirmAction, Confirmation};
+use cloudhost_tui::utils::folder_preview::FolderPreview;
+use cloudhost_tui::App;
+use common::TestApp;
+use std::path::PathBuf;
+
+/// Terminal sizes every scene is snapshotted at: roomy, the classic 80x24, and cramped
+const SIZES: &[(u16, u16)] = &[(120, 36), (80, 24), (50, 14)];
+
+/// Sizes only drawn: empty, a few cells, and around where the layouts change. Every size
+/// up to these takes a minute in a debug build.
+const SMALL_WIDTHS: &[u16] = &[0, 1, 2, 3, 5, 8, 12, 19, 20, 21, 30, 40, 60, 89];
+const SMALL_HEIGHTS: &[u16] = &[0, 1, 2, 3, 4, 5, 7, 8, 9, 12, 16, 23];
+
+/// Set to write snapshots instead of comparing with them
+const UPDATE_VAR: &str = "UPDATE_SNAPSHOTS";
+
+fn snapshot_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{}.txt", name))
+}
+
+/// Render `app` at each of `SIZES` and compare with the snapshot `name`
+fn assert_snapshot(name: &str, app: &mut TestApp) {
+    let rendered: String = SIZES
+        .iter()
+        .map(|&(width, height)| format!("{}x{}\n{}", width, height, app.render(width, height)))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let path = snapshot_path(name);
+    let update = std::env::var_os(UPDATE_VAR).is_some_and(|value| value != "0");
+    match std::fs::read_to_string(&path) {
+        Ok(expected) if !update => {
+            if expected != rendered {
+                let first_difference = expected
+                    .lines()
+                    .zip(rendered.lines())
+                    .position(|(expected, rendered)| expected != rendered)
+                    .unwrap_or(expected.lines().count().min(rendered.lines().count()));
+                panic!(
+                    "snapshot '{}' differs from line {}; run with {}=1 to update it\n\
+                     expected:\n{}\nrendered:\n{}",
+                    name,
+                    first_difference + 1,
+                    UPDATE_VAR,
+                    expected,
+                    rendered
+                );
+            }
+        }
+        _ => {
+            std::fs::create_dir_all(path.parent().expect("snapshots dir")).unwrap();
+            std::fs::write(&path, rendered).unwrap();
+        }
+    }
+}
+
+#[test]
+fn clouds_tab_without_clouds() {
+    let mut app = TestApp::new();
+    assert_snapshot("clouds_tab_without_clouds", &mut app);
+}
+
+#[test]
+fn clouds_tab() {
+    let mut app = TestApp::with_clouds();
+    assert_snapshot("clouds_tab", &mut app);
+}
+
+#[test]
+fn password_modal() {
+    let mut app = TestApp::with_clouds();
+    let password = &mut app.app.clouds_state.password_creation;
+    password.creating_password = true;
+    password.password_input.set("correct horse");
+    assert_snapshot("password_modal", &mut app);
+}
+
+#[test]
+fn folders_tab() {
+    let mut app = TestApp::with_clouds();
+    app.app.selected_tab = SelectedTab::Folders;
+    app.app.folders_state.selected_folders.insert(1);
+    assert_snapshot("folders_tab", &mut app);
+}
+
+#[test]
+fn folders_tab_detailed() {
+    let mut app = TestApp::with_clouds();
+    app.app.selected_tab = SelectedTab::Folders;
+    let folders = &mut app.app.folders_state;
+    folders.folder_details = true;
+    folders.selected_folders.insert(1);
+    // A scan that finished, and one that stopped early
+    for (path, file_count, total_bytes, truncated) in [
+        ("/srv/cloudhost/photos", 1200, 3 << 30, false),
+        ("/srv/cloudhost/documents", 50_000, 12 << 20, true),
+    ] {
+        let path = PathBuf::from(path);
+        folders.folder_sizes.insert(
+            path.clone(),
+            FolderPreview {
+                path,
+                exists: true,
+                is_dir: true,
+                file_count,
+                total_bytes,
+                truncated,
+                ..FolderPreview::default()
+            },
+        );
+    }
+    assert_snapshot("folders_tab_detailed", &mut app);
+}
+
+#[test]
+fn folders_tab_with_clouds_focused() {
+    let mut app = TestApp::with_clouds();
+    app.app.selected_tab = SelectedTab::Folders;
+    app.app.folders_state.focused_panel = FocusedPanel::Clouds;
+    app.app.folders_state.selected_cloud_index = 1;
+    assert_snapshot("folders_tab_with_clouds_focused", &mut app);
+}
+
+#[test]
+fn folder_creation_modal() {
+    let mut app = TestApp::with_clouds();
+    app.app.selected_tab = SelectedTab::Folders;
+    let folders = &mut app.app.folders_state;
+    folders.creating_folder = true;
+    folders.new_folder_name.set("music");
+    folders.folder_creation_error = Some("Folder name already exists".to_string());
+    assert_snapshot("folder_creation_modal", &mut app);
+}
+
+#[test]
+fn cloud_creation_modal() {
+    let mut app = TestApp::with_clouds();
+    app.app.selected_tab = SelectedTab::Folders;
+    let folders = &mut app.app.folders_state;
+    folders.creating_cloud = true;
+    folders.new_cloud_name.set("friends");
+    folders.selected_folders.extend([0, 2]);
+    assert_snapshot("cloud_creation_modal", &mut app);
+}
+
+#[test]
+fn delete_confirmation_modal() {
+    let mut app = TestApp::with_clouds();
+    app.app.selected_tab = SelectedTab::Folders;
+    app.app.confirmation = Some(Confirmation::new(ConfirmAction::DeleteCloud(
+        "work".to_string(),
+    )));
+    assert_snapshot("delete_confirmation_modal", &mut app);
+}
+
+#[test]
+fn client_tab() {
+    let mut app = TestApp::new();
+    app.app.selected_tab = SelectedTab::Client;
+    assert_snapshot("client_tab", &mut app);
+}
+
+#[test]
+fn settings_tab() {
+    let mut app = TestApp::new();
+    app.app.selected_tab = SelectedTab::Settings;
+    assert_snapshot("settings_tab", &mut app);
+}
+
+#[test]
+fn help_overlay() {
+    let mut app = TestApp::with_clouds();
+    app.app.show_help = true;
+    assert_snapshot("help_overlay", &mut app);
+}
+
+/// Puts an app with `TestApp::with_clouds` into the state it's drawn in
+type Setup = fn(&mut App);
+
+/// Every tab and modal
+const SCENES: &[(SelectedTab, Setup)] = &[
+    (SelectedTab::Clouds, |_| {}),
+    (SelectedTab::Clouds, |app| {
+        app.clouds_state.password_creation.creating_password = true
+    }),
+    (SelectedTab::Clouds, |app| {
+        app.clouds_state.password_change.active = true
+    }),
+    (SelectedTab::Folders, |_| {}),
+    (SelectedTab::Folders, |app| {
+        app.folders_state.folder_details = true
+    }),
+    (SelectedTab::Folders, |app| {
+        app.folders_state.creating_folder = true
+    }),
+    (SelectedTab::Folders, |app| {
+        app.folders_state.creating_cloud = true
+    }),
+    (SelectedTab::Folders, |app| {
+        app.folders_state.editing_folder = true
+    }),
+    (SelectedTab::Folders, |app| {
+        app.folders_state.editing_cloud = true
+    }),
+    (SelectedTab::Folders, |app| {
+        app.folders_state.files.creating_directory = true
+    }),
+    (SelectedTab::Folders, |app| {
+        app.confirmation = Some(Confirmation::new(ConfirmAction::DeleteFolder(
+            "photos".to_string(),
+        )))
+    }),
+    (SelectedTab::Client, |_| {}),
+    (SelectedTab::Client, |app| {
+        app.client_state.input_mode = ClientInputMode::DownloadPath
+    }),
+    (SelectedTab::Settings, |_| {}),
+    (SelectedTab::Clouds, |app| app.show_help = true),
+    (SelectedTab::Clouds, |app| app.show_jobs = true),
+    (SelectedTab::Clouds, |app| app.show_tasks = true),
+    (SelectedTab::Clouds, |app| {
+        app.notifications.show_history = true
+    }),
+    (SelectedTab::Clouds, |app| app.show_config_conflict = true),
+    (SelectedTab::Clouds, |app| app.fuzzy_finder.active = true),
+    (SelectedTab::Clouds, |app| {
+        app.command_line.active = true;
+        app.command_line.completions = vec!["start".to_string(), "stop".to_string()];
+    }),
+    (SelectedTab::Clouds, |app| app.debug_mode = true),
+];
+
+#[test]
+fn small_terminals_do_not_panic() {
+    for (tab, setup) in SCENES {
+        let mut app = TestApp::with_clouds();
+        app.app.selected_tab = *tab;
+        setup(&mut app.app);
+        for &width in SMALL_WIDTHS {
+            for &height in SMALL_HEIGHTS {
+                app.draw(width, height);
+            }
+        }
+    }
+}
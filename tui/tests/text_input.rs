@@ -0,0 +1,122 @@
+//! The text input of the modals and prompts: editing at the cursor, word-wise deletes,
+//! selections and pasted text.
+
+mod common;
+
+use cloudhost_tui::tabs::SelectedTab;
+use cloudhost_tui::utils::text_input::TextInput;
+use common::TestApp;
+use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+
+fn keys(input: &mut TextInput, keys: &[(KeyCode, KeyModifiers)]) {
+    for (key, modifiers) in keys {
+        input.handle_key(*key, *modifiers);
+    }
+}
+
+#[test]
+fn edits_at_the_cursor() {
+    let mut input = TextInput::new("photos");
+    keys(
+        &mut input,
+        &[
+            (KeyCode::Home, KeyModifiers::NONE),
+            (KeyCode::Char('m'), KeyModifiers::NONE),
+            (KeyCode::Char('y'), KeyModifiers::NONE),
+            (KeyCode::Char('_'), KeyModifiers::NONE),
+        ],
+    );
+    assert_eq!(input.value(), "my_photos");
+    assert_eq!(input.cursor(), 3);
+
+    keys(
+        &mut input,
+        &[
+            (KeyCode::End, KeyModifiers::NONE),
+            (KeyCode::Left, KeyModifiers::NONE),
+            (KeyCode::Backspace, KeyModifiers::NONE),
+            (KeyCode::Delete, KeyModifiers::NONE),
+        ],
+    );
+    assert_eq!(input.value(), "my_phot");
+}
+
+#[test]
+fn deletes_by_word() {
+    let mut input = TextInput::new("/srv/cloudhost/photos");
+    input.handle_key(KeyCode::Backspace, KeyModifiers::CONTROL);
+    assert_eq!(input.value(), "/srv/cloudhost/");
+    input.handle_key(KeyCode::Char('w'), KeyModifiers::CONTROL);
+    assert_eq!(input.value(), "/srv/");
+
+    input.handle_key(KeyCode::Home, KeyModifiers::NONE);
+    input.handle_key(KeyCode::Delete, KeyModifiers::CONTROL);
+    assert_eq!(input.value(), "/");
+
+    let mut input = TextInput::new("family photos");
+    input.handle_key(KeyCode::Left, KeyModifiers::CONTROL);
+    input.handle_key(KeyCode::Char('k'), KeyModifiers::CONTROL);
+    assert_eq!(input.value(), "family ");
+    input.handle_key(KeyCode::Char('u'), KeyModifiers::CONTROL);
+    assert_eq!(input.value(), "");
+}
+
+#[test]
+fn typing_replaces_the_selection() {
+    let mut input = TextInput::new("family photos");
+    keys(
+        &mut input,
+        &[
+            (KeyCode::Home, KeyModifiers::NONE),
+            (KeyCode::Right, KeyModifiers::CONTROL | KeyModifiers::SHIFT),
+        ],
+    );
+    assert_eq!(input.selection(), Some(0..6));
+    input.handle_key(KeyCode::Char('w'), KeyModifiers::NONE);
+    assert_eq!(input.value(), "w photos");
+    assert_eq!(input.selection(), None);
+
+    // Moving without Shift drops the selection
+    input.handle_key(KeyCode::End, KeyModifiers::SHIFT);
+    input.handle_key(KeyCode::Left, KeyModifiers::NONE);
+    assert_eq!(input.selection(), None);
+}
+
+#[test]
+fn paste_keeps_a_single_line() {
+    let mut input = TextInput::new("/srv/");
+    input.insert_str("cloud\nhost\t");
+    assert_eq!(input.value(), "/srv/cloudhost");
+    assert_eq!(input.cursor(), 14);
+}
+
+#[tokio::test]
+async fn folder_modal_edits_and_pastes_at_the_cursor() {
+    let mut app = TestApp::new();
+    app.app.selected_tab = SelectedTab::Folders;
+    app.app.apply_action("Create New", &[]).await.unwrap();
+    assert!(app.app.folders_state.creating_folder);
+
+    for c in "music".chars() {
+        app.app
+            .handle_dynamic_key(KeyCode::Char(c), KeyModifiers::NONE)
+            .await;
+    }
+    app.app
+        .handle_dynamic_key(KeyCode::Home, KeyModifiers::NONE)
+        .await;
+    app.app.handle_paste("my ");
+    assert_eq!(app.app.folders_state.new_folder_name.value(), "my music");
+
+    // Letters bound to actions elsewhere are typed, and pasted text never runs them
+    app.app
+        .handle_dynamic_key(KeyCode::Enter, KeyModifiers::NONE)
+        .await;
+    app.app.handle_paste("/srv/q\n");
+    assert_eq!(app.app.folders_state.new_folder_path.value(), "/srv/q");
+    assert!(app.app.folders_state.creating_folder);
+
+    let screen = app.render(120, 36);
+    assert!(screen.contains("my music"), "{}", screen);
+    assert!(screen.contains("/srv/q"), "{}", screen);
+}
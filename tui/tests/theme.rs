@@ -0,0 +1,78 @@
+//! Themes: `[theme]` in the TUI config, and switching themes while the TUI runs.
+
+mod common;
+
+use cloudhost_tui::config::Config;
+use cloudhost_tui::theme::{Theme, ThemeConfig, ThemeName};
+use common::TestApp;
+use ratatui::style::Color;
+use strum::IntoEnumIterator;
+
+/// Color of the focused clouds list's top-left corner on the clouds tab
+fn focused_corner(app: &mut TestApp) -> Color {
+    let terminal = app.draw(120, 36);
+    let cell = &terminal.backend().buffer()[(0, 1)];
+    assert_eq!(cell.symbol(), "┌");
+    cell.fg
+}
+
+#[tokio::test]
+async fn next_theme_redraws_and_is_saved() {
+    let mut app = TestApp::with_clouds();
+    assert_eq!(
+        focused_corner(&mut app),
+        Theme::builtin(ThemeName::Default).focused_border
+    );
+
+    app.app.apply_action("Next Theme", &[]).await.unwrap();
+    assert_eq!(app.app.config.theme.name, ThemeName::Light);
+    assert_eq!(
+        focused_corner(&mut app),
+        Theme::builtin(ThemeName::Light).focused_border
+    );
+    assert_eq!(Config::load().unwrap().theme.name, ThemeName::Light);
+
+    // Around the built-in themes and back to the first
+    for _ in 1..ThemeName::iter().count() {
+        app.app.apply_action("Next Theme", &[]).await.unwrap();
+    }
+    assert_eq!(app.app.config.theme.name, ThemeName::Default);
+}
+
+#[test]
+fn colors_override_the_named_theme() {
+    let config: ThemeConfig = toml::from_str(
+        r##"
+name = "high_contrast"
+colors = { selection = "magenta", log_debug = "#8888ff", focused_border = "202" }
+"##,
+    )
+    .unwrap();
+    assert!(config.validate().is_empty());
+
+    let theme = config.resolve();
+    let high_contrast = Theme::builtin(ThemeName::HighContrast);
+    assert_eq!(theme.selection, Color::Magenta);
+    assert_eq!(theme.log_debug, Color::Rgb(0x88, 0x88, 0xff));
+    assert_eq!(theme.focused_border, Color::Indexed(202));
+    assert_eq!(theme.border, high_contrast.border);
+}
+
+#[test]
+fn bad_colors_are_reported_and_skipped() {
+    let config: ThemeConfig = toml::from_str(
+        r#"
+colors = { selection = "not a color", backgrond = "red" }
+"#,
+    )
+    .unwrap();
+    let problems = config.validate();
+    assert_eq!(problems.len(), 2, "{:?}", problems);
+    assert!(problems
+        .iter()
+        .any(|p| p.contains("theme.colors.selection")));
+    assert!(problems
+        .iter()
+        .any(|p| p.contains("theme.colors.backgrond")));
+    assert_eq!(config.resolve(), Theme::default());
+}